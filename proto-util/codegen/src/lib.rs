@@ -0,0 +1,336 @@
+mod names;
+mod rust;
+
+use resolver::Workspace;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    // Path of the crate providing `Message`, `Reader`, `Writer` and the codecs to the generated
+    // code.
+    pub runtime: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            runtime: "::wire".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    // Relative to the directory of the generated files.
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub file: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.file, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Name of the file generated for the package.
+pub fn file_name(package: &str) -> String {
+    match package.is_empty() {
+        true => "_.rs".to_string(),
+        false => format!("{}.rs", package),
+    }
+}
+
+// Rust code for the files of the workspace: one file per package, and `mod.rs` declaring modules
+// mirroring the packages that include them. Types of other packages are referred to by relative
+// paths, so the files of the packages they are declared in have to be generated too.
+pub fn generate(
+    workspace: &Workspace,
+    files: &[usize],
+    options: &Options,
+) -> Result<Vec<Output>, Error> {
+    let mut packages: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for &file in files {
+        packages
+            .entry(workspace.file(file).package())
+            .or_default()
+            .push(file);
+    }
+
+    let graph = rust::graph(workspace);
+    let mut outputs = vec![];
+
+    for (package, files) in &packages {
+        let names: Vec<_> = files
+            .iter()
+            .map(|&file| workspace.file(file).name)
+            .collect();
+        let mut generator = rust::Generator::new(workspace, options, &graph, package);
+        for &file in files {
+            generator.file(file)?;
+        }
+
+        outputs.push(Output {
+            path: file_name(package),
+            content: format!(
+                "// Generated from {}. Do not edit.\n\n{}",
+                names.join(", "),
+                generator.finish()
+            ),
+        });
+    }
+
+    let paths: Vec<_> = outputs.iter().map(|output| output.path.clone()).collect();
+    outputs.insert(
+        0,
+        Output {
+            path: "mod.rs".to_string(),
+            content: rust::modules(
+                packages
+                    .keys()
+                    .copied()
+                    .zip(paths.iter().map(String::as_str)),
+            ),
+        },
+    );
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+#[allow(dead_code)]
+mod generated {
+    include!("../../../test-data/codegen/expected/mod.rs");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Options;
+    use crate::generated::acme::common::v1::Money;
+    use crate::generated::acme::store::v1::{Bundle, Category, Priority, Product, product};
+    use crate::generated::{Settings, settings};
+    use descriptor::Pool;
+    use resolver::{SourceTree, Workspace};
+    use std::path::PathBuf;
+    use wire::Message;
+
+    const FILES: [&str; 3] = [
+        "acme/common/v1/money.proto",
+        "acme/store/v1/store.proto",
+        "legacy/settings.proto",
+    ];
+
+    fn data() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-data/codegen")
+    }
+
+    fn tree() -> SourceTree {
+        let files: Vec<_> = FILES.iter().map(|file| data().join(file)).collect();
+        SourceTree::load(vec![data()], &files).unwrap()
+    }
+
+    fn product() -> Product {
+        let mut product = Product {
+            id: "p-1".to_string(),
+            price: Some(Money {
+                currency: "EUR".to_string(),
+                units: 12,
+                nanos: -500_000_000,
+            }),
+            tags: vec!["new".to_string(), "sale".to_string()],
+            stock: [("north".to_string(), 3), ("south".to_string(), -1)].into(),
+            origin: Some(product::Origin::Bundle(Box::new(Bundle {
+                products: vec![Product {
+                    id: "p-2".to_string(),
+                    origin: Some(product::Origin::Factory("Lyon".to_string())),
+                    ..Product::default()
+                }],
+                main: Some(Box::new(Product {
+                    id: "p-3".to_string(),
+                    ..Product::default()
+                })),
+            }))),
+            ratings: vec![5, -2, 300],
+            weight: Some(0.0),
+            thumbnail: vec![0, 255],
+            variants: vec![product::Variant {
+                name: "large".to_string(),
+                r#type: "size".to_string(),
+                available: true,
+            }],
+            sizes: [(-4, product::Variant::default())].into(),
+            category: Some(Category {
+                name: "tools".to_string(),
+                parent: Some(Box::new(Category {
+                    name: "home".to_string(),
+                    parent: None,
+                })),
+            }),
+            barcode: u64::MAX,
+            ..Product::default()
+        };
+        product.set_status(product::Status::Retired);
+        product
+    }
+
+    #[test]
+    fn golden() {
+        let tree = tree();
+        let workspace = Workspace::new(&tree).unwrap();
+        let files: Vec<_> = FILES
+            .iter()
+            .map(|file| workspace.file_index(file).unwrap())
+            .collect();
+        let options = Options {
+            runtime: "::wire".to_string(),
+        };
+
+        let outputs = crate::generate(&workspace, &files, &options).unwrap();
+        let paths: Vec<_> = outputs.iter().map(|output| output.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["mod.rs", "_.rs", "acme.common.v1.rs", "acme.store.v1.rs"]
+        );
+
+        for output in outputs {
+            let path = data().join("expected").join(&output.path);
+            assert_eq!(
+                output.content,
+                std::fs::read_to_string(path).unwrap(),
+                "{}",
+                output.path
+            );
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(crate::names::ident("type"), "r#type");
+        assert_eq!(crate::names::ident("self"), "self_");
+        assert_eq!(crate::names::type_name("HTTPServer"), "HttpServer");
+        assert_eq!(
+            crate::names::variant_name("Status", "STATUS_ACTIVE"),
+            "Active"
+        );
+        assert_eq!(crate::names::variant_name("Code", "CODE_404"), "Code404");
+        assert_eq!(
+            crate::names::path(
+                &["acme".to_string(), "store".to_string(), "v1".to_string()],
+                &["acme".to_string(), "common".to_string(), "v1".to_string()],
+                "Money"
+            ),
+            "super::super::common::v1::Money"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let product = product();
+        assert_eq!(Product::decode(&product.encode()).unwrap(), product);
+        assert_eq!(product.status(), product::Status::Retired);
+        assert_eq!(Product::default().encode(), Vec::<u8>::new());
+
+        // fields with presence are written even with their default value
+        let weight = Product {
+            weight: Some(0.0),
+            ..Product::default()
+        };
+        assert_eq!(weight.encode(), [0x49, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        // repeated scalars are packed in proto3, and read either way
+        let ratings = Product {
+            ratings: vec![1, 2],
+            ..Product::default()
+        };
+        assert_eq!(ratings.encode(), [0x42, 2, 1, 2]);
+        assert_eq!(
+            Product::decode(&[0x40, 1, 0x40, 2]).unwrap().ratings,
+            [1, 2]
+        );
+
+        // unknown enum values are kept
+        let unknown = Product::decode(&[0x28, 7]).unwrap();
+        assert_eq!(unknown.status, 7);
+        assert_eq!(unknown.status(), product::Status::Unspecified);
+
+        assert_eq!(
+            Priority::from_str_name("PRIORITY_URGENT"),
+            Some(Priority::High)
+        );
+        assert_eq!(Priority::URGENT.as_str_name(), "PRIORITY_HIGH");
+        assert_eq!(Priority::try_from(2), Err(2));
+    }
+
+    #[test]
+    fn proto2() {
+        let settings = Settings::default();
+        assert_eq!(settings.retries(), 3);
+        assert_eq!(settings.name(), "");
+        assert_eq!(settings.greeting(), "hi \"there\"");
+        assert_eq!(settings.mode(), settings::Mode::Fast);
+        assert_eq!(settings.fallback(), settings::Mode::Slow);
+        assert_eq!(settings.salt(), b"\x01x");
+        assert_eq!(settings.ratio(), f32::INFINITY);
+
+        let settings = Settings {
+            retries: Some(0),
+            codes: vec![1, 2],
+            flags: vec![1, 2],
+            parent: Some(Box::new(Settings {
+                name: Some("base".to_string()),
+                ..Settings::default()
+            })),
+            ..Settings::default()
+        };
+        assert_eq!(
+            settings.encode(),
+            [
+                0x08, 0, 0x2a, 2, 1, 2, 0x30, 1, 0x30, 2, 0x52, 6, 0x12, 4, b'b', b'a', b's', b'e'
+            ]
+        );
+        assert_eq!(Settings::decode(&settings.encode()).unwrap(), settings);
+    }
+
+    #[test]
+    fn compatibility() {
+        let tree = tree();
+        let workspace = Workspace::new(&tree).unwrap();
+        let pool = Pool::new(&resolver::compile(&workspace).unwrap());
+
+        let product = product();
+        let message = data::decode(&pool, "acme.store.v1.Product", &product.encode()).unwrap();
+        assert_eq!(
+            Product::decode(&data::encode(&pool, &message).unwrap()).unwrap(),
+            product
+        );
+
+        let text = "id: \"p-4\" price { currency: \"USD\" units: -3 nanos: 7 } \
+                    stock { key: \"east\" value: 9 } status: STATUS_ACTIVE \
+                    factory: \"Turin\" ratings: [4, 5] weight: 1.5 \
+                    sizes { key: 10 value { name: \"xl\" } } barcode: 42";
+        let message = data::text::parse(&pool, "acme.store.v1.Product", text).unwrap();
+        let decoded = Product::decode(&data::encode(&pool, &message).unwrap()).unwrap();
+
+        assert_eq!(decoded.price.as_ref().unwrap().units, -3);
+        assert_eq!(decoded.status(), product::Status::Active);
+        assert_eq!(
+            decoded.origin,
+            Some(product::Origin::Factory("Turin".to_string()))
+        );
+        assert_eq!(decoded.sizes[&10].name, "xl");
+        assert_eq!(
+            data::text::print(
+                &pool,
+                &data::decode(&pool, "acme.store.v1.Product", &decoded.encode()).unwrap()
+            ),
+            data::text::print(&pool, &message)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+// Rust names of the declarations: types in PascalCase, fields, functions and modules in
+// snake_case, and keywords escaped.
+
+use descriptor::case;
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+// Keywords that can't be raw identifiers get an underscore instead.
+pub fn ident(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+pub fn type_name(name: &str) -> String {
+    ident(&case::pascal_case(name))
+}
+
+pub fn field_name(name: &str) -> String {
+    ident(&case::lower_snake_case(name))
+}
+
+// Module holding the declarations nested in a message.
+pub fn module_name(message: &str) -> String {
+    field_name(message)
+}
+
+// Enum values lose the prefix naming their enum, unless nothing valid would be left.
+pub fn variant_name(r#enum: &str, value: &str) -> String {
+    let prefix = format!("{}_", case::upper_snake_case(r#enum));
+    let stripped = value
+        .strip_prefix(&prefix)
+        .filter(|rest| rest.starts_with(|ch: char| ch.is_ascii_alphabetic()))
+        .unwrap_or(value);
+
+    type_name(stripped)
+}
+
+// Path from the module at `from` to the item in the module at `to`, both given by their segments
+// from the root of the generated modules.
+pub fn path(from: &[String], to: &[String], item: &str) -> String {
+    let common = from
+        .iter()
+        .zip(to)
+        .take_while(|(from, to)| from == to)
+        .count();
+
+    let mut segments = vec!["super"; from.len() - common];
+    segments.extend(to[common..].iter().map(String::as_str));
+    segments.push(item);
+    segments.join("::")
+}
@@ -0,0 +1,1113 @@
+// Rust source of a package: per message a struct with its `Message` implementation, and a module
+// named after it with its nested declarations and the enums of its oneofs; per enum a
+// `#[repr(i32)]` enum; per service a trait. Paths to the other packages are relative, so the files
+// can be included at any depth as long as the modules mirror the packages.
+
+use crate::{Error, Options, names};
+use descriptor::{FieldType, case};
+use editor::Layout;
+use parser::ast;
+use resolver::{Node, Symbol, TypeRef, Workspace};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Default)]
+struct Code {
+    text: String,
+    depth: usize,
+}
+
+impl Code {
+    fn line(&mut self, line: &str) {
+        if !line.is_empty() {
+            self.text.push_str(&"    ".repeat(self.depth));
+            self.text.push_str(line);
+        }
+        self.text.push('\n');
+    }
+
+    fn open(&mut self, line: &str) {
+        self.line(line);
+        self.depth += 1;
+    }
+
+    fn close(&mut self, line: &str) {
+        self.depth -= 1;
+        self.line(line);
+    }
+
+    fn doc(&mut self, lines: &[String]) {
+        for line in lines {
+            match line.is_empty() {
+                true => self.line("///"),
+                false => self.line(&format!("/// {}", line)),
+            }
+        }
+    }
+
+    // Separates items, but not from the brace opening their block.
+    fn separate(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with("{\n") && !self.text.ends_with("\n\n") {
+            self.text.push('\n');
+        }
+    }
+}
+
+// Singular message fields of every message of the workspace: the types of their values, by the
+// fully-qualified names of both. Values of types reaching back to the message are boxed.
+pub(crate) fn graph(workspace: &Workspace) -> HashMap<String, Vec<String>> {
+    fn collect(
+        workspace: &Workspace,
+        file: usize,
+        message: &ast::Message,
+        scope: &str,
+        graph: &mut HashMap<String, Vec<String>>,
+    ) {
+        let name = descriptor::names::join(scope, message.ident);
+        let mut fields = vec![];
+        let mut edges = vec![];
+
+        for entry in &message.entries {
+            match entry {
+                ast::MessageEntry::Field(field) => fields.push(field),
+                ast::MessageEntry::OneOf(oneof) => {
+                    fields.extend(oneof.entries.iter().filter_map(|entry| match entry {
+                        ast::OneOfEntry::Field(field) => Some(field),
+                        _ => None,
+                    }))
+                }
+                ast::MessageEntry::Message(nested) => {
+                    collect(workspace, file, nested, &name, graph)
+                }
+                _ => {}
+            }
+        }
+
+        for field in fields {
+            if field.modifier == ast::FieldModifier::Repeated {
+                continue;
+            }
+            if let Ok(TypeRef::Message(symbol)) = workspace.resolve_type(file, &name, field.r#type)
+            {
+                edges.push(symbol.name.clone());
+            }
+        }
+
+        graph.insert(name, edges);
+    }
+
+    let mut graph = HashMap::new();
+    for (file, source) in workspace.files().iter().enumerate() {
+        for entry in source.ast.iter() {
+            if let ast::FileEntry::Message(entry) = entry {
+                collect(workspace, file, entry, source.package(), &mut graph);
+            }
+        }
+    }
+
+    graph
+}
+
+fn reaches(graph: &HashMap<String, Vec<String>>, from: &str, to: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![from];
+
+    while let Some(name) = stack.pop() {
+        if name == to {
+            return true;
+        }
+        if visited.insert(name) {
+            stack.extend(graph.get(name).into_iter().flatten().map(String::as_str));
+        }
+    }
+
+    false
+}
+
+// Module segments of a package.
+pub(crate) fn package_modules(package: &str) -> Vec<String> {
+    match package.is_empty() {
+        true => vec![],
+        false => package.split('.').map(names::field_name).collect(),
+    }
+}
+
+fn scalar(r#type: FieldType) -> (&'static str, &'static str) {
+    match r#type {
+        FieldType::Double => ("Double", "f64"),
+        FieldType::Float => ("Float", "f32"),
+        FieldType::Int64 => ("Int64", "i64"),
+        FieldType::Uint64 => ("Uint64", "u64"),
+        FieldType::Int32 => ("Int32", "i32"),
+        FieldType::Fixed64 => ("Fixed64", "u64"),
+        FieldType::Fixed32 => ("Fixed32", "u32"),
+        FieldType::Bool => ("Bool", "bool"),
+        FieldType::String => ("String", "::std::string::String"),
+        FieldType::Bytes => ("Bytes", "::std::vec::Vec<u8>"),
+        FieldType::Uint32 => ("Uint32", "u32"),
+        FieldType::Sfixed32 => ("Sfixed32", "i32"),
+        FieldType::Sfixed64 => ("Sfixed64", "i64"),
+        FieldType::Sint32 => ("Sint32", "i32"),
+        FieldType::Sint64 => ("Sint64", "i64"),
+        FieldType::Group | FieldType::Message | FieldType::Enum => unreachable!(),
+    }
+}
+
+// Values of an enum: their names in the schema and in Rust, their numbers, and whether they are
+// aliases of an earlier value.
+fn enum_values<'e>(r#enum: &ast::Enum<'e>) -> Vec<(&'e str, String, i64, bool)> {
+    let mut numbers = HashSet::new();
+
+    r#enum
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            ast::EnumEntry::Pair { ident, value, .. } => Some((
+                *ident,
+                names::variant_name(r#enum.ident, ident),
+                *value,
+                !numbers.insert(*value),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+// Type of the values of a field.
+struct Value {
+    // Codec kind, without the path of the runtime.
+    kind: String,
+    rust: String,
+    // Path of the Rust enum, for values of enums.
+    r#enum: Option<String>,
+    scalar: Option<FieldType>,
+}
+
+impl Value {
+    fn packable(&self) -> bool {
+        self.r#enum.is_some()
+            || self
+                .scalar
+                .is_some_and(|scalar| !matches!(scalar, FieldType::String | FieldType::Bytes))
+    }
+}
+
+// A field of a message struct and its code.
+struct Field {
+    doc: Vec<String>,
+    name: String,
+    rust: String,
+    numbers: Vec<i64>,
+    merge: String,
+    encode: Vec<String>,
+    accessors: Vec<Vec<String>>,
+}
+
+// A oneof enum, generated in the module of the message.
+struct OneOf {
+    doc: Vec<String>,
+    name: String,
+    // Docs, names, numbers and values of the variants.
+    variants: Vec<(Vec<String>, String, i64, Value)>,
+}
+
+pub(crate) struct Generator<'g, 'a> {
+    workspace: &'g Workspace<'a>,
+    options: &'g Options,
+    graph: &'g HashMap<String, Vec<String>>,
+    file: usize,
+    layout: Layout,
+    // Segments of the module being generated.
+    module: Vec<String>,
+    code: Code,
+}
+
+impl<'g, 'a> Generator<'g, 'a> {
+    pub(crate) fn new(
+        workspace: &'g Workspace<'a>,
+        options: &'g Options,
+        graph: &'g HashMap<String, Vec<String>>,
+        package: &str,
+    ) -> Self {
+        Self {
+            workspace,
+            options,
+            graph,
+            file: 0,
+            layout: Layout::new("", &vec![]),
+            module: package_modules(package),
+            code: Code::default(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.code.text
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error {
+            file: self.workspace.file(self.file).name.to_string(),
+            message,
+        }
+    }
+
+    fn runtime(&self, item: &str) -> String {
+        format!("{}::{}", self.options.runtime, item)
+    }
+
+    fn codec(&self, function: &str, kinds: &[&Value]) -> String {
+        let kinds: Vec<_> = kinds
+            .iter()
+            .map(|value| self.runtime(&format!("codec::{}", value.kind)))
+            .collect();
+        self.runtime(&format!("codec::{}::<{}>", function, kinds.join(", ")))
+    }
+
+    // Documentation of the declaration at the path, from its leading and trailing comments.
+    fn doc(&self, path: &[usize]) -> Vec<String> {
+        let source = self.workspace.file(self.file).source;
+        editor::comments_at(source, &self.layout, path).lines()
+    }
+
+    // Path to the Rust type of the message or enum from the module being generated.
+    fn type_path(&self, symbol: &Symbol) -> String {
+        let package = self.workspace.file(symbol.file).package();
+        let relative = match package.is_empty() {
+            true => symbol.name.as_str(),
+            false => &symbol.name[package.len() + 1..],
+        };
+
+        let mut module = package_modules(package);
+        let mut parts: Vec<_> = relative.split('.').collect();
+        let ident = parts.pop().unwrap_or_default();
+        module.extend(parts.into_iter().map(names::module_name));
+
+        names::path(&self.module, &module, &names::type_name(ident))
+    }
+
+    // Singular values of messages reaching back to the message of the field are boxed.
+    fn value(&self, scope: &str, r#type: &str, singular: bool) -> Result<Value, Error> {
+        let resolved = self
+            .workspace
+            .resolve_type(self.file, scope, r#type)
+            .map_err(|error| self.error(format!("{}: {}", scope, error)))?;
+
+        Ok(match resolved {
+            TypeRef::Scalar(scalar) => {
+                let (kind, rust) = self::scalar(scalar);
+                Value {
+                    kind: kind.to_string(),
+                    rust: rust.to_string(),
+                    r#enum: None,
+                    scalar: Some(scalar),
+                }
+            }
+            TypeRef::Enum(symbol) => Value {
+                kind: "Int32".to_string(),
+                rust: "i32".to_string(),
+                r#enum: Some(self.type_path(symbol)),
+                scalar: None,
+            },
+            TypeRef::Message(symbol) => {
+                let path = self.type_path(symbol);
+                let rust = match singular && reaches(self.graph, &symbol.name, scope) {
+                    true => format!("::std::boxed::Box<{}>", path),
+                    false => path,
+                };
+
+                Value {
+                    kind: format!("Nested<{}>", rust),
+                    rust,
+                    r#enum: None,
+                    scalar: None,
+                }
+            }
+        })
+    }
+
+    pub(crate) fn file(&mut self, file: usize) -> Result<(), Error> {
+        let source = self.workspace.file(file);
+        self.file = file;
+        self.layout = Layout::new(source.source, &source.ast);
+
+        let package = source.package();
+        for (index, entry) in source.ast.iter().enumerate() {
+            match entry {
+                ast::FileEntry::Message(message) => self.message(message, package, &[index])?,
+                ast::FileEntry::Enum(r#enum) => self.r#enum(r#enum, &[index]),
+                ast::FileEntry::Service(service) => self.service(service, package, &[index])?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn message(
+        &mut self,
+        message: &ast::Message,
+        scope: &str,
+        path: &[usize],
+    ) -> Result<(), Error> {
+        let name = descriptor::names::join(scope, message.ident);
+        let module = names::module_name(message.ident);
+
+        let mut fields = vec![];
+        let mut oneofs = vec![];
+        for (index, entry) in message.entries.iter().enumerate() {
+            let path = [path, &[index]].concat();
+
+            match entry {
+                ast::MessageEntry::Field(field) => fields.push(self.field(field, &name, &path)?),
+                ast::MessageEntry::OneOf(oneof) => {
+                    let oneof = self.oneof(oneof, &name, &module, &path)?;
+                    fields.push(self.oneof_field(&oneof, &module));
+                    oneofs.push(oneof);
+                }
+                _ => {}
+            }
+        }
+
+        let ident = names::type_name(message.ident);
+        self.code.separate();
+        self.code.doc(&self.doc(path));
+        self.code
+            .line("#[derive(Debug, Clone, PartialEq, Default)]");
+        match fields.is_empty() {
+            true => self.code.line(&format!("pub struct {} {{}}", ident)),
+            false => {
+                self.code.open(&format!("pub struct {} {{", ident));
+                for field in &fields {
+                    self.code.doc(&field.doc);
+                    self.code
+                        .line(&format!("pub {}: {},", field.name, field.rust));
+                }
+                self.code.close("}");
+            }
+        }
+
+        let accessors: Vec<_> = fields.iter().flat_map(|field| &field.accessors).collect();
+        if !accessors.is_empty() {
+            self.code.line("");
+            self.code.open(&format!("impl {} {{", ident));
+            for (index, accessor) in accessors.into_iter().enumerate() {
+                if index > 0 {
+                    self.code.line("");
+                }
+                for line in accessor {
+                    self.code.line(line);
+                }
+            }
+            self.code.close("}");
+        }
+
+        self.message_impl(&ident, &fields);
+
+        let nested = message.entries.iter().enumerate().filter(|(_, entry)| {
+            matches!(
+                entry,
+                ast::MessageEntry::Message(_) | ast::MessageEntry::Enum(_)
+            )
+        });
+        let mut nested = nested.peekable();
+
+        if nested.peek().is_some() || !oneofs.is_empty() {
+            self.code.line("");
+            self.code
+                .doc(&[format!("Nested declarations and oneofs of [`{}`].", ident)]);
+            self.code.open(&format!("pub mod {} {{", module));
+            self.module.push(module);
+
+            for (index, entry) in nested {
+                let path = [path, &[index]].concat();
+                match entry {
+                    ast::MessageEntry::Message(nested) => self.message(nested, &name, &path)?,
+                    ast::MessageEntry::Enum(r#enum) => self.r#enum(r#enum, &path),
+                    _ => {}
+                }
+            }
+            for oneof in &oneofs {
+                self.oneof_enum(oneof);
+            }
+
+            self.module.pop();
+            self.code.close("}");
+        }
+
+        Ok(())
+    }
+
+    fn message_impl(&mut self, ident: &str, fields: &[Field]) {
+        self.code.line("");
+        self.code.open(&format!(
+            "impl {} for {} {{",
+            self.runtime("Message"),
+            ident
+        ));
+
+        self.code.open("fn merge_field(");
+        self.code.line("&mut self,");
+        self.code.line(&format!("tag: {},", self.runtime("Tag")));
+        self.code
+            .line(&format!("reader: &mut {},", self.runtime("Reader")));
+        self.code.close(&format!(
+            ") -> ::std::result::Result<(), {}> {{",
+            self.runtime("DecodeError")
+        ));
+        self.code.depth += 1;
+        match fields.is_empty() {
+            true => self.code.line("reader.skip_field(tag).map(|_| ())"),
+            false => {
+                self.code.open("match tag.number {");
+                for field in fields {
+                    let numbers: Vec<_> = field.numbers.iter().map(i64::to_string).collect();
+                    self.code
+                        .line(&format!("{} => {},", numbers.join(" | "), field.merge));
+                }
+                self.code.line("_ => reader.skip_field(tag).map(|_| ()),");
+                self.code.close("}");
+            }
+        }
+        self.code.close("}");
+
+        self.code.line("");
+        match fields.is_empty() {
+            true => self.code.line(&format!(
+                "fn encode_to(&self, _writer: &mut {}) {{}}",
+                self.runtime("Writer")
+            )),
+            false => {
+                self.code.open(&format!(
+                    "fn encode_to(&self, writer: &mut {}) {{",
+                    self.runtime("Writer")
+                ));
+                for line in fields.iter().flat_map(|field| &field.encode) {
+                    self.code.line(line);
+                }
+                self.code.close("}");
+            }
+        }
+
+        self.code.close("}");
+    }
+
+    fn field(&self, field: &ast::Field, message: &str, path: &[usize]) -> Result<Field, Error> {
+        let name = names::field_name(field.ident);
+        let number = field.index;
+        let syntax = self.workspace.file(self.file).syntax();
+
+        let mut result = Field {
+            doc: self.doc(path),
+            name: name.clone(),
+            rust: String::new(),
+            numbers: vec![number],
+            merge: String::new(),
+            encode: vec![],
+            accessors: vec![],
+        };
+
+        if let Some((key, value)) = resolver::map_types(field.r#type) {
+            let key = self.value(message, key, false)?;
+            let value = self.value(message, value, false)?;
+
+            result.rust = format!("::std::collections::BTreeMap<{}, {}>", key.rust, value.rust);
+            result.merge = format!(
+                "{}(&mut self.{}, tag, reader)",
+                self.codec("merge_map", &[&key, &value]),
+                name
+            );
+            result.encode.push(format!(
+                "{}(&self.{}, {}, writer);",
+                self.codec("encode_map", &[&key, &value]),
+                name,
+                number
+            ));
+            return Ok(result);
+        }
+
+        let repeated = field.modifier == ast::FieldModifier::Repeated;
+        let value = self.value(message, field.r#type, !repeated)?;
+
+        if repeated {
+            let packed = field
+                .options
+                .iter()
+                .find(|option| option.key == "packed")
+                .map_or(syntax != "proto2", |option| {
+                    option.value == ast::MapValue::Boolean(true)
+                });
+            let encode = match packed && value.packable() {
+                true => "encode_packed",
+                false => "encode_repeated",
+            };
+
+            result.rust = format!("::std::vec::Vec<{}>", value.rust);
+            result.merge = format!(
+                "{}(&mut self.{}, tag, reader)",
+                self.codec("merge_repeated", &[&value]),
+                name
+            );
+            result.encode.push(format!(
+                "{}(&self.{}, {}, writer);",
+                self.codec(encode, &[&value]),
+                name,
+                number
+            ));
+            return Ok(result);
+        }
+
+        let message_value = value.r#enum.is_none() && value.scalar.is_none();
+        let presence =
+            message_value || syntax != "proto3" || field.modifier != ast::FieldModifier::None;
+
+        let (merge, encode) = match presence {
+            true => ("merge_optional", "encode_optional"),
+            false => ("merge", "encode"),
+        };
+        result.rust = match presence {
+            true => format!("::std::option::Option<{}>", value.rust),
+            false => value.rust.clone(),
+        };
+        result.merge = format!(
+            "{}(&mut self.{}, tag, reader)",
+            self.codec(merge, &[&value]),
+            name
+        );
+        result.encode.push(format!(
+            "{}(&self.{}, {}, writer);",
+            self.codec(encode, &[&value]),
+            name,
+            number
+        ));
+
+        if !message_value {
+            let default = field
+                .options
+                .iter()
+                .find(|option| option.key == "default")
+                .map(|option| self.default(&value, message, field.r#type, &option.value))
+                .transpose()?;
+            result.accessors = self.accessors(&name, &value, presence, default);
+        }
+
+        Ok(result)
+    }
+
+    // Getter applying the default value to fields with presence and converting values of enums,
+    // and a setter for the latter.
+    fn accessors(
+        &self,
+        name: &str,
+        value: &Value,
+        presence: bool,
+        default: Option<String>,
+    ) -> Vec<Vec<String>> {
+        let ident = name.strip_prefix("r#").unwrap_or(name);
+        let or_default = match &default {
+            Some(default) => format!("unwrap_or({})", default),
+            None => "unwrap_or_default()".to_string(),
+        };
+
+        if let Some(r#enum) = &value.r#enum {
+            let (body, set) = match presence {
+                true => (
+                    format!(
+                        "self.{}.and_then(|value| {}::try_from(value).ok()).{}",
+                        name, r#enum, or_default
+                    ),
+                    format!("self.{} = ::std::option::Option::Some(value as i32);", name),
+                ),
+                false => (
+                    format!("{}::try_from(self.{}).unwrap_or_default()", r#enum, name),
+                    format!("self.{} = value as i32;", name),
+                ),
+            };
+
+            let getter_doc = match presence {
+                true => format!(
+                    "/// Value of `{}` as an enum, or its default if it is unset or unknown.",
+                    ident
+                ),
+                false => format!(
+                    "/// Value of `{}` as an enum, or its default if it is unknown.",
+                    ident
+                ),
+            };
+
+            return vec![
+                vec![
+                    getter_doc,
+                    format!("pub fn {}(&self) -> {} {{", name, r#enum),
+                    format!("    {}", body),
+                    "}".to_string(),
+                ],
+                vec![
+                    format!("/// Sets `{}` to the value of the enum.", ident),
+                    format!("pub fn set_{}(&mut self, value: {}) {{", ident, r#enum),
+                    format!("    {}", set),
+                    "}".to_string(),
+                ],
+            ];
+        }
+
+        if !presence {
+            return vec![];
+        }
+
+        let (r#type, body) = match value.scalar {
+            Some(FieldType::String) => ("&str", format!("self.{}.as_deref().{}", name, or_default)),
+            Some(FieldType::Bytes) => ("&[u8]", format!("self.{}.as_deref().{}", name, or_default)),
+            _ => (value.rust.as_str(), format!("self.{}.{}", name, or_default)),
+        };
+
+        vec![vec![
+            format!("/// Value of `{}`, or its default if it is unset.", ident),
+            format!("pub fn {}(&self) -> {} {{", name, r#type),
+            format!("    {}", body),
+            "}".to_string(),
+        ]]
+    }
+
+    // Rust expression of the `default` option of a field.
+    fn default(
+        &self,
+        value: &Value,
+        scope: &str,
+        r#type: &str,
+        default: &ast::MapValue,
+    ) -> Result<String, Error> {
+        let invalid = || self.error(format!("{}: invalid default value for {}", scope, r#type));
+
+        if let Some(path) = &value.r#enum {
+            let ast::MapValue::Ident(ident) = default else {
+                return Err(invalid());
+            };
+            let Ok(TypeRef::Enum(symbol)) = self.workspace.resolve_type(self.file, scope, r#type)
+            else {
+                return Err(invalid());
+            };
+            let Some(Node::Enum(r#enum)) = self.workspace.node(symbol) else {
+                return Err(invalid());
+            };
+
+            let values = enum_values(r#enum);
+            let number = values
+                .iter()
+                .find(|(name, ..)| name == ident)
+                .map(|(.., number, _)| *number)
+                .ok_or_else(invalid)?;
+            let (_, variant, ..) = values
+                .iter()
+                .find(|(.., other, alias)| *other == number && !alias)
+                .ok_or_else(invalid)?;
+
+            return Ok(format!("{}::{}", path, variant));
+        }
+
+        let float = |value: f64, rust: &str| match value {
+            _ if value.is_nan() => format!("{}::NAN", rust),
+            _ if value == f64::INFINITY => format!("{}::INFINITY", rust),
+            _ if value == f64::NEG_INFINITY => format!("{}::NEG_INFINITY", rust),
+            _ => format!("{:?}", value),
+        };
+
+        Ok(match (value.scalar, default) {
+            (Some(FieldType::Bool), ast::MapValue::Boolean(value)) => value.to_string(),
+            (Some(FieldType::String), ast::MapValue::String(text)) => {
+                let bytes = descriptor::unescape(text).map_err(|_| invalid())?;
+                format!("{:?}", String::from_utf8(bytes).map_err(|_| invalid())?)
+            }
+            (Some(FieldType::Bytes), ast::MapValue::String(text)) => {
+                let bytes = descriptor::unescape(text).map_err(|_| invalid())?;
+                let escaped: String = bytes
+                    .iter()
+                    .flat_map(|byte| std::ascii::escape_default(*byte))
+                    .map(char::from)
+                    .collect();
+                format!("b\"{}\"", escaped)
+            }
+            (Some(FieldType::Float | FieldType::Double), ast::MapValue::Float(number)) => {
+                float(*number, &value.rust)
+            }
+            (Some(FieldType::Float | FieldType::Double), ast::MapValue::Integer(number)) => {
+                float(*number as f64, &value.rust)
+            }
+            (Some(FieldType::Float | FieldType::Double), ast::MapValue::Ident(ident)) => {
+                match *ident {
+                    "inf" => float(f64::INFINITY, &value.rust),
+                    "nan" => float(f64::NAN, &value.rust),
+                    _ => return Err(invalid()),
+                }
+            }
+            (Some(_), ast::MapValue::Integer(number)) => number.to_string(),
+            _ => return Err(invalid()),
+        })
+    }
+
+    // The enum is generated in the module of the message, so the paths to the types of its values
+    // start from there.
+    fn oneof(
+        &mut self,
+        oneof: &ast::OneOf,
+        message: &str,
+        module: &str,
+        path: &[usize],
+    ) -> Result<OneOf, Error> {
+        let mut variants = vec![];
+
+        self.module.push(module.to_string());
+        for (index, entry) in oneof.entries.iter().enumerate() {
+            if let ast::OneOfEntry::Field(field) = entry {
+                let value = self.value(message, field.r#type, true);
+                variants.push((
+                    self.doc(&[path, &[index]].concat()),
+                    names::type_name(field.ident),
+                    field.index,
+                    value,
+                ));
+            }
+        }
+        self.module.pop();
+
+        let variants = variants
+            .into_iter()
+            .map(|(doc, name, number, value)| Ok((doc, name, number, value?)))
+            .collect::<Result<_, Error>>()?;
+
+        Ok(OneOf {
+            doc: self.doc(path),
+            name: names::type_name(oneof.ident),
+            variants,
+        })
+    }
+
+    fn oneof_field(&self, oneof: &OneOf, module: &str) -> Field {
+        let name = names::field_name(&case::lower_snake_case(&oneof.name));
+        let r#type = format!("{}::{}", module, oneof.name);
+
+        Field {
+            doc: oneof.doc.clone(),
+            rust: format!("::std::option::Option<{}>", r#type),
+            numbers: oneof
+                .variants
+                .iter()
+                .map(|(_, _, number, _)| *number)
+                .collect(),
+            merge: format!("{}::merge(&mut self.{}, tag, reader)", r#type, name),
+            encode: vec![
+                format!(
+                    "if let ::std::option::Option::Some({}) = &self.{} {{",
+                    name.trim_start_matches("r#"),
+                    name
+                ),
+                format!("    {}.encode_to(writer);", name.trim_start_matches("r#")),
+                "}".to_string(),
+            ],
+            accessors: vec![],
+            name,
+        }
+    }
+
+    fn oneof_enum(&mut self, oneof: &OneOf) {
+        self.code.separate();
+        self.code.doc(&oneof.doc);
+        self.code.line("#[derive(Debug, Clone, PartialEq)]");
+        self.code.open(&format!("pub enum {} {{", oneof.name));
+        for (doc, variant, _, value) in &oneof.variants {
+            self.code.doc(doc);
+            self.code.line(&format!("{}({}),", variant, value.rust));
+        }
+        self.code.close("}");
+
+        self.code.line("");
+        self.code.open(&format!("impl {} {{", oneof.name));
+        self.code.open("pub fn merge(");
+        self.code.line("field: &mut ::std::option::Option<Self>,");
+        self.code.line(&format!("tag: {},", self.runtime("Tag")));
+        self.code
+            .line(&format!("reader: &mut {},", self.runtime("Reader")));
+        self.code.close(&format!(
+            ") -> ::std::result::Result<(), {}> {{",
+            self.runtime("DecodeError")
+        ));
+        self.code.depth += 1;
+        self.code.open("match tag.number {");
+        for (_, variant, number, value) in &oneof.variants {
+            self.code.open(&format!("{} => {{", number));
+            self.code.open("let mut value = match field.take() {");
+            self.code.line(&format!(
+                "::std::option::Option::Some(Self::{}(value)) => value,",
+                variant
+            ));
+            self.code.line("_ => ::std::default::Default::default(),");
+            self.code.close("};");
+            self.code.line(&format!(
+                "{}(&mut value, tag, reader)?;",
+                self.codec("merge", &[value])
+            ));
+            self.code.line(&format!(
+                "*field = ::std::option::Option::Some(Self::{}(value));",
+                variant
+            ));
+            self.code.line("::std::result::Result::Ok(())");
+            self.code.close("}");
+        }
+        self.code.line("_ => reader.skip_field(tag).map(|_| ()),");
+        self.code.close("}");
+        self.code.close("}");
+
+        self.code.line("");
+        self.code.open(&format!(
+            "pub fn encode_to(&self, writer: &mut {}) {{",
+            self.runtime("Writer")
+        ));
+        self.code.open("match self {");
+        for (_, variant, number, value) in &oneof.variants {
+            self.code.line(&format!(
+                "Self::{}(value) => {}(value, {}, writer),",
+                variant,
+                self.codec("write", &[value]),
+                number
+            ));
+        }
+        self.code.close("}");
+        self.code.close("}");
+        self.code.close("}");
+    }
+
+    fn r#enum(&mut self, r#enum: &ast::Enum, path: &[usize]) {
+        let ident = names::type_name(r#enum.ident);
+        let values = enum_values(r#enum);
+
+        self.code.separate();
+        self.code.doc(&self.doc(path));
+        self.code
+            .line("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]");
+        self.code.line("#[repr(i32)]");
+        self.code.open(&format!("pub enum {} {{", ident));
+        let mut first = true;
+        for (index, entry) in r#enum.entries.iter().enumerate() {
+            let ast::EnumEntry::Pair { ident, value, .. } = entry else {
+                continue;
+            };
+            if values
+                .iter()
+                .any(|(name, .., alias)| name == ident && *alias)
+            {
+                continue;
+            }
+
+            self.code.doc(&self.doc(&[path, &[index]].concat()));
+            if first {
+                self.code.line("#[default]");
+                first = false;
+            }
+            self.code.line(&format!(
+                "{} = {},",
+                names::variant_name(r#enum.ident, ident),
+                value
+            ));
+        }
+        self.code.close("}");
+
+        self.code.line("");
+        self.code.open(&format!("impl {} {{", ident));
+        for (name, variant, number, alias) in &values {
+            if *alias {
+                let original = values
+                    .iter()
+                    .find(|(.., other, alias)| other == number && !alias)
+                    .map(|(_, variant, ..)| variant.as_str())
+                    .unwrap_or_default();
+                self.code.line(&format!(
+                    "/// Alias of [`Self::{}`] named `{}`.",
+                    original, name
+                ));
+                self.code.line(&format!(
+                    "pub const {}: Self = Self::{};",
+                    case::upper_snake_case(variant),
+                    original
+                ));
+                self.code.line("");
+            }
+        }
+
+        self.code.line("/// Name of the value in the schema.");
+        self.code
+            .open("pub fn as_str_name(&self) -> &'static str {");
+        self.code.open("match self {");
+        for (name, variant, _, alias) in &values {
+            if !alias {
+                self.code
+                    .line(&format!("Self::{} => \"{}\",", variant, name));
+            }
+        }
+        self.code.close("}");
+        self.code.close("}");
+
+        self.code.line("");
+        self.code
+            .line("/// Value by its name in the schema, aliases included.");
+        self.code
+            .open("pub fn from_str_name(name: &str) -> ::std::option::Option<Self> {");
+        self.code.open("match name {");
+        for (name, variant, number, _) in &values {
+            let original = values
+                .iter()
+                .find(|(.., other, alias)| other == number && !alias)
+                .map_or(variant, |(_, variant, ..)| variant);
+            self.code.line(&format!(
+                "\"{}\" => ::std::option::Option::Some(Self::{}),",
+                name, original
+            ));
+        }
+        self.code.line("_ => ::std::option::Option::None,");
+        self.code.close("}");
+        self.code.close("}");
+        self.code.close("}");
+
+        self.code.line("");
+        self.code.open(&format!(
+            "impl ::std::convert::TryFrom<i32> for {} {{",
+            ident
+        ));
+        self.code.line("type Error = i32;");
+        self.code.line("");
+        self.code
+            .open("fn try_from(value: i32) -> ::std::result::Result<Self, i32> {");
+        self.code.open("match value {");
+        for (_, variant, number, alias) in &values {
+            if !alias {
+                self.code.line(&format!(
+                    "{} => ::std::result::Result::Ok(Self::{}),",
+                    number, variant
+                ));
+            }
+        }
+        self.code.line("_ => ::std::result::Result::Err(value),");
+        self.code.close("}");
+        self.code.close("}");
+        self.code.close("}");
+
+        self.code.line("");
+        self.code
+            .open(&format!("impl ::std::convert::From<{}> for i32 {{", ident));
+        self.code
+            .open(&format!("fn from(value: {}) -> i32 {{", ident));
+        self.code.line("value as i32");
+        self.code.close("}");
+        self.code.close("}");
+    }
+
+    // Methods of the trait take the requests and return the replies, client streams are
+    // iterators of requests and server streams are callbacks receiving the replies.
+    fn service(
+        &mut self,
+        service: &ast::Service,
+        scope: &str,
+        path: &[usize],
+    ) -> Result<(), Error> {
+        let name = descriptor::names::join(scope, service.ident);
+
+        self.code.separate();
+        self.code.doc(&self.doc(path));
+        self.code
+            .open(&format!("pub trait {} {{", names::type_name(service.ident)));
+        self.code.line("type Error;");
+        self.code.line("");
+        self.code.line("/// Fully-qualified name of the service.");
+        self.code
+            .line(&format!("const NAME: &'static str = \"{}\";", name));
+
+        for (index, entry) in service.entries.iter().enumerate() {
+            let ast::ServiceEntry::Rpc(rpc) = entry else {
+                continue;
+            };
+
+            let request = self.rpc_type(&name, rpc.request)?;
+            let reply = self.rpc_type(&name, rpc.reply)?;
+            let (client_streaming, server_streaming) = match rpc.stream {
+                ast::RpcStream::None => (false, false),
+                ast::RpcStream::ServerBound => (true, false),
+                ast::RpcStream::ClientBound => (false, true),
+                ast::RpcStream::Bidirectional => (true, true),
+            };
+
+            let mut parameters = vec!["&self".to_string()];
+            parameters.push(match client_streaming {
+                true => format!(
+                    "requests: &mut dyn ::std::iter::Iterator<Item = {}>",
+                    request
+                ),
+                false => format!("request: {}", request),
+            });
+            let output = match server_streaming {
+                true => {
+                    parameters.push(format!("replies: &mut dyn ::std::ops::FnMut({})", reply));
+                    "()".to_string()
+                }
+                false => reply,
+            };
+
+            self.code.line("");
+            self.code.doc(&self.doc(&[path, &[index]].concat()));
+            self.code
+                .open(&format!("fn {}(", names::field_name(rpc.ident)));
+            for parameter in parameters {
+                self.code.line(&format!("{},", parameter));
+            }
+            self.code.close(&format!(
+                ") -> ::std::result::Result<{}, Self::Error>;",
+                output
+            ));
+        }
+
+        self.code.close("}");
+        Ok(())
+    }
+
+    fn rpc_type(&self, service: &str, r#type: &str) -> Result<String, Error> {
+        match self.workspace.resolve_type(self.file, service, r#type) {
+            Ok(TypeRef::Message(symbol)) => Ok(self.type_path(symbol)),
+            Ok(_) => Err(self.error(format!("{}: {} is not a message", service, r#type))),
+            Err(error) => Err(self.error(format!("{}: {}", service, error))),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Tree {
+    file: Option<String>,
+    children: BTreeMap<String, Tree>,
+}
+
+impl Tree {
+    fn write(&self, code: &mut Code) {
+        if let Some(file) = &self.file {
+            code.line(&format!("include!(\"{}\");", file));
+        }
+
+        for (name, child) in &self.children {
+            code.separate();
+            code.open(&format!("pub mod {} {{", name));
+            child.write(code);
+            code.close("}");
+        }
+    }
+}
+
+// Modules mirroring the packages, each including the file generated for its package.
+pub(crate) fn modules<'p>(packages: impl IntoIterator<Item = (&'p str, &'p str)>) -> String {
+    let mut root = Tree::default();
+
+    for (package, file) in packages {
+        let mut tree = &mut root;
+        for module in package_modules(package) {
+            tree = tree.children.entry(module).or_default();
+        }
+        tree.file = Some(file.to_string());
+    }
+
+    let mut code = Code::default();
+    root.write(&mut code);
+    code.text
+}
@@ -0,0 +1,271 @@
+use crate::{DynamicMessage, Field, FieldValue, UnknownValue, Value};
+use descriptor::{FieldInfo, FieldType, Pool};
+use wire::{DecodeError, DecodeErrorKind, Reader, Tag, WireType, Writer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncodeError {
+    UnknownType(String),
+    UnknownField { message: String, number: u32 },
+    TypeMismatch { field: String, expected: FieldType },
+}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownType(name) => write!(f, "Unknown message type {}", name),
+            Self::UnknownField { message, number } => {
+                write!(f, "Message {} has no field {}", message, number)
+            }
+            Self::TypeMismatch { field, expected } => {
+                write!(
+                    f,
+                    "Field {} expects a value of type {}",
+                    field,
+                    expected.name()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+pub fn wire_type(r#type: FieldType) -> WireType {
+    match r#type {
+        FieldType::Double | FieldType::Fixed64 | FieldType::Sfixed64 => WireType::Fixed64,
+        FieldType::Float | FieldType::Fixed32 | FieldType::Sfixed32 => WireType::Fixed32,
+        FieldType::String | FieldType::Bytes | FieldType::Message => WireType::LengthDelimited,
+        FieldType::Group => WireType::StartGroup,
+        _ => WireType::Varint,
+    }
+}
+
+// Field of the message or an extension of it.
+pub fn find_field<'p>(pool: &'p Pool, message: &str, number: u32) -> Option<&'p FieldInfo> {
+    pool.message(message)?
+        .field(number)
+        .or_else(|| pool.extension_by_number(message, number))
+}
+
+pub fn decode(pool: &Pool, type_name: &str, data: &[u8]) -> Result<DynamicMessage, DecodeError> {
+    decode_at(pool, type_name, data, 0)
+}
+
+fn decode_at(
+    pool: &Pool,
+    type_name: &str,
+    data: &[u8],
+    offset: usize,
+) -> Result<DynamicMessage, DecodeError> {
+    let r#type = pool.message(type_name).ok_or_else(|| {
+        DecodeError::new(
+            DecodeErrorKind::Custom(format!("Unknown message type {}", type_name)),
+            offset,
+        )
+    })?;
+
+    let mut reader = Reader::with_offset(data, offset);
+    let mut message = DynamicMessage::new(r#type.name.clone());
+
+    while !reader.is_empty() {
+        let tag = reader.read_tag()?;
+        let start = reader.position();
+        let raw = reader.skip_field(tag)?;
+
+        let value = find_field(pool, &r#type.name, tag.number)
+            .and_then(|field| decode_field(pool, field, tag, raw, start))
+            .unwrap_or_else(|| {
+                FieldValue::Unknown(UnknownValue {
+                    wire_type: tag.wire_type,
+                    data: raw.to_vec(),
+                })
+            });
+
+        message.fields.push(Field {
+            number: tag.number,
+            value,
+        });
+    }
+
+    Ok(message)
+}
+
+// None when the value doesn't match the schema, or its encoding is not the one `encode` would
+// produce; such values are kept as unknown to be written back unchanged.
+fn decode_field(
+    pool: &Pool,
+    field: &FieldInfo,
+    tag: Tag,
+    raw: &[u8],
+    offset: usize,
+) -> Option<FieldValue> {
+    let expected = wire_type(field.r#type);
+
+    let value = if tag.wire_type == expected && field.r#type != FieldType::Group {
+        let mut reader = Reader::with_offset(raw, offset);
+        let value = read_value(pool, field, &mut reader)?;
+        reader.is_empty().then_some(FieldValue::Single(value))?
+    } else if field.is_repeated()
+        && field.r#type.is_packable()
+        && tag.wire_type == WireType::LengthDelimited
+    {
+        let mut reader = Reader::with_offset(raw, offset);
+        let data = reader.read_length_delimited().ok()?;
+        let mut reader = Reader::with_offset(data, offset + raw.len() - data.len());
+        let mut values = vec![];
+
+        while !reader.is_empty() {
+            values.push(read_value(pool, field, &mut reader)?);
+        }
+
+        FieldValue::Packed(values)
+    } else {
+        return None;
+    };
+
+    let mut writer = Writer::new();
+    write_payload(pool, field, &value, &mut writer).ok()?;
+
+    (writer.into_inner() == raw).then_some(value)
+}
+
+fn read_value(pool: &Pool, field: &FieldInfo, reader: &mut Reader) -> Option<Value> {
+    Some(match field.r#type {
+        FieldType::Double => Value::F64(f64::from_bits(reader.read_fixed64().ok()?)),
+        FieldType::Float => Value::F32(f32::from_bits(reader.read_fixed32().ok()?)),
+        FieldType::Int64 => Value::I64(reader.read_varint().ok()? as i64),
+        FieldType::Uint64 => Value::U64(reader.read_varint().ok()?),
+        FieldType::Int32 => Value::I32(reader.read_varint().ok()? as i32),
+        FieldType::Fixed64 => Value::U64(reader.read_fixed64().ok()?),
+        FieldType::Fixed32 => Value::U32(reader.read_fixed32().ok()?),
+        FieldType::Bool => Value::Bool(reader.read_varint().ok()? != 0),
+        FieldType::String => Value::String(reader.read_string().ok()?.to_string()),
+        FieldType::Bytes => Value::Bytes(reader.read_length_delimited().ok()?.to_vec()),
+        FieldType::Uint32 => Value::U32(reader.read_varint().ok()? as u32),
+        FieldType::Enum => Value::Enum(reader.read_varint().ok()? as i32),
+        FieldType::Sfixed32 => Value::I32(reader.read_fixed32().ok()? as i32),
+        FieldType::Sfixed64 => Value::I64(reader.read_fixed64().ok()? as i64),
+        FieldType::Sint32 => Value::I32(wire::decode_zigzag32(reader.read_varint().ok()? as u32)),
+        FieldType::Sint64 => Value::I64(wire::decode_zigzag64(reader.read_varint().ok()?)),
+        FieldType::Message => {
+            let data = reader.read_length_delimited().ok()?;
+            let offset = reader.position() - data.len();
+
+            Value::Message(decode_at(pool, field.type_name.as_deref()?, data, offset).ok()?)
+        }
+        FieldType::Group => return None,
+    })
+}
+
+pub fn encode(pool: &Pool, message: &DynamicMessage) -> Result<Vec<u8>, EncodeError> {
+    let mut writer = Writer::new();
+    encode_to(pool, message, &mut writer)?;
+    Ok(writer.into_inner())
+}
+
+fn encode_to(
+    pool: &Pool,
+    message: &DynamicMessage,
+    writer: &mut Writer,
+) -> Result<(), EncodeError> {
+    let r#type = pool
+        .message(&message.type_name)
+        .ok_or_else(|| EncodeError::UnknownType(message.type_name.clone()))?;
+
+    for field in &message.fields {
+        if let FieldValue::Unknown(value) = &field.value {
+            writer.write_tag(Tag::new(field.number, value.wire_type));
+            writer.write_raw(&value.data);
+            continue;
+        }
+
+        let info = find_field(pool, &r#type.name, field.number).ok_or_else(|| {
+            EncodeError::UnknownField {
+                message: message.type_name.clone(),
+                number: field.number,
+            }
+        })?;
+
+        let wire_type = match field.value {
+            FieldValue::Packed(_) => WireType::LengthDelimited,
+            _ => wire_type(info.r#type),
+        };
+
+        writer.write_tag(Tag::new(field.number, wire_type));
+        write_payload(pool, info, &field.value, writer)?;
+    }
+
+    Ok(())
+}
+
+// Encoding of the value without its tag.
+fn write_payload(
+    pool: &Pool,
+    field: &FieldInfo,
+    value: &FieldValue,
+    writer: &mut Writer,
+) -> Result<(), EncodeError> {
+    match value {
+        FieldValue::Single(value) => write_value(pool, field, value, writer)?,
+        FieldValue::Packed(values) => {
+            let mut packed = Writer::new();
+
+            for value in values {
+                write_value(pool, field, value, &mut packed)?;
+            }
+
+            writer.write_length_delimited(&packed.into_inner());
+        }
+        FieldValue::Unknown(value) => writer.write_raw(&value.data),
+    }
+
+    Ok(())
+}
+
+fn write_value(
+    pool: &Pool,
+    field: &FieldInfo,
+    value: &Value,
+    writer: &mut Writer,
+) -> Result<(), EncodeError> {
+    match (field.r#type, value) {
+        (FieldType::Double, Value::F64(value)) => writer.write_fixed64(value.to_bits()),
+        (FieldType::Float, Value::F32(value)) => writer.write_fixed32(value.to_bits()),
+        (FieldType::Int64, Value::I64(value)) => writer.write_varint(*value as u64),
+        (FieldType::Uint64, Value::U64(value)) => writer.write_varint(*value),
+        // negative values take 10 bytes, as if they were int64
+        (FieldType::Int32, Value::I32(value)) => writer.write_varint(*value as i64 as u64),
+        (FieldType::Fixed64, Value::U64(value)) => writer.write_fixed64(*value),
+        (FieldType::Fixed32, Value::U32(value)) => writer.write_fixed32(*value),
+        (FieldType::Bool, Value::Bool(value)) => writer.write_varint(*value as u64),
+        (FieldType::String, Value::String(value)) => {
+            writer.write_length_delimited(value.as_bytes())
+        }
+        (FieldType::Bytes, Value::Bytes(value)) => writer.write_length_delimited(value),
+        (FieldType::Uint32, Value::U32(value)) => writer.write_varint(*value as u64),
+        (FieldType::Enum, Value::Enum(value)) => writer.write_varint(*value as i64 as u64),
+        (FieldType::Sfixed32, Value::I32(value)) => writer.write_fixed32(*value as u32),
+        (FieldType::Sfixed64, Value::I64(value)) => writer.write_fixed64(*value as u64),
+        (FieldType::Sint32, Value::I32(value)) => {
+            writer.write_varint(wire::encode_zigzag32(*value) as u64)
+        }
+        (FieldType::Sint64, Value::I64(value)) => {
+            writer.write_varint(wire::encode_zigzag64(*value))
+        }
+        (FieldType::Message, Value::Message(message))
+            if field.type_name.as_deref() == Some(message.type_name.as_str()) =>
+        {
+            let mut nested = Writer::new();
+            encode_to(pool, message, &mut nested)?;
+            writer.write_length_delimited(&nested.into_inner());
+        }
+        (expected, _) => {
+            return Err(EncodeError::TypeMismatch {
+                field: field.full_name.clone(),
+                expected,
+            });
+        }
+    }
+
+    Ok(())
+}
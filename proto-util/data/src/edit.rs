@@ -0,0 +1,191 @@
+// Changes an editor makes to a decoded message. Each applies to the nested message a path leads
+// to, and leaves the other fields as they were decoded, so that saving re-encodes them the same.
+
+use crate::{DynamicMessage, EncodeError, Value};
+use descriptor::{FieldInfo, Pool};
+use std::path::Path;
+use wire::DecodeError;
+
+// Field number and index among the values of the field, leading to a nested message.
+pub type Step = (u32, usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    // Sets a singular field, or the element at the index of a repeated one.
+    Set {
+        number: u32,
+        index: Option<usize>,
+        value: Value,
+    },
+    // Appends an element to a repeated field.
+    Push {
+        number: u32,
+        value: Value,
+    },
+    // Removes the element at the index of a repeated field, or all the values of the field.
+    Delete {
+        number: u32,
+        index: Option<usize>,
+    },
+    // Sets the member of the oneof to its default value unless it is set already, which clears the
+    // other members; none clears them all.
+    OneOf {
+        oneof: usize,
+        case: Option<u32>,
+    },
+    // Removes the field at the position among the fields of the message, e.g. an unknown one.
+    Remove(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    // No nested message at the path.
+    NotFound(Vec<Step>),
+    UnknownField { message: String, number: u32 },
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(path) => write!(f, "No message at {:?}", path),
+            Self::UnknownField { message, number } => {
+                write!(f, "Message {} has no field {}", message, number)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+pub fn message_at<'m>(message: &'m DynamicMessage, path: &[Step]) -> Option<&'m DynamicMessage> {
+    path.iter().try_fold(message, |message, (number, index)| {
+        match message.get(*number).nth(*index)? {
+            Value::Message(nested) => Some(nested),
+            _ => None,
+        }
+    })
+}
+
+fn message_at_mut<'m>(
+    message: &'m mut DynamicMessage,
+    path: &[Step],
+) -> Option<&'m mut DynamicMessage> {
+    path.iter().try_fold(message, |message, (number, index)| {
+        match message.get_mut(*number).nth(*index)? {
+            Value::Message(nested) => Some(nested),
+            _ => None,
+        }
+    })
+}
+
+pub fn apply(
+    pool: &Pool,
+    message: &mut DynamicMessage,
+    path: &[Step],
+    change: Change,
+) -> Result<(), EditError> {
+    let message =
+        message_at_mut(message, path).ok_or_else(|| EditError::NotFound(path.to_vec()))?;
+    let field = |number: u32| -> Result<FieldInfo, EditError> {
+        crate::find_field(pool, &message.type_name, number)
+            .cloned()
+            .ok_or_else(|| EditError::UnknownField {
+                message: message.type_name.clone(),
+                number,
+            })
+    };
+
+    match change {
+        Change::Set {
+            number,
+            index: Some(index),
+            value,
+        } => {
+            if let Some(old) = message.get_mut(number).nth(index) {
+                *old = value;
+            }
+        }
+        Change::Set {
+            number,
+            index: None,
+            value,
+        } => {
+            let field = field(number)?;
+            message.set(pool, &field, value);
+        }
+        Change::Push { number, value } => {
+            let field = field(number)?;
+            message.push(&field, value);
+        }
+        Change::Delete {
+            number,
+            index: Some(index),
+        } => {
+            message.remove(number, index);
+        }
+        Change::Delete {
+            number,
+            index: None,
+        } => message.clear(number),
+        Change::OneOf {
+            case: Some(number), ..
+        } => {
+            let field = field(number)?;
+            if !message.has(number) {
+                message.set(pool, &field, Value::default_of(pool, &field));
+            }
+        }
+        Change::OneOf { oneof, case: None } => {
+            let members: Vec<u32> = pool
+                .message(&message.type_name)
+                .map(|info| info.oneof_fields(oneof).map(|field| field.number).collect())
+                .unwrap_or_default();
+
+            for number in members {
+                message.clear(number);
+            }
+        }
+        Change::Remove(position) => {
+            if position < message.fields.len() {
+                message.fields.remove(position);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum FileError {
+    Io(std::io::Error),
+    Decode(DecodeError),
+    Encode(EncodeError),
+}
+
+impl std::fmt::Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::Decode(error) => write!(f, "{}", error),
+            Self::Encode(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+// The message in the file, a new empty one if there is no file.
+pub fn load(pool: &Pool, type_name: &str, path: &Path) -> Result<DynamicMessage, FileError> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(error) => return Err(FileError::Io(error)),
+    };
+
+    crate::decode(pool, type_name, &data).map_err(FileError::Decode)
+}
+
+pub fn save(pool: &Pool, message: &DynamicMessage, path: &Path) -> Result<(), FileError> {
+    let data = crate::encode(pool, message).map_err(FileError::Encode)?;
+    std::fs::write(path, data).map_err(FileError::Io)
+}
@@ -0,0 +1,1075 @@
+// Canonical proto3 JSON mapping, including the special forms of the well-known types.
+
+use crate::{DynamicMessage, FieldValue, Value};
+use descriptor::{FieldInfo, FieldType, Pool};
+use serde_json::{Map, Number, Value as Json};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    // Location in the message, e.g. `items[1].price`; empty for the message itself.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn error(path: &str, message: impl Into<String>) -> Error {
+    Error {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", path, key),
+    }
+}
+
+// Seconds of 0001-01-01T00:00:00Z and 9999-12-31T23:59:59Z.
+const MIN_TIMESTAMP: i64 = -62135596800;
+const MAX_TIMESTAMP: i64 = 253402300799;
+// About 10000 years, the range of google.protobuf.Duration.
+const MAX_DURATION: i64 = 315576000000;
+
+const WRAPPERS: &[&str] = &[
+    "google.protobuf.DoubleValue",
+    "google.protobuf.FloatValue",
+    "google.protobuf.Int64Value",
+    "google.protobuf.UInt64Value",
+    "google.protobuf.Int32Value",
+    "google.protobuf.UInt32Value",
+    "google.protobuf.BoolValue",
+    "google.protobuf.StringValue",
+    "google.protobuf.BytesValue",
+];
+
+// Well-known types that are not mapped to a JSON object of their fields.
+fn is_special(type_name: &str) -> bool {
+    WRAPPERS.contains(&type_name)
+        || matches!(
+            type_name,
+            "google.protobuf.Timestamp"
+                | "google.protobuf.Duration"
+                | "google.protobuf.FieldMask"
+                | "google.protobuf.Struct"
+                | "google.protobuf.Value"
+                | "google.protobuf.ListValue"
+        )
+}
+
+pub fn print(pool: &Pool, message: &DynamicMessage) -> Result<String, Error> {
+    let json = to_json(pool, message)?;
+    Ok(serde_json::to_string_pretty(&json).unwrap())
+}
+
+pub fn to_json(pool: &Pool, message: &DynamicMessage) -> Result<Json, Error> {
+    message_to_json(pool, message, "")
+}
+
+pub fn parse(pool: &Pool, type_name: &str, input: &str) -> Result<DynamicMessage, Error> {
+    let json = serde_json::from_str(input).map_err(|error| self::error("", error.to_string()))?;
+    from_json(pool, type_name, &json)
+}
+
+pub fn from_json(pool: &Pool, type_name: &str, json: &Json) -> Result<DynamicMessage, Error> {
+    message_from_json(pool, type_name, json, "")
+}
+
+// All occurrences of a singular message field merged into one, as parsers do.
+fn merged(message: &DynamicMessage, number: u32) -> Option<DynamicMessage> {
+    let mut result: Option<DynamicMessage> = None;
+
+    for value in message.get(number) {
+        if let Value::Message(nested) = value {
+            match &mut result {
+                Some(result) => result.fields.extend(nested.fields.iter().cloned()),
+                None => result = Some(nested.clone()),
+            }
+        }
+    }
+
+    result
+}
+
+fn is_default(value: &Value) -> bool {
+    match value {
+        Value::Bool(value) => !value,
+        Value::I32(value) | Value::Enum(value) => *value == 0,
+        Value::I64(value) => *value == 0,
+        Value::U32(value) => *value == 0,
+        Value::U64(value) => *value == 0,
+        Value::F32(value) => *value == 0.0 && value.is_sign_positive(),
+        Value::F64(value) => *value == 0.0 && value.is_sign_positive(),
+        Value::String(value) => value.is_empty(),
+        Value::Bytes(value) => value.is_empty(),
+        Value::Message(_) => false,
+    }
+}
+
+// Fields of proto3 messages without presence are not printed when they have the default value.
+fn has_presence(field: &FieldInfo, proto3: bool) -> bool {
+    !proto3 || field.r#type == FieldType::Message || field.oneof.is_some() || field.is_extension()
+}
+
+fn message_to_json(pool: &Pool, message: &DynamicMessage, path: &str) -> Result<Json, Error> {
+    if let Some(json) = special_to_json(pool, message, path)? {
+        return Ok(json);
+    }
+
+    let r#type = pool
+        .message(&message.type_name)
+        .ok_or_else(|| error(path, format!("Unknown message type {}", message.type_name)))?;
+
+    if message.type_name == "google.protobuf.Any" {
+        return any_to_json(pool, message, path);
+    }
+
+    let mut object = Map::new();
+
+    // unknown fields have no JSON form and are dropped
+    for field in r#type.fields.iter().chain(pool.extensions_of(&r#type.name)) {
+        if message.get(field.number).next().is_none() {
+            continue;
+        }
+
+        let key = match field.is_extension() {
+            true => format!("[{}]", field.full_name),
+            false => field.json_name.clone(),
+        };
+        let path = join(path, &key);
+
+        let json = if let Some(entry) = pool.map_entry(field) {
+            let mut map = Map::new();
+
+            for value in message.get(field.number) {
+                let Value::Message(value) = value else {
+                    continue;
+                };
+
+                let key_field = entry.field(1).unwrap();
+                let value_field = entry.field(2).unwrap();
+
+                let key = match value.get_single(1) {
+                    Some(key) => key.clone(),
+                    None => Value::default_of(pool, key_field),
+                };
+                let key = match key {
+                    Value::String(key) => key,
+                    Value::Bool(key) => key.to_string(),
+                    Value::I32(key) => key.to_string(),
+                    Value::I64(key) => key.to_string(),
+                    Value::U32(key) => key.to_string(),
+                    Value::U64(key) => key.to_string(),
+                    _ => return Err(error(&path, "Invalid map key type")),
+                };
+
+                let item = match value_field.r#type {
+                    FieldType::Message => merged(value, 2).map(Value::Message),
+                    _ => value.get_single(2).cloned(),
+                }
+                .unwrap_or_else(|| Value::default_of(pool, value_field));
+
+                let path = format!("{}[\"{}\"]", path, key);
+                map.insert(key, value_to_json(pool, value_field, &item, &path)?);
+            }
+
+            Json::Object(map)
+        } else if field.is_repeated() {
+            message
+                .get(field.number)
+                .enumerate()
+                .map(|(index, value)| {
+                    value_to_json(pool, field, value, &format!("{}[{}]", path, index))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Json::Array)?
+        } else {
+            let value = match field.r#type {
+                FieldType::Message => Value::Message(merged(message, field.number).unwrap()),
+                _ => message.get_single(field.number).unwrap().clone(),
+            };
+
+            if !has_presence(field, r#type.proto3) && is_default(&value) {
+                continue;
+            }
+
+            value_to_json(pool, field, &value, &path)?
+        };
+
+        object.insert(key, json);
+    }
+
+    Ok(Json::Object(object))
+}
+
+fn value_to_json(pool: &Pool, field: &FieldInfo, value: &Value, path: &str) -> Result<Json, Error> {
+    Ok(match value {
+        Value::Bool(value) => Json::Bool(*value),
+        Value::I32(value) => Json::from(*value),
+        Value::U32(value) => Json::from(*value),
+        Value::I64(value) => Json::String(value.to_string()),
+        Value::U64(value) => Json::String(value.to_string()),
+        // the shortest form of the float, not of its widened value
+        Value::F32(value) => float_to_json(value.to_string().parse().unwrap_or(*value as f64)),
+        Value::F64(value) => float_to_json(*value),
+        Value::String(value) => Json::String(value.clone()),
+        Value::Bytes(value) => Json::String(base64_encode(value)),
+        Value::Enum(number) => {
+            let type_name = field.type_name.as_deref().unwrap_or_default();
+
+            if type_name == "google.protobuf.NullValue" {
+                return Ok(Json::Null);
+            }
+
+            pool.r#enum(type_name)
+                .and_then(|r#enum| r#enum.value(*number))
+                .map_or_else(
+                    || Json::from(*number),
+                    |value| Json::String(value.name.clone()),
+                )
+        }
+        Value::Message(message) => message_to_json(pool, message, path)?,
+    })
+}
+
+fn float_to_json(value: f64) -> Json {
+    if value.is_nan() {
+        Json::String("NaN".to_string())
+    } else if value.is_infinite() {
+        Json::String(match value > 0.0 {
+            true => "Infinity".to_string(),
+            false => "-Infinity".to_string(),
+        })
+    } else if value.fract() == 0.0 && value.abs() < (1u64 << 53) as f64 {
+        // `1` rather than `1.0`
+        Json::from(value as i64)
+    } else {
+        Number::from_f64(value).map_or(Json::Null, Json::Number)
+    }
+}
+
+fn any_to_json(pool: &Pool, any: &DynamicMessage, path: &str) -> Result<Json, Error> {
+    let url = match any.get_single(1) {
+        Some(Value::String(url)) => url.as_str(),
+        _ => "",
+    };
+    let data = match any.get_single(2) {
+        Some(Value::Bytes(data)) => data.as_slice(),
+        _ => &[],
+    };
+
+    if url.is_empty() && data.is_empty() {
+        return Ok(Json::Object(Map::new()));
+    }
+
+    let type_name = &url[url.rfind('/').map_or(0, |index| index + 1)..];
+    let value = crate::decode(pool, type_name, data)
+        .map_err(|_| error(path, format!("Unknown or invalid type {} in Any", url)))?;
+
+    let mut object = Map::new();
+    object.insert("@type".to_string(), Json::String(url.to_string()));
+
+    match message_to_json(pool, &value, path)? {
+        Json::Object(fields) if !is_special(type_name) => object.extend(fields),
+        json => {
+            object.insert("value".to_string(), json);
+        }
+    }
+
+    Ok(Json::Object(object))
+}
+
+fn special_to_json(
+    pool: &Pool,
+    message: &DynamicMessage,
+    path: &str,
+) -> Result<Option<Json>, Error> {
+    let integer = |number: u32| match message.get_single(number) {
+        Some(Value::I64(value)) => *value,
+        Some(Value::I32(value)) => *value as i64,
+        _ => 0,
+    };
+    let field = |number: u32| {
+        pool.message(&message.type_name)
+            .and_then(|r#type| r#type.field(number))
+            .ok_or_else(|| {
+                error(
+                    path,
+                    format!("{} has no field {}", message.type_name, number),
+                )
+            })
+    };
+
+    let json = match message.type_name.as_str() {
+        "google.protobuf.Timestamp" => {
+            let (seconds, nanos) = (integer(1), integer(2));
+
+            if !(MIN_TIMESTAMP..=MAX_TIMESTAMP).contains(&seconds)
+                || !(0..1_000_000_000).contains(&nanos)
+            {
+                return Err(error(path, "Timestamp is out of range"));
+            }
+
+            let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+            let (year, month, day) = civil_from_days(days);
+
+            Json::String(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+                year,
+                month,
+                day,
+                time / 3600,
+                time / 60 % 60,
+                time % 60,
+                fraction(nanos as u32)
+            ))
+        }
+        "google.protobuf.Duration" => {
+            let (seconds, nanos) = (integer(1), integer(2));
+
+            if seconds.abs() > MAX_DURATION
+                || nanos.abs() >= 1_000_000_000
+                || seconds.signum() * nanos.signum() < 0
+            {
+                return Err(error(path, "Duration is out of range"));
+            }
+
+            let sign = if seconds < 0 || nanos < 0 { "-" } else { "" };
+            Json::String(format!(
+                "{}{}{}s",
+                sign,
+                seconds.abs(),
+                fraction(nanos.unsigned_abs() as u32)
+            ))
+        }
+        "google.protobuf.FieldMask" => Json::String(
+            message
+                .get(1)
+                .filter_map(|path| match path {
+                    Value::String(path) => Some(
+                        path.split('.')
+                            .map(descriptor::names::json_name)
+                            .collect::<Vec<_>>()
+                            .join("."),
+                    ),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        "google.protobuf.Struct" => {
+            let mut object = Map::new();
+
+            for entry in message.get(1) {
+                let Value::Message(entry) = entry else {
+                    continue;
+                };
+
+                let key = match entry.get_single(1) {
+                    Some(Value::String(key)) => key.clone(),
+                    _ => String::new(),
+                };
+                let value = merged(entry, 2)
+                    .unwrap_or_else(|| DynamicMessage::new("google.protobuf.Value"));
+                let json = message_to_json(pool, &value, &join(path, &key))?;
+
+                object.insert(key, json);
+            }
+
+            Json::Object(object)
+        }
+        "google.protobuf.Value" => {
+            let kind = message
+                .fields
+                .iter()
+                .rev()
+                .find(|field| {
+                    (1..=6).contains(&field.number)
+                        && !matches!(field.value, FieldValue::Unknown(_))
+                })
+                .map(|field| field.number)
+                .ok_or_else(|| error(path, "Value has no kind set"))?;
+
+            match kind {
+                5 | 6 => message_to_json(pool, &merged(message, kind).unwrap(), path)?,
+                kind => value_to_json(pool, field(kind)?, message.get_single(kind).unwrap(), path)?,
+            }
+        }
+        "google.protobuf.ListValue" => message
+            .get(1)
+            .enumerate()
+            .map(|(index, value)| match value {
+                Value::Message(value) => {
+                    message_to_json(pool, value, &format!("{}[{}]", path, index))
+                }
+                _ => Ok(Json::Null),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Json::Array)?,
+        name if WRAPPERS.contains(&name) => {
+            let field = field(1)?;
+            let value = message
+                .get_single(1)
+                .cloned()
+                .unwrap_or_else(|| Value::default_of(pool, field));
+
+            value_to_json(pool, field, &value, path)?
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(json))
+}
+
+// Nanoseconds as 0, 3, 6 or 9 fractional digits.
+fn fraction(nanos: u32) -> String {
+    if nanos == 0 {
+        String::new()
+    } else if nanos.is_multiple_of(1_000_000) {
+        format!(".{:03}", nanos / 1_000_000)
+    } else if nanos.is_multiple_of(1_000) {
+        format!(".{:06}", nanos / 1_000)
+    } else {
+        format!(".{:09}", nanos)
+    }
+}
+
+// Date of the day counted from 1970-01-01, in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                result.push(BASE64[(bits >> (18 - index * 6) & 0x3F) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
+
+// Both the standard and the URL-safe alphabets are accepted, padding is optional.
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut result = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+
+        bits = bits << 6 | value as u32;
+        count += 6;
+
+        if count >= 8 {
+            count -= 8;
+            result.push((bits >> count) as u8);
+        }
+    }
+
+    // a single character left over can't encode a byte
+    (count < 6).then_some(result)
+}
+
+fn message_from_json(
+    pool: &Pool,
+    type_name: &str,
+    json: &Json,
+    path: &str,
+) -> Result<DynamicMessage, Error> {
+    if let Some(message) = special_from_json(pool, type_name, json, path)? {
+        return Ok(message);
+    }
+
+    let r#type = pool
+        .message(type_name)
+        .ok_or_else(|| error(path, format!("Unknown message type {}", type_name)))?;
+
+    let Json::Object(object) = json else {
+        return Err(error(path, format!("Expected an object for {}", type_name)));
+    };
+
+    if type_name == "google.protobuf.Any" {
+        return any_from_json(pool, object, path);
+    }
+
+    let mut message = DynamicMessage::new(type_name);
+
+    for (key, value) in object {
+        let path = join(path, key);
+        let field = match key.strip_prefix('[').and_then(|key| key.strip_suffix(']')) {
+            Some(name) => pool
+                .extension(name)
+                .filter(|field| field.extendee.as_deref() == Some(type_name)),
+            None => r#type
+                .field_by_json_name(key)
+                .or_else(|| r#type.field_by_name(key)),
+        }
+        .ok_or_else(|| {
+            error(
+                &path,
+                format!("Message type {} has no field named {}", type_name, key),
+            )
+        })?;
+
+        // null is the same as a missing field, except for google.protobuf.Value
+        if value.is_null() && field.type_name.as_deref() != Some("google.protobuf.Value") {
+            continue;
+        }
+
+        if message.has(field.number) {
+            return Err(error(
+                &path,
+                format!("Field {} is specified multiple times", field.name),
+            ));
+        }
+
+        if let Some(oneof) = field.oneof
+            && let Some(other) = message.which_oneof(pool, oneof)
+        {
+            return Err(error(
+                &path,
+                format!(
+                    "Field {} is specified along with field {}, another member of oneof {}",
+                    field.name,
+                    r#type.field(other).map_or("", |other| other.name.as_str()),
+                    r#type.oneofs[oneof].name
+                ),
+            ));
+        }
+
+        if let Some(entry) = pool.map_entry(field) {
+            let Json::Object(map) = value else {
+                return Err(error(&path, "Expected an object for map field"));
+            };
+
+            let key_field = entry.field(1).unwrap();
+            let value_field = entry.field(2).unwrap();
+
+            for (key, value) in map {
+                let path = format!("{}[\"{}\"]", path, key);
+                let key = match key_field.r#type {
+                    FieldType::String => Value::String(key.clone()),
+                    FieldType::Bool => match key.as_str() {
+                        "true" => Value::Bool(true),
+                        "false" => Value::Bool(false),
+                        _ => return Err(error(&path, "Expected a boolean map key")),
+                    },
+                    _ => value_from_json(pool, key_field, &Json::String(key.clone()), &path)?,
+                };
+                let value = value_from_json(pool, value_field, value, &path)?;
+
+                let mut item = DynamicMessage::new(entry.name.clone());
+                item.set(pool, key_field, key);
+                item.set(pool, value_field, value);
+                message.push(field, Value::Message(item));
+            }
+        } else if field.is_repeated() {
+            let Json::Array(values) = value else {
+                return Err(error(&path, "Expected an array for repeated field"));
+            };
+
+            for (index, value) in values.iter().enumerate() {
+                let path = format!("{}[{}]", path, index);
+
+                if value.is_null() && field.type_name.as_deref() != Some("google.protobuf.Value") {
+                    return Err(error(&path, "Repeated field elements can't be null"));
+                }
+
+                let value = value_from_json(pool, field, value, &path)?;
+                message.push(field, value);
+            }
+        } else {
+            let value = value_from_json(pool, field, value, &path)?;
+            message.set(pool, field, value);
+        }
+    }
+
+    message.fields.sort_by_key(|field| field.number);
+
+    Ok(message)
+}
+
+fn value_from_json(
+    pool: &Pool,
+    field: &FieldInfo,
+    json: &Json,
+    path: &str,
+) -> Result<Value, Error> {
+    let expected = |name: &str| error(path, format!("Expected {} for field {}", name, field.name));
+
+    Ok(match field.r#type {
+        FieldType::Message | FieldType::Group => Value::Message(message_from_json(
+            pool,
+            field.type_name.as_deref().unwrap_or_default(),
+            json,
+            path,
+        )?),
+        FieldType::Enum => {
+            let type_name = field.type_name.as_deref().unwrap_or_default();
+            let r#enum = pool
+                .r#enum(type_name)
+                .ok_or_else(|| error(path, format!("Unknown enum type {}", type_name)))?;
+
+            match json {
+                Json::Null if type_name == "google.protobuf.NullValue" => Value::Enum(0),
+                Json::String(name) => Value::Enum(
+                    r#enum
+                        .value_by_name(name)
+                        .ok_or_else(|| {
+                            error(
+                                path,
+                                format!("Unknown value {} of enum {}", name, type_name),
+                            )
+                        })?
+                        .number,
+                ),
+                Json::Number(_) => {
+                    let number = integer_from_json(json, i32::MIN as i128, i32::MAX as i128)
+                        .ok_or_else(|| expected("an enum value"))?
+                        as i32;
+
+                    if r#enum.closed && r#enum.value(number).is_none() {
+                        return Err(error(
+                            path,
+                            format!("Unknown value {} of enum {}", number, type_name),
+                        ));
+                    }
+
+                    Value::Enum(number)
+                }
+                _ => return Err(expected("an enum value")),
+            }
+        }
+        FieldType::Bool => Value::Bool(json.as_bool().ok_or_else(|| expected("a boolean"))?),
+        FieldType::String => Value::String(
+            json.as_str()
+                .ok_or_else(|| expected("a string"))?
+                .to_string(),
+        ),
+        FieldType::Bytes => Value::Bytes(
+            json.as_str()
+                .and_then(base64_decode)
+                .ok_or_else(|| expected("a base64 string"))?,
+        ),
+        FieldType::Double => Value::F64(float_from_json(json).ok_or_else(|| expected("a number"))?),
+        FieldType::Float => {
+            let value = float_from_json(json).ok_or_else(|| expected("a number"))?;
+
+            if value.is_finite() && value.abs() > f32::MAX as f64 {
+                return Err(error(path, "Float is out of range"));
+            }
+
+            Value::F32(value as f32)
+        }
+        FieldType::Int32 | FieldType::Sint32 | FieldType::Sfixed32 => Value::I32(
+            integer_from_json(json, i32::MIN as i128, i32::MAX as i128)
+                .ok_or_else(|| expected("a 32-bit integer"))? as i32,
+        ),
+        FieldType::Int64 | FieldType::Sint64 | FieldType::Sfixed64 => Value::I64(
+            integer_from_json(json, i64::MIN as i128, i64::MAX as i128)
+                .ok_or_else(|| expected("a 64-bit integer"))? as i64,
+        ),
+        FieldType::Uint32 | FieldType::Fixed32 => Value::U32(
+            integer_from_json(json, 0, u32::MAX as i128)
+                .ok_or_else(|| expected("an unsigned 32-bit integer"))? as u32,
+        ),
+        FieldType::Uint64 | FieldType::Fixed64 => Value::U64(
+            integer_from_json(json, 0, u64::MAX as i128)
+                .ok_or_else(|| expected("an unsigned 64-bit integer"))? as u64,
+        ),
+    })
+}
+
+// Integers are accepted as numbers and as strings, in exponent notation too if they are whole.
+fn integer_from_json(json: &Json, min: i128, max: i128) -> Option<i128> {
+    let value = match json {
+        Json::Number(number) => number
+            .as_i64()
+            .map(|value| value as i128)
+            .or(number.as_u64().map(|value| value as i128))
+            .or_else(|| whole(number.as_f64()?)),
+        Json::String(text) => text
+            .parse::<i128>()
+            .ok()
+            .or_else(|| whole(text.parse().ok()?)),
+        _ => None,
+    }?;
+
+    (min..=max).contains(&value).then_some(value)
+}
+
+fn whole(value: f64) -> Option<i128> {
+    (value.fract() == 0.0 && value.abs() < 1e38).then_some(value as i128)
+}
+
+fn float_from_json(json: &Json) -> Option<f64> {
+    match json {
+        Json::Number(number) => number.as_f64(),
+        Json::String(text) => match text.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            text => text.parse().ok().filter(|value: &f64| value.is_finite()),
+        },
+        _ => None,
+    }
+}
+
+fn any_from_json(
+    pool: &Pool,
+    object: &Map<String, Json>,
+    path: &str,
+) -> Result<DynamicMessage, Error> {
+    let mut any = DynamicMessage::new("google.protobuf.Any");
+
+    if object.is_empty() {
+        return Ok(any);
+    }
+
+    let url = object
+        .get("@type")
+        .and_then(Json::as_str)
+        .ok_or_else(|| error(path, "Any is missing @type"))?;
+    let type_name = &url[url.rfind('/').map_or(0, |index| index + 1)..];
+
+    if pool.message(type_name).is_none() {
+        return Err(error(path, format!("Unknown type {} in Any", url)));
+    }
+
+    let value = match is_special(type_name) {
+        true => message_from_json(
+            pool,
+            type_name,
+            object.get("value").unwrap_or(&Json::Null),
+            &join(path, "value"),
+        )?,
+        false => {
+            let mut fields = object.clone();
+            fields.remove("@type");
+            message_from_json(pool, type_name, &Json::Object(fields), path)?
+        }
+    };
+
+    let data = crate::encode(pool, &value).map_err(|error| self::error(path, error.to_string()))?;
+    let r#type = pool
+        .message("google.protobuf.Any")
+        .ok_or_else(|| error(path, "Unknown message type google.protobuf.Any"))?;
+
+    any.set(
+        pool,
+        r#type.field(1).unwrap(),
+        Value::String(url.to_string()),
+    );
+    any.set(pool, r#type.field(2).unwrap(), Value::Bytes(data));
+
+    Ok(any)
+}
+
+fn special_from_json(
+    pool: &Pool,
+    type_name: &str,
+    json: &Json,
+    path: &str,
+) -> Result<Option<DynamicMessage>, Error> {
+    if !is_special(type_name) {
+        return Ok(None);
+    }
+
+    let r#type = pool
+        .message(type_name)
+        .ok_or_else(|| error(path, format!("Unknown message type {}", type_name)))?;
+    let field = |number: u32| {
+        r#type
+            .field(number)
+            .ok_or_else(|| error(path, format!("{} has no field {}", type_name, number)))
+    };
+    let text = || {
+        json.as_str()
+            .ok_or_else(|| error(path, format!("Expected a string for {}", type_name)))
+    };
+
+    let mut message = DynamicMessage::new(type_name);
+
+    // fields with default values are left out, as the binary encoding would do
+    let set = |message: &mut DynamicMessage, number: u32, value: Value| -> Result<(), Error> {
+        if !is_default(&value) {
+            message.set(pool, field(number)?, value);
+        }
+        Ok(())
+    };
+
+    match type_name {
+        "google.protobuf.Timestamp" => {
+            let (seconds, nanos) = parse_timestamp(text()?).ok_or_else(|| {
+                error(
+                    path,
+                    "Invalid timestamp, expected RFC 3339 like 1972-01-01T10:00:20.021Z",
+                )
+            })?;
+
+            set(&mut message, 1, Value::I64(seconds))?;
+            set(&mut message, 2, Value::I32(nanos))?;
+        }
+        "google.protobuf.Duration" => {
+            let (seconds, nanos) = parse_duration(text()?)
+                .ok_or_else(|| error(path, "Invalid duration, expected seconds like 1.5s"))?;
+
+            set(&mut message, 1, Value::I64(seconds))?;
+            set(&mut message, 2, Value::I32(nanos))?;
+        }
+        "google.protobuf.FieldMask" => {
+            let paths = text()?;
+
+            for mask in paths.split(',').filter(|mask| !mask.is_empty()) {
+                let mut snake = String::with_capacity(mask.len());
+
+                for ch in mask.chars() {
+                    if ch.is_ascii_uppercase() {
+                        snake.push('_');
+                        snake.push(ch.to_ascii_lowercase());
+                    } else {
+                        snake.push(ch);
+                    }
+                }
+
+                message.push(field(1)?, Value::String(snake));
+            }
+        }
+        "google.protobuf.Struct" => {
+            let Json::Object(object) = json else {
+                return Err(error(path, "Expected an object for google.protobuf.Struct"));
+            };
+
+            let fields = field(1)?;
+            let entry = pool
+                .map_entry(fields)
+                .ok_or_else(|| error(path, "google.protobuf.Struct has no fields map"))?;
+
+            for (key, value) in object {
+                let value =
+                    message_from_json(pool, "google.protobuf.Value", value, &join(path, key))?;
+
+                let mut item = DynamicMessage::new(entry.name.clone());
+                item.set(pool, entry.field(1).unwrap(), Value::String(key.clone()));
+                item.set(pool, entry.field(2).unwrap(), Value::Message(value));
+                message.push(fields, Value::Message(item));
+            }
+        }
+        "google.protobuf.Value" => {
+            let (number, value) = match json {
+                Json::Null => (1, Value::Enum(0)),
+                Json::Number(number) => (2, Value::F64(number.as_f64().unwrap_or_default())),
+                Json::String(text) => (3, Value::String(text.clone())),
+                Json::Bool(value) => (4, Value::Bool(*value)),
+                Json::Object(_) => (
+                    5,
+                    Value::Message(message_from_json(
+                        pool,
+                        "google.protobuf.Struct",
+                        json,
+                        path,
+                    )?),
+                ),
+                Json::Array(_) => (
+                    6,
+                    Value::Message(message_from_json(
+                        pool,
+                        "google.protobuf.ListValue",
+                        json,
+                        path,
+                    )?),
+                ),
+            };
+
+            // the kind is a oneof, so it is written even when it has the default value
+            message.set(pool, field(number)?, value);
+        }
+        "google.protobuf.ListValue" => {
+            let Json::Array(values) = json else {
+                return Err(error(
+                    path,
+                    "Expected an array for google.protobuf.ListValue",
+                ));
+            };
+
+            for (index, value) in values.iter().enumerate() {
+                let value = message_from_json(
+                    pool,
+                    "google.protobuf.Value",
+                    value,
+                    &format!("{}[{}]", path, index),
+                )?;
+                message.push(field(1)?, Value::Message(value));
+            }
+        }
+        _ => {
+            let value = value_from_json(pool, field(1)?, json, path)?;
+            set(&mut message, 1, value)?;
+        }
+    }
+
+    Ok(Some(message))
+}
+
+fn digits(text: &str, len: usize) -> Option<u32> {
+    (text.len() == len && text.bytes().all(|byte| byte.is_ascii_digit()))
+        .then(|| text.parse().ok())
+        .flatten()
+}
+
+// Fractional seconds of 1 to 9 digits as nanoseconds.
+fn parse_nanos(text: &str) -> Option<u32> {
+    if text.is_empty() || text.len() > 9 || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(text.parse::<u32>().ok()? * 10u32.pow(9 - text.len() as u32))
+}
+
+// RFC 3339, e.g. `1972-01-01T10:00:20.021Z` or `1972-01-01T12:00:20+02:00`.
+fn parse_timestamp(text: &str) -> Option<(i64, i32)> {
+    let (date, time) = text.split_once(['T', 't'])?;
+    let mut date = date.split('-');
+    let year = digits(date.next()?, 4)? as i64;
+    let month = digits(date.next()?, 2)?;
+    let day = digits(date.next()?, 2)?;
+
+    if date.next().is_some()
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+    {
+        return None;
+    }
+
+    let (time, offset) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let index = time.rfind(['+', '-'])?;
+        let (hours, minutes) = time[index + 1..].split_once(':')?;
+        let offset = (digits(hours, 2)? * 3600 + digits(minutes, 2)? * 60) as i64;
+
+        match &time[index..index + 1] {
+            "-" => (&time[..index], -offset),
+            _ => (&time[..index], offset),
+        }
+    };
+
+    let (time, nanos) = match time.split_once('.') {
+        Some((time, fraction)) => (time, parse_nanos(fraction)?),
+        None => (time, 0),
+    };
+
+    let mut time = time.split(':');
+    let hours = digits(time.next()?, 2)?;
+    let minutes = digits(time.next()?, 2)?;
+    let seconds = digits(time.next()?, 2)?;
+
+    if time.next().is_some() || hours > 23 || minutes > 59 || seconds > 59 {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86400
+        + (hours * 3600 + minutes * 60 + seconds) as i64
+        - offset;
+
+    (MIN_TIMESTAMP..=MAX_TIMESTAMP)
+        .contains(&seconds)
+        .then_some((seconds, nanos as i32))
+}
+
+// Seconds with up to 9 fractional digits and the `s` suffix, e.g. `-1.5s`.
+fn parse_duration(text: &str) -> Option<(i64, i32)> {
+    let text = text.strip_suffix('s')?;
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+
+    let (seconds, nanos) = match text.split_once('.') {
+        Some((seconds, fraction)) => (seconds, parse_nanos(fraction)?),
+        None => (text, 0),
+    };
+
+    if seconds.is_empty() || !seconds.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let seconds: i64 = seconds
+        .parse()
+        .ok()
+        .filter(|seconds| *seconds <= MAX_DURATION)?;
+
+    Some(match negative {
+        true => (-seconds, -(nanos as i32)),
+        false => (seconds, nanos as i32),
+    })
+}
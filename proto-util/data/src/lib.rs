@@ -0,0 +1,624 @@
+mod codec;
+pub mod edit;
+pub mod json;
+pub mod print;
+pub mod raw;
+pub mod text;
+mod value;
+
+pub use codec::{EncodeError, decode, encode, find_field, wire_type};
+pub use value::{DynamicMessage, Field, FieldValue, UnknownValue, Value};
+
+#[cfg(test)]
+mod tests {
+    use crate::{DynamicMessage, FieldValue, Value};
+    use descriptor::Pool;
+    use resolver::{SourceTree, Workspace};
+    use wire::{Tag, WireType, Writer};
+
+    fn pool() -> Pool {
+        let mut tree = SourceTree::new(vec![]);
+        tree.add(
+            "shop.proto",
+            include_str!("../../../test-data/data/shop.proto"),
+        );
+        tree.add(
+            "config.proto",
+            include_str!("../../../test-data/data/config.proto"),
+        );
+        tree.add(
+            "event.proto",
+            include_str!("../../../test-data/data/event.proto"),
+        );
+        tree.load_imports().unwrap();
+
+        let workspace = Workspace::new(&tree).unwrap();
+        Pool::new(&resolver::compile(&workspace).unwrap())
+    }
+
+    fn varint(writer: &mut Writer, number: u32, value: u64) {
+        writer.write_tag(Tag::new(number, WireType::Varint));
+        writer.write_varint(value);
+    }
+
+    fn bytes(writer: &mut Writer, number: u32, value: &[u8]) {
+        writer.write_tag(Tag::new(number, WireType::LengthDelimited));
+        writer.write_length_delimited(value);
+    }
+
+    fn item(sku: &str, quantity: i32) -> Vec<u8> {
+        let mut writer = Writer::new();
+        bytes(&mut writer, 1, sku.as_bytes());
+        varint(&mut writer, 2, quantity as i64 as u64);
+        writer.write_tag(Tag::new(3, WireType::Fixed32));
+        writer.write_fixed32(2.5f32.to_bits());
+        writer.into_inner()
+    }
+
+    fn item_without_price(sku: &str, quantity: i32) -> Vec<u8> {
+        let mut writer = Writer::new();
+        bytes(&mut writer, 1, sku.as_bytes());
+        varint(&mut writer, 2, quantity as i64 as u64);
+        writer.into_inner()
+    }
+
+    fn order() -> Vec<u8> {
+        let mut writer = Writer::new();
+        varint(&mut writer, 1, 42);
+        bytes(&mut writer, 2, b"Alice");
+        bytes(&mut writer, 3, &item("apple", 3));
+        bytes(&mut writer, 3, &item("pear", -1));
+
+        let mut entry = Writer::new();
+        bytes(&mut entry, 1, b"vip");
+        varint(&mut entry, 2, 10);
+        bytes(&mut writer, 4, &entry.into_inner());
+
+        varint(&mut writer, 5, 1);
+        // unknown field between known ones
+        varint(&mut writer, 99, 7);
+
+        let mut packed = Writer::new();
+        packed.write_varint(wire::encode_zigzag32(-2) as u64);
+        packed.write_varint(wire::encode_zigzag32(5) as u64);
+        bytes(&mut writer, 6, &packed.into_inner());
+        // unpacked elements of a packed field are accepted too
+        varint(&mut writer, 6, wire::encode_zigzag32(1) as u64);
+
+        varint(&mut writer, 7, 1);
+        varint(&mut writer, 7, 2);
+        bytes(&mut writer, 8, b"4242");
+
+        writer.write_tag(Tag::new(10, WireType::Fixed64));
+        writer.write_fixed64(99.5f64.to_bits());
+
+        // overlong encoding of `true` is kept as is
+        writer.write_tag(Tag::new(12, WireType::Varint));
+        writer.write_raw(&[0x81, 0x00]);
+
+        writer.into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let pool = pool();
+        let data = order();
+        let message = crate::decode(&pool, "shop.Order", &data).unwrap();
+
+        assert_eq!(message.get_single(1), Some(&Value::U64(42)));
+        assert_eq!(
+            message.get_single(2),
+            Some(&Value::String("Alice".to_string()))
+        );
+        assert_eq!(message.get(3).count(), 2);
+        assert_eq!(message.get_single(5), Some(&Value::Enum(1)));
+        assert_eq!(
+            message.get(6).collect::<Vec<_>>(),
+            [&Value::I32(-2), &Value::I32(5), &Value::I32(1)]
+        );
+        assert_eq!(
+            message
+                .unknown()
+                .map(|(number, _)| number)
+                .collect::<Vec<_>>(),
+            [99, 12]
+        );
+        assert_eq!(message.which_oneof(&pool, 0), Some(8));
+
+        let Some(Value::Message(item)) = message.get(3).nth(1) else {
+            panic!("item is not a message");
+        };
+        assert_eq!(item.type_name, "shop.Order.Item");
+        assert_eq!(item.get_single(2), Some(&Value::I32(-1)));
+        assert_eq!(item.get_single(3), Some(&Value::F32(2.5)));
+
+        assert_eq!(crate::encode(&pool, &message).unwrap(), data);
+    }
+
+    #[test]
+    fn edit() {
+        let pool = pool();
+        let r#type = pool.message("shop.Order").unwrap();
+        let mut message = crate::decode(&pool, "shop.Order", &order()).unwrap();
+
+        message.set(
+            &pool,
+            r#type.field(2).unwrap(),
+            Value::String("Bob".to_string()),
+        );
+        // switching the oneof case drops the card
+        message.set(&pool, r#type.field(9).unwrap(), Value::Bytes(vec![1, 2]));
+        message.push(r#type.field(6).unwrap(), Value::I32(-7));
+        message.push(r#type.field(7).unwrap(), Value::I32(3));
+        assert!(message.remove(3, 0).is_some());
+        message.clear(99);
+
+        assert_eq!(message.fields[1].number, 2);
+        assert!(!message.has(8));
+        assert_eq!(message.which_oneof(&pool, 0), Some(9));
+        // the last occurrence is unpacked, so a new packed run is started
+        assert!(
+            matches!(&message.fields[12].value, FieldValue::Packed(values) if values.len() == 1)
+        );
+
+        let data = crate::encode(&pool, &message).unwrap();
+        let decoded = crate::decode(&pool, "shop.Order", &data).unwrap();
+
+        assert_eq!(decoded, message);
+        assert_eq!(
+            decoded.get(7).collect::<Vec<_>>(),
+            [&Value::I32(1), &Value::I32(2), &Value::I32(3)]
+        );
+
+        let mut wrong = DynamicMessage::new("shop.Order");
+        wrong.set(
+            &pool,
+            r#type.field(1).unwrap(),
+            Value::String("x".to_string()),
+        );
+        assert_eq!(
+            crate::encode(&pool, &wrong).unwrap_err().to_string(),
+            "Field id expects a value of type uint64"
+        );
+    }
+
+    #[test]
+    fn editor_changes() {
+        use crate::edit::{self, Change, EditError};
+
+        let pool = pool();
+        let mut message = crate::decode(&pool, "shop.Order", &order()).unwrap();
+        let mut apply =
+            |path: &[(u32, usize)], change| edit::apply(&pool, &mut message, path, change);
+
+        let name = Value::String("Bob".to_string());
+        apply(
+            &[],
+            Change::Set {
+                number: 2,
+                index: None,
+                value: name,
+            },
+        )
+        .unwrap();
+        // the quantity of the second item
+        apply(
+            &[(3, 1)],
+            Change::Set {
+                number: 2,
+                index: None,
+                value: Value::I32(5),
+            },
+        )
+        .unwrap();
+        apply(
+            &[],
+            Change::Set {
+                number: 6,
+                index: Some(1),
+                value: Value::I32(9),
+            },
+        )
+        .unwrap();
+
+        let item = Value::Message(DynamicMessage::new("shop.Order.Item"));
+        apply(
+            &[],
+            Change::Push {
+                number: 3,
+                value: item,
+            },
+        )
+        .unwrap();
+        apply(
+            &[],
+            Change::Delete {
+                number: 3,
+                index: Some(0),
+            },
+        )
+        .unwrap();
+
+        // selecting the other case clears the card, selecting it again keeps its value
+        apply(
+            &[],
+            Change::OneOf {
+                oneof: 0,
+                case: Some(9),
+            },
+        )
+        .unwrap();
+        apply(
+            &[],
+            Change::Set {
+                number: 9,
+                index: None,
+                value: Value::Bytes(vec![1]),
+            },
+        )
+        .unwrap();
+        apply(
+            &[],
+            Change::OneOf {
+                oneof: 0,
+                case: Some(9),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            apply(
+                &[(5, 0)],
+                Change::Delete {
+                    number: 1,
+                    index: None
+                }
+            ),
+            Err(EditError::NotFound(vec![(5, 0)]))
+        );
+        assert_eq!(
+            apply(
+                &[],
+                Change::Push {
+                    number: 50,
+                    value: Value::I32(1)
+                }
+            ),
+            Err(EditError::UnknownField {
+                message: "shop.Order".to_string(),
+                number: 50
+            })
+        );
+
+        assert_eq!(
+            message.get_single(2),
+            Some(&Value::String("Bob".to_string()))
+        );
+        assert_eq!(
+            message.get(6).collect::<Vec<_>>(),
+            [&Value::I32(-2), &Value::I32(9), &Value::I32(1)]
+        );
+        let items: Vec<_> = message.get(3).collect();
+        assert_eq!(items.len(), 2);
+        let Value::Message(pear) = items[0] else {
+            panic!("item is not a message");
+        };
+        assert_eq!(pear.get_single(1), Some(&Value::String("pear".to_string())));
+        assert_eq!(pear.get_single(2), Some(&Value::I32(5)));
+        assert_eq!(
+            edit::message_at(&message, &[(3, 1)]).unwrap().fields.len(),
+            0
+        );
+
+        assert!(!message.has(8));
+        assert_eq!(message.which_oneof(&pool, 0), Some(9));
+        assert_eq!(message.get_single(9), Some(&Value::Bytes(vec![1])));
+
+        let unknown = message
+            .fields
+            .iter()
+            .position(|field| field.number == 99)
+            .unwrap();
+        edit::apply(&pool, &mut message, &[], Change::Remove(unknown)).unwrap();
+        edit::apply(
+            &pool,
+            &mut message,
+            &[],
+            Change::OneOf {
+                oneof: 0,
+                case: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(message.which_oneof(&pool, 0), None);
+        assert!(message.unknown().all(|(number, _)| number != 99));
+
+        let path = std::env::temp_dir().join(format!("data-{}.bin", std::process::id()));
+        edit::save(&pool, &message, &path).unwrap();
+        let reloaded = edit::load(&pool, "shop.Order", &path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.unwrap(), message);
+        assert_eq!(
+            edit::load(&pool, "shop.Order", &path).unwrap(),
+            DynamicMessage::new("shop.Order")
+        );
+    }
+
+    #[test]
+    fn print_tree() {
+        let pool = pool();
+        let message = crate::decode(&pool, "shop.Order", &order()).unwrap();
+
+        assert_eq!(
+            crate::print::tree(&pool, &message),
+            include_str!("../../../test-data/data/order.tree")
+        );
+    }
+
+    #[test]
+    fn raw() {
+        let pool = pool();
+        let mut fields = crate::raw::inspect(&order()).unwrap();
+
+        assert_eq!(
+            crate::print::raw(None, &fields),
+            include_str!("../../../test-data/data/order.raw")
+        );
+
+        crate::raw::annotate(&mut fields, &pool, "shop.Order");
+
+        assert_eq!(
+            crate::print::raw(Some(&pool), &fields),
+            include_str!("../../../test-data/data/order.annotated.raw")
+        );
+    }
+
+    #[test]
+    fn raw_groups() {
+        let mut writer = Writer::new();
+        writer.write_tag(Tag::new(1, WireType::StartGroup));
+        varint(&mut writer, 2, 150);
+        writer.write_tag(Tag::new(1, WireType::EndGroup));
+        bytes(&mut writer, 3, &[0xFF, 0x00]);
+
+        let fields = crate::raw::inspect(&writer.into_inner()).unwrap();
+        assert_eq!(
+            crate::print::raw(None, &fields),
+            "[0..5] 1 group {\n  [1..4] 2: varint 150\n}\n[5..9] 3: bytes \"\\377\\000\"\n"
+        );
+
+        let error = crate::raw::inspect(&[0x0B, 0x10, 0x01]).unwrap_err();
+        assert_eq!(error.to_string(), "Group 1 is not terminated at byte 3");
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let pool = pool();
+        let message = crate::decode(&pool, "shop.Order", &order()).unwrap();
+        let text = crate::text::print(&pool, &message);
+
+        assert_eq!(text, include_str!("../../../test-data/data/order.txt"));
+
+        let parsed = crate::text::parse(&pool, "shop.Order", &text).unwrap();
+        let data = crate::encode(&pool, &parsed).unwrap();
+        let decoded = crate::decode(&pool, "shop.Order", &data).unwrap();
+
+        // known fields are written by number, the canonical `12: 1` is no longer unknown
+        assert_eq!(decoded.get_single(12), Some(&Value::Bool(true)));
+        assert_eq!(
+            decoded
+                .fields
+                .iter()
+                .map(|field| field.number)
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 3, 4, 5, 6, 7, 7, 8, 10, 99, 12]
+        );
+    }
+
+    #[test]
+    fn text_parse() {
+        let pool = pool();
+        let message = crate::text::parse(
+            &pool,
+            "config.Config",
+            include_str!("../../../test-data/data/config.txt"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            crate::text::print(&pool, &message),
+            include_str!("../../../test-data/data/config.print.txt")
+        );
+
+        // repeated values end up in a single packed run
+        assert_eq!(
+            message.fields[1].value,
+            FieldValue::Packed(vec![Value::I32(80), Value::I32(443), Value::I32(8080)])
+        );
+
+        let Some(Value::Message(any)) = message.get_single(4) else {
+            panic!("payload is not a message");
+        };
+        assert_eq!(
+            any.get_single(1),
+            Some(&Value::String(
+                "type.googleapis.com/shop.Order.Item".to_string()
+            ))
+        );
+        let Some(Value::Bytes(data)) = any.get_single(2) else {
+            panic!("payload has no value");
+        };
+        assert_eq!(*data, item_without_price("pear", -1));
+    }
+
+    #[test]
+    fn text_errors() {
+        let pool = pool();
+        let error = |input: &str| {
+            crate::text::parse(&pool, "shop.Order", input)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error("id: 1\nnope: 2"),
+            "Message type \"shop.Order\" has no field named \"nope\" at line 2, column 1"
+        );
+        assert_eq!(
+            error("id: 1 id: 2"),
+            "Non-repeated field \"id\" is specified multiple times at line 1, column 7"
+        );
+        assert_eq!(
+            error("card: \"x\" token: \"y\""),
+            "Field \"token\" is specified along with field \"card\", another member of oneof \"payment\" at line 1, column 11"
+        );
+        assert_eq!(error("id: -1"), "Integer out of range at line 1, column 5");
+        assert_eq!(
+            error("items { sku: \"a\""),
+            "Expected \"}\" at line 1, column 17"
+        );
+        assert_eq!(
+            error("status: STATUS_LOST"),
+            "Unknown enumeration value of \"STATUS_LOST\" for field \"status\" at line 1, column 9"
+        );
+    }
+
+    #[test]
+    fn text_values() {
+        let pool = pool();
+        let order = pool.message("shop.Order").unwrap();
+        let field = |name| order.field_by_name(name).unwrap();
+        let value = |name, input| crate::text::parse_value(&pool, field(name), input);
+
+        assert_eq!(
+            value("customer", "\"Bo\" 'b'"),
+            Ok(Value::String("Bob".into()))
+        );
+        assert_eq!(value("status", "STATUS_PAID"), Ok(Value::Enum(1)));
+        assert_eq!(value("deltas", "-3"), Ok(Value::I32(-3)));
+        assert_eq!(value("gift", "t"), Ok(Value::Bool(true)));
+        assert_eq!(
+            value("id", "1 2").unwrap_err().to_string(),
+            "Expected end of the value at line 1, column 3"
+        );
+
+        let items = value("items", "{ sku: \"fig\" quantity: 2 }").unwrap();
+        assert_eq!(
+            crate::print::scalar(&pool, field("status"), &Value::Enum(1)),
+            "STATUS_PAID"
+        );
+        assert!(matches!(items, Value::Message(item) if item.fields.len() == 2));
+
+        assert_eq!(Value::default_of(&pool, field("status")), Value::Enum(0));
+        assert_eq!(
+            Value::default_of(&pool, field("items")),
+            Value::Message(DynamicMessage::new("shop.Order.Item"))
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let pool = pool();
+        let json = include_str!("../../../test-data/data/event.json");
+        let message = crate::json::parse(&pool, "events.Event", json).unwrap();
+
+        assert_eq!(crate::json::print(&pool, &message).unwrap() + "\n", json);
+
+        let data = crate::encode(&pool, &message).unwrap();
+        let decoded = crate::decode(&pool, "events.Event", &data).unwrap();
+
+        // NaN in the scores makes the messages themselves incomparable
+        assert_eq!(crate::json::print(&pool, &decoded).unwrap() + "\n", json);
+        assert_eq!(decoded.get_single(1), Some(&Value::I64(-9007199254740993)));
+        assert_eq!(decoded.get_single(13), Some(&Value::U32(0)));
+
+        let Some(Value::Message(created)) = decoded.get_single(7) else {
+            panic!("created_at is not a message");
+        };
+        assert_eq!(created.get_single(1), Some(&Value::I64(63108020)));
+        assert_eq!(created.get_single(2), Some(&Value::I32(21000000)));
+    }
+
+    #[test]
+    fn json_inputs() {
+        let pool = pool();
+        // original field names, numbers for 64-bit integers, offsets and URL-safe base64
+        let message = crate::json::parse(
+            &pool,
+            "events.Event",
+            r#"{
+                "user_name": "bob",
+                "id": 1e3,
+                "status": 1,
+                "signature": "3q2-7w",
+                "createdAt": "1972-01-01T12:00:20.5+02:00",
+                "labels": {"7": "seven"},
+                "timeout": "3s",
+                "retries": null,
+                "metadata": {"x": 1.5}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            crate::json::to_json(&pool, &message).unwrap().to_string(),
+            concat!(
+                r#"{"id":"1000","login":"bob","signature":"3q2+7w==","status":"STATUS_PAID","#,
+                r#""labels":{"7":"seven"},"createdAt":"1972-01-01T10:00:20.500Z","timeout":"3s","#,
+                r#""metadata":{"x":1.5}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn json_errors() {
+        let pool = pool();
+        let error = |input: &str| {
+            crate::json::parse(&pool, "events.Event", input)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error(r#"{"nope": 1}"#),
+            "nope: Message type events.Event has no field named nope"
+        );
+        assert_eq!(
+            error(r#"{"login": "a", "user_name": "b"}"#),
+            "user_name: Field user_name is specified multiple times"
+        );
+        assert_eq!(
+            error(r#"{"scores": [1, "x"]}"#),
+            "scores[1]: Expected a number for field scores"
+        );
+        assert_eq!(
+            error(r#"{"priority": -1}"#),
+            "priority: Expected an unsigned 32-bit integer for field priority"
+        );
+        assert_eq!(
+            error(r#"{"createdAt": "1972-01-01"}"#),
+            "createdAt: Invalid timestamp, expected RFC 3339 like 1972-01-01T10:00:20.021Z"
+        );
+        // days past the end of the month, leap years included
+        for date in ["2024-02-30", "2023-02-29", "2024-04-31"] {
+            assert_eq!(
+                error(&format!(r#"{{"createdAt": "{}T00:00:00Z"}}"#, date)),
+                "createdAt: Invalid timestamp, expected RFC 3339 like 1972-01-01T10:00:20.021Z"
+            );
+        }
+        assert!(
+            crate::json::parse(
+                &pool,
+                "events.Event",
+                r#"{"createdAt": "2024-02-29T00:00:00Z"}"#
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            error(r#"{"details": {"@type": "x/nope.Nope"}}"#),
+            "details: Unknown type x/nope.Nope in Any"
+        );
+        assert_eq!(error("{"), "EOF while parsing an object at line 1 column 1");
+    }
+}
@@ -0,0 +1,212 @@
+// Human-readable trees of messages with field numbers, for inspection.
+
+use crate::raw::{Payload, RawField, RawValue};
+use crate::{DynamicMessage, FieldValue, UnknownValue, Value};
+use descriptor::{FieldInfo, FieldType, Pool};
+use wire::{Reader, WireType};
+
+pub fn tree(pool: &Pool, message: &DynamicMessage) -> String {
+    let mut out = String::new();
+    print_message(pool, message, 0, &mut out);
+    out
+}
+
+fn print_message(pool: &Pool, message: &DynamicMessage, indent: usize, out: &mut String) {
+    for field in &message.fields {
+        let info = crate::find_field(pool, &message.type_name, field.number);
+        let name = match info {
+            Some(info) if info.is_extension() => format!("[{}] ({})", info.full_name, field.number),
+            Some(info) => format!("{} ({})", info.name, field.number),
+            None => format!("({})", field.number),
+        };
+
+        out.push_str(&"  ".repeat(indent));
+
+        match (&field.value, info) {
+            (FieldValue::Single(Value::Message(nested)), _) => {
+                out.push_str(&format!("{} {{\n", name));
+                print_message(pool, nested, indent + 1, out);
+                out.push_str(&format!("{}}}\n", "  ".repeat(indent)));
+            }
+            (FieldValue::Single(value), Some(info)) => {
+                out.push_str(&format!("{}: {}\n", name, scalar(pool, info, value)));
+            }
+            (FieldValue::Packed(values), Some(info)) => {
+                let values = values
+                    .iter()
+                    .map(|value| scalar(pool, info, value))
+                    .collect::<Vec<_>>();
+
+                out.push_str(&format!("{}: [{}] (packed)\n", name, values.join(", ")));
+            }
+            (FieldValue::Unknown(value), _) => {
+                out.push_str(&format!("{}: {} (unknown)\n", name, unknown(value)));
+            }
+            _ => {}
+        }
+    }
+}
+
+// Value of a scalar field as it is written in the text format.
+pub fn scalar(pool: &Pool, field: &FieldInfo, value: &Value) -> String {
+    match value {
+        Value::Bool(value) => value.to_string(),
+        Value::I32(value) => value.to_string(),
+        Value::I64(value) => value.to_string(),
+        Value::U32(value) => value.to_string(),
+        Value::U64(value) => value.to_string(),
+        Value::F32(value) => float(*value as f64),
+        Value::F64(value) => float(*value),
+        Value::String(value) => format!("\"{}\"", descriptor::escape(value.as_bytes())),
+        Value::Bytes(value) => format!("\"{}\"", descriptor::escape(value)),
+        Value::Enum(number) => field
+            .type_name
+            .as_deref()
+            .and_then(|name| pool.r#enum(name))
+            .and_then(|r#enum| r#enum.value(*number))
+            .map_or_else(|| number.to_string(), |value| value.name.clone()),
+        Value::Message(message) => format!("{{ {} fields }}", message.fields.len()),
+    }
+}
+
+// `nan` rather than `NaN`, as in the text format.
+fn float(value: f64) -> String {
+    match value.is_nan() {
+        true => "nan".to_string(),
+        false => value.to_string(),
+    }
+}
+
+fn unknown(value: &UnknownValue) -> String {
+    let mut reader = Reader::new(&value.data);
+
+    match value.wire_type {
+        WireType::Varint => reader
+            .read_varint()
+            .map_or_else(|_| "?".to_string(), |value| format!("varint {}", value)),
+        WireType::Fixed32 => reader.read_fixed32().map_or_else(
+            |_| "?".to_string(),
+            |value| format!("fixed32 0x{:08x}", value),
+        ),
+        WireType::Fixed64 => reader.read_fixed64().map_or_else(
+            |_| "?".to_string(),
+            |value| format!("fixed64 0x{:016x}", value),
+        ),
+        WireType::LengthDelimited => reader.read_length_delimited().map_or_else(
+            |_| "?".to_string(),
+            |data| format!("bytes \"{}\"", descriptor::escape(data)),
+        ),
+        WireType::StartGroup | WireType::EndGroup => {
+            format!("group of {} bytes", value.data.len())
+        }
+    }
+}
+
+// Tree of the raw fields with their byte ranges; annotated fields are shown by name and type.
+pub fn raw(pool: Option<&Pool>, fields: &[RawField]) -> String {
+    let mut out = String::new();
+    print_raw(pool, fields, 0, &mut out);
+    out
+}
+
+fn print_raw(pool: Option<&Pool>, fields: &[RawField], indent: usize, out: &mut String) {
+    for field in fields {
+        let name = match &field.field {
+            Some(info) if info.is_extension() => format!("{} [{}]", field.number, info.full_name),
+            Some(info) => format!("{} {}", field.number, info.name),
+            None => field.number.to_string(),
+        };
+
+        out.push_str(&format!(
+            "{}[{}..{}] {}",
+            "  ".repeat(indent),
+            field.offset,
+            field.end,
+            name
+        ));
+
+        let nested = match &field.value {
+            RawValue::Group(fields) => Some(("group", fields)),
+            RawValue::LengthDelimited {
+                payload: Payload::Message(fields),
+                ..
+            } => Some(("", fields)),
+            _ => None,
+        };
+
+        match nested {
+            Some((kind, fields)) => {
+                out.push_str(&match kind {
+                    "" => " {\n".to_string(),
+                    kind => format!(" {} {{\n", kind),
+                });
+                print_raw(pool, fields, indent + 1, out);
+                out.push_str(&format!("{}}}\n", "  ".repeat(indent)));
+            }
+            None => out.push_str(&format!(": {}\n", raw_value(pool, field))),
+        }
+    }
+}
+
+fn raw_value(pool: Option<&Pool>, field: &RawField) -> String {
+    let info = field.field.as_ref();
+    let r#type = info.map(|info| info.r#type);
+
+    let varint = |value: u64| match r#type {
+        Some(FieldType::Int32) => (value as i32).to_string(),
+        Some(FieldType::Int64) => (value as i64).to_string(),
+        Some(FieldType::Sint32) => wire::decode_zigzag32(value as u32).to_string(),
+        Some(FieldType::Sint64) => wire::decode_zigzag64(value).to_string(),
+        Some(FieldType::Bool) => (value != 0).to_string(),
+        Some(FieldType::Enum) => info
+            .and_then(|info| {
+                pool?
+                    .r#enum(info.type_name.as_deref()?)?
+                    .value(value as i32)
+            })
+            .map_or_else(|| (value as i32).to_string(), |value| value.name.clone()),
+        Some(_) => value.to_string(),
+        None => format!("varint {}", value),
+    };
+
+    let fixed32 = |value: u32| match r#type {
+        Some(FieldType::Float) => f32::from_bits(value).to_string(),
+        Some(FieldType::Sfixed32) => (value as i32).to_string(),
+        Some(_) => value.to_string(),
+        None => format!(
+            "fixed32 0x{:08x} ({}, {})",
+            value,
+            value as i32,
+            f32::from_bits(value)
+        ),
+    };
+
+    let fixed64 = |value: u64| match r#type {
+        Some(FieldType::Double) => f64::from_bits(value).to_string(),
+        Some(FieldType::Sfixed64) => (value as i64).to_string(),
+        Some(_) => value.to_string(),
+        None => format!(
+            "fixed64 0x{:016x} ({}, {})",
+            value,
+            value as i64,
+            f64::from_bits(value)
+        ),
+    };
+
+    let list = |values: Vec<String>| format!("[{}] (packed)", values.join(", "));
+
+    match &field.value {
+        RawValue::Varint(value) => varint(*value),
+        RawValue::Fixed32(value) => fixed32(*value),
+        RawValue::Fixed64(value) => fixed64(*value),
+        RawValue::LengthDelimited { data, payload } => match payload {
+            Payload::String(text) => format!("\"{}\"", descriptor::escape(text.as_bytes())),
+            Payload::Bytes => format!("bytes \"{}\"", descriptor::escape(data)),
+            Payload::Packed(values) => list(values.iter().map(|value| varint(*value)).collect()),
+            Payload::Packed32(values) => list(values.iter().map(|value| fixed32(*value)).collect()),
+            Payload::Packed64(values) => list(values.iter().map(|value| fixed64(*value)).collect()),
+            Payload::Message(_) => unreachable!(),
+        },
+        RawValue::Group(_) => unreachable!(),
+    }
+}
@@ -0,0 +1,200 @@
+// Schema-less view of the wire format. Length-delimited values are ambiguous, so their contents
+// are guessed; a schema supplied later replaces the guesses with what the fields really are.
+
+use descriptor::{FieldInfo, FieldType, Pool};
+use wire::{DecodeError, Reader, WireType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Payload {
+    Bytes,
+    String(String),
+    Message(Vec<RawField>),
+    // Packed varints; fixed-size elements can't be told apart from bytes without a schema.
+    Packed(Vec<u64>),
+    Packed32(Vec<u32>),
+    Packed64(Vec<u64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawValue {
+    Varint(u64),
+    Fixed32(u32),
+    Fixed64(u64),
+    LengthDelimited { data: Vec<u8>, payload: Payload },
+    Group(Vec<RawField>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawField {
+    pub number: u32,
+    // Byte range of the whole field, tag included.
+    pub offset: usize,
+    pub end: usize,
+    pub value: RawValue,
+    // Set by `annotate` when the schema knows the field.
+    pub field: Option<FieldInfo>,
+}
+
+impl RawField {
+    pub fn wire_type(&self) -> WireType {
+        match self.value {
+            RawValue::Varint(_) => WireType::Varint,
+            RawValue::Fixed32(_) => WireType::Fixed32,
+            RawValue::Fixed64(_) => WireType::Fixed64,
+            RawValue::LengthDelimited { .. } => WireType::LengthDelimited,
+            RawValue::Group(_) => WireType::StartGroup,
+        }
+    }
+}
+
+pub fn inspect(data: &[u8]) -> Result<Vec<RawField>, DecodeError> {
+    parse_fields(&mut Reader::new(data), None)
+}
+
+fn parse_fields(reader: &mut Reader, group: Option<u32>) -> Result<Vec<RawField>, DecodeError> {
+    let mut fields = vec![];
+
+    while !reader.is_empty() {
+        let offset = reader.position();
+        let tag = reader.read_tag()?;
+
+        let value = match tag.wire_type {
+            WireType::Varint => RawValue::Varint(reader.read_varint()?),
+            WireType::Fixed32 => RawValue::Fixed32(reader.read_fixed32()?),
+            WireType::Fixed64 => RawValue::Fixed64(reader.read_fixed64()?),
+            WireType::LengthDelimited => {
+                let data = reader.read_length_delimited()?;
+                let start = reader.position() - data.len();
+
+                RawValue::LengthDelimited {
+                    data: data.to_vec(),
+                    payload: guess(data, start),
+                }
+            }
+            WireType::StartGroup => RawValue::Group(parse_fields(reader, Some(tag.number))?),
+            WireType::EndGroup if group == Some(tag.number) => return Ok(fields),
+            WireType::EndGroup => {
+                return Err(DecodeError::new(
+                    wire::DecodeErrorKind::UnexpectedEndGroup(tag.number),
+                    offset,
+                ));
+            }
+        };
+
+        fields.push(RawField {
+            number: tag.number,
+            offset,
+            end: reader.position(),
+            value,
+            field: None,
+        });
+    }
+
+    match group {
+        Some(number) => Err(reader.error(wire::DecodeErrorKind::UnterminatedGroup(number))),
+        None => Ok(fields),
+    }
+}
+
+fn is_text(text: &str) -> bool {
+    text.chars()
+        .all(|ch| !ch.is_control() || matches!(ch, '\n' | '\r' | '\t'))
+}
+
+fn message(data: &[u8], offset: usize) -> Option<Vec<RawField>> {
+    parse_fields(&mut Reader::with_offset(data, offset), None)
+        .ok()
+        .filter(|fields| !fields.is_empty())
+}
+
+fn packed(data: &[u8]) -> Option<Vec<u64>> {
+    let mut reader = Reader::new(data);
+    let mut values = vec![];
+
+    while !reader.is_empty() {
+        let start = reader.position();
+        let value = reader.read_varint().ok()?;
+
+        // overlong varints are a sign of random bytes
+        if reader.position() - start != wire::varint_len(value) {
+            return None;
+        }
+
+        values.push(value);
+    }
+
+    Some(values)
+}
+
+// Printable text wins over messages, since short strings often happen to be valid messages too.
+fn guess(data: &[u8], offset: usize) -> Payload {
+    if let Ok(text) = std::str::from_utf8(data)
+        && !text.is_empty()
+        && is_text(text)
+    {
+        return Payload::String(text.to_string());
+    }
+
+    if let Some(fields) = message(data, offset) {
+        return Payload::Message(fields);
+    }
+
+    match packed(data) {
+        Some(values) if values.len() > 1 => Payload::Packed(values),
+        _ => Payload::Bytes,
+    }
+}
+
+// Names the fields known to the schema and reinterprets length-delimited values by their types.
+pub fn annotate(fields: &mut [RawField], pool: &Pool, type_name: &str) {
+    for field in fields {
+        let Some(info) = crate::find_field(pool, type_name, field.number) else {
+            continue;
+        };
+
+        let nested_type = info.type_name.clone().unwrap_or_default();
+
+        match &mut field.value {
+            RawValue::LengthDelimited { data, payload } => {
+                let offset = field.end - data.len();
+
+                *payload = match info.r#type {
+                    FieldType::String => std::str::from_utf8(data)
+                        .map_or(Payload::Bytes, |text| Payload::String(text.to_string())),
+                    FieldType::Bytes => Payload::Bytes,
+                    FieldType::Message => {
+                        parse_fields(&mut Reader::with_offset(data, offset), None)
+                            .map_or(Payload::Bytes, Payload::Message)
+                    }
+                    r#type if info.is_repeated() && r#type.is_packable() => {
+                        match crate::wire_type(r#type) {
+                            WireType::Fixed32 if data.len() % 4 == 0 => Payload::Packed32(
+                                data.chunks(4)
+                                    .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+                                    .collect(),
+                            ),
+                            WireType::Fixed64 if data.len() % 8 == 0 => Payload::Packed64(
+                                data.chunks(8)
+                                    .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+                                    .collect(),
+                            ),
+                            WireType::Varint => {
+                                packed(data).map_or(Payload::Bytes, Payload::Packed)
+                            }
+                            _ => Payload::Bytes,
+                        }
+                    }
+                    _ => std::mem::replace(payload, Payload::Bytes),
+                };
+
+                if let Payload::Message(nested) = payload {
+                    annotate(nested, pool, &nested_type);
+                }
+            }
+            RawValue::Group(nested) => annotate(nested, pool, &nested_type),
+            _ => {}
+        }
+
+        field.field = Some(info.clone());
+    }
+}
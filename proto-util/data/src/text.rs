@@ -0,0 +1,816 @@
+// Protobuf text format (`name: "x" child { id: 3 }`), as read and written by protoc.
+
+use crate::{DynamicMessage, Field, FieldValue, UnknownValue, Value};
+use descriptor::{FieldInfo, FieldType, Pool};
+use wire::{Reader, Tag, WireType, Writer};
+
+const ANY: &str = "google.protobuf.Any";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    // Unsigned, as written; the sign is a separate symbol.
+    Integer(String),
+    Float(String),
+    String(Vec<u8>),
+    Symbol(char),
+}
+
+fn error_at(input: &str, offset: usize, message: impl Into<String>) -> ParseError {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+
+    ParseError {
+        message: message.into(),
+        line,
+        column,
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    let is_ident = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_';
+
+    while index < bytes.len() {
+        let start = index;
+        let byte = bytes[index];
+
+        if byte.is_ascii_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        if byte == b'#' {
+            while index < bytes.len() && bytes[index] != b'\n' {
+                index += 1;
+            }
+            continue;
+        }
+
+        let token = if byte.is_ascii_alphabetic() || byte == b'_' {
+            while index < bytes.len() && is_ident(bytes[index]) {
+                index += 1;
+            }
+            Token::Ident(input[start..index].to_string())
+        } else if byte.is_ascii_digit()
+            || byte == b'.' && bytes.get(index + 1).is_some_and(u8::is_ascii_digit)
+        {
+            let mut float = false;
+
+            if bytes[index..].starts_with(b"0x") || bytes[index..].starts_with(b"0X") {
+                index += 2;
+                while index < bytes.len() && bytes[index].is_ascii_hexdigit() {
+                    index += 1;
+                }
+            } else {
+                let digits = |index: &mut usize| {
+                    while *index < bytes.len() && bytes[*index].is_ascii_digit() {
+                        *index += 1;
+                    }
+                };
+
+                digits(&mut index);
+                if bytes.get(index) == Some(&b'.') {
+                    float = true;
+                    index += 1;
+                    digits(&mut index);
+                }
+                if matches!(bytes.get(index), Some(b'e' | b'E')) {
+                    float = true;
+                    index += 1;
+                    if matches!(bytes.get(index), Some(b'+' | b'-')) {
+                        index += 1;
+                    }
+                    digits(&mut index);
+                }
+                if matches!(bytes.get(index), Some(b'f' | b'F')) {
+                    float = true;
+                    index += 1;
+                }
+            }
+
+            if index < bytes.len() && is_ident(bytes[index]) {
+                return Err(error_at(input, start, "Invalid number"));
+            }
+
+            let text = input[start..index].to_string();
+            match float {
+                true => Token::Float(text),
+                false => Token::Integer(text),
+            }
+        } else if byte == b'"' || byte == b'\'' {
+            index += 1;
+            while index < bytes.len() && bytes[index] != byte {
+                match bytes[index] {
+                    b'\n' => break,
+                    b'\\' => index += 2,
+                    _ => index += 1,
+                }
+            }
+
+            if index >= bytes.len() || bytes[index] != byte {
+                return Err(error_at(input, start, "Unterminated string"));
+            }
+
+            index += 1;
+            let data = descriptor::unescape(&input[start + 1..index - 1]).map_err(|error| {
+                error_at(input, start + 1 + error.position, "Invalid escape sequence")
+            })?;
+            Token::String(data)
+        } else {
+            let ch = input[index..].chars().next().unwrap();
+            index += ch.len_utf8();
+            Token::Symbol(ch)
+        };
+
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+fn parse_integer(text: &str) -> Option<u64> {
+    if let Some(digits) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u64::from_str_radix(digits, 16).ok()
+    } else if text.len() > 1 && text.starts_with('0') {
+        u64::from_str_radix(&text[1..], 8).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+struct Parser<'a> {
+    pool: &'a Pool,
+    input: &'a str,
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(token, _)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map_or(self.input.len(), |(_, offset)| *offset)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        error_at(self.input, self.position(), message)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        let found = self.peek() == Some(&Token::Symbol(symbol));
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), ParseError> {
+        match self.eat(symbol) {
+            true => Ok(()),
+            false => Err(self.error(format!("Expected \"{}\"", symbol))),
+        }
+    }
+
+    // Closing symbol of the message value that starts here.
+    fn open(&mut self) -> Result<char, ParseError> {
+        if self.eat('{') {
+            Ok('}')
+        } else if self.eat('<') {
+            Ok('>')
+        } else {
+            Err(self.error("Expected \"{\""))
+        }
+    }
+
+    fn message(
+        &mut self,
+        type_name: &str,
+        close: Option<char>,
+    ) -> Result<DynamicMessage, ParseError> {
+        let pool = self.pool;
+        let r#type = pool
+            .message(type_name)
+            .ok_or_else(|| self.error(format!("Unknown message type {}", type_name)))?;
+        let mut message = DynamicMessage::new(type_name);
+
+        loop {
+            match (self.peek(), close) {
+                (None, None) => break,
+                (None, Some(close)) => return Err(self.error(format!("Expected \"{}\"", close))),
+                (Some(Token::Symbol(symbol)), Some(close)) if *symbol == close => {
+                    self.index += 1;
+                    break;
+                }
+                _ => {}
+            }
+
+            let start = self.position();
+
+            match self.next() {
+                Some(Token::Ident(name)) => {
+                    let field = r#type.field_by_name(&name).ok_or_else(|| {
+                        error_at(
+                            self.input,
+                            start,
+                            format!(
+                                "Message type \"{}\" has no field named \"{}\"",
+                                type_name, name
+                            ),
+                        )
+                    })?;
+                    self.field(&mut message, field, start)?;
+                }
+                Some(Token::Symbol('[')) => {
+                    let name = self.bracket_name()?;
+
+                    if name.contains('/') {
+                        self.any(&mut message, &name, start)?;
+                    } else {
+                        let field = pool
+                            .extension(&name)
+                            .filter(|field| field.extendee.as_deref() == Some(type_name))
+                            .ok_or_else(|| {
+                                error_at(
+                                    self.input,
+                                    start,
+                                    format!(
+                                        "Extension \"{}\" is not defined or is not an extension of \"{}\"",
+                                        name, type_name
+                                    ),
+                                )
+                            })?;
+                        self.field(&mut message, field, start)?;
+                    }
+                }
+                Some(Token::Integer(text)) => {
+                    let number = parse_integer(&text)
+                        .and_then(|number| u32::try_from(number).ok())
+                        .filter(|number| (1..=wire::MAX_FIELD_NUMBER).contains(number))
+                        .ok_or_else(|| error_at(self.input, start, "Invalid field number"))?;
+                    let value = self.unknown(number)?;
+
+                    message.fields.push(Field {
+                        number,
+                        value: FieldValue::Unknown(value),
+                    });
+                }
+                _ => return Err(error_at(self.input, start, "Expected field name")),
+            }
+
+            if !self.eat(';') {
+                self.eat(',');
+            }
+        }
+
+        // protoc writes known fields by number and unknown ones after them
+        message.fields.sort_by_key(|field| match field.value {
+            FieldValue::Unknown(_) => (1, 0),
+            _ => (0, field.number),
+        });
+
+        Ok(message)
+    }
+
+    // Extension name or type URL of an expanded Any, after the opening bracket.
+    fn bracket_name(&mut self) -> Result<String, ParseError> {
+        let mut name = String::new();
+
+        loop {
+            match self.next() {
+                Some(Token::Ident(ident)) => name.push_str(&ident),
+                _ => {
+                    self.index -= 1;
+                    return Err(self.error("Expected identifier"));
+                }
+            }
+
+            match self.next() {
+                Some(Token::Symbol(']')) => return Ok(name),
+                Some(Token::Symbol(symbol @ ('.' | '/'))) => name.push(symbol),
+                _ => {
+                    self.index -= 1;
+                    return Err(self.error("Expected \"]\""));
+                }
+            }
+        }
+    }
+
+    fn field(
+        &mut self,
+        message: &mut DynamicMessage,
+        field: &FieldInfo,
+        start: usize,
+    ) -> Result<(), ParseError> {
+        if field.r#type == FieldType::Message {
+            self.eat(':');
+        } else {
+            self.expect(':')?;
+        }
+
+        if field.is_repeated() {
+            if !self.eat('[') {
+                let value = self.value(field)?;
+                message.push(field, value);
+                return Ok(());
+            }
+
+            if self.eat(']') {
+                return Ok(());
+            }
+
+            loop {
+                let value = self.value(field)?;
+                message.push(field, value);
+
+                if self.eat(']') {
+                    return Ok(());
+                }
+                self.expect(',')?;
+            }
+        }
+
+        if message.has(field.number) {
+            return Err(error_at(
+                self.input,
+                start,
+                format!(
+                    "Non-repeated field \"{}\" is specified multiple times",
+                    field.name
+                ),
+            ));
+        }
+
+        if let (Some(oneof), Some(r#type)) = (field.oneof, self.pool.message(&message.type_name))
+            && let Some(other) = message.which_oneof(self.pool, oneof)
+        {
+            return Err(error_at(
+                self.input,
+                start,
+                format!(
+                    "Field \"{}\" is specified along with field \"{}\", another member of oneof \"{}\"",
+                    field.name,
+                    r#type.field(other).map_or("", |other| other.name.as_str()),
+                    r#type.oneofs[oneof].name
+                ),
+            ));
+        }
+
+        let value = self.value(field)?;
+        message.set(self.pool, field, value);
+
+        Ok(())
+    }
+
+    // `[type.googleapis.com/pkg.Type] { ... }` inside an Any.
+    fn any(
+        &mut self,
+        message: &mut DynamicMessage,
+        url: &str,
+        start: usize,
+    ) -> Result<(), ParseError> {
+        if message.type_name != ANY {
+            return Err(error_at(
+                self.input,
+                start,
+                format!("Type URL {} is only allowed in {}", url, ANY),
+            ));
+        }
+
+        if message.has(1) || message.has(2) {
+            return Err(error_at(
+                self.input,
+                start,
+                "Expanded Any is specified multiple times",
+            ));
+        }
+
+        let type_name = &url[url.rfind('/').unwrap() + 1..];
+        if self.pool.message(type_name).is_none() {
+            return Err(error_at(
+                self.input,
+                start,
+                format!("Unknown message type {}", type_name),
+            ));
+        }
+
+        self.eat(':');
+        let close = self.open()?;
+        let nested = self.message(type_name, Some(close))?;
+        let data = crate::encode(self.pool, &nested)
+            .map_err(|error| error_at(self.input, start, error.to_string()))?;
+
+        message.fields.push(Field {
+            number: 1,
+            value: FieldValue::Single(Value::String(url.to_string())),
+        });
+        message.fields.push(Field {
+            number: 2,
+            value: FieldValue::Single(Value::Bytes(data)),
+        });
+
+        Ok(())
+    }
+
+    fn value(&mut self, field: &FieldInfo) -> Result<Value, ParseError> {
+        Ok(match field.r#type {
+            FieldType::Message => {
+                let close = self.open()?;
+                Value::Message(
+                    self.message(field.type_name.as_deref().unwrap_or_default(), Some(close))?,
+                )
+            }
+            FieldType::Group => return Err(self.error("Groups are not supported")),
+            FieldType::String => {
+                let start = self.position();
+                String::from_utf8(self.string()?)
+                    .map(Value::String)
+                    .map_err(|_| {
+                        error_at(
+                            self.input,
+                            start,
+                            format!("String field \"{}\" contains invalid UTF-8", field.name),
+                        )
+                    })?
+            }
+            FieldType::Bytes => Value::Bytes(self.string()?),
+            FieldType::Enum => self.enum_value(field)?,
+            FieldType::Bool => match self.next() {
+                Some(Token::Ident(ident)) if matches!(ident.as_str(), "true" | "True" | "t") => {
+                    Value::Bool(true)
+                }
+                Some(Token::Ident(ident)) if matches!(ident.as_str(), "false" | "False" | "f") => {
+                    Value::Bool(false)
+                }
+                Some(Token::Integer(text)) if text == "0" || text == "1" => {
+                    Value::Bool(text == "1")
+                }
+                _ => {
+                    self.index -= 1;
+                    return Err(self.error("Expected \"true\" or \"false\""));
+                }
+            },
+            FieldType::Double => Value::F64(self.float()?),
+            FieldType::Float => Value::F32(self.float()? as f32),
+            FieldType::Int32 | FieldType::Sint32 | FieldType::Sfixed32 => {
+                Value::I32(self.integer(i32::MIN as i128, i32::MAX as i128)? as i32)
+            }
+            FieldType::Int64 | FieldType::Sint64 | FieldType::Sfixed64 => {
+                Value::I64(self.integer(i64::MIN as i128, i64::MAX as i128)? as i64)
+            }
+            FieldType::Uint32 | FieldType::Fixed32 => {
+                Value::U32(self.integer(0, u32::MAX as i128)? as u32)
+            }
+            FieldType::Uint64 | FieldType::Fixed64 => {
+                Value::U64(self.integer(0, u64::MAX as i128)? as u64)
+            }
+        })
+    }
+
+    // Adjacent string literals are concatenated.
+    fn string(&mut self) -> Result<Vec<u8>, ParseError> {
+        let Some(Token::String(mut data)) = self.peek().cloned() else {
+            return Err(self.error("Expected string"));
+        };
+        self.index += 1;
+
+        while let Some(Token::String(next)) = self.peek() {
+            data.extend_from_slice(next);
+            self.index += 1;
+        }
+
+        Ok(data)
+    }
+
+    fn integer(&mut self, min: i128, max: i128) -> Result<i128, ParseError> {
+        let start = self.position();
+        let negative = self.eat('-');
+
+        let Some(Token::Integer(text)) = self.next() else {
+            self.index -= 1;
+            return Err(self.error("Expected integer"));
+        };
+
+        let value = parse_integer(&text)
+            .map(|value| match negative {
+                true => -(value as i128),
+                false => value as i128,
+            })
+            .filter(|value| (min..=max).contains(value))
+            .ok_or_else(|| error_at(self.input, start, "Integer out of range"))?;
+
+        Ok(value)
+    }
+
+    fn float(&mut self) -> Result<f64, ParseError> {
+        let start = self.position();
+        let negative = self.eat('-');
+
+        let value = match self.next() {
+            Some(Token::Float(text)) => text.trim_end_matches(['f', 'F']).parse().ok(),
+            Some(Token::Integer(text)) => text.parse().ok(),
+            Some(Token::Ident(ident)) => match ident.to_ascii_lowercase().as_str() {
+                "inf" | "infinity" => Some(f64::INFINITY),
+                "nan" => Some(f64::NAN),
+                _ => None,
+            },
+            _ => None,
+        }
+        .ok_or_else(|| error_at(self.input, start, "Expected number"))?;
+
+        Ok(if negative { -value } else { value })
+    }
+
+    fn enum_value(&mut self, field: &FieldInfo) -> Result<Value, ParseError> {
+        let start = self.position();
+        let r#enum = field
+            .type_name
+            .as_deref()
+            .and_then(|name| self.pool.r#enum(name))
+            .ok_or_else(|| self.error(format!("Unknown enum type of field \"{}\"", field.name)))?;
+
+        let value = match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let value = r#enum.value_by_name(ident).map(|value| value.number);
+                self.index += 1;
+                value
+            }
+            // numbers outside of the enum are only allowed for open enums
+            _ => Some(self.integer(i32::MIN as i128, i32::MAX as i128)? as i32)
+                .filter(|number| !r#enum.closed || r#enum.value(*number).is_some()),
+        };
+
+        value.map(Value::Enum).ok_or_else(|| {
+            error_at(
+                self.input,
+                start,
+                format!(
+                    "Unknown enumeration value of \"{}\" for field \"{}\"",
+                    &self.input[start..self.position()],
+                    field.name
+                ),
+            )
+        })
+    }
+
+    // Fields by number are kept as unknown: integers are varints, or fixed32/fixed64 when written
+    // as hex with 8 or 16 digits, strings are length-delimited and `{ ... }` are groups.
+    fn unknown(&mut self, number: u32) -> Result<UnknownValue, ParseError> {
+        let mut writer = Writer::new();
+
+        if !self.eat(':') {
+            self.expect('{')?;
+
+            while !self.eat('}') {
+                let Some(Token::Integer(text)) = self.next() else {
+                    self.index -= 1;
+                    return Err(self.error("Expected field number"));
+                };
+                let nested = parse_integer(&text)
+                    .and_then(|number| u32::try_from(number).ok())
+                    .filter(|number| (1..=wire::MAX_FIELD_NUMBER).contains(number))
+                    .ok_or_else(|| self.error("Invalid field number"))?;
+                let value = self.unknown(nested)?;
+
+                writer.write_tag(Tag::new(nested, value.wire_type));
+                writer.write_raw(&value.data);
+
+                if !self.eat(';') {
+                    self.eat(',');
+                }
+            }
+
+            writer.write_tag(Tag::new(number, WireType::EndGroup));
+
+            return Ok(UnknownValue {
+                wire_type: WireType::StartGroup,
+                data: writer.into_inner(),
+            });
+        }
+
+        if let Some(Token::String(_)) = self.peek() {
+            writer.write_length_delimited(&self.string()?);
+
+            return Ok(UnknownValue {
+                wire_type: WireType::LengthDelimited,
+                data: writer.into_inner(),
+            });
+        }
+
+        let hex = match self.tokens.get(self.index) {
+            Some((Token::Integer(text), _)) if text.starts_with("0x") => text.len() - 2,
+            _ => 0,
+        };
+
+        let value = self.integer(i64::MIN as i128, u64::MAX as i128)?;
+
+        let wire_type = match hex {
+            8 => {
+                writer.write_fixed32(value as u32);
+                WireType::Fixed32
+            }
+            16 => {
+                writer.write_fixed64(value as u64);
+                WireType::Fixed64
+            }
+            _ => {
+                writer.write_varint(value as u64);
+                WireType::Varint
+            }
+        };
+
+        Ok(UnknownValue {
+            wire_type,
+            data: writer.into_inner(),
+        })
+    }
+}
+
+pub fn parse(pool: &Pool, type_name: &str, input: &str) -> Result<DynamicMessage, ParseError> {
+    let mut parser = Parser {
+        pool,
+        input,
+        tokens: tokenize(input)?,
+        index: 0,
+    };
+
+    parser.message(type_name, None)
+}
+
+// Single value of the field, e.g. `"text"`, `12` or an enum value name; messages are written in
+// braces.
+pub fn parse_value(pool: &Pool, field: &FieldInfo, input: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser {
+        pool,
+        input,
+        tokens: tokenize(input)?,
+        index: 0,
+    };
+
+    let value = parser.value(field)?;
+
+    match parser.peek() {
+        None => Ok(value),
+        Some(_) => Err(parser.error("Expected end of the value")),
+    }
+}
+
+pub fn print(pool: &Pool, message: &DynamicMessage) -> String {
+    let mut out = String::new();
+    print_message(pool, message, 0, &mut out);
+    out
+}
+
+fn print_message(pool: &Pool, message: &DynamicMessage, indent: usize, out: &mut String) {
+    for field in &message.fields {
+        let info = crate::find_field(pool, &message.type_name, field.number);
+
+        let values = match (&field.value, info) {
+            (FieldValue::Unknown(value), _) => {
+                print_unknown(field.number, value, indent, out);
+                continue;
+            }
+            (FieldValue::Single(value), Some(_)) => std::slice::from_ref(value),
+            (FieldValue::Packed(values), Some(_)) => values.as_slice(),
+            (_, None) => continue,
+        };
+        let info = info.unwrap();
+
+        let name = match info.is_extension() {
+            true => format!("[{}]", info.full_name),
+            false => info.name.clone(),
+        };
+
+        for value in values {
+            out.push_str(&"  ".repeat(indent));
+
+            match value {
+                Value::Message(nested) => {
+                    out.push_str(&format!("{} {{\n", name));
+
+                    if !print_any(pool, nested, indent + 1, out) {
+                        print_message(pool, nested, indent + 1, out);
+                    }
+
+                    out.push_str(&format!("{}}}\n", "  ".repeat(indent)));
+                }
+                value => out.push_str(&format!(
+                    "{}: {}\n",
+                    name,
+                    crate::print::scalar(pool, info, value)
+                )),
+            }
+        }
+    }
+}
+
+// Prints the Any with its value expanded, when the type of the value is known.
+fn print_any(pool: &Pool, any: &DynamicMessage, indent: usize, out: &mut String) -> bool {
+    if any.type_name != ANY
+        || !any
+            .fields
+            .iter()
+            .all(|field| matches!(field.value, FieldValue::Single(_)))
+    {
+        return false;
+    }
+
+    let Some(Value::String(url)) = any.get_single(1) else {
+        return false;
+    };
+    let data = match any.get_single(2) {
+        Some(Value::Bytes(data)) => data.as_slice(),
+        _ => &[],
+    };
+
+    let Some(type_name) = url.rfind('/').map(|index| &url[index + 1..]) else {
+        return false;
+    };
+    let Ok(value) = crate::decode(pool, type_name, data) else {
+        return false;
+    };
+
+    out.push_str(&format!("{}[{}] {{\n", "  ".repeat(indent), url));
+    print_message(pool, &value, indent + 1, out);
+    out.push_str(&format!("{}}}\n", "  ".repeat(indent)));
+
+    true
+}
+
+fn print_unknown(number: u32, value: &UnknownValue, indent: usize, out: &mut String) {
+    let mut reader = Reader::new(&value.data);
+    out.push_str(&"  ".repeat(indent));
+
+    let text = match value.wire_type {
+        WireType::Varint => reader.read_varint().map(|value| value.to_string()),
+        WireType::Fixed32 => reader
+            .read_fixed32()
+            .map(|value| format!("0x{:08x}", value)),
+        WireType::Fixed64 => reader
+            .read_fixed64()
+            .map(|value| format!("0x{:016x}", value)),
+        WireType::LengthDelimited => reader
+            .read_length_delimited()
+            .map(|data| format!("\"{}\"", descriptor::escape(data))),
+        WireType::StartGroup | WireType::EndGroup => {
+            out.push_str(&format!("{} {{\n", number));
+
+            while let Ok(tag) = reader.read_tag() {
+                if tag.wire_type == WireType::EndGroup {
+                    break;
+                }
+
+                let Ok(data) = reader.skip_field(tag) else {
+                    break;
+                };
+                let nested = UnknownValue {
+                    wire_type: tag.wire_type,
+                    data: data.to_vec(),
+                };
+                print_unknown(tag.number, &nested, indent + 1, out);
+            }
+
+            out.push_str(&format!("{}}}\n", "  ".repeat(indent)));
+            return;
+        }
+    };
+
+    out.push_str(&format!(
+        "{}: {}\n",
+        number,
+        text.unwrap_or_else(|_| "\"\"".to_string())
+    ));
+}
@@ -0,0 +1,246 @@
+use descriptor::{FieldInfo, FieldType, Pool};
+use wire::WireType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Enum(i32),
+    Message(DynamicMessage),
+}
+
+impl Value {
+    // Zero value of the field's type; enums get their first value and messages are empty.
+    pub fn default_of(pool: &Pool, field: &FieldInfo) -> Self {
+        match field.r#type {
+            FieldType::Double => Self::F64(0.0),
+            FieldType::Float => Self::F32(0.0),
+            FieldType::Int64 | FieldType::Sint64 | FieldType::Sfixed64 => Self::I64(0),
+            FieldType::Uint64 | FieldType::Fixed64 => Self::U64(0),
+            FieldType::Int32 | FieldType::Sint32 | FieldType::Sfixed32 => Self::I32(0),
+            FieldType::Uint32 | FieldType::Fixed32 => Self::U32(0),
+            FieldType::Bool => Self::Bool(false),
+            FieldType::String => Self::String(String::new()),
+            FieldType::Bytes => Self::Bytes(vec![]),
+            FieldType::Enum => Self::Enum(
+                field
+                    .type_name
+                    .as_deref()
+                    .and_then(|name| pool.r#enum(name))
+                    .and_then(|r#enum| r#enum.values.first())
+                    .map_or(0, |value| value.number),
+            ),
+            FieldType::Message | FieldType::Group => Self::Message(DynamicMessage::new(
+                field.type_name.clone().unwrap_or_default(),
+            )),
+        }
+    }
+}
+
+// Value of a field not described by the schema, or not encoded the way the schema says, kept
+// byte for byte.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownValue {
+    pub wire_type: WireType,
+    // Everything after the tag: the length prefix of length-delimited values is included, the
+    // end tag of groups too.
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    // One occurrence of a singular field or one element of an unpacked repeated field.
+    Single(Value),
+    // One packed run of a repeated field.
+    Packed(Vec<Value>),
+    Unknown(UnknownValue),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub number: u32,
+    pub value: FieldValue,
+}
+
+// Message as it appears on the wire: fields keep their order and packing, so encoding an
+// unmodified message gives back the same bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicMessage {
+    // Fully-qualified, without the leading dot.
+    pub type_name: String,
+    pub fields: Vec<Field>,
+}
+
+impl DynamicMessage {
+    pub fn new(type_name: impl Into<String>) -> Self {
+        Self {
+            type_name: type_name.into(),
+            fields: vec![],
+        }
+    }
+
+    pub fn has(&self, number: u32) -> bool {
+        self.fields.iter().any(|field| field.number == number)
+    }
+
+    // All known values of the field in wire order, packed runs flattened.
+    pub fn get(&self, number: u32) -> impl Iterator<Item = &Value> {
+        self.fields
+            .iter()
+            .filter(move |field| field.number == number)
+            .flat_map(|field| match &field.value {
+                FieldValue::Single(value) => std::slice::from_ref(value),
+                FieldValue::Packed(values) => values.as_slice(),
+                FieldValue::Unknown(_) => &[],
+            })
+    }
+
+    pub fn get_mut(&mut self, number: u32) -> impl Iterator<Item = &mut Value> {
+        self.fields
+            .iter_mut()
+            .filter(move |field| field.number == number)
+            .flat_map(|field| match &mut field.value {
+                FieldValue::Single(value) => std::slice::from_mut(value),
+                FieldValue::Packed(values) => values.as_mut_slice(),
+                FieldValue::Unknown(_) => &mut [],
+            })
+    }
+
+    // Value of a singular field; the last one wins when there are several on the wire.
+    pub fn get_single(&self, number: u32) -> Option<&Value> {
+        self.get(number).last()
+    }
+
+    pub fn unknown(&self) -> impl Iterator<Item = (u32, &UnknownValue)> {
+        self.fields.iter().filter_map(|field| match &field.value {
+            FieldValue::Unknown(value) => Some((field.number, value)),
+            _ => None,
+        })
+    }
+
+    pub fn clear(&mut self, number: u32) {
+        self.fields.retain(|field| field.number != number);
+    }
+
+    // Sets a singular field in place of its first occurrence. Setting a member of a oneof clears
+    // the other members.
+    pub fn set(&mut self, pool: &Pool, field: &FieldInfo, value: Value) {
+        let message = pool.message(&self.type_name);
+        let replaced = |other: &Field| {
+            other.number == field.number
+                || field.oneof.is_some()
+                    && message
+                        .and_then(|message| message.field(other.number))
+                        .is_some_and(|other| other.oneof == field.oneof)
+        };
+
+        let position = self
+            .fields
+            .iter()
+            .position(|other| other.number == field.number);
+        let removed_before = position.map_or(0, |position| {
+            self.fields[..position]
+                .iter()
+                .filter(|other| replaced(other))
+                .count()
+        });
+
+        self.fields.retain(|other| !replaced(other));
+        let position = position.map(|position| position - removed_before);
+
+        let field = Field {
+            number: field.number,
+            value: FieldValue::Single(value),
+        };
+
+        match position {
+            Some(position) => self.fields.insert(position, field),
+            None => self.fields.push(field),
+        }
+    }
+
+    // Appends an element to a repeated field, to its last packed run if it is packed.
+    pub fn push(&mut self, field: &FieldInfo, value: Value) {
+        if field.packed {
+            if let Some(FieldValue::Packed(values)) = self
+                .fields
+                .iter_mut()
+                .rev()
+                .find(|other| other.number == field.number)
+                .map(|other| &mut other.value)
+            {
+                values.push(value);
+                return;
+            }
+
+            self.fields.push(Field {
+                number: field.number,
+                value: FieldValue::Packed(vec![value]),
+            });
+        } else {
+            self.fields.push(Field {
+                number: field.number,
+                value: FieldValue::Single(value),
+            });
+        }
+    }
+
+    // Removes the element of a repeated field by its index among `get(number)`.
+    pub fn remove(&mut self, number: u32, index: usize) -> Option<Value> {
+        let mut remaining = index;
+
+        for position in 0..self.fields.len() {
+            let field = &mut self.fields[position];
+
+            if field.number != number {
+                continue;
+            }
+
+            match &mut field.value {
+                FieldValue::Single(_) if remaining == 0 => {
+                    return match self.fields.remove(position).value {
+                        FieldValue::Single(value) => Some(value),
+                        _ => None,
+                    };
+                }
+                FieldValue::Single(_) => remaining -= 1,
+                FieldValue::Packed(values) if remaining < values.len() => {
+                    let value = values.remove(remaining);
+
+                    if values.is_empty() {
+                        self.fields.remove(position);
+                    }
+
+                    return Some(value);
+                }
+                FieldValue::Packed(values) => remaining -= values.len(),
+                FieldValue::Unknown(_) => {}
+            }
+        }
+
+        None
+    }
+
+    // Number of the member of the oneof that is set.
+    pub fn which_oneof(&self, pool: &Pool, oneof: usize) -> Option<u32> {
+        let message = pool.message(&self.type_name)?;
+
+        self.fields
+            .iter()
+            .rev()
+            .filter(|field| !matches!(field.value, FieldValue::Unknown(_)))
+            .find(|field| {
+                message
+                    .field(field.number)
+                    .is_some_and(|info| info.oneof == Some(oneof))
+            })
+            .map(|field| field.number)
+    }
+}
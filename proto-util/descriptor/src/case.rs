@@ -0,0 +1,76 @@
+// Naming styles of the style guide, and conversions to them for suggestions.
+
+// Words of a name in any style: split at underscores and other punctuation, and where a lowercase
+// letter or a digit is followed by an uppercase one ("HTTPServer" gives "HTTP" and "Server").
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = vec![];
+    let mut word = String::new();
+
+    for (index, ch) in chars.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|index| chars[index]);
+        let next = chars.get(index + 1);
+
+        let boundary = ch.is_uppercase()
+            && previous.is_some_and(|previous| {
+                !previous.is_uppercase() || next.is_some_and(|next| next.is_lowercase())
+            });
+
+        let separator = !ch.is_ascii_alphanumeric();
+        if (separator || boundary) && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if !separator {
+            word.push(*ch);
+        }
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+pub fn is_pascal_case(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_uppercase())
+        && name.chars().all(|ch| ch.is_ascii_alphanumeric())
+}
+
+fn is_snake_case(name: &str, letter: fn(&char) -> bool) -> bool {
+    name.starts_with(|ch: char| letter(&ch))
+        && !name.ends_with('_')
+        && !name.contains("__")
+        && name
+            .chars()
+            .all(|ch| letter(&ch) || ch.is_ascii_digit() || ch == '_')
+}
+
+pub fn is_lower_snake_case(name: &str) -> bool {
+    is_snake_case(name, char::is_ascii_lowercase)
+}
+
+pub fn is_upper_snake_case(name: &str) -> bool {
+    is_snake_case(name, char::is_ascii_uppercase)
+}
+
+pub fn pascal_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().into_iter().flat_map(char::to_uppercase);
+            first
+                .chain(chars.flat_map(char::to_lowercase))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+pub fn lower_snake_case(name: &str) -> String {
+    words(name).join("_").to_lowercase()
+}
+
+pub fn upper_snake_case(name: &str) -> String {
+    words(name).join("_").to_uppercase()
+}
@@ -0,0 +1,254 @@
+// Field codecs used by the `message!` macro, one module per field kind.
+
+use crate::Message;
+use wire::{DecodeError, Reader, Tag, WireType, Writer};
+
+fn nested<T: Message>(value: &mut T, reader: &mut Reader) -> Result<(), DecodeError> {
+    let data = reader.read_length_delimited()?;
+    value.merge(&mut Reader::with_offset(
+        data,
+        reader.position() - data.len(),
+    ))
+}
+
+fn read_int32(tag: Tag, reader: &mut Reader) -> Result<i32, DecodeError> {
+    reader.expect_wire_type(tag, WireType::Varint)?;
+    Ok(reader.read_varint()? as i32)
+}
+
+fn write_int32(value: i32, number: u32, writer: &mut Writer) {
+    writer.write_tag(Tag::new(number, WireType::Varint));
+    writer.write_varint(value as i64 as u64);
+}
+
+fn read_string(tag: Tag, reader: &mut Reader) -> Result<String, DecodeError> {
+    reader.expect_wire_type(tag, WireType::LengthDelimited)?;
+    Ok(reader.read_string()?.to_string())
+}
+
+fn write_bytes(value: &[u8], number: u32, writer: &mut Writer) {
+    writer.write_tag(Tag::new(number, WireType::LengthDelimited));
+    writer.write_length_delimited(value);
+}
+
+pub mod string {
+    use super::*;
+
+    pub fn merge(
+        value: &mut Option<String>,
+        tag: Tag,
+        reader: &mut Reader,
+    ) -> Result<(), DecodeError> {
+        *value = Some(read_string(tag, reader)?);
+        Ok(())
+    }
+
+    pub fn encode(value: &Option<String>, number: u32, writer: &mut Writer) {
+        if let Some(value) = value {
+            write_bytes(value.as_bytes(), number, writer);
+        }
+    }
+}
+
+// Embedded messages that are kept undecoded, i.e. options. Repeated occurrences are concatenated,
+// which is how protobuf merges embedded messages.
+pub mod bytes {
+    use super::*;
+
+    pub fn merge(
+        value: &mut Option<Vec<u8>>,
+        tag: Tag,
+        reader: &mut Reader,
+    ) -> Result<(), DecodeError> {
+        reader.expect_wire_type(tag, WireType::LengthDelimited)?;
+        value
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(reader.read_length_delimited()?);
+        Ok(())
+    }
+
+    pub fn encode(value: &Option<Vec<u8>>, number: u32, writer: &mut Writer) {
+        if let Some(value) = value {
+            write_bytes(value, number, writer);
+        }
+    }
+}
+
+pub mod int32 {
+    use super::*;
+
+    pub fn merge(
+        value: &mut Option<i32>,
+        tag: Tag,
+        reader: &mut Reader,
+    ) -> Result<(), DecodeError> {
+        *value = Some(read_int32(tag, reader)?);
+        Ok(())
+    }
+
+    pub fn encode(value: &Option<i32>, number: u32, writer: &mut Writer) {
+        if let Some(value) = value {
+            write_int32(*value, number, writer);
+        }
+    }
+}
+
+pub mod uint64 {
+    use super::*;
+
+    pub fn merge(
+        value: &mut Option<u64>,
+        tag: Tag,
+        reader: &mut Reader,
+    ) -> Result<(), DecodeError> {
+        reader.expect_wire_type(tag, WireType::Varint)?;
+        *value = Some(reader.read_varint()?);
+        Ok(())
+    }
+
+    pub fn encode(value: &Option<u64>, number: u32, writer: &mut Writer) {
+        if let Some(value) = value {
+            writer.write_tag(Tag::new(number, WireType::Varint));
+            writer.write_varint(*value);
+        }
+    }
+}
+
+pub mod boolean {
+    use super::*;
+
+    pub fn merge(
+        value: &mut Option<bool>,
+        tag: Tag,
+        reader: &mut Reader,
+    ) -> Result<(), DecodeError> {
+        reader.expect_wire_type(tag, WireType::Varint)?;
+        *value = Some(reader.read_varint()? != 0);
+        Ok(())
+    }
+
+    pub fn encode(value: &Option<bool>, number: u32, writer: &mut Writer) {
+        if let Some(value) = value {
+            writer.write_tag(Tag::new(number, WireType::Varint));
+            writer.write_varint(*value as u64);
+        }
+    }
+}
+
+pub mod message {
+    use super::*;
+
+    pub fn merge<T: Message>(
+        value: &mut Option<T>,
+        tag: Tag,
+        reader: &mut Reader,
+    ) -> Result<(), DecodeError> {
+        reader.expect_wire_type(tag, WireType::LengthDelimited)?;
+        nested(value.get_or_insert_with(T::default), reader)
+    }
+
+    pub fn encode<T: Message>(value: &Option<T>, number: u32, writer: &mut Writer) {
+        if let Some(value) = value {
+            writer.write_nested(number, |writer| value.encode_to(writer));
+        }
+    }
+}
+
+pub mod repeated_string {
+    use super::*;
+
+    pub fn merge(
+        value: &mut Vec<String>,
+        tag: Tag,
+        reader: &mut Reader,
+    ) -> Result<(), DecodeError> {
+        value.push(read_string(tag, reader)?);
+        Ok(())
+    }
+
+    pub fn encode(value: &[String], number: u32, writer: &mut Writer) {
+        for value in value {
+            write_bytes(value.as_bytes(), number, writer);
+        }
+    }
+}
+
+pub mod repeated_message {
+    use super::*;
+
+    pub fn merge<T: Message>(
+        value: &mut Vec<T>,
+        tag: Tag,
+        reader: &mut Reader,
+    ) -> Result<(), DecodeError> {
+        reader.expect_wire_type(tag, WireType::LengthDelimited)?;
+
+        let mut message = T::default();
+        nested(&mut message, reader)?;
+        value.push(message);
+
+        Ok(())
+    }
+
+    pub fn encode<T: Message>(value: &[T], number: u32, writer: &mut Writer) {
+        for value in value {
+            writer.write_nested(number, |writer| value.encode_to(writer));
+        }
+    }
+}
+
+// Both packed and unpacked encodings are accepted while decoding.
+fn merge_repeated_int32(
+    value: &mut Vec<i32>,
+    tag: Tag,
+    reader: &mut Reader,
+) -> Result<(), DecodeError> {
+    if tag.wire_type != WireType::LengthDelimited {
+        value.push(read_int32(tag, reader)?);
+        return Ok(());
+    }
+
+    let data = reader.read_length_delimited()?;
+    let mut packed = Reader::with_offset(data, reader.position() - data.len());
+
+    while !packed.is_empty() {
+        value.push(packed.read_varint()? as i32);
+    }
+
+    Ok(())
+}
+
+pub mod repeated_int32 {
+    use super::*;
+
+    pub fn merge(value: &mut Vec<i32>, tag: Tag, reader: &mut Reader) -> Result<(), DecodeError> {
+        merge_repeated_int32(value, tag, reader)
+    }
+
+    pub fn encode(value: &[i32], number: u32, writer: &mut Writer) {
+        for value in value {
+            write_int32(*value, number, writer);
+        }
+    }
+}
+
+pub mod packed_int32 {
+    use super::*;
+
+    pub fn merge(value: &mut Vec<i32>, tag: Tag, reader: &mut Reader) -> Result<(), DecodeError> {
+        merge_repeated_int32(value, tag, reader)
+    }
+
+    pub fn encode(value: &[i32], number: u32, writer: &mut Writer) {
+        if value.is_empty() {
+            return;
+        }
+
+        let mut packed = Writer::new();
+        for value in value {
+            packed.write_varint(*value as i64 as u64);
+        }
+
+        write_bytes(&packed.into_inner(), number, writer);
+    }
+}
@@ -0,0 +1,1053 @@
+use crate::escape::escape;
+use crate::model::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FieldLabel, FieldType,
+    FileDescriptorProto, FileDescriptorSet, Location, ServiceDescriptorProto,
+};
+use crate::names;
+use crate::options::{OptionsKind, StandardOptionType};
+use parser::ast;
+use std::collections::{HashMap, HashSet};
+use typed_arena::Arena;
+use wire::{DecodeError, Reader, Tag, WireType};
+
+#[derive(Debug)]
+pub enum DecompileError {
+    UnsupportedSyntax { file: String, syntax: String },
+    InvalidOptions { file: String, error: DecodeError },
+}
+
+impl std::fmt::Display for DecompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedSyntax { file, syntax } => {
+                write!(f, "{}: syntax \"{}\" is not supported", file, syntax)
+            }
+            Self::InvalidOptions { file, error } => {
+                write!(f, "{}: malformed options: {}", file, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecompileError {}
+
+#[derive(Debug)]
+pub struct DecompiledFile<'a> {
+    pub name: &'a str,
+    pub ast: ast::File<'a>,
+}
+
+pub fn decompile<'a>(
+    set: &'a FileDescriptorSet,
+    arena: &'a Arena<String>,
+) -> Result<Vec<DecompiledFile<'a>>, DecompileError> {
+    let decompiler = Decompiler::new(set, arena);
+
+    set.file
+        .iter()
+        .map(|file| {
+            Ok(DecompiledFile {
+                name: file.name(),
+                ast: decompiler.file(file)?,
+            })
+        })
+        .collect()
+}
+
+// Field numbers of descriptor.proto used in SourceCodeInfo paths.
+mod path {
+    pub const FILE_PACKAGE: i32 = 2;
+    pub const FILE_DEPENDENCY: i32 = 3;
+    pub const FILE_MESSAGE: i32 = 4;
+    pub const FILE_ENUM: i32 = 5;
+    pub const FILE_SERVICE: i32 = 6;
+    pub const FILE_EXTENSION: i32 = 7;
+    pub const FILE_SYNTAX: i32 = 12;
+
+    pub const MESSAGE_FIELD: i32 = 2;
+    pub const MESSAGE_NESTED: i32 = 3;
+    pub const MESSAGE_ENUM: i32 = 4;
+    pub const MESSAGE_EXTENSION_RANGE: i32 = 5;
+    pub const MESSAGE_EXTENSION: i32 = 6;
+    pub const MESSAGE_ONEOF: i32 = 8;
+    pub const MESSAGE_RESERVED_RANGE: i32 = 9;
+    pub const MESSAGE_RESERVED_NAME: i32 = 10;
+
+    pub const ENUM_VALUE: i32 = 2;
+    pub const ENUM_RESERVED_RANGE: i32 = 4;
+    pub const ENUM_RESERVED_NAME: i32 = 5;
+
+    pub const SERVICE_METHOD: i32 = 2;
+}
+
+const MAX_FIELD_NUMBER: i32 = wire::MAX_FIELD_NUMBER as i32;
+
+// Entries of a block together with their comments, ordered by the source position if it's known.
+struct Chunk<T> {
+    position: Option<(i32, i32)>,
+    entries: Vec<T>,
+}
+
+fn flatten<T>(mut chunks: Vec<Chunk<T>>) -> Vec<T> {
+    if chunks.iter().all(|chunk| chunk.position.is_some()) {
+        chunks.sort_by_key(|chunk| chunk.position);
+    }
+
+    chunks.into_iter().flat_map(|chunk| chunk.entries).collect()
+}
+
+fn child(path: &[i32], field: i32, index: usize) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.extend([field, index as i32]);
+    path
+}
+
+pub struct Decompiler<'a> {
+    arena: &'a Arena<String>,
+    names: HashSet<String>,
+    messages: HashMap<String, &'a DescriptorProto>,
+    enums: HashMap<String, &'a EnumDescriptorProto>,
+    extensions: HashMap<(String, i32), (String, &'a FieldDescriptorProto)>,
+}
+
+impl<'a> Decompiler<'a> {
+    pub fn new(set: &'a FileDescriptorSet, arena: &'a Arena<String>) -> Self {
+        let mut decompiler = Self {
+            arena,
+            names: HashSet::new(),
+            messages: HashMap::new(),
+            enums: HashMap::new(),
+            extensions: HashMap::new(),
+        };
+
+        for file in &set.file {
+            let package = file.package();
+            names::insert_package(&mut decompiler.names, package);
+
+            for message in &file.message_type {
+                decompiler.index_message(message, package);
+            }
+            for r#enum in &file.enum_type {
+                decompiler.index_enum(r#enum, package);
+            }
+            for service in &file.service {
+                decompiler
+                    .names
+                    .insert(names::join(package, service.name()));
+            }
+            decompiler.index_extensions(&file.extension, package);
+        }
+
+        decompiler
+    }
+
+    fn index_message(&mut self, message: &'a DescriptorProto, scope: &str) {
+        let name = names::join(scope, message.name());
+
+        for nested in &message.nested_type {
+            self.index_message(nested, &name);
+        }
+        for r#enum in &message.enum_type {
+            self.index_enum(r#enum, &name);
+        }
+        self.index_extensions(&message.extension, &name);
+
+        self.names.insert(name.clone());
+        self.messages.insert(name, message);
+    }
+
+    fn index_enum(&mut self, r#enum: &'a EnumDescriptorProto, scope: &str) {
+        let name = names::join(scope, r#enum.name());
+
+        self.names.insert(name.clone());
+        self.enums.insert(name, r#enum);
+    }
+
+    fn index_extensions(&mut self, extensions: &'a [FieldDescriptorProto], scope: &str) {
+        for extension in extensions {
+            let name = names::join(scope, extension.name());
+            let extendee = extension.extendee.as_deref().unwrap_or_default();
+            let extendee = extendee.strip_prefix('.').unwrap_or(extendee).to_string();
+
+            self.names.insert(name.clone());
+            self.extensions
+                .insert((extendee, extension.number()), (name, extension));
+        }
+    }
+
+    fn alloc(&self, value: String) -> &'a str {
+        self.arena.alloc(value).as_str()
+    }
+
+    // `reference` is fully qualified with a leading dot, so the shortest form is always its suffix.
+    fn reference(&self, reference: &'a str, scope: &str) -> &'a str {
+        let Some(target) = reference.strip_prefix('.') else {
+            return reference;
+        };
+
+        let short = names::shortest(target, scope, |name| self.names.contains(name));
+        &reference[reference.len() - short.len()..]
+    }
+
+    pub fn file(&self, file: &'a FileDescriptorProto) -> Result<ast::File<'a>, DecompileError> {
+        let syntax = match file.syntax.as_deref() {
+            None | Some("") | Some("proto2") => "proto2",
+            Some("proto3") => "proto3",
+            Some(syntax) => {
+                return Err(DecompileError::UnsupportedSyntax {
+                    file: file.name().to_string(),
+                    syntax: syntax.to_string(),
+                });
+            }
+        };
+
+        let context = FileContext {
+            decompiler: self,
+            name: file.name(),
+            proto3: syntax == "proto3",
+            locations: file
+                .source_code_info
+                .iter()
+                .flat_map(|info| &info.location)
+                .map(|location| (location.path.as_slice(), location))
+                .collect(),
+        };
+
+        context.file(file, syntax)
+    }
+}
+
+struct FileContext<'a, 'd> {
+    decompiler: &'d Decompiler<'a>,
+    name: &'a str,
+    proto3: bool,
+    locations: HashMap<&'a [i32], &'a Location>,
+}
+
+impl<'a> FileContext<'a, '_> {
+    fn error(&self, error: DecodeError) -> DecompileError {
+        DecompileError::InvalidOptions {
+            file: self.name.to_string(),
+            error,
+        }
+    }
+
+    fn comments<T>(&self, text: &'a str, wrap: fn(ast::Comment<'a>) -> T, entries: &mut Vec<T>) {
+        let text = text.strip_suffix('\n').unwrap_or(text);
+
+        for line in text.split('\n') {
+            let source = self.decompiler.alloc(format!("//{}", line.trim_end()));
+            entries.push(wrap(ast::Comment::single_line(source)));
+        }
+    }
+
+    fn chunk<T>(&self, path: &[i32], wrap: fn(ast::Comment<'a>) -> T, entry: T) -> Chunk<T> {
+        let location = self.locations.get(path);
+        let mut entries = vec![];
+
+        if let Some(location) = location {
+            for detached in &location.leading_detached_comments {
+                self.comments(detached, wrap, &mut entries);
+            }
+            if let Some(leading) = &location.leading_comments {
+                self.comments(leading, wrap, &mut entries);
+            }
+        }
+
+        entries.push(entry);
+
+        if let Some(trailing) = location.and_then(|location| location.trailing_comments.as_ref()) {
+            self.comments(trailing, wrap, &mut entries);
+        }
+
+        Chunk {
+            position: location
+                .filter(|location| location.span.len() >= 2)
+                .map(|location| (location.span[0], location.span[1])),
+            entries,
+        }
+    }
+
+    fn file(
+        &self,
+        file: &'a FileDescriptorProto,
+        syntax: &'static str,
+    ) -> Result<ast::File<'a>, DecompileError> {
+        let package = file.package();
+        let mut entries = vec![];
+
+        entries.extend(
+            self.chunk(
+                &[path::FILE_SYNTAX],
+                ast::FileEntry::Comment,
+                ast::FileEntry::Syntax(syntax),
+            )
+            .entries,
+        );
+
+        if !package.is_empty() {
+            entries.extend(
+                self.chunk(
+                    &[path::FILE_PACKAGE],
+                    ast::FileEntry::Comment,
+                    ast::FileEntry::Package(package),
+                )
+                .entries,
+            );
+        }
+
+        for (index, dependency) in file.dependency.iter().enumerate() {
+            let modifier = if file.public_dependency.contains(&(index as i32)) {
+                ast::ImportModifier::Public
+            } else if file.weak_dependency.contains(&(index as i32)) {
+                ast::ImportModifier::Weak
+            } else {
+                ast::ImportModifier::None
+            };
+
+            let import = ast::FileEntry::Import(ast::Import {
+                modifier,
+                path: self.decompiler.alloc(escape(dependency.as_bytes())),
+            });
+            entries.extend(
+                self.chunk(
+                    &[path::FILE_DEPENDENCY, index as i32],
+                    ast::FileEntry::Comment,
+                    import,
+                )
+                .entries,
+            );
+        }
+
+        for option in self.options(OptionsKind::File, &file.options, package)? {
+            entries.push(ast::FileEntry::Option(option));
+        }
+
+        let mut chunks = vec![];
+
+        for (index, message) in file.message_type.iter().enumerate() {
+            let path = [path::FILE_MESSAGE, index as i32];
+            let message = self.message(message, &path, package)?;
+            chunks.push(self.chunk(
+                &path,
+                ast::FileEntry::Comment,
+                ast::FileEntry::Message(message),
+            ));
+        }
+
+        for (index, r#enum) in file.enum_type.iter().enumerate() {
+            let path = [path::FILE_ENUM, index as i32];
+            let r#enum = self.r#enum(r#enum, &path, package)?;
+            chunks.push(self.chunk(&path, ast::FileEntry::Comment, ast::FileEntry::Enum(r#enum)));
+        }
+
+        for (index, service) in file.service.iter().enumerate() {
+            let path = [path::FILE_SERVICE, index as i32];
+            let service = self.service(service, &path, package)?;
+            chunks.push(self.chunk(
+                &path,
+                ast::FileEntry::Comment,
+                ast::FileEntry::Service(service),
+            ));
+        }
+
+        for (path, extend) in self.extends(&file.extension, &[path::FILE_EXTENSION], package)? {
+            chunks.push(self.chunk(
+                &path,
+                ast::FileEntry::Comment,
+                ast::FileEntry::Extend(extend),
+            ));
+        }
+
+        entries.extend(flatten(chunks));
+        Ok(entries)
+    }
+
+    // Extensions are grouped into `extend` blocks by their extendee.
+    fn extends(
+        &self,
+        extensions: &'a [FieldDescriptorProto],
+        path: &[i32],
+        scope: &str,
+    ) -> Result<Vec<(Vec<i32>, ast::Extend<'a>)>, DecompileError> {
+        let mut extends: Vec<(Vec<i32>, ast::Extend<'a>)> = vec![];
+
+        for (index, extension) in extensions.iter().enumerate() {
+            let field_path = child(&path[..path.len() - 1], path[path.len() - 1], index);
+            let extendee = self
+                .decompiler
+                .reference(extension.extendee.as_deref().unwrap_or_default(), scope);
+
+            let field = self.field(extension, scope)?;
+            let chunk = self.chunk(
+                &field_path,
+                ast::ExtendEntry::Comment,
+                ast::ExtendEntry::Field(field),
+            );
+
+            match extends
+                .iter_mut()
+                .find(|(_, extend)| extend.r#type == extendee)
+            {
+                Some((_, extend)) => extend.entries.extend(chunk.entries),
+                None => extends.push((
+                    field_path,
+                    ast::Extend {
+                        r#type: extendee,
+                        entries: chunk.entries,
+                    },
+                )),
+            }
+        }
+
+        Ok(extends)
+    }
+
+    fn message(
+        &self,
+        message: &'a DescriptorProto,
+        path: &[i32],
+        scope: &str,
+    ) -> Result<ast::Message<'a>, DecompileError> {
+        let name = names::join(scope, message.name());
+        let mut entries = vec![];
+
+        for option in self.options(OptionsKind::Message, &message.options, &name)? {
+            entries.push(ast::MessageEntry::Option(option));
+        }
+
+        let mut chunks = vec![];
+        let mut oneofs = HashSet::new();
+
+        for (index, field) in message.field.iter().enumerate() {
+            let oneof = field
+                .oneof_index
+                .filter(|_| field.proto3_optional != Some(true));
+
+            let Some(oneof) = oneof else {
+                let entry = ast::MessageEntry::Field(self.field(field, &name)?);
+                chunks.push(self.chunk(
+                    &child(path, path::MESSAGE_FIELD, index),
+                    ast::MessageEntry::Comment,
+                    entry,
+                ));
+                continue;
+            };
+
+            if !oneofs.insert(oneof) {
+                continue;
+            }
+
+            let decl = message.oneof_decl.get(oneof as usize);
+            let mut oneof_entries = vec![];
+
+            if let Some(decl) = decl {
+                for option in self.options(OptionsKind::OneOf, &decl.options, &name)? {
+                    oneof_entries.push(ast::OneOfEntry::Option(option));
+                }
+            }
+
+            for (index, field) in message.field.iter().enumerate() {
+                if field.oneof_index == Some(oneof) && field.proto3_optional != Some(true) {
+                    let entry = ast::OneOfEntry::Field(self.field(field, &name)?);
+                    oneof_entries.extend(
+                        self.chunk(
+                            &child(path, path::MESSAGE_FIELD, index),
+                            ast::OneOfEntry::Comment,
+                            entry,
+                        )
+                        .entries,
+                    );
+                }
+            }
+
+            let entry = ast::MessageEntry::OneOf(ast::OneOf {
+                ident: decl
+                    .and_then(|decl| decl.name.as_deref())
+                    .unwrap_or_default(),
+                entries: oneof_entries,
+            });
+            chunks.push(self.chunk(
+                &child(path, path::MESSAGE_ONEOF, oneof as usize),
+                ast::MessageEntry::Comment,
+                entry,
+            ));
+        }
+
+        for (index, nested) in message.nested_type.iter().enumerate() {
+            if self.is_map_entry(nested) {
+                continue;
+            }
+
+            let nested_path = child(path, path::MESSAGE_NESTED, index);
+            let entry = ast::MessageEntry::Message(self.message(nested, &nested_path, &name)?);
+            chunks.push(self.chunk(&nested_path, ast::MessageEntry::Comment, entry));
+        }
+
+        for (index, r#enum) in message.enum_type.iter().enumerate() {
+            let enum_path = child(path, path::MESSAGE_ENUM, index);
+            let entry = ast::MessageEntry::Enum(self.r#enum(r#enum, &enum_path, &name)?);
+            chunks.push(self.chunk(&enum_path, ast::MessageEntry::Comment, entry));
+        }
+
+        let mut extension_path = path.to_vec();
+        extension_path.push(path::MESSAGE_EXTENSION);
+        for (extend_path, extend) in self.extends(&message.extension, &extension_path, &name)? {
+            chunks.push(self.chunk(
+                &extend_path,
+                ast::MessageEntry::Comment,
+                ast::MessageEntry::Extend(extend),
+            ));
+        }
+
+        if !message.extension_range.is_empty() {
+            let ranges = message
+                .extension_range
+                .iter()
+                .map(|range| message_range(range.start, range.end))
+                .collect();
+
+            chunks.push(self.chunk(
+                &child(path, path::MESSAGE_EXTENSION_RANGE, 0),
+                ast::MessageEntry::Comment,
+                ast::MessageEntry::Extensions(ranges),
+            ));
+        }
+
+        if !message.reserved_range.is_empty() {
+            let ranges = message
+                .reserved_range
+                .iter()
+                .map(|range| message_range(range.start, range.end))
+                .collect();
+
+            chunks.push(self.chunk(
+                &child(path, path::MESSAGE_RESERVED_RANGE, 0),
+                ast::MessageEntry::Comment,
+                ast::MessageEntry::ReservedIndices(ranges),
+            ));
+        }
+
+        if !message.reserved_name.is_empty() {
+            let idents = message
+                .reserved_name
+                .iter()
+                .map(|name| self.decompiler.alloc(escape(name.as_bytes())))
+                .collect();
+
+            chunks.push(self.chunk(
+                &child(path, path::MESSAGE_RESERVED_NAME, 0),
+                ast::MessageEntry::Comment,
+                ast::MessageEntry::ReservedIdents(idents),
+            ));
+        }
+
+        entries.extend(flatten(chunks));
+
+        Ok(ast::Message {
+            ident: message.name(),
+            entries,
+        })
+    }
+
+    fn is_map_entry(&self, message: &DescriptorProto) -> bool {
+        let Some(options) = &message.options else {
+            return false;
+        };
+
+        let mut reader = Reader::new(options);
+        while let Ok(tag) = reader.read_tag() {
+            if tag.number == 7 && tag.wire_type == WireType::Varint {
+                return reader.read_varint().is_ok_and(|value| value != 0);
+            }
+            if reader.skip_field(tag).is_err() {
+                break;
+            }
+        }
+
+        false
+    }
+
+    fn map_entry(&self, field: &FieldDescriptorProto) -> Option<&'a DescriptorProto> {
+        if field.r#type() != Some(FieldType::Message) || field.label() != FieldLabel::Repeated {
+            return None;
+        }
+
+        let type_name = field.type_name.as_deref()?;
+        let message = self
+            .decompiler
+            .messages
+            .get(type_name.strip_prefix('.').unwrap_or(type_name))?;
+
+        self.is_map_entry(message).then_some(*message)
+    }
+
+    fn field_type(&self, field: &'a FieldDescriptorProto, scope: &str) -> &'a str {
+        match field.r#type() {
+            Some(r#type) if r#type.is_scalar() => r#type.name(),
+            _ => self
+                .decompiler
+                .reference(field.type_name.as_deref().unwrap_or_default(), scope),
+        }
+    }
+
+    fn field(
+        &self,
+        field: &'a FieldDescriptorProto,
+        scope: &str,
+    ) -> Result<ast::Field<'a>, DecompileError> {
+        let map_entry = self.map_entry(field);
+
+        let modifier = match field.label() {
+            _ if map_entry.is_some() => ast::FieldModifier::None,
+            FieldLabel::Repeated => ast::FieldModifier::Repeated,
+            FieldLabel::Required => ast::FieldModifier::Required,
+            FieldLabel::Optional if field.proto3_optional == Some(true) => {
+                ast::FieldModifier::Optional
+            }
+            FieldLabel::Optional if self.proto3 => ast::FieldModifier::None,
+            FieldLabel::Optional => ast::FieldModifier::Optional,
+        };
+
+        let r#type = match map_entry {
+            Some(entry) => {
+                let key = entry.field.iter().find(|field| field.number == Some(1));
+                let value = entry.field.iter().find(|field| field.number == Some(2));
+
+                self.decompiler.alloc(format!(
+                    "map<{}, {}>",
+                    key.map_or("", |key| self.field_type(key, scope)),
+                    value.map_or("", |value| self.field_type(value, scope))
+                ))
+            }
+            None => self.field_type(field, scope),
+        };
+
+        let mut options = vec![];
+
+        if let Some(default) = &field.default_value {
+            options.push(ast::Option {
+                key: "default",
+                value: self.default_value(field, default),
+            });
+        }
+
+        if let Some(json_name) = &field.json_name
+            && *json_name != names::json_name(field.name())
+        {
+            options.push(ast::Option {
+                key: "json_name",
+                value: ast::MapValue::String(self.decompiler.alloc(escape(json_name.as_bytes()))),
+            });
+        }
+
+        options.extend(self.options(OptionsKind::Field, &field.options, scope)?);
+
+        Ok(ast::Field {
+            modifier,
+            r#type,
+            ident: field.name(),
+            index: field.number() as i64,
+            options,
+        })
+    }
+
+    fn default_value(&self, field: &FieldDescriptorProto, default: &'a str) -> ast::MapValue<'a> {
+        match field.r#type() {
+            Some(FieldType::String) => {
+                ast::MapValue::String(self.decompiler.alloc(escape(default.as_bytes())))
+            }
+            // already escaped by protoc
+            Some(FieldType::Bytes) => ast::MapValue::String(default),
+            Some(FieldType::Bool) => ast::MapValue::Boolean(default == "true"),
+            Some(FieldType::Float | FieldType::Double) => match default.parse() {
+                Ok(value) => ast::MapValue::Float(value),
+                Err(_) => ast::MapValue::Ident(default),
+            },
+            Some(FieldType::Enum) => ast::MapValue::Ident(default),
+            _ => match default.parse() {
+                Ok(value) => ast::MapValue::Integer(value),
+                Err(_) => ast::MapValue::Ident(default),
+            },
+        }
+    }
+
+    fn r#enum(
+        &self,
+        r#enum: &'a EnumDescriptorProto,
+        path: &[i32],
+        scope: &str,
+    ) -> Result<ast::Enum<'a>, DecompileError> {
+        let mut entries = vec![];
+
+        for option in self.options(OptionsKind::Enum, &r#enum.options, scope)? {
+            entries.push(ast::EnumEntry::Option(option));
+        }
+
+        let mut chunks = vec![];
+
+        for (index, value) in r#enum.value.iter().enumerate() {
+            let entry = ast::EnumEntry::Pair {
+                ident: value.name.as_deref().unwrap_or_default(),
+                value: value.number.unwrap_or_default() as i64,
+                options: self.options(OptionsKind::EnumValue, &value.options, scope)?,
+            };
+
+            chunks.push(self.chunk(
+                &child(path, path::ENUM_VALUE, index),
+                ast::EnumEntry::Comment,
+                entry,
+            ));
+        }
+
+        if !r#enum.reserved_range.is_empty() {
+            let ranges = r#enum
+                .reserved_range
+                .iter()
+                .map(|range| {
+                    let start = range.start.unwrap_or_default() as i64;
+                    ast::Range {
+                        start,
+                        end: match range.end.unwrap_or(range.start.unwrap_or_default()) {
+                            i32::MAX => ast::RangeEnd::Max,
+                            end => ast::RangeEnd::Integer(end as i64 + 1),
+                        },
+                    }
+                })
+                .collect();
+
+            chunks.push(self.chunk(
+                &child(path, path::ENUM_RESERVED_RANGE, 0),
+                ast::EnumEntry::Comment,
+                ast::EnumEntry::ReservedIndices(ranges),
+            ));
+        }
+
+        if !r#enum.reserved_name.is_empty() {
+            let idents = r#enum
+                .reserved_name
+                .iter()
+                .map(|name| self.decompiler.alloc(escape(name.as_bytes())))
+                .collect();
+
+            chunks.push(self.chunk(
+                &child(path, path::ENUM_RESERVED_NAME, 0),
+                ast::EnumEntry::Comment,
+                ast::EnumEntry::ReservedIdents(idents),
+            ));
+        }
+
+        entries.extend(flatten(chunks));
+
+        Ok(ast::Enum {
+            ident: r#enum.name(),
+            entries,
+        })
+    }
+
+    fn service(
+        &self,
+        service: &'a ServiceDescriptorProto,
+        path: &[i32],
+        scope: &str,
+    ) -> Result<ast::Service<'a>, DecompileError> {
+        let mut entries = vec![];
+
+        for option in self.options(OptionsKind::Service, &service.options, scope)? {
+            entries.push(ast::ServiceEntry::Option(option));
+        }
+
+        for (index, method) in service.method.iter().enumerate() {
+            let rpc = ast::Rpc {
+                ident: method.name.as_deref().unwrap_or_default(),
+                request: self
+                    .decompiler
+                    .reference(method.input_type.as_deref().unwrap_or_default(), scope),
+                reply: self
+                    .decompiler
+                    .reference(method.output_type.as_deref().unwrap_or_default(), scope),
+                stream: ast::RpcStream::new(
+                    method.client_streaming == Some(true),
+                    method.server_streaming == Some(true),
+                ),
+                entries: self
+                    .options(OptionsKind::Method, &method.options, scope)?
+                    .into_iter()
+                    .map(ast::RpcEntry::Option)
+                    .collect(),
+            };
+
+            entries.extend(
+                self.chunk(
+                    &child(path, path::SERVICE_METHOD, index),
+                    ast::ServiceEntry::Comment,
+                    ast::ServiceEntry::Rpc(rpc),
+                )
+                .entries,
+            );
+        }
+
+        Ok(ast::Service {
+            ident: service.name(),
+            entries,
+        })
+    }
+
+    fn options(
+        &self,
+        kind: OptionsKind,
+        options: &'a Option<Vec<u8>>,
+        scope: &str,
+    ) -> Result<Vec<ast::Option<'a>>, DecompileError> {
+        let Some(data) = options else {
+            return Ok(vec![]);
+        };
+
+        let mut result = vec![];
+        let mut reader = Reader::new(data);
+
+        while !reader.is_empty() {
+            let tag = reader.read_tag().map_err(|error| self.error(error))?;
+
+            if let Some(standard) = kind.by_number(tag.number) {
+                let values = self
+                    .standard_option(standard.r#type, tag, &mut reader)
+                    .map_err(|error| self.error(error))?;
+
+                // implied by the map<K, V> syntax
+                if standard.name == "map_entry" {
+                    continue;
+                }
+
+                result.extend(values.into_iter().map(|value| ast::Option {
+                    key: standard.name,
+                    value,
+                }));
+                continue;
+            }
+
+            let extension = self
+                .decompiler
+                .extensions
+                .get(&(kind.extendee().to_string(), tag.number as i32));
+
+            let Some((name, extension)) = extension else {
+                // an extension from a file that isn't in the set, there's no name for it
+                reader.skip_field(tag).map_err(|error| self.error(error))?;
+                continue;
+            };
+
+            let short = names::shortest(name, scope, |name| self.decompiler.names.contains(name));
+            let key = self.decompiler.alloc(format!("({})", short));
+
+            let values = self
+                .field_values(extension, tag, &mut reader)
+                .map_err(|error| self.error(error))?;
+
+            result.extend(values.into_iter().map(|value| ast::Option { key, value }));
+        }
+
+        Ok(result)
+    }
+
+    fn standard_option(
+        &self,
+        r#type: StandardOptionType,
+        tag: Tag,
+        reader: &mut Reader<'a>,
+    ) -> Result<Vec<ast::MapValue<'a>>, DecodeError> {
+        let to_value = |value: u64| match r#type {
+            StandardOptionType::Bool => ast::MapValue::Boolean(value != 0),
+            _ => match r#type.enum_name(value as i32) {
+                Some(name) => ast::MapValue::Ident(name),
+                None => ast::MapValue::Integer(value as i32 as i64),
+            },
+        };
+
+        match (r#type, tag.wire_type) {
+            (StandardOptionType::String, _) => {
+                reader.expect_wire_type(tag, WireType::LengthDelimited)?;
+                let value = reader.read_length_delimited()?;
+                Ok(vec![ast::MapValue::String(
+                    self.decompiler.alloc(escape(value)),
+                )])
+            }
+            (_, WireType::LengthDelimited) => {
+                let data = reader.read_length_delimited()?;
+                let mut packed = Reader::with_offset(data, reader.position() - data.len());
+
+                let mut values = vec![];
+                while !packed.is_empty() {
+                    values.push(to_value(packed.read_varint()?));
+                }
+                Ok(values)
+            }
+            _ => {
+                reader.expect_wire_type(tag, WireType::Varint)?;
+                Ok(vec![to_value(reader.read_varint()?)])
+            }
+        }
+    }
+
+    fn field_values(
+        &self,
+        field: &'a FieldDescriptorProto,
+        tag: Tag,
+        reader: &mut Reader<'a>,
+    ) -> Result<Vec<ast::MapValue<'a>>, DecodeError> {
+        let r#type = field.r#type().unwrap_or(FieldType::Message);
+
+        if tag.wire_type == WireType::LengthDelimited && r#type.is_packable() {
+            let data = reader.read_length_delimited()?;
+            let mut packed = Reader::with_offset(data, reader.position() - data.len());
+
+            let wire_type = match r#type {
+                FieldType::Fixed32 | FieldType::Sfixed32 | FieldType::Float => WireType::Fixed32,
+                FieldType::Fixed64 | FieldType::Sfixed64 | FieldType::Double => WireType::Fixed64,
+                _ => WireType::Varint,
+            };
+
+            let mut values = vec![];
+            while !packed.is_empty() {
+                values.push(self.field_value(
+                    field,
+                    Tag::new(tag.number, wire_type),
+                    &mut packed,
+                )?);
+            }
+            return Ok(values);
+        }
+
+        Ok(vec![self.field_value(field, tag, reader)?])
+    }
+
+    fn field_value(
+        &self,
+        field: &'a FieldDescriptorProto,
+        tag: Tag,
+        reader: &mut Reader<'a>,
+    ) -> Result<ast::MapValue<'a>, DecodeError> {
+        let r#type = field.r#type().unwrap_or(FieldType::Message);
+
+        let expected = match r#type {
+            FieldType::Fixed32 | FieldType::Sfixed32 | FieldType::Float => WireType::Fixed32,
+            FieldType::Fixed64 | FieldType::Sfixed64 | FieldType::Double => WireType::Fixed64,
+            FieldType::String | FieldType::Bytes | FieldType::Message => WireType::LengthDelimited,
+            FieldType::Group => WireType::StartGroup,
+            _ => WireType::Varint,
+        };
+        reader.expect_wire_type(tag, expected)?;
+
+        let value = match r#type {
+            FieldType::Double => ast::MapValue::Float(f64::from_bits(reader.read_fixed64()?)),
+            FieldType::Float => ast::MapValue::Float(f32::from_bits(reader.read_fixed32()?) as f64),
+            FieldType::Fixed32 => ast::MapValue::Integer(reader.read_fixed32()? as i64),
+            FieldType::Sfixed32 => ast::MapValue::Integer(reader.read_fixed32()? as i32 as i64),
+            FieldType::Sfixed64 => ast::MapValue::Integer(reader.read_fixed64()? as i64),
+            FieldType::Fixed64 => unsigned(self, reader.read_fixed64()?),
+            FieldType::Uint64 => unsigned(self, reader.read_varint()?),
+            FieldType::Int64 => ast::MapValue::Integer(reader.read_varint()? as i64),
+            FieldType::Int32 => ast::MapValue::Integer(reader.read_varint()? as i32 as i64),
+            FieldType::Uint32 => ast::MapValue::Integer(reader.read_varint()? as u32 as i64),
+            FieldType::Sint32 => {
+                ast::MapValue::Integer(wire::decode_zigzag32(reader.read_varint()? as u32) as i64)
+            }
+            FieldType::Sint64 => {
+                ast::MapValue::Integer(wire::decode_zigzag64(reader.read_varint()?))
+            }
+            FieldType::Bool => ast::MapValue::Boolean(reader.read_varint()? != 0),
+            FieldType::Enum => {
+                let value = reader.read_varint()? as i32;
+                let name = self
+                    .enum_type(field)
+                    .and_then(|r#enum| r#enum.value.iter().find(|pair| pair.number == Some(value)))
+                    .and_then(|pair| pair.name.as_deref());
+
+                match name {
+                    Some(name) => ast::MapValue::Ident(name),
+                    None => ast::MapValue::Integer(value as i64),
+                }
+            }
+            FieldType::String | FieldType::Bytes => ast::MapValue::String(
+                self.decompiler
+                    .alloc(escape(reader.read_length_delimited()?)),
+            ),
+            FieldType::Message | FieldType::Group => {
+                let (data, offset) = if r#type == FieldType::Group {
+                    let offset = reader.position();
+                    (reader.read_group(tag.number)?, offset)
+                } else {
+                    let data = reader.read_length_delimited()?;
+                    (data, reader.position() - data.len())
+                };
+
+                self.message_value(field, &mut Reader::with_offset(data, offset))?
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn enum_type(&self, field: &FieldDescriptorProto) -> Option<&'a EnumDescriptorProto> {
+        let type_name = field.type_name.as_deref()?;
+        self.decompiler
+            .enums
+            .get(type_name.strip_prefix('.').unwrap_or(type_name))
+            .copied()
+    }
+
+    // Aggregate option values, with an entry per value of repeated fields.
+    fn message_value(
+        &self,
+        field: &FieldDescriptorProto,
+        reader: &mut Reader<'a>,
+    ) -> Result<ast::MapValue<'a>, DecodeError> {
+        let message = field.type_name.as_deref().and_then(|type_name| {
+            self.decompiler
+                .messages
+                .get(type_name.strip_prefix('.').unwrap_or(type_name))
+        });
+
+        let mut map = ast::JSONLikeMap::new();
+
+        while !reader.is_empty() {
+            let tag = reader.read_tag()?;
+            let field = message.and_then(|message| {
+                message
+                    .field
+                    .iter()
+                    .find(|field| field.number() == tag.number as i32)
+            });
+
+            let Some(field) = field else {
+                reader.skip_field(tag)?;
+                continue;
+            };
+
+            for value in self.field_values(field, tag, reader)? {
+                map.push((field.name(), value));
+            }
+        }
+
+        Ok(ast::MapValue::Map(map))
+    }
+}
+
+fn unsigned<'a>(context: &FileContext<'a, '_>, value: u64) -> ast::MapValue<'a> {
+    match i64::try_from(value) {
+        Ok(value) => ast::MapValue::Integer(value),
+        Err(_) => ast::MapValue::Ident(context.decompiler.alloc(value.to_string())),
+    }
+}
+
+fn message_range(start: Option<i32>, end: Option<i32>) -> ast::Range {
+    let start = start.unwrap_or_default() as i64;
+
+    ast::Range {
+        start,
+        end: match end {
+            Some(end) if end <= MAX_FIELD_NUMBER => ast::RangeEnd::Integer(end as i64),
+            _ => ast::RangeEnd::Max,
+        },
+    }
+}
@@ -0,0 +1,123 @@
+// C-style escaping used by string literals in .proto files.
+
+pub fn escape(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len());
+    let text = String::from_utf8_lossy(data);
+
+    // valid UTF-8 is kept as is, everything else is escaped byte by byte
+    if text.as_bytes() == data {
+        for ch in text.chars() {
+            match ch {
+                '\n' => result.push_str("\\n"),
+                '\r' => result.push_str("\\r"),
+                '\t' => result.push_str("\\t"),
+                '"' => result.push_str("\\\""),
+                '\'' => result.push_str("\\'"),
+                '\\' => result.push_str("\\\\"),
+                ch if (ch as u32) < 0x20 || ch as u32 == 0x7F => {
+                    result.push_str(&format!("\\{:03o}", ch as u32))
+                }
+                ch => result.push(ch),
+            }
+        }
+        return result;
+    }
+
+    for &byte in data {
+        match byte {
+            b'\n' => result.push_str("\\n"),
+            b'\r' => result.push_str("\\r"),
+            b'\t' => result.push_str("\\t"),
+            b'"' => result.push_str("\\\""),
+            b'\'' => result.push_str("\\'"),
+            b'\\' => result.push_str("\\\\"),
+            0x20..=0x7E => result.push(byte as char),
+            byte => result.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+
+    result
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnescapeError {
+    pub position: usize,
+}
+
+impl std::fmt::Display for UnescapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Invalid escape sequence at {}", self.position)
+    }
+}
+
+impl std::error::Error for UnescapeError {}
+
+pub fn unescape(text: &str) -> Result<Vec<u8>, UnescapeError> {
+    let bytes = text.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    let digits = |start: usize, radix: u32, max: usize| {
+        bytes[start..]
+            .iter()
+            .take(max)
+            .take_while(|byte| (**byte as char).is_digit(radix))
+            .count()
+    };
+
+    while index < bytes.len() {
+        if bytes[index] != b'\\' {
+            result.push(bytes[index]);
+            index += 1;
+            continue;
+        }
+
+        let error = UnescapeError { position: index };
+        let Some(&escape) = bytes.get(index + 1) else {
+            return Err(error);
+        };
+        index += 2;
+
+        match escape {
+            b'n' => result.push(b'\n'),
+            b'r' => result.push(b'\r'),
+            b't' => result.push(b'\t'),
+            b'a' => result.push(0x07),
+            b'b' => result.push(0x08),
+            b'f' => result.push(0x0C),
+            b'v' => result.push(0x0B),
+            b'?' => result.push(b'?'),
+            b'\\' | b'\'' | b'"' => result.push(escape),
+            b'0'..=b'7' => {
+                let len = 1 + digits(index, 8, 2);
+                let value = u32::from_str_radix(&text[index - 1..index - 1 + len], 8).unwrap();
+                result.push(u8::try_from(value).map_err(|_| error)?);
+                index += len - 1;
+            }
+            b'x' | b'X' => {
+                let len = digits(index, 16, 2);
+                if len == 0 {
+                    return Err(error);
+                }
+                result.push(u8::from_str_radix(&text[index..index + len], 16).unwrap());
+                index += len;
+            }
+            b'u' | b'U' => {
+                let len = if escape == b'u' { 4 } else { 8 };
+                if digits(index, 16, len) != len {
+                    return Err(error);
+                }
+
+                let ch = u32::from_str_radix(&text[index..index + len], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(error)?;
+                result.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                index += len;
+            }
+            _ => return Err(error),
+        }
+    }
+
+    Ok(result)
+}
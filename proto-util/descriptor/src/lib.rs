@@ -0,0 +1,417 @@
+pub mod case;
+mod codec;
+mod decompile;
+mod escape;
+mod model;
+pub mod names;
+pub mod options;
+mod pool;
+
+pub use decompile::{DecompileError, DecompiledFile, Decompiler, decompile};
+pub use escape::{UnescapeError, escape, unescape};
+pub use model::*;
+pub use pool::{
+    EnumType, EnumValueInfo, FieldInfo, MessageType, MethodInfo, OneofInfo, Pool, ServiceType,
+};
+pub use typed_arena::Arena;
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Arena, DescriptorProto, EnumDescriptorProto, EnumReservedRange, EnumValueDescriptorProto,
+        ExtensionRange, FieldDescriptorProto, FieldLabel, FieldType, FileDescriptorProto,
+        FileDescriptorSet, Location, Message, MethodDescriptorProto, OneofDescriptorProto,
+        ReservedRange, ServiceDescriptorProto, SourceCodeInfo,
+    };
+    use parser::{lexer, proto};
+    use wire::{Tag, WireType, Writer};
+
+    fn field(
+        name: &str,
+        number: i32,
+        label: FieldLabel,
+        r#type: FieldType,
+    ) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(label.as_i32()),
+            r#type: Some(r#type.as_i32()),
+            json_name: Some(crate::names::json_name(name)),
+            ..Default::default()
+        }
+    }
+
+    fn typed(mut field: FieldDescriptorProto, type_name: &str) -> FieldDescriptorProto {
+        field.type_name = Some(type_name.to_string());
+        field
+    }
+
+    fn location(
+        path: &[i32],
+        line: i32,
+        leading: Option<&str>,
+        trailing: Option<&str>,
+    ) -> Location {
+        Location {
+            path: path.to_vec(),
+            span: vec![line, 0, 1],
+            leading_comments: leading.map(str::to_string),
+            trailing_comments: trailing.map(str::to_string),
+            leading_detached_comments: vec![],
+        }
+    }
+
+    fn options(build: impl FnOnce(&mut Writer)) -> Option<Vec<u8>> {
+        let mut writer = Writer::new();
+        build(&mut writer);
+        Some(writer.into_inner())
+    }
+
+    fn proto3_file() -> FileDescriptorProto {
+        use FieldLabel::*;
+
+        let mut age = field("age", 2, Optional, FieldType::Int32);
+        age.proto3_optional = Some(true);
+        age.oneof_index = Some(1);
+
+        let mut email = field("email", 5, Optional, FieldType::String);
+        email.oneof_index = Some(0);
+
+        let mut phone = field("phone", 6, Optional, FieldType::String);
+        phone.oneof_index = Some(0);
+        phone.json_name = Some("tel".to_string());
+        phone.options = options(|writer| {
+            writer.write_tag(Tag::new(50000, WireType::LengthDelimited));
+            writer.write_length_delimited(b"Phone \"number\"");
+        });
+
+        let addresses_entry = DescriptorProto {
+            name: Some("AddressesEntry".to_string()),
+            field: vec![
+                field("key", 1, Optional, FieldType::String),
+                typed(
+                    field("value", 2, Optional, FieldType::Message),
+                    ".test.pkg.User.Address",
+                ),
+            ],
+            options: options(|writer| {
+                writer.write_tag(Tag::new(7, WireType::Varint));
+                writer.write_varint(1);
+            }),
+            ..Default::default()
+        };
+
+        let user = DescriptorProto {
+            name: Some("User".to_string()),
+            field: vec![
+                field("name", 1, Optional, FieldType::String),
+                age,
+                typed(
+                    field("roles", 3, Repeated, FieldType::Enum),
+                    ".test.pkg.User.Role",
+                ),
+                typed(
+                    field("addresses", 4, Repeated, FieldType::Message),
+                    ".test.pkg.User.AddressesEntry",
+                ),
+                email,
+                phone,
+                typed(
+                    field("friend", 7, Optional, FieldType::Message),
+                    ".test.pkg.User",
+                ),
+            ],
+            nested_type: vec![
+                DescriptorProto {
+                    name: Some("Address".to_string()),
+                    field: vec![field("city", 1, Optional, FieldType::String)],
+                    ..Default::default()
+                },
+                addresses_entry,
+            ],
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Role".to_string()),
+                value: vec![
+                    EnumValueDescriptorProto {
+                        name: Some("ROLE_UNSPECIFIED".to_string()),
+                        number: Some(0),
+                        options: None,
+                    },
+                    EnumValueDescriptorProto {
+                        name: Some("ROLE_ADMIN".to_string()),
+                        number: Some(1),
+                        options: options(|writer| {
+                            writer.write_tag(Tag::new(1, WireType::Varint));
+                            writer.write_varint(1);
+                        }),
+                    },
+                ],
+                reserved_range: vec![EnumReservedRange {
+                    start: Some(5),
+                    end: Some(10),
+                }],
+                reserved_name: vec!["OLD".to_string()],
+                ..Default::default()
+            }],
+            oneof_decl: vec![
+                OneofDescriptorProto {
+                    name: Some("contact".to_string()),
+                    options: None,
+                },
+                OneofDescriptorProto {
+                    name: Some("_age".to_string()),
+                    options: None,
+                },
+            ],
+            reserved_range: vec![
+                ReservedRange {
+                    start: Some(8),
+                    end: Some(9),
+                },
+                ReservedRange {
+                    start: Some(10),
+                    end: Some(13),
+                },
+            ],
+            reserved_name: vec!["legacy".to_string()],
+            ..Default::default()
+        };
+
+        let mut label = field("label", 50000, Optional, FieldType::String);
+        label.extendee = Some(".google.protobuf.FieldOptions".to_string());
+
+        FileDescriptorProto {
+            name: Some("test/user.proto".to_string()),
+            package: Some("test.pkg".to_string()),
+            dependency: vec!["google/protobuf/descriptor.proto".to_string()],
+            message_type: vec![user],
+            service: vec![ServiceDescriptorProto {
+                name: Some("UserService".to_string()),
+                method: vec![
+                    MethodDescriptorProto {
+                        name: Some("Get".to_string()),
+                        input_type: Some(".test.pkg.User".to_string()),
+                        output_type: Some(".test.pkg.User".to_string()),
+                        ..Default::default()
+                    },
+                    MethodDescriptorProto {
+                        name: Some("Watch".to_string()),
+                        input_type: Some(".test.pkg.User".to_string()),
+                        output_type: Some(".test.pkg.User".to_string()),
+                        server_streaming: Some(true),
+                        options: options(|writer| {
+                            writer.write_tag(Tag::new(33, WireType::Varint));
+                            writer.write_varint(1);
+                        }),
+                        ..Default::default()
+                    },
+                ],
+                options: None,
+            }],
+            extension: vec![label],
+            options: options(|writer| {
+                writer.write_tag(Tag::new(1, WireType::LengthDelimited));
+                writer.write_length_delimited(b"com.example.test");
+                writer.write_tag(Tag::new(9, WireType::Varint));
+                writer.write_varint(2);
+            }),
+            source_code_info: Some(SourceCodeInfo {
+                location: vec![
+                    location(&[12], 0, None, None),
+                    location(
+                        &[4, 0],
+                        8,
+                        Some(" A user of the system.\n Second line.\n"),
+                        None,
+                    ),
+                    location(&[4, 0, 2, 0], 9, None, Some(" display name\n")),
+                    Location {
+                        leading_detached_comments: vec![" Services\n".to_string()],
+                        ..location(&[6, 0], 30, None, None)
+                    },
+                    location(&[7, 0], 4, None, None),
+                ],
+            }),
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn proto2_file() -> FileDescriptorProto {
+        use FieldLabel::*;
+
+        let mut ratio = field("ratio", 1, Optional, FieldType::Double);
+        ratio.default_value = Some("0.5".to_string());
+
+        let mut title = field("title", 2, Required, FieldType::String);
+        title.default_value = Some("say \"hi\"\n".to_string());
+
+        let mut kind = typed(
+            field("kind", 3, Optional, FieldType::Enum),
+            ".test.pkg.User.Role",
+        );
+        kind.default_value = Some("ROLE_ADMIN".to_string());
+
+        let mut values = field("values", 4, Repeated, FieldType::Int32);
+        values.options = options(|writer| {
+            writer.write_tag(Tag::new(2, WireType::Varint));
+            writer.write_varint(1);
+        });
+
+        FileDescriptorProto {
+            name: Some("test/legacy.proto".to_string()),
+            package: Some("test.legacy".to_string()),
+            dependency: vec!["test/user.proto".to_string()],
+            public_dependency: vec![0],
+            message_type: vec![DescriptorProto {
+                name: Some("Legacy".to_string()),
+                field: vec![
+                    ratio,
+                    title,
+                    kind,
+                    values,
+                    typed(
+                        field("user", 5, Optional, FieldType::Message),
+                        ".test.pkg.User",
+                    ),
+                ],
+                extension_range: vec![
+                    ExtensionRange {
+                        start: Some(100),
+                        end: Some(200),
+                        options: None,
+                    },
+                    ExtensionRange {
+                        start: Some(1000),
+                        end: Some(536870912),
+                        options: None,
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn decompile_set(set: FileDescriptorSet) -> Vec<(String, String)> {
+        // everything goes through the binary encoding first
+        let set = FileDescriptorSet::decode(&set.encode()).unwrap();
+        let arena = Arena::new();
+
+        crate::decompile(&set, &arena)
+            .unwrap()
+            .into_iter()
+            .map(|file| {
+                let source = formatter::format(&file.ast);
+
+                proto::FileParser::new()
+                    .parse(&source, lexer::Lexer::new(&source))
+                    .unwrap_or_else(|error| panic!("{}: {}\n{}", file.name, error, source));
+
+                (file.name.to_string(), source)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let set = FileDescriptorSet {
+            file: vec![proto3_file(), proto2_file()],
+        };
+
+        assert_eq!(FileDescriptorSet::decode(&set.encode()).unwrap(), set);
+    }
+
+    #[test]
+    fn decompile() {
+        let files = decompile_set(FileDescriptorSet {
+            file: vec![proto3_file(), proto2_file()],
+        });
+
+        assert_eq!(files[0].0, "test/user.proto");
+        assert_eq!(
+            files[0].1,
+            include_str!("../../../test-data/descriptor/user.proto")
+        );
+
+        assert_eq!(files[1].0, "test/legacy.proto");
+        assert_eq!(
+            files[1].1,
+            include_str!("../../../test-data/descriptor/legacy.proto")
+        );
+    }
+
+    #[test]
+    fn unknown_syntax() {
+        let set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("editions.proto".to_string()),
+                syntax: Some("editions".to_string()),
+                ..Default::default()
+            }],
+        };
+
+        let arena = Arena::new();
+        let error = crate::decompile(&set, &arena).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "editions.proto: syntax \"editions\" is not supported"
+        );
+    }
+
+    #[test]
+    fn shortest_names() {
+        let names = [
+            "a",
+            "a.b",
+            "a.b.Msg",
+            "a.b.Outer",
+            "a.b.Outer.Msg",
+            "c",
+            "c.Msg",
+        ]
+        .into_iter()
+        .map(str::to_string)
+        .collect::<std::collections::HashSet<_>>();
+        let is_defined = |name: &str| names.contains(name);
+
+        assert_eq!(crate::names::shortest("a.b.Msg", "a.b", is_defined), "Msg");
+        assert_eq!(
+            crate::names::shortest("a.b.Msg", "a.b.Outer", is_defined),
+            "b.Msg"
+        );
+        assert_eq!(crate::names::shortest("c.Msg", "a.b", is_defined), "c.Msg");
+        assert_eq!(
+            crate::names::shortest("a.b.Outer.Msg", "c", is_defined),
+            "a.b.Outer.Msg"
+        );
+        assert_eq!(crate::names::json_name("foo_bar_baz"), "fooBarBaz");
+    }
+
+    #[test]
+    fn naming() {
+        use crate::case;
+
+        assert!(case::is_pascal_case("HttpServer"));
+        assert!(!case::is_pascal_case("Http_Server"));
+        assert!(case::is_lower_snake_case("unit_price2"));
+        assert!(!case::is_lower_snake_case("unit__price"));
+        assert!(case::is_upper_snake_case("SHIPPING_AIR"));
+        assert!(!case::is_upper_snake_case("SHIPPING_air"));
+
+        assert_eq!(case::pascal_case("variant_info"), "VariantInfo");
+        assert_eq!(case::lower_snake_case("HTTPServer2Id"), "http_server2_id");
+        assert_eq!(case::upper_snake_case("unitPrice"), "UNIT_PRICE");
+        assert_eq!(case::lower_snake_case("first-name"), "first_name");
+        assert_eq!(case::pascal_case("shipping.address"), "ShippingAddress");
+    }
+
+    #[test]
+    fn escapes() {
+        let text = crate::escape(b"a\"b\\\n\x01\xFF");
+        assert_eq!(text, "a\\\"b\\\\\\n\\001\\377");
+        assert_eq!(crate::unescape(&text).unwrap(), b"a\"b\\\n\x01\xFF");
+        assert_eq!(crate::unescape("\\x41\\u00e9").unwrap(), "Aé".as_bytes());
+    }
+}
@@ -0,0 +1,395 @@
+// Subset of google/protobuf/descriptor.proto and google/protobuf/compiler/plugin.proto.
+// Options are kept as raw bytes and interpreted by the `options` module.
+
+pub use wire::Message;
+use wire::{DecodeError, Reader, Tag, Writer};
+
+macro_rules! message {
+    (
+        pub struct $name:ident {
+            $($number:literal => $field:ident: $kind:ident $(($inner:ty))?,)*
+        }
+    ) => {
+        #[derive(Debug, Default, Clone, PartialEq)]
+        pub struct $name {
+            $(pub $field: message!(@type $kind $(($inner))?),)*
+        }
+
+        impl Message for $name {
+            fn merge_field(&mut self, tag: Tag, reader: &mut Reader) -> Result<(), DecodeError> {
+                match tag.number {
+                    $($number => crate::codec::$kind::merge(&mut self.$field, tag, reader),)*
+                    _ => reader.skip_field(tag).map(|_| ()),
+                }
+            }
+
+            fn encode_to(&self, writer: &mut Writer) {
+                $(crate::codec::$kind::encode(&self.$field, $number, writer);)*
+            }
+        }
+    };
+    (@type string) => { Option<String> };
+    (@type bytes) => { Option<Vec<u8>> };
+    (@type int32) => { Option<i32> };
+    (@type uint64) => { Option<u64> };
+    (@type boolean) => { Option<bool> };
+    (@type message($inner:ty)) => { Option<$inner> };
+    (@type repeated_string) => { Vec<String> };
+    (@type repeated_message($inner:ty)) => { Vec<$inner> };
+    (@type repeated_int32) => { Vec<i32> };
+    (@type packed_int32) => { Vec<i32> };
+}
+
+message! {
+    pub struct FileDescriptorSet {
+        1 => file: repeated_message(FileDescriptorProto),
+    }
+}
+
+message! {
+    pub struct FileDescriptorProto {
+        1 => name: string,
+        2 => package: string,
+        3 => dependency: repeated_string,
+        10 => public_dependency: repeated_int32,
+        11 => weak_dependency: repeated_int32,
+        4 => message_type: repeated_message(DescriptorProto),
+        5 => enum_type: repeated_message(EnumDescriptorProto),
+        6 => service: repeated_message(ServiceDescriptorProto),
+        7 => extension: repeated_message(FieldDescriptorProto),
+        8 => options: bytes,
+        9 => source_code_info: message(SourceCodeInfo),
+        12 => syntax: string,
+        14 => edition: int32,
+    }
+}
+
+message! {
+    pub struct DescriptorProto {
+        1 => name: string,
+        2 => field: repeated_message(FieldDescriptorProto),
+        6 => extension: repeated_message(FieldDescriptorProto),
+        3 => nested_type: repeated_message(DescriptorProto),
+        4 => enum_type: repeated_message(EnumDescriptorProto),
+        5 => extension_range: repeated_message(ExtensionRange),
+        8 => oneof_decl: repeated_message(OneofDescriptorProto),
+        7 => options: bytes,
+        9 => reserved_range: repeated_message(ReservedRange),
+        10 => reserved_name: repeated_string,
+    }
+}
+
+message! {
+    pub struct ExtensionRange {
+        1 => start: int32,
+        2 => end: int32,
+        3 => options: bytes,
+    }
+}
+
+message! {
+    pub struct ReservedRange {
+        1 => start: int32,
+        2 => end: int32,
+    }
+}
+
+message! {
+    pub struct FieldDescriptorProto {
+        1 => name: string,
+        3 => number: int32,
+        4 => label: int32,
+        5 => r#type: int32,
+        6 => type_name: string,
+        2 => extendee: string,
+        7 => default_value: string,
+        9 => oneof_index: int32,
+        10 => json_name: string,
+        8 => options: bytes,
+        17 => proto3_optional: boolean,
+    }
+}
+
+message! {
+    pub struct OneofDescriptorProto {
+        1 => name: string,
+        2 => options: bytes,
+    }
+}
+
+message! {
+    pub struct EnumDescriptorProto {
+        1 => name: string,
+        2 => value: repeated_message(EnumValueDescriptorProto),
+        3 => options: bytes,
+        4 => reserved_range: repeated_message(EnumReservedRange),
+        5 => reserved_name: repeated_string,
+    }
+}
+
+// Unlike `ReservedRange`, the end is inclusive.
+message! {
+    pub struct EnumReservedRange {
+        1 => start: int32,
+        2 => end: int32,
+    }
+}
+
+message! {
+    pub struct EnumValueDescriptorProto {
+        1 => name: string,
+        2 => number: int32,
+        3 => options: bytes,
+    }
+}
+
+message! {
+    pub struct ServiceDescriptorProto {
+        1 => name: string,
+        2 => method: repeated_message(MethodDescriptorProto),
+        3 => options: bytes,
+    }
+}
+
+message! {
+    pub struct MethodDescriptorProto {
+        1 => name: string,
+        2 => input_type: string,
+        3 => output_type: string,
+        4 => options: bytes,
+        5 => client_streaming: boolean,
+        6 => server_streaming: boolean,
+    }
+}
+
+message! {
+    pub struct SourceCodeInfo {
+        1 => location: repeated_message(Location),
+    }
+}
+
+message! {
+    pub struct Location {
+        1 => path: packed_int32,
+        2 => span: packed_int32,
+        3 => leading_comments: string,
+        4 => trailing_comments: string,
+        6 => leading_detached_comments: repeated_string,
+    }
+}
+
+message! {
+    pub struct Version {
+        1 => major: int32,
+        2 => minor: int32,
+        3 => patch: int32,
+        4 => suffix: string,
+    }
+}
+
+message! {
+    pub struct CodeGeneratorRequest {
+        1 => file_to_generate: repeated_string,
+        2 => parameter: string,
+        15 => proto_file: repeated_message(FileDescriptorProto),
+        17 => source_file_descriptors: repeated_message(FileDescriptorProto),
+        3 => compiler_version: message(Version),
+    }
+}
+
+message! {
+    pub struct CodeGeneratorResponse {
+        1 => error: string,
+        2 => supported_features: uint64,
+        3 => minimum_edition: int32,
+        4 => maximum_edition: int32,
+        15 => file: repeated_message(CodeGeneratorResponseFile),
+    }
+}
+
+// CodeGeneratorResponse.File
+message! {
+    pub struct CodeGeneratorResponseFile {
+        1 => name: string,
+        2 => insertion_point: string,
+        15 => content: string,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Double,
+    Float,
+    Int64,
+    Uint64,
+    Int32,
+    Fixed64,
+    Fixed32,
+    Bool,
+    String,
+    Group,
+    Message,
+    Bytes,
+    Uint32,
+    Enum,
+    Sfixed32,
+    Sfixed64,
+    Sint32,
+    Sint64,
+}
+
+impl FieldType {
+    const ALL: [FieldType; 18] = [
+        Self::Double,
+        Self::Float,
+        Self::Int64,
+        Self::Uint64,
+        Self::Int32,
+        Self::Fixed64,
+        Self::Fixed32,
+        Self::Bool,
+        Self::String,
+        Self::Group,
+        Self::Message,
+        Self::Bytes,
+        Self::Uint32,
+        Self::Enum,
+        Self::Sfixed32,
+        Self::Sfixed64,
+        Self::Sint32,
+        Self::Sint64,
+    ];
+
+    pub fn from_i32(value: i32) -> Option<Self> {
+        Self::ALL.get((value as usize).wrapping_sub(1)).copied()
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        Self::ALL.iter().position(|r#type| r#type == self).unwrap() as i32 + 1
+    }
+
+    // Scalar types by their keyword in .proto files.
+    pub fn from_scalar_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|r#type| r#type.is_scalar() && r#type.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Double => "double",
+            Self::Float => "float",
+            Self::Int64 => "int64",
+            Self::Uint64 => "uint64",
+            Self::Int32 => "int32",
+            Self::Fixed64 => "fixed64",
+            Self::Fixed32 => "fixed32",
+            Self::Bool => "bool",
+            Self::String => "string",
+            Self::Group => "group",
+            Self::Message => "message",
+            Self::Bytes => "bytes",
+            Self::Uint32 => "uint32",
+            Self::Enum => "enum",
+            Self::Sfixed32 => "sfixed32",
+            Self::Sfixed64 => "sfixed64",
+            Self::Sint32 => "sint32",
+            Self::Sint64 => "sint64",
+        }
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        !matches!(self, Self::Group | Self::Message | Self::Enum)
+    }
+
+    pub fn is_packable(&self) -> bool {
+        !matches!(
+            self,
+            Self::String | Self::Bytes | Self::Group | Self::Message
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldLabel {
+    Optional,
+    Required,
+    Repeated,
+}
+
+impl FieldLabel {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            1 => Some(Self::Optional),
+            2 => Some(Self::Required),
+            3 => Some(Self::Repeated),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            Self::Optional => 1,
+            Self::Required => 2,
+            Self::Repeated => 3,
+        }
+    }
+}
+
+impl FieldDescriptorProto {
+    pub fn r#type(&self) -> Option<FieldType> {
+        self.r#type.and_then(FieldType::from_i32)
+    }
+
+    pub fn label(&self) -> FieldLabel {
+        self.label
+            .and_then(FieldLabel::from_i32)
+            .unwrap_or(FieldLabel::Optional)
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or_default()
+    }
+
+    pub fn number(&self) -> i32 {
+        self.number.unwrap_or_default()
+    }
+}
+
+impl DescriptorProto {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or_default()
+    }
+}
+
+impl EnumDescriptorProto {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or_default()
+    }
+}
+
+impl ServiceDescriptorProto {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or_default()
+    }
+}
+
+impl FileDescriptorProto {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or_default()
+    }
+
+    pub fn package(&self) -> &str {
+        self.package.as_deref().unwrap_or_default()
+    }
+}
+
+impl CodeGeneratorResponse {
+    pub const FEATURE_PROTO3_OPTIONAL: u64 = 1;
+    pub const FEATURE_SUPPORTS_EDITIONS: u64 = 2;
+
+    pub fn supports(&self, feature: u64) -> bool {
+        self.supported_features.unwrap_or_default() & feature != 0
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+
+// Same as protoc's ToJsonName: underscores are dropped and the next letter is capitalized.
+pub fn json_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut capitalize = false;
+
+    for ch in name.chars() {
+        if ch == '_' {
+            capitalize = true;
+        } else if capitalize {
+            result.extend(ch.to_uppercase());
+            capitalize = false;
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
+}
+
+// Same as protoc's MapEntryName: "foo_bar" becomes "FooBarEntry".
+pub fn map_entry_name(field: &str) -> String {
+    let mut result = String::with_capacity(field.len() + 5);
+    let mut capitalize = true;
+
+    for ch in field.chars() {
+        if ch == '_' {
+            capitalize = true;
+        } else if capitalize {
+            result.push(ch.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result.push_str("Entry");
+    result
+}
+
+pub fn parent(name: &str) -> &str {
+    name.rfind('.').map_or("", |index| &name[..index])
+}
+
+pub fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+// Every package component is a name too, e.g. "google" and "google.protobuf".
+pub fn insert_package(names: &mut HashSet<String>, package: &str) {
+    let mut end = 0;
+
+    for component in package.split('.').filter(|component| !component.is_empty()) {
+        end += component.len();
+        names.insert(package[..end].to_string());
+        end += 1;
+    }
+}
+
+// Resolves a (possibly relative) reference the way protoc does: the first component is looked up
+// from the innermost scope outwards, the rest must exist under it.
+pub fn resolve(reference: &str, scope: &str, is_defined: impl Fn(&str) -> bool) -> Option<String> {
+    if let Some(absolute) = reference.strip_prefix('.') {
+        return is_defined(absolute).then(|| absolute.to_string());
+    }
+
+    let first = reference.split('.').next().unwrap_or(reference);
+    let mut scope = scope;
+
+    loop {
+        let candidate = join(scope, first);
+
+        if is_defined(&candidate) {
+            let full = join(scope, reference);
+            return is_defined(&full).then_some(full);
+        }
+
+        if scope.is_empty() {
+            return None;
+        }
+
+        scope = parent(scope);
+    }
+}
+
+// Shortest reference that resolves to `target` from `scope`; falls back to the absolute form.
+pub fn shortest(target: &str, scope: &str, is_defined: impl Fn(&str) -> bool) -> String {
+    let mut start = target.len();
+
+    loop {
+        start = target[..start].rfind('.').unwrap_or(0);
+
+        let candidate = if start == 0 {
+            target
+        } else {
+            &target[start + 1..]
+        };
+
+        if resolve(candidate, scope, &is_defined).as_deref() == Some(target) {
+            return candidate.to_string();
+        }
+
+        if start == 0 {
+            return format!(".{}", target);
+        }
+    }
+}
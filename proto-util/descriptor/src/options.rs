@@ -0,0 +1,201 @@
+// Options defined by google/protobuf/descriptor.proto itself. Everything else in an options
+// message is a custom option, i.e. an extension of the options message.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptionsKind {
+    File,
+    Message,
+    Field,
+    OneOf,
+    Enum,
+    EnumValue,
+    Service,
+    Method,
+    ExtensionRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StandardOptionType {
+    Bool,
+    String,
+    Enum(&'static [(i32, &'static str)]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StandardOption {
+    pub number: u32,
+    pub name: &'static str,
+    pub r#type: StandardOptionType,
+    pub repeated: bool,
+}
+
+const fn option(number: u32, name: &'static str, r#type: StandardOptionType) -> StandardOption {
+    StandardOption {
+        number,
+        name,
+        r#type,
+        repeated: false,
+    }
+}
+
+use StandardOptionType::{Bool, String};
+
+const OPTIMIZE_MODE: &[(i32, &str)] = &[(1, "SPEED"), (2, "CODE_SIZE"), (3, "LITE_RUNTIME")];
+const C_TYPE: &[(i32, &str)] = &[(0, "STRING"), (1, "CORD"), (2, "STRING_PIECE")];
+const JS_TYPE: &[(i32, &str)] = &[(0, "JS_NORMAL"), (1, "JS_STRING"), (2, "JS_NUMBER")];
+const RETENTION: &[(i32, &str)] = &[
+    (0, "RETENTION_UNKNOWN"),
+    (1, "RETENTION_RUNTIME"),
+    (2, "RETENTION_SOURCE"),
+];
+const TARGET_TYPE: &[(i32, &str)] = &[
+    (0, "TARGET_TYPE_UNKNOWN"),
+    (1, "TARGET_TYPE_FILE"),
+    (2, "TARGET_TYPE_EXTENSION_RANGE"),
+    (3, "TARGET_TYPE_MESSAGE"),
+    (4, "TARGET_TYPE_FIELD"),
+    (5, "TARGET_TYPE_ONEOF"),
+    (6, "TARGET_TYPE_ENUM"),
+    (7, "TARGET_TYPE_ENUM_ENTRY"),
+    (8, "TARGET_TYPE_SERVICE"),
+    (9, "TARGET_TYPE_METHOD"),
+];
+const IDEMPOTENCY_LEVEL: &[(i32, &str)] = &[
+    (0, "IDEMPOTENCY_UNKNOWN"),
+    (1, "NO_SIDE_EFFECTS"),
+    (2, "IDEMPOTENT"),
+];
+
+const FILE_OPTIONS: &[StandardOption] = &[
+    option(1, "java_package", String),
+    option(8, "java_outer_classname", String),
+    option(10, "java_multiple_files", Bool),
+    option(20, "java_generate_equals_and_hash", Bool),
+    option(27, "java_string_check_utf8", Bool),
+    option(9, "optimize_for", StandardOptionType::Enum(OPTIMIZE_MODE)),
+    option(11, "go_package", String),
+    option(16, "cc_generic_services", Bool),
+    option(17, "java_generic_services", Bool),
+    option(18, "py_generic_services", Bool),
+    option(23, "deprecated", Bool),
+    option(31, "cc_enable_arenas", Bool),
+    option(36, "objc_class_prefix", String),
+    option(37, "csharp_namespace", String),
+    option(39, "swift_prefix", String),
+    option(40, "php_class_prefix", String),
+    option(41, "php_namespace", String),
+    option(44, "php_metadata_namespace", String),
+    option(45, "ruby_package", String),
+];
+
+const MESSAGE_OPTIONS: &[StandardOption] = &[
+    option(1, "message_set_wire_format", Bool),
+    option(2, "no_standard_descriptor_accessor", Bool),
+    option(3, "deprecated", Bool),
+    option(7, "map_entry", Bool),
+    option(11, "deprecated_legacy_json_field_conflicts", Bool),
+];
+
+const FIELD_OPTIONS: &[StandardOption] = &[
+    option(1, "ctype", StandardOptionType::Enum(C_TYPE)),
+    option(2, "packed", Bool),
+    option(6, "jstype", StandardOptionType::Enum(JS_TYPE)),
+    option(5, "lazy", Bool),
+    option(15, "unverified_lazy", Bool),
+    option(3, "deprecated", Bool),
+    option(10, "weak", Bool),
+    option(16, "debug_redact", Bool),
+    option(17, "retention", StandardOptionType::Enum(RETENTION)),
+    StandardOption {
+        number: 19,
+        name: "targets",
+        r#type: StandardOptionType::Enum(TARGET_TYPE),
+        repeated: true,
+    },
+];
+
+const ENUM_OPTIONS: &[StandardOption] = &[
+    option(2, "allow_alias", Bool),
+    option(3, "deprecated", Bool),
+    option(6, "deprecated_legacy_json_field_conflicts", Bool),
+];
+
+const ENUM_VALUE_OPTIONS: &[StandardOption] = &[
+    option(1, "deprecated", Bool),
+    option(3, "debug_redact", Bool),
+];
+
+const SERVICE_OPTIONS: &[StandardOption] = &[option(33, "deprecated", Bool)];
+
+const METHOD_OPTIONS: &[StandardOption] = &[
+    option(33, "deprecated", Bool),
+    option(
+        34,
+        "idempotency_level",
+        StandardOptionType::Enum(IDEMPOTENCY_LEVEL),
+    ),
+];
+
+impl OptionsKind {
+    pub fn extendee(&self) -> &'static str {
+        match self {
+            Self::File => "google.protobuf.FileOptions",
+            Self::Message => "google.protobuf.MessageOptions",
+            Self::Field => "google.protobuf.FieldOptions",
+            Self::OneOf => "google.protobuf.OneofOptions",
+            Self::Enum => "google.protobuf.EnumOptions",
+            Self::EnumValue => "google.protobuf.EnumValueOptions",
+            Self::Service => "google.protobuf.ServiceOptions",
+            Self::Method => "google.protobuf.MethodOptions",
+            Self::ExtensionRange => "google.protobuf.ExtensionRangeOptions",
+        }
+    }
+
+    pub fn standard(&self) -> &'static [StandardOption] {
+        match self {
+            Self::File => FILE_OPTIONS,
+            Self::Message => MESSAGE_OPTIONS,
+            Self::Field => FIELD_OPTIONS,
+            Self::Enum => ENUM_OPTIONS,
+            Self::EnumValue => ENUM_VALUE_OPTIONS,
+            Self::Service => SERVICE_OPTIONS,
+            Self::Method => METHOD_OPTIONS,
+            Self::OneOf | Self::ExtensionRange => &[],
+        }
+    }
+
+    pub fn by_number(&self, number: u32) -> Option<&'static StandardOption> {
+        self.standard()
+            .iter()
+            .find(|option| option.number == number)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&'static StandardOption> {
+        self.standard().iter().find(|option| option.name == name)
+    }
+}
+
+impl StandardOptionType {
+    pub fn enum_name(&self, value: i32) -> Option<&'static str> {
+        match self {
+            Self::Enum(values) => values
+                .iter()
+                .find(|(number, _)| *number == value)
+                .map(|(_, name)| *name),
+            _ => None,
+        }
+    }
+
+    pub fn enum_value(&self, name: &str) -> Option<i32> {
+        match self {
+            Self::Enum(values) => values
+                .iter()
+                .find(|(_, value_name)| *value_name == name)
+                .map(|(number, _)| *number),
+            _ => None,
+        }
+    }
+}
+
+// Option "names" that are not options at all, but fields of the descriptor itself.
+pub const FIELD_PSEUDO_OPTIONS: &[&str] = &["default", "json_name"];
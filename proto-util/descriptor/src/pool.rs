@@ -0,0 +1,378 @@
+// Index of the types of a FileDescriptorSet by their fully-qualified names (without the leading
+// dot), with the parts of the options that affect encoding already interpreted.
+
+use crate::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FieldLabel, FieldType,
+    FileDescriptorSet, ServiceDescriptorProto,
+};
+use std::collections::HashMap;
+use wire::{Reader, WireType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInfo {
+    pub name: String,
+    // Fully-qualified name for extensions, same as `name` otherwise.
+    pub full_name: String,
+    pub number: u32,
+    pub label: FieldLabel,
+    pub r#type: FieldType,
+    pub type_name: Option<String>,
+    pub extendee: Option<String>,
+    pub json_name: String,
+    pub default_value: Option<String>,
+    pub oneof: Option<usize>,
+    pub proto3_optional: bool,
+    pub packed: bool,
+    pub options: Option<Vec<u8>>,
+}
+
+impl FieldInfo {
+    pub fn is_repeated(&self) -> bool {
+        self.label == FieldLabel::Repeated
+    }
+
+    pub fn is_extension(&self) -> bool {
+        self.extendee.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OneofInfo {
+    pub name: String,
+    // Oneofs generated for proto3 `optional` fields.
+    pub synthetic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageType {
+    pub name: String,
+    pub file: String,
+    pub proto3: bool,
+    pub fields: Vec<FieldInfo>,
+    pub oneofs: Vec<OneofInfo>,
+    pub map_entry: bool,
+    pub options: Option<Vec<u8>>,
+}
+
+impl MessageType {
+    pub fn field(&self, number: u32) -> Option<&FieldInfo> {
+        self.fields.iter().find(|field| field.number == number)
+    }
+
+    pub fn field_by_name(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn field_by_json_name(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields.iter().find(|field| field.json_name == name)
+    }
+
+    pub fn oneof_fields(&self, index: usize) -> impl Iterator<Item = &FieldInfo> {
+        self.fields
+            .iter()
+            .filter(move |field| field.oneof == Some(index))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValueInfo {
+    pub name: String,
+    pub number: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumType {
+    pub name: String,
+    pub file: String,
+    // proto2 enums reject unknown values.
+    pub closed: bool,
+    pub values: Vec<EnumValueInfo>,
+}
+
+impl EnumType {
+    pub fn value(&self, number: i32) -> Option<&EnumValueInfo> {
+        self.values.iter().find(|value| value.number == number)
+    }
+
+    pub fn value_by_name(&self, name: &str) -> Option<&EnumValueInfo> {
+        self.values.iter().find(|value| value.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodInfo {
+    pub name: String,
+    pub input_type: String,
+    pub output_type: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+    pub options: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceType {
+    pub name: String,
+    pub file: String,
+    pub methods: Vec<MethodInfo>,
+    pub options: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Pool {
+    messages: HashMap<String, MessageType>,
+    enums: HashMap<String, EnumType>,
+    services: HashMap<String, ServiceType>,
+    extensions: HashMap<String, FieldInfo>,
+    extensions_by_number: HashMap<(String, u32), String>,
+}
+
+fn strip(name: &Option<String>) -> Option<String> {
+    name.as_deref()
+        .map(|name| name.strip_prefix('.').unwrap_or(name).to_string())
+}
+
+// Reads a bool field of an options message, the last occurrence wins.
+fn bool_option(options: &Option<Vec<u8>>, number: u32) -> Option<bool> {
+    let mut reader = Reader::new(options.as_deref()?);
+    let mut result = None;
+
+    while !reader.is_empty() {
+        let tag = reader.read_tag().ok()?;
+
+        if tag.number == number && tag.wire_type == WireType::Varint {
+            result = Some(reader.read_varint().ok()? != 0);
+        } else {
+            reader.skip_field(tag).ok()?;
+        }
+    }
+
+    result
+}
+
+impl Pool {
+    pub fn new(set: &FileDescriptorSet) -> Self {
+        let mut pool = Self::default();
+
+        for file in &set.file {
+            let proto3 = file.syntax.as_deref() == Some("proto3");
+            let context = Context {
+                file: file.name(),
+                proto3,
+            };
+
+            for message in &file.message_type {
+                pool.add_message(&context, file.package(), message);
+            }
+
+            for r#enum in &file.enum_type {
+                pool.add_enum(&context, file.package(), r#enum);
+            }
+
+            for service in &file.service {
+                pool.add_service(&context, file.package(), service);
+            }
+
+            for extension in &file.extension {
+                pool.add_extension(&context, file.package(), extension);
+            }
+        }
+
+        pool
+    }
+
+    fn add_message(&mut self, context: &Context, scope: &str, message: &DescriptorProto) {
+        let name = crate::names::join(scope, message.name());
+
+        let fields = message
+            .field
+            .iter()
+            .map(|field| context.field(field, field.name().to_string()))
+            .collect();
+
+        let oneofs = message
+            .oneof_decl
+            .iter()
+            .enumerate()
+            .map(|(index, oneof)| OneofInfo {
+                name: oneof.name.clone().unwrap_or_default(),
+                synthetic: message.field.iter().any(|field| {
+                    field.oneof_index == Some(index as i32) && field.proto3_optional == Some(true)
+                }),
+            })
+            .collect();
+
+        for nested in &message.nested_type {
+            self.add_message(context, &name, nested);
+        }
+
+        for r#enum in &message.enum_type {
+            self.add_enum(context, &name, r#enum);
+        }
+
+        for extension in &message.extension {
+            self.add_extension(context, &name, extension);
+        }
+
+        self.messages.insert(
+            name.clone(),
+            MessageType {
+                name,
+                file: context.file.to_string(),
+                proto3: context.proto3,
+                fields,
+                oneofs,
+                map_entry: bool_option(&message.options, 7).unwrap_or(false),
+                options: message.options.clone(),
+            },
+        );
+    }
+
+    fn add_enum(&mut self, context: &Context, scope: &str, r#enum: &EnumDescriptorProto) {
+        let name = crate::names::join(scope, r#enum.name());
+
+        let values = r#enum
+            .value
+            .iter()
+            .map(|value| EnumValueInfo {
+                name: value.name.clone().unwrap_or_default(),
+                number: value.number.unwrap_or_default(),
+            })
+            .collect();
+
+        self.enums.insert(
+            name.clone(),
+            EnumType {
+                name,
+                file: context.file.to_string(),
+                closed: !context.proto3,
+                values,
+            },
+        );
+    }
+
+    fn add_service(&mut self, context: &Context, scope: &str, service: &ServiceDescriptorProto) {
+        let name = crate::names::join(scope, service.name());
+
+        let methods = service
+            .method
+            .iter()
+            .map(|method| MethodInfo {
+                name: method.name.clone().unwrap_or_default(),
+                input_type: strip(&method.input_type).unwrap_or_default(),
+                output_type: strip(&method.output_type).unwrap_or_default(),
+                client_streaming: method.client_streaming.unwrap_or(false),
+                server_streaming: method.server_streaming.unwrap_or(false),
+                options: method.options.clone(),
+            })
+            .collect();
+
+        self.services.insert(
+            name.clone(),
+            ServiceType {
+                name,
+                file: context.file.to_string(),
+                methods,
+                options: service.options.clone(),
+            },
+        );
+    }
+
+    fn add_extension(&mut self, context: &Context, scope: &str, extension: &FieldDescriptorProto) {
+        let name = crate::names::join(scope, extension.name());
+        let info = context.field(extension, name.clone());
+
+        if let Some(extendee) = &info.extendee {
+            self.extensions_by_number
+                .insert((extendee.clone(), info.number), name.clone());
+        }
+
+        self.extensions.insert(name, info);
+    }
+
+    pub fn message(&self, name: &str) -> Option<&MessageType> {
+        self.messages.get(name.strip_prefix('.').unwrap_or(name))
+    }
+
+    pub fn r#enum(&self, name: &str) -> Option<&EnumType> {
+        self.enums.get(name.strip_prefix('.').unwrap_or(name))
+    }
+
+    pub fn service(&self, name: &str) -> Option<&ServiceType> {
+        self.services.get(name.strip_prefix('.').unwrap_or(name))
+    }
+
+    pub fn extension(&self, name: &str) -> Option<&FieldInfo> {
+        self.extensions.get(name.strip_prefix('.').unwrap_or(name))
+    }
+
+    pub fn extension_by_number(&self, extendee: &str, number: u32) -> Option<&FieldInfo> {
+        let extendee = extendee.strip_prefix('.').unwrap_or(extendee);
+
+        self.extensions_by_number
+            .get(&(extendee.to_string(), number))
+            .and_then(|name| self.extensions.get(name))
+    }
+
+    pub fn extensions_of<'s>(&'s self, extendee: &'s str) -> impl Iterator<Item = &'s FieldInfo> {
+        self.extensions
+            .values()
+            .filter(move |field| field.extendee.as_deref() == Some(extendee))
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = &MessageType> {
+        self.messages.values()
+    }
+
+    pub fn enums(&self) -> impl Iterator<Item = &EnumType> {
+        self.enums.values()
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = &ServiceType> {
+        self.services.values()
+    }
+
+    // Map fields are repeated fields of a generated `map_entry` message.
+    pub fn map_entry(&self, field: &FieldInfo) -> Option<&MessageType> {
+        if !field.is_repeated() || field.r#type != FieldType::Message {
+            return None;
+        }
+
+        self.message(field.type_name.as_deref()?)
+            .filter(|message| message.map_entry)
+    }
+}
+
+struct Context<'s> {
+    file: &'s str,
+    proto3: bool,
+}
+
+impl Context<'_> {
+    fn field(&self, field: &FieldDescriptorProto, full_name: String) -> FieldInfo {
+        let label = field.label();
+        let r#type = field.r#type().unwrap_or(FieldType::Message);
+
+        let packed = label == FieldLabel::Repeated
+            && r#type.is_packable()
+            && bool_option(&field.options, 2).unwrap_or(self.proto3);
+
+        FieldInfo {
+            name: field.name().to_string(),
+            full_name,
+            number: field.number() as u32,
+            label,
+            r#type,
+            type_name: strip(&field.type_name),
+            extendee: strip(&field.extendee),
+            json_name: field
+                .json_name
+                .clone()
+                .unwrap_or_else(|| crate::names::json_name(field.name())),
+            default_value: field.default_value.clone(),
+            oneof: field.oneof_index.map(|index| index as usize),
+            proto3_optional: field.proto3_optional.unwrap_or(false),
+            packed,
+            options: field.options.clone(),
+        }
+    }
+}
@@ -0,0 +1,942 @@
+// Changes between two versions of a schema that break data or code written against the old one.
+// Elements are matched by fully-qualified name, fields and enum values by number first.
+
+use descriptor::{
+    DescriptorProto, EnumDescriptorProto, EnumType, FieldInfo, FieldLabel, FieldType,
+    FileDescriptorSet, MessageType, Pool, ServiceType, names,
+};
+use resolver::{CompileError, Workspace};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    // Binary data written with the old schema, and the RPCs calling the old services.
+    Wire,
+    // JSON written with the old schema.
+    Json,
+    // Code generated from the old schema.
+    Source,
+}
+
+impl Level {
+    pub const ALL: [Level; 3] = [Self::Wire, Self::Json, Self::Source];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Wire => "WIRE",
+            Self::Json => "JSON",
+            Self::Source => "SOURCE",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    PackageChanged {
+        old: String,
+        new: String,
+    },
+    // A top-level definition now lives in another file of the same package.
+    FileChanged {
+        name: String,
+        old: String,
+    },
+    MessageRemoved(String),
+    EnumRemoved(String),
+    ServiceRemoved(String),
+    ExtensionRemoved(String),
+    FieldRemoved {
+        message: String,
+        name: String,
+        number: u32,
+    },
+    FieldNumberChanged {
+        message: String,
+        name: String,
+        old: u32,
+        new: u32,
+    },
+    FieldRenamed {
+        message: String,
+        number: u32,
+        old: String,
+        new: String,
+    },
+    JsonNameChanged {
+        message: String,
+        name: String,
+        old: String,
+        new: String,
+    },
+    FieldTypeChanged {
+        message: String,
+        name: String,
+        old: String,
+        new: String,
+    },
+    CardinalityChanged {
+        message: String,
+        name: String,
+        old: &'static str,
+        new: &'static str,
+    },
+    OneofChanged {
+        message: String,
+        name: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    EnumValueRemoved {
+        r#enum: String,
+        name: String,
+        number: i32,
+    },
+    EnumValueRenamed {
+        r#enum: String,
+        number: i32,
+        old: String,
+        new: String,
+    },
+    EnumValueNumberChanged {
+        r#enum: String,
+        name: String,
+        old: i32,
+        new: i32,
+    },
+    MethodRemoved {
+        service: String,
+        name: String,
+    },
+    // `request` tells whether it is the request or the response that changed.
+    MethodTypeChanged {
+        service: String,
+        name: String,
+        request: bool,
+        old: String,
+        new: String,
+    },
+    StreamingChanged {
+        service: String,
+        name: String,
+        request: bool,
+        streaming: bool,
+    },
+}
+
+fn oneof_name(oneof: &Option<String>) -> String {
+    match oneof {
+        Some(name) => format!("oneof {}", name),
+        None => "no oneof".to_string(),
+    }
+}
+
+fn side(request: bool) -> &'static str {
+    match request {
+        true => "request",
+        false => "response",
+    }
+}
+
+impl std::fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PackageChanged { old, new } => {
+                write!(f, "package changed from \"{}\" to \"{}\"", old, new)
+            }
+            Self::FileChanged { name, old } => write!(f, "{} was moved here from {}", name, old),
+            Self::MessageRemoved(name) => write!(f, "message {} was removed", name),
+            Self::EnumRemoved(name) => write!(f, "enum {} was removed", name),
+            Self::ServiceRemoved(name) => write!(f, "service {} was removed", name),
+            Self::ExtensionRemoved(name) => write!(f, "extension {} was removed", name),
+            Self::FieldRemoved {
+                message,
+                name,
+                number,
+            } => write!(f, "field {} = {} of {} was removed", name, number, message),
+            Self::FieldNumberChanged {
+                message,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "field {} of {} changed its number from {} to {}",
+                name, message, old, new
+            ),
+            Self::FieldRenamed {
+                message,
+                number,
+                old,
+                new,
+            } => write!(
+                f,
+                "field {} of {} was renamed from {} to {}",
+                number, message, old, new
+            ),
+            Self::JsonNameChanged {
+                message,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "JSON name of field {} of {} changed from \"{}\" to \"{}\"",
+                name, message, old, new
+            ),
+            Self::FieldTypeChanged {
+                message,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "field {} of {} changed its type from {} to {}",
+                name, message, old, new
+            ),
+            Self::CardinalityChanged {
+                message,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "field {} of {} changed from {} to {}",
+                name, message, old, new
+            ),
+            Self::OneofChanged {
+                message,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "field {} of {} moved from {} to {}",
+                name,
+                message,
+                oneof_name(old),
+                oneof_name(new)
+            ),
+            Self::EnumValueRemoved {
+                r#enum,
+                name,
+                number,
+            } => write!(f, "value {} = {} of {} was removed", name, number, r#enum),
+            Self::EnumValueRenamed {
+                r#enum,
+                number,
+                old,
+                new,
+            } => write!(
+                f,
+                "value {} of {} was renamed from {} to {}",
+                number, r#enum, old, new
+            ),
+            Self::EnumValueNumberChanged {
+                r#enum,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "value {} of {} changed its number from {} to {}",
+                name, r#enum, old, new
+            ),
+            Self::MethodRemoved { service, name } => {
+                write!(f, "rpc {} of {} was removed", name, service)
+            }
+            Self::MethodTypeChanged {
+                service,
+                name,
+                request,
+                old,
+                new,
+            } => write!(
+                f,
+                "{} of rpc {} of {} changed from {} to {}",
+                side(*request),
+                name,
+                service,
+                old,
+                new
+            ),
+            Self::StreamingChanged {
+                service,
+                name,
+                request,
+                streaming,
+            } => write!(
+                f,
+                "{} of rpc {} of {} is {} streaming",
+                side(*request),
+                name,
+                service,
+                if *streaming { "now" } else { "no longer" }
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    // File of the element in the new schema, or in the old one if it was removed.
+    pub file: String,
+    pub kind: ChangeKind,
+    // Compatibilities broken by the change, most severe first.
+    pub breaks: Vec<Level>,
+}
+
+impl Change {
+    pub fn breaks(&self, level: Level) -> bool {
+        self.breaks.contains(&level)
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let levels: Vec<_> = self.breaks.iter().map(Level::name).collect();
+
+        write!(f, "{}: {} [{}]", self.file, self.kind, levels.join(", "))
+    }
+}
+
+// Reserved numbers (as inclusive ranges) and names of a message or an enum.
+#[derive(Debug, Default)]
+struct Reserved {
+    ranges: Vec<(i32, i32)>,
+    names: Vec<String>,
+}
+
+impl Reserved {
+    fn number(&self, number: i32) -> bool {
+        self.ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&number))
+    }
+
+    fn name(&self, name: &str) -> bool {
+        self.names.iter().any(|reserved| reserved == name)
+    }
+}
+
+// What the pool doesn't keep: the reservations, and the file every name is defined in.
+#[derive(Debug, Default)]
+struct Index {
+    reserved: HashMap<String, Reserved>,
+    files: HashMap<String, String>,
+    packages: HashMap<String, String>,
+}
+
+impl Index {
+    fn new(set: &FileDescriptorSet) -> Self {
+        let mut index = Self::default();
+
+        for file in &set.file {
+            index
+                .packages
+                .insert(file.name().to_string(), file.package().to_string());
+
+            for message in &file.message_type {
+                index.message(file.name(), file.package(), message);
+            }
+
+            for r#enum in &file.enum_type {
+                index.r#enum(file.name(), file.package(), r#enum);
+            }
+
+            let names = file.service.iter().map(|service| service.name());
+            let names = names.chain(file.extension.iter().map(|extension| extension.name()));
+
+            for name in names {
+                index
+                    .files
+                    .insert(names::join(file.package(), name), file.name().to_string());
+            }
+        }
+
+        index
+    }
+
+    fn message(&mut self, file: &str, scope: &str, message: &DescriptorProto) {
+        let name = names::join(scope, message.name());
+
+        for nested in &message.nested_type {
+            self.message(file, &name, nested);
+        }
+
+        for r#enum in &message.enum_type {
+            self.r#enum(file, &name, r#enum);
+        }
+
+        for extension in &message.extension {
+            self.files
+                .insert(names::join(&name, extension.name()), file.to_string());
+        }
+
+        let reserved = Reserved {
+            ranges: message
+                .reserved_range
+                .iter()
+                .map(|range| (range.start.unwrap_or(0), range.end.unwrap_or(0) - 1))
+                .collect(),
+            names: message.reserved_name.clone(),
+        };
+
+        self.files.insert(name.clone(), file.to_string());
+        self.reserved.insert(name, reserved);
+    }
+
+    fn r#enum(&mut self, file: &str, scope: &str, r#enum: &EnumDescriptorProto) {
+        let name = names::join(scope, r#enum.name());
+
+        let reserved = Reserved {
+            ranges: r#enum
+                .reserved_range
+                .iter()
+                .map(|range| (range.start.unwrap_or(0), range.end.unwrap_or(0)))
+                .collect(),
+            names: r#enum.reserved_name.clone(),
+        };
+
+        self.files.insert(name.clone(), file.to_string());
+        self.reserved.insert(name, reserved);
+    }
+}
+
+fn levels(levels: impl IntoIterator<Item = (Level, bool)>) -> Vec<Level> {
+    levels
+        .into_iter()
+        .filter_map(|(level, broken)| broken.then_some(level))
+        .collect()
+}
+
+// Types sharing an encoding on the wire, as listed in the language guide.
+fn wire_class(r#type: FieldType) -> u8 {
+    use FieldType::*;
+
+    match r#type {
+        Int32 | Uint32 | Int64 | Uint64 | Bool | Enum => 0,
+        Sint32 | Sint64 => 1,
+        Fixed32 | Sfixed32 => 2,
+        Fixed64 | Sfixed64 => 3,
+        String | Bytes | Message => 4,
+        Float => 5,
+        Double => 6,
+        Group => 7,
+    }
+}
+
+// Types JSON parsers read from the same values: integers are accepted as numbers and strings.
+fn json_class(r#type: FieldType) -> u8 {
+    use FieldType::*;
+
+    match r#type {
+        Int32 | Uint32 | Int64 | Uint64 | Sint32 | Sint64 | Fixed32 | Sfixed32 | Fixed64
+        | Sfixed64 => 0,
+        Float | Double => 1,
+        Bool => 2,
+        String => 3,
+        Bytes => 4,
+        Enum => 5,
+        Message | Group => 6,
+    }
+}
+
+fn change(file: &str, kind: ChangeKind, breaks: Vec<Level>) -> Change {
+    Change {
+        file: file.to_string(),
+        kind,
+        breaks,
+    }
+}
+
+struct Checker {
+    old: Pool,
+    new: Pool,
+    old_index: Index,
+    new_index: Index,
+    // Old and new package of the files that changed it, by file.
+    moved: HashMap<String, (String, String)>,
+}
+
+impl Checker {
+    // Name of an old definition in the new schema, following the package of its file.
+    fn rename(&self, name: &str) -> String {
+        let moved = self
+            .old_index
+            .files
+            .get(name)
+            .and_then(|file| self.moved.get(file));
+
+        match moved {
+            Some((old, new)) if old.is_empty() => names::join(new, name),
+            Some((old, new)) => names::join(new, name[old.len()..].trim_start_matches('.')),
+            None => name.to_string(),
+        }
+    }
+
+    // Whether the definition went away with the message it was nested in.
+    fn parent_removed(&self, name: &str) -> bool {
+        let parent = names::parent(name);
+
+        self.old.message(parent).is_some() && self.new.message(&self.rename(parent)).is_none()
+    }
+
+    fn check_file(&self, changes: &mut Vec<Change>, name: &str, old: &str, new: &str) {
+        let parent = names::parent(name);
+        let nested = self.old.message(parent).is_some();
+
+        if nested || old == new || self.moved.contains_key(old) {
+            return;
+        }
+
+        let kind = ChangeKind::FileChanged {
+            name: name.to_string(),
+            old: old.to_string(),
+        };
+        changes.push(change(new, kind, vec![Level::Source]));
+    }
+
+    // Type of the field as written in .proto files, with the names of the old schema renamed.
+    fn type_of(&self, pool: &Pool, field: &FieldInfo, old: bool) -> String {
+        if let Some(entry) = pool.map_entry(field) {
+            let (Some(key), Some(value)) = (entry.field(1), entry.field(2)) else {
+                return entry.name.clone();
+            };
+
+            return format!(
+                "map<{}, {}>",
+                self.type_of(pool, key, old),
+                self.type_of(pool, value, old)
+            );
+        }
+
+        match &field.type_name {
+            Some(name) if old => self.rename(name),
+            Some(name) => name.clone(),
+            None => field.r#type.name().to_string(),
+        }
+    }
+
+    fn check_messages(&self, changes: &mut Vec<Change>) {
+        let mut messages: Vec<_> = self
+            .old
+            .messages()
+            .filter(|message| !message.map_entry)
+            .collect();
+        messages.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for old in messages {
+            match self.new.message(&self.rename(&old.name)) {
+                Some(new) => {
+                    self.check_file(changes, &old.name, &old.file, &new.file);
+                    self.check_fields(changes, old, new);
+                }
+                None if self.parent_removed(&old.name) => {}
+                None => {
+                    let kind = ChangeKind::MessageRemoved(old.name.clone());
+                    changes.push(change(&old.file, kind, vec![Level::Source]));
+                }
+            }
+        }
+    }
+
+    fn check_fields(&self, changes: &mut Vec<Change>, old: &MessageType, new: &MessageType) {
+        let empty = Reserved::default();
+        let reserved = self.new_index.reserved.get(&new.name).unwrap_or(&empty);
+
+        for field in &old.fields {
+            if let Some(other) = new.field(field.number) {
+                self.compare_field(changes, &new.file, &new.name, (old, field), (new, other));
+                continue;
+            }
+
+            let moved = new
+                .field_by_name(&field.name)
+                .filter(|other| old.field(other.number).is_none());
+
+            if let Some(other) = moved {
+                let kind = ChangeKind::FieldNumberChanged {
+                    message: new.name.clone(),
+                    name: field.name.clone(),
+                    old: field.number,
+                    new: other.number,
+                };
+                changes.push(change(&new.file, kind, vec![Level::Wire]));
+
+                self.compare_field(changes, &new.file, &new.name, (old, field), (new, other));
+                continue;
+            }
+
+            let kind = ChangeKind::FieldRemoved {
+                message: new.name.clone(),
+                name: field.name.clone(),
+                number: field.number,
+            };
+            let breaks = levels([
+                (Level::Wire, !reserved.number(field.number as i32)),
+                (Level::Json, !reserved.name(&field.name)),
+                (Level::Source, true),
+            ]);
+            changes.push(change(&new.file, kind, breaks));
+        }
+    }
+
+    // Compares a field with the one it became, each given with the message containing it.
+    fn compare_field(
+        &self,
+        changes: &mut Vec<Change>,
+        file: &str,
+        message: &str,
+        (old, field): (&MessageType, &FieldInfo),
+        (new, other): (&MessageType, &FieldInfo),
+    ) {
+        let json_changed = field.json_name != other.json_name;
+
+        if field.name != other.name {
+            let kind = ChangeKind::FieldRenamed {
+                message: message.to_string(),
+                number: other.number,
+                old: field.name.clone(),
+                new: other.name.clone(),
+            };
+            let breaks = levels([(Level::Json, json_changed), (Level::Source, true)]);
+            changes.push(change(file, kind, breaks));
+        } else if json_changed {
+            let kind = ChangeKind::JsonNameChanged {
+                message: message.to_string(),
+                name: other.name.clone(),
+                old: field.json_name.clone(),
+                new: other.json_name.clone(),
+            };
+            changes.push(change(file, kind, vec![Level::Json]));
+        }
+
+        let old_type = self.type_of(&self.old, field, true);
+        let new_type = self.type_of(&self.new, other, false);
+        let is_map = old_type.starts_with("map<") || new_type.starts_with("map<");
+
+        if old_type != new_type {
+            let messages = field.r#type == FieldType::Message && other.r#type == FieldType::Message;
+            let wire = is_map || messages || wire_class(field.r#type) != wire_class(other.r#type);
+            // enums are written by name, messages by their fields
+            let json = is_map
+                || json_class(field.r#type) != json_class(other.r#type)
+                || matches!(field.r#type, FieldType::Enum | FieldType::Message);
+
+            let kind = ChangeKind::FieldTypeChanged {
+                message: message.to_string(),
+                name: other.name.clone(),
+                old: old_type,
+                new: new_type,
+            };
+            let breaks = levels([
+                (Level::Wire, wire),
+                (Level::Json, json),
+                (Level::Source, true),
+            ]);
+            changes.push(change(file, kind, breaks));
+        }
+
+        let old_label = cardinality(old, field);
+        let new_label = cardinality(new, other);
+
+        if old_label != new_label && !is_map {
+            let repeated = field.is_repeated() != other.is_repeated();
+            let required =
+                field.label == FieldLabel::Required || other.label == FieldLabel::Required;
+            // parsers of singular fields can't read packed values
+            let packed = repeated && (field.packed || other.packed);
+
+            let kind = ChangeKind::CardinalityChanged {
+                message: message.to_string(),
+                name: other.name.clone(),
+                old: old_label,
+                new: new_label,
+            };
+            let breaks = levels([
+                (Level::Wire, required || packed),
+                (Level::Json, repeated),
+                (Level::Source, true),
+            ]);
+            changes.push(change(file, kind, breaks));
+        }
+
+        let old_oneof = oneof(old, field);
+        let new_oneof = oneof(new, other);
+
+        if old_oneof != new_oneof {
+            // fields joining or leaving a oneof no longer clear the same fields when set
+            let membership = old_oneof.is_none() || new_oneof.is_none();
+
+            let kind = ChangeKind::OneofChanged {
+                message: message.to_string(),
+                name: other.name.clone(),
+                old: old_oneof,
+                new: new_oneof,
+            };
+            let breaks = levels([
+                (Level::Wire, membership),
+                (Level::Json, membership),
+                (Level::Source, true),
+            ]);
+            changes.push(change(file, kind, breaks));
+        }
+    }
+
+    fn check_enums(&self, changes: &mut Vec<Change>) {
+        let mut enums: Vec<_> = self.old.enums().collect();
+        enums.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for old in enums {
+            match self.new.r#enum(&self.rename(&old.name)) {
+                Some(new) => {
+                    self.check_file(changes, &old.name, &old.file, &new.file);
+                    self.check_values(changes, old, new);
+                }
+                None if self.parent_removed(&old.name) => {}
+                None => {
+                    let kind = ChangeKind::EnumRemoved(old.name.clone());
+                    changes.push(change(&old.file, kind, vec![Level::Source]));
+                }
+            }
+        }
+    }
+
+    fn check_values(&self, changes: &mut Vec<Change>, old: &EnumType, new: &EnumType) {
+        let empty = Reserved::default();
+        let reserved = self.new_index.reserved.get(&new.name).unwrap_or(&empty);
+
+        for value in &old.values {
+            let r#enum = new.name.clone();
+
+            if let Some(other) = new.value_by_name(&value.name) {
+                if other.number != value.number {
+                    let kind = ChangeKind::EnumValueNumberChanged {
+                        r#enum,
+                        name: value.name.clone(),
+                        old: value.number,
+                        new: other.number,
+                    };
+                    changes.push(change(&new.file, kind, vec![Level::Wire]));
+                }
+                continue;
+            }
+
+            let renamed = new
+                .value(value.number)
+                .filter(|other| old.value_by_name(&other.name).is_none());
+
+            if let Some(other) = renamed {
+                let kind = ChangeKind::EnumValueRenamed {
+                    r#enum,
+                    number: value.number,
+                    old: value.name.clone(),
+                    new: other.name.clone(),
+                };
+                changes.push(change(&new.file, kind, vec![Level::Json, Level::Source]));
+                continue;
+            }
+
+            // an alias can go as long as another name is left for the number
+            let number_kept = new.value(value.number).is_some() || reserved.number(value.number);
+
+            let kind = ChangeKind::EnumValueRemoved {
+                r#enum,
+                name: value.name.clone(),
+                number: value.number,
+            };
+            let breaks = levels([
+                (Level::Wire, !number_kept),
+                (Level::Json, !reserved.name(&value.name)),
+                (Level::Source, true),
+            ]);
+            changes.push(change(&new.file, kind, breaks));
+        }
+    }
+
+    fn check_services(&self, changes: &mut Vec<Change>) {
+        let mut services: Vec<_> = self.old.services().collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for old in services {
+            match self.new.service(&self.rename(&old.name)) {
+                Some(new) => {
+                    self.check_file(changes, &old.name, &old.file, &new.file);
+                    self.check_methods(changes, old, new);
+                }
+                None => {
+                    let kind = ChangeKind::ServiceRemoved(old.name.clone());
+                    changes.push(change(&old.file, kind, Level::ALL.to_vec()));
+                }
+            }
+        }
+    }
+
+    fn check_methods(&self, changes: &mut Vec<Change>, old: &ServiceType, new: &ServiceType) {
+        let mut push = |kind| changes.push(change(&new.file, kind, Level::ALL.to_vec()));
+
+        for method in &old.methods {
+            let (service, name) = (new.name.clone(), method.name.clone());
+
+            let Some(other) = new.methods.iter().find(|other| other.name == method.name) else {
+                push(ChangeKind::MethodRemoved { service, name });
+                continue;
+            };
+
+            let types = [
+                (true, &method.input_type, &other.input_type),
+                (false, &method.output_type, &other.output_type),
+            ];
+
+            for (request, old_type, new_type) in types {
+                let old_type = self.rename(old_type);
+
+                if old_type != *new_type {
+                    push(ChangeKind::MethodTypeChanged {
+                        service: service.clone(),
+                        name: name.clone(),
+                        request,
+                        old: old_type,
+                        new: new_type.clone(),
+                    });
+                }
+            }
+
+            let streaming = [
+                (true, method.client_streaming, other.client_streaming),
+                (false, method.server_streaming, other.server_streaming),
+            ];
+
+            for (request, old_streaming, new_streaming) in streaming {
+                if old_streaming != new_streaming {
+                    push(ChangeKind::StreamingChanged {
+                        service: service.clone(),
+                        name: name.clone(),
+                        request,
+                        streaming: new_streaming,
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_extensions(&self, changes: &mut Vec<Change>) {
+        let mut extensions: Vec<_> = (self.old_index.files.keys())
+            .filter_map(|name| self.old.extension(name))
+            .collect();
+        extensions.sort_by(|a, b| a.full_name.cmp(&b.full_name));
+
+        for old in extensions {
+            let Some(new) = self.new.extension(&self.rename(&old.full_name)) else {
+                if !self.parent_removed(&old.full_name) {
+                    let kind = ChangeKind::ExtensionRemoved(old.full_name.clone());
+                    let file = &self.old_index.files[&old.full_name];
+                    changes.push(change(file, kind, vec![Level::Json, Level::Source]));
+                }
+                continue;
+            };
+
+            let extendees = (
+                self.old
+                    .message(old.extendee.as_deref().unwrap_or_default()),
+                self.new
+                    .message(new.extendee.as_deref().unwrap_or_default()),
+            );
+
+            if let (Some(old_extendee), Some(new_extendee)) = extendees {
+                let file = &self.new_index.files[&new.full_name];
+                let message = &new_extendee.name;
+
+                self.compare_field(
+                    changes,
+                    file,
+                    message,
+                    (old_extendee, old),
+                    (new_extendee, new),
+                );
+            }
+        }
+    }
+}
+
+fn cardinality(message: &MessageType, field: &FieldInfo) -> &'static str {
+    match field.label {
+        FieldLabel::Repeated => "repeated",
+        FieldLabel::Required => "required",
+        FieldLabel::Optional if message.proto3 && !field.proto3_optional => "singular",
+        FieldLabel::Optional => "optional",
+    }
+}
+
+// Oneof the field is in, not counting the ones of proto3 `optional` fields.
+fn oneof(message: &MessageType, field: &FieldInfo) -> Option<String> {
+    let oneof = message.oneofs.get(field.oneof?)?;
+
+    (!oneof.synthetic).then(|| oneof.name.clone())
+}
+
+// Breaking changes between two sets of compiled files.
+pub fn descriptor_changes(old: &FileDescriptorSet, new: &FileDescriptorSet) -> Vec<Change> {
+    let mut checker = Checker {
+        old: Pool::new(old),
+        new: Pool::new(new),
+        old_index: Index::new(old),
+        new_index: Index::new(new),
+        moved: HashMap::new(),
+    };
+    let mut changes = vec![];
+
+    for file in &old.file {
+        let Some(package) = checker.new_index.packages.get(file.name()) else {
+            continue;
+        };
+
+        if package == file.package() {
+            continue;
+        }
+
+        // the package is part of the path of RPCs and of the type URLs of `Any`s
+        let broken = !file.service.is_empty();
+        let kind = ChangeKind::PackageChanged {
+            old: file.package().to_string(),
+            new: package.clone(),
+        };
+        let breaks = levels([
+            (Level::Wire, broken),
+            (Level::Json, broken),
+            (Level::Source, true),
+        ]);
+
+        changes.push(change(file.name(), kind, breaks));
+        checker.moved.insert(
+            file.name().to_string(),
+            (file.package().to_string(), package.clone()),
+        );
+    }
+
+    checker.check_messages(&mut changes);
+    checker.check_enums(&mut changes);
+    checker.check_services(&mut changes);
+    checker.check_extensions(&mut changes);
+    changes
+}
+
+// Breaking changes between two versions of the files of a workspace.
+pub fn breaking_changes(old: &Workspace, new: &Workspace) -> Result<Vec<Change>, CompileError> {
+    Ok(descriptor_changes(
+        &resolver::compile(old)?,
+        &resolver::compile(new)?,
+    ))
+}
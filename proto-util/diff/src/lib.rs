@@ -0,0 +1,168 @@
+mod breaking;
+mod semantic;
+
+pub use breaking::{Change, ChangeKind, Level, breaking_changes, descriptor_changes};
+pub use semantic::{DiffOptions, Node, Property, Status, print_json, semantic_diff};
+
+#[cfg(test)]
+mod tests {
+    use crate::{DiffOptions, Level, Status};
+    use resolver::{SourceTree, Workspace};
+    use std::path::PathBuf;
+
+    fn load(version: &str) -> SourceTree {
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data/diff")
+            .join(version);
+
+        SourceTree::load(vec![data.clone()], &[data.join("shop.proto")]).unwrap()
+    }
+
+    #[test]
+    fn breaking_changes() {
+        let (old, new) = (load("old"), load("new"));
+        let old = Workspace::new(&old).unwrap();
+        let new = Workspace::new(&new).unwrap();
+        let changes = crate::breaking_changes(&old, &new).unwrap();
+
+        let lines: Vec<_> = changes.iter().map(|change| change.to_string()).collect();
+
+        for expected in [
+            "common.proto: package changed from \"common\" to \"common.v2\" [SOURCE]",
+            "common.proto: field units of common.v2.Money changed its type from int64 to sint64 [WIRE, SOURCE]",
+            "shop.proto: message shop.Discount was removed [SOURCE]",
+            "shop.proto: field 2 of shop.Product was renamed from title to name [JSON, SOURCE]",
+            "shop.proto: field price of shop.Product changed its type from int32 to int64 [SOURCE]",
+            "shop.proto: field tags of shop.Product changed from repeated to singular [JSON, SOURCE]",
+            "shop.proto: field description = 5 of shop.Product was removed [JSON, SOURCE]",
+            "shop.proto: field stock = 6 of shop.Product was removed [WIRE, SOURCE]",
+            "shop.proto: field cost of shop.Product changed its type from common.v2.Money to bytes [JSON, SOURCE]",
+            "shop.proto: field counts of shop.Product changed its type from map<string, int32> to map<string, string> [WIRE, JSON, SOURCE]",
+            "shop.proto: field sku of shop.Product changed its number from 9 to 13 [WIRE]",
+            "shop.proto: field factory of shop.Product moved from oneof origin to no oneof [WIRE, JSON, SOURCE]",
+            "shop.proto: value 1 of shop.Status was renamed from STATUS_ACTIVE to STATUS_VISIBLE [JSON, SOURCE]",
+            "shop.proto: value STATUS_HIDDEN of shop.Status changed its number from 2 to 4 [WIRE]",
+            "shop.proto: value STATUS_ARCHIVED = 3 of shop.Status was removed [JSON, SOURCE]",
+            "shop.proto: request of rpc ListProducts of shop.Catalog is now streaming [WIRE, JSON, SOURCE]",
+            "shop.proto: rpc DeleteProduct of shop.Catalog was removed [WIRE, JSON, SOURCE]",
+        ] {
+            assert!(lines.contains(&expected.to_string()), "{}", expected);
+        }
+        assert_eq!(lines.len(), 18);
+
+        let wire = changes.iter().filter(|change| change.breaks(Level::Wire));
+        assert_eq!(wire.count(), 9);
+        assert!(crate::breaking_changes(&old, &old).unwrap().is_empty());
+
+        // the package is part of the paths of RPCs
+        let mut moved = load("old");
+        let source = moved
+            .file("shop.proto")
+            .unwrap()
+            .source
+            .replace("package shop;", "package store;");
+        moved.add("shop.proto", source);
+        let moved = Workspace::new(&moved).unwrap();
+        let changes = crate::breaking_changes(&old, &moved).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].breaks, Level::ALL);
+    }
+
+    #[test]
+    fn semantic_diff() {
+        let (old, new) = (load("old"), load("new"));
+        let source = |tree: &SourceTree| tree.file("shop.proto").unwrap().source.clone();
+        let (old, new) = (source(&old), source(&new));
+        let (old_file, new_file) = (parser::parse(&old).unwrap(), parser::parse(&new).unwrap());
+
+        let node = crate::semantic_diff(
+            "shop.proto",
+            Some((&old, &old_file)),
+            Some((&new, &new_file)),
+            DiffOptions::default(),
+        )
+        .unwrap();
+        let tree = node.to_string();
+
+        assert!(tree.starts_with(
+            "~ file shop.proto\n  ~ message shop.Product\n      reserved numbers: (none) -> 5\n"
+        ));
+        assert!(tree.contains("    ~ field name = 2\n        name: title -> name\n"));
+        assert!(tree.contains("    ~ field sku = 13\n        number: 9 -> 13\n"));
+        assert!(tree.contains("    ~ field factory = 10\n        oneof: origin -> (none)\n"));
+        assert!(tree.contains("    + field stock_count = 12\n"));
+        assert!(tree.contains("    - field stock = 6\n"));
+        assert!(tree.contains("    ~ value STATUS_HIDDEN = 4\n        number: 2 -> 4\n"));
+        assert!(
+            tree.contains("    ~ rpc ListProducts\n        request: Product -> stream Product\n")
+        );
+        assert!(tree.ends_with("  - message shop.Discount\n    - field percent = 1\n"));
+
+        let json = node.to_json();
+        let removed = &json["children"][2];
+        assert_eq!(removed["name"], "shop.Catalog");
+        assert_eq!(removed["children"][1]["status"], "removed");
+        assert_eq!(
+            json["children"][0]["children"][1]["properties"][0]["new"],
+            "int64"
+        );
+
+        // reordering, reformatting and comments only show up when asked for
+        let old = "syntax = \"proto3\";\n// a user\nmessage User {\n  string name = 1;\n  int32 id = 2;\n}\n";
+        let new = "syntax = \"proto3\";\n\n// the user\nmessage User { int32 id = 2;   string name = 1; }\n";
+        let (old_file, new_file) = (parser::parse(old).unwrap(), parser::parse(new).unwrap());
+        let diff = |options| {
+            crate::semantic_diff(
+                "user.proto",
+                Some((old, &old_file)),
+                Some((new, &new_file)),
+                options,
+            )
+        };
+
+        assert_eq!(diff(DiffOptions::default()), None);
+
+        let node = diff(DiffOptions {
+            comments: true,
+            order: true,
+        })
+        .unwrap();
+        let properties = &node.children[0].properties;
+
+        assert_eq!(properties[0].name, "leading comment");
+        assert_eq!(properties[0].new.as_deref(), Some("the user"));
+        assert_eq!(properties[1].old.as_deref(), Some("name = 1, id = 2"));
+
+        // a comment after a field trails it instead of leading the next one
+        let old = "syntax = \"proto3\";\nmessage User {\n  string name = 1; // full name\n  int32 id = 2;\n}\n";
+        let new = "syntax = \"proto3\";\nmessage User {\n  string name = 1; // display name\n  int32 id = 2;\n}\n";
+        let (old_file, new_file) = (parser::parse(old).unwrap(), parser::parse(new).unwrap());
+        let options = DiffOptions {
+            comments: true,
+            order: false,
+        };
+        let node = crate::semantic_diff(
+            "user.proto",
+            Some((old, &old_file)),
+            Some((new, &new_file)),
+            options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            node.to_string(),
+            "~ file user.proto\n  ~ message User\n    ~ field name = 1\n        trailing comment: full name -> display name\n"
+        );
+
+        let node = crate::semantic_diff(
+            "user.proto",
+            None,
+            Some((new, &new_file)),
+            DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(node.status, Status::Added);
+        assert_eq!(node.children[0].children.len(), 2);
+    }
+}
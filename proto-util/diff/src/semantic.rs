@@ -0,0 +1,617 @@
+// Structural comparison of two versions of a file. Definitions are matched by fully-qualified
+// name, fields by number and enum values by name, so that reordering or reformatting them doesn't
+// show up as a change.
+
+use descriptor::names;
+use editor::{Entry, Layout};
+use parser::ast;
+use serde_json::{Value as Json, json};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        }
+    }
+
+    fn sign(&self) -> char {
+        match self {
+            Self::Added => '+',
+            Self::Removed => '-',
+            Self::Changed => '~',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    // "file", "message", "field", "oneof", "enum", "value", "service", "rpc", "extend", "import"
+    // or "option".
+    pub kind: &'static str,
+    pub name: String,
+    pub status: Status,
+    // Properties that differ; all of them for added and removed elements.
+    pub properties: Vec<Property>,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn to_json(&self) -> Json {
+        let properties: Vec<_> = self
+            .properties
+            .iter()
+            .map(|property| json!({ "name": property.name, "old": property.old, "new": property.new }))
+            .collect();
+
+        json!({
+            "kind": self.kind,
+            "name": self.name,
+            "status": self.status.name(),
+            "properties": properties,
+            "children": self.children.iter().map(Node::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        let indent = "  ".repeat(depth);
+        writeln!(
+            f,
+            "{}{} {} {}",
+            indent,
+            self.status.sign(),
+            self.kind,
+            self.name
+        )?;
+
+        if self.status == Status::Changed {
+            for property in &self.properties {
+                let value = |value: &Option<String>| value.clone().unwrap_or("(none)".to_string());
+
+                writeln!(
+                    f,
+                    "{}    {}: {} -> {}",
+                    indent,
+                    property.name,
+                    value(&property.old),
+                    value(&property.new)
+                )?;
+            }
+        }
+
+        for child in &self.children {
+            child.write(f, depth + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Differences of several files as a JSON array.
+pub fn print_json(nodes: &[Node]) -> String {
+    let json = Json::Array(nodes.iter().map(Node::to_json).collect());
+    serde_json::to_string_pretty(&json).unwrap()
+}
+
+// Indented tree, one element per line prefixed with `+`, `-` or `~`.
+impl std::fmt::Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiffOptions {
+    // Report changed comments: leading, trailing and detached ones, attached the way protoc does.
+    pub comments: bool,
+    // Report elements that were reordered.
+    pub order: bool,
+}
+
+// An element of one of the versions.
+struct Item {
+    kind: &'static str,
+    key: String,
+    // Matched by this when nothing has the same key, e.g. renumbered fields by their name.
+    fallback: Option<String>,
+    name: String,
+    properties: Vec<(String, String)>,
+    children: Vec<Item>,
+}
+
+impl Item {
+    fn new(kind: &'static str, key: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            kind,
+            key: key.into(),
+            fallback: None,
+            name: name.into(),
+            properties: vec![],
+            children: vec![],
+        }
+    }
+
+    fn property(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.properties.push((name.into(), value.into()));
+    }
+}
+
+struct Builder<'s> {
+    options: DiffOptions,
+    // The source the file was parsed from, to find the comments of its elements.
+    source: &'s str,
+}
+
+impl Builder<'_> {
+    // Takes the comments of the element at the index among its siblings when they are compared.
+    fn attach(&self, item: &mut Item, siblings: &[Entry], index: usize) {
+        if !self.options.comments {
+            return;
+        }
+
+        let comments = editor::comments(self.source, siblings, index);
+        let text = |block: &editor::CommentBlock| block.lines().join("\n");
+
+        if let Some(leading) = &comments.leading {
+            item.property("leading comment", text(leading));
+        }
+        if let Some(trailing) = &comments.trailing {
+            item.property("trailing comment", text(trailing));
+        }
+        if !comments.detached.is_empty() {
+            let detached: Vec<_> = comments.detached.iter().map(text).collect();
+            item.property("detached comments", detached.join("\n\n"));
+        }
+    }
+
+    fn option(&self, option: &ast::Option, siblings: &[Entry], index: usize) -> Item {
+        let mut item = Item::new("option", option.key, option.key);
+        item.property("value", formatter::map_value(&option.value));
+        self.attach(&mut item, siblings, index);
+        item
+    }
+
+    fn file(&self, name: &str, file: &ast::File) -> Item {
+        let mut item = Item::new("file", name, name);
+        let package = file
+            .iter()
+            .find_map(|entry| match entry {
+                ast::FileEntry::Package(package) => Some(*package),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let layout = Layout::new(self.source, file);
+        let siblings = layout.entries.as_slice();
+
+        for (index, entry) in file.iter().enumerate() {
+            match entry {
+                ast::FileEntry::Comment(_) => {}
+                ast::FileEntry::Syntax(syntax) => item.property("syntax", *syntax),
+                ast::FileEntry::Package(package) => item.property("package", *package),
+                ast::FileEntry::Import(import) => {
+                    let mut child =
+                        Item::new("import", import.path, formatter::string(import.path));
+
+                    match import.modifier {
+                        ast::ImportModifier::None => {}
+                        ast::ImportModifier::Public => child.property("modifier", "public"),
+                        ast::ImportModifier::Weak => child.property("modifier", "weak"),
+                    }
+
+                    self.attach(&mut child, siblings, index);
+                    item.children.push(child);
+                }
+                ast::FileEntry::Option(option) => {
+                    item.children.push(self.option(option, siblings, index))
+                }
+                ast::FileEntry::Message(message) => item
+                    .children
+                    .push(self.message(package, message, siblings, index)),
+                ast::FileEntry::Enum(r#enum) => item
+                    .children
+                    .push(self.r#enum(package, r#enum, siblings, index)),
+                ast::FileEntry::Service(service) => item
+                    .children
+                    .push(self.service(package, service, siblings, index)),
+                ast::FileEntry::Extend(extend) => {
+                    item.children.push(self.extend(extend, siblings, index))
+                }
+            }
+        }
+
+        item
+    }
+
+    fn field(
+        &self,
+        field: &ast::Field,
+        oneof: Option<&str>,
+        siblings: &[Entry],
+        index: usize,
+    ) -> Item {
+        let name = format!("{} = {}", field.ident, field.index);
+        let mut item = Item::new("field", field.index.to_string(), name);
+        item.fallback = Some(field.ident.to_string());
+
+        item.property("name", field.ident);
+        item.property("number", field.index.to_string());
+        item.property("type", formatter::field_type(field.r#type));
+
+        match field.modifier {
+            ast::FieldModifier::None => {}
+            ast::FieldModifier::Optional => item.property("label", "optional"),
+            ast::FieldModifier::Required => item.property("label", "required"),
+            ast::FieldModifier::Repeated => item.property("label", "repeated"),
+        }
+
+        if let Some(oneof) = oneof {
+            item.property("oneof", oneof);
+        }
+
+        for option in &field.options {
+            item.property(
+                format!("option {}", option.key),
+                formatter::map_value(&option.value),
+            );
+        }
+
+        self.attach(&mut item, siblings, index);
+        item
+    }
+
+    fn message(
+        &self,
+        scope: &str,
+        message: &ast::Message,
+        siblings: &[Entry],
+        index: usize,
+    ) -> Item {
+        let name = names::join(scope, message.ident);
+        let mut item = Item::new("message", &name, &name);
+        self.attach(&mut item, siblings, index);
+
+        let entries = siblings[index].entries.as_slice();
+        let (mut ranges, mut idents, mut extensions) = (vec![], vec![], vec![]);
+
+        for (index, entry) in message.entries.iter().enumerate() {
+            match entry {
+                ast::MessageEntry::Comment(_) => {}
+                ast::MessageEntry::Option(option) => {
+                    item.children.push(self.option(option, entries, index))
+                }
+                ast::MessageEntry::Field(field) => {
+                    item.children.push(self.field(field, None, entries, index))
+                }
+                ast::MessageEntry::OneOf(oneof) => {
+                    let mut child = Item::new("oneof", oneof.ident, oneof.ident);
+                    self.attach(&mut child, entries, index);
+
+                    let fields = entries[index].entries.as_slice();
+                    for (index, entry) in oneof.entries.iter().enumerate() {
+                        match entry {
+                            ast::OneOfEntry::Comment(_) => {}
+                            ast::OneOfEntry::Option(option) => {
+                                child.children.push(self.option(option, fields, index))
+                            }
+                            ast::OneOfEntry::Field(field) => item.children.push(self.field(
+                                field,
+                                Some(oneof.ident),
+                                fields,
+                                index,
+                            )),
+                        }
+                    }
+
+                    item.children.push(child);
+                }
+                ast::MessageEntry::Message(nested) => item
+                    .children
+                    .push(self.message(&name, nested, entries, index)),
+                ast::MessageEntry::Enum(r#enum) => item
+                    .children
+                    .push(self.r#enum(&name, r#enum, entries, index)),
+                ast::MessageEntry::Extend(extend) => {
+                    item.children.push(self.extend(extend, entries, index))
+                }
+                ast::MessageEntry::ReservedIndices(list) => {
+                    ranges.push(formatter::ranges_list(list))
+                }
+                ast::MessageEntry::ReservedIdents(list) => {
+                    idents.push(formatter::idents_list(list))
+                }
+                ast::MessageEntry::Extensions(list) => {
+                    extensions.push(formatter::ranges_list(list))
+                }
+            }
+        }
+
+        reservations(&mut item, ranges, idents);
+        if !extensions.is_empty() {
+            item.property("extensions", extensions.join(", "));
+        }
+
+        item
+    }
+
+    fn r#enum(&self, scope: &str, r#enum: &ast::Enum, siblings: &[Entry], index: usize) -> Item {
+        let name = names::join(scope, r#enum.ident);
+        let mut item = Item::new("enum", &name, &name);
+        self.attach(&mut item, siblings, index);
+
+        let entries = siblings[index].entries.as_slice();
+        let (mut ranges, mut idents) = (vec![], vec![]);
+
+        for (index, entry) in r#enum.entries.iter().enumerate() {
+            match entry {
+                ast::EnumEntry::Comment(_) => {}
+                ast::EnumEntry::Option(option) => {
+                    item.children.push(self.option(option, entries, index))
+                }
+                ast::EnumEntry::Pair {
+                    ident,
+                    value,
+                    options,
+                } => {
+                    let mut child = Item::new("value", *ident, format!("{} = {}", ident, value));
+                    child.fallback = Some(value.to_string());
+                    child.property("name", *ident);
+                    child.property("number", value.to_string());
+
+                    for option in options {
+                        child.property(
+                            format!("option {}", option.key),
+                            formatter::map_value(&option.value),
+                        );
+                    }
+
+                    self.attach(&mut child, entries, index);
+                    item.children.push(child);
+                }
+                ast::EnumEntry::ReservedIndices(list) => ranges.push(formatter::ranges_list(list)),
+                ast::EnumEntry::ReservedIdents(list) => idents.push(formatter::idents_list(list)),
+            }
+        }
+
+        reservations(&mut item, ranges, idents);
+        item
+    }
+
+    fn service(
+        &self,
+        scope: &str,
+        service: &ast::Service,
+        siblings: &[Entry],
+        index: usize,
+    ) -> Item {
+        let name = names::join(scope, service.ident);
+        let mut item = Item::new("service", &name, &name);
+        self.attach(&mut item, siblings, index);
+
+        let entries = siblings[index].entries.as_slice();
+
+        for (index, entry) in service.entries.iter().enumerate() {
+            match entry {
+                ast::ServiceEntry::Comment(_) => {}
+                ast::ServiceEntry::Option(option) => {
+                    item.children.push(self.option(option, entries, index))
+                }
+                ast::ServiceEntry::Rpc(rpc) => {
+                    let mut child = Item::new("rpc", rpc.ident, rpc.ident);
+                    let (request, reply) = match rpc.stream {
+                        ast::RpcStream::None => ("", ""),
+                        ast::RpcStream::ServerBound => ("stream ", ""),
+                        ast::RpcStream::ClientBound => ("", "stream "),
+                        ast::RpcStream::Bidirectional => ("stream ", "stream "),
+                    };
+
+                    child.property("request", format!("{}{}", request, rpc.request));
+                    child.property("response", format!("{}{}", reply, rpc.reply));
+                    self.attach(&mut child, entries, index);
+
+                    let options = entries[index].entries.as_slice();
+                    for (index, entry) in rpc.entries.iter().enumerate() {
+                        match entry {
+                            ast::RpcEntry::Comment(_) => {}
+                            ast::RpcEntry::Option(option) => {
+                                child.children.push(self.option(option, options, index))
+                            }
+                        }
+                    }
+
+                    item.children.push(child);
+                }
+            }
+        }
+
+        item
+    }
+
+    fn extend(&self, extend: &ast::Extend, siblings: &[Entry], index: usize) -> Item {
+        let mut item = Item::new("extend", extend.r#type, extend.r#type);
+        self.attach(&mut item, siblings, index);
+
+        let entries = siblings[index].entries.as_slice();
+
+        for (index, entry) in extend.entries.iter().enumerate() {
+            match entry {
+                ast::ExtendEntry::Comment(_) => {}
+                ast::ExtendEntry::Field(field) => {
+                    item.children.push(self.field(field, None, entries, index))
+                }
+            }
+        }
+
+        item
+    }
+}
+
+fn reservations(item: &mut Item, ranges: Vec<String>, idents: Vec<String>) {
+    if !ranges.is_empty() {
+        item.property("reserved numbers", ranges.join(", "));
+    }
+
+    if !idents.is_empty() {
+        item.property("reserved names", idents.join(", "));
+    }
+}
+
+// Pairs the children of both versions: the ones of the new version in their order, then the
+// removed ones.
+fn pair<'i>(old: &'i [Item], new: &'i [Item]) -> Vec<(Option<&'i Item>, Option<&'i Item>)> {
+    let mut matched: Vec<Option<usize>> = vec![None; new.len()];
+    let mut taken = vec![false; old.len()];
+
+    let passes: [fn(&Item) -> Option<&str>; 2] =
+        [|item| Some(&item.key), |item| item.fallback.as_deref()];
+
+    for key in passes {
+        for (index, item) in new.iter().enumerate() {
+            if matched[index].is_some() || key(item).is_none() {
+                continue;
+            }
+
+            let found = old.iter().enumerate().position(|(other, candidate)| {
+                !taken[other] && candidate.kind == item.kind && key(candidate) == key(item)
+            });
+
+            if let Some(other) = found {
+                matched[index] = Some(other);
+                taken[other] = true;
+            }
+        }
+    }
+
+    let mut pairs: Vec<_> = new
+        .iter()
+        .zip(&matched)
+        .map(|(item, other)| (other.map(|other| &old[other]), Some(item)))
+        .collect();
+
+    pairs.extend(
+        old.iter()
+            .zip(&taken)
+            .filter(|(_, taken)| !**taken)
+            .map(|(item, _)| (Some(item), None)),
+    );
+
+    pairs
+}
+
+fn compare(old: Option<&Item>, new: Option<&Item>, options: DiffOptions) -> Option<Node> {
+    let item = new.or(old)?;
+    let status = match (old, new) {
+        (None, _) => Status::Added,
+        (_, None) => Status::Removed,
+        _ => Status::Changed,
+    };
+
+    let old_properties = old.map_or(&[][..], |item| item.properties.as_slice());
+    let new_properties = new.map_or(&[][..], |item| item.properties.as_slice());
+    let value = |properties: &[(String, String)], name: &str| {
+        properties
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, value)| value.clone())
+    };
+
+    let mut properties = vec![];
+
+    for (name, _) in new_properties.iter().chain(old_properties) {
+        let (old, new) = (value(old_properties, name), value(new_properties, name));
+
+        if old != new
+            && !properties
+                .iter()
+                .any(|property: &Property| property.name == *name)
+        {
+            properties.push(Property {
+                name: name.clone(),
+                old,
+                new,
+            });
+        }
+    }
+
+    let old_children = old.map_or(&[][..], |item| item.children.as_slice());
+    let new_children = new.map_or(&[][..], |item| item.children.as_slice());
+    let pairs = pair(old_children, new_children);
+
+    if options.order && status == Status::Changed {
+        let kept: Vec<_> = pairs
+            .iter()
+            .filter_map(|pair| match pair {
+                (Some(old), Some(new)) => Some((*old, *new)),
+                _ => None,
+            })
+            .collect();
+        let mut before = kept.clone();
+        before.sort_by_key(|(item, _)| {
+            old_children
+                .iter()
+                .position(|other| std::ptr::eq(other, *item))
+        });
+
+        if before
+            .iter()
+            .zip(&kept)
+            .any(|(a, b)| !std::ptr::eq(a.0, b.0))
+        {
+            let names = |items: &[(&Item, &Item)]| {
+                let names: Vec<_> = items.iter().map(|(_, new)| new.name.as_str()).collect();
+                names.join(", ")
+            };
+
+            properties.push(Property {
+                name: "order".to_string(),
+                old: Some(names(&before)),
+                new: Some(names(&kept)),
+            });
+        }
+    }
+
+    let children: Vec<_> = pairs
+        .into_iter()
+        .filter_map(|(old, new)| compare(old, new, options))
+        .collect();
+
+    if status == Status::Changed && properties.is_empty() && children.is_empty() {
+        return None;
+    }
+
+    Some(Node {
+        kind: item.kind,
+        name: item.name.clone(),
+        status,
+        properties,
+        children,
+    })
+}
+
+// Differences between two versions of a file, either of which may not exist, given with the
+// source it was parsed from; None if there are none.
+pub fn semantic_diff(
+    name: &str,
+    old: Option<(&str, &ast::File)>,
+    new: Option<(&str, &ast::File)>,
+    options: DiffOptions,
+) -> Option<Node> {
+    let item = |(source, file)| Builder { options, source }.file(name, file);
+
+    let (old, new) = (old.map(item), new.map(item));
+    compare(old.as_ref(), new.as_ref(), options)
+}
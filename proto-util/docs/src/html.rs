@@ -0,0 +1,225 @@
+// Static HTML pages with their style inlined, so that the directory can be published as it is.
+// Declarations are anchored by their fully-qualified names.
+
+use crate::model::{Package, Type};
+use crate::{Format, href, page_name, relative};
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+code { background: #f4f4f4; padding: 0 0.2em; }
+.badge { display: inline-block; font-size: 0.8em; padding: 0 0.5em; border-radius: 0.8em; background: #def; }
+.deprecated { background: #fdd; }
+.signature { font-family: monospace; }";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+fn r#type(r#type: &Type, package: &str) -> String {
+    match r#type {
+        Type::Scalar(name) | Type::Unresolved(name) => format!("<code>{}</code>", escape(name)),
+        Type::Named { name, page: None } => format!("<code>{}</code>", escape(name)),
+        Type::Named {
+            name,
+            page: Some(page),
+        } => format!(
+            "<a href=\"{}\"><code>{}</code></a>",
+            escape(&href(page, name, package, Format::Html)),
+            escape(relative(name, package))
+        ),
+        Type::Map(key, value) => format!(
+            "<code>map&lt;{}, </code>{}<code>&gt;</code>",
+            escape(key),
+            self::r#type(value, package)
+        ),
+    }
+}
+
+fn badge(text: &str) -> String {
+    let class = match text {
+        "deprecated" => "badge deprecated",
+        _ => "badge",
+    };
+
+    format!(" <span class=\"{}\">{}</span>", class, text)
+}
+
+fn heading(html: &mut String, level: usize, name: &str, package: &str, deprecated: bool) {
+    html.push_str(&format!(
+        "<h{} id=\"{}\">{}",
+        level,
+        escape(name),
+        escape(relative(name, package))
+    ));
+    if deprecated {
+        html.push_str(&badge("deprecated"));
+    }
+    html.push_str(&format!("</h{}>\n", level));
+}
+
+// Paragraphs separated by blank lines.
+fn description(html: &mut String, lines: &[String]) {
+    for paragraph in lines.split(|line| line.is_empty()) {
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", escape(&paragraph.join("\n"))));
+        }
+    }
+}
+
+fn cell(lines: &[String], deprecated: bool) -> String {
+    let text = escape(&lines.join("\n"));
+
+    match deprecated {
+        true => format!("{} {}", badge("deprecated").trim_start(), text)
+            .trim_end()
+            .to_string(),
+        false => text,
+    }
+}
+
+pub fn package(package: &Package) -> String {
+    let name = &package.name;
+    let mut html = format!(
+        "<p><a href=\"index.html\">API reference</a></p>\n<h1>{}</h1>\n",
+        escape(page_name(name))
+    );
+
+    let files: Vec<_> = package
+        .files
+        .iter()
+        .map(|file| format!("<code>{}</code>", escape(file)))
+        .collect();
+    html.push_str(&format!("<p>Files: {}</p>\n", files.join(", ")));
+
+    if !package.services.is_empty() {
+        html.push_str("<h2>Services</h2>\n");
+    }
+    for service in &package.services {
+        heading(&mut html, 3, &service.name, name, service.deprecated);
+        description(&mut html, &service.description);
+
+        for rpc in &service.rpcs {
+            html.push_str(&format!("<h4>{}", escape(&rpc.name)));
+            match (rpc.client_streaming, rpc.server_streaming) {
+                (true, true) => html.push_str(&badge("bidirectional streaming")),
+                (true, false) => html.push_str(&badge("client streaming")),
+                (false, true) => html.push_str(&badge("server streaming")),
+                (false, false) => {}
+            }
+            if rpc.deprecated {
+                html.push_str(&badge("deprecated"));
+            }
+            html.push_str("</h4>\n");
+
+            html.push_str(&format!(
+                "<p class=\"signature\">rpc {} ({}{}) returns ({}{})</p>\n",
+                escape(&rpc.name),
+                if rpc.client_streaming { "stream " } else { "" },
+                r#type(&rpc.request, name),
+                if rpc.server_streaming { "stream " } else { "" },
+                r#type(&rpc.reply, name)
+            ));
+            description(&mut html, &rpc.description);
+        }
+    }
+
+    if !package.messages.is_empty() {
+        html.push_str("<h2>Messages</h2>\n");
+    }
+    for message in &package.messages {
+        heading(&mut html, 3, &message.name, name, message.deprecated);
+        description(&mut html, &message.description);
+
+        if message.fields.is_empty() {
+            html.push_str("<p>No fields.</p>\n");
+            continue;
+        }
+
+        html.push_str("<table>\n<tr><th>Field</th><th>Number</th><th>Type</th><th>Label</th>");
+        html.push_str("<th>Default</th><th>Description</th></tr>\n");
+        for field in &message.fields {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&field.name),
+                field.number,
+                r#type(&field.r#type, name),
+                escape(&field.label),
+                field
+                    .default
+                    .as_deref()
+                    .map_or(String::new(), |default| format!(
+                        "<code>{}</code>",
+                        escape(default)
+                    )),
+                cell(&field.description, field.deprecated)
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    if !package.enums.is_empty() {
+        html.push_str("<h2>Enums</h2>\n");
+    }
+    for r#enum in &package.enums {
+        heading(&mut html, 3, &r#enum.name, name, r#enum.deprecated);
+        description(&mut html, &r#enum.description);
+
+        html.push_str("<table>\n<tr><th>Value</th><th>Number</th><th>Description</th></tr>\n");
+        for value in &r#enum.values {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&value.name),
+                value.number,
+                cell(&value.description, value.deprecated)
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    document(page_name(name), &html)
+}
+
+pub fn index(packages: &[Package]) -> String {
+    let mut html = String::from("<h1>API reference</h1>\n<ul>\n");
+
+    for package in packages {
+        let page = escape(page_name(&package.name));
+        html.push_str(&format!(
+            "<li><a href=\"{}.html\">{}</a>\n<ul>\n",
+            page, page
+        ));
+
+        let declarations = package
+            .services
+            .iter()
+            .map(|service| &service.name)
+            .chain(package.messages.iter().map(|message| &message.name))
+            .chain(package.enums.iter().map(|r#enum| &r#enum.name));
+        for name in declarations {
+            html.push_str(&format!(
+                "<li><a href=\"{}.html#{}\">{}</a></li>\n",
+                page,
+                escape(name),
+                escape(relative(name, &package.name))
+            ));
+        }
+        html.push_str("</ul>\n</li>\n");
+    }
+    html.push_str("</ul>\n");
+
+    document("API reference", &html)
+}
@@ -0,0 +1,244 @@
+mod html;
+mod markdown;
+mod model;
+
+pub use model::{Enum, Field, Message, Package, Rpc, Service, Type, Value, packages};
+use resolver::Workspace;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    // Relative to the directory of the pages.
+    pub path: String,
+    pub content: String,
+}
+
+// Name of the page of the package, without the extension.
+pub fn page_name(package: &str) -> &str {
+    match package.is_empty() {
+        true => "default",
+        false => package,
+    }
+}
+
+// Pages documenting the files of the workspace, one per package and an index of them.
+pub fn generate(workspace: &Workspace, files: &[usize], format: Format) -> Vec<Page> {
+    let packages = packages(workspace, files);
+    let page = |name: &str, content| Page {
+        path: format!("{}.{}", name, format.extension()),
+        content,
+    };
+
+    let mut pages = vec![page(
+        "index",
+        match format {
+            Format::Markdown => markdown::index(&packages),
+            Format::Html => html::index(&packages),
+        },
+    )];
+
+    for package in &packages {
+        pages.push(page(
+            page_name(&package.name),
+            match format {
+                Format::Markdown => markdown::package(package),
+                Format::Html => html::package(package),
+            },
+        ));
+    }
+
+    pages
+}
+
+// Name of the declaration as written in the package, fully-qualified if it is in another one.
+fn relative<'n>(name: &'n str, package: &str) -> &'n str {
+    match package.is_empty() {
+        true => name,
+        false => name
+            .strip_prefix(package)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .unwrap_or(name),
+    }
+}
+
+// Link to the declaration from the page of the package.
+fn href(page: &str, name: &str, package: &str, format: Format) -> String {
+    match page == package {
+        true => format!("#{}", name),
+        false => format!("{}.{}#{}", page_name(page), format.extension(), name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Format, Page, Type};
+    use resolver::{SourceTree, Workspace};
+    use std::path::PathBuf;
+
+    const FILES: [&str; 3] = [
+        "acme/common/v1/money.proto",
+        "acme/common/v1/paging.proto",
+        "acme/shop/v1/shop.proto",
+    ];
+
+    fn tree() -> SourceTree {
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-data/docs");
+        let files: Vec<_> = FILES.iter().map(|file| data.join(file)).collect();
+
+        SourceTree::load(vec![data], &files).unwrap()
+    }
+
+    fn documented(workspace: &Workspace) -> Vec<usize> {
+        FILES
+            .iter()
+            .map(|file| workspace.file_index(file).unwrap())
+            .collect()
+    }
+
+    fn page<'p>(pages: &'p [Page], path: &str) -> &'p str {
+        &pages.iter().find(|page| page.path == path).unwrap().content
+    }
+
+    #[test]
+    fn model() {
+        let tree = tree();
+        let workspace = Workspace::new(&tree).unwrap();
+        let packages = crate::packages(&workspace, &documented(&workspace));
+
+        let names: Vec<_> = packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+        assert_eq!(names, ["acme.common.v1", "acme.shop.v1"]);
+        assert_eq!(
+            packages[0].files,
+            ["acme/common/v1/money.proto", "acme/common/v1/paging.proto"]
+        );
+
+        let shop = &packages[1];
+        let messages: Vec<_> = shop.messages.iter().map(|message| &message.name).collect();
+        assert_eq!(
+            messages,
+            [
+                "acme.shop.v1.GetProductRequest",
+                "acme.shop.v1.Product",
+                "acme.shop.v1.Product.Variant"
+            ]
+        );
+
+        let product = &shop.messages[1];
+        assert_eq!(product.description, ["Something for sale."]);
+        assert_eq!(
+            product.fields[1].r#type,
+            Type::Named {
+                name: "acme.common.v1.Money".to_string(),
+                page: Some("acme.common.v1".to_string()),
+            }
+        );
+        assert_eq!(
+            product.fields[2].r#type,
+            Type::Map(
+                "string".to_string(),
+                Box::new(Type::Named {
+                    name: "acme.shop.v1.Product.Variant".to_string(),
+                    page: Some("acme.shop.v1".to_string()),
+                })
+            )
+        );
+        assert_eq!(
+            product.fields[3].r#type,
+            Type::Named {
+                name: "google.protobuf.Timestamp".to_string(),
+                page: None,
+            }
+        );
+        assert_eq!(product.fields[4].label, "oneof origin");
+        assert!(product.fields[7].deprecated);
+        assert_eq!(product.fields[7].description, ["Use id."]);
+
+        let rpcs = &shop.services[0].rpcs;
+        assert_eq!(rpcs[0].description, ["Looks a product up."]);
+        assert!(rpcs[1].server_streaming && !rpcs[1].client_streaming);
+        assert!(rpcs[2].client_streaming && rpcs[2].deprecated);
+
+        let status = &shop.enums[0];
+        assert_eq!(status.name, "acme.shop.v1.Product.Status");
+        assert!(status.values[2].deprecated);
+
+        let page = &packages[0].messages[1];
+        assert_eq!(page.fields[0].default.as_deref(), Some("20"));
+        assert_eq!(page.fields[0].label, "optional");
+    }
+
+    #[test]
+    fn markdown() {
+        let tree = tree();
+        let workspace = Workspace::new(&tree).unwrap();
+        let pages = crate::generate(&workspace, &documented(&workspace), Format::Markdown);
+
+        let paths: Vec<_> = pages.iter().map(|page| page.path.as_str()).collect();
+        assert_eq!(paths, ["index.md", "acme.common.v1.md", "acme.shop.v1.md"]);
+        assert!(
+            page(&pages, "index.md")
+                .contains("  - [Product.Variant](acme.shop.v1.md#acme.shop.v1.Product.Variant)\n")
+        );
+
+        let shop = page(&pages, "acme.shop.v1.md");
+        for expected in [
+            "<a id=\"acme.shop.v1.Product\"></a>\n\n### Product\n\nSomething for sale.\n\n",
+            "| price | 2 | [acme.common.v1.Money](acme.common.v1.md#acme.common.v1.Money) |  |  |  |\n",
+            "| variants | 3 | map&lt;`string`, [Product.Variant](#acme.shop.v1.Product.Variant)&gt; |",
+            "| created | 4 | `google.protobuf.Timestamp` |",
+            "| factory | 5 | `string` | oneof origin |  |  |\n",
+            "| sku | 8 | `string` |  |  | **Deprecated.** Use id. |\n",
+            "`rpc WatchProducts` ([GetProductRequest](#acme.shop.v1.GetProductRequest)) \
+             returns (stream [Product](#acme.shop.v1.Product)) `server streaming`\n\n\
+             Products as they change.\n",
+            "`client streaming` *(deprecated)*",
+            "| STATUS_RETIRED | 2 | **Deprecated.** |\n",
+        ] {
+            assert!(shop.contains(expected), "{}", expected);
+        }
+
+        let common = page(&pages, "acme.common.v1.md");
+        assert!(
+            common.contains("| units | 2 | `int64` |  |  | Whole units \\| cents are separate. |")
+        );
+        assert!(common.contains("| size | 1 | `int32` | optional | `20` |  |"));
+    }
+
+    #[test]
+    fn html() {
+        let tree = tree();
+        let workspace = Workspace::new(&tree).unwrap();
+        let pages = crate::generate(&workspace, &documented(&workspace), Format::Html);
+
+        let shop = page(&pages, "acme.shop.v1.html");
+        assert!(shop.starts_with("<!DOCTYPE html>"));
+        for expected in [
+            "<h3 id=\"acme.shop.v1.Product\">Product</h3>\n<p>Something for sale.</p>\n",
+            "<a href=\"acme.common.v1.html#acme.common.v1.Money\"><code>acme.common.v1.Money</code></a>",
+            "<code>map&lt;string, </code><a href=\"#acme.shop.v1.Product.Variant\">",
+            "<h4>Import <span class=\"badge\">client streaming</span> \
+             <span class=\"badge deprecated\">deprecated</span></h4>",
+            "<p class=\"signature\">rpc Import (stream <a href=\"#acme.shop.v1.Product\">",
+        ] {
+            assert!(shop.contains(expected), "{}", expected);
+        }
+        assert!(page(&pages, "index.html").contains("<a href=\"acme.common.v1.html\">"));
+    }
+}
@@ -0,0 +1,188 @@
+// Pages in GitHub-flavored Markdown. Declarations are anchored by their fully-qualified names
+// with explicit anchors, since headings only show the names relative to the package.
+
+use crate::model::{Package, Type};
+use crate::{Format, href, page_name, relative};
+
+// Text in a table cell stays on one line.
+fn cell(lines: &[String]) -> String {
+    lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| escape(line).replace('|', "\\|"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn r#type(r#type: &Type, package: &str) -> String {
+    match r#type {
+        Type::Scalar(name) | Type::Unresolved(name) => format!("`{}`", name),
+        Type::Named { name, page: None } => format!("`{}`", name),
+        Type::Named {
+            name,
+            page: Some(page),
+        } => format!(
+            "[{}]({})",
+            relative(name, package),
+            href(page, name, package, Format::Markdown)
+        ),
+        Type::Map(key, value) => format!("map&lt;`{}`, {}&gt;", key, self::r#type(value, package)),
+    }
+}
+
+fn heading(text: &mut String, level: usize, name: &str, package: &str, deprecated: bool) {
+    text.push_str(&format!(
+        "<a id=\"{}\"></a>\n\n{} {}",
+        name,
+        "#".repeat(level),
+        relative(name, package)
+    ));
+    if deprecated {
+        text.push_str(" *(deprecated)*");
+    }
+    text.push_str("\n\n");
+}
+
+fn description(text: &mut String, lines: &[String]) {
+    if !lines.is_empty() {
+        text.push_str(&escape(&lines.join("\n")));
+        text.push_str("\n\n");
+    }
+}
+
+fn deprecated(lines: &[String], deprecated: bool) -> String {
+    match deprecated {
+        true => format!("**Deprecated.** {}", cell(lines))
+            .trim_end()
+            .to_string(),
+        false => cell(lines),
+    }
+}
+
+pub fn package(package: &Package) -> String {
+    let name = &package.name;
+    let mut text = format!("# {}\n\n", page_name(name));
+
+    let files: Vec<_> = package
+        .files
+        .iter()
+        .map(|file| format!("`{}`", file))
+        .collect();
+    text.push_str(&format!("Files: {}\n\n", files.join(", ")));
+
+    if !package.services.is_empty() {
+        text.push_str("## Services\n\n");
+    }
+    for service in &package.services {
+        heading(&mut text, 3, &service.name, name, service.deprecated);
+        description(&mut text, &service.description);
+
+        for rpc in &service.rpcs {
+            text.push_str(&format!("#### {}\n\n", rpc.name));
+            text.push_str(&format!(
+                "`rpc {}` ({}{}) returns ({}{})",
+                rpc.name,
+                if rpc.client_streaming { "stream " } else { "" },
+                r#type(&rpc.request, name),
+                if rpc.server_streaming { "stream " } else { "" },
+                r#type(&rpc.reply, name)
+            ));
+            match (rpc.client_streaming, rpc.server_streaming) {
+                (true, true) => text.push_str(" `bidirectional streaming`"),
+                (true, false) => text.push_str(" `client streaming`"),
+                (false, true) => text.push_str(" `server streaming`"),
+                (false, false) => {}
+            }
+            if rpc.deprecated {
+                text.push_str(" *(deprecated)*");
+            }
+            text.push_str("\n\n");
+            description(&mut text, &rpc.description);
+        }
+    }
+
+    if !package.messages.is_empty() {
+        text.push_str("## Messages\n\n");
+    }
+    for message in &package.messages {
+        heading(&mut text, 3, &message.name, name, message.deprecated);
+        description(&mut text, &message.description);
+
+        if message.fields.is_empty() {
+            text.push_str("No fields.\n\n");
+            continue;
+        }
+
+        text.push_str("| Field | Number | Type | Label | Default | Description |\n");
+        text.push_str("| --- | --- | --- | --- | --- | --- |\n");
+        for field in &message.fields {
+            text.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                field.name,
+                field.number,
+                r#type(&field.r#type, name),
+                field.label,
+                field
+                    .default
+                    .as_deref()
+                    .map_or(String::new(), |default| format!("`{}`", default)),
+                deprecated(&field.description, field.deprecated)
+            ));
+        }
+        text.push('\n');
+    }
+
+    if !package.enums.is_empty() {
+        text.push_str("## Enums\n\n");
+    }
+    for r#enum in &package.enums {
+        heading(&mut text, 3, &r#enum.name, name, r#enum.deprecated);
+        description(&mut text, &r#enum.description);
+
+        text.push_str("| Value | Number | Description |\n");
+        text.push_str("| --- | --- | --- |\n");
+        for value in &r#enum.values {
+            text.push_str(&format!(
+                "| {} | {} | {} |\n",
+                value.name,
+                value.number,
+                deprecated(&value.description, value.deprecated)
+            ));
+        }
+        text.push('\n');
+    }
+
+    text.truncate(text.trim_end().len());
+    text.push('\n');
+    text
+}
+
+pub fn index(packages: &[Package]) -> String {
+    let mut text = String::from("# API reference\n\n");
+
+    for package in packages {
+        let page = page_name(&package.name);
+        text.push_str(&format!("- [{}]({}.md)\n", page, page));
+
+        let declarations = package
+            .services
+            .iter()
+            .map(|service| &service.name)
+            .chain(package.messages.iter().map(|message| &message.name))
+            .chain(package.enums.iter().map(|r#enum| &r#enum.name));
+        for name in declarations {
+            text.push_str(&format!(
+                "  - [{}]({}.md#{})\n",
+                relative(name, &package.name),
+                page,
+                name
+            ));
+        }
+    }
+
+    text
+}
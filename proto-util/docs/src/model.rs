@@ -0,0 +1,295 @@
+// What the pages say, collected from the files of a workspace: the declarations of every package
+// in the order they are written, nested ones after their parents, with their comments and the
+// types they use resolved to the declarations they are linked to.
+
+use editor::Layout;
+use parser::ast;
+use resolver::{TypeRef, Workspace};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Scalar(String),
+    // Fully-qualified, with the package of the page documenting it, if any.
+    Named { name: String, page: Option<String> },
+    Map(String, Box<Type>),
+    // Not defined in the workspace, as written.
+    Unresolved(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Package {
+    pub name: String,
+    pub files: Vec<String>,
+    pub messages: Vec<Message>,
+    pub enums: Vec<Enum>,
+    pub services: Vec<Service>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub name: String,
+    pub description: Vec<String>,
+    pub deprecated: bool,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub number: i64,
+    pub name: String,
+    pub r#type: Type,
+    // `repeated`, `optional`, `required` or the oneof of the field, empty otherwise.
+    pub label: String,
+    pub default: Option<String>,
+    pub deprecated: bool,
+    pub description: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    pub name: String,
+    pub description: Vec<String>,
+    pub deprecated: bool,
+    pub values: Vec<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value {
+    pub name: String,
+    pub number: i64,
+    pub deprecated: bool,
+    pub description: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Service {
+    pub name: String,
+    pub description: Vec<String>,
+    pub deprecated: bool,
+    pub rpcs: Vec<Rpc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rpc {
+    pub name: String,
+    pub request: Type,
+    pub reply: Type,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+    pub deprecated: bool,
+    pub description: Vec<String>,
+}
+
+fn deprecated<'o, 'a: 'o>(options: impl IntoIterator<Item = &'o ast::Option<'a>>) -> bool {
+    options
+        .into_iter()
+        .any(|option| option.key == "deprecated" && option.value == ast::MapValue::Boolean(true))
+}
+
+fn join(scope: &str, ident: &str) -> String {
+    match scope.is_empty() {
+        true => ident.to_string(),
+        false => format!("{}.{}", scope, ident),
+    }
+}
+
+struct Collector<'w, 'a> {
+    workspace: &'w Workspace<'a>,
+    // Files whose declarations get pages.
+    documented: &'w [usize],
+    file: usize,
+    layout: Layout,
+}
+
+impl Collector<'_, '_> {
+    // Lines of the comments leading and trailing the declaration at the path.
+    fn description(&self, path: &[usize]) -> Vec<String> {
+        let source = self.workspace.file(self.file).source;
+        editor::comments_at(source, &self.layout, path).lines()
+    }
+
+    fn r#type(&self, scope: &str, r#type: &str) -> Type {
+        if let Some((key, value)) = resolver::map_types(r#type) {
+            return Type::Map(key.to_string(), Box::new(self.r#type(scope, value)));
+        }
+
+        match self.workspace.resolve_type(self.file, scope, r#type) {
+            Ok(TypeRef::Scalar(_)) => Type::Scalar(r#type.to_string()),
+            Ok(TypeRef::Message(symbol) | TypeRef::Enum(symbol)) => Type::Named {
+                name: symbol.name.clone(),
+                page: self
+                    .documented
+                    .contains(&symbol.file)
+                    .then(|| self.workspace.file(symbol.file).package().to_string()),
+            },
+            Err(_) => Type::Unresolved(r#type.to_string()),
+        }
+    }
+
+    fn field(&self, field: &ast::Field, scope: &str, label: String, path: &[usize]) -> Field {
+        let label = match field.modifier {
+            ast::FieldModifier::None => label,
+            ast::FieldModifier::Optional => "optional".to_string(),
+            ast::FieldModifier::Required => "required".to_string(),
+            ast::FieldModifier::Repeated => "repeated".to_string(),
+        };
+
+        Field {
+            number: field.index,
+            name: field.ident.to_string(),
+            r#type: self.r#type(scope, field.r#type),
+            label,
+            default: field
+                .options
+                .iter()
+                .find(|option| option.key == "default")
+                .map(|option| formatter::map_value(&option.value)),
+            deprecated: deprecated(&field.options),
+            description: self.description(path),
+        }
+    }
+
+    fn message(&self, message: &ast::Message, scope: &str, path: &[usize], package: &mut Package) {
+        let name = join(scope, message.ident);
+        let at = package.messages.len();
+        package.messages.push(Message {
+            name: name.clone(),
+            description: self.description(path),
+            deprecated: deprecated(message.entries.iter().filter_map(|entry| match entry {
+                ast::MessageEntry::Option(option) => Some(option),
+                _ => None,
+            })),
+            fields: vec![],
+        });
+
+        let mut fields = vec![];
+        for (index, entry) in message.entries.iter().enumerate() {
+            let path = [path, &[index]].concat();
+
+            match entry {
+                ast::MessageEntry::Field(field) => {
+                    fields.push(self.field(field, &name, String::new(), &path))
+                }
+                ast::MessageEntry::OneOf(oneof) => {
+                    for (index, entry) in oneof.entries.iter().enumerate() {
+                        if let ast::OneOfEntry::Field(field) = entry {
+                            let label = format!("oneof {}", oneof.ident);
+                            let path = [path.as_slice(), &[index]].concat();
+                            fields.push(self.field(field, &name, label, &path));
+                        }
+                    }
+                }
+                ast::MessageEntry::Message(nested) => self.message(nested, &name, &path, package),
+                ast::MessageEntry::Enum(r#enum) => self.r#enum(r#enum, &name, &path, package),
+                _ => {}
+            }
+        }
+
+        package.messages[at].fields = fields;
+    }
+
+    fn r#enum(&self, r#enum: &ast::Enum, scope: &str, path: &[usize], package: &mut Package) {
+        let mut values = vec![];
+        let mut options = vec![];
+
+        for (index, entry) in r#enum.entries.iter().enumerate() {
+            match entry {
+                ast::EnumEntry::Pair {
+                    ident,
+                    value,
+                    options,
+                } => values.push(Value {
+                    name: ident.to_string(),
+                    number: *value,
+                    deprecated: deprecated(options),
+                    description: self.description(&[path, &[index]].concat()),
+                }),
+                ast::EnumEntry::Option(option) => options.push(option),
+                _ => {}
+            }
+        }
+
+        package.enums.push(Enum {
+            name: join(scope, r#enum.ident),
+            description: self.description(path),
+            deprecated: deprecated(options),
+            values,
+        });
+    }
+
+    fn service(&self, service: &ast::Service, scope: &str, path: &[usize], package: &mut Package) {
+        let name = join(scope, service.ident);
+        let mut rpcs = vec![];
+        let mut options = vec![];
+
+        for (index, entry) in service.entries.iter().enumerate() {
+            match entry {
+                ast::ServiceEntry::Rpc(rpc) => rpcs.push(Rpc {
+                    name: rpc.ident.to_string(),
+                    request: self.r#type(&name, rpc.request),
+                    reply: self.r#type(&name, rpc.reply),
+                    client_streaming: matches!(
+                        rpc.stream,
+                        ast::RpcStream::ServerBound | ast::RpcStream::Bidirectional
+                    ),
+                    server_streaming: matches!(
+                        rpc.stream,
+                        ast::RpcStream::ClientBound | ast::RpcStream::Bidirectional
+                    ),
+                    deprecated: deprecated(rpc.entries.iter().filter_map(|entry| match entry {
+                        ast::RpcEntry::Option(option) => Some(option),
+                        _ => None,
+                    })),
+                    description: self.description(&[path, &[index]].concat()),
+                }),
+                ast::ServiceEntry::Option(option) => options.push(option),
+                _ => {}
+            }
+        }
+
+        package.services.push(Service {
+            name,
+            description: self.description(path),
+            deprecated: deprecated(options),
+            rpcs,
+        });
+    }
+}
+
+// Packages declared by the files, sorted by name.
+pub fn packages(workspace: &Workspace, files: &[usize]) -> Vec<Package> {
+    let mut packages: BTreeMap<&str, Package> = BTreeMap::new();
+
+    for &file in files {
+        let source = workspace.file(file);
+        let collector = Collector {
+            workspace,
+            documented: files,
+            file,
+            layout: Layout::new(source.source, &source.ast),
+        };
+
+        let package = packages.entry(source.package()).or_insert_with(|| Package {
+            name: source.package().to_string(),
+            ..Package::default()
+        });
+        package.files.push(source.name.to_string());
+
+        let scope = source.package();
+        for (index, entry) in source.ast.iter().enumerate() {
+            match entry {
+                ast::FileEntry::Message(message) => {
+                    collector.message(message, scope, &[index], package)
+                }
+                ast::FileEntry::Enum(r#enum) => collector.r#enum(r#enum, scope, &[index], package),
+                ast::FileEntry::Service(service) => {
+                    collector.service(service, scope, &[index], package)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    packages.into_values().collect()
+}
@@ -0,0 +1,328 @@
+// Comments attached to the declarations of a file the way protoc attaches them in SourceCodeInfo.
+// A comment on the line where a declaration ends, or on the next line when a blank line or the end
+// of the block follows, trails it; the comments right above a declaration lead it, and the others
+// since the previous declaration are detached. Comments are entries of their own in the layout, so
+// they are found in the text between the other entries.
+
+use crate::layout::{Entry, Layout};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentBlock {
+    // From the first comment of the block to the end of its last one.
+    pub range: Range<usize>,
+    // As protoc records it: without the comment markers, the lines of line comments ending with a
+    // newline and those of block comments without their leading `*`.
+    pub text: String,
+}
+
+impl CommentBlock {
+    // Lines of the text without the space after the comment markers, and without the blank lines
+    // around them.
+    pub fn lines(&self) -> Vec<&str> {
+        let lines: Vec<_> = self
+            .text
+            .lines()
+            .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end())
+            .collect();
+
+        let start = lines
+            .iter()
+            .position(|line| !line.is_empty())
+            .unwrap_or(lines.len());
+        let end = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(start, |index| index + 1);
+
+        lines[start..end].to_vec()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comments {
+    pub leading: Option<CommentBlock>,
+    // For blocks, the comment after the opening brace.
+    pub trailing: Option<CommentBlock>,
+    pub detached: Vec<CommentBlock>,
+}
+
+impl Comments {
+    // Lines of the leading and trailing comments, with an empty line between the two.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![];
+
+        for block in self.leading.iter().chain(&self.trailing) {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.extend(block.lines().into_iter().map(str::to_string));
+        }
+
+        lines
+    }
+}
+
+pub(crate) fn is_comment(source: &str, entry: &Entry) -> bool {
+    let text = &source[entry.range.clone()];
+    text.starts_with("//") || text.starts_with("/*")
+}
+
+// Comments of the declaration at the path in the layout, none if there is nothing there.
+pub fn comments_at(source: &str, layout: &Layout, path: &[usize]) -> Comments {
+    let Some((index, parent)) = path.split_last() else {
+        return Comments::default();
+    };
+
+    match layout.children(parent) {
+        Some(siblings) if *index < siblings.len() => comments(source, siblings, *index),
+        _ => Comments::default(),
+    }
+}
+
+// Comments of the entry at the index among its siblings, none for comment entries.
+pub fn comments(source: &str, siblings: &[Entry], index: usize) -> Comments {
+    let entry = &siblings[index];
+    if is_comment(source, entry) {
+        return Comments::default();
+    }
+
+    // the token before the entry ends the previous declaration or opens the block, and there is
+    // none for the first declaration of the file
+    let mut from = siblings[..index]
+        .iter()
+        .rfind(|sibling| !is_comment(source, sibling))
+        .map_or_else(
+            || source[..siblings[0].range.start].trim_end().len(),
+            |sibling| sibling.range.end,
+        );
+    let mut after_token = from > 0;
+
+    let before = loop {
+        let gap = gap(source, from, after_token);
+        if gap.next >= entry.range.start || gap.next >= source.len() {
+            break gap;
+        }
+
+        // a stray semicolon
+        from = gap.next + 1;
+        after_token = true;
+    };
+
+    let end = entry
+        .body
+        .as_ref()
+        .map_or(entry.range.end, |body| body.start);
+
+    Comments {
+        leading: before.leading,
+        trailing: gap(source, end, true).trailing,
+        detached: before.detached,
+    }
+}
+
+// Comments between two tokens as protoc's tokenizer splits them.
+#[derive(Default)]
+struct Gap {
+    leading: Option<CommentBlock>,
+    trailing: Option<CommentBlock>,
+    detached: Vec<CommentBlock>,
+    // Start of the token after the comments.
+    next: usize,
+}
+
+#[derive(Default)]
+struct Collector {
+    // The comment being read, and whether it is made of line comments.
+    buffer: Option<(CommentBlock, bool)>,
+    can_attach: bool,
+    trailing: Option<CommentBlock>,
+    detached: Vec<CommentBlock>,
+    flushed: usize,
+}
+
+impl Collector {
+    // Consecutive line comments make one comment.
+    fn line(&mut self, range: Range<usize>, text: &str) {
+        if let Some((block, true)) = &mut self.buffer {
+            block.range.end = range.end;
+            block.text.push_str(text);
+            return;
+        }
+
+        self.flush();
+        self.buffer = Some((
+            CommentBlock {
+                range,
+                text: text.to_string(),
+            },
+            true,
+        ));
+    }
+
+    fn block(&mut self, range: Range<usize>, text: String) {
+        self.flush();
+        self.buffer = Some((CommentBlock { range, text }, false));
+    }
+
+    fn flush(&mut self) {
+        let Some((block, _)) = self.buffer.take() else {
+            return;
+        };
+
+        match self.can_attach {
+            true => {
+                self.trailing = Some(block);
+                self.can_attach = false;
+            }
+            false => self.detached.push(block),
+        }
+        self.flushed += 1;
+    }
+
+    // A single comment between tokens on the same line belongs to neither of them.
+    fn maybe_detach(&mut self) {
+        if self.flushed + usize::from(self.buffer.is_some()) == 1 {
+            if let Some(trailing) = self.trailing.take() {
+                self.detached.insert(0, trailing);
+            }
+            self.can_attach = false;
+            self.flush();
+        }
+    }
+}
+
+fn skip_spaces(source: &str, at: usize) -> usize {
+    let rest = &source[at..];
+    at + rest.len()
+        - rest
+            .trim_start_matches([' ', '\t', '\r', '\x0b', '\x0c'])
+            .len()
+}
+
+// The comment starting at the offset, with its text and whether it is a line comment.
+fn comment_at(source: &str, at: usize) -> Option<(Range<usize>, String, bool)> {
+    let rest = &source[at..];
+
+    if let Some(text) = rest.strip_prefix("//") {
+        let end = text.find('\n').unwrap_or(text.len());
+        return Some((at..at + 2 + end, format!("{}\n", &text[..end]), true));
+    }
+
+    let text = rest.strip_prefix("/*")?;
+    let (inner, end) = match text.find("*/") {
+        Some(index) => (&text[..index], at + 4 + index),
+        None => (text, source.len()),
+    };
+
+    // lines after the first lose their indentation and the `*` starting them
+    let text = inner
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(index, line)| match index {
+            0 => line,
+            _ => {
+                let line = line.trim_start_matches([' ', '\t', '\r', '\x0b', '\x0c']);
+                line.strip_prefix('*').unwrap_or(line)
+            }
+        })
+        .collect();
+
+    Some((at..end, text, false))
+}
+
+// Offset after the newline at the offset, if there is one.
+fn newline(source: &str, at: usize) -> Option<usize> {
+    source[at..].starts_with('\n').then_some(at + 1)
+}
+
+fn next_token(source: &str, mut at: usize) -> usize {
+    loop {
+        at = skip_spaces(source, at);
+
+        if let Some(after) = newline(source, at) {
+            at = after;
+        } else if let Some((range, _, _)) = comment_at(source, at) {
+            at = range.end;
+        } else {
+            return at;
+        }
+    }
+}
+
+// Comments after the token ending at `from`, or from the start of the file.
+fn gap(source: &str, from: usize, after_token: bool) -> Gap {
+    let mut collector = Collector {
+        can_attach: after_token,
+        ..Collector::default()
+    };
+    let mut at = from;
+
+    // a comment on the line of the previous token trails it, unless the next token is on that
+    // line too
+    if after_token {
+        at = skip_spaces(source, at);
+
+        match comment_at(source, at) {
+            Some((range, text, true)) => {
+                collector.line(range.clone(), &text);
+                at = newline(source, range.end).unwrap_or(range.end);
+                collector.flush();
+            }
+            Some((range, text, false)) => {
+                collector.block(range.clone(), text);
+                let Some(after) = newline(source, skip_spaces(source, range.end)) else {
+                    return Gap {
+                        next: next_token(source, range.end),
+                        ..Gap::default()
+                    };
+                };
+                at = after;
+                collector.flush();
+            }
+            None => match newline(source, at) {
+                Some(after) => at = after,
+                None => {
+                    return Gap {
+                        next: next_token(source, at),
+                        ..Gap::default()
+                    };
+                }
+            },
+        }
+    }
+
+    loop {
+        at = skip_spaces(source, at);
+
+        if let Some((range, text, is_line)) = comment_at(source, at) {
+            match is_line {
+                true => collector.line(range.clone(), &text),
+                false => collector.block(range.clone(), text),
+            }
+            at = skip_spaces(source, range.end);
+            at = newline(source, at).unwrap_or(at);
+        } else if let Some(after) = newline(source, at) {
+            // a blank line
+            collector.flush();
+            collector.can_attach = false;
+            at = after;
+        } else {
+            // nothing is attached to the end of a block
+            if at == source.len() || source[at..].starts_with('}') {
+                collector.flush();
+            }
+            if !source[from..at].contains('\n') {
+                collector.maybe_detach();
+            }
+            break;
+        }
+    }
+
+    Gap {
+        leading: collector.buffer.map(|(block, _)| block),
+        trailing: collector.trailing,
+        detached: collector.detached,
+        next: at,
+    }
+}
@@ -0,0 +1,380 @@
+// Changes to the source as replaced byte ranges, so that everything around them, comments and
+// formatting included, stays as it was written.
+
+use crate::comments::{comments, is_comment};
+use crate::layout::{Entry, Layout};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn insert(offset: usize, text: impl Into<String>) -> Self {
+        Self {
+            range: offset..offset,
+            text: text.into(),
+        }
+    }
+
+    pub fn replace(range: Range<usize>, text: impl Into<String>) -> Self {
+        Self {
+            range,
+            text: text.into(),
+        }
+    }
+
+    pub fn delete(range: Range<usize>) -> Self {
+        Self {
+            range,
+            text: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    NotFound(Vec<usize>),
+    // No symbol with the name in the file.
+    Undefined(String),
+    // The change would make the schema invalid.
+    Invalid(String),
+    // The edited source doesn't parse anymore.
+    Parse(String),
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(path) => write!(f, "No entry at {:?}", path),
+            Self::Undefined(name) => write!(f, "{} is not defined", name),
+            Self::Invalid(message) => write!(f, "{}", message),
+            Self::Parse(message) => write!(f, "Edit breaks the file: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+// Edits must not overlap; insertions at the same offset keep their order.
+pub fn apply(source: &str, edits: &[Edit]) -> String {
+    let mut edits = edits.iter().collect::<Vec<_>>();
+    edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+
+    let mut result = String::with_capacity(source.len());
+    let mut position = 0;
+
+    for edit in edits {
+        assert!(edit.range.start >= position, "overlapping edits");
+
+        result.push_str(&source[position..edit.range.start]);
+        result.push_str(&edit.text);
+        position = edit.range.end;
+    }
+
+    result.push_str(&source[position..]);
+    result
+}
+
+// Applies the edits and makes sure the result still parses.
+pub fn edit(source: &str, edits: &[Edit]) -> Result<String, EditError> {
+    let result = apply(source, edits);
+
+    match parser::parse(&result) {
+        Ok(_) => Ok(result),
+        Err(error) => Err(EditError::Parse(parser::error_message(&result, &error))),
+    }
+}
+
+// Range of a string slice borrowed from the source, e.g. an identifier in the AST.
+pub fn span_of(source: &str, slice: &str) -> Option<Range<usize>> {
+    let start = (slice.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+
+    (start + slice.len() <= source.len()).then_some(start..start + slice.len())
+}
+
+pub fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+// Offset after the newline ending the line.
+pub fn line_end(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |index| offset + index + 1)
+}
+
+// Whitespace the line of the offset starts with.
+pub fn indentation(source: &str, offset: usize) -> &str {
+    let start = line_start(source, offset);
+    let line = &source[start..];
+
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+// One level of indentation as used in the file, two spaces if nothing is indented.
+pub fn indent_unit(source: &str) -> String {
+    source
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .filter(|indent| !indent.is_empty())
+        .min_by_key(|indent| indent.len())
+        .map_or_else(|| "  ".to_string(), |indent| indent.to_string())
+}
+
+fn starts_line(source: &str, offset: usize) -> bool {
+    source[line_start(source, offset)..offset].trim().is_empty()
+}
+
+// Range of the entry with the comments attached to it, and for blocks a comment following the
+// closing brace on its line. Whole lines are taken when the entry is alone on them.
+pub fn extent(source: &str, siblings: &[Entry], index: usize) -> Range<usize> {
+    let entry = &siblings[index];
+    let comments = comments(source, siblings, index);
+
+    let mut start = comments
+        .leading
+        .map_or(entry.range.start, |leading| leading.range.start);
+    let mut end = match (&entry.body, comments.trailing) {
+        (None, Some(trailing)) => trailing.range.end,
+        _ => entry.range.end,
+    };
+
+    if entry.body.is_some()
+        && let Some(next) = siblings.get(index + 1)
+        && is_comment(source, next)
+        && !source[end..next.range.start].contains('\n')
+    {
+        end = next.range.end;
+    }
+
+    let line_end = line_end(source, end);
+    if starts_line(source, start) && source[end..line_end].trim().is_empty() {
+        start = line_start(source, start);
+        end = line_end;
+    }
+
+    start..end
+}
+
+fn indent_lines(text: &str, indent: &str) -> String {
+    let mut result = String::new();
+
+    for line in text.trim_end_matches('\n').lines() {
+        if !line.is_empty() {
+            result.push_str(indent);
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    result
+}
+
+fn is_block(text: &str) -> bool {
+    text.trim_end().ends_with('}')
+}
+
+fn split_path(path: &[usize]) -> Result<(&[usize], usize), EditError> {
+    match path.split_last() {
+        Some((index, parent)) => Ok((parent, *index)),
+        None => Err(EditError::NotFound(path.to_vec())),
+    }
+}
+
+fn siblings<'l>(layout: &'l Layout, parent: &[usize]) -> Result<&'l [Entry], EditError> {
+    layout
+        .children(parent)
+        .ok_or_else(|| EditError::NotFound(parent.to_vec()))
+}
+
+// Inserts the entry, given without indentation, at the index among the entries of the block at
+// `parent` (of the file if it is empty).
+pub fn insert(
+    source: &str,
+    layout: &Layout,
+    parent: &[usize],
+    index: usize,
+    text: &str,
+) -> Result<Edit, EditError> {
+    let siblings = siblings(layout, parent)?;
+    let unit = indent_unit(source);
+
+    let indent = match siblings
+        .iter()
+        .find(|entry| starts_line(source, entry.range.start))
+    {
+        Some(sibling) => indentation(source, sibling.range.start).to_string(),
+        None if parent.is_empty() => String::new(),
+        None => {
+            let entry = layout.entry(parent).unwrap();
+            format!("{}{}", indentation(source, entry.range.start), unit)
+        }
+    };
+    let lines = indent_lines(text, &indent);
+    let blank = if is_block(text) { "\n" } else { "" };
+
+    // statements go right after the statement before them, not after the blank line following it
+    if let Some(previous) = index.checked_sub(1)
+        && index < siblings.len()
+        && !is_block(text)
+        && siblings[previous].body.is_none()
+    {
+        let extent = extent(source, siblings, previous);
+
+        if source[..extent.end].ends_with('\n') {
+            return Ok(Edit::insert(extent.end, lines));
+        }
+    }
+
+    if index < siblings.len() {
+        let extent = extent(source, siblings, index);
+
+        return Ok(match starts_line(source, extent.start) {
+            true => Edit::insert(
+                line_start(source, extent.start),
+                format!("{}{}", lines, blank),
+            ),
+            false => Edit::insert(extent.start, format!("{} ", text.trim())),
+        });
+    }
+
+    if let Some(last) = siblings.len().checked_sub(1) {
+        let extent = extent(source, siblings, last);
+        let blank = match siblings[last].body {
+            Some(_) => "\n",
+            None => blank,
+        };
+
+        return Ok(
+            match (
+                source[..extent.end].ends_with('\n'),
+                starts_line(source, extent.start),
+            ) {
+                (true, _) => Edit::insert(extent.end, format!("{}{}", blank, lines)),
+                (false, true) => Edit::insert(
+                    extent.end,
+                    format!("\n{}{}", blank, lines.trim_end_matches('\n')),
+                ),
+                (false, false) => Edit::insert(extent.end, format!(" {}", text.trim())),
+            },
+        );
+    }
+
+    if parent.is_empty() {
+        let newline = if source.is_empty() || source.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        return Ok(Edit::insert(source.len(), format!("{}{}", newline, lines)));
+    }
+
+    let entry = layout.entry(parent).unwrap();
+    let body = entry
+        .body
+        .clone()
+        .ok_or_else(|| EditError::NotFound(parent.to_vec()))?;
+
+    Ok(match source[body.clone()].contains('\n') {
+        true => Edit::insert(line_start(source, body.end), lines),
+        false => Edit::replace(
+            body,
+            format!("\n{}{}", lines, indentation(source, entry.range.start)),
+        ),
+    })
+}
+
+pub fn remove(source: &str, layout: &Layout, path: &[usize]) -> Result<Edit, EditError> {
+    let (parent, index) = split_path(path)?;
+    let siblings = siblings(layout, parent)?;
+
+    if index >= siblings.len() {
+        return Err(EditError::NotFound(path.to_vec()));
+    }
+
+    let mut range = extent(source, siblings, index);
+
+    // entries sharing a line with others take the space before them along
+    if range.start != line_start(source, range.start) {
+        range.start = source[..range.start].trim_end_matches([' ', '\t']).len();
+    }
+
+    // don't leave two blank lines, or a blank line at the start of a block, behind
+    let before = source[..range.start].trim_end_matches([' ', '\t']);
+    if source[range.end..].starts_with('\n')
+        && (before.is_empty() || before.ends_with("\n\n") || before.ends_with("{\n"))
+    {
+        range.end += 1;
+    }
+
+    Ok(Edit::delete(range))
+}
+
+// Replaces the entry; lines of the text after the first one get the indentation of the entry.
+pub fn replace(
+    source: &str,
+    layout: &Layout,
+    path: &[usize],
+    text: &str,
+) -> Result<Edit, EditError> {
+    let entry = layout
+        .entry(path)
+        .ok_or_else(|| EditError::NotFound(path.to_vec()))?;
+    let indent = indentation(source, entry.range.start);
+
+    let mut lines = text.trim_end_matches('\n').lines();
+    let mut result = lines.next().unwrap_or_default().to_string();
+
+    for line in lines {
+        result.push('\n');
+        if !line.is_empty() {
+            result.push_str(indent);
+        }
+        result.push_str(line);
+    }
+
+    Ok(Edit::replace(entry.range.clone(), result))
+}
+
+// Moves the entry with its comments so that it ends up at index `to` among its siblings.
+pub fn move_to(
+    source: &str,
+    layout: &Layout,
+    path: &[usize],
+    to: usize,
+) -> Result<Vec<Edit>, EditError> {
+    let (parent, from) = split_path(path)?;
+    let siblings = siblings(layout, parent)?;
+
+    if from >= siblings.len() || to >= siblings.len() {
+        return Err(EditError::NotFound(path.to_vec()));
+    }
+
+    if from == to {
+        return Ok(vec![]);
+    }
+
+    let moved = extent(source, siblings, from);
+    let target = extent(source, siblings, to);
+    let mut text = source[moved.clone()].to_string();
+
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+
+    let offset = match to < from {
+        true => line_start(source, target.start),
+        false => line_end(source, target.end.saturating_sub(1)),
+    };
+
+    Ok(vec![Edit::delete(moved), Edit::insert(offset, text)])
+}
+
+// Replaces an identifier or type name borrowed from the AST of the source.
+pub fn rename(source: &str, slice: &str, name: &str) -> Option<Edit> {
+    span_of(source, slice).map(|range| Edit::replace(range, name))
+}
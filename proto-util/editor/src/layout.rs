@@ -0,0 +1,256 @@
+// Where the entries of a parsed file are in its source. The AST doesn't keep positions, so they
+// are recovered by walking the tokens alongside the entries: each entry is a comment, a statement
+// ending with `;`, or a block with entries of its own.
+
+use parser::ast;
+use parser::lexer::{Lexer, Token};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    // From the first token of the entry to the end of its last one.
+    pub range: Range<usize>,
+    // Between the braces of blocks.
+    pub body: Option<Range<usize>>,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub entries: Vec<Entry>,
+}
+
+impl Layout {
+    // The file must have been parsed from the source.
+    pub fn new(source: &str, file: &ast::File) -> Self {
+        let tokens = Lexer::new(source).map_while(Result::ok).collect();
+
+        let mut walker = Walker { tokens, index: 0 };
+        let shapes = file.iter().map(file_shape).collect::<Vec<_>>();
+
+        Self {
+            entries: walker.entries(&shapes),
+        }
+    }
+
+    pub fn entry(&self, path: &[usize]) -> Option<&Entry> {
+        let (first, rest) = path.split_first()?;
+        let mut entry = self.entries.get(*first)?;
+
+        for index in rest {
+            entry = entry.entries.get(*index)?;
+        }
+
+        Some(entry)
+    }
+
+    // Entries of the file for an empty path, of the block at the path otherwise.
+    pub fn children(&self, path: &[usize]) -> Option<&[Entry]> {
+        match path.is_empty() {
+            true => Some(&self.entries),
+            false => self.entry(path).map(|entry| entry.entries.as_slice()),
+        }
+    }
+}
+
+enum Shape {
+    Comment,
+    Statement,
+    Block(Vec<Shape>),
+    // rpc ending either with `;` or with a block of options
+    Rpc(Vec<Shape>),
+}
+
+fn file_shape(entry: &ast::FileEntry) -> Shape {
+    match entry {
+        ast::FileEntry::Comment(_) => Shape::Comment,
+        ast::FileEntry::Syntax(_)
+        | ast::FileEntry::Package(_)
+        | ast::FileEntry::Import(_)
+        | ast::FileEntry::Option(_) => Shape::Statement,
+        ast::FileEntry::Service(service) => Shape::Block(
+            service
+                .entries
+                .iter()
+                .map(|entry| match entry {
+                    ast::ServiceEntry::Comment(_) => Shape::Comment,
+                    ast::ServiceEntry::Option(_) => Shape::Statement,
+                    ast::ServiceEntry::Rpc(rpc) => Shape::Rpc(
+                        rpc.entries
+                            .iter()
+                            .map(|entry| match entry {
+                                ast::RpcEntry::Comment(_) => Shape::Comment,
+                                ast::RpcEntry::Option(_) => Shape::Statement,
+                            })
+                            .collect(),
+                    ),
+                })
+                .collect(),
+        ),
+        ast::FileEntry::Message(message) => message_shape(message),
+        ast::FileEntry::Extend(extend) => extend_shape(extend),
+        ast::FileEntry::Enum(r#enum) => enum_shape(r#enum),
+    }
+}
+
+fn message_shape(message: &ast::Message) -> Shape {
+    Shape::Block(
+        message
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::MessageEntry::Comment(_) => Shape::Comment,
+                ast::MessageEntry::Message(message) => message_shape(message),
+                ast::MessageEntry::Enum(r#enum) => enum_shape(r#enum),
+                ast::MessageEntry::Extend(extend) => extend_shape(extend),
+                ast::MessageEntry::OneOf(oneof) => Shape::Block(
+                    oneof
+                        .entries
+                        .iter()
+                        .map(|entry| match entry {
+                            ast::OneOfEntry::Comment(_) => Shape::Comment,
+                            _ => Shape::Statement,
+                        })
+                        .collect(),
+                ),
+                _ => Shape::Statement,
+            })
+            .collect(),
+    )
+}
+
+fn extend_shape(extend: &ast::Extend) -> Shape {
+    Shape::Block(
+        extend
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::ExtendEntry::Comment(_) => Shape::Comment,
+                ast::ExtendEntry::Field(_) => Shape::Statement,
+            })
+            .collect(),
+    )
+}
+
+fn enum_shape(r#enum: &ast::Enum) -> Shape {
+    Shape::Block(
+        r#enum
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::EnumEntry::Comment(_) => Shape::Comment,
+                _ => Shape::Statement,
+            })
+            .collect(),
+    )
+}
+
+struct Walker<'a> {
+    tokens: Vec<(usize, Token<'a>, usize)>,
+    index: usize,
+}
+
+impl<'a> Walker<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.index).map(|(_, token, _)| token)
+    }
+
+    fn start(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map_or(0, |(start, _, _)| *start)
+    }
+
+    // End of the token just consumed.
+    fn end(&self) -> usize {
+        self.index
+            .checked_sub(1)
+            .and_then(|index| self.tokens.get(index))
+            .map_or(0, |(_, _, end)| *end)
+    }
+
+    // Stray semicolons between entries are allowed by the grammar.
+    fn skip_semicolons(&mut self) {
+        while self.peek() == Some(&Token::Semicolon) {
+            self.index += 1;
+        }
+    }
+
+    // Consumes tokens up to and including the first of `stops` outside of any brackets.
+    fn until(&mut self, stops: &[Token]) -> Option<Token<'a>> {
+        let mut depth = 0usize;
+
+        while let Some(token) = self.peek().cloned() {
+            self.index += 1;
+
+            if depth == 0 && stops.contains(&token) {
+                return Some(token);
+            }
+
+            match token {
+                Token::OpenBrace | Token::OpenBracket | Token::OpenPth | Token::OpenAngle => {
+                    depth += 1
+                }
+                Token::CloseBrace | Token::CloseBracket | Token::ClosePth | Token::CloseAngle => {
+                    depth = depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn entries(&mut self, shapes: &[Shape]) -> Vec<Entry> {
+        shapes
+            .iter()
+            .map(|shape| {
+                self.skip_semicolons();
+                self.entry(shape)
+            })
+            .collect()
+    }
+
+    fn block(&mut self, start: usize, shapes: &[Shape]) -> Entry {
+        let body_start = self.end();
+        let entries = self.entries(shapes);
+        self.skip_semicolons();
+
+        let body_end = self.start();
+        self.until(&[Token::CloseBrace]);
+
+        Entry {
+            range: start..self.end(),
+            body: Some(body_start..body_end),
+            entries,
+        }
+    }
+
+    fn entry(&mut self, shape: &Shape) -> Entry {
+        let start = self.start();
+
+        match shape {
+            Shape::Comment => {
+                self.index += 1;
+            }
+            Shape::Statement => {
+                self.until(&[Token::Semicolon]);
+            }
+            Shape::Block(shapes) => {
+                self.until(&[Token::OpenBrace]);
+                return self.block(start, shapes);
+            }
+            Shape::Rpc(shapes) => {
+                if self.until(&[Token::Semicolon, Token::OpenBrace]) == Some(Token::OpenBrace) {
+                    return self.block(start, shapes);
+                }
+            }
+        }
+
+        Entry {
+            range: start..self.end(),
+            body: None,
+            entries: vec![],
+        }
+    }
+}
@@ -0,0 +1,714 @@
+mod comments;
+mod edit;
+mod layout;
+mod merge;
+mod ops;
+mod refactor;
+mod source_info;
+mod validate;
+
+pub use comments::{CommentBlock, Comments, comments, comments_at};
+pub use edit::{
+    Edit, EditError, apply, edit, extent, indent_unit, indentation, insert, line_end, line_start,
+    move_to, remove, rename, replace, span_of,
+};
+pub use layout::{Entry, Layout};
+pub use merge::{Conflict, Merge, merge};
+pub use ops::{
+    NewField, add_enum_value, add_field, find, ident_of, remove_at, remove_enum_value,
+    remove_field, reserve, reserve_freed, retire, retire_at, set_option,
+};
+pub use refactor::{FileEdits, move_definition, rename as rename_symbol};
+pub use source_info::source_code_info;
+pub use validate::{
+    MAX_FIELD_NUMBER, check_enum_value, check_field, check_field_number, check_ident, check_label,
+    check_name, check_number, check_type, message_fields, message_names, next_field_number,
+};
+
+#[cfg(test)]
+mod tests {
+    use crate::{Edit, Layout};
+    use parser::ast;
+    use resolver::{SourceTree, Workspace};
+    use std::path::PathBuf;
+
+    fn source() -> String {
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-data/editor/library.proto");
+
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn layout(source: &str) -> Layout {
+        Layout::new(source, &parser::parse(source).unwrap())
+    }
+
+    fn text<'s>(source: &'s str, layout: &Layout, path: &[usize]) -> &'s str {
+        &source[layout.entry(path).unwrap().range.clone()]
+    }
+
+    fn edited(source: &str, edits: &[Edit]) -> String {
+        crate::edit(source, edits).unwrap()
+    }
+
+    #[test]
+    fn layout_spans() {
+        let source = source();
+        let layout = layout(&source);
+
+        assert_eq!(layout.entries.len(), 7);
+        assert_eq!(text(&source, &layout, &[0]), "syntax = \"proto3\";");
+        assert_eq!(text(&source, &layout, &[2]), "// A book on the shelves.");
+        assert_eq!(text(&source, &layout, &[3, 0]), "string title = 1;");
+        assert_eq!(
+            text(&source, &layout, &[3, 1]),
+            "// as printed on the cover"
+        );
+        assert_eq!(text(&source, &layout, &[3, 4, 1]), "string borrower = 4;");
+        assert_eq!(text(&source, &layout, &[3, 5]), "reserved 5, 8 to 10;");
+        assert_eq!(
+            text(&source, &layout, &[3, 7]),
+            "enum Format { FORMAT_UNSPECIFIED = 0; HARDCOVER = 1; }"
+        );
+        assert_eq!(text(&source, &layout, &[3, 7, 1]), "HARDCOVER = 1;");
+        assert_eq!(text(&source, &layout, &[5]), "message Empty {}");
+        assert_eq!(layout.entry(&[5]).unwrap().body, Some(493..493));
+        assert_eq!(
+            text(&source, &layout, &[6, 1]),
+            "rpc Lend(Book) returns (Book) {\n    option deprecated = true;\n  }"
+        );
+        assert_eq!(
+            text(&source, &layout, &[6, 1, 0]),
+            "option deprecated = true;"
+        );
+    }
+
+    #[test]
+    fn comment_attachment() {
+        let source = "syntax = \"proto3\";
+
+message Foo { // Trailing the message.
+  int32 foo = 1;  // Trailing foo.
+  // Leading bar.
+  int32 bar = 2;
+
+  string baz = 3;
+  // Trailing baz.
+  // Another line of it.
+
+  // Leading moo.
+  //
+  // Another line of it.
+  double moo = 4;
+
+  // Detached from corge.
+
+  // Detached too.
+
+  string corge = 5;
+  /* Trailing corge.
+   * Without the asterisks. */
+  /* Leading grault. */
+  int32 grault = 6; int32 garply = 7;
+
+  // Not attached to anything.
+}
+";
+        let layout = layout(source);
+        let entries = &layout.entries[1].entries;
+        let comments = |index| crate::comments(source, entries, index);
+        let lines =
+            |block: Option<crate::CommentBlock>| block.map(|block| block.lines().join("\n"));
+
+        let message = crate::comments(source, &layout.entries, 1);
+        assert_eq!(
+            lines(message.trailing).as_deref(),
+            Some("Trailing the message.")
+        );
+
+        let foo = comments(1);
+        assert_eq!(foo.leading, None);
+        assert_eq!(foo.trailing.unwrap().text, " Trailing foo.\n");
+        assert_eq!(lines(comments(4).leading).as_deref(), Some("Leading bar."));
+        assert_eq!(comments(4).trailing, None);
+        assert_eq!(
+            lines(comments(5).trailing).as_deref(),
+            Some("Trailing baz.\nAnother line of it.")
+        );
+        assert_eq!(
+            comments(11).leading.unwrap().text,
+            " Leading moo.\n\n Another line of it.\n"
+        );
+
+        let corge = comments(14);
+        let detached: Vec<_> = corge.detached.iter().map(|block| block.lines()).collect();
+        assert_eq!(detached, [["Detached from corge."], ["Detached too."]]);
+        assert_eq!(
+            corge.trailing.unwrap().text,
+            " Trailing corge.\n Without the asterisks. "
+        );
+        assert_eq!(
+            lines(comments(17).leading).as_deref(),
+            Some("Leading grault.")
+        );
+        assert_eq!(comments(17).trailing, None);
+        assert_eq!(comments(18), crate::Comments::default());
+        assert_eq!(comments(19), crate::Comments::default());
+
+        let bar = crate::comments_at(source, &layout, &[1, 4]);
+        assert_eq!(bar.lines(), ["Leading bar."]);
+        let moo = crate::comments_at(source, &layout, &[1, 11]);
+        assert_eq!(moo.lines(), ["Leading moo.", "", "Another line of it."]);
+        let corge = crate::comments_at(source, &layout, &[1, 14]);
+        assert_eq!(corge.lines(), ["Trailing corge.", "Without the asterisks."]);
+        assert_eq!(
+            crate::comments_at(source, &layout, &[1, 99]),
+            crate::Comments::default()
+        );
+
+        // moving a declaration moves the comments attached to it
+        let moved = crate::move_to(source, &layout, &[1, 5], 1).unwrap();
+        assert!(edited(source, &moved).contains(
+            "message.\n  string baz = 3;\n  // Trailing baz.\n  // Another line of it.\n  int32 foo = 1;"
+        ));
+    }
+
+    #[test]
+    fn insert_entries() {
+        let source = source();
+        let layout = layout(&source);
+
+        let field = crate::insert(&source, &layout, &[3], 3, "int32 pages = 6;").unwrap();
+        let value = crate::insert(&source, &layout, &[4], 5, "EPIC = 2;").unwrap();
+        let message = crate::insert(&source, &layout, &[5], 0, "message Nothing {\n}").unwrap();
+        let inline = crate::insert(&source, &layout, &[3, 7], 2, "EBOOK = 2;").unwrap();
+        let result = edited(&source, &[field, value, message, inline]);
+
+        assert!(result.contains(
+            "  repeated string authors = 2;\n  int32 pages = 6;\n\n  /* Where the book is. */"
+        ));
+        assert!(result.contains("  reserved 7 to max;\n  EPIC = 2;\n}"));
+        assert!(result.contains("message Empty {\n  message Nothing {\n  }\n}"));
+        assert!(result.contains("HARDCOVER = 1; EBOOK = 2; }"));
+
+        let file = crate::insert(&source, &layout, &[], 6, "message Loan {}").unwrap();
+        assert!(
+            edited(&source, &[file]).contains("message Empty {}\n\nmessage Loan {}\n\nservice")
+        );
+    }
+
+    #[test]
+    fn remove_entries() {
+        let source = source();
+        let layout = layout(&source);
+
+        let title = crate::remove(&source, &layout, &[3, 0]).unwrap();
+        let oneof = crate::remove(&source, &layout, &[3, 4]).unwrap();
+        let result = edited(&source, &[title, oneof]);
+
+        assert!(
+            result.contains(
+                "message Book {\n  repeated string authors = 2;\n\n  reserved 5, 8 to 10;"
+            )
+        );
+        assert!(!result.contains("cover") && !result.contains("Where"));
+
+        let book = crate::remove(&source, &layout, &[3]).unwrap();
+        assert!(edited(&source, &[book]).contains("package library;\n\nenum Genre"));
+
+        assert!(crate::remove(&source, &layout, &[3, 20]).is_err());
+    }
+
+    #[test]
+    fn move_and_replace() {
+        let source = source();
+        let layout = layout(&source);
+
+        let down = crate::move_to(&source, &layout, &[3, 0], 2).unwrap();
+        assert!(edited(&source, &down).contains(
+            "message Book {\n  repeated string authors = 2;\n  string title = 1; // as printed on the cover\n"
+        ));
+
+        let up = crate::move_to(&source, &layout, &[4, 2], 1).unwrap();
+        assert!(
+            edited(&source, &up)
+                .contains("  option allow_alias = true;\n  NOVEL = 1;\n  GENRE_UNSPECIFIED = 0;\n")
+        );
+
+        let rpc = crate::replace(
+            &source,
+            &layout,
+            &[6, 1],
+            "rpc Lend(Book) returns (stream Book) {\n  option deprecated = true;\n}",
+        )
+        .unwrap();
+        assert!(edited(&source, &[rpc]).contains(
+            "  rpc Lend(Book) returns (stream Book) {\n    option deprecated = true;\n  };"
+        ));
+    }
+
+    #[test]
+    fn rename_and_breakage() {
+        let source = source();
+        let file = parser::parse(&source).unwrap();
+
+        let ast::FileEntry::Message(book) = &file[3] else {
+            panic!("not a message");
+        };
+
+        let rename = crate::rename(&source, book.ident, "Volume").unwrap();
+        assert!(crate::apply(&source, &[rename]).contains("message Volume {"));
+
+        let broken = crate::edit(&source, &[Edit::delete(190..191)]);
+        assert!(matches!(broken, Err(crate::EditError::Parse(_))));
+    }
+
+    #[test]
+    fn validation() {
+        let source = source();
+        let file = parser::parse(&source).unwrap();
+
+        let (ast::FileEntry::Message(book), ast::FileEntry::Enum(genre)) = (&file[3], &file[4])
+        else {
+            panic!("unexpected entries");
+        };
+
+        let check = |r#type, ident, index, replaced| {
+            crate::check_field(book, &ast::Field::basic(r#type, ident, index), replaced)
+        };
+
+        assert_eq!(check("int32", "pages", 6, None), Ok(()));
+        assert_eq!(check("map<string, Book>", "index", 6, None), Ok(()));
+        assert_eq!(check("string", "title", 1, Some("title")), Ok(()));
+        assert_eq!(
+            check("int32", "shelf", 6, None),
+            Err("shelf is already defined".to_string())
+        );
+        assert_eq!(
+            check("int32", "pages", 3, None),
+            Err("Field number 3 is already used by shelf".to_string())
+        );
+        assert_eq!(
+            check("int32", "pages", 9, None),
+            Err("Field number 9 is reserved".to_string())
+        );
+        assert_eq!(
+            check("int32", "isbn", 6, None),
+            Err("Field name isbn is reserved".to_string())
+        );
+        assert_eq!(
+            check("int32", "pages", 19500, None),
+            Err("Field number 19500 is reserved for the protobuf implementation".to_string())
+        );
+        assert_eq!(
+            check("int32", "pages", 0, None),
+            Err(format!(
+                "Field number 0 is out of range 1 to {}",
+                wire::MAX_FIELD_NUMBER
+            ))
+        );
+        assert_eq!(
+            check("int32", "2pages", 6, None),
+            Err("2pages is not a valid identifier".to_string())
+        );
+        assert_eq!(
+            check("foo..Bar", "pages", 6, None),
+            Err("foo..Bar is not a valid type name".to_string())
+        );
+
+        assert_eq!(crate::next_field_number(book), 6);
+
+        assert_eq!(crate::check_enum_value(genre, "POEM", 1, None), Ok(()));
+        assert_eq!(
+            crate::check_enum_value(genre, "NOVEL", 2, None),
+            Err("NOVEL is already defined".to_string())
+        );
+        assert_eq!(
+            crate::check_enum_value(genre, "POEM", 8, None),
+            Err("Enum value 8 is reserved".to_string())
+        );
+    }
+
+    #[test]
+    fn scripted_changes() {
+        let source = source();
+        let file = parser::parse(&source).unwrap();
+
+        assert_eq!(crate::find(&file, "library.Book"), Some(vec![3]));
+        assert_eq!(
+            crate::find(&file, ".library.Book.borrower"),
+            Some(vec![3, 4, 1])
+        );
+        assert_eq!(
+            crate::find(&file, "Book.Format.HARDCOVER"),
+            Some(vec![3, 7, 1])
+        );
+        assert_eq!(crate::find(&file, "Library.Lend"), Some(vec![6, 1]));
+        assert_eq!(crate::find(&file, "Book.pages"), None);
+
+        let field = |name, oneof| crate::NewField {
+            modifier: ast::FieldModifier::None,
+            r#type: "int32",
+            name,
+            number: None,
+            oneof,
+        };
+
+        let pages = crate::add_field(&source, "library.Book", field("pages", None)).unwrap();
+        assert!(edited(&source, &pages).contains(
+            "  repeated string authors = 2;\n  int32 pages = 6;\n\n  /* Where the book is. */"
+        ));
+
+        let cart = crate::add_field(&source, "Book", field("cart", Some("location"))).unwrap();
+        assert!(
+            edited(&source, &cart).contains("    string borrower = 4;\n    int32 cart = 6;\n  }")
+        );
+
+        assert!(matches!(
+            crate::add_field(&source, "Book", field("title", None)),
+            Err(crate::EditError::Invalid(_))
+        ));
+        assert!(matches!(
+            crate::add_field(&source, "Shelf", field("pages", None)),
+            Err(crate::EditError::Undefined(_))
+        ));
+
+        let removed = crate::remove_field(&source, "Book", "title").unwrap();
+        assert!(
+            edited(&source, &removed).contains("message Book {\n  repeated string authors = 2;")
+        );
+
+        let value = crate::add_enum_value(&source, "Genre", "EPIC", None).unwrap();
+        assert!(
+            edited(&source, &value).contains("  FICTION = 1;\n  EPIC = 2;\n  reserved 7 to max;")
+        );
+        assert!(crate::add_enum_value(&source, "Genre", "EPIC", Some(8)).is_err());
+
+        let reserved =
+            crate::reserve(&source, "Book", &[ast::Range::from(11..12)], &["summary"]).unwrap();
+        assert!(edited(&source, &reserved).contains(
+            "  reserved \"isbn\";\n  reserved 11 to 12;\n  reserved \"summary\";\n\n  enum Format"
+        ));
+        assert!(crate::reserve(&source, "Book", &[ast::Range::from(2)], &[]).is_err());
+    }
+
+    #[test]
+    fn option_changes() {
+        let source = source();
+
+        let file = crate::set_option(&source, None, "java_package", "\"org.library\"").unwrap();
+        assert!(
+            edited(&source, &file).contains(
+                "package library;\n\noption java_package = \"org.library\";\n\n// A book"
+            )
+        );
+
+        let alias = crate::set_option(&source, Some("Genre"), "allow_alias", "false").unwrap();
+        assert!(
+            edited(&source, &alias).contains("  option allow_alias = false;\n  GENRE_UNSPECIFIED")
+        );
+
+        let find = crate::set_option(&source, Some("Library.Find"), "deprecated", "true").unwrap();
+        assert!(
+            edited(&source, &find).contains(
+                "  rpc Find(Book) returns (Book) {\n    option deprecated = true;\n  }\n"
+            )
+        );
+
+        let title = crate::set_option(&source, Some("Book.title"), "deprecated", "true").unwrap();
+        let result = edited(&source, &title);
+        assert!(result.contains("string title = 1 [deprecated = true]; // as printed"));
+
+        let json = crate::set_option(&result, Some("Book.title"), "json_name", "\"name\"").unwrap();
+        let result = edited(&result, &json);
+        assert!(result.contains("[deprecated = true, json_name = \"name\"];"));
+
+        let again = crate::set_option(&result, Some("Book.title"), "deprecated", "false").unwrap();
+        assert!(edited(&result, &again).contains("[deprecated = false, json_name = \"name\"];"));
+    }
+
+    #[test]
+    fn retire_entries() {
+        let source = source();
+
+        let title = crate::retire(&source, "Book.title").unwrap();
+        let result = edited(&source, &title);
+        assert!(result.contains("message Book {\n  repeated string authors = 2;"));
+        assert!(result.contains("  reserved 1, 5, 8 to 10;\n  reserved \"isbn\", \"title\";"));
+
+        let borrower = crate::retire(&source, "library.Book.borrower").unwrap();
+        let result = edited(&source, &borrower);
+        assert!(result.contains("    string shelf = 3;\n  }"));
+        assert!(result.contains("  reserved 4 to 5, 8 to 10;"));
+
+        // the oneof goes away with its last field
+        let shelf = crate::retire(&result, "Book.shelf").unwrap();
+        let result = edited(&result, &shelf);
+        assert!(result.contains("  repeated string authors = 2;\n\n  reserved 3 to 5, 8 to 10;"));
+        assert!(!result.contains("oneof"));
+
+        // the number stays in use by the alias
+        let novel = crate::retire(&source, "Genre.NOVEL").unwrap();
+        assert!(
+            edited(&source, &novel)
+                .contains("  FICTION = 1;\n  reserved 7 to max;\n  reserved \"NOVEL\";\n}")
+        );
+
+        let hardcover = crate::retire(&source, "Book.Format.HARDCOVER").unwrap();
+        assert!(edited(&source, &hardcover).contains(
+            "enum Format { FORMAT_UNSPECIFIED = 0; reserved 1; reserved \"HARDCOVER\"; }"
+        ));
+
+        assert!(crate::retire(&source, "Book.location").is_err());
+
+        // proto3 enums start with a zero value, and no enum is left empty
+        assert!(matches!(
+            crate::retire(&source, "Genre.GENRE_UNSPECIFIED"),
+            Err(crate::EditError::Invalid(_))
+        ));
+        let result = edited(&source, &hardcover);
+        assert!(crate::remove_enum_value(&result, "Book.Format", "FORMAT_UNSPECIFIED").is_err());
+
+        // what is reserved or in use already is left alone
+        let freed =
+            crate::reserve_freed(&source, "Book", &[1, 6, 9, 11], &["isbn", "title", "sku"])
+                .unwrap();
+        assert!(
+            edited(&source, &freed)
+                .contains("  reserved 5 to 6, 8 to 11;\n  reserved \"isbn\", \"sku\";\n")
+        );
+        assert!(
+            crate::reserve_freed(&source, "Book", &[1], &["title"])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn rename_across_files() {
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-data/editor");
+        let tree = SourceTree::load(vec![data.clone()], &[data.join("loans.proto")]).unwrap();
+        let workspace = Workspace::new(&tree).unwrap();
+
+        let renamed = |name, new_name| {
+            let mut sources: Vec<String> = workspace
+                .files()
+                .iter()
+                .map(|file| file.source.to_string())
+                .collect();
+
+            for change in crate::rename_symbol(&workspace, name, new_name)? {
+                sources[change.file] = edited(&sources[change.file], &change.edits);
+            }
+
+            Ok::<_, crate::EditError>(sources)
+        };
+
+        let sources = renamed("library.Book", "Volume").unwrap();
+        assert!(
+            sources[0].contains("  library.Volume book = 1;\n  .library.Volume.Format format = 2;")
+        );
+        assert!(sources[0].contains("rpc Lend(library.Volume) returns (Loan);"));
+        assert!(sources[1].contains("message Volume {"));
+        assert!(sources[1].contains("rpc Find(Volume) returns (Volume);"));
+
+        // `Record` would now mean the renamed message
+        let sources = renamed("loans.Loan.Entry", "Record").unwrap();
+        assert!(
+            sources[0]
+                .contains("  loans.Record record = 3;\n  Record entry = 4;\n\n  message Record {}")
+        );
+
+        let sources = renamed("library.Book.authors", "writers").unwrap();
+        assert!(sources[1].contains("repeated string writers = 2;"));
+
+        assert_eq!(
+            renamed("library.Book.authors", "title"),
+            Err(crate::EditError::Invalid(
+                "library.Book.title is already defined".to_string()
+            ))
+        );
+        assert!(renamed("library", "books").is_err());
+    }
+
+    #[test]
+    fn move_across_files() {
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-data/editor");
+        let mut tree = SourceTree::load(vec![data.clone()], &[data.join("loans.proto")]).unwrap();
+        tree.add("books.proto", "syntax = \"proto3\";\n\npackage books;\n");
+        tree.add("genres.proto", "syntax = \"proto3\";\n\npackage library;\n");
+        let workspace = Workspace::new(&tree).unwrap();
+
+        let moved = |name, to, public| {
+            let mut sources: Vec<String> = workspace
+                .files()
+                .iter()
+                .map(|file| file.source.to_string())
+                .collect();
+
+            let to = workspace.file_index(to).unwrap();
+            for change in crate::move_definition(&workspace, name, to, public)? {
+                sources[change.file] = edited(&sources[change.file], &change.edits);
+            }
+
+            Ok::<_, crate::EditError>(sources)
+        };
+
+        let sources = moved("library.Book", "books.proto", false).unwrap();
+        assert!(sources[2].starts_with(
+            "syntax = \"proto3\";\n\npackage books;\n\n// A book on the shelves.\nmessage Book {\n"
+        ));
+        assert!(sources[1].contains("package library;\n\nimport \"books.proto\";\n\nenum Genre"));
+        assert!(sources[1].contains("rpc Find(books.Book) returns (books.Book);"));
+        // nothing is used from library.proto anymore
+        assert!(sources[0].contains("package loans;\n\nimport \"books.proto\";\n\nmessage Loan"));
+        assert!(!sources[0].contains("library.proto"));
+        assert!(sources[0].contains("  books.Book book = 1;\n  .books.Book.Format format = 2;"));
+        assert!(sources[0].contains("rpc Lend(books.Book) returns (Loan);"));
+
+        // or see it through the old file
+        let sources = moved("library.Book", "books.proto", true).unwrap();
+        assert!(sources[0].contains("package loans;\n\nimport \"library.proto\";\n\nmessage Loan"));
+
+        // files importing the old one see the enum through it
+        let sources = moved("library.Genre", "genres.proto", true).unwrap();
+        assert!(sources[1].contains("package library;\n\nimport public \"genres.proto\";\n\n//"));
+        assert!(sources[3].ends_with("package library;\n\nenum Genre {\n  option allow_alias = true;\n  GENRE_UNSPECIFIED = 0;\n  NOVEL = 1;\n  FICTION = 1;\n  reserved 7 to max;\n}\n"));
+
+        let sources = moved("loans.Record", "library.proto", false).unwrap();
+        assert!(sources[0].contains("  library.Record record = 3;"));
+        assert!(!sources[0].contains("message Record"));
+        assert!(sources[1].ends_with("  };\n}\n\nmessage Record {}\n"));
+
+        // the service of library.proto would need loans.proto, which imports it
+        assert!(matches!(
+            moved("loans.Loan", "library.proto", false),
+            Err(crate::EditError::Invalid(_))
+        ));
+        assert!(moved("library.Book.Format", "books.proto", false).is_err());
+    }
+
+    #[test]
+    fn merge_versions() {
+        let base = source();
+        let change = |from: &str, to: &str| base.replacen(from, to, 1);
+
+        let ours = change(
+            "  repeated string authors = 2;\n",
+            "  repeated string authors = 2;\n  int32 pages = 6;\n",
+        )
+        .replacen("  NOVEL = 1;\n", "  NOVEL = 1;\n  POETRY = 2;\n", 1);
+        let theirs = change(
+            "  reserved \"isbn\";\n",
+            "  reserved \"isbn\";\n  string publisher = 7;\n",
+        )
+        .replacen(
+            "rpc Find(Book) returns (Book);",
+            "rpc Find(Empty) returns (Book);",
+            1,
+        )
+        .replacen("message Empty {}\n\n", "", 1)
+            + "\nmessage Shelf {\n  repeated Book books = 1;\n}\n";
+
+        let merged = crate::merge(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged.conflicts, vec![]);
+        assert!(
+            merged
+                .source
+                .contains("  repeated string authors = 2;\n  int32 pages = 6;\n")
+        );
+        assert!(
+            merged
+                .source
+                .contains("  reserved \"isbn\";\n  string publisher = 7;\n")
+        );
+        assert!(merged.source.contains("  NOVEL = 1;\n  POETRY = 2;\n"));
+        assert!(merged.source.contains("rpc Find(Empty) returns (Book);"));
+        assert!(!merged.source.contains("message Empty"));
+        assert!(
+            merged
+                .source
+                .ends_with("}\n\nmessage Shelf {\n  repeated Book books = 1;\n}\n")
+        );
+        // merging is symmetric apart from the order of the additions
+        let merged = crate::merge(&base, &theirs, &ours).unwrap();
+        assert_eq!(merged.conflicts, vec![]);
+        assert!(merged.source.contains("int32 pages = 6;"));
+
+        // the same number for different fields
+        let theirs = change(
+            "  repeated string authors = 2;\n",
+            "  repeated string authors = 2;\n  string subtitle = 6;\n",
+        );
+        let merged = crate::merge(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(
+            merged.conflicts[0].to_string(),
+            "field 6 in message Book: the number is used by different fields on both sides"
+        );
+        assert!(merged.source.contains(
+            "<<<<<<< ours\n  int32 pages = 6;\n=======\n  string subtitle = 6;\n>>>>>>> theirs\n"
+        ));
+
+        // the same field under different numbers
+        let theirs = change(
+            "  repeated string authors = 2;\n",
+            "  repeated string authors = 2;\n  int32 pages = 7;\n",
+        );
+        let merged = crate::merge(&base, &ours, &theirs).unwrap();
+        assert_eq!(
+            merged.conflicts[0].to_string(),
+            "message Book: field name pages is used by both sides"
+        );
+        assert!(merged.source.contains(
+            "<<<<<<< ours\n  int32 pages = 6;\n=======\n  int32 pages = 7;\n>>>>>>> theirs\n"
+        ));
+
+        // additions at the same place, ours first
+        let theirs = change(
+            "  repeated string authors = 2;\n",
+            "  repeated string authors = 2;\n  string subtitle = 9;\n",
+        )
+        .replacen("  NOVEL = 1;\n", "  NOVEL = 1;\n  EPIC = 2;\n", 1);
+        let merged = crate::merge(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged.conflicts, vec![]);
+        assert!(merged.source.contains(
+            "  repeated string authors = 2;\n  int32 pages = 6;\n  string subtitle = 9;\n"
+        ));
+        assert!(
+            merged
+                .source
+                .contains("  NOVEL = 1;\n  POETRY = 2;\n  EPIC = 2;\n")
+        );
+
+        // the same number for different values of an enum without aliases
+        let no_alias = |source: &str| source.replacen("  option allow_alias = true;\n", "", 1);
+        let merged = crate::merge(&no_alias(&base), &no_alias(&ours), &no_alias(&theirs)).unwrap();
+        assert_eq!(
+            merged.conflicts[0].to_string(),
+            "enum Genre: value number 2 is used by both sides"
+        );
+        assert!(
+            merged
+                .source
+                .contains("<<<<<<< ours\n  POETRY = 2;\n=======\n  EPIC = 2;\n>>>>>>> theirs\n")
+        );
+
+        // changed by one side, removed by the other
+        let theirs = change("message Empty {}\n\n", "");
+        let ours = change("message Empty {}", "message Empty {\n  bool flag = 1;\n}");
+        let merged = crate::merge(&base, &ours, &theirs).unwrap();
+        assert_eq!(
+            merged.conflicts[0].to_string(),
+            "message Empty: changed by us and removed by them"
+        );
+        assert!(merged.source.contains(
+            "<<<<<<< ours\nmessage Empty {\n  bool flag = 1;\n}\n=======\n>>>>>>> theirs\n"
+        ));
+
+        assert!(matches!(
+            crate::merge(&base, "message {", &theirs),
+            Err(crate::EditError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,594 @@
+// Three-way merge of the versions of a file changed on two branches. Entries are matched by what
+// they define (fields by number, everything else by name), so that additions on both sides merge
+// cleanly; the merged file is our version with their changes applied to it.
+
+use crate::edit::{self, Edit, EditError};
+use crate::layout::Layout;
+use crate::validate;
+use parser::ast;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    // Where the entries are, e.g. "field 5 in message User".
+    pub location: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    // With git-style conflict markers around the entries changed differently on both sides.
+    pub source: String,
+    pub conflicts: Vec<Conflict>,
+}
+
+struct Version<'s> {
+    source: &'s str,
+    file: ast::File<'s>,
+    layout: Layout,
+}
+
+impl<'s> Version<'s> {
+    fn new(name: &str, source: &'s str) -> Result<Self, EditError> {
+        let file = parser::parse(source).map_err(|error| {
+            EditError::Parse(format!(
+                "{}: {}",
+                name,
+                parser::error_message(source, &error)
+            ))
+        })?;
+        let layout = Layout::new(source, &file);
+
+        Ok(Self {
+            source,
+            file,
+            layout,
+        })
+    }
+
+    // The entry with its comments, as whole lines when it is alone on them.
+    fn text(&self, path: &[usize]) -> &'s str {
+        let (index, parent) = path.split_last().unwrap();
+        let siblings = self.layout.children(parent).unwrap();
+
+        &self.source[edit::extent(self.source, siblings, *index)]
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Block<'r, 'a> {
+    File(&'r ast::File<'a>),
+    Message(&'r ast::Message<'a>),
+    Enum(&'r ast::Enum<'a>),
+    Service(&'r ast::Service<'a>),
+    OneOf(&'r ast::OneOf<'a>),
+    Extend(&'r ast::Extend<'a>),
+}
+
+// A keyed entry of a block; comments go along with the entries they are attached to.
+struct Child<'r, 'a> {
+    key: String,
+    index: usize,
+    block: Option<Block<'r, 'a>>,
+}
+
+fn child<'r, 'a>(key: String, index: usize, block: Option<Block<'r, 'a>>) -> Child<'r, 'a> {
+    Child { key, index, block }
+}
+
+fn option_key(option: &ast::Option) -> String {
+    format!("option {}", option.key)
+}
+
+fn ranges_key(kind: &str, ranges: &[ast::Range]) -> String {
+    format!("{} {}", kind, formatter::ranges_list(ranges))
+}
+
+fn idents_key(idents: &[&str]) -> String {
+    format!("reserved {}", formatter::idents_list(idents))
+}
+
+impl<'r, 'a> Block<'r, 'a> {
+    fn children(&self) -> Vec<Child<'r, 'a>> {
+        let mut children = vec![];
+        // extends of the same type are told apart by their order
+        let mut extends = HashSet::new();
+        let mut extend_key = |r#type: &str| {
+            let mut count = 1;
+            while !extends.insert(format!("extend {} #{}", r#type, count)) {
+                count += 1;
+            }
+            format!("extend {} #{}", r#type, count)
+        };
+
+        match self {
+            Self::File(file) => {
+                for (index, entry) in file.iter().enumerate() {
+                    children.push(match entry {
+                        ast::FileEntry::Comment(_) => continue,
+                        ast::FileEntry::Syntax(_) => child("syntax".to_string(), index, None),
+                        ast::FileEntry::Package(_) => child("package".to_string(), index, None),
+                        ast::FileEntry::Import(import) => {
+                            child(format!("import \"{}\"", import.path), index, None)
+                        }
+                        ast::FileEntry::Option(option) => child(option_key(option), index, None),
+                        ast::FileEntry::Message(message) => child(
+                            format!("message {}", message.ident),
+                            index,
+                            Some(Block::Message(message)),
+                        ),
+                        ast::FileEntry::Enum(r#enum) => child(
+                            format!("enum {}", r#enum.ident),
+                            index,
+                            Some(Block::Enum(r#enum)),
+                        ),
+                        ast::FileEntry::Service(service) => child(
+                            format!("service {}", service.ident),
+                            index,
+                            Some(Block::Service(service)),
+                        ),
+                        ast::FileEntry::Extend(extend) => child(
+                            extend_key(extend.r#type),
+                            index,
+                            Some(Block::Extend(extend)),
+                        ),
+                    });
+                }
+            }
+            Self::Message(message) => {
+                for (index, entry) in message.entries.iter().enumerate() {
+                    children.push(match entry {
+                        ast::MessageEntry::Comment(_) => continue,
+                        ast::MessageEntry::Option(option) => child(option_key(option), index, None),
+                        ast::MessageEntry::Field(field) => {
+                            child(format!("field {}", field.index), index, None)
+                        }
+                        ast::MessageEntry::OneOf(oneof) => child(
+                            format!("oneof {}", oneof.ident),
+                            index,
+                            Some(Block::OneOf(oneof)),
+                        ),
+                        ast::MessageEntry::Message(message) => child(
+                            format!("message {}", message.ident),
+                            index,
+                            Some(Block::Message(message)),
+                        ),
+                        ast::MessageEntry::Enum(r#enum) => child(
+                            format!("enum {}", r#enum.ident),
+                            index,
+                            Some(Block::Enum(r#enum)),
+                        ),
+                        ast::MessageEntry::Extend(extend) => child(
+                            extend_key(extend.r#type),
+                            index,
+                            Some(Block::Extend(extend)),
+                        ),
+                        ast::MessageEntry::ReservedIndices(ranges) => {
+                            child(ranges_key("reserved", ranges), index, None)
+                        }
+                        ast::MessageEntry::ReservedIdents(idents) => {
+                            child(idents_key(idents), index, None)
+                        }
+                        ast::MessageEntry::Extensions(ranges) => {
+                            child(ranges_key("extensions", ranges), index, None)
+                        }
+                    });
+                }
+            }
+            Self::Enum(r#enum) => {
+                for (index, entry) in r#enum.entries.iter().enumerate() {
+                    children.push(match entry {
+                        ast::EnumEntry::Comment(_) => continue,
+                        ast::EnumEntry::Option(option) => child(option_key(option), index, None),
+                        ast::EnumEntry::Pair { ident, .. } => {
+                            child(format!("value {}", ident), index, None)
+                        }
+                        ast::EnumEntry::ReservedIndices(ranges) => {
+                            child(ranges_key("reserved", ranges), index, None)
+                        }
+                        ast::EnumEntry::ReservedIdents(idents) => {
+                            child(idents_key(idents), index, None)
+                        }
+                    });
+                }
+            }
+            Self::Service(service) => {
+                for (index, entry) in service.entries.iter().enumerate() {
+                    children.push(match entry {
+                        ast::ServiceEntry::Comment(_) => continue,
+                        ast::ServiceEntry::Option(option) => child(option_key(option), index, None),
+                        ast::ServiceEntry::Rpc(rpc) => {
+                            child(format!("rpc {}", rpc.ident), index, None)
+                        }
+                    });
+                }
+            }
+            Self::OneOf(oneof) => {
+                for (index, entry) in oneof.entries.iter().enumerate() {
+                    children.push(match entry {
+                        ast::OneOfEntry::Comment(_) => continue,
+                        ast::OneOfEntry::Option(option) => child(option_key(option), index, None),
+                        ast::OneOfEntry::Field(field) => {
+                            child(format!("field {}", field.index), index, None)
+                        }
+                    });
+                }
+            }
+            Self::Extend(extend) => {
+                for (index, entry) in extend.entries.iter().enumerate() {
+                    if let ast::ExtendEntry::Field(field) = entry {
+                        children.push(child(format!("field {}", field.index), index, None));
+                    }
+                }
+            }
+        }
+
+        children
+    }
+
+    // What the field or enum value at the index can't share with one added by the other side:
+    // the name of a field, or the number of a value unless the enum allows aliases.
+    fn clash(&self, index: usize) -> Option<String> {
+        let field = |field: &ast::Field| Some(format!("field name {}", field.ident));
+
+        match self {
+            Self::Message(message) => match &message.entries[index] {
+                ast::MessageEntry::Field(entry) => field(entry),
+                _ => None,
+            },
+            Self::OneOf(oneof) => match &oneof.entries[index] {
+                ast::OneOfEntry::Field(entry) => field(entry),
+                _ => None,
+            },
+            Self::Extend(extend) => match &extend.entries[index] {
+                ast::ExtendEntry::Field(entry) => field(entry),
+                _ => None,
+            },
+            Self::Enum(r#enum) if !validate::allows_alias(r#enum) => match &r#enum.entries[index] {
+                ast::EnumEntry::Pair { value, .. } => Some(format!("value number {}", value)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+// A block in one of the versions, with its path in the layout.
+type Located<'r, 'a> = (Block<'r, 'a>, Vec<usize>);
+
+fn same(a: &str, b: &str) -> bool {
+    a.split_whitespace().eq(b.split_whitespace())
+}
+
+// Removes the indentation of the first line from every line.
+fn dedent(text: &str) -> String {
+    let indent = &text[..text.len() - text.trim_start_matches([' ', '\t']).len()];
+
+    text.lines()
+        .map(|line| line.strip_prefix(indent).unwrap_or(line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn lines(text: &str) -> String {
+    match text.is_empty() || text.ends_with('\n') {
+        true => text.to_string(),
+        false => format!("{}\n", text),
+    }
+}
+
+fn markers(ours: &str, theirs: &str) -> String {
+    format!(
+        "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n",
+        lines(ours),
+        lines(theirs)
+    )
+}
+
+struct Merger<'v, 's> {
+    base: &'v Version<'s>,
+    ours: &'v Version<'s>,
+    theirs: &'v Version<'s>,
+    edits: Vec<Edit>,
+    conflicts: Vec<Conflict>,
+}
+
+impl<'v, 's> Merger<'v, 's> {
+    fn conflict(&mut self, location: String, message: &str) {
+        self.conflicts.push(Conflict {
+            location,
+            message: message.to_string(),
+        });
+    }
+
+    fn block<'r>(
+        &mut self,
+        scope: &str,
+        base: Option<&Located<'r, 's>>,
+        ours: &Located<'r, 's>,
+        theirs: &Located<'r, 's>,
+    ) -> Result<(), EditError>
+    where
+        's: 'r,
+    {
+        let base_children = base.map_or(vec![], |(block, _)| block.children());
+        let our_children = ours.0.children();
+        let their_children = theirs.0.children();
+
+        let find = |children: &'_ [Child<'r, 's>], key: &str| {
+            children.iter().position(|child| child.key == key)
+        };
+        let path = |located: &Located, index: usize| [located.1.as_slice(), &[index]].concat();
+        let location = |key: &str| match scope.is_empty() {
+            true => key.to_string(),
+            false => format!("{} in {}", key, scope),
+        };
+
+        for our in &our_children {
+            let our_path = path(ours, our.index);
+            let our_text = self.ours.text(&our_path);
+            let base_child = find(&base_children, &our.key).map(|index| &base_children[index]);
+            let base_text =
+                base_child.map(|child| self.base.text(&path(base.unwrap(), child.index)));
+
+            let Some(their) = find(&their_children, &our.key).map(|index| &their_children[index])
+            else {
+                // removed by them
+                match base_text {
+                    Some(base_text) if same(base_text, our_text) => {
+                        self.edits.push(edit::remove(
+                            self.ours.source,
+                            &self.ours.layout,
+                            &our_path,
+                        )?);
+                    }
+                    Some(_) => {
+                        let edit = Edit::replace(
+                            edit::extent(
+                                self.ours.source,
+                                self.ours.layout.children(&ours.1).unwrap(),
+                                our.index,
+                            ),
+                            markers(our_text, ""),
+                        );
+                        self.edits.push(edit);
+                        self.conflict(location(&our.key), "changed by us and removed by them");
+                    }
+                    None => {}
+                }
+                continue;
+            };
+
+            let their_path = path(theirs, their.index);
+            let their_text = self.theirs.text(&their_path);
+
+            if same(our_text, their_text) || base_text.is_some_and(|text| same(text, their_text)) {
+                continue;
+            }
+
+            let range = edit::extent(
+                self.ours.source,
+                self.ours.layout.children(&ours.1).unwrap(),
+                our.index,
+            );
+
+            if base_text.is_some_and(|text| same(text, our_text)) {
+                self.edits.push(Edit::replace(range, lines(their_text)));
+                continue;
+            }
+
+            // changed (or added) differently on both sides
+            if let (Some(our_block), Some(their_block)) = (our.block, their.block) {
+                let base = base_child
+                    .and_then(|child| Some((child.block?, path(base.unwrap(), child.index))));
+                let scope = location(&our.key);
+
+                self.block(
+                    &scope,
+                    base.as_ref(),
+                    &(our_block, our_path),
+                    &(their_block, their_path),
+                )?;
+                continue;
+            }
+
+            let message = match (base_text, our.key.starts_with("field ")) {
+                (None, true) => "the number is used by different fields on both sides",
+                (None, false) => "added differently on both sides",
+                (Some(_), _) => "changed differently on both sides",
+            };
+
+            self.edits
+                .push(Edit::replace(range, markers(our_text, their_text)));
+            self.conflict(location(&our.key), message);
+        }
+
+        for (position, their) in their_children.iter().enumerate() {
+            if find(&our_children, &their.key).is_some() {
+                continue;
+            }
+
+            let their_text = self.theirs.text(&path(theirs, their.index));
+            let base_text = find(&base_children, &their.key).map(|index| {
+                self.base
+                    .text(&path(base.unwrap(), base_children[index].index))
+            });
+
+            if base_text.is_some_and(|text| same(text, their_text)) {
+                continue;
+            }
+
+            // added by both sides under different keys, e.g. a field under different numbers
+            let clash = base_text
+                .is_none()
+                .then(|| theirs.0.clash(their.index))
+                .flatten();
+            let ours_too = clash.as_ref().and_then(|clash| {
+                our_children.iter().find(|our| {
+                    find(&their_children, &our.key).is_none()
+                        && find(&base_children, &our.key).is_none()
+                        && ours.0.clash(our.index).as_ref() == Some(clash)
+                })
+            });
+
+            if let (Some(clash), Some(our)) = (clash, ours_too) {
+                let range = edit::extent(
+                    self.ours.source,
+                    self.ours.layout.children(&ours.1).unwrap(),
+                    our.index,
+                );
+                let our_text = self.ours.text(&path(ours, our.index));
+
+                self.edits
+                    .push(Edit::replace(range, markers(our_text, their_text)));
+                self.conflict(
+                    scope.to_string(),
+                    &format!("{} is used by both sides", clash),
+                );
+                continue;
+            }
+
+            // after the closest entry before it that we have too, and after what we added there
+            let previous = their_children[..position]
+                .iter()
+                .rev()
+                .find_map(|previous| find(&our_children, &previous.key));
+            let mut next = previous.map_or(0, |index| index + 1);
+            while next < our_children.len()
+                && find(&their_children, &our_children[next].key).is_none()
+            {
+                next += 1;
+            }
+
+            let index = match (next, &ours.0) {
+                (0, Block::File(file)) if previous.is_none() => file.len(),
+                (0, _) => 0,
+                (next, _) => our_children[next - 1].index + 1,
+            };
+
+            let mut edit = edit::insert(
+                self.ours.source,
+                &self.ours.layout,
+                &ours.1,
+                index,
+                &dedent(their_text),
+            )?;
+
+            if base_text.is_some() {
+                edit.text = markers("", &edit.text);
+                self.conflict(location(&their.key), "removed by us and changed by them");
+            }
+
+            self.edits.push(edit);
+        }
+
+        Ok(())
+    }
+}
+
+fn message_conflicts(scope: &str, message: &ast::Message, conflicts: &mut Vec<Conflict>) {
+    let name = descriptor::names::join(scope, message.ident);
+    let mut names = HashSet::new();
+    let mut numbers = HashSet::new();
+
+    for field in validate::message_fields(message) {
+        let duplicates = [
+            (!names.insert(field.ident), format!("name {}", field.ident)),
+            (
+                !numbers.insert(field.index),
+                format!("number {}", field.index),
+            ),
+        ];
+
+        for (_, what) in duplicates.into_iter().filter(|(duplicate, _)| *duplicate) {
+            conflicts.push(Conflict {
+                location: format!("message {}", name),
+                message: format!("field {} is used by both sides", what),
+            });
+        }
+    }
+
+    for entry in &message.entries {
+        match entry {
+            ast::MessageEntry::Message(nested) => message_conflicts(&name, nested, conflicts),
+            ast::MessageEntry::Enum(r#enum) => enum_conflicts(&name, r#enum, conflicts),
+            _ => {}
+        }
+    }
+}
+
+fn enum_conflicts(scope: &str, r#enum: &ast::Enum, conflicts: &mut Vec<Conflict>) {
+    let location = format!("enum {}", descriptor::names::join(scope, r#enum.ident));
+    let allow_alias = validate::allows_alias(r#enum);
+    let mut names = HashSet::new();
+    let mut numbers = HashSet::new();
+
+    for entry in &r#enum.entries {
+        let ast::EnumEntry::Pair { ident, value, .. } = entry else {
+            continue;
+        };
+
+        if !names.insert(*ident) {
+            conflicts.push(Conflict {
+                location: location.clone(),
+                message: format!("value {} is added by both sides", ident),
+            });
+        }
+
+        if !numbers.insert(*value) && !allow_alias {
+            conflicts.push(Conflict {
+                location: location.clone(),
+                message: format!("value number {} is used by both sides", value),
+            });
+        }
+    }
+}
+
+pub fn merge(base: &str, ours: &str, theirs: &str) -> Result<Merge, EditError> {
+    let base = Version::new("base", base)?;
+    let ours = Version::new("ours", ours)?;
+    let theirs = Version::new("theirs", theirs)?;
+
+    let mut merger = Merger {
+        base: &base,
+        ours: &ours,
+        theirs: &theirs,
+        edits: vec![],
+        conflicts: vec![],
+    };
+    merger.block(
+        "",
+        Some(&(Block::File(&base.file), vec![])),
+        &(Block::File(&ours.file), vec![]),
+        &(Block::File(&theirs.file), vec![]),
+    )?;
+
+    let Merger {
+        edits,
+        mut conflicts,
+        ..
+    } = merger;
+    let source = edit::apply(ours.source, &edits);
+
+    // additions on both sides that don't collide entry by entry may still clash
+    if conflicts.is_empty() {
+        let file = parser::parse(&source)
+            .map_err(|error| EditError::Parse(parser::error_message(&source, &error)))?;
+
+        for entry in &file {
+            match entry {
+                ast::FileEntry::Message(message) => message_conflicts("", message, &mut conflicts),
+                ast::FileEntry::Enum(r#enum) => enum_conflicts("", r#enum, &mut conflicts),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(Merge { source, conflicts })
+}
@@ -0,0 +1,831 @@
+// Changes addressed by symbol names rather than entry paths, for scripted edits. Each one returns
+// the edits to make, leaving everything else in the source untouched.
+
+use crate::edit::{self, Edit, EditError};
+use crate::layout::Layout;
+use crate::validate;
+use parser::ast;
+use parser::lexer::{Lexer, Token};
+use resolver::Node;
+use std::ops::Range;
+
+fn parse(source: &str) -> Result<ast::File<'_>, EditError> {
+    parser::parse(source).map_err(|error| EditError::Parse(parser::error_message(source, &error)))
+}
+
+fn package<'a>(file: &ast::File<'a>) -> &'a str {
+    file.iter()
+        .find_map(|entry| match entry {
+            ast::FileEntry::Package(package) => Some(*package),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn is_proto3(file: &ast::File) -> bool {
+    file.iter()
+        .any(|entry| matches!(entry, ast::FileEntry::Syntax("proto3")))
+}
+
+// Path of the entry named `part` among the children of the node; fields of oneofs are found
+// from their message too.
+fn child(node: Node, part: &str) -> Option<Vec<usize>> {
+    let index = match node {
+        Node::Message(message) => {
+            return message
+                .entries
+                .iter()
+                .enumerate()
+                .find_map(|(index, entry)| match entry {
+                    ast::MessageEntry::Message(message) if message.ident == part => {
+                        Some(vec![index])
+                    }
+                    ast::MessageEntry::Enum(r#enum) if r#enum.ident == part => Some(vec![index]),
+                    ast::MessageEntry::Field(field) if field.ident == part => Some(vec![index]),
+                    ast::MessageEntry::OneOf(oneof) if oneof.ident == part => Some(vec![index]),
+                    ast::MessageEntry::OneOf(oneof) => {
+                        child(Node::OneOf(oneof), part).map(|path| [vec![index], path].concat())
+                    }
+                    _ => None,
+                });
+        }
+        Node::Enum(r#enum) => r#enum.entries.iter().position(
+            |entry| matches!(entry, ast::EnumEntry::Pair { ident, .. } if *ident == part),
+        ),
+        Node::Service(service) => service
+            .entries
+            .iter()
+            .position(|entry| matches!(entry, ast::ServiceEntry::Rpc(rpc) if rpc.ident == part)),
+        Node::OneOf(oneof) => oneof.entries.iter().position(
+            |entry| matches!(entry, ast::OneOfEntry::Field(field) if field.ident == part),
+        ),
+        _ => None,
+    };
+
+    index.map(|index| vec![index])
+}
+
+// Entry path of a definition in the file by its name, fully-qualified or relative to the package,
+// e.g. `pkg.User`, `User.Address` or `pkg.User.email`.
+pub fn find(file: &ast::File, name: &str) -> Option<Vec<usize>> {
+    let name = name.strip_prefix('.').unwrap_or(name);
+    let package = package(file);
+    let name = match package.is_empty() {
+        true => name,
+        false => name
+            .strip_prefix(package)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .unwrap_or(name),
+    };
+
+    let mut parts = name.split('.');
+    let first = parts.next()?;
+    let mut path = vec![file.iter().position(|entry| match entry {
+        ast::FileEntry::Message(message) => message.ident == first,
+        ast::FileEntry::Enum(r#enum) => r#enum.ident == first,
+        ast::FileEntry::Service(service) => service.ident == first,
+        _ => false,
+    })?];
+
+    for part in parts {
+        let node = Node::at(file, &path)?;
+        path.extend(child(node, part)?);
+    }
+
+    Some(path)
+}
+
+fn find_node<'r, 'a>(
+    file: &'r ast::File<'a>,
+    name: &str,
+) -> Result<(Vec<usize>, Node<'r, 'a>), EditError> {
+    find(file, name)
+        .and_then(|path| Some((path.clone(), Node::at(file, &path)?)))
+        .ok_or_else(|| EditError::Undefined(name.to_string()))
+}
+
+// Identifier of the definition as borrowed from the source.
+pub fn ident_of<'a>(node: Node<'_, 'a>) -> Option<&'a str> {
+    match node {
+        Node::Message(message) => Some(message.ident),
+        Node::Enum(r#enum) => Some(r#enum.ident),
+        Node::EnumValue(ast::EnumEntry::Pair { ident, .. }) => Some(ident),
+        Node::Service(service) => Some(service.ident),
+        Node::Rpc(rpc) => Some(rpc.ident),
+        Node::Field(field) => Some(field.ident),
+        Node::OneOf(oneof) => Some(oneof.ident),
+        _ => None,
+    }
+}
+
+// Index after the last entry matching `preferred`, or after the last matching `fallback`.
+pub(crate) fn after_last<T>(
+    entries: &[T],
+    preferred: impl Fn(&T) -> bool,
+    fallback: impl Fn(&T) -> bool,
+) -> usize {
+    entries
+        .iter()
+        .rposition(preferred)
+        .or_else(|| entries.iter().rposition(fallback))
+        .map_or(0, |index| index + 1)
+}
+
+pub struct NewField<'s> {
+    pub modifier: ast::FieldModifier,
+    pub r#type: &'s str,
+    pub name: &'s str,
+    // The next free number if not given.
+    pub number: Option<i64>,
+    // Oneof of the message to add the field to.
+    pub oneof: Option<&'s str>,
+}
+
+// Adds the field after the last field of the message or oneof.
+pub fn add_field(source: &str, message: &str, field: NewField) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let layout = Layout::new(source, &file);
+
+    let (path, Node::Message(node)) = find_node(&file, message)? else {
+        return Err(EditError::Invalid(format!("{} is not a message", message)));
+    };
+
+    let new = ast::Field {
+        modifier: field.modifier,
+        r#type: field.r#type,
+        ident: field.name,
+        index: field
+            .number
+            .unwrap_or_else(|| validate::next_field_number(node)),
+        options: vec![],
+    };
+
+    validate::check_label(&new, is_proto3(&file), field.oneof.is_some())
+        .and_then(|()| validate::check_field(node, &new, None))
+        .map_err(EditError::Invalid)?;
+
+    let (parent, index) = match field.oneof {
+        Some(oneof) => {
+            let (index, oneof) = node
+                .entries
+                .iter()
+                .enumerate()
+                .find_map(|(index, entry)| match entry {
+                    ast::MessageEntry::OneOf(other) if other.ident == oneof => Some((index, other)),
+                    _ => None,
+                })
+                .ok_or_else(|| EditError::Undefined(format!("{}.{}", message, oneof)))?;
+
+            let position = after_last(
+                &oneof.entries,
+                |entry| matches!(entry, ast::OneOfEntry::Field(_)),
+                |entry| matches!(entry, ast::OneOfEntry::Option(_)),
+            );
+
+            ([path, vec![index]].concat(), position)
+        }
+        None => {
+            let position = after_last(
+                &node.entries,
+                |entry| matches!(entry, ast::MessageEntry::Field(_)),
+                |entry| matches!(entry, ast::MessageEntry::Option(_)),
+            );
+
+            (path, position)
+        }
+    };
+
+    let text = formatter::field_line(&new);
+    Ok(vec![edit::insert(source, &layout, &parent, index, &text)?])
+}
+
+pub fn remove_field(source: &str, message: &str, field: &str) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let name = format!("{}.{}", message, field);
+
+    let (path, Node::Field(_)) = find_node(&file, &name)? else {
+        return Err(EditError::Invalid(format!("{} is not a field", name)));
+    };
+
+    remove_at(source, &path)
+}
+
+pub fn remove_enum_value(source: &str, r#enum: &str, value: &str) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let name = format!("{}.{}", r#enum, value);
+
+    let (path, Node::EnumValue(_)) = find_node(&file, &name)? else {
+        return Err(EditError::Invalid(format!("{} is not an enum value", name)));
+    };
+
+    remove_at(source, &path)
+}
+
+// What goes away with the entry at the path: the oneof of its last field with it. An enum keeps
+// its last value, and in proto3 a zero value first.
+fn removed(file: &ast::File, path: &[usize]) -> Result<Vec<usize>, EditError> {
+    let Some((&index, parent)) = path.split_last() else {
+        return Err(EditError::NotFound(path.to_vec()));
+    };
+
+    match (Node::at(file, parent), Node::at(file, path)) {
+        (Some(Node::OneOf(oneof)), Some(Node::Field(_)))
+            if oneof
+                .entries
+                .iter()
+                .filter(|entry| matches!(entry, ast::OneOfEntry::Field(_)))
+                .count()
+                == 1 =>
+        {
+            Ok(parent.to_vec())
+        }
+        (Some(Node::Enum(node)), Some(Node::EnumValue(_))) => {
+            let values: Vec<(usize, i64)> = node
+                .entries
+                .iter()
+                .enumerate()
+                .filter_map(|(index, entry)| match entry {
+                    ast::EnumEntry::Pair { value, .. } => Some((index, *value)),
+                    _ => None,
+                })
+                .collect();
+
+            match values.as_slice() {
+                [_] => Err(EditError::Invalid(format!(
+                    "{} can't lose its last value",
+                    node.ident
+                ))),
+                [(first, _), (_, next), ..] if is_proto3(file) && *first == index && *next != 0 => {
+                    Err(EditError::Invalid(
+                        "The first enum value must be zero in proto3".to_string(),
+                    ))
+                }
+                _ => Ok(path.to_vec()),
+            }
+        }
+        _ => Ok(path.to_vec()),
+    }
+}
+
+// Removes the entry at the path, along with its oneof if it is the last field of one.
+pub fn remove_at(source: &str, path: &[usize]) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let layout = Layout::new(source, &file);
+
+    Ok(vec![edit::remove(source, &layout, &removed(&file, path)?)?])
+}
+
+// Tokens of the range with their offsets in the source.
+fn tokens(source: &str, range: Range<usize>) -> Vec<(usize, Token<'_>, usize)> {
+    Lexer::new(&source[range.clone()])
+        .map_while(Result::ok)
+        .map(|(start, token, end)| (range.start + start, token, range.start + end))
+        .collect()
+}
+
+// Range of the `[...]` option list of a field or an enum value.
+fn options_list(source: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let tokens = tokens(source, range);
+    let open = tokens
+        .iter()
+        .position(|(_, token, _)| *token == Token::OpenBracket)?;
+    let close = tokens
+        .iter()
+        .rposition(|(_, token, _)| *token == Token::CloseBracket)?;
+
+    Some(tokens[open].0..tokens[close].2)
+}
+
+// Range of `key = value` in an option list, from the key up to the comma or bracket after it.
+fn option_in_list(source: &str, list: Range<usize>, key: &str) -> Option<Range<usize>> {
+    let start = edit::span_of(source, key)?.start;
+    let mut depth = 0usize;
+
+    for (offset, token, _) in tokens(source, start..list.end) {
+        match token {
+            Token::Comma | Token::CloseBracket if depth == 0 => return Some(start..offset),
+            Token::OpenBrace | Token::OpenBracket | Token::OpenPth => depth += 1,
+            Token::CloseBrace | Token::CloseBracket | Token::ClosePth => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn same_key(a: &str, b: &str) -> bool {
+    a.split_whitespace().collect::<String>() == b.split_whitespace().collect::<String>()
+}
+
+// Sets an option of the file (no target), of a definition, or of a field or an enum value. The
+// value is written as in a .proto file, e.g. `true` or `"text"`.
+pub fn set_option(
+    source: &str,
+    target: Option<&str>,
+    key: &str,
+    value: &str,
+) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let layout = Layout::new(source, &file);
+    let statement = format!("option {} = {};", key, value);
+
+    let Some(target) = target else {
+        if let Some(index) = file.iter().position(
+            |entry| matches!(entry, ast::FileEntry::Option(option) if same_key(option.key, key)),
+        ) {
+            return Ok(vec![edit::replace(source, &layout, &[index], &statement)?]);
+        }
+
+        let index = after_last(
+            &file,
+            |entry| matches!(entry, ast::FileEntry::Option(_)),
+            |entry| {
+                matches!(
+                    entry,
+                    ast::FileEntry::Syntax(_)
+                        | ast::FileEntry::Package(_)
+                        | ast::FileEntry::Import(_)
+                )
+            },
+        );
+
+        // the first option is set apart from the header
+        let text = match file
+            .iter()
+            .any(|entry| matches!(entry, ast::FileEntry::Option(_)))
+        {
+            true => statement,
+            false => format!("\n{}", statement),
+        };
+
+        return Ok(vec![edit::insert(source, &layout, &[], index, &text)?]);
+    };
+
+    let (path, node) = find_node(&file, target)?;
+
+    // options of statements, as a list of flags telling the options with the key apart
+    let options: Vec<Option<bool>> = match node {
+        Node::Message(message) => message
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::MessageEntry::Option(option) => Some(same_key(option.key, key)),
+                _ => None,
+            })
+            .collect(),
+        Node::Enum(r#enum) => r#enum
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::EnumEntry::Option(option) => Some(same_key(option.key, key)),
+                _ => None,
+            })
+            .collect(),
+        Node::Service(service) => service
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::ServiceEntry::Option(option) => Some(same_key(option.key, key)),
+                _ => None,
+            })
+            .collect(),
+        Node::OneOf(oneof) => oneof
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::OneOfEntry::Option(option) => Some(same_key(option.key, key)),
+                _ => None,
+            })
+            .collect(),
+        Node::Rpc(rpc) => rpc
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::RpcEntry::Option(option) => Some(same_key(option.key, key)),
+                _ => None,
+            })
+            .collect(),
+        Node::Field(ast::Field { options, .. })
+        | Node::EnumValue(ast::EnumEntry::Pair { options, .. }) => {
+            return set_list_option(source, &layout, &path, options, key, value);
+        }
+        _ => return Err(EditError::Invalid(format!("{} can't have options", target))),
+    };
+
+    if let Some(index) = options.iter().position(|option| *option == Some(true)) {
+        let path = [path, vec![index]].concat();
+        return Ok(vec![edit::replace(source, &layout, &path, &statement)?]);
+    }
+
+    let entry = layout
+        .entry(&path)
+        .ok_or_else(|| EditError::NotFound(path.clone()))?;
+
+    // an rpc ending with `;` gets a block for its options
+    if entry.body.is_none() {
+        let indent = edit::indentation(source, entry.range.start);
+        let text = format!(
+            " {{\n{}{}{}\n{}}}",
+            indent,
+            edit::indent_unit(source),
+            statement,
+            indent
+        );
+
+        return Ok(vec![Edit::replace(
+            entry.range.end - 1..entry.range.end,
+            text,
+        )]);
+    }
+
+    let index = after_last(&options, |option| option.is_some(), |_| false);
+    Ok(vec![edit::insert(
+        source, &layout, &path, index, &statement,
+    )?])
+}
+
+fn set_list_option(
+    source: &str,
+    layout: &Layout,
+    path: &[usize],
+    options: &[ast::Option],
+    key: &str,
+    value: &str,
+) -> Result<Vec<Edit>, EditError> {
+    let entry = layout
+        .entry(path)
+        .ok_or_else(|| EditError::NotFound(path.to_vec()))?;
+    let option = format!("{} = {}", key, value);
+
+    let Some(list) = options_list(source, entry.range.clone()) else {
+        // before the `;` ending the entry
+        let end = source[..entry.range.end - 1].trim_end().len();
+        return Ok(vec![Edit::insert(end, format!(" [{}]", option))]);
+    };
+
+    if let Some(existing) = options.iter().find(|other| same_key(other.key, key)) {
+        let range = option_in_list(source, list.clone(), existing.key)
+            .ok_or_else(|| EditError::NotFound(path.to_vec()))?;
+
+        return Ok(vec![Edit::replace(
+            range.start..range.start + source[range].trim_end().len(),
+            option,
+        )]);
+    }
+
+    let end = source[..list.end - 1].trim_end().len();
+    Ok(vec![Edit::insert(end, format!(", {}", option))])
+}
+
+// Adds the value after the last value of the enum, numbered after the largest one if no number
+// is given.
+pub fn add_enum_value(
+    source: &str,
+    r#enum: &str,
+    name: &str,
+    number: Option<i64>,
+) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let layout = Layout::new(source, &file);
+
+    let (path, Node::Enum(node)) = find_node(&file, r#enum)? else {
+        return Err(EditError::Invalid(format!("{} is not an enum", r#enum)));
+    };
+
+    let values: Vec<i64> = node
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            ast::EnumEntry::Pair { value, .. } => Some(*value),
+            _ => None,
+        })
+        .collect();
+    let number = number.unwrap_or_else(|| values.iter().max().map_or(0, |max| max + 1));
+
+    validate::check_enum_value(node, name, number, None).map_err(EditError::Invalid)?;
+
+    if is_proto3(&file) && values.is_empty() && number != 0 {
+        return Err(EditError::Invalid(
+            "The first enum value must be zero in proto3".to_string(),
+        ));
+    }
+
+    let index = after_last(
+        &node.entries,
+        |entry| matches!(entry, ast::EnumEntry::Pair { .. }),
+        |entry| matches!(entry, ast::EnumEntry::Option(_)),
+    );
+    let text = format!("{} = {};", name, number);
+
+    Ok(vec![edit::insert(source, &layout, &path, index, &text)?])
+}
+
+// Numbers and names used and reserved in a message or an enum, the `reserved` statements given
+// by their indices among the entries.
+struct Block<'r, 'a> {
+    used: Vec<(&'a str, i64)>,
+    indices: Vec<(usize, &'r [ast::Range])>,
+    idents: Vec<(usize, &'r [&'a str])>,
+    // where new `reserved` statements go: after the last one, or at the end
+    end: usize,
+}
+
+fn block<'r, 'a>(node: Node<'r, 'a>) -> Option<Block<'r, 'a>> {
+    let mut block = Block {
+        used: vec![],
+        indices: vec![],
+        idents: vec![],
+        end: 0,
+    };
+
+    let len = match node {
+        Node::Message(message) => {
+            block.used = validate::message_fields(message)
+                .map(|field| (field.ident, field.index))
+                .collect();
+
+            for (index, entry) in message.entries.iter().enumerate() {
+                match entry {
+                    ast::MessageEntry::ReservedIndices(ranges) => {
+                        block.indices.push((index, ranges))
+                    }
+                    ast::MessageEntry::ReservedIdents(idents) => block.idents.push((index, idents)),
+                    _ => {}
+                }
+            }
+
+            message.entries.len()
+        }
+        Node::Enum(r#enum) => {
+            for (index, entry) in r#enum.entries.iter().enumerate() {
+                match entry {
+                    ast::EnumEntry::Pair { ident, value, .. } => block.used.push((ident, *value)),
+                    ast::EnumEntry::ReservedIndices(ranges) => block.indices.push((index, ranges)),
+                    ast::EnumEntry::ReservedIdents(idents) => block.idents.push((index, idents)),
+                    _ => {}
+                }
+            }
+
+            r#enum.entries.len()
+        }
+        _ => return None,
+    };
+
+    block.end = (block.indices.iter().map(|(index, _)| *index))
+        .chain(block.idents.iter().map(|(index, _)| *index))
+        .max()
+        .map_or(len, |index| index + 1);
+
+    Some(block)
+}
+
+// Start and exclusive end of the range, `max` being the largest integer.
+fn bounds(range: &ast::Range) -> (i64, i64) {
+    match range.end {
+        ast::RangeEnd::Integer(end) => (range.start, end),
+        ast::RangeEnd::Max => (range.start, i64::MAX),
+    }
+}
+
+// Sorted ranges with the overlapping and adjacent ones merged.
+fn coalesce(bounds: impl IntoIterator<Item = (i64, i64)>) -> Vec<ast::Range> {
+    let mut bounds: Vec<_> = bounds.into_iter().collect();
+    bounds.sort();
+
+    let mut merged: Vec<(i64, i64)> = vec![];
+
+    for (start, end) in bounds {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(start, end)| ast::Range {
+            start,
+            end: match end {
+                i64::MAX => ast::RangeEnd::Max,
+                end => ast::RangeEnd::Integer(end),
+            },
+        })
+        .collect()
+}
+
+// Reserves numbers and names in a message or an enum, after its last `reserved` statement.
+pub fn reserve(
+    source: &str,
+    target: &str,
+    ranges: &[ast::Range],
+    names: &[&str],
+) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let layout = Layout::new(source, &file);
+    let (path, node) = find_node(&file, target)?;
+
+    let block = block(node).ok_or_else(|| {
+        EditError::Invalid(format!("{} is neither a message nor an enum", target))
+    })?;
+
+    for (ident, number) in &block.used {
+        if let Some(range) = ranges.iter().find(|range| {
+            let (start, end) = bounds(range);
+            (start..end).contains(number)
+        }) {
+            return Err(EditError::Invalid(format!(
+                "{} is used by {}",
+                formatter::ranges_list(std::slice::from_ref(range)),
+                ident
+            )));
+        }
+
+        if names.contains(ident) {
+            return Err(EditError::Invalid(format!("{} is in use", ident)));
+        }
+    }
+
+    let mut lines = vec![];
+
+    if !ranges.is_empty() {
+        lines.push(format!("reserved {};", formatter::ranges_list(ranges)));
+    }
+
+    if !names.is_empty() {
+        lines.push(format!("reserved {};", formatter::idents_list(names)));
+    }
+
+    lines
+        .iter()
+        .map(|line| edit::insert(source, &layout, &path, block.end, line))
+        .collect()
+}
+
+// Removes a field or an enum value by its name and reserves its number and name.
+pub fn retire(source: &str, name: &str) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let path = find(&file, name).ok_or_else(|| EditError::Undefined(name.to_string()))?;
+
+    retire_at(source, &path)
+}
+
+// Removes the field or enum value at the path and reserves its number and name, so that they
+// can't be reused by accident. The number goes to the `reserved` statement with a range next to
+// it, or else to the last one, and the name to the last statement of names.
+pub fn retire_at(source: &str, path: &[usize]) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let layout = Layout::new(source, &file);
+
+    let (ident, number) = match Node::at(&file, path) {
+        Some(Node::Field(field)) => (field.ident, field.index),
+        Some(Node::EnumValue(ast::EnumEntry::Pair { ident, value, .. })) => (*ident, *value),
+        Some(_) => {
+            return Err(EditError::Invalid(
+                "Only fields and enum values can be reserved".to_string(),
+            ));
+        }
+        None => return Err(EditError::NotFound(path.to_vec())),
+    };
+
+    // fields of a oneof are reserved in its message
+    let mut parent = &path[..path.len() - 1];
+    if let Some(Node::OneOf(_)) = Node::at(&file, parent) {
+        parent = &parent[..parent.len() - 1];
+    }
+
+    let block = Node::at(&file, parent)
+        .and_then(block)
+        .ok_or_else(|| EditError::Invalid("Extensions can't be reserved".to_string()))?;
+
+    // an alias of the value keeps the number in use
+    let numbers = match block
+        .used
+        .iter()
+        .filter(|(_, other)| *other == number)
+        .count()
+    {
+        1 => vec![number],
+        _ => vec![],
+    };
+
+    let mut edits = vec![edit::remove(source, &layout, &removed(&file, path)?)?];
+    edits.extend(merge_reservations(
+        source,
+        &layout,
+        parent,
+        &block,
+        &numbers,
+        &[ident],
+    )?);
+
+    Ok(edits)
+}
+
+// Reserves the numbers and names freed by removing or renumbering fields or enum values of a
+// message or an enum, merging them into its `reserved` statements like `retire` does. The ones
+// already reserved or still in use are left alone.
+pub fn reserve_freed(
+    source: &str,
+    target: &str,
+    numbers: &[i64],
+    names: &[&str],
+) -> Result<Vec<Edit>, EditError> {
+    let file = parse(source)?;
+    let layout = Layout::new(source, &file);
+    let (path, node) = find_node(&file, target)?;
+
+    let block = block(node).ok_or_else(|| {
+        EditError::Invalid(format!("{} is neither a message nor an enum", target))
+    })?;
+
+    let reserved = |number: &i64| {
+        block.indices.iter().any(|(_, ranges)| {
+            ranges.iter().any(|range| {
+                let (start, end) = bounds(range);
+                (start..end).contains(number)
+            })
+        })
+    };
+    let numbers: Vec<_> = numbers
+        .iter()
+        .filter(|number| !reserved(number) && !block.used.iter().any(|(_, used)| used == *number))
+        .copied()
+        .collect();
+    let names: Vec<_> = names
+        .iter()
+        .filter(|name| {
+            !block.idents.iter().any(|(_, idents)| idents.contains(name))
+                && !block.used.iter().any(|(used, _)| used == *name)
+        })
+        .copied()
+        .collect();
+
+    merge_reservations(source, &layout, &path, &block, &numbers, &names)
+}
+
+// Adds the numbers and names to the `reserved` statements of the block at the path: a number to
+// the statement with a range next to it, or else to the last one, and a name to the last statement
+// of names. New statements go after the others when there are none.
+fn merge_reservations(
+    source: &str,
+    layout: &Layout,
+    path: &[usize],
+    block: &Block,
+    numbers: &[i64],
+    names: &[&str],
+) -> Result<Vec<Edit>, EditError> {
+    let statement = |index: usize| [path, &[index]].concat();
+    let mut merged: Vec<(usize, &[ast::Range], Vec<i64>)> = vec![];
+    let mut unmerged = vec![];
+
+    for number in numbers {
+        let next_to = |(_, ranges): &&(usize, &[ast::Range])| {
+            ranges.iter().any(|range| {
+                let (start, end) = bounds(range);
+                start == number + 1 || end == *number
+            })
+        };
+
+        match block.indices.iter().find(next_to).or(block.indices.last()) {
+            Some((index, ranges)) => match merged.iter_mut().find(|(other, ..)| other == index) {
+                Some((.., numbers)) => numbers.push(*number),
+                None => merged.push((*index, ranges, vec![*number])),
+            },
+            None => unmerged.push(*number),
+        }
+    }
+
+    let mut edits = vec![];
+    let mut lines = vec![];
+
+    for (index, ranges, numbers) in merged {
+        let numbers = numbers.into_iter().map(|number| (number, number + 1));
+        let ranges = coalesce(ranges.iter().map(bounds).chain(numbers));
+        let text = format!("reserved {};", formatter::ranges_list(&ranges));
+
+        edits.push(edit::replace(source, layout, &statement(index), &text)?);
+    }
+
+    if !unmerged.is_empty() {
+        let ranges = coalesce(unmerged.into_iter().map(|number| (number, number + 1)));
+        lines.push(format!("reserved {};", formatter::ranges_list(&ranges)));
+    }
+
+    if !names.is_empty() {
+        match block.idents.last() {
+            Some((index, idents)) => {
+                let idents = [idents, names].concat();
+                let text = format!("reserved {};", formatter::idents_list(&idents));
+
+                edits.push(edit::replace(source, layout, &statement(*index), &text)?);
+            }
+            None => lines.push(format!("reserved {};", formatter::idents_list(names))),
+        }
+    }
+
+    for line in lines {
+        edits.push(edit::insert(source, layout, path, block.end, &line)?);
+    }
+
+    Ok(edits)
+}
@@ -0,0 +1,622 @@
+// Changes spanning every file of a workspace, made on top of the resolver so that references keep
+// pointing at the same symbols.
+
+use crate::edit::{self, Edit, EditError};
+use crate::layout::Layout;
+use crate::{ops, validate};
+use descriptor::names;
+use parser::ast;
+use resolver::{File, Reference, ReferenceKind, SymbolKind, Workspace};
+use std::collections::HashSet;
+
+// Edits of one file of a workspace, given by its index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEdits {
+    pub file: usize,
+    pub edits: Vec<Edit>,
+}
+
+// The name with the component naming the renamed symbol replaced, when it is written out; `Item`
+// referring to `pkg.Order.Item` doesn't mention `pkg.Order`.
+fn mention(name: &str, target: &str, old: &str, ident: &str) -> Option<String> {
+    if target != old && !target.starts_with(&format!("{}.", old)) {
+        return None;
+    }
+
+    let (dot, relative) = match name.strip_prefix('.') {
+        Some(relative) => (".", relative),
+        None => ("", name),
+    };
+
+    let mut components: Vec<&str> = relative.split('.').collect();
+    let first = target.split('.').count().checked_sub(components.len())?;
+    let index = (old.split('.').count() - 1).checked_sub(first)?;
+
+    components[index] = ident;
+    Some(format!("{}{}", dot, components.join(".")))
+}
+
+// Shortest name resolving to the symbol from the place of the reference, the absolute one if
+// every partial name is taken by something else or the reference was absolute already.
+fn qualify(workspace: &Workspace, reference: &Reference, target: &str) -> String {
+    let components: Vec<&str> = target.split('.').collect();
+
+    if reference.name.starts_with('.') {
+        return format!(".{}", target);
+    }
+
+    (1..=components.len())
+        .map(|count| components[components.len() - count..].join("."))
+        .find(|name| {
+            workspace
+                .resolve_name(reference, name)
+                .is_ok_and(|symbol| symbol.name == target)
+        })
+        .unwrap_or_else(|| format!(".{}", target))
+}
+
+// Renames the symbol and updates the references to it in every file. References whose meaning
+// would change, either because they mention the symbol or because the new name shadows what they
+// referred to, are qualified again.
+pub fn rename(
+    workspace: &Workspace,
+    name: &str,
+    new_ident: &str,
+) -> Result<Vec<FileEdits>, EditError> {
+    let symbol = workspace
+        .symbol(name)
+        .ok_or_else(|| EditError::Undefined(name.to_string()))?;
+
+    if symbol.kind == SymbolKind::Package {
+        return Err(EditError::Invalid("Packages can't be renamed".to_string()));
+    }
+
+    let ident = workspace
+        .node(symbol)
+        .and_then(ops::ident_of)
+        .ok_or_else(|| EditError::Undefined(name.to_string()))?;
+
+    validate::check_ident(new_ident).map_err(EditError::Invalid)?;
+
+    let old = symbol.name.clone();
+    let new = names::join(symbol.scope(), new_ident);
+
+    if old == new {
+        return Ok(vec![]);
+    }
+
+    if workspace.symbol(&new).is_some() {
+        return Err(EditError::Invalid(format!("{} is already defined", new)));
+    }
+
+    let renamed = |target: &str| match target.strip_prefix(old.as_str()) {
+        Some(rest) if rest.is_empty() || rest.starts_with('.') => format!("{}{}", new, rest),
+        _ => target.to_string(),
+    };
+
+    let files = workspace.files();
+
+    // what every reference resolves to now, and the names to write instead
+    let references: Vec<Vec<(Reference, Option<String>)>> = (0..files.len())
+        .map(|file| {
+            workspace
+                .references(file)
+                .into_iter()
+                .map(|reference| {
+                    let target = workspace
+                        .resolve_reference(&reference)
+                        .ok()
+                        .map(|symbol| symbol.name.clone());
+                    (reference, target)
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut replaced: Vec<Vec<Option<String>>> = references
+        .iter()
+        .map(|references| {
+            references
+                .iter()
+                .map(|(reference, target)| {
+                    mention(reference.name, target.as_deref()?, &old, new_ident)
+                })
+                .collect()
+        })
+        .collect();
+
+    let edits = |replaced: &[Vec<Option<String>>]| -> Vec<Vec<Edit>> {
+        (0..files.len())
+            .map(|file| {
+                let source = files[file].source;
+                let mut edits: Vec<Edit> = references[file]
+                    .iter()
+                    .zip(&replaced[file])
+                    .filter_map(|((reference, _), name)| {
+                        edit::rename(source, reference.name, name.as_deref()?)
+                    })
+                    .collect();
+
+                if file == symbol.file {
+                    edits.extend(edit::rename(source, ident, new_ident));
+                }
+
+                edits
+            })
+            .collect()
+    };
+
+    // resolve everything again in the renamed files
+    let sources: Vec<String> = edits(&replaced)
+        .iter()
+        .zip(files)
+        .map(|(edits, file)| edit::apply(file.source, edits))
+        .collect();
+
+    let result = reparse(workspace, &sources)?;
+
+    for (file, references) in references.iter().enumerate() {
+        let now = result.references(file);
+
+        for (index, ((reference, target), now)) in references.iter().zip(&now).enumerate() {
+            let Some(target) = target else {
+                continue;
+            };
+
+            let expected = renamed(target);
+            if result
+                .resolve_reference(now)
+                .is_ok_and(|symbol| symbol.name == expected)
+            {
+                continue;
+            }
+
+            if reference.kind == ReferenceKind::DefaultValue {
+                return Err(EditError::Invalid(format!(
+                    "Default value {} would refer to another value",
+                    reference.name
+                )));
+            }
+
+            replaced[file][index] = Some(qualify(&result, now, &expected));
+        }
+    }
+
+    Ok(edits(&replaced)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, edits)| !edits.is_empty())
+        .map(|(file, edits)| FileEdits { file, edits })
+        .collect())
+}
+
+// Workspace of the sources, named as the files of the original one.
+fn reparse<'s>(
+    workspace: &Workspace<'s>,
+    sources: &'s [String],
+) -> Result<Workspace<'s>, EditError> {
+    let files = sources
+        .iter()
+        .zip(workspace.files())
+        .map(|(source, file)| {
+            parser::parse(source)
+                .map(|ast| File {
+                    name: file.name,
+                    source,
+                    ast,
+                })
+                .map_err(|error| EditError::Parse(parser::error_message(source, &error)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Workspace::from_files(files))
+}
+
+fn import_lines(imports: &[(&str, bool)]) -> String {
+    imports
+        .iter()
+        .map(|(name, public)| match public {
+            true => format!("import public \"{}\";", name),
+            false => format!("import \"{}\";", name),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Import statements added after the imports of the file, or after its header.
+fn add_imports(file: &File, imports: &[(&str, bool)]) -> Result<Option<Edit>, EditError> {
+    if imports.is_empty() {
+        return Ok(None);
+    }
+
+    let layout = Layout::new(file.source, &file.ast);
+    let is_import = |entry: &ast::FileEntry| matches!(entry, ast::FileEntry::Import(_));
+    let index = ops::after_last(&file.ast, is_import, |entry| {
+        matches!(
+            entry,
+            ast::FileEntry::Syntax(_) | ast::FileEntry::Package(_)
+        )
+    });
+
+    let mut text = import_lines(imports);
+
+    // the first imports are set apart from the header
+    if !file.ast.iter().any(is_import) {
+        text.insert(0, '\n');
+    }
+
+    edit::insert(file.source, &layout, &[], index, &text).map(Some)
+}
+
+// Added imports and removed ones, given by the index of their statement. The added imports take
+// the place of the first removed one.
+fn update_imports(
+    file: &File,
+    added: &[(&str, bool)],
+    removed: &[usize],
+) -> Result<Vec<Edit>, EditError> {
+    let Some((&first, rest)) = removed.split_first() else {
+        return Ok(add_imports(file, added)?.into_iter().collect());
+    };
+
+    let layout = Layout::new(file.source, &file.ast);
+    let mut edits = vec![match added.is_empty() {
+        true => edit::remove(file.source, &layout, &[first])?,
+        false => {
+            let entry = layout
+                .entry(&[first])
+                .ok_or_else(|| EditError::NotFound(vec![first]))?;
+            Edit::replace(entry.range.clone(), import_lines(added))
+        }
+    }];
+
+    for &index in rest {
+        edits.push(edit::remove(file.source, &layout, &[index])?);
+    }
+
+    Ok(edits)
+}
+
+// The file and the files it makes visible to its importers through public imports.
+fn exported_files(workspace: &Workspace, file: usize) -> HashSet<usize> {
+    let mut files = HashSet::from([file]);
+    let mut pending = vec![file];
+
+    while let Some(file) = pending.pop() {
+        for import in workspace.file(file).imports() {
+            if import.modifier == ast::ImportModifier::Public
+                && let Some(import) = workspace.file_index(import.path)
+                && files.insert(import)
+            {
+                pending.push(import);
+            }
+        }
+    }
+
+    files
+}
+
+// Plain imports of the file that something was used from before, given the files the references
+// of the file pointed at, and nothing is used from after. Nothing is removed when some reference
+// can't be resolved.
+fn unused_imports(
+    before: (&Workspace, &[Option<usize>]),
+    after: (&Workspace, &[Option<usize>]),
+    file: usize,
+) -> Vec<usize> {
+    let used = |(workspace, targets): (&Workspace, &[Option<usize>]), path: &str| {
+        workspace.file_index(path).is_some_and(|import| {
+            let exported = exported_files(workspace, import);
+            targets
+                .iter()
+                .any(|target| target.is_some_and(|target| exported.contains(&target)))
+        })
+    };
+
+    if after.1.iter().any(Option::is_none) {
+        return vec![];
+    }
+
+    before
+        .0
+        .file(file)
+        .ast
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| match entry {
+            ast::FileEntry::Import(import) if import.modifier == ast::ImportModifier::None => {
+                (used(before, import.path) && !used(after, import.path)).then_some(index)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn has_cycle(workspace: &Workspace) -> bool {
+    fn visit(workspace: &Workspace, file: usize, state: &mut [u8]) -> bool {
+        match state[file] {
+            1 => return true,
+            2 => return false,
+            _ => state[file] = 1,
+        }
+
+        let cycle = workspace.file(file).imports().any(|import| {
+            workspace
+                .file_index(import.path)
+                .is_some_and(|import| visit(workspace, import, state))
+        });
+
+        state[file] = 2;
+        cycle
+    }
+
+    let mut state = vec![0; workspace.files().len()];
+    (0..state.len()).any(|file| visit(workspace, file, &mut state))
+}
+
+// Moves a top-level message, enum or service with its comments to the end of another file of the
+// workspace. The files referring to it get imports of the new file, or the old file keeps an
+// `import public` of it, and references are qualified again when the package differs. Imports
+// nothing is used from anymore are removed.
+pub fn move_definition(
+    workspace: &Workspace,
+    name: &str,
+    to: usize,
+    public: bool,
+) -> Result<Vec<FileEdits>, EditError> {
+    let symbol = workspace
+        .symbol(name)
+        .ok_or_else(|| EditError::Undefined(name.to_string()))?;
+
+    let (from, index) = match (symbol.kind, symbol.path.as_slice()) {
+        (SymbolKind::Message | SymbolKind::Enum | SymbolKind::Service, &[index]) => {
+            (symbol.file, index)
+        }
+        _ => {
+            return Err(EditError::Invalid(
+                "Only top-level messages, enums and services can be moved".to_string(),
+            ));
+        }
+    };
+
+    let files = workspace.files();
+    let (source, target) = (&files[from], &files[to]);
+
+    if from == to {
+        return Ok(vec![]);
+    }
+
+    if source.syntax() != target.syntax() {
+        return Err(EditError::Invalid(format!(
+            "{} uses {} and {} uses {}",
+            source.name,
+            source.syntax(),
+            target.name,
+            target.syntax()
+        )));
+    }
+
+    let old = symbol.name.clone();
+    let new = names::join(target.package(), symbol.ident());
+
+    if old != new && workspace.symbol(&new).is_some() {
+        return Err(EditError::Invalid(format!("{} is already defined", new)));
+    }
+
+    let is_moved = |target: &str| {
+        target
+            .strip_prefix(old.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    };
+    let renamed = |target: &str| match is_moved(target) {
+        true => format!("{}{}", new, &target[old.len()..]),
+        false => target.to_string(),
+    };
+
+    let references: Vec<Vec<(Reference, Option<String>)>> = (0..files.len())
+        .map(|file| {
+            workspace
+                .references(file)
+                .into_iter()
+                .map(|reference| {
+                    let target = workspace
+                        .resolve_reference(&reference)
+                        .ok()
+                        .map(|symbol| symbol.name.clone());
+                    (reference, target)
+                })
+                .collect()
+        })
+        .collect();
+    let inside = |file: usize, reference: &Reference| file == from && reference.path[0] == index;
+
+    // imports every file needs to see what it referred to
+    let mut imports: Vec<Vec<(&str, bool)>> = vec![vec![]; files.len()];
+    let mut add = |file: usize, import: usize, public: bool| {
+        let name = files[import].name;
+        let imported = files[file].imports().any(|other| other.path == name);
+
+        if file != import && !imported && !imports[file].iter().any(|(other, _)| *other == name) {
+            imports[file].push((name, public));
+        }
+    };
+
+    if public {
+        add(from, to, true);
+    }
+
+    for (file, references) in references.iter().enumerate() {
+        for (reference, target) in references {
+            let Some(symbol) = target
+                .as_deref()
+                .and_then(|target| workspace.symbol(target))
+            else {
+                continue;
+            };
+
+            match (inside(file, reference), is_moved(&symbol.name)) {
+                (true, true) => {}
+                (true, false) => {
+                    if symbol.file == from || !workspace.visible_files(to).contains(&symbol.file) {
+                        add(to, symbol.file, false);
+                    }
+                }
+                // the old file keeps the moved symbols visible through its public import
+                (false, true) if public && workspace.visible_files(file).contains(&from) => {}
+                (false, true) => add(file, to, false),
+                (false, false) => {}
+            }
+        }
+    }
+
+    let layout = Layout::new(source.source, &source.ast);
+    let siblings = layout
+        .children(&[])
+        .ok_or_else(|| EditError::NotFound(vec![]))?;
+    let extent = edit::extent(source.source, siblings, index);
+    let removal = edit::remove(source.source, &layout, &[index])?;
+    let target_layout = Layout::new(target.source, &target.ast);
+
+    // edits of every file, with the text of the moved definition given separately
+    let edits = |moved: &str,
+                 replaced: &[Vec<Option<String>>],
+                 unused: &[Vec<usize>]|
+     -> Result<Vec<Vec<Edit>>, EditError> {
+        (0..files.len())
+            .map(|file| {
+                let mut edits = update_imports(&files[file], &imports[file], &unused[file])?;
+
+                if file == from {
+                    edits.push(removal.clone());
+                }
+
+                if file == to {
+                    edits.push(edit::insert(
+                        target.source,
+                        &target_layout,
+                        &[],
+                        target.ast.len(),
+                        moved,
+                    )?);
+                }
+
+                for ((reference, _), name) in references[file].iter().zip(&replaced[file]) {
+                    if let Some(name) = name
+                        && !inside(file, reference)
+                    {
+                        edits.extend(edit::rename(files[file].source, reference.name, name));
+                    }
+                }
+
+                Ok(edits)
+            })
+            .collect()
+    };
+
+    let mut replaced: Vec<Vec<Option<String>>> = references
+        .iter()
+        .map(|references| vec![None; references.len()])
+        .collect();
+
+    let sources: Vec<String> = edits(
+        &source.source[extent.clone()],
+        &replaced,
+        &vec![vec![]; files.len()],
+    )?
+    .iter()
+    .zip(files)
+    .map(|(edits, file)| edit::apply(file.source, edits))
+    .collect();
+    let result = reparse(workspace, &sources)?;
+
+    if has_cycle(&result) {
+        return Err(EditError::Invalid(format!(
+            "Moving {} to {} would make the imports cyclic",
+            name, target.name
+        )));
+    }
+
+    // references as they are in the changed files, the moved ones being at the end of the target
+    let mut before: Vec<Vec<(usize, usize)>> = (0..files.len())
+        .map(|file| {
+            (0..references[file].len())
+                .filter(|&position| !inside(file, &references[file][position].0))
+                .map(|position| (file, position))
+                .collect()
+        })
+        .collect();
+    let moved: Vec<(usize, usize)> = (0..references[from].len())
+        .filter(|&position| inside(from, &references[from][position].0))
+        .map(|position| (from, position))
+        .collect();
+    before[to].extend(moved);
+
+    for (file, before) in before.iter().enumerate() {
+        for (&(origin, position), now) in before.iter().zip(result.references(file)) {
+            let (reference, target) = &references[origin][position];
+            let Some(target) = target else {
+                continue;
+            };
+
+            let expected = renamed(target);
+            if result
+                .resolve_reference(&now)
+                .is_ok_and(|symbol| symbol.name == expected)
+            {
+                continue;
+            }
+
+            if reference.kind == ReferenceKind::DefaultValue {
+                return Err(EditError::Invalid(format!(
+                    "Default value {} would refer to another value",
+                    reference.name
+                )));
+            }
+
+            replaced[origin][position] = Some(qualify(&result, &now, &expected));
+        }
+    }
+
+    // the moved text with its references qualified again
+    let moved_edits: Vec<Edit> = references[from]
+        .iter()
+        .zip(&replaced[from])
+        .filter_map(|((reference, _), name)| {
+            let name = name.as_deref()?;
+            let range = edit::span_of(source.source, reference.name)?;
+            (range.start >= extent.start && range.end <= extent.end)
+                .then(|| Edit::replace(range.start - extent.start..range.end - extent.start, name))
+        })
+        .collect();
+    let moved = edit::apply(&source.source[extent], &moved_edits);
+
+    // files of the symbols every file refers to, before and after the move
+    let unused: Vec<Vec<usize>> = (0..files.len())
+        .map(|file| {
+            let targets_before: Vec<Option<usize>> = references[file]
+                .iter()
+                .map(|(_, target)| Some(workspace.symbol(target.as_deref()?)?.file))
+                .collect();
+            let targets_after: Vec<Option<usize>> = before[file]
+                .iter()
+                .map(|&(origin, position)| {
+                    let target = references[origin][position].1.as_deref()?;
+                    Some(result.symbol(&renamed(target))?.file)
+                })
+                .collect();
+
+            unused_imports(
+                (workspace, &targets_before),
+                (&result, &targets_after),
+                file,
+            )
+        })
+        .collect();
+
+    Ok(edits(&moved, &replaced, &unused)?
+        .into_iter()
+        .enumerate()
+        .filter(|(_, edits)| !edits.is_empty())
+        .map(|(file, edits)| FileEdits { file, edits })
+        .collect())
+}
@@ -0,0 +1,196 @@
+// SourceCodeInfo of a file as protoc records it for plugins: the span and the comments of every
+// declaration, identified by its path of field numbers and indices in the FileDescriptorProto.
+// Indices follow the compiler, e.g. the fields of oneofs are fields of their message and map
+// fields take a nested type for their entry.
+
+use crate::comments::comments;
+use crate::layout::{Entry, Layout};
+use descriptor::{Location, SourceCodeInfo};
+use parser::ast;
+use std::ops::Range;
+
+struct Collector<'s> {
+    source: &'s str,
+    locations: Vec<Location>,
+}
+
+// Lines and columns from zero, the end line left out when it is the start line.
+fn span(source: &str, range: &Range<usize>) -> Vec<i32> {
+    let (start_line, start_column) = parser::line_column(source, range.start);
+    let (end_line, end_column) = parser::line_column(source, range.end);
+    let [start_line, start_column, end_line, end_column] =
+        [start_line, start_column, end_line, end_column].map(|value| value as i32 - 1);
+
+    match start_line == end_line {
+        true => vec![start_line, start_column, end_column],
+        false => vec![start_line, start_column, end_line, end_column],
+    }
+}
+
+impl Collector<'_> {
+    fn add(&mut self, path: Vec<i32>, siblings: &[Entry], index: usize) {
+        let comments = comments(self.source, siblings, index);
+
+        self.locations.push(Location {
+            path,
+            span: span(self.source, &siblings[index].range),
+            leading_comments: comments.leading.map(|block| block.text),
+            trailing_comments: comments.trailing.map(|block| block.text),
+            leading_detached_comments: comments
+                .detached
+                .into_iter()
+                .map(|block| block.text)
+                .collect(),
+        });
+    }
+
+    fn message(
+        &mut self,
+        path: Vec<i32>,
+        message: &ast::Message,
+        siblings: &[Entry],
+        index: usize,
+    ) {
+        self.add(path.clone(), siblings, index);
+
+        let entries = &siblings[index].entries;
+        let within = |kind: i32, index: usize| [path.as_slice(), &[kind, index as i32]].concat();
+        let (mut fields, mut nested, mut enums, mut extensions, mut oneofs) = (0, 0, 0, 0, 0);
+
+        for (position, entry) in message.entries.iter().enumerate() {
+            match entry {
+                ast::MessageEntry::Field(field) => {
+                    self.add(within(2, fields), entries, position);
+                    fields += 1;
+
+                    if resolver::map_types(field.r#type).is_some() {
+                        nested += 1;
+                    }
+                }
+                ast::MessageEntry::OneOf(oneof) => {
+                    self.add(within(8, oneofs), entries, position);
+                    oneofs += 1;
+
+                    for (member, entry) in oneof.entries.iter().enumerate() {
+                        if let ast::OneOfEntry::Field(_) = entry {
+                            self.add(within(2, fields), &entries[position].entries, member);
+                            fields += 1;
+                        }
+                    }
+                }
+                ast::MessageEntry::Message(message) => {
+                    self.message(within(3, nested), message, entries, position);
+                    nested += 1;
+                }
+                ast::MessageEntry::Enum(r#enum) => {
+                    self.r#enum(within(4, enums), r#enum, entries, position);
+                    enums += 1;
+                }
+                ast::MessageEntry::Extend(extend) => {
+                    extensions = self.extend(&path, 6, extensions, extend, &entries[position]);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn r#enum(&mut self, path: Vec<i32>, r#enum: &ast::Enum, siblings: &[Entry], index: usize) {
+        self.add(path.clone(), siblings, index);
+
+        let values = r#enum
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| matches!(entry, ast::EnumEntry::Pair { .. }));
+
+        for (value, (position, _)) in values.enumerate() {
+            let path = [path.as_slice(), &[2, value as i32]].concat();
+            self.add(path, &siblings[index].entries, position);
+        }
+    }
+
+    // Fields of the extend block, numbered on from the extensions before it; returns how many
+    // extensions there are after it.
+    fn extend(
+        &mut self,
+        path: &[i32],
+        kind: i32,
+        first: usize,
+        extend: &ast::Extend,
+        entry: &Entry,
+    ) -> usize {
+        let fields = extend
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| matches!(entry, ast::ExtendEntry::Field(_)));
+        let mut count = first;
+
+        for (position, _) in fields {
+            self.add(
+                [path, &[kind, count as i32]].concat(),
+                &entry.entries,
+                position,
+            );
+            count += 1;
+        }
+
+        count
+    }
+}
+
+pub fn source_code_info(source: &str, file: &ast::File) -> SourceCodeInfo {
+    let layout = Layout::new(source, file);
+    let siblings = layout.entries.as_slice();
+    let mut collector = Collector {
+        source,
+        locations: vec![Location {
+            path: vec![],
+            span: span(source, &(0..source.len())),
+            ..Default::default()
+        }],
+    };
+    let (mut imports, mut messages, mut enums, mut services, mut extensions) = (0, 0, 0, 0, 0);
+
+    for (index, entry) in file.iter().enumerate() {
+        match entry {
+            ast::FileEntry::Syntax(_) => collector.add(vec![12], siblings, index),
+            ast::FileEntry::Package(_) => collector.add(vec![2], siblings, index),
+            ast::FileEntry::Import(_) => {
+                collector.add(vec![3, imports], siblings, index);
+                imports += 1;
+            }
+            ast::FileEntry::Message(message) => {
+                collector.message(vec![4, messages], message, siblings, index);
+                messages += 1;
+            }
+            ast::FileEntry::Enum(r#enum) => {
+                collector.r#enum(vec![5, enums], r#enum, siblings, index);
+                enums += 1;
+            }
+            ast::FileEntry::Service(service) => {
+                collector.add(vec![6, services], siblings, index);
+
+                let methods = service
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| matches!(entry, ast::ServiceEntry::Rpc(_)));
+                for (method, (position, _)) in methods.enumerate() {
+                    let path = vec![6, services, 2, method as i32];
+                    collector.add(path, &siblings[index].entries, position);
+                }
+
+                services += 1;
+            }
+            ast::FileEntry::Extend(extend) => {
+                extensions = collector.extend(&[], 7, extensions, extend, &siblings[index]);
+            }
+            ast::FileEntry::Option(_) | ast::FileEntry::Comment(_) => {}
+        }
+    }
+
+    SourceCodeInfo {
+        location: collector.locations,
+    }
+}
@@ -0,0 +1,255 @@
+// Checks of new or changed entries against their neighbours, done before the source is edited.
+
+use parser::ast;
+
+pub const MAX_FIELD_NUMBER: i64 = wire::MAX_FIELD_NUMBER as i64;
+
+pub fn check_ident(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        None => Err("Name is empty".to_string()),
+        Some(first)
+            if (first.is_ascii_alphabetic() || first == '_')
+                && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_') =>
+        {
+            Ok(())
+        }
+        Some(_) => Err(format!("{} is not a valid identifier", name)),
+    }
+}
+
+// Type of a field: a scalar, a possibly qualified name or a map.
+pub fn check_type(r#type: &str) -> Result<(), String> {
+    if let Some((key, value)) = r#type
+        .strip_prefix("map<")
+        .and_then(|rest| rest.strip_suffix('>'))
+        .and_then(|rest| rest.split_once(','))
+    {
+        check_ident(key.trim())
+            .map_err(|_| format!("{} is not a valid map key type", key.trim()))?;
+        return check_type(value.trim());
+    }
+
+    let path = r#type.strip_prefix('.').unwrap_or(r#type);
+
+    match path.split('.').all(|part| check_ident(part).is_ok()) {
+        true => Ok(()),
+        false => Err(format!("{} is not a valid type name", r#type)),
+    }
+}
+
+pub fn check_field_number(number: i64) -> Result<(), String> {
+    if !(1..=MAX_FIELD_NUMBER).contains(&number) {
+        return Err(format!(
+            "Field number {} is out of range 1 to {}",
+            number, MAX_FIELD_NUMBER
+        ));
+    }
+
+    if (19000..=19999).contains(&number) {
+        return Err(format!(
+            "Field number {} is reserved for the protobuf implementation",
+            number
+        ));
+    }
+
+    Ok(())
+}
+
+fn contains(ranges: &[ast::Range], number: i64) -> bool {
+    ranges.iter().any(|range| {
+        number >= range.start
+            && match range.end {
+                ast::RangeEnd::Integer(end) => number < end,
+                ast::RangeEnd::Max => true,
+            }
+    })
+}
+
+// Fields of the message, those in oneofs included.
+pub fn message_fields<'m, 'a>(
+    message: &'m ast::Message<'a>,
+) -> impl Iterator<Item = &'m ast::Field<'a>> {
+    message.entries.iter().flat_map(|entry| match entry {
+        ast::MessageEntry::Field(field) => vec![field],
+        ast::MessageEntry::OneOf(oneof) => oneof
+            .entries
+            .iter()
+            .filter_map(|entry| match entry {
+                ast::OneOfEntry::Field(field) => Some(field),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    })
+}
+
+// Names declared in the scope of the message: fields, oneofs and nested types.
+pub fn message_names<'m>(message: &'m ast::Message) -> impl Iterator<Item = &'m str> {
+    message
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            ast::MessageEntry::Message(message) => Some(message.ident),
+            ast::MessageEntry::Enum(r#enum) => Some(r#enum.ident),
+            ast::MessageEntry::OneOf(oneof) => Some(oneof.ident),
+            _ => None,
+        })
+        .chain(message_fields(message).map(|field| field.ident))
+}
+
+// `replaced` is the name of the entry being changed, which doesn't conflict with itself.
+pub fn check_name<'n>(
+    names: impl IntoIterator<Item = &'n str>,
+    name: &str,
+    replaced: Option<&str>,
+) -> Result<(), String> {
+    check_ident(name)?;
+
+    match names
+        .into_iter()
+        .any(|other| other == name && Some(other) != replaced)
+    {
+        true => Err(format!("{} is already defined", name)),
+        false => Ok(()),
+    }
+}
+
+// Whether the label of the field is allowed where it is declared.
+pub fn check_label(field: &ast::Field, proto3: bool, in_oneof: bool) -> Result<(), String> {
+    let map = resolver::map_types(field.r#type).is_some();
+
+    match field.modifier {
+        ast::FieldModifier::None if !proto3 && !in_oneof && !map => {
+            Err("Fields in proto2 must have a label".to_string())
+        }
+        ast::FieldModifier::None => Ok(()),
+        _ if in_oneof => Err("Fields of a oneof can't have a label".to_string()),
+        _ if map => Err("Map fields can't have a label".to_string()),
+        ast::FieldModifier::Required if proto3 => {
+            Err("Required fields are not allowed in proto3".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn check_field(
+    message: &ast::Message,
+    field: &ast::Field,
+    replaced: Option<&str>,
+) -> Result<(), String> {
+    check_name(message_names(message), field.ident, replaced)?;
+    check_type(field.r#type)?;
+    check_number(message, field.index, replaced)?;
+
+    for entry in &message.entries {
+        if let ast::MessageEntry::ReservedIdents(idents) = entry
+            && idents.contains(&field.ident)
+        {
+            return Err(format!("Field name {} is reserved", field.ident));
+        }
+    }
+
+    Ok(())
+}
+
+// Whether a field of the message can have the number.
+pub fn check_number(
+    message: &ast::Message,
+    number: i64,
+    replaced: Option<&str>,
+) -> Result<(), String> {
+    check_field_number(number)?;
+
+    if let Some(other) =
+        message_fields(message).find(|other| other.index == number && Some(other.ident) != replaced)
+    {
+        return Err(format!(
+            "Field number {} is already used by {}",
+            number, other.ident
+        ));
+    }
+
+    for entry in &message.entries {
+        match entry {
+            ast::MessageEntry::ReservedIndices(ranges) if contains(ranges, number) => {
+                return Err(format!("Field number {} is reserved", number));
+            }
+            ast::MessageEntry::Extensions(ranges) if contains(ranges, number) => {
+                return Err(format!("Field number {} is in an extension range", number));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// First number after the largest one in use that a new field can have.
+pub fn next_field_number(message: &ast::Message) -> i64 {
+    let mut number = message_fields(message)
+        .map(|field| field.index)
+        .max()
+        .unwrap_or_default()
+        + 1;
+
+    while number < MAX_FIELD_NUMBER && check_number(message, number, None).is_err() {
+        number += 1;
+    }
+
+    number
+}
+
+pub(crate) fn allows_alias(r#enum: &ast::Enum) -> bool {
+    r#enum.entries.iter().any(|entry| {
+        matches!(
+            entry,
+            ast::EnumEntry::Option(ast::Option {
+                key: "allow_alias",
+                value: ast::MapValue::Boolean(true),
+            })
+        )
+    })
+}
+
+pub fn check_enum_value(
+    r#enum: &ast::Enum,
+    ident: &str,
+    value: i64,
+    replaced: Option<&str>,
+) -> Result<(), String> {
+    let pairs = || {
+        r#enum.entries.iter().filter_map(|entry| match entry {
+            ast::EnumEntry::Pair { ident, value, .. } => Some((*ident, *value)),
+            _ => None,
+        })
+    };
+
+    check_name(pairs().map(|(ident, _)| ident), ident, replaced)?;
+
+    if i32::try_from(value).is_err() {
+        return Err(format!("Enum value {} is out of the int32 range", value));
+    }
+
+    if !allows_alias(r#enum)
+        && let Some((other, _)) =
+            pairs().find(|(other, number)| *number == value && Some(*other) != replaced)
+    {
+        return Err(format!("Enum value {} is already used by {}", value, other));
+    }
+
+    for entry in &r#enum.entries {
+        match entry {
+            ast::EnumEntry::ReservedIndices(ranges) if contains(ranges, value) => {
+                return Err(format!("Enum value {} is reserved", value));
+            }
+            ast::EnumEntry::ReservedIdents(idents) if idents.contains(&ident) => {
+                return Err(format!("Enum value name {} is reserved", ident));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,489 @@
+use parser::ast;
+
+const INDENT: &str = "  ";
+
+pub fn format(file: &ast::File) -> String {
+    let mut formatter = Formatter::default();
+    formatter.file(file);
+    formatter.out
+}
+
+// Groups of consecutive entries; a blank line is inserted when the group changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Group {
+    Comment,
+    Syntax,
+    Package,
+    Import,
+    Option,
+    Reserved,
+    Field,
+    Block,
+}
+
+fn separate(groups: impl Iterator<Item = Group>) -> Vec<bool> {
+    let groups = groups.collect::<Vec<_>>();
+    let mut previous: std::option::Option<Group> = None;
+    let mut blank_lines = vec![false; groups.len()];
+
+    for (index, &group) in groups.iter().enumerate() {
+        // comments stick to the entry below them
+        if index == 0 || groups[index - 1] != Group::Comment {
+            let next = groups[index..]
+                .iter()
+                .copied()
+                .find(|&group| group != Group::Comment)
+                .unwrap_or(Group::Comment);
+
+            if let Some(previous) = previous {
+                blank_lines[index] = previous != next || next == Group::Block;
+            }
+        }
+
+        if group != Group::Comment {
+            previous = Some(group);
+        }
+    }
+
+    blank_lines
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    indent: usize,
+}
+
+impl Formatter {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn blank_line(&mut self) {
+        self.out.push('\n');
+    }
+
+    fn block<T>(
+        &mut self,
+        header: String,
+        entries: &[T],
+        group: impl Fn(&T) -> Group,
+        mut entry: impl FnMut(&mut Self, &T),
+    ) {
+        if entries.is_empty() {
+            self.line(&format!("{} {{}}", header));
+            return;
+        }
+
+        self.line(&format!("{} {{", header));
+        self.indent += 1;
+
+        let blank_lines = separate(entries.iter().map(&group));
+        for (item, blank_line) in entries.iter().zip(blank_lines) {
+            if blank_line {
+                self.blank_line();
+            }
+            entry(self, item);
+        }
+
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn file(&mut self, file: &ast::File) {
+        let blank_lines = separate(file.iter().map(|entry| match entry {
+            ast::FileEntry::Comment(_) => Group::Comment,
+            ast::FileEntry::Syntax(_) => Group::Syntax,
+            ast::FileEntry::Package(_) => Group::Package,
+            ast::FileEntry::Import(_) => Group::Import,
+            ast::FileEntry::Option(_) => Group::Option,
+            ast::FileEntry::Service(_)
+            | ast::FileEntry::Message(_)
+            | ast::FileEntry::Extend(_)
+            | ast::FileEntry::Enum(_) => Group::Block,
+        }));
+
+        for (entry, blank_line) in file.iter().zip(blank_lines) {
+            if blank_line {
+                self.blank_line();
+            }
+
+            match entry {
+                ast::FileEntry::Comment(comment) => self.comment(comment),
+                ast::FileEntry::Syntax(syntax) => {
+                    self.line(&format!("syntax = {};", string(syntax)))
+                }
+                ast::FileEntry::Package(package) => self.line(&format!("package {};", package)),
+                ast::FileEntry::Import(import) => self.line(&format!(
+                    "import {}{};",
+                    match import.modifier {
+                        ast::ImportModifier::None => "",
+                        ast::ImportModifier::Public => "public ",
+                        ast::ImportModifier::Weak => "weak ",
+                    },
+                    string(import.path)
+                )),
+                ast::FileEntry::Option(option) => self.option(option),
+                ast::FileEntry::Service(service) => self.service(service),
+                ast::FileEntry::Message(message) => self.message(message),
+                ast::FileEntry::Extend(extend) => self.extend(extend),
+                ast::FileEntry::Enum(r#enum) => self.r#enum(r#enum),
+            }
+        }
+    }
+
+    fn comment(&mut self, comment: &ast::Comment) {
+        self.line(comment.source.trim_end());
+    }
+
+    fn option(&mut self, option: &ast::Option) {
+        self.line(&format!(
+            "option {} = {};",
+            option.key,
+            map_value(&option.value)
+        ));
+    }
+
+    fn service(&mut self, service: &ast::Service) {
+        self.block(
+            format!("service {}", service.ident),
+            &service.entries,
+            |entry| match entry {
+                ast::ServiceEntry::Comment(_) => Group::Comment,
+                ast::ServiceEntry::Option(_) => Group::Option,
+                ast::ServiceEntry::Rpc(_) => Group::Field,
+            },
+            |formatter, entry| match entry {
+                ast::ServiceEntry::Comment(comment) => formatter.comment(comment),
+                ast::ServiceEntry::Option(option) => formatter.option(option),
+                ast::ServiceEntry::Rpc(rpc) => formatter.rpc(rpc),
+            },
+        );
+    }
+
+    fn rpc(&mut self, rpc: &ast::Rpc) {
+        if rpc.entries.is_empty() {
+            self.line(&format!("{};", rpc_signature(rpc)));
+            return;
+        }
+
+        self.block(
+            rpc_signature(rpc),
+            &rpc.entries,
+            |entry| match entry {
+                ast::RpcEntry::Comment(_) => Group::Comment,
+                ast::RpcEntry::Option(_) => Group::Option,
+            },
+            |formatter, entry| match entry {
+                ast::RpcEntry::Comment(comment) => formatter.comment(comment),
+                ast::RpcEntry::Option(option) => formatter.option(option),
+            },
+        );
+    }
+
+    fn message(&mut self, message: &ast::Message) {
+        self.block(
+            format!("message {}", message.ident),
+            &message.entries,
+            |entry| match entry {
+                ast::MessageEntry::Comment(_) => Group::Comment,
+                ast::MessageEntry::Option(_) => Group::Option,
+                ast::MessageEntry::Field(_) => Group::Field,
+                ast::MessageEntry::OneOf(_)
+                | ast::MessageEntry::Message(_)
+                | ast::MessageEntry::Extend(_)
+                | ast::MessageEntry::Enum(_) => Group::Block,
+                ast::MessageEntry::ReservedIndices(_)
+                | ast::MessageEntry::ReservedIdents(_)
+                | ast::MessageEntry::Extensions(_) => Group::Reserved,
+            },
+            |formatter, entry| match entry {
+                ast::MessageEntry::Comment(comment) => formatter.comment(comment),
+                ast::MessageEntry::Option(option) => formatter.option(option),
+                ast::MessageEntry::Field(field) => formatter.line(&field_line(field)),
+                ast::MessageEntry::OneOf(oneof) => formatter.oneof(oneof),
+                ast::MessageEntry::Message(message) => formatter.message(message),
+                ast::MessageEntry::Extend(extend) => formatter.extend(extend),
+                ast::MessageEntry::Enum(r#enum) => formatter.r#enum(r#enum),
+                ast::MessageEntry::ReservedIndices(ranges) => {
+                    formatter.line(&format!("reserved {};", ranges_list(ranges)))
+                }
+                ast::MessageEntry::ReservedIdents(idents) => {
+                    formatter.line(&format!("reserved {};", idents_list(idents)))
+                }
+                ast::MessageEntry::Extensions(ranges) => {
+                    formatter.line(&format!("extensions {};", ranges_list(ranges)))
+                }
+            },
+        );
+    }
+
+    fn oneof(&mut self, oneof: &ast::OneOf) {
+        self.block(
+            format!("oneof {}", oneof.ident),
+            &oneof.entries,
+            |entry| match entry {
+                ast::OneOfEntry::Comment(_) => Group::Comment,
+                ast::OneOfEntry::Option(_) => Group::Option,
+                ast::OneOfEntry::Field(_) => Group::Field,
+            },
+            |formatter, entry| match entry {
+                ast::OneOfEntry::Comment(comment) => formatter.comment(comment),
+                ast::OneOfEntry::Option(option) => formatter.option(option),
+                ast::OneOfEntry::Field(field) => formatter.line(&field_line(field)),
+            },
+        );
+    }
+
+    fn extend(&mut self, extend: &ast::Extend) {
+        self.block(
+            format!("extend {}", extend.r#type),
+            &extend.entries,
+            |entry| match entry {
+                ast::ExtendEntry::Comment(_) => Group::Comment,
+                ast::ExtendEntry::Field(_) => Group::Field,
+            },
+            |formatter, entry| match entry {
+                ast::ExtendEntry::Comment(comment) => formatter.comment(comment),
+                ast::ExtendEntry::Field(field) => formatter.line(&field_line(field)),
+            },
+        );
+    }
+
+    fn r#enum(&mut self, r#enum: &ast::Enum) {
+        self.block(
+            format!("enum {}", r#enum.ident),
+            &r#enum.entries,
+            |entry| match entry {
+                ast::EnumEntry::Comment(_) => Group::Comment,
+                ast::EnumEntry::Option(_) => Group::Option,
+                ast::EnumEntry::Pair { .. } => Group::Field,
+                ast::EnumEntry::ReservedIndices(_) | ast::EnumEntry::ReservedIdents(_) => {
+                    Group::Reserved
+                }
+            },
+            |formatter, entry| match entry {
+                ast::EnumEntry::Comment(comment) => formatter.comment(comment),
+                ast::EnumEntry::Option(option) => formatter.option(option),
+                ast::EnumEntry::Pair {
+                    ident,
+                    value,
+                    options,
+                } => formatter.line(&format!("{} = {}{};", ident, value, options_list(options))),
+                ast::EnumEntry::ReservedIndices(ranges) => {
+                    formatter.line(&format!("reserved {};", ranges_list(ranges)))
+                }
+                ast::EnumEntry::ReservedIdents(idents) => {
+                    formatter.line(&format!("reserved {};", idents_list(idents)))
+                }
+            },
+        );
+    }
+}
+
+pub fn field_line(field: &ast::Field) -> String {
+    let modifier = match field.modifier {
+        ast::FieldModifier::None => "",
+        ast::FieldModifier::Optional => "optional ",
+        ast::FieldModifier::Required => "required ",
+        ast::FieldModifier::Repeated => "repeated ",
+    };
+
+    format!(
+        "{}{} {} = {}{};",
+        modifier,
+        field_type(field.r#type),
+        field.ident,
+        field.index,
+        options_list(&field.options)
+    )
+}
+
+pub fn rpc_signature(rpc: &ast::Rpc) -> String {
+    let (request_stream, reply_stream) = match rpc.stream {
+        ast::RpcStream::None => ("", ""),
+        ast::RpcStream::ServerBound => ("stream ", ""),
+        ast::RpcStream::ClientBound => ("", "stream "),
+        ast::RpcStream::Bidirectional => ("stream ", "stream "),
+    };
+
+    format!(
+        "rpc {} ({}{}) returns ({}{})",
+        rpc.ident, request_stream, rpc.request, reply_stream, rpc.reply
+    )
+}
+
+// map<k,v> written in any spacing is normalized to map<k, v>
+pub fn field_type(r#type: &str) -> String {
+    if let Some(inner) = r#type
+        .strip_prefix("map")
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix('<'))
+        .and_then(|rest| rest.strip_suffix('>'))
+        && let Some((key, value)) = inner.split_once(',')
+    {
+        return format!("map<{}, {}>", key.trim(), value.trim());
+    }
+
+    r#type.to_string()
+}
+
+pub fn options_list(options: &[ast::Option]) -> String {
+    if options.is_empty() {
+        return String::new();
+    }
+
+    let options = options
+        .iter()
+        .map(|option| format!("{} = {}", option.key, map_value(&option.value)))
+        .collect::<Vec<_>>();
+
+    format!(" [{}]", options.join(", "))
+}
+
+pub fn ranges_list(ranges: &[ast::Range]) -> String {
+    ranges
+        .iter()
+        .map(|range| match range.end {
+            ast::RangeEnd::Integer(end) if end == range.start + 1 => range.start.to_string(),
+            ast::RangeEnd::Integer(end) => format!("{} to {}", range.start, end - 1),
+            ast::RangeEnd::Max => format!("{} to max", range.start),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn idents_list(idents: &[&str]) -> String {
+    idents
+        .iter()
+        .map(|ident| string(ident))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Strings in the AST are kept as written between the quotes, so only the quote style is chosen here.
+pub fn string(value: &str) -> String {
+    let mut escaped = false;
+    let mut has_double_quote = false;
+
+    for ch in value.chars() {
+        match ch {
+            '"' if !escaped => has_double_quote = true,
+            '\\' => {
+                escaped = !escaped;
+                continue;
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+
+    if has_double_quote {
+        format!("'{}'", value)
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+pub fn float(value: f64) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    }
+
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+
+    let text = value.to_string();
+    if text.contains(['.', 'e', 'E']) {
+        text
+    } else {
+        format!("{}.0", text)
+    }
+}
+
+pub fn map_value(value: &ast::MapValue) -> String {
+    match value {
+        ast::MapValue::Boolean(value) => value.to_string(),
+        ast::MapValue::Integer(value) => value.to_string(),
+        ast::MapValue::Float(value) => float(*value),
+        ast::MapValue::Ident(value) => value.to_string(),
+        ast::MapValue::String(value) => string(value),
+        ast::MapValue::Map(map) => {
+            if map.is_empty() {
+                return "{}".to_string();
+            }
+
+            // sorted by key, values of a repeated field stay in their order
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(key, _)| *key);
+
+            let pairs = entries
+                .into_iter()
+                .map(|(key, value)| format!("{}: {}", key, map_value(value)))
+                .collect::<Vec<_>>();
+
+            format!("{{ {} }}", pairs.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::{lexer, proto};
+
+    macro_rules! assert_formatted {
+        ($input:literal, $expected:literal) => {{
+            let input = include_str!(concat!("../../../test-data/formatter/", $input));
+            let expected = include_str!(concat!("../../../test-data/formatter/", $expected));
+
+            let ast = proto::FileParser::new()
+                .parse(input, lexer::Lexer::new(input))
+                .unwrap();
+            let formatted = crate::format(&ast);
+
+            assert_eq!(formatted, expected);
+
+            // formatting is idempotent
+            let ast = proto::FileParser::new()
+                .parse(&formatted, lexer::Lexer::new(&formatted))
+                .unwrap();
+            assert_eq!(crate::format(&ast), expected);
+        }};
+    }
+
+    #[test]
+    fn messages() {
+        assert_formatted!("messages.proto", "messages.expected.proto");
+    }
+
+    #[test]
+    fn options() {
+        assert_formatted!("options.proto", "options.expected.proto");
+    }
+
+    #[test]
+    fn services() {
+        assert_formatted!("services.proto", "services.expected.proto");
+    }
+
+    #[test]
+    fn map_type() {
+        assert_eq!(
+            crate::field_type("map < string ,int32>"),
+            "map<string, int32>"
+        );
+        assert_eq!(crate::field_type("mapping.Type"), "mapping.Type");
+    }
+
+    #[test]
+    fn float() {
+        assert_eq!(crate::float(1.0), "1.0");
+        assert_eq!(crate::float(-0.25), "-0.25");
+        assert_eq!(crate::float(f64::INFINITY), "inf");
+    }
+}
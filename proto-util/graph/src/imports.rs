@@ -0,0 +1,40 @@
+// Files and the files they import, in the order of the workspace.
+
+use crate::{Edge, EdgeKind, Graph, Node, NodeKind};
+use parser::ast::ImportModifier;
+use resolver::Workspace;
+
+pub fn imports(workspace: &Workspace) -> Graph {
+    let mut graph = Graph::default();
+    let file_node = |graph: &mut Graph, name: &str| {
+        graph.add_node(Node {
+            name: name.to_string(),
+            kind: NodeKind::File,
+            file: name.to_string(),
+        })
+    };
+
+    for file in workspace.files() {
+        file_node(&mut graph, file.name);
+    }
+
+    for file in workspace.files() {
+        let from = file_node(&mut graph, file.name);
+
+        for import in file.imports() {
+            let to = file_node(&mut graph, import.path);
+            graph.edges.push(Edge {
+                from,
+                to,
+                kind: match import.modifier {
+                    ImportModifier::None => EdgeKind::Import,
+                    ImportModifier::Public => EdgeKind::PublicImport,
+                    ImportModifier::Weak => EdgeKind::WeakImport,
+                },
+                label: String::new(),
+            });
+        }
+    }
+
+    graph
+}
@@ -0,0 +1,426 @@
+mod imports;
+mod output;
+mod types;
+
+pub use imports::imports;
+pub use output::print_json;
+pub use types::types;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    File,
+    Message,
+    Enum,
+    Service,
+}
+
+impl std::fmt::Display for NodeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::File => "file",
+            Self::Message => "message",
+            Self::Enum => "enum",
+            Self::Service => "service",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    Import,
+    PublicImport,
+    WeakImport,
+    // Type of a field of the message.
+    Field,
+    // Request and reply of a method of the service.
+    Request,
+    Reply,
+    // Message extended by an `extend` in the message.
+    Extends,
+    // Type of an extension of the message.
+    Extension,
+}
+
+impl std::fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Import => "import",
+            Self::PublicImport => "public import",
+            Self::WeakImport => "weak import",
+            Self::Field => "field",
+            Self::Request => "request",
+            Self::Reply => "reply",
+            Self::Extends => "extends",
+            Self::Extension => "extension",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    // Fully-qualified name of a type or service, or name of a file.
+    pub name: String,
+    pub kind: NodeKind,
+    // Where it is declared.
+    pub file: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    // Indices of the nodes.
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+    // Field or method making the reference, empty for imports.
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    fn add_node(&mut self, node: Node) -> usize {
+        match self.node(&node.name) {
+            Some(index) => index,
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    // The nodes kept, in the same order, with the edges between them that are kept too.
+    fn subgraph(&self, nodes: &[bool], edge: impl Fn(&Edge) -> bool) -> Graph {
+        let mut indices = vec![None; self.nodes.len()];
+        let mut graph = Graph::default();
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if nodes[index] {
+                indices[index] = Some(graph.nodes.len());
+                graph.nodes.push(node.clone());
+            }
+        }
+
+        for edge in self.edges.iter().filter(|other| edge(other)) {
+            if let (Some(from), Some(to)) = (indices[edge.from], indices[edge.to]) {
+                graph.edges.push(Edge {
+                    from,
+                    to,
+                    ..edge.clone()
+                });
+            }
+        }
+
+        graph
+    }
+
+    // The node and everything it depends on, directly or not.
+    pub fn closure(&self, name: &str) -> Option<Graph> {
+        let mut reached = vec![false; self.nodes.len()];
+        let mut pending = vec![self.node(name)?];
+
+        while let Some(index) = pending.pop() {
+            if std::mem::replace(&mut reached[index], true) {
+                continue;
+            }
+            pending.extend(
+                self.edges
+                    .iter()
+                    .filter(|edge| edge.from == index)
+                    .map(|edge| edge.to),
+            );
+        }
+
+        Some(self.subgraph(&reached, |_| true))
+    }
+
+    // Services with the requests and replies of their methods, without what those depend on.
+    pub fn services(&self) -> Graph {
+        let method = |edge: &Edge| matches!(edge.kind, EdgeKind::Request | EdgeKind::Reply);
+        let mut kept: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|node| node.kind == NodeKind::Service)
+            .collect();
+
+        for edge in self.edges.iter().filter(|edge| method(edge)) {
+            kept[edge.to] = true;
+        }
+
+        self.subgraph(&kept, method)
+    }
+
+    // Groups of nodes depending on each other, i.e. the strongly connected components with more
+    // than one node or with a node depending on itself, each sorted, in the order of their nodes.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        struct Tarjan<'g> {
+            graph: &'g Graph,
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            next: usize,
+            components: Vec<Vec<usize>>,
+        }
+
+        impl Tarjan<'_> {
+            fn visit(&mut self, node: usize) {
+                self.index[node] = Some(self.next);
+                self.low[node] = self.next;
+                self.next += 1;
+                self.stack.push(node);
+                self.on_stack[node] = true;
+
+                let graph = self.graph;
+                for edge in graph.edges.iter().filter(|edge| edge.from == node) {
+                    match self.index[edge.to] {
+                        None => {
+                            self.visit(edge.to);
+                            self.low[node] = self.low[node].min(self.low[edge.to]);
+                        }
+                        Some(index) if self.on_stack[edge.to] => {
+                            self.low[node] = self.low[node].min(index);
+                        }
+                        Some(_) => {}
+                    }
+                }
+
+                if Some(self.low[node]) == self.index[node] {
+                    let mut component = vec![];
+                    while let Some(other) = self.stack.pop() {
+                        self.on_stack[other] = false;
+                        component.push(other);
+                        if other == node {
+                            break;
+                        }
+                    }
+                    self.components.push(component);
+                }
+            }
+        }
+
+        let count = self.nodes.len();
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: vec![None; count],
+            low: vec![0; count],
+            stack: vec![],
+            on_stack: vec![false; count],
+            next: 0,
+            components: vec![],
+        };
+
+        for node in 0..count {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+
+        let mut cycles: Vec<_> = tarjan
+            .components
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1
+                    || self
+                        .edges
+                        .iter()
+                        .any(|edge| edge.from == component[0] && edge.to == component[0])
+            })
+            .map(|mut component| {
+                component.sort();
+                component
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EdgeKind, Graph, NodeKind};
+    use resolver::{SourceTree, Workspace};
+    use std::path::PathBuf;
+
+    fn data() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-data/graph")
+    }
+
+    fn expected(name: &str) -> String {
+        std::fs::read_to_string(data().join("expected").join(name)).unwrap()
+    }
+
+    fn tree() -> SourceTree {
+        let files: Vec<_> = ["common", "item", "order"]
+            .iter()
+            .map(|name| data().join(format!("shop/v1/{}.proto", name)))
+            .collect();
+
+        SourceTree::load(vec![data()], &files).unwrap()
+    }
+
+    fn types(workspace: &Workspace) -> Graph {
+        let files: Vec<_> = ["common", "item", "order"]
+            .iter()
+            .filter_map(|name| workspace.file_index(&format!("shop/v1/{}.proto", name)))
+            .collect();
+
+        crate::types(workspace, &files)
+    }
+
+    fn edges(graph: &Graph) -> Vec<(&str, &str, EdgeKind, &str)> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| {
+                let (from, to) = (&graph.nodes[edge.from], &graph.nodes[edge.to]);
+                (
+                    from.name.as_str(),
+                    to.name.as_str(),
+                    edge.kind,
+                    edge.label.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    fn names(graph: &Graph, indices: &[usize]) -> Vec<String> {
+        indices
+            .iter()
+            .map(|index| graph.nodes[*index].name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn type_graph() {
+        let tree = tree();
+        let workspace = Workspace::new(&tree).unwrap();
+        let graph = types(&workspace);
+
+        assert_eq!(graph.to_dot("types"), expected("types.dot"));
+
+        let options = graph.node("google.protobuf.MessageOptions").unwrap();
+        assert_eq!(
+            graph.nodes[options].file,
+            "google/protobuf/descriptor.proto"
+        );
+        assert_eq!(
+            graph.nodes[graph.node("shop.v1.OrderService").unwrap()].kind,
+            NodeKind::Service
+        );
+
+        let edges = edges(&graph);
+        assert!(edges.contains(&(
+            "shop.v1.Item",
+            "shop.v1.Money",
+            EdgeKind::Field,
+            "prices_by_region"
+        )));
+        assert!(edges.contains(&("shop.v1.Discount", "shop.v1.Order", EdgeKind::Extends, "")));
+        assert!(edges.contains(&(
+            "shop.v1.Order",
+            "shop.v1.Money",
+            EdgeKind::Extension,
+            "discount"
+        )));
+        assert!(edges.contains(&(
+            "google.protobuf.MessageOptions",
+            "shop.v1.Currency",
+            EdgeKind::Extension,
+            "default_currency"
+        )));
+        assert!(edges.contains(&(
+            "shop.v1.OrderService",
+            "shop.v1.Item",
+            EdgeKind::Reply,
+            "WatchItems"
+        )));
+
+        // recursive messages are cycles of their own
+        let cycles: Vec<_> = graph
+            .cycles()
+            .iter()
+            .map(|cycle| names(&graph, cycle))
+            .collect();
+        assert_eq!(cycles, [["shop.v1.Item"]]);
+    }
+
+    #[test]
+    fn focus_and_services() {
+        let tree = tree();
+        let workspace = Workspace::new(&tree).unwrap();
+        let graph = types(&workspace);
+
+        let line = graph.closure("shop.v1.Order.Line").unwrap();
+        let nodes: Vec<_> = line.nodes.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(
+            nodes,
+            [
+                "shop.v1.Currency",
+                "shop.v1.Money",
+                "shop.v1.Item",
+                "shop.v1.Order.Line"
+            ]
+        );
+        assert_eq!(line.edges.len(), 5);
+        assert_eq!(graph.closure("shop.v1.Missing"), None);
+
+        let services = graph.services();
+        let nodes: Vec<_> = services
+            .nodes
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(
+            nodes,
+            [
+                "shop.v1.Item",
+                "shop.v1.Order",
+                "shop.v1.GetOrderRequest",
+                "shop.v1.OrderService"
+            ]
+        );
+        assert!(
+            services
+                .edges
+                .iter()
+                .all(|edge| matches!(edge.kind, EdgeKind::Request | EdgeKind::Reply))
+        );
+        assert_eq!(services.edges.len(), 4);
+    }
+
+    #[test]
+    fn import_graph() {
+        let tree = tree();
+        let workspace = Workspace::new(&tree).unwrap();
+        let graph = crate::imports(&workspace);
+
+        assert_eq!(crate::print_json(&graph) + "\n", expected("imports.json"));
+
+        let edges = edges(&graph);
+        assert!(edges.contains(&(
+            "shop/v1/order.proto",
+            "shop/v1/item.proto",
+            EdgeKind::PublicImport,
+            ""
+        )));
+
+        let cycles: Vec<_> = graph
+            .cycles()
+            .iter()
+            .map(|cycle| names(&graph, cycle))
+            .collect();
+        assert_eq!(cycles, [["shop/v1/common.proto", "shop/v1/item.proto"]]);
+
+        let item = graph.closure("shop/v1/item.proto").unwrap();
+        assert_eq!(item.nodes.len(), 2);
+        assert_eq!(item.cycles().len(), 1);
+    }
+}
@@ -0,0 +1,130 @@
+// Graphviz DOT and JSON forms of graphs. Edges between nodes of a cycle are drawn in red, and
+// types are grouped by the file declaring them.
+
+use crate::{EdgeKind, Graph, NodeKind};
+use serde_json::{Value as Json, json};
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Graph {
+    pub fn to_dot(&self, name: &str) -> String {
+        let cycles = self.cycles();
+        let in_cycle = |from: usize, to: usize| {
+            cycles
+                .iter()
+                .any(|cycle| cycle.contains(&from) && cycle.contains(&to))
+        };
+
+        let mut out = format!("digraph {} {{\n  rankdir=LR;\n", quote(name));
+        let shape = |kind: NodeKind| match kind {
+            NodeKind::File => "note",
+            NodeKind::Message => "box",
+            NodeKind::Enum => "ellipse",
+            NodeKind::Service => "component",
+        };
+
+        let mut files: Vec<&str> = vec![];
+        for node in &self.nodes {
+            if node.kind != NodeKind::File && !files.contains(&node.file.as_str()) {
+                files.push(&node.file);
+            }
+        }
+
+        for node in self.nodes.iter().filter(|node| node.kind == NodeKind::File) {
+            out.push_str(&format!("  {} [shape=note];\n", quote(&node.name)));
+        }
+        for (index, file) in files.iter().enumerate() {
+            out.push_str(&format!(
+                "  subgraph cluster_{} {{\n    label={};\n",
+                index,
+                quote(file)
+            ));
+            for node in self.nodes.iter().filter(|node| node.file == *file) {
+                out.push_str(&format!(
+                    "    {} [shape={}];\n",
+                    quote(&node.name),
+                    shape(node.kind)
+                ));
+            }
+            out.push_str("  }\n");
+        }
+
+        for edge in &self.edges {
+            let mut attributes = vec![];
+            match edge.kind {
+                EdgeKind::Request | EdgeKind::Reply => {
+                    let label = format!("{} ({})", edge.label, edge.kind);
+                    attributes.push(format!("label={}", quote(&label)));
+                }
+                _ if !edge.label.is_empty() => {
+                    attributes.push(format!("label={}", quote(&edge.label)))
+                }
+                _ => {}
+            }
+            match edge.kind {
+                EdgeKind::PublicImport => attributes.push("style=bold".to_string()),
+                EdgeKind::WeakImport | EdgeKind::Extends => {
+                    attributes.push("style=dashed".to_string())
+                }
+                EdgeKind::Extension => attributes.push("style=dotted".to_string()),
+                _ => {}
+            }
+            if in_cycle(edge.from, edge.to) {
+                attributes.push("color=red".to_string());
+            }
+
+            out.push_str(&format!(
+                "  {} -> {}",
+                quote(&self.nodes[edge.from].name),
+                quote(&self.nodes[edge.to].name)
+            ));
+            if !attributes.is_empty() {
+                out.push_str(&format!(" [{}]", attributes.join(", ")));
+            }
+            out.push_str(";\n");
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_json(&self) -> Json {
+        let name = |index: usize| self.nodes[index].name.as_str();
+
+        let nodes: Vec<_> = self
+            .nodes
+            .iter()
+            .map(
+                |node| json!({"name": node.name, "kind": node.kind.to_string(), "file": node.file}),
+            )
+            .collect();
+        let edges: Vec<_> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                let mut json = json!({
+                    "from": name(edge.from),
+                    "to": name(edge.to),
+                    "kind": edge.kind.to_string(),
+                });
+                if !edge.label.is_empty() {
+                    json["label"] = json!(edge.label);
+                }
+                json
+            })
+            .collect();
+        let cycles: Vec<_> = self
+            .cycles()
+            .into_iter()
+            .map(|cycle| cycle.into_iter().map(name).collect::<Vec<_>>())
+            .collect();
+
+        json!({"nodes": nodes, "edges": edges, "cycles": cycles})
+    }
+}
+
+pub fn print_json(graph: &Graph) -> String {
+    serde_json::to_string_pretty(&graph.to_json()).unwrap()
+}
@@ -0,0 +1,104 @@
+// Messages, enums and services of the files with the types they refer to: the types of the fields
+// of messages, the requests and replies of methods, and for extensions the extended message, which
+// depends on the types of its extensions, and the message the `extend` is written in, if any.
+// Types of other files only referred to are nodes too, without their own references.
+
+use crate::{Edge, EdgeKind, Graph, Node, NodeKind};
+use resolver::{Node as AstNode, ReferenceKind, Symbol, SymbolKind, Workspace};
+use std::collections::{HashMap, HashSet};
+
+fn node(workspace: &Workspace, symbol: &Symbol) -> Option<Node> {
+    let kind = match symbol.kind {
+        SymbolKind::Message => NodeKind::Message,
+        SymbolKind::Enum => NodeKind::Enum,
+        SymbolKind::Service => NodeKind::Service,
+        _ => return None,
+    };
+
+    Some(Node {
+        name: symbol.name.clone(),
+        kind,
+        file: workspace.file(symbol.file).name.to_string(),
+    })
+}
+
+pub fn types(workspace: &Workspace, files: &[usize]) -> Graph {
+    let mut graph = Graph::default();
+
+    let mut symbols: Vec<_> = workspace
+        .symbols()
+        .filter(|symbol| files.contains(&symbol.file))
+        .collect();
+    symbols.sort_by_key(|symbol| {
+        let file = files.iter().position(|file| *file == symbol.file);
+        (file, symbol.path.clone())
+    });
+
+    let mut declared = HashMap::new();
+    for symbol in symbols {
+        if let Some(node) = node(workspace, symbol) {
+            let index = graph.add_node(node);
+            declared.insert((symbol.file, symbol.path.clone()), index);
+        }
+    }
+
+    for &file in files {
+        let ast = &workspace.file(file).ast;
+        // nodes of the messages extended by the extends, which are referred to before their fields
+        let mut extendees = HashMap::new();
+        // methods whose request has been seen, as it is referred to before the reply
+        let mut requests = HashSet::new();
+
+        for reference in workspace.references(file) {
+            if !matches!(reference.kind, ReferenceKind::Type | ReferenceKind::Message) {
+                continue;
+            }
+            let Some(target) = workspace
+                .resolve_reference(&reference)
+                .ok()
+                .and_then(|symbol| node(workspace, symbol))
+            else {
+                continue;
+            };
+            let to = graph.add_node(target);
+
+            let path = &reference.path;
+            // the message or service the reference is written in
+            let owner = (0..=path.len())
+                .rev()
+                .find_map(|length| declared.get(&(file, path[..length].to_vec())))
+                .copied();
+            let parent = &path[..path.len().saturating_sub(1)];
+            let extension = matches!(AstNode::at(ast, parent), Some(AstNode::Extend(_)));
+
+            let (from, kind, label) = match (AstNode::at(ast, path), owner) {
+                (Some(AstNode::Extend(_)), owner) => {
+                    extendees.insert(path.clone(), to);
+                    match owner {
+                        Some(owner) => (owner, EdgeKind::Extends, ""),
+                        None => continue,
+                    }
+                }
+                (Some(AstNode::Field(field)), _) if extension => match extendees.get(parent) {
+                    Some(&extendee) => (extendee, EdgeKind::Extension, field.ident),
+                    None => continue,
+                },
+                (Some(AstNode::Field(field)), Some(owner)) => (owner, EdgeKind::Field, field.ident),
+                (Some(AstNode::Rpc(rpc)), Some(owner)) => match requests.insert(path.clone()) {
+                    true => (owner, EdgeKind::Request, rpc.ident),
+                    false => (owner, EdgeKind::Reply, rpc.ident),
+                },
+                _ => continue,
+            };
+
+            graph.edges.push(Edge {
+                from,
+                to,
+                kind,
+                label: label.to_string(),
+            });
+        }
+    }
+
+    graph
+}
@@ -0,0 +1,186 @@
+// The `google.api.http` option of methods, read from the encoded method options so that the
+// definition of google.api.HttpRule doesn't have to be in the pool.
+
+use wire::{DecodeError, Reader, WireType};
+
+// Field number of the `google.api.http` extension of google.protobuf.MethodOptions.
+const HTTP: u32 = 72295728;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpRule {
+    // Lower case, e.g. "get", or the kind of a custom pattern.
+    pub method: String,
+    pub path: String,
+    // "*" for the whole request message, the name of a field, or empty without a body.
+    pub body: String,
+    // Field of the response message sent as the body, the whole message if empty.
+    pub response_body: String,
+    pub additional_bindings: Vec<HttpRule>,
+}
+
+fn custom(data: &[u8], rule: &mut HttpRule) -> Result<(), DecodeError> {
+    let mut reader = Reader::new(data);
+
+    while !reader.is_empty() {
+        let tag = reader.read_tag()?;
+
+        match (tag.number, tag.wire_type) {
+            (1, WireType::LengthDelimited) => {
+                rule.method = reader.read_string()?.to_ascii_lowercase()
+            }
+            (2, WireType::LengthDelimited) => rule.path = reader.read_string()?.to_string(),
+            _ => {
+                reader.skip_field(tag)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn rule(data: &[u8]) -> Result<HttpRule, DecodeError> {
+    let mut reader = Reader::new(data);
+    let mut rule = HttpRule::default();
+
+    while !reader.is_empty() {
+        let tag = reader.read_tag()?;
+        if tag.wire_type != WireType::LengthDelimited {
+            reader.skip_field(tag)?;
+            continue;
+        }
+
+        let method = match tag.number {
+            2 => "get",
+            3 => "put",
+            4 => "post",
+            5 => "delete",
+            6 => "patch",
+            7 => {
+                rule.body = reader.read_string()?.to_string();
+                continue;
+            }
+            8 => {
+                custom(reader.read_length_delimited()?, &mut rule)?;
+                continue;
+            }
+            11 => {
+                let binding = self::rule(reader.read_length_delimited()?)?;
+                rule.additional_bindings.push(binding);
+                continue;
+            }
+            12 => {
+                rule.response_body = reader.read_string()?.to_string();
+                continue;
+            }
+            _ => {
+                reader.skip_field(tag)?;
+                continue;
+            }
+        };
+
+        rule.method = method.to_string();
+        rule.path = reader.read_string()?.to_string();
+    }
+
+    Ok(rule)
+}
+
+// The last occurrence wins, like for any non-repeated field.
+pub fn http_rule(options: Option<&[u8]>) -> Result<Option<HttpRule>, DecodeError> {
+    let Some(options) = options else {
+        return Ok(None);
+    };
+    let mut reader = Reader::new(options);
+    let mut result = None;
+
+    while !reader.is_empty() {
+        let tag = reader.read_tag()?;
+
+        if tag.number == HTTP && tag.wire_type == WireType::LengthDelimited {
+            result = Some(rule(reader.read_length_delimited()?)?);
+        } else {
+            reader.skip_field(tag)?;
+        }
+    }
+
+    Ok(result)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    // Dot-separated path of the field the variable is bound to.
+    pub field: String,
+    // Regular expression of the segments it matches, when they are given.
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    // With the variables written `{field.path}`, as OpenAPI wants them.
+    pub path: String,
+    pub variables: Vec<Variable>,
+}
+
+// `*` matches one segment, `**` any number of them.
+fn pattern(segments: &str) -> String {
+    let segments: Vec<_> = segments
+        .split('/')
+        .map(|segment| match segment {
+            "*" => "[^/]+".to_string(),
+            "**" => ".+".to_string(),
+            literal => literal
+                .chars()
+                .map(|ch| match ch {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => ch.to_string(),
+                    _ => format!("\\{}", ch),
+                })
+                .collect(),
+        })
+        .collect();
+
+    format!("^{}$", segments.join("/"))
+}
+
+// Path template of a rule, e.g. `/v1/{name=shelves/*}/books`.
+pub fn template(path: &str) -> Result<Template, String> {
+    let mut template = Template {
+        path: String::new(),
+        variables: vec![],
+    };
+    let mut rest = path;
+
+    if !path.starts_with('/') {
+        return Err(format!("path {} doesn't start with /", path));
+    }
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| format!("unclosed variable in {}", path))?;
+        let variable = &rest[start + 1..end];
+        let (field, segments) = match variable.split_once('=') {
+            Some((field, segments)) => (field.trim(), Some(segments.trim())),
+            None => (variable.trim(), None),
+        };
+
+        if field.is_empty() || variable.contains('{') || segments == Some("") {
+            return Err(format!("invalid variable {{{}}} in {}", variable, path));
+        }
+
+        template.path.push_str(&rest[..start]);
+        template.path.push_str(&format!("{{{}}}", field));
+        template.variables.push(Variable {
+            field: field.to_string(),
+            pattern: segments.filter(|segments| *segments != "*").map(pattern),
+        });
+        rest = &rest[end + 1..];
+    }
+
+    if rest.contains('}') {
+        return Err(format!("unopened variable in {}", path));
+    }
+
+    template.path.push_str(rest);
+    Ok(template)
+}
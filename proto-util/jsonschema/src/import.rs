@@ -0,0 +1,657 @@
+// Messages and enums bootstrapped from the schemas of a JSON Schema document (`$defs`, the older
+// `definitions` and the root schema) or from the components of an OpenAPI document, as a first
+// version of a .proto file to be reviewed by hand. Object schemas become messages, objects defined
+// inline become messages nested in the one using them, and string enums become enums. Fields are
+// numbered in the order of the properties, unless a property has an `x-proto-number`. Schemas that
+// are neither objects nor enums, e.g. arrays or strings with a format, take the place of the
+// references to them.
+
+use crate::Error;
+use descriptor::{Arena, case, names};
+use parser::ast;
+use serde_json::{Map, Value as Json};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportOptions {
+    // No package statement if empty.
+    pub package: String,
+    // Message of the root schema of a JSON Schema document without a title, "Root" if empty.
+    pub root: String,
+}
+
+fn pascal(name: &str) -> String {
+    let name = case::pascal_case(name);
+
+    match name.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        true => name,
+        false => format!("Type{}", name),
+    }
+}
+
+fn snake(name: &str) -> String {
+    let name = case::lower_snake_case(name);
+
+    match name.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        true => name,
+        false => format!("field_{}", name).trim_end_matches('_').to_string(),
+    }
+}
+
+// Name of the items of an array from the name of the array, e.g. Item from Items.
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        return format!("{}y", stem);
+    }
+    match name.strip_suffix('s') {
+        Some(stem) if !stem.is_empty() && !stem.ends_with('s') => stem.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+// First free name of the form `name`, `name2`, `name3`…
+fn unique(name: String, taken: impl Fn(&str) -> bool) -> String {
+    (1..)
+        .map(|index| match index {
+            1 => name.clone(),
+            index => format!("{}{}", name, index),
+        })
+        .find(|name| !taken(name))
+        .expect("some name is free")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Message,
+    Enum,
+    // Used in place of the references to it.
+    Alias,
+}
+
+fn is_map(schema: &Json) -> bool {
+    schema.get("properties").is_none()
+        && schema
+            .get("additionalProperties")
+            .is_some_and(Json::is_object)
+}
+
+fn is_any(schema: &Json) -> bool {
+    schema
+        .get("properties")
+        .and_then(Json::as_object)
+        .is_some_and(|properties| properties.len() == 1 && properties.contains_key("@type"))
+}
+
+// Definitions without properties are still messages, just empty ones, while objects without
+// properties defined inline are structs.
+fn kind(schema: &Json, definition: bool) -> Kind {
+    let enum_values = schema.get("enum").and_then(Json::as_array);
+    if enum_values.is_some_and(|values| !values.is_empty() && values.iter().all(Json::is_string)) {
+        return Kind::Enum;
+    }
+    if is_map(schema) || is_any(schema) {
+        return Kind::Alias;
+    }
+
+    let properties = schema.get("properties").and_then(Json::as_object);
+    let all_of = schema
+        .get("allOf")
+        .and_then(Json::as_array)
+        .is_some_and(|members| members.len() > 1);
+    let object = schema.get("type").and_then(Json::as_str) == Some("object");
+
+    let message = match definition {
+        true => properties.is_some() || all_of || object,
+        false => properties.is_some_and(|properties| !properties.is_empty()) || all_of,
+    };
+    match message {
+        true => Kind::Message,
+        false => Kind::Alias,
+    }
+}
+
+enum Type {
+    Single(String),
+    Repeated(String),
+    // Of the values, keys are strings.
+    Map(String),
+}
+
+struct Definition<'j> {
+    schema: &'j Json,
+    kind: Kind,
+    // Names of the messages it is nested in and its own, empty for aliases.
+    path: Vec<String>,
+}
+
+// Names of the document, in the order they are defined, with the references to them.
+fn definitions<'j>(document: &'j Json, options: &ImportOptions) -> Vec<(String, String, &'j Json)> {
+    let mut definitions = vec![];
+    let mut add = |prefix: &str, schemas: Option<&'j Json>| {
+        for (name, schema) in schemas.and_then(Json::as_object).into_iter().flatten() {
+            let pointer = name.replace('~', "~0").replace('/', "~1");
+            definitions.push((format!("{}{}", prefix, pointer), name.clone(), schema));
+        }
+    };
+
+    if document.get("openapi").is_some() {
+        add(
+            "#/components/schemas/",
+            document.pointer("/components/schemas"),
+        );
+        return definitions;
+    }
+    if document.get("swagger").is_some() {
+        add("#/definitions/", document.get("definitions"));
+        return definitions;
+    }
+
+    add("#/$defs/", document.get("$defs"));
+    add("#/definitions/", document.get("definitions"));
+
+    if kind(document, true) != Kind::Alias && document.get("$ref").is_none() {
+        let name = match document.get("title").and_then(Json::as_str) {
+            Some(title) => title.to_string(),
+            None if options.root.is_empty() => "Root".to_string(),
+            None => options.root.clone(),
+        };
+        definitions.insert(0, ("#".to_string(), name, document));
+    }
+
+    definitions
+}
+
+// Messages and enums being built.
+struct Scope<'a> {
+    path: Vec<String>,
+    nested: Vec<ast::MessageEntry<'a>>,
+}
+
+struct Importer<'a, 'j> {
+    arena: &'a Arena<String>,
+    definitions: Vec<Definition<'j>>,
+    references: HashMap<String, usize>,
+    // Paths of the messages and enums, so that nested ones get names of their own.
+    taken: HashSet<Vec<String>>,
+    imports: Vec<&'static str>,
+    // Aliases being resolved, against cycles of them.
+    resolving: Vec<usize>,
+}
+
+impl<'a, 'j> Importer<'a, 'j> {
+    fn new(arena: &'a Arena<String>, definitions: Vec<(String, String, &'j Json)>) -> Self {
+        let mut importer = Self {
+            arena,
+            definitions: vec![],
+            references: HashMap::new(),
+            taken: HashSet::new(),
+            imports: vec![],
+            resolving: vec![],
+        };
+
+        for (index, (reference, _, schema)) in definitions.iter().enumerate() {
+            importer.references.insert(reference.clone(), index);
+            importer.definitions.push(Definition {
+                schema,
+                kind: kind(schema, true),
+                path: vec![],
+            });
+        }
+
+        // names with dots nest in the message named by the part before, as in "pkg.Book.Review",
+        // which needs the shorter names to be placed first
+        let mut order: Vec<_> = (0..definitions.len()).collect();
+        order.sort_by_key(|&index| definitions[index].1.len());
+
+        for index in order {
+            if importer.definitions[index].kind == Kind::Alias {
+                continue;
+            }
+
+            let name = &definitions[index].1;
+            let parent = definitions
+                .iter()
+                .enumerate()
+                .filter(|(other, (_, parent, _))| {
+                    *other != index
+                        && importer.definitions[*other].kind == Kind::Message
+                        && !importer.definitions[*other].path.is_empty()
+                        && name.len() > parent.len() + 1
+                        && name.starts_with(parent.as_str())
+                        && name[parent.len()..].starts_with('.')
+                })
+                .max_by_key(|(_, (_, parent, _))| parent.len());
+
+            let (scope, ident) = match parent {
+                Some((parent, (_, parent_name, _))) => (
+                    importer.definitions[parent].path.clone(),
+                    pascal(&name[parent_name.len() + 1..]),
+                ),
+                None => (vec![], pascal(name.rsplit('.').next().unwrap_or(name))),
+            };
+            importer.definitions[index].path = importer.declare(&scope, ident);
+        }
+
+        importer
+    }
+
+    fn alloc(&self, value: String) -> &'a str {
+        self.arena.alloc(value).as_str()
+    }
+
+    // Path of a new message or enum, with a name not used in the scope nor at the top, so that
+    // references to top-level ones still find them.
+    fn declare(&mut self, scope: &[String], name: String) -> Vec<String> {
+        let name = unique(name, |name| {
+            let mut path = scope.to_vec();
+            path.push(name.to_string());
+            self.taken.contains(&path) || self.taken.contains(&vec![name.to_string()])
+        });
+
+        let mut path = scope.to_vec();
+        path.push(name);
+        self.taken.insert(path.clone());
+        path
+    }
+
+    fn comments<T>(&self, schema: &Json, wrap: fn(ast::Comment<'a>) -> T, entries: &mut Vec<T>) {
+        let description = schema.get("description").and_then(Json::as_str);
+
+        for line in description.unwrap_or_default().trim().lines() {
+            let source = match line.trim_end() {
+                "" => "//".to_string(),
+                line => format!("// {}", line),
+            };
+            entries.push(wrap(ast::Comment::single_line(self.alloc(source))));
+        }
+    }
+
+    fn deprecated(&self, schema: &Json) -> Option<ast::Option<'a>> {
+        (schema.get("deprecated") == Some(&Json::Bool(true))).then_some(ast::Option {
+            key: "deprecated",
+            value: ast::MapValue::Boolean(true),
+        })
+    }
+
+    fn well_known(&mut self, name: &str) -> Type {
+        let file = match name {
+            "Timestamp" => "google/protobuf/timestamp.proto",
+            "Duration" => "google/protobuf/duration.proto",
+            "Any" => "google/protobuf/any.proto",
+            _ => "google/protobuf/struct.proto",
+        };
+        if !self.imports.contains(&file) {
+            self.imports.push(file);
+        }
+
+        Type::Single(format!("google.protobuf.{}", name))
+    }
+
+    fn reference(&self, reference: &str) -> Result<usize, Error> {
+        self.references
+            .get(reference)
+            .copied()
+            .ok_or_else(|| Error::UnresolvedReference(reference.to_string()))
+    }
+
+    // Properties of an object, after those of the schemas it is made of with `allOf`.
+    fn properties(
+        &mut self,
+        schema: &'j Json,
+        properties: &mut Map<String, Json>,
+        order: &mut Vec<(&'j str, &'j Json)>,
+    ) -> Result<(), Error> {
+        for member in schema
+            .get("allOf")
+            .and_then(Json::as_array)
+            .into_iter()
+            .flatten()
+        {
+            match member.get("$ref").and_then(Json::as_str) {
+                Some(reference) => {
+                    let index = self.reference(reference)?;
+                    if !self.resolving.contains(&index) {
+                        self.resolving.push(index);
+                        let schema = self.definitions[index].schema;
+                        let result = self.properties(schema, properties, order);
+                        self.resolving.pop();
+                        result?;
+                    }
+                }
+                None => self.properties(member, properties, order)?,
+            }
+        }
+
+        let own = schema.get("properties").and_then(Json::as_object);
+        for (name, property) in own.into_iter().flatten() {
+            if !properties.contains_key(name) {
+                properties.insert(name.clone(), Json::Null);
+                order.push((name, property));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Message with a single field `values`, for arrays and maps in arrays and maps.
+    fn wrapper(&mut self, scope: &mut Scope<'a>, name: String, r#type: Type) -> String {
+        let path = self.declare(&scope.path, name);
+        let ident = path.last().cloned().unwrap_or_default();
+        let field = self.field("values", r#type, 1);
+
+        scope.nested.push(ast::MessageEntry::Message(ast::Message {
+            ident: self.alloc(ident.clone()),
+            entries: vec![ast::MessageEntry::Field(field)],
+        }));
+        ident
+    }
+
+    fn field(&self, ident: &str, r#type: Type, index: i64) -> ast::Field<'a> {
+        let (modifier, r#type) = match r#type {
+            Type::Single(r#type) => (ast::FieldModifier::None, r#type),
+            Type::Repeated(r#type) => (ast::FieldModifier::Repeated, r#type),
+            Type::Map(value) => (ast::FieldModifier::None, format!("map<string, {}>", value)),
+        };
+
+        ast::Field {
+            modifier,
+            r#type: self.alloc(r#type),
+            ident: self.alloc(ident.to_string()),
+            index,
+            options: vec![],
+        }
+    }
+
+    // Type of the values of a schema, declaring the messages and enums defined inline in the scope
+    // under the name given.
+    fn r#type(
+        &mut self,
+        scope: &mut Scope<'a>,
+        name: &str,
+        schema: &'j Json,
+    ) -> Result<Type, Error> {
+        if let Some(reference) = schema.get("$ref").and_then(Json::as_str) {
+            let index = self.reference(reference)?;
+            let (kind, schema) = (self.definitions[index].kind, self.definitions[index].schema);
+
+            return match kind {
+                Kind::Message | Kind::Enum => {
+                    Ok(Type::Single(self.definitions[index].path.join(".")))
+                }
+                Kind::Alias if self.resolving.contains(&index) => Ok(self.well_known("Value")),
+                Kind::Alias => {
+                    self.resolving.push(index);
+                    let r#type = self.r#type(scope, name, schema);
+                    self.resolving.pop();
+                    r#type
+                }
+            };
+        }
+
+        // a reference with a description of its own
+        let all_of = schema.get("allOf").and_then(Json::as_array);
+        if let (Some([member]), None) = (all_of.map(Vec::as_slice), schema.get("properties")) {
+            return self.r#type(scope, name, member);
+        }
+
+        match kind(schema, false) {
+            Kind::Message => {
+                let path = self.declare(&scope.path, pascal(name));
+                let message = self.message(path.clone(), schema)?;
+                scope.nested.push(ast::MessageEntry::Message(message));
+                return Ok(Type::Single(path.last().cloned().unwrap_or_default()));
+            }
+            Kind::Enum => {
+                let path = self.declare(&scope.path, pascal(name));
+                let ident = path.last().cloned().unwrap_or_default();
+                let r#enum = self.r#enum(&ident, schema);
+                scope.nested.push(ast::MessageEntry::Enum(r#enum));
+                return Ok(Type::Single(ident));
+            }
+            Kind::Alias => {}
+        }
+
+        if is_any(schema) {
+            return Ok(self.well_known("Any"));
+        }
+
+        let types: Vec<_> = match schema.get("type") {
+            Some(Json::String(r#type)) => vec![r#type.as_str()],
+            Some(Json::Array(types)) => types
+                .iter()
+                .filter_map(Json::as_str)
+                .filter(|r#type| *r#type != "null")
+                .collect(),
+            _ => vec![],
+        };
+        let format = schema
+            .get("format")
+            .and_then(Json::as_str)
+            .unwrap_or_default();
+        let scalar = |name: &str| Type::Single(name.to_string());
+
+        Ok(match types.as_slice() {
+            ["string"] => match format {
+                "date-time" => self.well_known("Timestamp"),
+                "duration" => self.well_known("Duration"),
+                "int32" | "uint32" | "int64" | "uint64" => scalar(format),
+                "byte" | "binary" => scalar("bytes"),
+                _ if schema.get("contentEncoding") == Some(&Json::from("base64")) => {
+                    scalar("bytes")
+                }
+                _ => scalar("string"),
+            },
+            ["integer"] => match format {
+                "int32" | "uint32" | "uint64" => scalar(format),
+                _ => scalar("int64"),
+            },
+            ["number"] => match format {
+                "float" => scalar("float"),
+                _ => scalar("double"),
+            },
+            ["boolean"] => scalar("bool"),
+            ["array"] => match schema.get("items").filter(|items| items.is_object()) {
+                Some(items) => match self.r#type(scope, &singular(name), items)? {
+                    Type::Single(r#type) => Type::Repeated(r#type),
+                    r#type => {
+                        let name = format!("{}Item", pascal(name));
+                        Type::Repeated(self.wrapper(scope, name, r#type))
+                    }
+                },
+                None => self.well_known("ListValue"),
+            },
+            ["object"] if is_map(schema) => {
+                let values = &schema["additionalProperties"];
+                match self.r#type(scope, name, values)? {
+                    Type::Single(r#type) => Type::Map(r#type),
+                    r#type => {
+                        let name = format!("{}Value", pascal(name));
+                        Type::Map(self.wrapper(scope, name, r#type))
+                    }
+                }
+            }
+            ["object"] => self.well_known("Struct"),
+            _ => self.well_known("Value"),
+        })
+    }
+
+    fn r#enum(&self, ident: &str, schema: &Json) -> ast::Enum<'a> {
+        let prefix = format!("{}_", case::upper_snake_case(ident));
+        let (mut strings, mut values): (Vec<&str>, Vec<String>) = (vec![], vec![]);
+
+        for value in schema["enum"].as_array().into_iter().flatten() {
+            let string = value.as_str().unwrap_or_default();
+            if strings.contains(&string) {
+                continue;
+            }
+            strings.push(string);
+
+            // different strings with the same name, e.g. "active" and "ACTIVE", get a suffix
+            let value = case::upper_snake_case(string);
+            let value = match value.starts_with(&prefix) {
+                true => value,
+                false => format!("{}{}", prefix, value),
+            };
+            let value = unique(value, |name| values.iter().any(|other| other == name));
+            values.push(value);
+        }
+
+        // proto3 enums start with a zero value standing for "not set"
+        if !values
+            .first()
+            .is_some_and(|value| value.ends_with("_UNSPECIFIED"))
+        {
+            let zero = format!("{}UNSPECIFIED", prefix);
+            values.retain(|value| *value != zero);
+            values.insert(0, zero);
+        }
+
+        let mut entries = vec![];
+        if let Some(option) = self.deprecated(schema) {
+            entries.push(ast::EnumEntry::Option(option));
+        }
+        for (number, value) in values.into_iter().enumerate() {
+            entries.push(ast::EnumEntry::Pair {
+                ident: self.alloc(value),
+                value: number as i64,
+                options: vec![],
+            });
+        }
+
+        ast::Enum {
+            ident: self.alloc(ident.to_string()),
+            entries,
+        }
+    }
+
+    fn message(&mut self, path: Vec<String>, schema: &'j Json) -> Result<ast::Message<'a>, Error> {
+        let mut properties = vec![];
+        self.properties(schema, &mut Map::new(), &mut properties)?;
+
+        let number = |property: &Json| {
+            property
+                .get("x-proto-number")
+                .and_then(Json::as_i64)
+                .filter(|number| editor::check_field_number(*number).is_ok())
+        };
+        let explicit: HashSet<i64> = properties
+            .iter()
+            .filter_map(|(_, property)| number(property))
+            .collect();
+
+        let mut scope = Scope {
+            path: path.clone(),
+            nested: vec![],
+        };
+        let mut fields = vec![];
+        let mut idents: HashSet<String> = HashSet::new();
+        let mut numbers: HashSet<i64> = HashSet::new();
+        let mut next = 1;
+
+        if let Some(option) = self.deprecated(schema) {
+            fields.push(ast::MessageEntry::Option(option));
+        }
+
+        for (name, property) in properties {
+            let ident = unique(snake(name), |ident| idents.contains(ident));
+            idents.insert(ident.clone());
+
+            let index = match number(property) {
+                Some(number) if !numbers.contains(&number) => number,
+                _ => {
+                    while explicit.contains(&next)
+                        || numbers.contains(&next)
+                        || editor::check_field_number(next).is_err()
+                    {
+                        next += 1;
+                    }
+                    next
+                }
+            };
+            numbers.insert(index);
+
+            let r#type = self.r#type(&mut scope, name, property)?;
+            let mut field = self.field(&ident, r#type, index);
+            if names::json_name(&ident) != name {
+                let name = descriptor::escape(name.as_bytes());
+                field.options.push(ast::Option {
+                    key: "json_name",
+                    value: ast::MapValue::String(self.alloc(name)),
+                });
+            }
+            field.options.extend(self.deprecated(property));
+
+            self.comments(property, ast::MessageEntry::Comment, &mut fields);
+            fields.push(ast::MessageEntry::Field(field));
+        }
+
+        let mut entries = fields;
+        entries.append(&mut scope.nested);
+        entries.extend(self.children(&path)?);
+
+        Ok(ast::Message {
+            ident: self.alloc(path.last().cloned().unwrap_or_default()),
+            entries,
+        })
+    }
+
+    // Messages and enums of the definitions nested in the message at the path.
+    fn children(&mut self, path: &[String]) -> Result<Vec<ast::MessageEntry<'a>>, Error> {
+        let mut entries = vec![];
+
+        for index in 0..self.definitions.len() {
+            let definition = &self.definitions[index];
+            if definition.path.len() != path.len() + 1 || !definition.path.starts_with(path) {
+                continue;
+            }
+
+            let (schema, kind, path) =
+                (definition.schema, definition.kind, definition.path.clone());
+            self.comments(schema, ast::MessageEntry::Comment, &mut entries);
+            entries.push(match kind {
+                Kind::Enum => ast::MessageEntry::Enum(self.r#enum(&path[path.len() - 1], schema)),
+                _ => ast::MessageEntry::Message(self.message(path, schema)?),
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+pub fn import<'a>(
+    source: &str,
+    options: &ImportOptions,
+    arena: &'a Arena<String>,
+) -> Result<ast::File<'a>, Error> {
+    let document: Json =
+        serde_json::from_str(source).map_err(|error| Error::InvalidJson(error.to_string()))?;
+    let mut importer = Importer::new(arena, definitions(&document, options));
+
+    let mut declarations = vec![];
+    for entry in importer.children(&[])? {
+        declarations.push(match entry {
+            ast::MessageEntry::Comment(comment) => ast::FileEntry::Comment(comment),
+            ast::MessageEntry::Enum(r#enum) => ast::FileEntry::Enum(r#enum),
+            ast::MessageEntry::Message(message) => ast::FileEntry::Message(message),
+            _ => unreachable!("only messages and enums are defined"),
+        });
+    }
+    if declarations.is_empty() {
+        return Err(Error::NoSchemas);
+    }
+
+    let mut file = vec![ast::FileEntry::Syntax("proto3")];
+    if !options.package.is_empty() {
+        file.push(ast::FileEntry::Package(
+            importer.alloc(options.package.clone()),
+        ));
+    }
+
+    importer.imports.sort();
+    for import in importer.imports.iter().copied() {
+        file.push(ast::FileEntry::Import(ast::Import::basic(import)));
+    }
+
+    file.extend(declarations);
+    Ok(file)
+}
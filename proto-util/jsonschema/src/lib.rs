@@ -0,0 +1,229 @@
+mod http;
+mod import;
+mod openapi;
+mod schema;
+
+pub use http::{HttpRule, Template, Variable, http_rule, template};
+pub use import::{ImportOptions, import};
+pub use openapi::{Info, openapi};
+pub use schema::{DIALECT, json_schema};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    UnknownType(String),
+    UnknownService(String),
+    // Method, and what is wrong with its `google.api.http` option.
+    Http { method: String, message: String },
+    InvalidJson(String),
+    UnresolvedReference(String),
+    // Nothing in the document to make messages or enums of.
+    NoSchemas,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownType(name) => write!(f, "Unknown type {}", name),
+            Self::UnknownService(name) => write!(f, "Unknown service {}", name),
+            Self::Http { method, message } => write!(f, "{}: google.api.http: {}", method, message),
+            Self::InvalidJson(message) => write!(f, "Invalid JSON: {}", message),
+            Self::UnresolvedReference(reference) => write!(f, "Unresolved reference {}", reference),
+            Self::NoSchemas => write!(f, "No object or enum schemas to import"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn print(json: &serde_json::Value) -> String {
+    serde_json::to_string_pretty(json).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, ImportOptions, Info, Variable};
+    use descriptor::{Arena, Pool};
+    use resolver::{SourceTree, Workspace};
+    use std::path::PathBuf;
+
+    fn data() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-data/jsonschema")
+    }
+
+    fn pool() -> Pool {
+        let files = [
+            data().join("library/v1/library.proto"),
+            data().join("library/v1/legacy.proto"),
+        ];
+        let tree = SourceTree::load(vec![data()], &files).unwrap();
+        let workspace = Workspace::new(&tree).unwrap();
+
+        Pool::new(&resolver::compile(&workspace).unwrap())
+    }
+
+    fn expected(name: &str) -> String {
+        let path = data().join("expected").join(name);
+        std::fs::read_to_string(path)
+            .unwrap()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn json_schema() {
+        let pool = pool();
+        let mut types: Vec<_> = pool
+            .messages()
+            .filter(|message| message.name.starts_with("library.") && !message.map_entry)
+            .map(|message| message.name.clone())
+            .chain(pool.enums().map(|r#enum| r#enum.name.clone()))
+            .filter(|name| name.starts_with("library."))
+            .collect();
+        types.sort();
+
+        let schema = crate::json_schema(&pool, &types).unwrap();
+        assert_eq!(crate::print(&schema), expected("library.schema.json"));
+
+        // a single type is the schema itself
+        let schema = crate::json_schema(&pool, &["library.v1.LegacyLoan".to_string()]).unwrap();
+        assert_eq!(schema["$ref"], "#/$defs/library.v1.LegacyLoan");
+        assert_eq!(
+            schema["$defs"]["library.v1.LegacyLoan"]["required"],
+            serde_json::json!(["book"])
+        );
+
+        assert_eq!(
+            crate::json_schema(&pool, &["library.v1.Missing".to_string()]),
+            Err(Error::UnknownType("library.v1.Missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn openapi() {
+        let pool = pool();
+        let info = Info {
+            title: "Library".to_string(),
+            version: "1.0.0".to_string(),
+        };
+
+        let document =
+            crate::openapi(&pool, &["library.v1.LibraryService".to_string()], &info).unwrap();
+        assert_eq!(crate::print(&document), expected("library.openapi.json"));
+
+        // every additional binding has its own operation
+        for (path, id) in [
+            ("/v1/books", "LibraryService_ListBooks_1"),
+            ("/v2/books", "LibraryService_ListBooks_2"),
+        ] {
+            assert_eq!(document["paths"][path]["get"]["operationId"], id);
+        }
+
+        assert_eq!(
+            crate::openapi(&pool, &["library.v1.Missing".to_string()], &info),
+            Err(Error::UnknownService("library.v1.Missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn template() {
+        let template = crate::template("/v1/{name=shelves/*/books/**}:move/{target.id}").unwrap();
+        assert_eq!(template.path, "/v1/{name}:move/{target.id}");
+        assert_eq!(
+            template.variables,
+            [
+                Variable {
+                    field: "name".to_string(),
+                    pattern: Some("^shelves/[^/]+/books/.+$".to_string()),
+                },
+                Variable {
+                    field: "target.id".to_string(),
+                    pattern: None,
+                },
+            ]
+        );
+
+        assert!(crate::template("v1/books").is_err());
+        assert!(crate::template("/v1/{name").is_err());
+        assert!(crate::template("/v1/{=shelves/*}").is_err());
+        assert!(crate::template("/v1/name}").is_err());
+    }
+
+    // Formatted .proto file of the document, checked to compile.
+    fn import(source: &str, package: &str) -> String {
+        let options = ImportOptions {
+            package: package.to_string(),
+            root: String::new(),
+        };
+        let arena = Arena::new();
+        let proto = formatter::format(&crate::import(source, &options, &arena).unwrap());
+
+        let mut tree = SourceTree::new(vec![]);
+        tree.add("imported.proto", proto.clone());
+        tree.load_imports().unwrap();
+        resolver::compile(&Workspace::new(&tree).unwrap()).unwrap();
+
+        proto
+    }
+
+    #[test]
+    fn import_openapi() {
+        let source = std::fs::read_to_string(data().join("import/petstore.openapi.json")).unwrap();
+        assert_eq!(
+            import(&source, "petstore.v1").trim_end(),
+            expected("petstore.proto")
+        );
+    }
+
+    #[test]
+    fn import_exported() {
+        let source = std::fs::read_to_string(data().join("expected/library.schema.json")).unwrap();
+        assert_eq!(
+            import(&source, "library.v1").trim_end(),
+            expected("library.proto")
+        );
+    }
+
+    #[test]
+    fn import_root() {
+        let source = r##"{
+            "title": "user account",
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer"},
+                "roles": {"type": "array", "items": {"$ref": "#/definitions/role"}},
+                "self": {"$ref": "#"}
+            },
+            "definitions": {
+                "role": {"type": "string", "enum": ["ROLE_UNSPECIFIED", "admin"]}
+            }
+        }"##;
+        let expected = "syntax = \"proto3\";\n\n\
+            message UserAccount {\n  int64 id = 1;\n  repeated Role roles = 2;\n  \
+            UserAccount self = 3;\n}\n\n\
+            enum Role {\n  ROLE_UNSPECIFIED = 0;\n  ROLE_ADMIN = 1;\n}\n";
+        assert_eq!(import(source, ""), expected);
+
+        let colliding =
+            r#"{"$defs": {"Status": {"enum": ["active", "ACTIVE", "active", "closed"]}}}"#;
+        let expected = "syntax = \"proto3\";\n\n\
+            enum Status {\n  STATUS_UNSPECIFIED = 0;\n  STATUS_ACTIVE = 1;\n  \
+            STATUS_ACTIVE2 = 2;\n  STATUS_CLOSED = 3;\n}\n";
+        assert_eq!(import(colliding, ""), expected);
+
+        let arena = Arena::new();
+        let options = ImportOptions::default();
+        let missing = r##"{"$defs": {"A": {"properties": {"b": {"$ref": "#/$defs/B"}}}}}"##;
+        assert_eq!(
+            crate::import(missing, &options, &arena),
+            Err(Error::UnresolvedReference("#/$defs/B".to_string()))
+        );
+        assert_eq!(
+            crate::import(r#"{"type": "string"}"#, &options, &arena),
+            Err(Error::NoSchemas)
+        );
+        assert!(matches!(
+            crate::import("{", &options, &arena),
+            Err(Error::InvalidJson(_))
+        ));
+    }
+}
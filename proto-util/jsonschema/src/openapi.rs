@@ -0,0 +1,239 @@
+// OpenAPI 3.1 document of the methods of services bound to HTTP by `google.api.http` options, the
+// way gRPC transcoding maps them: variables of the path template are fields of the request, the
+// body is the request or one of its fields, and the remaining scalar fields are query parameters.
+// Methods without the option aren't part of the HTTP API.
+
+use crate::Error;
+use crate::http::{self, HttpRule};
+use crate::schema::{self, Schemas};
+use descriptor::{FieldInfo, FieldType, MessageType, MethodInfo, Pool, ServiceType};
+use serde_json::{Map, Value as Json, json};
+
+pub const VERSION: &str = "3.1.0";
+
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    pub title: String,
+    pub version: String,
+}
+
+// Field at the dot-separated path of names, through singular message fields.
+fn field_path<'p>(pool: &'p Pool, message: &'p MessageType, path: &str) -> Option<&'p FieldInfo> {
+    let (first, rest) = match path.split_once('.') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (path, None),
+    };
+    let field = message.field_by_name(first)?;
+
+    match rest {
+        None => Some(field),
+        Some(_) if field.is_repeated() || field.r#type != FieldType::Message => None,
+        Some(rest) => field_path(pool, pool.message(field.type_name.as_deref()?)?, rest),
+    }
+}
+
+// Query parameters can only be scalars, enums, repeated ones of them, and the well-known types
+// written as scalars.
+fn query(pool: &Pool, field: &FieldInfo) -> bool {
+    match field.r#type {
+        FieldType::Group => false,
+        FieldType::Message => {
+            let name = field.type_name.as_deref().unwrap_or_default();
+            let scalar = schema::well_known(name).is_some_and(|schema| {
+                matches!(
+                    schema["type"].as_str(),
+                    Some("string" | "number" | "integer" | "boolean")
+                )
+            });
+            scalar && !field.is_repeated() && pool.map_entry(field).is_none()
+        }
+        _ => true,
+    }
+}
+
+fn content(schema: Json) -> Json {
+    json!({"application/json": {"schema": schema}})
+}
+
+struct Binding<'a> {
+    service: &'a ServiceType,
+    method: &'a MethodInfo,
+    rule: &'a HttpRule,
+    // Position among the bindings of the method, the main one first.
+    index: usize,
+}
+
+impl Binding<'_> {
+    fn error(&self, message: String) -> Error {
+        Error::Http {
+            method: format!("{}.{}", self.service.name, self.method.name),
+            message,
+        }
+    }
+
+    fn field<'p>(
+        &self,
+        pool: &'p Pool,
+        message: &'p MessageType,
+        name: &str,
+    ) -> Result<&'p FieldInfo, Error> {
+        field_path(pool, message, name)
+            .ok_or_else(|| self.error(format!("no field {} in {}", name, message.name)))
+    }
+
+    // Path with the variables in OpenAPI form, HTTP method and operation.
+    fn operation(&self, schemas: &mut Schemas) -> Result<(String, String, Json), Error> {
+        let pool = schemas.pool();
+        let rule = self.rule;
+        if !METHODS.contains(&rule.method.as_str()) {
+            return Err(self.error(format!("unsupported HTTP method {:?}", rule.method)));
+        }
+
+        let template = http::template(&rule.path).map_err(|message| self.error(message))?;
+        let input = pool
+            .message(&self.method.input_type)
+            .ok_or_else(|| Error::UnknownType(self.method.input_type.clone()))?;
+        let output = pool
+            .message(&self.method.output_type)
+            .ok_or_else(|| Error::UnknownType(self.method.output_type.clone()))?;
+        let field = |message, name| self.field(pool, message, name);
+
+        let mut parameters = vec![];
+        for variable in &template.variables {
+            let mut schema = schemas.field(field(input, &variable.field)?)?;
+            if let (Some(pattern), Json::Object(schema)) = (&variable.pattern, &mut schema) {
+                schema.insert("pattern".to_string(), json!(pattern));
+            }
+
+            parameters.push(json!({
+                "name": variable.field,
+                "in": "path",
+                "required": true,
+                "schema": schema,
+            }));
+        }
+
+        let body = match rule.body.as_str() {
+            "" => None,
+            "*" => Some(schemas.named(&input.name)?),
+            name => Some(schemas.field(field(input, name)?)?),
+        };
+
+        if rule.body != "*" {
+            for field in &input.fields {
+                let bound = field.name == rule.body
+                    || template
+                        .variables
+                        .iter()
+                        .any(|variable| variable.field.split('.').next() == Some(&field.name));
+
+                if !bound && query(pool, field) {
+                    parameters.push(json!({
+                        "name": field.json_name,
+                        "in": "query",
+                        "schema": schemas.field(field)?,
+                    }));
+                }
+            }
+        }
+
+        let response = match rule.response_body.as_str() {
+            "" => schemas.named(&output.name)?,
+            name => schemas.field(field(output, name)?)?,
+        };
+
+        let mut operation = Map::new();
+        let suffix = match self.index {
+            0 => String::new(),
+            index => format!("_{}", index),
+        };
+        let service = self.service.name.rsplit('.').next().unwrap_or_default();
+        operation.insert(
+            "operationId".to_string(),
+            json!(format!("{}_{}{}", service, self.method.name, suffix)),
+        );
+        operation.insert("tags".to_string(), json!([self.service.name]));
+        if !parameters.is_empty() {
+            operation.insert("parameters".to_string(), Json::Array(parameters));
+        }
+        if let Some(body) = body {
+            operation.insert(
+                "requestBody".to_string(),
+                json!({"required": true, "content": content(body)}),
+            );
+        }
+        operation.insert(
+            "responses".to_string(),
+            json!({"200": {"description": "OK", "content": content(response)}}),
+        );
+
+        Ok((template.path, rule.method.clone(), Json::Object(operation)))
+    }
+}
+
+// Document of the HTTP bindings of the methods of the services, in the order of the services and
+// of their methods.
+pub fn openapi(pool: &Pool, services: &[String], info: &Info) -> Result<Json, Error> {
+    let mut schemas = Schemas::new(pool, "#/components/schemas/");
+    let mut paths = Map::new();
+
+    for name in services {
+        let service = pool
+            .service(name)
+            .ok_or_else(|| Error::UnknownService(name.clone()))?;
+
+        for method in &service.methods {
+            let rule = http::http_rule(method.options.as_deref()).map_err(|error| Error::Http {
+                method: format!("{}.{}", service.name, method.name),
+                message: error.to_string(),
+            })?;
+            let Some(rule) = rule else {
+                continue;
+            };
+
+            let rules = std::iter::once(&rule).chain(&rule.additional_bindings);
+            for (index, rule) in rules.enumerate() {
+                let binding = Binding {
+                    service,
+                    method,
+                    rule,
+                    index,
+                };
+                let (path, verb, operation) = binding.operation(&mut schemas)?;
+
+                let item = paths
+                    .entry(path.clone())
+                    .or_insert_with(|| json!({}))
+                    .as_object_mut()
+                    .expect("path items are objects");
+                if item.contains_key(&verb) {
+                    return Err(binding.error(format!(
+                        "{} {} is bound to another method too",
+                        verb.to_uppercase(),
+                        path
+                    )));
+                }
+                item.insert(verb, operation);
+            }
+        }
+    }
+
+    let mut document = Map::new();
+    document.insert("openapi".to_string(), json!(VERSION));
+    document.insert(
+        "info".to_string(),
+        json!({"title": info.title, "version": info.version}),
+    );
+    document.insert("paths".to_string(), Json::Object(paths));
+
+    let defs = schemas.finish()?;
+    if !defs.is_empty() {
+        document.insert("components".to_string(), json!({"schemas": defs}));
+    }
+
+    Ok(Json::Object(document))
+}
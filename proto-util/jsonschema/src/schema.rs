@@ -0,0 +1,206 @@
+// JSON Schemas of the values of messages and enums as the proto3 JSON mapping writes them: fields
+// under their JSON names, 64-bit integers as strings, bytes in base64, enums by the names of their
+// values, and the special forms of the well-known types. Messages and enums are definitions
+// referred to by name, so that recursive messages work.
+
+use crate::Error;
+use descriptor::{EnumType, FieldInfo, FieldLabel, FieldType, MessageType, Pool};
+use serde_json::{Map, Value as Json, json};
+use std::collections::BTreeMap;
+
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+fn scalar(r#type: FieldType) -> Json {
+    match r#type {
+        FieldType::Int32 | FieldType::Sint32 | FieldType::Sfixed32 => {
+            json!({"type": "integer", "format": "int32"})
+        }
+        FieldType::Uint32 | FieldType::Fixed32 => {
+            json!({"type": "integer", "format": "uint32", "minimum": 0})
+        }
+        FieldType::Int64 | FieldType::Sint64 | FieldType::Sfixed64 => {
+            json!({"type": "string", "format": "int64"})
+        }
+        FieldType::Uint64 | FieldType::Fixed64 => json!({"type": "string", "format": "uint64"}),
+        FieldType::Float => json!({"type": "number", "format": "float"}),
+        FieldType::Double => json!({"type": "number", "format": "double"}),
+        FieldType::Bool => json!({"type": "boolean"}),
+        FieldType::String => json!({"type": "string"}),
+        FieldType::Bytes => json!({"type": "string", "contentEncoding": "base64"}),
+        FieldType::Message | FieldType::Group | FieldType::Enum => json!({}),
+    }
+}
+
+// Types the JSON mapping doesn't write as objects of their fields or names of their values.
+pub fn well_known(name: &str) -> Option<Json> {
+    let wrapped = match name {
+        "google.protobuf.Timestamp" => {
+            return Some(json!({"type": "string", "format": "date-time"}));
+        }
+        "google.protobuf.Duration" => {
+            return Some(json!({"type": "string", "pattern": "^-?[0-9]+(\\.[0-9]{1,9})?s$"}));
+        }
+        "google.protobuf.FieldMask" => return Some(json!({"type": "string"})),
+        "google.protobuf.Struct" => return Some(json!({"type": "object"})),
+        "google.protobuf.Value" => return Some(json!({})),
+        "google.protobuf.ListValue" => return Some(json!({"type": "array"})),
+        "google.protobuf.NullValue" => return Some(json!({"type": "null"})),
+        "google.protobuf.Any" => {
+            return Some(json!({
+                "type": "object",
+                "properties": {"@type": {"type": "string"}},
+                "required": ["@type"],
+            }));
+        }
+        "google.protobuf.DoubleValue" => FieldType::Double,
+        "google.protobuf.FloatValue" => FieldType::Float,
+        "google.protobuf.Int64Value" => FieldType::Int64,
+        "google.protobuf.UInt64Value" => FieldType::Uint64,
+        "google.protobuf.Int32Value" => FieldType::Int32,
+        "google.protobuf.UInt32Value" => FieldType::Uint32,
+        "google.protobuf.BoolValue" => FieldType::Bool,
+        "google.protobuf.StringValue" => FieldType::String,
+        "google.protobuf.BytesValue" => FieldType::Bytes,
+        _ => return None,
+    };
+
+    Some(scalar(wrapped))
+}
+
+fn r#enum(r#enum: &EnumType) -> Json {
+    let names: Vec<_> = r#enum.values.iter().map(|value| &value.name).collect();
+
+    json!({"type": "string", "enum": names})
+}
+
+pub struct Schemas<'p> {
+    pool: &'p Pool,
+    // Where the definitions are, e.g. "#/$defs/".
+    prefix: &'static str,
+    defs: BTreeMap<String, Json>,
+    // Referred to, but not defined yet.
+    pending: Vec<String>,
+}
+
+impl<'p> Schemas<'p> {
+    pub fn new(pool: &'p Pool, prefix: &'static str) -> Self {
+        Self {
+            pool,
+            prefix,
+            defs: BTreeMap::new(),
+            pending: vec![],
+        }
+    }
+
+    pub fn pool(&self) -> &'p Pool {
+        self.pool
+    }
+
+    // Schema of the values of the message or enum: a reference to its definition, unless it is a
+    // well-known type with a special form.
+    pub fn named(&mut self, name: &str) -> Result<Json, Error> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        if let Some(schema) = well_known(name) {
+            return Ok(schema);
+        }
+
+        if self.pool.message(name).is_none() && self.pool.r#enum(name).is_none() {
+            return Err(Error::UnknownType(name.to_string()));
+        }
+
+        if !self.defs.contains_key(name) && !self.pending.iter().any(|pending| pending == name) {
+            self.pending.push(name.to_string());
+        }
+
+        Ok(json!({"$ref": format!("{}{}", self.prefix, name)}))
+    }
+
+    fn value(&mut self, field: &FieldInfo) -> Result<Json, Error> {
+        match field.r#type {
+            FieldType::Message | FieldType::Group | FieldType::Enum => {
+                self.named(field.type_name.as_deref().unwrap_or_default())
+            }
+            r#type => Ok(scalar(r#type)),
+        }
+    }
+
+    // Maps are objects, as their keys are written as strings.
+    pub fn field(&mut self, field: &FieldInfo) -> Result<Json, Error> {
+        if let Some(entry) = self.pool.map_entry(field) {
+            let value = match entry.field(2) {
+                Some(value) => self.value(value)?,
+                None => json!({}),
+            };
+            return Ok(json!({"type": "object", "additionalProperties": value}));
+        }
+
+        let value = self.value(field)?;
+        Ok(match field.is_repeated() {
+            true => json!({"type": "array", "items": value}),
+            false => value,
+        })
+    }
+
+    fn message(&mut self, message: &MessageType) -> Result<Json, Error> {
+        let mut properties = Map::new();
+        let mut required = vec![];
+
+        for field in &message.fields {
+            properties.insert(field.json_name.clone(), self.field(field)?);
+            if field.label == FieldLabel::Required {
+                required.push(field.json_name.clone());
+            }
+        }
+
+        let mut schema = Map::new();
+        schema.insert("type".to_string(), json!("object"));
+        if !properties.is_empty() {
+            schema.insert("properties".to_string(), Json::Object(properties));
+        }
+        if !required.is_empty() {
+            schema.insert("required".to_string(), json!(required));
+        }
+
+        Ok(Json::Object(schema))
+    }
+
+    // Definitions of the types referred to so far and of the ones they use, by name.
+    pub fn finish(mut self) -> Result<Map<String, Json>, Error> {
+        let pool = self.pool;
+
+        while let Some(name) = self.pending.pop() {
+            // taken before it is built, for the messages referring to themselves
+            self.defs.insert(name.clone(), Json::Null);
+            let schema = match pool.message(&name) {
+                Some(message) => self.message(message)?,
+                None => r#enum(pool.r#enum(&name).ok_or(Error::UnknownType(name.clone()))?),
+            };
+            self.defs.insert(name, schema);
+        }
+
+        Ok(self.defs.into_iter().collect())
+    }
+}
+
+// Schema with the definitions of the messages and enums and of the types they use; it validates
+// the type itself when there is only one.
+pub fn json_schema(pool: &Pool, types: &[String]) -> Result<Json, Error> {
+    let mut schemas = Schemas::new(pool, "#/$defs/");
+    let roots = types
+        .iter()
+        .map(|name| schemas.named(name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut document = Map::new();
+    document.insert("$schema".to_string(), json!(DIALECT));
+    if let [Json::Object(root)] = roots.as_slice() {
+        document.extend(root.clone());
+    }
+
+    let defs = schemas.finish()?;
+    if !defs.is_empty() {
+        document.insert("$defs".to_string(), Json::Object(defs));
+    }
+
+    Ok(Json::Object(document))
+}
@@ -0,0 +1,660 @@
+// Style and validity checks over the AST of a file. Positions come from the layout of the source,
+// the leading comments of an element document it, and `// lint:ignore [RULE...]` in its leading or
+// trailing comments silences the rules (all of them without any) on that element only.
+
+use crate::rules::{Config, Severity};
+use descriptor::case;
+use editor::{Edit, EditError, Entry, Layout};
+use parser::ast;
+use parser::lexer::{Lexer, Token};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+const IGNORE: &str = "lint:ignore";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    // The name the diagnostic is about, or the whole element.
+    pub range: Range<usize>,
+    pub message: String,
+    // Edits fixing the problem, empty if there is no safe mechanical fix.
+    pub fix: Vec<Edit>,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} [{}]", self.severity, self.message, self.rule)
+    }
+}
+
+// What the comments attached to an element say about it.
+#[derive(Default)]
+struct Element {
+    documented: bool,
+    ignore_all: bool,
+    ignored: Vec<String>,
+}
+
+impl Element {
+    fn ignores(&self, rule: &str) -> bool {
+        self.ignore_all || self.ignored.iter().any(|ignored| ignored == rule)
+    }
+}
+
+fn directive(line: &str) -> Option<Vec<String>> {
+    let rules = line.trim().strip_prefix(IGNORE)?;
+
+    (rules.is_empty() || rules.starts_with(char::is_whitespace)).then(|| {
+        rules
+            .split(|ch: char| ch.is_whitespace() || ch == ',')
+            .filter(|rule| !rule.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+// The element at the index among its siblings.
+fn attached(source: &str, siblings: &[Entry], index: usize) -> Element {
+    let comments = editor::comments(source, siblings, index);
+    let leading: Vec<_> = comments
+        .leading
+        .iter()
+        .flat_map(|block| block.lines())
+        .collect();
+    let trailing: Vec<_> = comments
+        .trailing
+        .iter()
+        .flat_map(|block| block.lines())
+        .collect();
+
+    let mut element = Element {
+        documented: leading.iter().any(|line| directive(line).is_none()),
+        ..Element::default()
+    };
+
+    for rules in leading.into_iter().chain(trailing).filter_map(directive) {
+        element.ignore_all |= rules.is_empty();
+        element.ignored.extend(rules);
+    }
+
+    element
+}
+
+// Messages of the editor's checks start with a capital letter.
+fn lowercase(message: &str) -> String {
+    let mut chars = message.chars();
+    chars
+        .next()
+        .into_iter()
+        .flat_map(char::to_lowercase)
+        .chain(chars)
+        .collect()
+}
+
+fn contains(ranges: &[ast::Range], number: i64) -> bool {
+    ranges.iter().any(|range| {
+        number >= range.start
+            && match range.end {
+                ast::RangeEnd::Integer(end) => number < end,
+                ast::RangeEnd::Max => true,
+            }
+    })
+}
+
+// Why a field of the message can't have its number, besides another field having it.
+fn unavailable(message: &ast::Message, number: i64) -> Option<String> {
+    if let Err(error) = editor::check_field_number(number) {
+        return Some(error);
+    }
+
+    message.entries.iter().find_map(|entry| match entry {
+        ast::MessageEntry::ReservedIndices(ranges) if contains(ranges, number) => {
+            Some(format!("Field number {} is reserved", number))
+        }
+        ast::MessageEntry::Extensions(ranges) if contains(ranges, number) => {
+            Some(format!("Field number {} is in an extension range", number))
+        }
+        _ => None,
+    })
+}
+
+struct Linter<'l, 's> {
+    source: &'s str,
+    layout: &'l Layout,
+    config: &'l Config,
+    proto3: bool,
+    // How many times each identifier appears in the file, references included.
+    idents: HashMap<&'s str, usize>,
+    integers: Vec<Range<usize>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'l, 's> Linter<'l, 's> {
+    fn report(
+        &mut self,
+        element: &Element,
+        rule: &'static str,
+        range: Range<usize>,
+        message: String,
+        fix: Vec<Edit>,
+    ) {
+        let Some(severity) = self.config.severity(rule) else {
+            return;
+        };
+
+        if !element.ignores(rule) {
+            self.diagnostics.push(Diagnostic {
+                rule,
+                severity,
+                range,
+                message,
+                fix,
+            });
+        }
+    }
+
+    fn entry(&self, path: &[usize]) -> &'l Entry {
+        self.layout.entry(path).unwrap()
+    }
+
+    // Range of a name borrowed from the source, or of the entry it is in.
+    fn span(&self, name: &str, path: &[usize]) -> Range<usize> {
+        editor::span_of(self.source, name).unwrap_or(self.entry(path).range.clone())
+    }
+
+    // Renames a declaration the file doesn't refer to, to a name it doesn't use; references from
+    // other files can't be seen, so only names that aren't types are renamed.
+    fn rename(&self, name: &str, to: &str) -> Vec<Edit> {
+        let range = editor::span_of(self.source, name);
+
+        match (range, self.idents.get(name), self.idents.contains_key(to)) {
+            (Some(range), Some(1), false) => vec![Edit::replace(range, to)],
+            _ => vec![],
+        }
+    }
+
+    fn documented(&mut self, element: &Element, rule: &'static str, what: &str, path: &[usize]) {
+        if !element.documented {
+            let start = self.entry(path).range.start;
+            let message = format!("{} has no comment", what);
+            self.report(element, rule, start..start, message, vec![]);
+        }
+    }
+
+    fn pascal_case(
+        &mut self,
+        element: &Element,
+        rule: &'static str,
+        kind: &str,
+        name: &str,
+        path: &[usize],
+        fixable: bool,
+    ) {
+        if !case::is_pascal_case(name) {
+            let expected = case::pascal_case(name);
+            let message = format!(
+                "{} name {} should be PascalCase, e.g. {}",
+                kind, name, expected
+            );
+            let fix = match fixable {
+                true => self.rename(name, &expected),
+                false => vec![],
+            };
+            self.report(element, rule, self.span(name, path), message, fix);
+        }
+    }
+
+    fn lower_snake_case(
+        &mut self,
+        element: &Element,
+        rule: &'static str,
+        kind: &str,
+        name: &str,
+        path: &[usize],
+        fixable: bool,
+    ) {
+        if !case::is_lower_snake_case(name) {
+            let expected = case::lower_snake_case(name);
+            let message = format!(
+                "{} name {} should be lower_snake_case, e.g. {}",
+                kind, name, expected
+            );
+            let fix = match fixable {
+                true => self.rename(name, &expected),
+                false => vec![],
+            };
+            self.report(element, rule, self.span(name, path), message, fix);
+        }
+    }
+
+    fn file(&mut self, path: &str, file: &ast::File) {
+        let siblings = &self.layout.entries;
+
+        let package = file
+            .iter()
+            .position(|entry| matches!(entry, ast::FileEntry::Package(_)));
+        match package {
+            Some(index) => {
+                let ast::FileEntry::Package(package) = &file[index] else {
+                    unreachable!()
+                };
+                let element = attached(self.source, siblings, index);
+                self.package(path, package, &[index], &element);
+            }
+            None => self.report(
+                &Element::default(),
+                "PACKAGE_DEFINED",
+                0..0,
+                "file has no package".to_string(),
+                vec![],
+            ),
+        }
+
+        for (index, entry) in file.iter().enumerate() {
+            let element = attached(self.source, siblings, index);
+            let path = [index];
+
+            match entry {
+                ast::FileEntry::Message(message) => self.message(message, &path, &element),
+                ast::FileEntry::Enum(r#enum) => self.r#enum(r#enum, &path, &element),
+                ast::FileEntry::Service(service) => self.service(service, &path, &element),
+                ast::FileEntry::Extend(extend) => self.extend(extend, &path),
+                _ => {}
+            }
+        }
+    }
+
+    fn package(&mut self, path: &str, package: &str, at: &[usize], element: &Element) {
+        let range = self.span(package, at);
+
+        if !package.split('.').all(case::is_lower_snake_case) {
+            let expected: Vec<_> = package.split('.').map(case::lower_snake_case).collect();
+            let message = format!(
+                "package name {} should be lower_snake_case, e.g. {}",
+                package,
+                expected.join(".")
+            );
+            self.report(
+                element,
+                "PACKAGE_LOWER_SNAKE_CASE",
+                range.clone(),
+                message,
+                vec![],
+            );
+        }
+
+        let directory = path.rsplit_once('/').map_or("", |(directory, _)| directory);
+        if directory.replace('/', ".") != package {
+            let message = format!(
+                "files of package {} should be in directory {}",
+                package,
+                package.replace('.', "/")
+            );
+            self.report(element, "PACKAGE_DIRECTORY_MATCH", range, message, vec![]);
+        }
+    }
+
+    fn message(&mut self, message: &ast::Message, path: &[usize], element: &Element) {
+        let what = format!("message {}", message.ident);
+        self.pascal_case(
+            element,
+            "MESSAGE_PASCAL_CASE",
+            "message",
+            message.ident,
+            path,
+            false,
+        );
+        self.documented(element, "COMMENT_MESSAGE", &what, path);
+
+        let siblings = &self.entry(path).entries;
+
+        for (index, child) in message.entries.iter().enumerate() {
+            let element = attached(self.source, siblings, index);
+            let path = [path, &[index]].concat();
+
+            match child {
+                ast::MessageEntry::Field(field) => self.field(field, &path, &element, false),
+                ast::MessageEntry::OneOf(oneof) => self.oneof(oneof, &path, &element),
+                ast::MessageEntry::Message(message) => self.message(message, &path, &element),
+                ast::MessageEntry::Enum(r#enum) => self.r#enum(r#enum, &path, &element),
+                ast::MessageEntry::Extend(extend) => self.extend(extend, &path),
+                _ => {}
+            }
+        }
+
+        self.numbers(message);
+    }
+
+    // Fields with numbers taken by an earlier field, reserved or out of range get free ones.
+    fn numbers(&mut self, message: &ast::Message) {
+        let mut seen = HashMap::new();
+        let mut assigned = HashSet::new();
+        let mut next = editor::next_field_number(message);
+
+        for field in editor::message_fields(message) {
+            let error = match seen.get(&field.index) {
+                Some(other) => Some(format!(
+                    "Field number {} is already used by {}",
+                    field.index, other
+                )),
+                None => unavailable(message, field.index),
+            };
+            seen.entry(field.index).or_insert(field.ident);
+
+            let Some(error) = error else {
+                continue;
+            };
+
+            // the number is the first one after the name
+            let range = editor::span_of(self.source, field.ident).unwrap_or_default();
+            let number = self
+                .integers
+                .iter()
+                .find(|integer| integer.start >= range.end)
+                .cloned();
+
+            while assigned.contains(&next) || editor::check_number(message, next, None).is_err() {
+                next += 1;
+            }
+            let fix = match number {
+                Some(number) if next <= editor::MAX_FIELD_NUMBER => {
+                    assigned.insert(next);
+                    vec![Edit::replace(number, next.to_string())]
+                }
+                _ => vec![],
+            };
+
+            let message = format!("field {}: {}", field.ident, lowercase(&error));
+            let element = Element::default();
+            self.report(&element, "FIELD_NUMBER_AVAILABLE", range, message, fix);
+        }
+    }
+
+    fn field(&mut self, field: &ast::Field, path: &[usize], element: &Element, in_oneof: bool) {
+        let what = format!("field {}", field.ident);
+        self.lower_snake_case(
+            element,
+            "FIELD_LOWER_SNAKE_CASE",
+            "field",
+            field.ident,
+            path,
+            true,
+        );
+        self.documented(element, "COMMENT_FIELD", &what, path);
+
+        if let Err(error) = editor::check_label(field, self.proto3, in_oneof) {
+            let start = self.entry(path).range.start;
+            let fix = match (&field.modifier, editor::span_of(self.source, field.r#type)) {
+                (ast::FieldModifier::None, _) => vec![Edit::insert(start, "optional ")],
+                (_, Some(r#type)) => vec![Edit::delete(start..r#type.start)],
+                (_, None) => vec![],
+            };
+
+            let message = format!("{}: {}", what, lowercase(&error));
+            self.report(element, "FIELD_LABEL_VALID", start..start, message, fix);
+        }
+    }
+
+    fn oneof(&mut self, oneof: &ast::OneOf, path: &[usize], element: &Element) {
+        let what = format!("oneof {}", oneof.ident);
+        self.lower_snake_case(
+            element,
+            "ONEOF_LOWER_SNAKE_CASE",
+            "oneof",
+            oneof.ident,
+            path,
+            true,
+        );
+        self.documented(element, "COMMENT_ONEOF", &what, path);
+
+        let siblings = &self.entry(path).entries;
+
+        for (index, child) in oneof.entries.iter().enumerate() {
+            if let ast::OneOfEntry::Field(field) = child {
+                let element = attached(self.source, siblings, index);
+                self.field(field, &[path, &[index]].concat(), &element, true);
+            }
+        }
+    }
+
+    // Extensions are named like fields, but are documented and referred to where they are used.
+    fn extend(&mut self, extend: &ast::Extend, path: &[usize]) {
+        let siblings = &self.entry(path).entries;
+
+        for (index, child) in extend.entries.iter().enumerate() {
+            if let ast::ExtendEntry::Field(field) = child {
+                let element = attached(self.source, siblings, index);
+                let path = [path, &[index]].concat();
+                let rule = "FIELD_LOWER_SNAKE_CASE";
+                self.lower_snake_case(&element, rule, "field", field.ident, &path, false);
+            }
+        }
+    }
+
+    fn r#enum(&mut self, r#enum: &ast::Enum, path: &[usize], element: &Element) {
+        let what = format!("enum {}", r#enum.ident);
+        self.pascal_case(
+            element,
+            "ENUM_PASCAL_CASE",
+            "enum",
+            r#enum.ident,
+            path,
+            false,
+        );
+        self.documented(element, "COMMENT_ENUM", &what, path);
+
+        let siblings = &self.entry(path).entries;
+        let prefix = format!("{}_", case::upper_snake_case(r#enum.ident));
+
+        let first = r#enum
+            .entries
+            .iter()
+            .position(|entry| matches!(entry, ast::EnumEntry::Pair { .. }));
+
+        for (index, child) in r#enum.entries.iter().enumerate() {
+            let ast::EnumEntry::Pair { ident, value, .. } = child else {
+                continue;
+            };
+            let element = attached(self.source, siblings, index);
+            let path = [path, &[index]].concat();
+            let range = self.span(ident, &path);
+
+            self.documented(
+                &element,
+                "COMMENT_ENUM_VALUE",
+                &format!("enum value {}", ident),
+                &path,
+            );
+
+            let upper = case::upper_snake_case(ident);
+            let expected = match upper.starts_with(&prefix) {
+                true => upper.clone(),
+                false => format!("{}{}", prefix, upper),
+            };
+
+            if !case::is_upper_snake_case(ident) {
+                let message = format!(
+                    "enum value name {} should be UPPER_SNAKE_CASE, e.g. {}",
+                    ident, upper
+                );
+                let fix = self.rename(ident, &expected);
+                let rule = "ENUM_VALUE_UPPER_SNAKE_CASE";
+                self.report(&element, rule, range.clone(), message, fix);
+            }
+
+            if !upper.starts_with(&prefix) {
+                let message = format!(
+                    "enum value name {} should start with {}, e.g. {}",
+                    ident, prefix, expected
+                );
+                let fix = self.rename(ident, &expected);
+                self.report(&element, "ENUM_VALUE_PREFIX", range.clone(), message, fix);
+            }
+
+            if *value == 0 && !ident.ends_with("_UNSPECIFIED") {
+                let message = format!(
+                    "zero value {} of enum {} should end with _UNSPECIFIED, e.g. {}UNSPECIFIED",
+                    ident, r#enum.ident, prefix
+                );
+                self.report(
+                    &element,
+                    "ENUM_ZERO_VALUE_SUFFIX",
+                    range.clone(),
+                    message,
+                    vec![],
+                );
+            }
+
+            if self.proto3 && Some(index) == first && *value != 0 {
+                let message = format!("the first value of enum {} should be zero", r#enum.ident);
+                let fix = self.zero_value(r#enum, &prefix, &path);
+                self.report(&element, "ENUM_FIRST_VALUE_ZERO", range, message, fix);
+            }
+        }
+    }
+
+    // A new `<ENUM>_UNSPECIFIED = 0` before the first value, unless zero is used by another one.
+    fn zero_value(&self, r#enum: &ast::Enum, prefix: &str, first: &[usize]) -> Vec<Edit> {
+        let name = format!("{}UNSPECIFIED", prefix);
+        let (index, parent) = first.split_last().unwrap();
+
+        let taken = r#enum.entries.iter().any(|entry| {
+            matches!(entry, ast::EnumEntry::Pair { ident, value, .. } if *value == 0 || *ident == name)
+        });
+        let text = format!("{} = 0;", name);
+
+        match taken {
+            true => vec![],
+            false => editor::insert(self.source, self.layout, parent, *index, &text)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn service(&mut self, service: &ast::Service, path: &[usize], element: &Element) {
+        let what = format!("service {}", service.ident);
+        self.pascal_case(
+            element,
+            "SERVICE_PASCAL_CASE",
+            "service",
+            service.ident,
+            path,
+            false,
+        );
+        self.documented(element, "COMMENT_SERVICE", &what, path);
+
+        let siblings = &self.entry(path).entries;
+
+        for (index, child) in service.entries.iter().enumerate() {
+            let ast::ServiceEntry::Rpc(rpc) = child else {
+                continue;
+            };
+            let element = attached(self.source, siblings, index);
+            let path = [path, &[index]].concat();
+
+            // the name of the method is part of the path of its calls
+            self.pascal_case(
+                &element,
+                "RPC_PASCAL_CASE",
+                "method",
+                rpc.ident,
+                &path,
+                false,
+            );
+            self.documented(
+                &element,
+                "COMMENT_RPC",
+                &format!("method {}", rpc.ident),
+                &path,
+            );
+
+            for (rule, r#type, suffix) in [
+                ("RPC_REQUEST_STANDARD_NAME", rpc.request, "Request"),
+                ("RPC_RESPONSE_STANDARD_NAME", rpc.reply, "Response"),
+            ] {
+                let name = r#type.rsplit('.').next().unwrap_or(r#type);
+                let expected = format!("{}{}", case::pascal_case(rpc.ident), suffix);
+
+                if name != expected && name != format!("{}{}", service.ident, expected) {
+                    let message = format!(
+                        "{} of method {} should be named {} or {}{}",
+                        suffix.to_lowercase(),
+                        rpc.ident,
+                        expected,
+                        service.ident,
+                        expected
+                    );
+                    self.report(&element, rule, self.span(r#type, &path), message, vec![]);
+                }
+            }
+        }
+    }
+}
+
+// Path is relative to the root of the import paths, for the directory of the package.
+pub fn lint(path: &str, source: &str, file: &ast::File, config: &Config) -> Vec<Diagnostic> {
+    let layout = Layout::new(source, file);
+    let mut idents = HashMap::new();
+    let mut integers = vec![];
+
+    for (start, token, end) in Lexer::new(source).map_while(Result::ok) {
+        match token {
+            Token::Ident(ident) => *idents.entry(ident).or_default() += 1,
+            Token::Integer(_) => integers.push(start..end),
+            _ => {}
+        }
+    }
+
+    let mut linter = Linter {
+        source,
+        layout: &layout,
+        config,
+        proto3: file
+            .iter()
+            .any(|entry| matches!(entry, ast::FileEntry::Syntax("proto3"))),
+        idents,
+        integers,
+        diagnostics: vec![],
+    };
+
+    linter.file(path, file);
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+
+    diagnostics
+}
+
+// The source with the fixes of the diagnostics applied, and how many of them were fixed. Fixes
+// overlapping an earlier one are left for another run, unless they make the same edits; the result
+// must parse.
+pub fn fix(source: &str, diagnostics: &[Diagnostic]) -> Result<(String, usize), EditError> {
+    let mut edits: Vec<Edit> = vec![];
+    let mut fixed = 0;
+
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|diagnostic| !diagnostic.fix.is_empty())
+    {
+        let new: Vec<_> = diagnostic
+            .fix
+            .iter()
+            .filter(|edit| !edits.contains(edit))
+            .collect();
+
+        let overlaps = new.iter().any(|edit| {
+            edits.iter().any(|other| {
+                edit.range.start < other.range.end && other.range.start < edit.range.end
+            })
+        });
+
+        if !overlaps {
+            edits.extend(new.into_iter().cloned());
+            fixed += 1;
+        }
+    }
+
+    Ok((editor::edit(source, &edits)?, fixed))
+}
@@ -0,0 +1,170 @@
+mod check;
+mod rules;
+
+pub use check::{Diagnostic, fix, lint};
+pub use rules::{Config, LintError, Preset, RULES, Rule, Severity, find_rule};
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, LintError, Preset, Severity};
+    use std::path::PathBuf;
+
+    fn source() -> String {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data/lint/acme/v1/store.proto");
+
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    // Lines and rules of the diagnostics.
+    fn lint(path: &str, source: &str, config: &Config) -> Vec<(usize, &'static str)> {
+        let file = parser::parse(source).unwrap();
+
+        crate::lint(path, source, &file, config)
+            .iter()
+            .map(|diagnostic| {
+                let (line, _) = parser::line_column(source, diagnostic.range.start);
+                (line, diagnostic.rule)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn style_rules() {
+        let source = source();
+        let file = parser::parse(&source).unwrap();
+        let diagnostics = crate::lint("acme/v1/store.proto", &source, &file, &Config::default());
+
+        let lines: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "error: field name unitPrice should be lower_snake_case, e.g. unit_price [FIELD_LOWER_SNAKE_CASE]",
+                "error: oneof name Availability should be lower_snake_case, e.g. availability [ONEOF_LOWER_SNAKE_CASE]",
+                "error: message name variant_info should be PascalCase, e.g. VariantInfo [MESSAGE_PASCAL_CASE]",
+                "error: enum value name NONE should start with SHIPPING_, e.g. SHIPPING_NONE [ENUM_VALUE_PREFIX]",
+                "error: zero value NONE of enum Shipping should end with _UNSPECIFIED, e.g. SHIPPING_UNSPECIFIED [ENUM_ZERO_VALUE_SUFFIX]",
+                "error: enum value name SHIPPING_air should be UPPER_SNAKE_CASE, e.g. SHIPPING_AIR [ENUM_VALUE_UPPER_SNAKE_CASE]",
+                "error: response of method GetProduct should be named GetProductResponse or StoreGetProductResponse [RPC_RESPONSE_STANDARD_NAME]",
+                "error: method name list_products should be PascalCase, e.g. ListProducts [RPC_PASCAL_CASE]",
+            ]
+        );
+        assert_eq!(
+            &source[diagnostics[0].range.clone()],
+            "unitPrice",
+            "diagnostics point at the names"
+        );
+
+        // in another directory, and without a package
+        assert_eq!(
+            lint("store.proto", &source, &Config::default())[0],
+            (3, "PACKAGE_DIRECTORY_MATCH")
+        );
+        assert_eq!(
+            lint("a.proto", "syntax = \"proto3\";\n", &Config::default()),
+            [(1, "PACKAGE_DEFINED")]
+        );
+    }
+
+    #[test]
+    fn comment_rules() {
+        let source = source();
+        let comments: Vec<_> = lint(
+            "acme/v1/store.proto",
+            &source,
+            &Config::preset(Preset::Strict),
+        )
+        .into_iter()
+        .filter(|(_, rule)| rule.starts_with("COMMENT_"))
+        .collect();
+
+        assert_eq!(
+            comments,
+            [
+                (7, "COMMENT_FIELD"),
+                (8, "COMMENT_FIELD"),
+                // only ignoring another rule is no documentation
+                (10, "COMMENT_FIELD"),
+                (11, "COMMENT_ONEOF"),
+                (12, "COMMENT_FIELD"),
+                (13, "COMMENT_FIELD"),
+                (16, "COMMENT_MESSAGE"),
+                (23, "COMMENT_ENUM_VALUE"),
+                (24, "COMMENT_ENUM_VALUE"),
+                (25, "COMMENT_ENUM_VALUE"),
+                (28, "COMMENT_ENUM"),
+                (29, "COMMENT_ENUM_VALUE"),
+                (30, "COMMENT_ENUM_VALUE"),
+                (37, "COMMENT_RPC"),
+                (40, "COMMENT_MESSAGE"),
+                (41, "COMMENT_FIELD"),
+                (44, "COMMENT_MESSAGE"),
+                (46, "COMMENT_MESSAGE"),
+                (47, "COMMENT_FIELD"),
+            ]
+        );
+    }
+
+    #[test]
+    fn fixes() {
+        let source = "syntax = \"proto3\";\n\npackage acme.v1;\n\nmessage Order {\n  required string orderId = 1;\n  int32 count = 1;\n  int32 total = 5;\n  string Orders = 4;\n  reserved 5;\n  oneof Payment {\n    string card = 2;\n  }\n}\n\nenum Color {\n  red = 1;\n  COLOR_BLUE = 2;\n}\n\nservice Orders {\n  rpc get_order(GetOrderRequest) returns (GetOrderResponse);\n}\n\nmessage GetOrderRequest {}\n\nmessage GetOrderResponse {}\n";
+        let file = parser::parse(source).unwrap();
+        let diagnostics = crate::lint("acme/v1/order.proto", source, &file, &Config::default());
+
+        let (fixed, count) = crate::fix(source, &diagnostics).unwrap();
+        assert_eq!(count, diagnostics.len() - 2);
+        assert_eq!(
+            fixed,
+            "syntax = \"proto3\";\n\npackage acme.v1;\n\nmessage Order {\n  string order_id = 1;\n  int32 count = 6;\n  int32 total = 7;\n  string Orders = 4;\n  reserved 5;\n  oneof payment {\n    string card = 2;\n  }\n}\n\nenum Color {\n  COLOR_UNSPECIFIED = 0;\n  COLOR_RED = 1;\n  COLOR_BLUE = 2;\n}\n\nservice Orders {\n  rpc get_order(GetOrderRequest) returns (GetOrderResponse);\n}\n\nmessage GetOrderRequest {}\n\nmessage GetOrderResponse {}\n"
+        );
+
+        // the name of the service is taken, and renaming a method breaks its callers
+        assert_eq!(
+            lint("acme/v1/order.proto", &fixed, &Config::default()),
+            [(9, "FIELD_LOWER_SNAKE_CASE"), (23, "RPC_PASCAL_CASE")]
+        );
+    }
+
+    #[test]
+    fn configuration() {
+        let config = Config::parse(
+            "# shared settings\npreset = strict\nCOMMENT_FIELD = off\nENUM_VALUE_PREFIX = warning\n",
+        )
+        .unwrap();
+        assert_eq!(config.severity("COMMENT_FIELD"), None);
+        assert_eq!(config.severity("COMMENT_RPC"), Some(Severity::Warning));
+        assert_eq!(
+            config.severity("ENUM_VALUE_PREFIX"),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            config.severity("MESSAGE_PASCAL_CASE"),
+            Some(Severity::Error)
+        );
+
+        let mut config = Config::default();
+        config.disable("FIELD_LOWER_SNAKE_CASE").unwrap();
+        config.enable("COMMENT_RPC").unwrap();
+        let source = source();
+        let rules: Vec<_> = lint("acme/v1/store.proto", &source, &config)
+            .into_iter()
+            .map(|(_, rule)| rule)
+            .collect();
+        assert!(!rules.contains(&"FIELD_LOWER_SNAKE_CASE"));
+        assert!(rules.contains(&"COMMENT_RPC"));
+
+        assert_eq!(
+            config.enable("MESSAGE_NAMES"),
+            Err(LintError::UnknownRule("MESSAGE_NAMES".to_string()))
+        );
+        assert_eq!(
+            Config::parse("\nCOMMENT_RPC = loud")
+                .unwrap_err()
+                .to_string(),
+            "Line 2: Unknown level loud, expected off, warning or error"
+        );
+    }
+}
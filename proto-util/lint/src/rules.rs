@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "warning" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    // Part of the default preset; the strict one has every rule.
+    pub default: bool,
+    pub severity: Severity,
+}
+
+const fn rule(id: &'static str, description: &'static str, default: bool) -> Rule {
+    Rule {
+        id,
+        description,
+        default,
+        severity: match default {
+            true => Severity::Error,
+            false => Severity::Warning,
+        },
+    }
+}
+
+pub const RULES: &[Rule] = &[
+    rule("PACKAGE_DEFINED", "files declare a package", true),
+    rule(
+        "PACKAGE_LOWER_SNAKE_CASE",
+        "package names are lower_snake_case",
+        true,
+    ),
+    rule(
+        "PACKAGE_DIRECTORY_MATCH",
+        "files are in the directory matching their package",
+        true,
+    ),
+    rule("MESSAGE_PASCAL_CASE", "message names are PascalCase", true),
+    rule(
+        "FIELD_LOWER_SNAKE_CASE",
+        "field names are lower_snake_case",
+        true,
+    ),
+    rule(
+        "ONEOF_LOWER_SNAKE_CASE",
+        "oneof names are lower_snake_case",
+        true,
+    ),
+    rule("ENUM_PASCAL_CASE", "enum names are PascalCase", true),
+    rule(
+        "ENUM_VALUE_UPPER_SNAKE_CASE",
+        "enum value names are UPPER_SNAKE_CASE",
+        true,
+    ),
+    rule(
+        "ENUM_VALUE_PREFIX",
+        "enum value names start with the name of their enum",
+        true,
+    ),
+    rule(
+        "ENUM_ZERO_VALUE_SUFFIX",
+        "the zero values of enums end with _UNSPECIFIED",
+        true,
+    ),
+    rule("SERVICE_PASCAL_CASE", "service names are PascalCase", true),
+    rule("RPC_PASCAL_CASE", "method names are PascalCase", true),
+    rule(
+        "RPC_REQUEST_STANDARD_NAME",
+        "methods take <Method>Request messages",
+        true,
+    ),
+    rule(
+        "RPC_RESPONSE_STANDARD_NAME",
+        "methods return <Method>Response messages",
+        true,
+    ),
+    rule(
+        "FIELD_LABEL_VALID",
+        "field labels are allowed where the fields are",
+        true,
+    ),
+    rule(
+        "FIELD_NUMBER_AVAILABLE",
+        "field numbers are valid, unique and not reserved",
+        true,
+    ),
+    rule(
+        "ENUM_FIRST_VALUE_ZERO",
+        "the first values of proto3 enums are zero",
+        true,
+    ),
+    rule("COMMENT_MESSAGE", "messages have leading comments", false),
+    rule("COMMENT_FIELD", "fields have leading comments", false),
+    rule("COMMENT_ONEOF", "oneofs have leading comments", false),
+    rule("COMMENT_ENUM", "enums have leading comments", false),
+    rule(
+        "COMMENT_ENUM_VALUE",
+        "enum values have leading comments",
+        false,
+    ),
+    rule("COMMENT_SERVICE", "services have leading comments", false),
+    rule("COMMENT_RPC", "methods have leading comments", false),
+];
+
+pub fn find_rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    // Naming of the Google and Buf style guides.
+    Default,
+    // Also documentation comments on every element.
+    Strict,
+}
+
+impl Preset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::Default),
+            "strict" => Some(Self::Strict),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintError {
+    UnknownRule(String),
+    UnknownLevel(String),
+    // Line of the configuration and what is wrong with it.
+    Config(usize, String),
+}
+
+impl std::fmt::Display for LintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownRule(id) => write!(f, "Unknown lint rule {}", id),
+            Self::UnknownLevel(level) => {
+                write!(f, "Unknown level {}, expected off, warning or error", level)
+            }
+            Self::Config(line, message) => write!(f, "Line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for LintError {}
+
+// Enabled rules with their severities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    rules: BTreeMap<&'static str, Severity>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::preset(Preset::Default)
+    }
+}
+
+impl Config {
+    pub fn preset(preset: Preset) -> Self {
+        let rules = RULES
+            .iter()
+            .filter(|rule| rule.default || preset == Preset::Strict)
+            .map(|rule| (rule.id, rule.severity))
+            .collect();
+
+        Self { rules }
+    }
+
+    // Lines of `preset = <name>` and `<RULE> = off|warning|error`, applied to the default preset
+    // in order; `#` starts a comment.
+    pub fn parse(text: &str) -> Result<Self, LintError> {
+        let mut config = Self::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: String| LintError::Config(index + 1, message);
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(format!("Expected <RULE> = <level>, found {}", line)));
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            if key == "preset" {
+                let preset = Preset::from_name(value)
+                    .ok_or_else(|| error(format!("Unknown preset {}", value)))?;
+                config = Self::preset(preset);
+                continue;
+            }
+
+            config
+                .set(key, value)
+                .map_err(|inner| error(inner.to_string()))?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn enable(&mut self, id: &str) -> Result<(), LintError> {
+        let rule = find_rule(id).ok_or_else(|| LintError::UnknownRule(id.to_string()))?;
+        self.rules.insert(rule.id, rule.severity);
+
+        Ok(())
+    }
+
+    pub fn disable(&mut self, id: &str) -> Result<(), LintError> {
+        let rule = find_rule(id).ok_or_else(|| LintError::UnknownRule(id.to_string()))?;
+        self.rules.remove(rule.id);
+
+        Ok(())
+    }
+
+    // Enables the rule with the severity, or disables it for "off".
+    pub fn set(&mut self, id: &str, level: &str) -> Result<(), LintError> {
+        let rule = find_rule(id).ok_or_else(|| LintError::UnknownRule(id.to_string()))?;
+
+        match (level, Severity::from_name(level)) {
+            ("off", _) => self.rules.remove(rule.id),
+            (_, Some(severity)) => self.rules.insert(rule.id, severity),
+            (_, None) => return Err(LintError::UnknownLevel(level.to_string())),
+        };
+
+        Ok(())
+    }
+
+    // None if the rule is disabled.
+    pub fn severity(&self, id: &str) -> Option<Severity> {
+        self.rules.get(id).copied()
+    }
+}
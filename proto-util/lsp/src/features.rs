@@ -0,0 +1,707 @@
+// Answers to the requests of the editor, made on top of the resolver. Offsets are those of the
+// sources in the workspace; the server makes sure they are the ones the editor shows.
+
+use crate::protocol;
+use crate::snapshot::Snapshot;
+use descriptor::names;
+use descriptor::options::OptionsKind;
+use editor::{Entry, Layout, ident_of, span_of};
+use parser::ast;
+use parser::lexer::{Lexer, Token};
+use resolver::{Node, Reference, Symbol, SymbolKind, Workspace};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+const ERROR: u8 = 1;
+const WARNING: u8 = 2;
+
+const SCALARS: &[&str] = &[
+    "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32",
+    "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes",
+];
+
+// A component of a name written in a file with the symbol it stands for: `Order.Item` mentions
+// both `Order` and `Order.Item`.
+struct Mention<'w> {
+    range: Range<usize>,
+    symbol: &'w Symbol,
+}
+
+fn mentions<'w>(workspace: &'w Workspace, reference: &Reference) -> Vec<Mention<'w>> {
+    let name = reference.name;
+    let Some(span) = span_of(workspace.file(reference.file).source, name) else {
+        return vec![];
+    };
+
+    let mut mentions = vec![];
+    let mut start = usize::from(name.starts_with('.'));
+
+    while start < name.len() {
+        let end = name[start..]
+            .find('.')
+            .map_or(name.len(), |index| start + index);
+        let symbol = match end == name.len() {
+            true => workspace.resolve_reference(reference),
+            false => workspace.resolve(reference.file, &reference.scope, &name[..end], |kind| {
+                kind != SymbolKind::Package
+            }),
+        };
+
+        if let Ok(symbol) = symbol {
+            mentions.push(Mention {
+                range: span.start + start..span.start + end,
+                symbol,
+            });
+        }
+
+        start = end + 1;
+    }
+
+    mentions
+}
+
+// Range of the identifier declaring the symbol.
+fn declaration(workspace: &Workspace, symbol: &Symbol) -> Option<Range<usize>> {
+    let ident = workspace.node(symbol).and_then(ident_of)?;
+    span_of(workspace.file(symbol.file).source, ident)
+}
+
+// Symbol mentioned or declared at the offset, with the range of its name there.
+fn symbol_at<'w>(
+    workspace: &'w Workspace,
+    file: usize,
+    offset: usize,
+) -> Option<(&'w Symbol, Range<usize>)> {
+    let contains = |range: &Range<usize>| range.start <= offset && offset <= range.end;
+
+    let mentioned = workspace
+        .references(file)
+        .iter()
+        .flat_map(|reference| mentions(workspace, reference))
+        .find(|mention| contains(&mention.range));
+
+    if let Some(mention) = mentioned {
+        return Some((mention.symbol, mention.range));
+    }
+
+    workspace
+        .symbols()
+        .filter(|symbol| symbol.file == file)
+        .find_map(|symbol| {
+            let range = declaration(workspace, symbol)?;
+            contains(&range).then_some((symbol, range))
+        })
+}
+
+fn location(
+    snapshot: &Snapshot,
+    workspace: &Workspace,
+    file: usize,
+    range: Range<usize>,
+) -> Option<Value> {
+    let file = workspace.file(file);
+
+    Some(json!({
+        "uri": snapshot.uri(file.name)?,
+        "range": protocol::range(file.source, range),
+    }))
+}
+
+pub fn definition(snapshot: &Snapshot, workspace: &Workspace, file: usize, offset: usize) -> Value {
+    symbol_at(workspace, file, offset)
+        .and_then(|(symbol, _)| {
+            let range = declaration(workspace, symbol)?;
+            location(snapshot, workspace, symbol.file, range)
+        })
+        .unwrap_or(Value::Null)
+}
+
+pub fn references(
+    snapshot: &Snapshot,
+    workspace: &Workspace,
+    file: usize,
+    offset: usize,
+    include_declaration: bool,
+) -> Value {
+    let Some((target, _)) = symbol_at(workspace, file, offset) else {
+        return Value::Null;
+    };
+
+    let mut locations = vec![];
+
+    if include_declaration && let Some(range) = declaration(workspace, target) {
+        locations.extend(location(snapshot, workspace, target.file, range));
+    }
+
+    for file in 0..workspace.files().len() {
+        for reference in workspace.references(file) {
+            for mention in mentions(workspace, &reference) {
+                if mention.symbol.name == target.name {
+                    locations.extend(location(snapshot, workspace, file, mention.range));
+                }
+            }
+        }
+    }
+
+    Value::Array(locations)
+}
+
+// Lines of the leading and trailing comments of the declaration at the path.
+fn documentation(source: &str, file: &ast::File, path: &[usize]) -> Vec<String> {
+    let layout = Layout::new(source, file);
+    let comments = editor::comments_at(source, &layout, path);
+    let blocks: Vec<_> = comments
+        .leading
+        .into_iter()
+        .chain(comments.trailing)
+        .collect();
+
+    blocks
+        .iter()
+        .map(|block| block.lines().join("\n"))
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+pub fn hover(workspace: &Workspace, file: usize, offset: usize) -> Value {
+    let Some((symbol, range)) = symbol_at(workspace, file, offset) else {
+        return Value::Null;
+    };
+
+    let mut code = format!("{} {}", symbol.kind, symbol.name);
+    match workspace.node(symbol) {
+        Some(Node::Field(field)) => code = format!("{}\n{}", code, formatter::field_line(field)),
+        Some(Node::Rpc(rpc)) => code = format!("{}\n{}", code, formatter::rpc_signature(rpc)),
+        _ => {}
+    }
+
+    let declared = workspace.file(symbol.file);
+    let comments = documentation(declared.source, &declared.ast, &symbol.path);
+
+    let mut value = format!("```proto\n{}\n```", code);
+    if !comments.is_empty() {
+        value = format!("{}\n\n{}", value, comments.join("\n\n"));
+    }
+
+    json!({
+        "contents": { "kind": "markdown", "value": value },
+        "range": protocol::range(workspace.file(file).source, range),
+    })
+}
+
+enum Expected {
+    Type {
+        messages_only: bool,
+    },
+    // Custom options only after `(`, written without the parentheses.
+    Option {
+        kind: OptionsKind,
+        custom_only: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Block<'s> {
+    Message(&'s str),
+    Enum,
+    Service(&'s str),
+    Rpc,
+    OneOf,
+    Extend,
+    // option values and anything unexpected
+    Other,
+}
+
+// What may be written at the end of the text, the scope it is written in and where the name
+// being written starts. The text is tokenized rather than parsed, since it is being edited.
+fn expected(text: &str) -> (Option<Expected>, String, usize) {
+    let mut package = vec![];
+    let mut blocks = vec![];
+    let mut statement: Vec<(usize, Token, usize)> = vec![];
+
+    for (start, token, end) in Lexer::new(text).filter_map(Result::ok) {
+        match token {
+            Token::SingleLineComment(_) | Token::MultiLineComment(_) => {}
+            Token::OpenBrace => {
+                let ident = match statement.get(1) {
+                    Some((_, Token::Ident(ident), _)) => ident,
+                    _ => "",
+                };
+
+                blocks.push(match statement.first().map(|(_, token, _)| token) {
+                    Some(Token::Message) => Block::Message(ident),
+                    Some(Token::Enum) => Block::Enum,
+                    Some(Token::Service) => Block::Service(ident),
+                    Some(Token::Rpc) => Block::Rpc,
+                    Some(Token::OneOf) => Block::OneOf,
+                    Some(Token::Extend) => Block::Extend,
+                    _ => Block::Other,
+                });
+                statement.clear();
+            }
+            Token::CloseBrace => {
+                blocks.pop();
+                statement.clear();
+            }
+            Token::Semicolon => {
+                if let Some((_, Token::Package, _)) = statement.first() {
+                    package = statement[1..]
+                        .iter()
+                        .filter_map(|(_, token, _)| match token {
+                            Token::Ident(ident) => Some(*ident),
+                            _ => None,
+                        })
+                        .collect();
+                }
+                statement.clear();
+            }
+            token => statement.push((start, token, end)),
+        }
+    }
+
+    let mut scope = package.join(".");
+    for block in &blocks {
+        if let Block::Message(ident) | Block::Service(ident) = block {
+            scope = names::join(&scope, ident);
+        }
+    }
+
+    // the name being written is made of the identifiers and dots touching the end
+    let mut typed = statement.len();
+    let mut start = text.len();
+    while typed > 0
+        && matches!(statement[typed - 1].1, Token::Ident(_) | Token::Period)
+        && statement[typed - 1].2 == start
+    {
+        typed -= 1;
+        start = statement[typed].0;
+    }
+
+    let before = &statement[..typed];
+    let first = before.first().map(|(_, token, _)| token);
+    let last = before.last().map(|(_, token, _)| token);
+    let block = blocks.last().copied();
+
+    let brackets = before.iter().fold(0, |depth, (_, token, _)| match token {
+        Token::OpenBracket => depth + 1,
+        Token::CloseBracket => depth - 1,
+        _ => depth,
+    });
+
+    let expected = if brackets > 0
+        && matches!(
+            last,
+            Some(Token::OpenBracket | Token::Comma | Token::OpenPth)
+        ) {
+        let kind = match block {
+            Some(Block::Enum) => OptionsKind::EnumValue,
+            _ => OptionsKind::Field,
+        };
+
+        Some(Expected::Option {
+            kind,
+            custom_only: last == Some(&Token::OpenPth),
+        })
+    } else if first == Some(&Token::Option) && before.len() <= 2 {
+        let kind = match block {
+            None => OptionsKind::File,
+            Some(Block::Message(_)) => OptionsKind::Message,
+            Some(Block::Enum) => OptionsKind::Enum,
+            Some(Block::Service(_)) => OptionsKind::Service,
+            Some(Block::Rpc) => OptionsKind::Method,
+            Some(Block::OneOf) => OptionsKind::OneOf,
+            Some(Block::Extend | Block::Other) => return (None, scope, start),
+        };
+
+        match last {
+            Some(Token::Option) => Some(Expected::Option {
+                kind,
+                custom_only: false,
+            }),
+            Some(Token::OpenPth) => Some(Expected::Option {
+                kind,
+                custom_only: true,
+            }),
+            _ => None,
+        }
+    } else {
+        let labeled = before
+            .iter()
+            .skip_while(|(_, token, _)| {
+                matches!(token, Token::Optional | Token::Required | Token::Repeated)
+            })
+            .count();
+
+        match (block, first, last) {
+            (Some(Block::Message(_) | Block::OneOf | Block::Extend), _, _) if labeled == 0 => {
+                Some(Expected::Type {
+                    messages_only: false,
+                })
+            }
+            (
+                Some(Block::Message(_) | Block::OneOf | Block::Extend),
+                Some(Token::Map),
+                Some(Token::OpenAngle | Token::Comma),
+            ) => Some(Expected::Type {
+                messages_only: false,
+            }),
+            (Some(Block::Service(_)), Some(Token::Rpc), Some(Token::OpenPth | Token::Stream)) => {
+                Some(Expected::Type {
+                    messages_only: true,
+                })
+            }
+            (None | Some(Block::Message(_)), Some(Token::Extend), _) if before.len() == 1 => {
+                Some(Expected::Type {
+                    messages_only: true,
+                })
+            }
+            _ => None,
+        }
+    };
+
+    (expected, scope, start)
+}
+
+// Message the extension extends, as a fully-qualified name.
+fn extendee<'w>(workspace: &'w Workspace, symbol: &Symbol) -> Option<&'w str> {
+    let (_, parent) = symbol.path.split_last()?;
+    let Some(Node::Extend(extend)) = workspace.node_at(symbol.file, parent) else {
+        return None;
+    };
+
+    let scope = workspace.scope_of(symbol.file, parent);
+    workspace
+        .resolve(symbol.file, &scope, extend.r#type, |kind| {
+            kind == SymbolKind::Message
+        })
+        .ok()
+        .map(|symbol| symbol.name.as_str())
+}
+
+// Completion of the text up to the offset, which needn't parse. The file is the one of the
+// document in the workspace if it has ever parsed; every file is visible from it otherwise.
+pub fn completion(workspace: &Workspace, file: Option<usize>, text: &str, offset: usize) -> Value {
+    let (expected, scope, start) = expected(&text[..offset]);
+    let Some(expected) = expected else {
+        return json!([]);
+    };
+
+    let visible = |symbol: &Symbol| {
+        file.is_none_or(|file| workspace.visible_files(file).contains(&symbol.file))
+    };
+    let shortest = |symbol: &Symbol| {
+        names::shortest(&symbol.name, &scope, |name| {
+            workspace.symbol(name).is_some()
+        })
+    };
+
+    // label, kind and detail; the kinds are those of CompletionItemKind
+    let mut items: Vec<(String, u8, String)> = vec![];
+
+    match expected {
+        Expected::Type { messages_only } => {
+            if !messages_only {
+                for scalar in SCALARS {
+                    items.push((scalar.to_string(), 14, String::new()));
+                }
+            }
+
+            for symbol in workspace.symbols().filter(|symbol| visible(symbol)) {
+                match symbol.kind {
+                    SymbolKind::Message => items.push((shortest(symbol), 22, symbol.name.clone())),
+                    SymbolKind::Enum if !messages_only => {
+                        items.push((shortest(symbol), 13, symbol.name.clone()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Expected::Option { kind, custom_only } => {
+            if !custom_only {
+                let pseudo: &[&str] = match kind {
+                    OptionsKind::Field => &["default", "json_name"],
+                    _ => &[],
+                };
+                let standard = kind.standard().iter().map(|option| option.name);
+
+                for name in pseudo.iter().copied().chain(standard) {
+                    items.push((name.to_string(), 10, String::new()));
+                }
+            }
+
+            for symbol in workspace.symbols() {
+                if symbol.kind == SymbolKind::Extension
+                    && visible(symbol)
+                    && extendee(workspace, symbol) == Some(kind.extendee())
+                {
+                    let label = match custom_only {
+                        true => shortest(symbol),
+                        false => format!("({})", shortest(symbol)),
+                    };
+                    items.push((label, 10, symbol.name.clone()));
+                }
+            }
+        }
+    }
+
+    items.sort();
+    items.dedup_by(|a, b| a.0 == b.0);
+
+    let range = protocol::range(text, start..offset);
+    let items: Vec<_> = items
+        .into_iter()
+        .map(|(label, kind, detail)| {
+            let mut item = json!({
+                "label": label,
+                "kind": kind,
+                "textEdit": { "range": range, "newText": label },
+            });
+            if !detail.is_empty() {
+                item["detail"] = json!(detail);
+            }
+            item
+        })
+        .collect();
+
+    Value::Array(items)
+}
+
+// The kinds are those of SymbolKind in the protocol.
+fn outline(source: &str, file: &ast::File, path: Vec<usize>, entry: &Entry) -> Option<Value> {
+    let node = Node::at(file, &path)?;
+
+    let (name, kind, detail) = match node {
+        Node::Message(message) => (message.ident, 23, String::new()),
+        Node::Enum(r#enum) => (r#enum.ident, 10, String::new()),
+        Node::EnumValue(ast::EnumEntry::Pair { ident, value, .. }) => {
+            (*ident, 22, value.to_string())
+        }
+        Node::Service(service) => (service.ident, 11, String::new()),
+        Node::Rpc(rpc) => (rpc.ident, 6, formatter::rpc_signature(rpc)),
+        Node::Field(field) => (field.ident, 8, formatter::field_type(field.r#type)),
+        Node::OneOf(oneof) => (oneof.ident, 8, "oneof".to_string()),
+        Node::Extend(extend) => (extend.r#type, 3, "extend".to_string()),
+        Node::EnumValue(_) => return None,
+    };
+
+    let children: Vec<_> = entry
+        .entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, child)| outline(source, file, [&path[..], &[index]].concat(), child))
+        .collect();
+
+    let selection = span_of(source, name).unwrap_or(entry.range.clone());
+    let mut symbol = json!({
+        "name": name,
+        "kind": kind,
+        "range": protocol::range(source, entry.range.clone()),
+        "selectionRange": protocol::range(source, selection),
+        "children": children,
+    });
+    if !detail.is_empty() {
+        symbol["detail"] = json!(detail);
+    }
+
+    Some(symbol)
+}
+
+pub fn document_symbols(text: &str) -> Value {
+    let Ok(file) = parser::parse(text) else {
+        return Value::Null;
+    };
+    let layout = Layout::new(text, &file);
+
+    layout
+        .entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| outline(text, &file, vec![index], entry))
+        .collect()
+}
+
+// Edits keyed by URI. Open documents must be in the workspace as they are in the editor.
+pub fn rename(
+    snapshot: &Snapshot,
+    workspace: &Workspace,
+    file: usize,
+    offset: usize,
+    new_name: &str,
+    documents: &BTreeMap<String, String>,
+) -> Result<Value, String> {
+    let (symbol, _) = symbol_at(workspace, file, offset)
+        .ok_or_else(|| "There is no symbol to rename here".to_string())?;
+
+    let mut changes = serde_json::Map::new();
+
+    for file_edits in editor::rename_symbol(workspace, &symbol.name, new_name)
+        .map_err(|error| error.to_string())?
+    {
+        let file = workspace.file(file_edits.file);
+        let uri = snapshot
+            .uri(file.name)
+            .ok_or_else(|| format!("{} can't be edited", file.name))?;
+
+        if documents
+            .get(uri)
+            .is_some_and(|text| text.as_str() != file.source)
+        {
+            return Err(format!("{} has syntax errors", file.name));
+        }
+
+        let edits: Vec<_> = file_edits
+            .edits
+            .iter()
+            .map(|edit| {
+                json!({ "range": protocol::range(file.source, edit.range.clone()), "newText": edit.text })
+            })
+            .collect();
+        changes.insert(uri.to_string(), Value::Array(edits));
+    }
+
+    Ok(json!({ "changes": changes }))
+}
+
+pub fn formatting(text: &str) -> Value {
+    let Ok(file) = parser::parse(text) else {
+        return Value::Null;
+    };
+
+    let formatted = formatter::format(&file);
+    if formatted == text {
+        return json!([]);
+    }
+
+    json!([{ "range": protocol::range(text, 0..text.len()), "newText": formatted }])
+}
+
+fn diagnostic(
+    source: &str,
+    range: Range<usize>,
+    severity: u8,
+    message: impl Into<String>,
+    code: Option<&str>,
+) -> Value {
+    let mut diagnostic = json!({
+        "range": protocol::range(source, range),
+        "severity": severity,
+        "source": "protobuf-editor",
+        "message": message.into(),
+    });
+    if let Some(code) = code {
+        diagnostic["code"] = json!(code);
+    }
+
+    diagnostic
+}
+
+// Problems of the document named `name` in the snapshot, whose text is the one in the editor.
+// Only syntax errors are reported while it doesn't parse.
+pub fn diagnostics(
+    snapshot: &Snapshot,
+    workspace: Option<&Workspace>,
+    name: &str,
+    text: &str,
+    config: &lint::Config,
+) -> Vec<Value> {
+    let file = match parser::parse(text) {
+        Ok(file) => file,
+        Err(error) => {
+            return vec![diagnostic(
+                text,
+                parser::error_span(&error),
+                ERROR,
+                parser::error_message(text, &error),
+                None,
+            )];
+        }
+    };
+
+    let mut diagnostics = vec![];
+
+    for (_, missing) in snapshot
+        .missing
+        .iter()
+        .filter(|(importer, _)| importer == name)
+    {
+        let import = file.iter().find_map(|entry| match entry {
+            ast::FileEntry::Import(import) if import.path == missing => span_of(text, import.path),
+            _ => None,
+        });
+
+        diagnostics.push(diagnostic(
+            text,
+            import.unwrap_or_default(),
+            ERROR,
+            format!("Import \"{}\" was not found", missing),
+            None,
+        ));
+    }
+
+    if let Some(workspace) = workspace
+        && let Some(index) = workspace.file_index(name)
+        && workspace.file(index).source == text
+    {
+        // names in the AST are borrowed from the source in the workspace, which equals the text
+        let source = workspace.file(index).source;
+
+        for reference in workspace.references(index) {
+            if let Err(error) = workspace.resolve_reference(&reference) {
+                let range = span_of(source, reference.name).unwrap_or_default();
+                diagnostics.push(diagnostic(text, range, ERROR, error.to_string(), None));
+            }
+        }
+
+        for conflict in workspace.conflicts() {
+            let Some(existing) = workspace.symbol(&conflict.name) else {
+                continue;
+            };
+
+            // reported where both declarations are visible
+            let (here, other) = match (conflict.file == index, existing.file == index) {
+                (true, _) => (conflict, existing),
+                (false, true) => (existing, conflict),
+                (false, false) => continue,
+            };
+            if here.file != other.file && !workspace.visible_files(here.file).contains(&other.file)
+            {
+                continue;
+            }
+
+            let message = match here.file == other.file {
+                true => format!("\"{}\" is already defined", conflict.name),
+                false => format!(
+                    "\"{}\" is already defined in \"{}\"",
+                    conflict.name,
+                    workspace.file(other.file).name
+                ),
+            };
+            let range = declaration(workspace, here).unwrap_or_default();
+            diagnostics.push(diagnostic(text, range, ERROR, message, None));
+        }
+
+        if diagnostics.is_empty()
+            && let Err(error) = resolver::compile(workspace)
+            && error.file == name
+        {
+            diagnostics.push(diagnostic(text, 0..0, ERROR, error.message, None));
+        }
+    }
+
+    for problem in lint::lint(name, text, &file, config) {
+        let severity = match problem.severity {
+            lint::Severity::Error => ERROR,
+            lint::Severity::Warning => WARNING,
+        };
+        diagnostics.push(diagnostic(
+            text,
+            problem.range,
+            severity,
+            problem.message,
+            Some(problem.rule),
+        ));
+    }
+
+    diagnostics
+}
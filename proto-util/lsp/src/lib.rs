@@ -0,0 +1,359 @@
+mod features;
+mod protocol;
+mod server;
+mod snapshot;
+
+pub use protocol::{path_of, uri_of};
+pub use server::{Settings, serve};
+
+#[cfg(test)]
+mod tests {
+    use crate::{Settings, protocol};
+    use serde_json::{Value, json};
+    use std::path::PathBuf;
+
+    fn test_data(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data/lsp")
+            .join(name)
+    }
+
+    fn request(id: i64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn notification(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "method": method, "params": params })
+    }
+
+    // Plays the messages of a client against the server and returns everything it sent back.
+    fn session(messages: Vec<Value>) -> Vec<Value> {
+        let mut initialize = vec![
+            request(
+                0,
+                "initialize",
+                json!({ "rootUri": crate::uri_of(&test_data("")), "capabilities": {} }),
+            ),
+            notification("initialized", json!({})),
+        ];
+        initialize.extend(messages);
+        initialize.push(request(99, "shutdown", Value::Null));
+        initialize.push(notification("exit", Value::Null));
+
+        let mut input = vec![];
+        for message in &initialize {
+            protocol::write_message(&mut input, message).unwrap();
+        }
+
+        let mut output = vec![];
+        crate::serve(Settings::default(), input.as_slice(), &mut output).unwrap();
+
+        let mut output = output.as_slice();
+        let mut replies = vec![];
+        while let Some(content) = protocol::read_message(&mut output).unwrap() {
+            replies.push(serde_json::from_slice(&content).unwrap());
+        }
+
+        replies
+    }
+
+    fn reply(replies: &[Value], id: i64) -> &Value {
+        replies.iter().find(|reply| reply["id"] == id).unwrap()
+    }
+
+    // Position of the nth occurrence of the text in the file, plus some characters.
+    fn position(source: &str, text: &str, nth: usize, plus: usize) -> Value {
+        let offset = source.match_indices(text).nth(nth).unwrap().0;
+        protocol::position(source, offset + plus)
+    }
+
+    fn document(name: &str) -> (String, String) {
+        let path = test_data(name);
+        (crate::uri_of(&path), std::fs::read_to_string(path).unwrap())
+    }
+
+    fn open(uri: &str, text: &str) -> Value {
+        notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": uri, "languageId": "proto", "version": 1, "text": text } }),
+        )
+    }
+
+    fn at(uri: &str, position: Value) -> Value {
+        json!({ "textDocument": { "uri": uri }, "position": position })
+    }
+
+    // Diagnostics of the last publication for the document, as line and message.
+    fn diagnostics(replies: &[Value], uri: &str) -> Vec<(u64, String)> {
+        let published = replies
+            .iter()
+            .rfind(|reply| {
+                reply["method"] == "textDocument/publishDiagnostics"
+                    && reply["params"]["uri"] == uri
+            })
+            .unwrap();
+
+        published["params"]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic["range"]["start"]["line"].as_u64().unwrap(),
+                    diagnostic["message"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn positions() {
+        let source = "a\nпривет 𝄞x\n";
+        let offset = source.find('x').unwrap();
+
+        assert_eq!(
+            protocol::position(source, offset),
+            json!({ "line": 1, "character": 9 })
+        );
+        assert_eq!(
+            protocol::offset(source, &protocol::position(source, offset)),
+            Some(offset)
+        );
+        assert_eq!(
+            protocol::offset(source, &json!({ "line": 0, "character": 10 })),
+            Some(1)
+        );
+
+        let path = PathBuf::from("/tmp/my protos/a.proto");
+        let uri = crate::uri_of(&path);
+        assert_eq!(uri, "file:///tmp/my%20protos/a.proto");
+        assert_eq!(crate::path_of(&uri), Some(path));
+    }
+
+    #[test]
+    fn navigation() {
+        let (api, api_text) = document("acme/v1/api.proto");
+        let (types, types_text) = document("acme/v1/types.proto");
+
+        let replies = session(vec![
+            open(&api, &api_text),
+            request(
+                1,
+                "textDocument/definition",
+                at(&api, position(&api_text, "Order.Item", 0, 7)),
+            ),
+            request(
+                2,
+                "textDocument/definition",
+                at(&api, position(&api_text, "Order.Item", 0, 1)),
+            ),
+            request(
+                3,
+                "textDocument/references",
+                json!({
+                    "textDocument": { "uri": types },
+                    "position": position(&types_text, "Order", 0, 0),
+                    "context": { "includeDeclaration": true },
+                }),
+            ),
+            request(
+                4,
+                "textDocument/hover",
+                at(&api, position(&api_text, "CreateOrder", 0, 0)),
+            ),
+            request(
+                5,
+                "textDocument/hover",
+                at(&types, position(&types_text, "Money", 1, 0)),
+            ),
+            request(
+                6,
+                "textDocument/hover",
+                at(&types, position(&types_text, "currency =", 0, 0)),
+            ),
+        ]);
+
+        assert_eq!(
+            reply(&replies, 0)["result"]["capabilities"]["renameProvider"],
+            true
+        );
+        assert_eq!(diagnostics(&replies, &api), []);
+
+        let item = &reply(&replies, 1)["result"];
+        assert_eq!(item["uri"], types);
+        assert_eq!(item["range"]["start"], position(&types_text, "Item", 0, 0));
+
+        let order = &reply(&replies, 2)["result"];
+        assert_eq!(
+            order["range"]["start"],
+            position(&types_text, "Order", 0, 0)
+        );
+
+        let locations: Vec<_> = reply(&replies, 3)["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|location| {
+                let uri = location["uri"].as_str().unwrap();
+                let line = location["range"]["start"]["line"].as_u64().unwrap();
+                (uri.rsplit('/').next().unwrap(), line)
+            })
+            .collect();
+        assert_eq!(
+            locations,
+            [("types.proto", 11), ("api.proto", 12), ("api.proto", 16)]
+        );
+
+        assert_eq!(
+            reply(&replies, 4)["result"]["contents"]["value"],
+            "```proto\nrpc acme.v1.OrderService.CreateOrder\n\
+             rpc CreateOrder (CreateOrderRequest) returns (CreateOrderResponse)\n```\n\n\
+             Places an order."
+        );
+        assert_eq!(
+            reply(&replies, 5)["result"]["contents"]["value"],
+            "```proto\nmessage acme.v1.Money\n```\n\n\
+             An amount of money.\nIn the smallest unit of the currency."
+        );
+        assert_eq!(
+            reply(&replies, 6)["result"]["contents"]["value"],
+            "```proto\nfield acme.v1.Money.currency\nstring currency = 1;\n```\n\nISO 4217 code."
+        );
+    }
+
+    #[test]
+    fn editing() {
+        let (api, api_text) = document("acme/v1/api.proto");
+        let typing = api_text.replace("  Order order = 1;", "  Order order = 1;\n  repeated It");
+        let option = api_text.replace("  Order order = 1;", "  Order order = 1 [dep");
+        let broken = api_text.replace("Order order = 1;", "Order order = 1");
+        let unresolved = api_text.replace("Order order", "Missing order");
+
+        let replies = session(vec![
+            open(&api, &api_text),
+            request(
+                1,
+                "textDocument/documentSymbol",
+                json!({ "textDocument": { "uri": api } }),
+            ),
+            request(
+                2,
+                "textDocument/rename",
+                json!({
+                    "textDocument": { "uri": api },
+                    "position": position(&api_text, "Item", 0, 0),
+                    "newName": "LineItem",
+                }),
+            ),
+            request(
+                3,
+                "textDocument/formatting",
+                json!({ "textDocument": { "uri": api } }),
+            ),
+            notification(
+                "textDocument/didChange",
+                json!({ "textDocument": { "uri": api }, "contentChanges": [{ "text": typing }] }),
+            ),
+            request(
+                4,
+                "textDocument/completion",
+                at(&api, position(&typing, "repeated It", 0, 11)),
+            ),
+            notification(
+                "textDocument/didChange",
+                json!({ "textDocument": { "uri": api }, "contentChanges": [{ "text": option }] }),
+            ),
+            request(
+                5,
+                "textDocument/completion",
+                at(&api, position(&option, "[dep", 0, 4)),
+            ),
+            notification(
+                "textDocument/didChange",
+                json!({ "textDocument": { "uri": api }, "contentChanges": [{ "text": broken }] }),
+            ),
+            request(
+                6,
+                "textDocument/hover",
+                at(&api, position(&broken, "CreateOrder", 0, 0)),
+            ),
+            notification(
+                "textDocument/didChange",
+                json!({ "textDocument": { "uri": api }, "contentChanges": [{ "text": unresolved }] }),
+            ),
+            request(7, "textDocument/unknown", json!({})),
+        ]);
+
+        let symbols: Vec<_> = reply(&replies, 1)["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|symbol| {
+                let children: Vec<_> = symbol["children"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|child| child["name"].as_str().unwrap())
+                    .collect();
+                (symbol["name"].as_str().unwrap(), children)
+            })
+            .collect();
+        assert_eq!(
+            symbols,
+            [
+                ("OrderService", vec!["CreateOrder"]),
+                ("CreateOrderRequest", vec!["items"]),
+                ("CreateOrderResponse", vec!["order"]),
+            ]
+        );
+
+        let changes = reply(&replies, 2)["result"]["changes"].as_object().unwrap();
+        let mut edited: Vec<_> = changes
+            .iter()
+            .map(|(uri, edits)| {
+                let name = uri.rsplit('/').next().unwrap();
+                (name, edits.as_array().unwrap().len())
+            })
+            .collect();
+        edited.sort();
+        // the declaration and its use in types.proto, the reference in api.proto
+        assert_eq!(edited, [("api.proto", 1), ("types.proto", 2)]);
+        assert_eq!(changes[&api][0]["newText"], "Order.LineItem");
+
+        assert_eq!(reply(&replies, 3)["result"], json!([]));
+
+        let labels = |id| -> Vec<String> {
+            reply(&replies, id)["result"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["label"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let types = labels(4);
+        assert!(types.contains(&"Order.Item".to_string()));
+        assert!(types.contains(&"Status".to_string()));
+        assert!(types.contains(&"int32".to_string()));
+        assert!(!types.contains(&"Order.Item.sku".to_string()));
+        assert!(labels(5).contains(&"deprecated".to_string()));
+
+        assert_eq!(reply(&replies, 6)["result"], Value::Null);
+        assert_eq!(
+            reply(&replies, 7)["error"]["code"],
+            protocol::METHOD_NOT_FOUND
+        );
+        assert_eq!(reply(&replies, 99)["result"], Value::Null);
+
+        let published: Vec<_> = replies
+            .iter()
+            .filter(|reply| reply["method"] == "textDocument/publishDiagnostics")
+            .map(|reply| reply["params"]["diagnostics"].as_array().unwrap().len())
+            .collect();
+        // opened, typing, option, broken, unresolved
+        assert_eq!(published.len(), 5);
+        assert_eq!(
+            diagnostics(&replies, &api),
+            [(16, "\"Missing\" is not defined".to_string())]
+        );
+    }
+}
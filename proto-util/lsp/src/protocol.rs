@@ -0,0 +1,160 @@
+// JSON-RPC messages framed with a Content-Length header, and the conversions between the positions
+// and URIs of the protocol and the offsets and paths used everywhere else.
+
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const SERVER_NOT_INITIALIZED: i64 = -32002;
+pub const REQUEST_FAILED: i64 = -32803;
+
+// Content of the next message, None at the end of the input.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "message without Content-Length")
+    })?;
+
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+
+    Ok(Some(content))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+pub fn response(id: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error(id: &Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+// Positions count lines from 0 and characters in UTF-16 code units.
+pub fn position(source: &str, offset: usize) -> Value {
+    let offset = offset.min(source.len());
+    let start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line = source[..start].matches('\n').count();
+    let character: usize = source[start..offset].chars().map(char::len_utf16).sum();
+
+    json!({ "line": line, "character": character })
+}
+
+pub fn range(source: &str, range: Range<usize>) -> Value {
+    json!({ "start": position(source, range.start), "end": position(source, range.end) })
+}
+
+// Offset of the position, clamped to the end of its line and of the source.
+pub fn offset(source: &str, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+
+    let mut start = 0;
+    for _ in 0..line {
+        match source[start..].find('\n') {
+            Some(index) => start += index + 1,
+            None => return Some(source.len()),
+        }
+    }
+
+    let mut units = 0;
+    for (index, char) in source[start..].char_indices() {
+        if units >= character || char == '\n' {
+            return Some(start + index);
+        }
+        units += char.len_utf16();
+    }
+
+    Some(source.len())
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte)
+}
+
+pub fn uri_of(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = String::from("file://");
+
+    for byte in path.to_string_lossy().replace('\\', "/").bytes() {
+        match is_unreserved(byte) {
+            true => uri.push(byte as char),
+            false => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    uri
+}
+
+// None for URIs of other schemes than file.
+pub fn path_of(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut index = 0;
+
+    while index < encoded.len() {
+        let decoded = match encoded[index] {
+            b'%' => encoded
+                .get(index + 1..index + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+
+        match decoded {
+            Some(byte) => {
+                bytes.push(byte);
+                index += 3;
+            }
+            None => {
+                bytes.push(encoded[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
@@ -0,0 +1,327 @@
+// Lifecycle of the server and the documents open in the editor. The workspace is built again for
+// every request from the documents and the files on disk, so there is nothing to keep in sync.
+
+use crate::features;
+use crate::protocol::{self, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND};
+use crate::snapshot::{self, Snapshot};
+use resolver::Workspace;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    // Searched for imports before the workspace root.
+    pub include_paths: Vec<PathBuf>,
+    pub lint: lint::Config,
+}
+
+#[derive(PartialEq)]
+enum State {
+    Uninitialized,
+    Running,
+    ShutDown,
+}
+
+type Reply = Result<Value, (i64, String)>;
+
+struct Server<W> {
+    settings: Settings,
+    output: W,
+    state: State,
+    root: Option<PathBuf>,
+    // Texts of the open documents by URI, and the last of each that parsed.
+    documents: BTreeMap<String, String>,
+    parsed: BTreeMap<String, String>,
+}
+
+fn invalid_params() -> (i64, String) {
+    (INVALID_PARAMS, "Invalid parameters".to_string())
+}
+
+impl<W: Write> Server<W> {
+    fn send(&mut self, message: Value) -> io::Result<()> {
+        protocol::write_message(&mut self.output, &message)
+    }
+
+    // False once the client asked the server to exit.
+    fn handle(&mut self, message: Value) -> io::Result<bool> {
+        let params = &message["params"];
+
+        match (message["method"].as_str(), message.get("id")) {
+            (Some("exit"), _) => return Ok(false),
+            (Some(method), Some(id)) => {
+                let reply = match self.request(method, params) {
+                    Ok(result) => protocol::response(id, result),
+                    Err((code, text)) => protocol::error(id, code, text),
+                };
+                self.send(reply)?;
+            }
+            (Some(method), None) if self.state == State::Running => {
+                self.notification(method, params)?
+            }
+            // responses, and notifications before initialization or after shutdown
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Reply {
+        match (&self.state, method) {
+            (State::Uninitialized, "initialize") => Ok(self.initialize(params)),
+            (State::Uninitialized, _) => Err((
+                protocol::SERVER_NOT_INITIALIZED,
+                "The server is not initialized".to_string(),
+            )),
+            (State::ShutDown, _) => {
+                Err((INVALID_REQUEST, "The server is shutting down".to_string()))
+            }
+            (State::Running, "initialize") => {
+                Err((INVALID_REQUEST, "The server is initialized".to_string()))
+            }
+            (State::Running, "shutdown") => {
+                self.state = State::ShutDown;
+                Ok(Value::Null)
+            }
+            (State::Running, "textDocument/definition") => {
+                self.at(params, |snapshot, workspace, file, offset| {
+                    Ok(features::definition(snapshot, workspace, file, offset))
+                })
+            }
+            (State::Running, "textDocument/references") => {
+                let include = params["context"]["includeDeclaration"].as_bool() == Some(true);
+
+                self.at(params, |snapshot, workspace, file, offset| {
+                    Ok(features::references(
+                        snapshot, workspace, file, offset, include,
+                    ))
+                })
+            }
+            (State::Running, "textDocument/hover") => self
+                .at(params, |_, workspace, file, offset| {
+                    Ok(features::hover(workspace, file, offset))
+                }),
+            (State::Running, "textDocument/rename") => {
+                let name = params["newName"].as_str().ok_or_else(invalid_params)?;
+
+                self.at(params, |snapshot, workspace, file, offset| {
+                    features::rename(snapshot, workspace, file, offset, name, &self.documents)
+                })
+            }
+            (State::Running, "textDocument/completion") => self.completion(params),
+            (State::Running, "textDocument/documentSymbol") => {
+                Ok(features::document_symbols(&self.text(params)?))
+            }
+            (State::Running, "textDocument/formatting") => {
+                Ok(features::formatting(&self.text(params)?))
+            }
+            (State::Running, _) => Err((METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<()> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.update(uri, text.to_string());
+            }
+            // the whole text is sent on every change
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                let Some(text) = changes.and_then(|changes| changes.last()?["text"].as_str())
+                else {
+                    return Ok(());
+                };
+                self.update(uri, text.to_string());
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                self.parsed.remove(uri);
+                self.send(protocol::notification(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                ))?;
+            }
+            "textDocument/didSave" | "workspace/didChangeWatchedFiles" => {}
+            _ => return Ok(()),
+        }
+
+        self.publish_diagnostics()
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        let root = params["rootUri"]
+            .as_str()
+            .or_else(|| params["workspaceFolders"][0]["uri"].as_str())
+            .and_then(protocol::path_of)
+            .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+
+        self.root = root;
+        self.state = State::Running;
+
+        json!({
+            "capabilities": {
+                "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                "definitionProvider": true,
+                "referencesProvider": true,
+                "hoverProvider": true,
+                "completionProvider": { "triggerCharacters": [".", "(", "["] },
+                "documentSymbolProvider": true,
+                "renameProvider": true,
+                "documentFormattingProvider": true,
+            },
+            "serverInfo": { "name": "protobuf-editor", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+
+    fn update(&mut self, uri: &str, text: String) {
+        if parser::parse(&text).is_ok() {
+            self.parsed.insert(uri.to_string(), text.clone());
+        }
+
+        self.documents.insert(uri.to_string(), text);
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        snapshot::build(
+            &self.settings.include_paths,
+            self.root.as_deref(),
+            &self.parsed,
+        )
+    }
+
+    // Text of the document in the editor, or on disk if it isn't open.
+    fn text(&self, params: &Value) -> Result<String, (i64, String)> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or_else(invalid_params)?;
+
+        match self.documents.get(uri) {
+            Some(text) => Ok(text.clone()),
+            None => protocol::path_of(uri)
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .ok_or_else(|| (protocol::REQUEST_FAILED, format!("{} can't be read", uri))),
+        }
+    }
+
+    // Answers a request about a position in a document, or with null when the document isn't in
+    // the workspace as the editor shows it, i.e. it doesn't parse.
+    fn at(
+        &self,
+        params: &Value,
+        answer: impl FnOnce(&Snapshot, &Workspace, usize, usize) -> Result<Value, String>,
+    ) -> Reply {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or_else(invalid_params)?;
+        let snapshot = self.snapshot();
+        let Ok(workspace) = Workspace::new(&snapshot.tree) else {
+            return Ok(Value::Null);
+        };
+
+        let Some(file) = snapshot
+            .name(uri)
+            .and_then(|name| workspace.file_index(name))
+        else {
+            return Ok(Value::Null);
+        };
+
+        let source = workspace.file(file).source;
+        if self
+            .documents
+            .get(uri)
+            .is_some_and(|text| text.as_str() != source)
+        {
+            return Ok(Value::Null);
+        }
+
+        let offset = protocol::offset(source, &params["position"]).ok_or_else(invalid_params)?;
+        answer(&snapshot, &workspace, file, offset).map_err(|text| (protocol::REQUEST_FAILED, text))
+    }
+
+    // Completion works on the text being edited, which usually doesn't parse.
+    fn completion(&self, params: &Value) -> Reply {
+        let text = self.text(params)?;
+        let offset = protocol::offset(&text, &params["position"]).ok_or_else(invalid_params)?;
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        let snapshot = self.snapshot();
+        let Ok(workspace) = Workspace::new(&snapshot.tree) else {
+            return Ok(json!([]));
+        };
+        let file = snapshot
+            .name(uri)
+            .and_then(|name| workspace.file_index(name));
+
+        Ok(features::completion(&workspace, file, &text, offset))
+    }
+
+    fn publish_diagnostics(&mut self) -> io::Result<()> {
+        let snapshot = self.snapshot();
+        let workspace = Workspace::new(&snapshot.tree).ok();
+
+        let published: Vec<_> = self
+            .documents
+            .iter()
+            .map(|(uri, text)| {
+                let name = snapshot
+                    .name(uri)
+                    .unwrap_or_else(|| uri.rsplit('/').next().unwrap_or(uri));
+                let diagnostics = features::diagnostics(
+                    &snapshot,
+                    workspace.as_ref(),
+                    name,
+                    text,
+                    &self.settings.lint,
+                );
+
+                json!({ "uri": uri, "diagnostics": diagnostics })
+            })
+            .collect();
+
+        for params in published {
+            self.send(protocol::notification(
+                "textDocument/publishDiagnostics",
+                params,
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Serves one client until it exits or the input ends.
+pub fn serve(settings: Settings, mut input: impl BufRead, output: impl Write) -> io::Result<()> {
+    let mut server = Server {
+        settings,
+        output,
+        state: State::Uninitialized,
+        root: None,
+        documents: BTreeMap::new(),
+        parsed: BTreeMap::new(),
+    };
+
+    while let Some(content) = protocol::read_message(&mut input)? {
+        let message = match serde_json::from_slice(&content) {
+            Ok(message) => message,
+            Err(error) => {
+                server.send(protocol::error(
+                    &Value::Null,
+                    protocol::PARSE_ERROR,
+                    error.to_string(),
+                ))?;
+                continue;
+            }
+        };
+
+        if !server.handle(message)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
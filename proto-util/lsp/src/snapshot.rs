@@ -0,0 +1,144 @@
+// Sources a request is answered from: the files under the workspace root as they are on disk, the
+// open documents as they are in the editor, and everything they import.
+
+use crate::protocol;
+use resolver::{LoadErrorKind, SourceTree};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+pub struct Snapshot {
+    pub tree: SourceTree,
+    // URIs of the files that can be opened, by name.
+    pub uris: HashMap<String, String>,
+    // Names of the open documents in the tree, by URI.
+    pub names: HashMap<String, String>,
+    // Imports that couldn't be loaded, with the names of the files importing them.
+    pub missing: Vec<(String, String)>,
+}
+
+impl Snapshot {
+    pub fn uri(&self, name: &str) -> Option<&str> {
+        self.uris.get(name).map(String::as_str)
+    }
+
+    pub fn name(&self, uri: &str) -> Option<&str> {
+        self.names.get(uri).map(String::as_str).or_else(|| {
+            self.uris
+                .iter()
+                .find(|(_, other)| *other == uri)
+                .map(|(name, _)| name.as_str())
+        })
+    }
+}
+
+// .proto files in the directory and its subdirectories, skipping hidden ones.
+fn proto_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            proto_files(&path, files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "proto")
+        {
+            files.push(path);
+        }
+    }
+}
+
+// Loads the imports, replacing those that can't be loaded with empty files so that the workspace
+// can always be built.
+fn load_imports(tree: &mut SourceTree, missing: &mut Vec<(String, String)>) {
+    while let Err(error) = tree.load_imports() {
+        if let LoadErrorKind::NotFound {
+            imported_by: Some(importer),
+        } = error.kind
+        {
+            missing.push((importer, error.file.clone()));
+        }
+
+        tree.add(error.file, "");
+    }
+}
+
+// The open documents come first with their imports, so that what they refer to is defined by the
+// files they see when other files under the root define the same names. Those are only there to
+// be found by references and renames, and the ones that don't parse are left out.
+pub fn build(
+    include_paths: &[PathBuf],
+    root: Option<&Path>,
+    documents: &BTreeMap<String, String>,
+) -> Snapshot {
+    let mut include_paths = include_paths.to_vec();
+    include_paths.extend(root.map(Path::to_path_buf));
+
+    let mut tree = SourceTree::new(include_paths);
+    let mut names = HashMap::new();
+
+    for (uri, text) in documents {
+        let path = protocol::path_of(uri);
+        let name = match path.as_deref() {
+            Some(path) => match tree.name_of(path) {
+                Some(name) => name,
+                None => tree.add_path(path).unwrap_or_else(|_| file_name(uri)),
+            },
+            None => file_name(uri),
+        };
+
+        tree.add(name.clone(), text.clone());
+        names.insert(uri.clone(), name);
+    }
+
+    let mut missing = vec![];
+    load_imports(&mut tree, &mut missing);
+
+    if let Some(root) = root {
+        let mut files = vec![];
+        proto_files(root, &mut files);
+
+        for path in files {
+            let known = tree
+                .name_of(&path)
+                .is_some_and(|name| tree.file(&name).is_some());
+            let parses =
+                std::fs::read_to_string(&path).is_ok_and(|source| parser::parse(&source).is_ok());
+
+            if !known && parses {
+                let _ = tree.add_path(&path);
+            }
+        }
+
+        load_imports(&mut tree, &mut missing);
+    }
+
+    let mut uris: HashMap<String, String> = tree
+        .files()
+        .iter()
+        .filter_map(|file| Some((file.name.clone(), protocol::uri_of(file.path.as_ref()?))))
+        .collect();
+    uris.extend(names.iter().map(|(uri, name)| (name.clone(), uri.clone())));
+
+    Snapshot {
+        tree,
+        uris,
+        names,
+        missing,
+    }
+}
+
+fn file_name(uri: &str) -> String {
+    uri.rsplit('/').next().unwrap_or(uri).to_string()
+}
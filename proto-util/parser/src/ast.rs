@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 pub type File<'a> = Vec<FileEntry<'a>>;
 
 #[derive(Debug, PartialEq)]
@@ -45,12 +43,14 @@ impl From<(i64, ())> for Range {
 pub enum MapValue<'a> {
     Boolean(bool),
     Integer(i64),
+    Float(f64),
     Ident(&'a str),
     String(&'a str),
     Map(JSONLikeMap<'a>),
 }
 
-pub type JSONLikeMap<'a> = HashMap<&'a str, MapValue<'a>>;
+// Entries in the order they are written, keys of repeated fields appear once per value.
+pub type JSONLikeMap<'a> = Vec<(&'a str, MapValue<'a>)>;
 
 #[derive(Debug, PartialEq)]
 pub struct Option<'a> {
@@ -94,7 +94,7 @@ pub enum FileEntry<'a> {
     Comment(Comment<'a>),
     Syntax(&'a str),
     Package(&'a str),
-    Import(&'a str),
+    Import(Import<'a>),
     Option(Option<'a>),
     Service(Service<'a>),
     Message(Message<'a>),
@@ -102,6 +102,28 @@ pub enum FileEntry<'a> {
     Enum(Enum<'a>),
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Import<'a> {
+    pub modifier: ImportModifier,
+    pub path: &'a str,
+}
+
+impl<'a> Import<'a> {
+    pub fn basic(path: &'a str) -> Self {
+        Self {
+            modifier: ImportModifier::None,
+            path,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ImportModifier {
+    None,
+    Public,
+    Weak,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Service<'a> {
     pub ident: &'a str,
@@ -124,6 +146,14 @@ pub struct Rpc<'a> {
     pub reply: &'a str,
 
     pub stream: RpcStream,
+
+    pub entries: Vec<RpcEntry<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RpcEntry<'a> {
+    Comment(Comment<'a>),
+    Option(Option<'a>),
 }
 
 #[derive(Debug, PartialEq)]
@@ -247,4 +277,7 @@ pub enum EnumEntry<'a> {
         value: i64,
         options: Vec<Option<'a>>,
     },
+
+    ReservedIndices(Vec<Range>),
+    ReservedIdents(Vec<&'a str>),
 }
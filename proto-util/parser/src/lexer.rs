@@ -21,6 +21,12 @@ pub struct LexicalError<'a> {
     span: Span,
 }
 
+impl<'a> LexicalError<'a> {
+    pub fn span(&self) -> Span {
+        self.span.clone()
+    }
+}
+
 impl<'a> std::fmt::Display for LexicalError<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let line = self.input[..self.span.start]
@@ -118,6 +124,11 @@ pub enum Token<'a> {
     #[regex(r"0x[0-9a-fA-F]{1,16}", |lex| i64::from_str_radix(&lex.slice()[2..], 16))]
     Integer(i64),
 
+    #[regex(r"-?(?:[0-9]+\.[0-9]*|\.[0-9]+)(?:[eE][+-]?[0-9]+)?", |lex| lex.slice().parse().ok())]
+    #[regex(r"-?[0-9]+[eE][+-]?[0-9]+", |lex| lex.slice().parse().ok())]
+    #[token("-inf", |_| f64::NEG_INFINITY)]
+    Float(f64),
+
     #[token("to")]
     To,
 
@@ -136,6 +147,12 @@ pub enum Token<'a> {
     #[token("import")]
     Import,
 
+    #[token("public")]
+    Public,
+
+    #[token("weak")]
+    Weak,
+
     #[token("service")]
     Service,
 
@@ -9,6 +9,76 @@ lalrpop_mod!(
 pub mod ast;
 pub mod lexer;
 
+pub type Error<'a> = lalrpop_util::ParseError<usize, lexer::Token<'a>, lexer::LexicalError<'a>>;
+
+pub fn parse(input: &str) -> Result<ast::File<'_>, Error<'_>> {
+    proto::FileParser::new().parse(input, lexer::Lexer::new(input))
+}
+
+// 1-based line and column of the byte offset.
+pub fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(input.len());
+    let line = input[..offset].matches('\n').count() + 1;
+    let column = offset - input[..offset].rfind('\n').map_or(0, |index| index + 1) + 1;
+
+    (line, column)
+}
+
+pub fn error_message(input: &str, error: &Error) -> String {
+    let position = |offset: usize| {
+        let (line, column) = line_column(input, offset);
+        format!("line {}, column {}", line, column)
+    };
+
+    let expected = |expected: &[String]| match expected.len() {
+        0 => String::new(),
+        _ => format!(", expected one of {}", expected.join(", ")),
+    };
+
+    match error {
+        lalrpop_util::ParseError::InvalidToken { location } => {
+            format!("Invalid token at {}", position(*location))
+        }
+        lalrpop_util::ParseError::UnrecognizedEof {
+            location,
+            expected: tokens,
+        } => format!(
+            "Unexpected end of file at {}{}",
+            position(*location),
+            expected(tokens)
+        ),
+        lalrpop_util::ParseError::UnrecognizedToken {
+            token: (start, token, _),
+            expected: tokens,
+        } => format!(
+            "Unexpected token {} at {}{}",
+            token,
+            position(*start),
+            expected(tokens)
+        ),
+        lalrpop_util::ParseError::ExtraToken {
+            token: (start, token, _),
+        } => format!("Extra token {} at {}", token, position(*start)),
+        lalrpop_util::ParseError::User { error } => error.to_string(),
+    }
+}
+
+// Byte range the error points to.
+pub fn error_span(error: &Error) -> std::ops::Range<usize> {
+    match error {
+        lalrpop_util::ParseError::InvalidToken { location }
+        | lalrpop_util::ParseError::UnrecognizedEof { location, .. } => *location..*location,
+        lalrpop_util::ParseError::UnrecognizedToken {
+            token: (start, _, end),
+            ..
+        }
+        | lalrpop_util::ParseError::ExtraToken {
+            token: (start, _, end),
+        } => *start..*end,
+        lalrpop_util::ParseError::User { error } => error.span(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ast;
@@ -28,6 +98,19 @@ mod tests {
         }};
     }
 
+    #[test]
+    fn errors() {
+        let input = "syntax = \"proto3\";\nmessage {}";
+        let error = crate::parse(input).unwrap_err();
+
+        assert_eq!(crate::error_span(&error), 27..28);
+        assert!(
+            crate::error_message(input, &error)
+                .starts_with("Unexpected token OpenBrace at line 2, column 9")
+        );
+        assert_eq!(crate::line_column(input, 0), (1, 1));
+    }
+
     #[test]
     fn empty() {
         let ast = parse_ast!("empty.proto");
@@ -69,7 +152,26 @@ mod tests {
         let ast = parse_ast!("import.proto");
         let target_ast = vec![
             ast::FileEntry::Syntax("proto3"),
-            ast::FileEntry::Import("google/protobuf/any.proto"),
+            ast::FileEntry::Import(ast::Import::basic("google/protobuf/any.proto")),
+        ];
+
+        assert_eq!(ast, target_ast);
+    }
+
+    #[test]
+    fn import_modifiers() {
+        let ast = parse_ast!("import-modifiers.proto");
+        let target_ast = vec![
+            ast::FileEntry::Syntax("proto3"),
+            ast::FileEntry::Import(ast::Import::basic("first.proto")),
+            ast::FileEntry::Import(ast::Import {
+                modifier: ast::ImportModifier::Public,
+                path: "second.proto",
+            }),
+            ast::FileEntry::Import(ast::Import {
+                modifier: ast::ImportModifier::Weak,
+                path: "third.proto",
+            }),
         ];
 
         assert_eq!(ast, target_ast);
@@ -137,6 +239,31 @@ mod tests {
         assert_eq!(ast, target_ast);
     }
 
+    #[test]
+    fn message_map() {
+        let ast = parse_ast!("message-map.proto");
+        let target_ast = vec![
+            ast::FileEntry::Syntax("proto3"),
+            ast::FileEntry::Message(ast::Message {
+                ident: "Message",
+                entries: vec![
+                    ast::MessageEntry::Field(ast::Field::basic(
+                        "map<string, .google.protobuf.Any>",
+                        "first",
+                        1,
+                    )),
+                    ast::MessageEntry::Field(ast::Field::basic(
+                        "map<int32, pkg.Inner>",
+                        "second",
+                        2,
+                    )),
+                ],
+            }),
+        ];
+
+        assert_eq!(ast, target_ast);
+    }
+
     #[test]
     fn message_inner() {
         let ast = parse_ast!("message-inner.proto");
@@ -199,12 +326,38 @@ mod tests {
         assert_eq!(ast, target_ast);
     }
 
+    #[test]
+    fn enum_reserved() {
+        let ast = parse_ast!("enum-reserved.proto");
+        let target_ast = vec![
+            ast::FileEntry::Syntax("proto3"),
+            ast::FileEntry::Enum(ast::Enum {
+                ident: "Enum",
+                entries: vec![
+                    ast::EnumEntry::ReservedIndices(vec![
+                        ast::Range::from(-2),
+                        ast::Range::from(2..3),
+                        ast::Range::from((10, ())),
+                    ]),
+                    ast::EnumEntry::ReservedIdents(vec!["OLD"]),
+                    ast::EnumEntry::Pair {
+                        ident: "ZERO",
+                        value: 0,
+                        options: vec![],
+                    },
+                ],
+            }),
+        ];
+
+        assert_eq!(ast, target_ast);
+    }
+
     #[test]
     fn options() {
         let ast = parse_ast!("options.proto");
         let target_ast = vec![
             ast::FileEntry::Syntax("proto3"),
-            ast::FileEntry::Import("google/protobuf/descriptor.proto"),
+            ast::FileEntry::Import(ast::Import::basic("google/protobuf/descriptor.proto")),
             ast::FileEntry::Option(ast::Option {
                 key: "java_multiple_files",
                 value: ast::MapValue::Boolean(true),
@@ -305,12 +458,67 @@ mod tests {
         assert_eq!(ast, target_ast);
     }
 
+    #[test]
+    fn options_float() {
+        let ast = parse_ast!("options-float.proto");
+        let target_ast = vec![
+            ast::FileEntry::Syntax("proto2"),
+            ast::FileEntry::Message(ast::Message {
+                ident: "Message",
+                entries: vec![
+                    ast::MessageEntry::Field(ast::Field {
+                        modifier: ast::FieldModifier::Optional,
+                        r#type: "double",
+                        ident: "first",
+                        index: 1,
+                        options: vec![ast::Option {
+                            key: "default",
+                            value: ast::MapValue::Float(1.5),
+                        }],
+                    }),
+                    ast::MessageEntry::Field(ast::Field {
+                        modifier: ast::FieldModifier::Optional,
+                        r#type: "float",
+                        ident: "second",
+                        index: 2,
+                        options: vec![ast::Option {
+                            key: "default",
+                            value: ast::MapValue::Float(-2e-3),
+                        }],
+                    }),
+                    ast::MessageEntry::Field(ast::Field {
+                        modifier: ast::FieldModifier::Optional,
+                        r#type: "double",
+                        ident: "third",
+                        index: 3,
+                        options: vec![ast::Option {
+                            key: "default",
+                            value: ast::MapValue::Ident("inf"),
+                        }],
+                    }),
+                    ast::MessageEntry::Field(ast::Field {
+                        modifier: ast::FieldModifier::Optional,
+                        r#type: "double",
+                        ident: "fourth",
+                        index: 4,
+                        options: vec![ast::Option {
+                            key: "default",
+                            value: ast::MapValue::Float(f64::NEG_INFINITY),
+                        }],
+                    }),
+                ],
+            }),
+        ];
+
+        assert_eq!(ast, target_ast);
+    }
+
     #[test]
     fn comments() {
         let ast = parse_ast!("comments.proto");
         let target_ast = vec![
             ast::FileEntry::Syntax("proto3"),
-            ast::FileEntry::Import("google/protobuf/descriptor.proto"),
+            ast::FileEntry::Import(ast::Import::basic("google/protobuf/descriptor.proto")),
             ast::FileEntry::Comment(ast::Comment::single_line("// single line comment")),
             ast::FileEntry::Comment(ast::Comment::single_line("// another single line comment")),
             ast::FileEntry::Comment(ast::Comment::multi_line("/* multi\n   line\n   comment */")),
@@ -489,24 +697,28 @@ mod tests {
                         request: "Request",
                         reply: "Reply",
                         stream: ast::RpcStream::None,
+                        entries: vec![],
                     }),
                     ast::ServiceEntry::Rpc(ast::Rpc {
                         ident: "RPC2",
                         request: "Request",
                         reply: "Reply",
                         stream: ast::RpcStream::ServerBound,
+                        entries: vec![],
                     }),
                     ast::ServiceEntry::Rpc(ast::Rpc {
                         ident: "RPC3",
                         request: "Request",
                         reply: "Reply",
                         stream: ast::RpcStream::ClientBound,
+                        entries: vec![],
                     }),
                     ast::ServiceEntry::Rpc(ast::Rpc {
                         ident: "RPC4",
                         request: "Request",
                         reply: "Reply",
                         stream: ast::RpcStream::Bidirectional,
+                        entries: vec![],
                     }),
                 ],
             }),
@@ -516,4 +728,61 @@ mod tests {
 
         assert_eq!(ast, target_ast);
     }
+
+    #[test]
+    fn rpc_options() {
+        let ast = parse_ast!("rpc-options.proto");
+        let target_ast = vec![
+            ast::FileEntry::Syntax("proto3"),
+            ast::FileEntry::Service(ast::Service {
+                ident: "Service",
+                entries: vec![ast::ServiceEntry::Rpc(ast::Rpc {
+                    ident: "Get",
+                    request: "Request",
+                    reply: "Reply",
+                    stream: ast::RpcStream::None,
+                    entries: vec![
+                        ast::RpcEntry::Comment(ast::Comment::single_line("// in rpc")),
+                        ast::RpcEntry::Option(ast::Option {
+                            key: "deprecated",
+                            value: ast::MapValue::Boolean(true),
+                        }),
+                    ],
+                })],
+            }),
+        ];
+
+        assert_eq!(ast, target_ast);
+    }
+
+    #[test]
+    fn options_text_format() {
+        let ast = parse_ast!("options-text-format.proto");
+        let target_ast = vec![
+            ast::FileEntry::Syntax("proto3"),
+            ast::FileEntry::Option(ast::Option {
+                key: "(http)",
+                value: ast::MapValue::Map(ast::JSONLikeMap::from([
+                    ("get", ast::MapValue::String("/v1/books")),
+                    (
+                        "binding",
+                        ast::MapValue::Map(ast::JSONLikeMap::from([
+                            ("post", ast::MapValue::String("/v1/books")),
+                            ("body", ast::MapValue::String("*")),
+                        ])),
+                    ),
+                    // repeated keys are all kept
+                    (
+                        "binding",
+                        ast::MapValue::Map(ast::JSONLikeMap::from([(
+                            "get",
+                            ast::MapValue::String("/v2/books"),
+                        )])),
+                    ),
+                ])),
+            }),
+        ];
+
+        assert_eq!(ast, target_ast);
+    }
 }
@@ -0,0 +1,235 @@
+// Checks run as a protoc plugin: the schemas of the files to generate are decompiled from the
+// descriptors of the request (with their comments when protoc sends source info) for linting and
+// compared with a previous FileDescriptorSet for breaking changes. Formatting is about the source
+// itself, so that check reads the files from the import paths given as parameters.
+
+use descriptor::{Arena, CodeGeneratorRequest, CodeGeneratorResponse, FileDescriptorSet, Message};
+use diff::Level;
+use lint::{Config, Preset, Severity};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterError {
+    pub parameter: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid parameter {}: {}", self.parameter, self.message)
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Checks {
+    pub lint: Option<Config>,
+    pub format: bool,
+    // Where the sources of the files are looked for, "." if empty.
+    pub proto_path: Vec<PathBuf>,
+    // Previous version of the schema.
+    pub breaking: Option<FileDescriptorSet>,
+    // Compatibilities the breaking changes are reported for, all of them if empty.
+    pub levels: Vec<Level>,
+}
+
+impl Checks {
+    // Comma-separated `lint`, `lint_preset=NAME`, `lint_config=FILE`, `format`, `proto_path=DIR`,
+    // `breaking=FILE` and `breaking_level=LEVEL`; just linting without any.
+    pub fn parse(parameter: &str) -> Result<Self, ParameterError> {
+        let mut checks = Self::default();
+
+        for item in parameter.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item.split_once('=').unwrap_or((item, ""));
+            let error = |message: String| ParameterError {
+                parameter: item.to_string(),
+                message,
+            };
+            let required = || match value.is_empty() {
+                true => Err(error("missing value".to_string())),
+                false => Ok(value),
+            };
+
+            match key {
+                "lint" => {
+                    checks.lint.get_or_insert_with(Config::default);
+                }
+                "lint_preset" => {
+                    let preset = Preset::from_name(required()?)
+                        .ok_or_else(|| error("expected default or strict".to_string()))?;
+                    checks.lint = Some(Config::preset(preset));
+                }
+                "lint_config" => {
+                    let text =
+                        std::fs::read_to_string(required()?).map_err(|io| error(io.to_string()))?;
+                    checks.lint =
+                        Some(Config::parse(&text).map_err(|lint| error(lint.to_string()))?);
+                }
+                "format" => checks.format = true,
+                "proto_path" => checks.proto_path.push(PathBuf::from(required()?)),
+                "breaking" => {
+                    let data = std::fs::read(required()?).map_err(|io| error(io.to_string()))?;
+                    let set = FileDescriptorSet::decode(&data)
+                        .map_err(|decode| error(decode.to_string()))?;
+                    checks.breaking = Some(set);
+                }
+                "breaking_level" => {
+                    let level = Level::from_name(required()?)
+                        .ok_or_else(|| error("expected wire, json or source".to_string()))?;
+                    checks.levels.push(level);
+                }
+                _ => return Err(error("unknown parameter".to_string())),
+            }
+        }
+
+        if checks.lint.is_none() && !checks.format && checks.breaking.is_none() {
+            checks.lint = Some(Config::default());
+        }
+
+        Ok(checks)
+    }
+}
+
+// Problems found, one per line; errors fail the build.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl Report {
+    // No files are generated, only the errors are reported.
+    pub fn response(&self) -> CodeGeneratorResponse {
+        CodeGeneratorResponse {
+            error: (!self.errors.is_empty()).then(|| self.errors.join("\n")),
+            supported_features: Some(CodeGeneratorResponse::FEATURE_PROTO3_OPTIONAL),
+            ..CodeGeneratorResponse::default()
+        }
+    }
+}
+
+fn lint(request: &CodeGeneratorRequest, config: &Config, report: &mut Report) {
+    let set = FileDescriptorSet {
+        file: request.proto_file.clone(),
+    };
+    let arena = Arena::new();
+    let decompiled = match descriptor::decompile(&set, &arena) {
+        Ok(decompiled) => decompiled,
+        Err(error) => return report.errors.push(error.to_string()),
+    };
+
+    for file in decompiled {
+        if !request
+            .file_to_generate
+            .iter()
+            .any(|name| name == file.name)
+        {
+            continue;
+        }
+
+        // positions would be those of the decompiled source, so only the element is named
+        let source = formatter::format(&file.ast);
+        let ast = match parser::parse(&source) {
+            Ok(ast) => ast,
+            Err(error) => {
+                let message = parser::error_message(&source, &error);
+                report.errors.push(format!("{}: {}", file.name, message));
+                continue;
+            }
+        };
+
+        for diagnostic in lint::lint(file.name, &source, &ast, config) {
+            let line = format!("{}: {}", file.name, diagnostic);
+            match diagnostic.severity {
+                Severity::Error => report.errors.push(line),
+                Severity::Warning => report.warnings.push(line),
+            }
+        }
+    }
+}
+
+fn format(request: &CodeGeneratorRequest, proto_path: &[PathBuf], report: &mut Report) {
+    let default = [PathBuf::from(".")];
+    let roots = match proto_path.is_empty() {
+        true => &default[..],
+        false => proto_path,
+    };
+
+    for name in &request.file_to_generate {
+        let Some(path) = roots
+            .iter()
+            .map(|root| root.join(name))
+            .find(|path| path.is_file())
+        else {
+            report.errors.push(format!(
+                "{}: source not found, set proto_path to check its format",
+                name
+            ));
+            continue;
+        };
+
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) => {
+                report.errors.push(format!("{}: {}", path.display(), error));
+                continue;
+            }
+        };
+        let formatted = match parser::parse(&source) {
+            Ok(ast) => formatter::format(&ast),
+            Err(error) => {
+                let message = parser::error_message(&source, &error);
+                report.errors.push(format!("{}: {}", name, message));
+                continue;
+            }
+        };
+
+        let lines = source.lines().map(Some).chain(std::iter::repeat(None));
+        let expected = formatted.lines().map(Some).chain(std::iter::repeat(None));
+        let difference = lines
+            .zip(expected)
+            .take_while(|pair| *pair != (None, None))
+            .position(|(line, expected)| line != expected);
+
+        if let Some(index) = difference {
+            report
+                .errors
+                .push(format!("{}:{}: not formatted", name, index + 1));
+        }
+    }
+}
+
+fn breaking(
+    request: &CodeGeneratorRequest,
+    old: &FileDescriptorSet,
+    levels: &[Level],
+    report: &mut Report,
+) {
+    let new = FileDescriptorSet {
+        file: request.proto_file.clone(),
+    };
+
+    for change in diff::descriptor_changes(old, &new) {
+        let generated = request.file_to_generate.contains(&change.file);
+        if generated && (levels.is_empty() || levels.iter().any(|level| change.breaks(*level))) {
+            report.errors.push(change.to_string());
+        }
+    }
+}
+
+pub fn check(request: &CodeGeneratorRequest, checks: &Checks) -> Report {
+    let mut report = Report::default();
+
+    if let Some(config) = &checks.lint {
+        lint(request, config, &mut report);
+    }
+    if checks.format {
+        format(request, &checks.proto_path, &mut report);
+    }
+    if let Some(old) = &checks.breaking {
+        breaking(request, old, &checks.levels, &mut report);
+    }
+
+    report
+}
@@ -0,0 +1,156 @@
+// Running protoc plugins: the request holds the descriptors of every file of the workspace, imports
+// first, and names the files to generate code for; the plugin reads it from its stdin and writes
+// the response to its stdout.
+
+use descriptor::{
+    CodeGeneratorRequest, CodeGeneratorResponse, DescriptorProto, FileDescriptorProto, Message,
+};
+use resolver::{CompileError, Workspace};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use wire::DecodeError;
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Io(std::io::Error),
+    Exit(ExitStatus),
+    Decode(DecodeError),
+    // Reported by the plugin in the response.
+    Failed(String),
+    // A file to generate has proto3 optional fields, which the plugin doesn't declare support for.
+    Proto3Optional(String),
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub program: String,
+    pub kind: ErrorKind,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::Io(error) => write!(f, "{}: {}", self.program, error),
+            ErrorKind::Exit(status) => write!(f, "{}: plugin failed with {}", self.program, status),
+            ErrorKind::Decode(error) => {
+                write!(
+                    f,
+                    "{}: invalid CodeGeneratorResponse: {}",
+                    self.program, error
+                )
+            }
+            ErrorKind::Failed(message) => write!(f, "{}: {}", self.program, message),
+            ErrorKind::Proto3Optional(file) => write!(
+                f,
+                "{}: {} is a proto3 file with optional fields, which the plugin doesn't support",
+                self.program, file
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Request to generate code for the files of the workspace, without a parameter. Like protoc, only
+// the files to generate come with their source info, for the comments and the positions of their
+// declarations.
+pub fn request(
+    workspace: &Workspace,
+    files: &[usize],
+) -> Result<CodeGeneratorRequest, CompileError> {
+    let mut set = resolver::compile(workspace)?;
+    let file_to_generate: Vec<_> = files
+        .iter()
+        .map(|&file| workspace.file(file).name.to_string())
+        .collect();
+
+    for descriptor in &mut set.file {
+        if let Some(file) = files
+            .iter()
+            .map(|&file| workspace.file(file))
+            .find(|file| file.name == descriptor.name())
+        {
+            descriptor.source_code_info = Some(editor::source_code_info(file.source, &file.ast));
+        }
+    }
+
+    let source_file_descriptors = set
+        .file
+        .iter()
+        .filter(|file| file_to_generate.iter().any(|name| name == file.name()))
+        .cloned()
+        .collect();
+
+    Ok(CodeGeneratorRequest {
+        file_to_generate,
+        parameter: None,
+        proto_file: set.file,
+        source_file_descriptors,
+        compiler_version: None,
+    })
+}
+
+fn has_proto3_optional(message: &DescriptorProto) -> bool {
+    message
+        .field
+        .iter()
+        .any(|field| field.proto3_optional == Some(true))
+        || message.nested_type.iter().any(has_proto3_optional)
+}
+
+fn uses_proto3_optional(file: &FileDescriptorProto) -> bool {
+    file.message_type.iter().any(has_proto3_optional)
+}
+
+// Runs the plugin with the request. Its stderr is left to the terminal, for its warnings.
+pub fn run(program: &Path, request: &CodeGeneratorRequest) -> Result<CodeGeneratorResponse, Error> {
+    let error = |kind| Error {
+        program: program.display().to_string(),
+        kind,
+    };
+
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|io| error(ErrorKind::Io(io)))?;
+
+    // written from another thread, so that a plugin answering before it has read everything
+    // can't block both sides
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = request.encode();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+
+    let output = child
+        .wait_with_output()
+        .map_err(|io| error(ErrorKind::Io(io)))?;
+    if !output.status.success() {
+        return Err(error(ErrorKind::Exit(output.status)));
+    }
+    if let Ok(Err(io)) = writer.join() {
+        return Err(error(ErrorKind::Io(io)));
+    }
+
+    let response = CodeGeneratorResponse::decode(&output.stdout)
+        .map_err(|decode| error(ErrorKind::Decode(decode)))?;
+    if let Some(message) = &response.error {
+        return Err(error(ErrorKind::Failed(message.clone())));
+    }
+
+    if !response.supports(CodeGeneratorResponse::FEATURE_PROTO3_OPTIONAL) {
+        let unsupported = request.proto_file.iter().find(|file| {
+            request
+                .file_to_generate
+                .iter()
+                .any(|name| name == file.name())
+                && uses_proto3_optional(file)
+        });
+        if let Some(file) = unsupported {
+            return Err(error(ErrorKind::Proto3Optional(file.name().to_string())));
+        }
+    }
+
+    Ok(response)
+}
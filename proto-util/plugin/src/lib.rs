@@ -0,0 +1,308 @@
+mod check;
+mod host;
+mod output;
+
+pub use check::{Checks, ParameterError, Report, check};
+pub use host::{Error, ErrorKind, request, run};
+pub use output::{Output, OutputError, OutputErrorKind};
+
+#[cfg(test)]
+mod tests {
+    use crate::{Checks, ErrorKind, Output, OutputErrorKind};
+    use descriptor::{CodeGeneratorRequest, CodeGeneratorResponse, CodeGeneratorResponseFile};
+    use diff::Level;
+    use lint::{Config, Preset};
+    use resolver::{SourceTree, Workspace};
+    use std::path::{Path, PathBuf};
+    use wire::Message;
+
+    fn data() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-data/plugin")
+    }
+
+    fn compile(root: &Path, name: &str) -> CodeGeneratorRequest {
+        let tree = SourceTree::load(vec![root.to_path_buf()], &[root.join(name)]).unwrap();
+        let workspace = Workspace::new(&tree).unwrap();
+        let file = workspace.file_index(name).unwrap();
+
+        crate::request(&workspace, &[file]).unwrap()
+    }
+
+    fn request() -> CodeGeneratorRequest {
+        let mut request = compile(&data(), "shop/item.proto");
+        request.parameter = Some("paths=source_relative".to_string());
+        request
+    }
+
+    fn file(name: &str, insertion_point: Option<&str>, content: &str) -> CodeGeneratorResponseFile {
+        CodeGeneratorResponseFile {
+            name: Some(name.to_string()),
+            insertion_point: insertion_point.map(str::to_string),
+            content: Some(content.to_string()),
+        }
+    }
+
+    fn response(files: Vec<CodeGeneratorResponseFile>) -> CodeGeneratorResponse {
+        CodeGeneratorResponse {
+            supported_features: Some(CodeGeneratorResponse::FEATURE_PROTO3_OPTIONAL),
+            file: files,
+            ..CodeGeneratorResponse::default()
+        }
+    }
+
+    // Directory of its own for each test, as they run in parallel.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("plugin-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Plugin recording the request it gets and answering with the response.
+    #[cfg(unix)]
+    fn plugin(dir: &Path, response: &CodeGeneratorResponse) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::write(dir.join("response.bin"), response.encode()).unwrap();
+        let program = dir.join("protoc-gen-fake");
+        std::fs::write(
+            &program,
+            format!(
+                "#!/bin/sh\ncat > '{0}/request.bin'\ncat '{0}/response.bin'\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        program
+    }
+
+    #[test]
+    fn compiled_request() {
+        let request = request();
+        assert_eq!(request.file_to_generate, ["shop/item.proto"]);
+
+        let names: Vec<_> = request.proto_file.iter().map(|file| file.name()).collect();
+        assert_eq!(names, ["shop/price.proto", "shop/item.proto"]);
+
+        assert_eq!(request.source_file_descriptors.len(), 1);
+        assert_eq!(request.source_file_descriptors[0], request.proto_file[1]);
+
+        // only the file to generate has source info, with the indices of the descriptors
+        assert_eq!(request.proto_file[0].source_code_info, None);
+        let info = request.proto_file[1].source_code_info.as_ref().unwrap();
+        let spans: Vec<(&[i32], &[i32])> = info
+            .location
+            .iter()
+            .map(|location| (location.path.as_slice(), location.span.as_slice()))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (&[][..], &[0, 0, 16, 0][..]),
+                (&[12], &[0, 0, 18]),
+                (&[2], &[2, 0, 13]),
+                (&[3, 0], &[4, 0, 26]),
+                (&[4, 0], &[6, 0, 15, 1]),
+                (&[4, 0, 2, 0], &[7, 2, 18]),
+                (&[4, 0, 2, 1], &[8, 2, 18]),
+                (&[4, 0, 3, 0], &[10, 2, 12, 3]),
+                (&[4, 0, 3, 0, 2, 0], &[11, 4, 29]),
+                (&[4, 0, 2, 2], &[14, 2, 18]),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run() {
+        let dir = scratch("run");
+        let request = request();
+        let response = response(vec![
+            file(
+                "shop/item.txt",
+                None,
+                "begin\n  // @@protoc_insertion_point(fields)\nend\n",
+            ),
+            file("", None, "more\n"),
+            file("shop/item.txt", Some("fields"), "name\n\nprice\n"),
+            file("shop/price.txt", None, "price"),
+        ]);
+        let program = plugin(&dir, &response);
+
+        assert_eq!(crate::run(&program, &request).unwrap(), response);
+        let recorded = std::fs::read(dir.join("request.bin")).unwrap();
+        assert_eq!(CodeGeneratorRequest::decode(&recorded).unwrap(), request);
+
+        let mut output = Output::default();
+        output.add(&response).unwrap();
+        output.write(&dir.join("out")).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out/shop/item.txt")).unwrap(),
+            "begin\n  name\n\n  price\n  // @@protoc_insertion_point(fields)\nend\nmore\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out/shop/price.txt")).unwrap(),
+            "price"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failures() {
+        let dir = scratch("failures");
+        let request = request();
+
+        // the optional field is nested in Item
+        let program = plugin(&dir, &CodeGeneratorResponse::default());
+        let error = crate::run(&program, &request).unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::Proto3Optional(file) if file == "shop/item.proto"));
+
+        let failed = CodeGeneratorResponse {
+            error: Some("unknown parameter".to_string()),
+            ..response(vec![])
+        };
+        let program = plugin(&dir, &failed);
+        let error = crate::run(&program, &request).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .ends_with("protoc-gen-fake: unknown parameter")
+        );
+
+        let program = dir.join("protoc-gen-fake");
+        std::fs::write(&program, "#!/bin/sh\nexit 3\n").unwrap();
+        let error = crate::run(&program, &request).unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::Exit(status) if status.code() == Some(3)));
+
+        let error = crate::run(&dir.join("missing"), &request).unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::Io(_)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn output_errors() {
+        let add = |files| Output::default().add(&response(files)).unwrap_err().kind;
+
+        assert_eq!(
+            add(vec![file("../escape.txt", None, "")]),
+            OutputErrorKind::InvalidName
+        );
+        assert_eq!(
+            add(vec![file("/etc/passwd", None, "")]),
+            OutputErrorKind::InvalidName
+        );
+        assert_eq!(add(vec![file("", None, "")]), OutputErrorKind::MissingName);
+        assert_eq!(
+            add(vec![file("a.txt", None, ""), file("a.txt", None, "")]),
+            OutputErrorKind::Duplicate
+        );
+        assert_eq!(
+            add(vec![file("a.txt", Some("top"), "")]),
+            OutputErrorKind::MissingFile("top".to_string())
+        );
+        assert_eq!(
+            add(vec![
+                file("a.txt", None, "// @@protoc_insertion_point(top)\n"),
+                file("a.txt", Some("bottom"), ""),
+            ]),
+            OutputErrorKind::MissingPoint("bottom".to_string())
+        );
+
+        // outputs of several plugins can't overwrite each other either
+        let mut output = Output::default();
+        output
+            .add(&response(vec![file("a.txt", None, "")]))
+            .unwrap();
+        assert_eq!(
+            output
+                .add(&response(vec![file("a.txt", None, "")]))
+                .unwrap_err()
+                .kind,
+            OutputErrorKind::Duplicate
+        );
+    }
+
+    #[test]
+    fn parameters() {
+        let checks = Checks::parse("").unwrap();
+        assert_eq!(checks.lint, Some(Config::default()));
+        assert!(!checks.format);
+
+        let checks = Checks::parse("format,proto_path=protos,breaking_level=wire").unwrap();
+        assert_eq!(checks.lint, None);
+        assert!(checks.format);
+        assert_eq!(checks.proto_path, [PathBuf::from("protos")]);
+        assert_eq!(checks.levels, [Level::Wire]);
+
+        let checks = Checks::parse("lint_preset=strict").unwrap();
+        assert_eq!(checks.lint, Some(Config::preset(Preset::Strict)));
+
+        let error = Checks::parse("lint,lint_preset=loose").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid parameter lint_preset=loose: expected default or strict"
+        );
+        assert!(Checks::parse("proto_path").is_err());
+        assert!(Checks::parse("paths=source_relative").is_err());
+    }
+
+    #[test]
+    fn checks() {
+        let checks = data().join("checks");
+        let order = compile(&checks.join("new"), "shop/order.proto");
+        let old = compile(&checks.join("old"), "shop/order.proto");
+
+        let report = crate::check(
+            &order,
+            &Checks {
+                lint: Some(Config::preset(Preset::Strict)),
+                format: true,
+                proto_path: vec![checks.join("new")],
+                breaking: Some(descriptor::FileDescriptorSet {
+                    file: old.proto_file,
+                }),
+                levels: vec![],
+            },
+        );
+
+        assert_eq!(
+            report.errors,
+            [
+                "shop/order.proto: error: field name TotalCents should be lower_snake_case, \
+                 e.g. total_cents [FIELD_LOWER_SNAKE_CASE]",
+                "shop/order.proto:8: not formatted",
+                "shop/order.proto: field quantity = 2 of shop.Order was removed [WIRE, JSON, SOURCE]",
+            ]
+        );
+        assert!(report.warnings.contains(
+            &"shop/order.proto: warning: field id has no comment [COMMENT_FIELD]".to_string()
+        ));
+        // the comment of the message comes with the source info
+        assert!(
+            !report
+                .warnings
+                .iter()
+                .any(|warning| warning.contains("[COMMENT_MESSAGE]"))
+        );
+
+        let response = report.response();
+        assert_eq!(response.error, Some(report.errors.join("\n")));
+        assert!(response.file.is_empty());
+
+        // the other file is formatted and follows the default rules
+        let report = crate::check(
+            &request(),
+            &Checks {
+                proto_path: vec![data()],
+                ..Checks::parse("lint,format").unwrap()
+            },
+        );
+        assert_eq!(report, crate::Report::default());
+        assert_eq!(report.response().error, None);
+    }
+}
@@ -0,0 +1,149 @@
+// Files generated by plugins into one output directory. Like protoc, a file without a name
+// continues the previous one, and content for an insertion point goes right above the line
+// marking it in a file generated earlier, with the indentation of that line.
+
+use descriptor::CodeGeneratorResponse;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputErrorKind {
+    // Absolute, or going out of the output directory.
+    InvalidName,
+    // First file of the response, without a name.
+    MissingName,
+    Duplicate,
+    // The file for the insertion point wasn't generated.
+    MissingFile(String),
+    MissingPoint(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputError {
+    pub file: String,
+    pub kind: OutputErrorKind,
+}
+
+impl std::fmt::Display for OutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            OutputErrorKind::InvalidName => write!(f, "invalid output file name {:?}", self.file),
+            OutputErrorKind::MissingName => write!(f, "first output file has no name"),
+            OutputErrorKind::Duplicate => write!(f, "{}: generated more than once", self.file),
+            OutputErrorKind::MissingFile(point) => write!(
+                f,
+                "{}: insertion point {} in a file that wasn't generated",
+                self.file, point
+            ),
+            OutputErrorKind::MissingPoint(point) => {
+                write!(f, "{}: no insertion point {}", self.file, point)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Output {
+    pub files: Vec<(String, String)>,
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('/')
+        && !name.contains('\\')
+        && !name
+            .split('/')
+            .any(|segment| segment.is_empty() || segment == "..")
+}
+
+fn insert(content: &str, point: &str, insertion: &str) -> Option<String> {
+    let marker = format!("@@protoc_insertion_point({})", point);
+    let start = content
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .find(|(_, line)| line.contains(&marker))
+        .map(|(start, _)| start)?;
+
+    let line = &content[start..];
+    let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+
+    let mut result = content[..start].to_string();
+    for line in insertion.split_inclusive('\n') {
+        if line != "\n" {
+            result.push_str(indent);
+        }
+        result.push_str(line);
+    }
+    if !insertion.is_empty() && !insertion.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(line);
+    Some(result)
+}
+
+impl Output {
+    pub fn add(&mut self, response: &CodeGeneratorResponse) -> Result<(), OutputError> {
+        let mut last: Option<usize> = None;
+
+        for file in &response.file {
+            let content = file.content.as_deref().unwrap_or_default();
+            let name = file.name.as_deref().unwrap_or_default();
+
+            if name.is_empty() {
+                let index = last.ok_or_else(|| OutputError {
+                    file: String::new(),
+                    kind: OutputErrorKind::MissingName,
+                })?;
+                self.files[index].1.push_str(content);
+                continue;
+            }
+
+            let error = |kind| OutputError {
+                file: name.to_string(),
+                kind,
+            };
+            if !valid_name(name) {
+                return Err(error(OutputErrorKind::InvalidName));
+            }
+            let existing = self.files.iter().position(|(path, _)| path == name);
+
+            match file
+                .insertion_point
+                .as_deref()
+                .filter(|point| !point.is_empty())
+            {
+                Some(point) => {
+                    let index = existing
+                        .ok_or_else(|| error(OutputErrorKind::MissingFile(point.to_string())))?;
+                    self.files[index].1 = insert(&self.files[index].1, point, content)
+                        .ok_or_else(|| error(OutputErrorKind::MissingPoint(point.to_string())))?;
+                    last = Some(index);
+                }
+                None if existing.is_some() => return Err(error(OutputErrorKind::Duplicate)),
+                None => {
+                    self.files.push((name.to_string(), content.to_string()));
+                    last = Some(self.files.len() - 1);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write(&self, dir: &Path) -> std::io::Result<()> {
+        for (name, content) in &self.files {
+            let path = dir.join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+
+        Ok(())
+    }
+}
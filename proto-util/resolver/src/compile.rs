@@ -0,0 +1,831 @@
+// Builds descriptors out of the parsed files, the same ones protoc would produce with
+// --descriptor_set_out (without source info).
+
+use crate::{SymbolKind, TypeRef, Workspace, map_types};
+use descriptor::{
+    DescriptorProto, EnumDescriptorProto, EnumReservedRange, EnumValueDescriptorProto,
+    ExtensionRange, FieldDescriptorProto, FieldInfo, FieldLabel, FieldType, FileDescriptorProto,
+    FileDescriptorSet, MethodDescriptorProto, OneofDescriptorProto, Pool, ReservedRange,
+    ServiceDescriptorProto, names,
+    options::{OptionsKind, StandardOption, StandardOptionType},
+};
+use parser::ast;
+use wire::{Tag, WireType, Writer};
+
+// Exclusive end of `extensions N to max` and `reserved N to max` in messages.
+const MAX_RANGE_END: i32 = wire::MAX_FIELD_NUMBER as i32 + 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileError {
+    pub file: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.file, self.message)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+type Result<T> = std::result::Result<T, CompileError>;
+
+// Compiles every file of the workspace, imports first.
+pub fn compile(workspace: &Workspace) -> Result<FileDescriptorSet> {
+    let order = workspace.dependency_order();
+
+    // Custom options can only be encoded once the types of all extensions are known.
+    let draft = Compiler {
+        workspace,
+        pool: None,
+    }
+    .files(&order)?;
+
+    let pool = Pool::new(&draft);
+
+    Compiler {
+        workspace,
+        pool: Some(&pool),
+    }
+    .files(&order)
+}
+
+struct Compiler<'w, 'a> {
+    workspace: &'w Workspace<'a>,
+    pool: Option<&'w Pool>,
+}
+
+struct Context<'s> {
+    file: usize,
+    scope: &'s str,
+    proto3: bool,
+}
+
+impl Context<'_> {
+    fn nested<'n>(&self, scope: &'n str) -> Context<'n> {
+        Context {
+            file: self.file,
+            scope,
+            proto3: self.proto3,
+        }
+    }
+}
+
+fn options<'r, 'a>(
+    entries: impl IntoIterator<Item = Option<&'r ast::Option<'a>>>,
+) -> Vec<&'r ast::Option<'a>> {
+    entries.into_iter().flatten().collect()
+}
+
+fn range_end(end: &ast::RangeEnd, max: i32) -> i32 {
+    match end {
+        ast::RangeEnd::Integer(end) => *end as i32,
+        ast::RangeEnd::Max => max,
+    }
+}
+
+fn absolute(name: &str) -> String {
+    format!(".{}", name)
+}
+
+impl<'w, 'a> Compiler<'w, 'a> {
+    fn error(&self, context: &Context, message: impl Into<String>) -> CompileError {
+        CompileError {
+            file: self.workspace.file(context.file).name.to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn files(&self, order: &[usize]) -> Result<FileDescriptorSet> {
+        Ok(FileDescriptorSet {
+            file: order
+                .iter()
+                .map(|&index| self.file(index))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn file(&self, index: usize) -> Result<FileDescriptorProto> {
+        let file = self.workspace.file(index);
+        let package = file.package();
+        let context = Context {
+            file: index,
+            scope: package,
+            proto3: file.syntax() == "proto3",
+        };
+
+        let mut result = FileDescriptorProto {
+            name: Some(file.name.to_string()),
+            package: (!package.is_empty()).then(|| package.to_string()),
+            syntax: context.proto3.then(|| "proto3".to_string()),
+            ..Default::default()
+        };
+
+        let mut file_options = vec![];
+
+        for entry in &file.ast {
+            match entry {
+                ast::FileEntry::Import(import) => {
+                    let dependency = result.dependency.len() as i32;
+
+                    match import.modifier {
+                        ast::ImportModifier::Public => result.public_dependency.push(dependency),
+                        ast::ImportModifier::Weak => result.weak_dependency.push(dependency),
+                        ast::ImportModifier::None => {}
+                    }
+
+                    result.dependency.push(import.path.to_string());
+                }
+                ast::FileEntry::Option(option) => file_options.push(option),
+                ast::FileEntry::Message(message) => {
+                    result.message_type.push(self.message(&context, message)?)
+                }
+                ast::FileEntry::Enum(r#enum) => {
+                    result.enum_type.push(self.r#enum(&context, r#enum)?)
+                }
+                ast::FileEntry::Service(service) => {
+                    result.service.push(self.service(&context, service)?)
+                }
+                ast::FileEntry::Extend(extend) => {
+                    result.extension.extend(self.extend(&context, extend)?)
+                }
+                ast::FileEntry::Comment(_)
+                | ast::FileEntry::Syntax(_)
+                | ast::FileEntry::Package(_) => {}
+            }
+        }
+
+        result.options = self.options(&context, OptionsKind::File, &file_options)?;
+        Ok(result)
+    }
+
+    fn message(&self, context: &Context, message: &ast::Message) -> Result<DescriptorProto> {
+        let name = names::join(context.scope, message.ident);
+        let inner = context.nested(&name);
+
+        let mut result = DescriptorProto {
+            name: Some(message.ident.to_string()),
+            ..Default::default()
+        };
+
+        let mut message_options = vec![];
+        let mut optional_fields = vec![];
+
+        for entry in &message.entries {
+            match entry {
+                ast::MessageEntry::Option(option) => message_options.push(option),
+                ast::MessageEntry::Field(field) => {
+                    if let Some((key, value)) = map_types(field.r#type) {
+                        let entry = self.map_entry(&inner, field.ident, key, value)?;
+                        let mut map = self.field(&inner, field, FieldType::Message)?;
+
+                        map.label = Some(FieldLabel::Repeated.as_i32());
+                        map.r#type = Some(FieldType::Message.as_i32());
+                        map.type_name = Some(absolute(&names::join(&name, entry.name())));
+                        result.nested_type.push(entry);
+                        result.field.push(map);
+                    } else {
+                        let field = self.typed_field(&inner, field)?;
+
+                        if field.proto3_optional == Some(true) {
+                            optional_fields.push(result.field.len());
+                        }
+
+                        result.field.push(field);
+                    }
+                }
+                ast::MessageEntry::OneOf(oneof) => {
+                    let index = result.oneof_decl.len() as i32;
+                    let mut oneof_options = vec![];
+
+                    for entry in &oneof.entries {
+                        match entry {
+                            ast::OneOfEntry::Field(field) => {
+                                let mut field = self.typed_field(&inner, field)?;
+                                field.oneof_index = Some(index);
+                                result.field.push(field);
+                            }
+                            ast::OneOfEntry::Option(option) => oneof_options.push(option),
+                            ast::OneOfEntry::Comment(_) => {}
+                        }
+                    }
+
+                    result.oneof_decl.push(OneofDescriptorProto {
+                        name: Some(oneof.ident.to_string()),
+                        options: self.options(&inner, OptionsKind::OneOf, &oneof_options)?,
+                    });
+                }
+                ast::MessageEntry::Message(nested) => {
+                    result.nested_type.push(self.message(&inner, nested)?)
+                }
+                ast::MessageEntry::Enum(r#enum) => {
+                    result.enum_type.push(self.r#enum(&inner, r#enum)?)
+                }
+                ast::MessageEntry::Extend(extend) => {
+                    result.extension.extend(self.extend(&inner, extend)?)
+                }
+                ast::MessageEntry::ReservedIndices(ranges) => {
+                    result
+                        .reserved_range
+                        .extend(ranges.iter().map(|range| ReservedRange {
+                            start: Some(range.start as i32),
+                            end: Some(range_end(&range.end, MAX_RANGE_END)),
+                        }))
+                }
+                ast::MessageEntry::ReservedIdents(idents) => result
+                    .reserved_name
+                    .extend(idents.iter().map(|ident| ident.to_string())),
+                ast::MessageEntry::Extensions(ranges) => {
+                    result
+                        .extension_range
+                        .extend(ranges.iter().map(|range| ExtensionRange {
+                            start: Some(range.start as i32),
+                            end: Some(range_end(&range.end, MAX_RANGE_END)),
+                            options: None,
+                        }))
+                }
+                ast::MessageEntry::Comment(_) => {}
+            }
+        }
+
+        // Synthetic oneofs of proto3 optional fields come after all the real ones.
+        for index in optional_fields {
+            let mut oneof = format!("_{}", result.field[index].name());
+
+            while result
+                .oneof_decl
+                .iter()
+                .any(|other| other.name.as_ref() == Some(&oneof))
+                || message_has_name(message, &oneof)
+            {
+                oneof.insert(0, 'X');
+            }
+
+            result.field[index].oneof_index = Some(result.oneof_decl.len() as i32);
+            result.oneof_decl.push(OneofDescriptorProto {
+                name: Some(oneof),
+                options: None,
+            });
+        }
+
+        result.options = self.options(context, OptionsKind::Message, &message_options)?;
+        Ok(result)
+    }
+
+    fn map_entry(
+        &self,
+        context: &Context,
+        field: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<DescriptorProto> {
+        let entry_field = |name: &str, number: i32, r#type: &str| -> Result<FieldDescriptorProto> {
+            let mut result = FieldDescriptorProto {
+                name: Some(name.to_string()),
+                number: Some(number),
+                label: Some(FieldLabel::Optional.as_i32()),
+                json_name: Some(name.to_string()),
+                ..Default::default()
+            };
+
+            self.set_type(context, &mut result, r#type)?;
+            Ok(result)
+        };
+
+        let mut options = Writer::new();
+        options.write_tag(Tag::new(7, WireType::Varint));
+        options.write_varint(1);
+
+        Ok(DescriptorProto {
+            name: Some(names::map_entry_name(field)),
+            field: vec![entry_field("key", 1, key)?, entry_field("value", 2, value)?],
+            options: Some(options.into_inner()),
+            ..Default::default()
+        })
+    }
+
+    fn set_type(
+        &self,
+        context: &Context,
+        field: &mut FieldDescriptorProto,
+        r#type: &str,
+    ) -> Result<FieldType> {
+        let resolved = self
+            .workspace
+            .resolve_type(context.file, context.scope, r#type)
+            .map_err(|error| self.error(context, format!("{}: {}", field.name(), error)))?;
+
+        let r#type = match resolved {
+            TypeRef::Scalar(r#type) => r#type,
+            TypeRef::Message(symbol) => {
+                field.type_name = Some(absolute(&symbol.name));
+                FieldType::Message
+            }
+            TypeRef::Enum(symbol) => {
+                field.type_name = Some(absolute(&symbol.name));
+                FieldType::Enum
+            }
+        };
+
+        field.r#type = Some(r#type.as_i32());
+        Ok(r#type)
+    }
+
+    fn typed_field(&self, context: &Context, field: &ast::Field) -> Result<FieldDescriptorProto> {
+        let mut result = FieldDescriptorProto {
+            name: Some(field.ident.to_string()),
+            ..Default::default()
+        };
+
+        let r#type = self.set_type(context, &mut result, field.r#type)?;
+        let mut compiled = self.field(context, field, r#type)?;
+
+        compiled.r#type = result.r#type;
+        compiled.type_name = result.type_name;
+        Ok(compiled)
+    }
+
+    // Everything but the type of the field.
+    fn field(
+        &self,
+        context: &Context,
+        field: &ast::Field,
+        r#type: FieldType,
+    ) -> Result<FieldDescriptorProto> {
+        let label = match field.modifier {
+            ast::FieldModifier::Repeated => FieldLabel::Repeated,
+            ast::FieldModifier::Required => FieldLabel::Required,
+            ast::FieldModifier::None | ast::FieldModifier::Optional => FieldLabel::Optional,
+        };
+
+        let mut result = FieldDescriptorProto {
+            name: Some(field.ident.to_string()),
+            number: Some(field.index as i32),
+            label: Some(label.as_i32()),
+            json_name: Some(names::json_name(field.ident)),
+            proto3_optional: (context.proto3 && field.modifier == ast::FieldModifier::Optional)
+                .then_some(true),
+            ..Default::default()
+        };
+
+        let mut field_options = vec![];
+
+        for option in &field.options {
+            match (option.key, &option.value) {
+                ("json_name", ast::MapValue::String(json_name)) => {
+                    result.json_name = Some(self.string(context, json_name)?)
+                }
+                ("json_name", _) => {
+                    return Err(self.error(context, "json_name must be a string"));
+                }
+                ("default", value) => {
+                    result.default_value = Some(self.default_value(context, r#type, value)?)
+                }
+                _ => field_options.push(option),
+            }
+        }
+
+        result.options = self.options(context, OptionsKind::Field, &field_options)?;
+        Ok(result)
+    }
+
+    fn extend(&self, context: &Context, extend: &ast::Extend) -> Result<Vec<FieldDescriptorProto>> {
+        let extendee = self
+            .workspace
+            .resolve(context.file, context.scope, extend.r#type, |kind| {
+                kind == SymbolKind::Message
+            })
+            .map_err(|error| self.error(context, format!("extend {}: {}", extend.r#type, error)))?;
+
+        extend
+            .entries
+            .iter()
+            .filter_map(|entry| match entry {
+                ast::ExtendEntry::Field(field) => Some(field),
+                ast::ExtendEntry::Comment(_) => None,
+            })
+            .map(|field| {
+                let mut field = self.typed_field(context, field)?;
+                field.extendee = Some(absolute(&extendee.name));
+                // Extensions never have synthetic oneofs.
+                field.proto3_optional = None;
+                Ok(field)
+            })
+            .collect()
+    }
+
+    fn r#enum(&self, context: &Context, r#enum: &ast::Enum) -> Result<EnumDescriptorProto> {
+        let mut result = EnumDescriptorProto {
+            name: Some(r#enum.ident.to_string()),
+            ..Default::default()
+        };
+
+        let mut enum_options = vec![];
+
+        for entry in &r#enum.entries {
+            match entry {
+                ast::EnumEntry::Pair {
+                    ident,
+                    value,
+                    options,
+                } => result.value.push(EnumValueDescriptorProto {
+                    name: Some(ident.to_string()),
+                    number: Some(*value as i32),
+                    options: self.options(
+                        context,
+                        OptionsKind::EnumValue,
+                        &options.iter().collect::<Vec<_>>(),
+                    )?,
+                }),
+                ast::EnumEntry::Option(option) => enum_options.push(option),
+                ast::EnumEntry::ReservedIndices(ranges) => {
+                    result
+                        .reserved_range
+                        .extend(ranges.iter().map(|range| EnumReservedRange {
+                            start: Some(range.start as i32),
+                            end: Some(match range.end {
+                                ast::RangeEnd::Integer(end) => end as i32 - 1,
+                                ast::RangeEnd::Max => i32::MAX,
+                            }),
+                        }))
+                }
+                ast::EnumEntry::ReservedIdents(idents) => result
+                    .reserved_name
+                    .extend(idents.iter().map(|ident| ident.to_string())),
+                ast::EnumEntry::Comment(_) => {}
+            }
+        }
+
+        result.options = self.options(context, OptionsKind::Enum, &enum_options)?;
+        Ok(result)
+    }
+
+    fn service(&self, context: &Context, service: &ast::Service) -> Result<ServiceDescriptorProto> {
+        let name = names::join(context.scope, service.ident);
+        let inner = context.nested(&name);
+
+        let mut result = ServiceDescriptorProto {
+            name: Some(service.ident.to_string()),
+            ..Default::default()
+        };
+
+        let mut service_options = vec![];
+
+        for entry in &service.entries {
+            match entry {
+                ast::ServiceEntry::Rpc(rpc) => result.method.push(self.rpc(&inner, rpc)?),
+                ast::ServiceEntry::Option(option) => service_options.push(option),
+                ast::ServiceEntry::Comment(_) => {}
+            }
+        }
+
+        result.options = self.options(context, OptionsKind::Service, &service_options)?;
+        Ok(result)
+    }
+
+    fn rpc(&self, context: &Context, rpc: &ast::Rpc) -> Result<MethodDescriptorProto> {
+        let message = |reference: &str| {
+            self.workspace
+                .resolve(context.file, context.scope, reference, |kind| {
+                    kind == SymbolKind::Message
+                })
+                .map(|symbol| absolute(&symbol.name))
+                .map_err(|error| self.error(context, format!("rpc {}: {}", rpc.ident, error)))
+        };
+
+        let (client_streaming, server_streaming) = match rpc.stream {
+            ast::RpcStream::None => (false, false),
+            ast::RpcStream::ServerBound => (true, false),
+            ast::RpcStream::ClientBound => (false, true),
+            ast::RpcStream::Bidirectional => (true, true),
+        };
+
+        let rpc_options = options(rpc.entries.iter().map(|entry| match entry {
+            ast::RpcEntry::Option(option) => Some(option),
+            ast::RpcEntry::Comment(_) => None,
+        }));
+
+        Ok(MethodDescriptorProto {
+            name: Some(rpc.ident.to_string()),
+            input_type: Some(message(rpc.request)?),
+            output_type: Some(message(rpc.reply)?),
+            options: self.options(context, OptionsKind::Method, &rpc_options)?,
+            client_streaming: client_streaming.then_some(true),
+            server_streaming: server_streaming.then_some(true),
+        })
+    }
+
+    fn string(&self, context: &Context, value: &str) -> Result<String> {
+        self.bytes(context, value).and_then(|bytes| {
+            String::from_utf8(bytes).map_err(|_| self.error(context, "string is not valid UTF-8"))
+        })
+    }
+
+    fn bytes(&self, context: &Context, value: &str) -> Result<Vec<u8>> {
+        descriptor::unescape(value).map_err(|error| self.error(context, error.to_string()))
+    }
+
+    // Default values are stored as text: numbers as written, enums by name, bytes escaped.
+    fn default_value(
+        &self,
+        context: &Context,
+        r#type: FieldType,
+        value: &ast::MapValue,
+    ) -> Result<String> {
+        use FieldType::*;
+
+        Ok(match (r#type, value) {
+            (String, ast::MapValue::String(value)) => self.string(context, value)?,
+            (Bytes, ast::MapValue::String(value)) => {
+                descriptor::escape(&self.bytes(context, value)?)
+            }
+            (Bool, ast::MapValue::Boolean(value)) => value.to_string(),
+            (Float | Double, ast::MapValue::Float(value)) => value.to_string(),
+            (Float | Double, ast::MapValue::Ident(value @ ("inf" | "nan"))) => value.to_string(),
+            (Enum, ast::MapValue::Ident(value)) => value.to_string(),
+            (
+                Double | Float | Int64 | Uint64 | Int32 | Fixed64 | Fixed32 | Uint32 | Sfixed32
+                | Sfixed64 | Sint32 | Sint64,
+                ast::MapValue::Integer(value),
+            ) => value.to_string(),
+            (r#type, _) => {
+                return Err(self.error(
+                    context,
+                    format!(
+                        "invalid default value for a field of type {}",
+                        r#type.name()
+                    ),
+                ));
+            }
+        })
+    }
+
+    fn options(
+        &self,
+        context: &Context,
+        kind: OptionsKind,
+        options: &[&ast::Option],
+    ) -> Result<Option<Vec<u8>>> {
+        let mut writer = Writer::new();
+
+        for option in options {
+            self.option(context, kind, option, &mut writer)?;
+        }
+
+        Ok((!writer.is_empty()).then(|| writer.into_inner()))
+    }
+
+    fn option(
+        &self,
+        context: &Context,
+        kind: OptionsKind,
+        option: &ast::Option,
+        writer: &mut Writer,
+    ) -> Result<()> {
+        let key = option.key;
+        let error = |message: &str| self.error(context, format!("option {}: {}", key, message));
+
+        if let Some(extension) = key.strip_prefix('(').and_then(|key| key.strip_suffix(')')) {
+            // Custom options need types of the extensions, available after the first pass.
+            let Some(pool) = self.pool else {
+                return Ok(());
+            };
+
+            let symbol = self
+                .workspace
+                .resolve(context.file, context.scope, extension, |kind| {
+                    kind == SymbolKind::Extension
+                })
+                .map_err(|resolve| error(&resolve.to_string()))?;
+
+            let field = pool
+                .extension(&symbol.name)
+                .ok_or_else(|| error("unknown extension"))?;
+
+            if field.extendee.as_deref() != Some(kind.extendee()) {
+                return Err(error(&format!("extension must extend {}", kind.extendee())));
+            }
+
+            return OptionEncoder {
+                pool,
+                file: self.workspace.file(context.file).name,
+                key,
+            }
+            .field(field, &[], &option.value, writer);
+        }
+
+        let mut components = key.split('.');
+        let name = components.next().unwrap_or_default();
+        let rest: Vec<&str> = components.collect();
+
+        // Fields of the options message in descriptor.proto, when it is part of the workspace.
+        if let Some(pool) = self.pool
+            && let Some(field) = pool
+                .message(kind.extendee())
+                .and_then(|message| message.field_by_name(name))
+        {
+            return OptionEncoder {
+                pool,
+                file: self.workspace.file(context.file).name,
+                key,
+            }
+            .field(field, &rest, &option.value, writer);
+        }
+
+        let standard = kind
+            .by_name(name)
+            .filter(|_| rest.is_empty())
+            .ok_or_else(|| error("unknown option"))?;
+
+        encode_standard(standard, &option.value, writer).ok_or_else(|| error("invalid value"))
+    }
+}
+
+fn message_has_name(message: &ast::Message, name: &str) -> bool {
+    message.entries.iter().any(|entry| match entry {
+        ast::MessageEntry::Field(field) => field.ident == name,
+        ast::MessageEntry::OneOf(oneof) => oneof.ident == name,
+        ast::MessageEntry::Message(message) => message.ident == name,
+        ast::MessageEntry::Enum(r#enum) => r#enum.ident == name,
+        _ => false,
+    })
+}
+
+fn encode_standard(
+    option: &StandardOption,
+    value: &ast::MapValue,
+    writer: &mut Writer,
+) -> Option<()> {
+    match (option.r#type, value) {
+        (StandardOptionType::Bool, ast::MapValue::Boolean(value)) => {
+            writer.write_tag(Tag::new(option.number, WireType::Varint));
+            writer.write_varint(*value as u64);
+        }
+        (StandardOptionType::String, ast::MapValue::String(value)) => {
+            writer.write_tag(Tag::new(option.number, WireType::LengthDelimited));
+            writer.write_length_delimited(&descriptor::unescape(value).ok()?);
+        }
+        (r#type @ StandardOptionType::Enum(_), ast::MapValue::Ident(name)) => {
+            writer.write_tag(Tag::new(option.number, WireType::Varint));
+            writer.write_varint(r#type.enum_value(name)? as u64);
+        }
+        _ => return None,
+    }
+
+    Some(())
+}
+
+// Encodes option values according to the field types known to the pool.
+struct OptionEncoder<'p> {
+    pool: &'p Pool,
+    file: &'p str,
+    key: &'p str,
+}
+
+impl OptionEncoder<'_> {
+    fn error(&self, message: impl std::fmt::Display) -> CompileError {
+        CompileError {
+            file: self.file.to_string(),
+            message: format!("option {}: {}", self.key, message),
+        }
+    }
+
+    // `path` selects fields of a message-typed option, e.g. `features.field_presence`.
+    fn field(
+        &self,
+        field: &FieldInfo,
+        path: &[&str],
+        value: &ast::MapValue,
+        writer: &mut Writer,
+    ) -> Result<()> {
+        let Some((first, rest)) = path.split_first() else {
+            return self.value(field, value, writer);
+        };
+
+        let inner = self.message_field(field, first)?;
+
+        writer.write_tag(Tag::new(field.number, WireType::LengthDelimited));
+
+        let mut nested = Writer::new();
+        self.field(inner, rest, value, &mut nested)?;
+        writer.write_length_delimited(&nested.into_inner());
+
+        Ok(())
+    }
+
+    fn message_field(&self, field: &FieldInfo, name: &str) -> Result<&FieldInfo> {
+        if !matches!(field.r#type, FieldType::Message | FieldType::Group) {
+            return Err(self.error(format!("{} is not a message", field.name)));
+        }
+
+        let message = field
+            .type_name
+            .as_deref()
+            .and_then(|name| self.pool.message(name))
+            .ok_or_else(|| self.error(format!("unknown type of {}", field.name)))?;
+
+        message
+            .field_by_name(name)
+            .ok_or_else(|| self.error(format!("{} has no field {}", message.name, name)))
+    }
+
+    fn value(&self, field: &FieldInfo, value: &ast::MapValue, writer: &mut Writer) -> Result<()> {
+        use FieldType::*;
+
+        let invalid = || {
+            self.error(format!(
+                "invalid value for {} of type {}",
+                field.name,
+                field.r#type.name()
+            ))
+        };
+
+        let integer = |min: i64, max: i64| match value {
+            ast::MapValue::Integer(value) if (min..=max).contains(value) => Ok(*value),
+            _ => Err(invalid()),
+        };
+
+        let float = || match value {
+            ast::MapValue::Float(value) => Ok(*value),
+            ast::MapValue::Integer(value) => Ok(*value as f64),
+            ast::MapValue::Ident("inf") => Ok(f64::INFINITY),
+            ast::MapValue::Ident("nan") => Ok(f64::NAN),
+            _ => Err(invalid()),
+        };
+
+        let wire_type = match field.r#type {
+            Double | Fixed64 | Sfixed64 => WireType::Fixed64,
+            Float | Fixed32 | Sfixed32 => WireType::Fixed32,
+            String | Bytes | Message | Group => WireType::LengthDelimited,
+            _ => WireType::Varint,
+        };
+
+        writer.write_tag(Tag::new(field.number, wire_type));
+
+        match field.r#type {
+            Double => writer.write_fixed64(float()?.to_bits()),
+            Float => writer.write_fixed32((float()? as f32).to_bits()),
+            Int32 => writer.write_varint(integer(i32::MIN as i64, i32::MAX as i64)? as u64),
+            Int64 => writer.write_varint(integer(i64::MIN, i64::MAX)? as u64),
+            Uint32 => writer.write_varint(integer(0, u32::MAX as i64)? as u64),
+            Uint64 => writer.write_varint(integer(0, i64::MAX)? as u64),
+            Sint32 => writer
+                .write_varint(wire::encode_zigzag32(
+                    integer(i32::MIN as i64, i32::MAX as i64)? as i32
+                ) as u64),
+            Sint64 => writer.write_varint(wire::encode_zigzag64(integer(i64::MIN, i64::MAX)?)),
+            Fixed32 => writer.write_fixed32(integer(0, u32::MAX as i64)? as u32),
+            Sfixed32 => writer.write_fixed32(integer(i32::MIN as i64, i32::MAX as i64)? as u32),
+            Fixed64 => writer.write_fixed64(integer(0, i64::MAX)? as u64),
+            Sfixed64 => writer.write_fixed64(integer(i64::MIN, i64::MAX)? as u64),
+            Bool => match value {
+                ast::MapValue::Boolean(value) => writer.write_varint(*value as u64),
+                _ => return Err(invalid()),
+            },
+            String | Bytes => match value {
+                ast::MapValue::String(value) => writer.write_length_delimited(
+                    &descriptor::unescape(value).map_err(|error| self.error(error))?,
+                ),
+                _ => return Err(invalid()),
+            },
+            Enum => {
+                let number = match value {
+                    ast::MapValue::Ident(name) => field
+                        .type_name
+                        .as_deref()
+                        .and_then(|name| self.pool.r#enum(name))
+                        .and_then(|r#enum| r#enum.value_by_name(name))
+                        .map(|value| value.number)
+                        .ok_or_else(invalid)?,
+                    _ => integer(i32::MIN as i64, i32::MAX as i64)? as i32,
+                };
+
+                writer.write_varint(number as i64 as u64);
+            }
+            Message | Group => {
+                let ast::MapValue::Map(map) = value else {
+                    return Err(invalid());
+                };
+
+                let mut fields = map
+                    .iter()
+                    .map(|(name, value)| Ok((self.message_field(field, name)?, value)))
+                    .collect::<Result<Vec<_>>>()?;
+
+                // in the order of the numbers, values of repeated fields in the order written
+                fields.sort_by_key(|(field, _)| field.number);
+
+                let mut nested = Writer::new();
+
+                for (field, value) in fields {
+                    self.value(field, value, &mut nested)?;
+                }
+
+                writer.write_length_delimited(&nested.into_inner());
+            }
+        }
+
+        Ok(())
+    }
+}
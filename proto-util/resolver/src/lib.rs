@@ -0,0 +1,218 @@
+mod compile;
+mod references;
+mod source;
+mod well_known;
+mod workspace;
+
+pub use compile::{CompileError, compile};
+pub use references::{Reference, ReferenceKind};
+pub use source::{LoadError, LoadErrorKind, SourceFile, SourceTree};
+pub use workspace::{
+    File, Node, ResolveError, Symbol, SymbolKind, TypeRef, Workspace, WorkspaceError, map_types,
+};
+
+#[cfg(test)]
+mod tests {
+    use crate::{ResolveError, SourceTree, SymbolKind, Workspace};
+    use descriptor::{Arena, FieldLabel, FieldType, Message, Pool};
+    use std::path::PathBuf;
+
+    fn test_data(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data/resolver")
+            .join(name)
+    }
+
+    fn load() -> SourceTree {
+        SourceTree::load(
+            vec![test_data("")],
+            &[test_data("order.proto"), test_data("hidden.proto")],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn load_imports() {
+        let tree = load();
+        let names: Vec<_> = tree.files().iter().map(|file| file.name.as_str()).collect();
+
+        assert_eq!(
+            names,
+            [
+                "order.proto",
+                "hidden.proto",
+                "common.proto",
+                "google/protobuf/timestamp.proto",
+                "base.proto"
+            ]
+        );
+        assert_eq!(
+            tree.file("google/protobuf/timestamp.proto").unwrap().path,
+            None
+        );
+
+        let mut tree = SourceTree::new(vec![]);
+        tree.add("broken.proto", "import \"missing.proto\";");
+        assert_eq!(
+            tree.load_imports().unwrap_err().to_string(),
+            "broken.proto: import \"missing.proto\" was not found"
+        );
+    }
+
+    #[test]
+    fn resolve() {
+        let tree = load();
+        let workspace = Workspace::new(&tree).unwrap();
+        let order = workspace.file_index("order.proto").unwrap();
+        let is_type = |kind: SymbolKind| kind.is_type();
+
+        let resolve = |scope: &str, reference: &str| {
+            workspace
+                .resolve(order, scope, reference, is_type)
+                .map(|symbol| symbol.name.as_str())
+        };
+
+        assert_eq!(resolve("acme.app.Order", "base.Id"), Ok("acme.base.Id"));
+        assert_eq!(resolve("acme.app.Order", "Item"), Ok("acme.app.Order.Item"));
+        assert_eq!(resolve("acme.app", "Order.Item"), Ok("acme.app.Order.Item"));
+        // visible through `import public` in common.proto
+        assert_eq!(resolve("acme.app", ".acme.base.Kind"), Ok("acme.base.Kind"));
+        // "base" is found in the Shadow scope first, and it has no "Id"
+        assert_eq!(
+            resolve("acme.app.Shadow", "base.Id"),
+            Err(ResolveError::NotFound("base.Id".to_string()))
+        );
+        assert_eq!(
+            resolve("acme.app", "acme.hidden.Secret"),
+            Err(ResolveError::NotImported {
+                name: "acme.hidden.Secret".to_string(),
+                file: "hidden.proto".to_string()
+            })
+        );
+        assert_eq!(
+            resolve("acme.app", "Order.id"),
+            Err(ResolveError::WrongKind {
+                name: "acme.app.Order.id".to_string(),
+                kind: SymbolKind::Field
+            })
+        );
+
+        let symbol = workspace.symbol("acme.app.Order.Item.count").unwrap();
+        assert_eq!(symbol.kind, SymbolKind::Field);
+        assert_eq!(symbol.path, [4, 4, 1]);
+        assert!(
+            matches!(workspace.node(symbol), Some(crate::Node::Field(field)) if field.index == 2)
+        );
+        assert_eq!(
+            workspace.scope_of(order, &symbol.path),
+            "acme.app.Order.Item"
+        );
+    }
+
+    #[test]
+    fn references() {
+        let tree = load();
+        let workspace = Workspace::new(&tree).unwrap();
+        let order = workspace.file_index("order.proto").unwrap();
+
+        let references = workspace.references(order);
+        let resolved: Vec<_> = references
+            .iter()
+            .map(|reference| {
+                let symbol = workspace.resolve_reference(reference).unwrap();
+                (
+                    reference.name,
+                    symbol.name.as_str(),
+                    reference.scope.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            resolved,
+            [
+                ("base.Id", "acme.base.Id", "acme.app.Order"),
+                ("Item", "acme.app.Order.Item", "acme.app.Order"),
+                ("acme.base.Meta", "acme.base.Meta", "acme.app.Order"),
+                (
+                    "google.protobuf.Timestamp",
+                    "google.protobuf.Timestamp",
+                    "acme.app.Order"
+                ),
+                (".acme.base.Kind", "acme.base.Kind", "acme.app.Order.Item"),
+            ]
+        );
+        assert_eq!(references[4].path, [4, 4, 0]);
+    }
+
+    #[test]
+    fn compile() {
+        let tree = load();
+        let workspace = Workspace::new(&tree).unwrap();
+        let set = crate::compile(&workspace).unwrap();
+
+        let names: Vec<_> = set.file.iter().map(|file| file.name()).collect();
+        assert_eq!(
+            names,
+            [
+                "base.proto",
+                "common.proto",
+                "google/protobuf/timestamp.proto",
+                "order.proto",
+                "hidden.proto"
+            ]
+        );
+
+        let pool = Pool::new(&set);
+        let order = pool.message("acme.app.Order").unwrap();
+        assert_eq!(
+            order.field(1).unwrap().type_name.as_deref(),
+            Some("acme.base.Id")
+        );
+        assert_eq!(order.field(2).unwrap().label, FieldLabel::Repeated);
+
+        let item = pool.message("acme.app.Order.Item").unwrap();
+        assert_eq!(item.field(1).unwrap().r#type, FieldType::Enum);
+        assert_eq!(item.field(2).unwrap().oneof, Some(0));
+        assert_eq!(item.oneofs[0].name, "_count");
+        assert!(item.oneofs[0].synthetic);
+
+        let meta = pool.message("acme.base.Meta").unwrap();
+        let labels = meta.field(1).unwrap();
+        assert_eq!(
+            labels.type_name.as_deref(),
+            Some("acme.base.Meta.LabelsEntry")
+        );
+        assert!(pool.map_entry(labels).is_some());
+    }
+
+    fn compile_source(source: &str) -> descriptor::FileDescriptorProto {
+        let mut tree = SourceTree::new(vec![]);
+        tree.add("user.proto", source);
+        tree.load_imports().unwrap();
+
+        let workspace = Workspace::new(&tree).unwrap();
+        let set = crate::compile(&workspace).unwrap();
+
+        set.file
+            .into_iter()
+            .find(|file| file.name() == "user.proto")
+            .unwrap()
+    }
+
+    // Decompiling the descriptors gives back a schema compiling to the same descriptors.
+    #[test]
+    fn decompile_compiled() {
+        let file = compile_source(include_str!("../../../test-data/descriptor/user.proto"));
+
+        let set = descriptor::FileDescriptorSet {
+            file: vec![file.clone()],
+        };
+        let set = descriptor::FileDescriptorSet::decode(&set.encode()).unwrap();
+
+        let arena = Arena::new();
+        let files = descriptor::decompile(&set, &arena).unwrap();
+
+        assert_eq!(compile_source(&formatter::format(&files[0].ast)), file);
+    }
+}
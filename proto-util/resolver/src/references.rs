@@ -0,0 +1,228 @@
+// Names written in the files that refer to other symbols, with the scopes they are resolved from
+// as the compiler does it.
+
+use crate::workspace::{ResolveError, Symbol, SymbolKind, Workspace, map_types};
+use descriptor::{FieldType, names};
+use parser::ast;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceKind {
+    // Type of a field, a message or an enum.
+    Type,
+    // Type of an rpc request or reply, or the message an extend extends.
+    Message,
+    // Custom option, e.g. `(my.option)`.
+    Extension,
+    // Enum value given as the default of a field.
+    DefaultValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference<'a> {
+    // The name as written, borrowed from the source.
+    pub name: &'a str,
+    pub kind: ReferenceKind,
+    pub file: usize,
+    // Path of the declaration making the reference, empty for options of the file.
+    pub path: Vec<usize>,
+    pub scope: String,
+}
+
+struct Collector<'a> {
+    file: usize,
+    references: Vec<Reference<'a>>,
+}
+
+impl<'a> Collector<'a> {
+    fn push(&mut self, name: &'a str, kind: ReferenceKind, path: &[usize], scope: &str) {
+        self.references.push(Reference {
+            name,
+            kind,
+            file: self.file,
+            path: path.to_vec(),
+            scope: scope.to_string(),
+        });
+    }
+
+    fn options<'o>(
+        &mut self,
+        options: impl IntoIterator<Item = &'o ast::Option<'a>>,
+        path: &[usize],
+        scope: &str,
+    ) where
+        'a: 'o,
+    {
+        for option in options {
+            if let Some(name) = option
+                .key
+                .strip_prefix('(')
+                .and_then(|key| key.strip_suffix(')'))
+            {
+                self.push(name.trim(), ReferenceKind::Extension, path, scope);
+            }
+        }
+    }
+
+    fn field(&mut self, field: &ast::Field<'a>, path: &[usize], scope: &str) {
+        match map_types(field.r#type) {
+            Some((key, value)) => {
+                for name in [key, value] {
+                    if FieldType::from_scalar_name(name).is_none() {
+                        self.push(name, ReferenceKind::Type, path, scope);
+                    }
+                }
+            }
+            None if FieldType::from_scalar_name(field.r#type).is_none() => {
+                self.push(field.r#type, ReferenceKind::Type, path, scope);
+
+                for option in &field.options {
+                    if let ("default", ast::MapValue::Ident(value)) = (option.key, &option.value) {
+                        self.push(value, ReferenceKind::DefaultValue, path, scope);
+                    }
+                }
+            }
+            None => {}
+        }
+
+        self.options(&field.options, path, scope);
+    }
+
+    fn message(&mut self, message: &ast::Message<'a>, path: &[usize], scope: &str) {
+        let name = names::join(scope, message.ident);
+
+        for (index, entry) in message.entries.iter().enumerate() {
+            let child = [path, &[index]].concat();
+
+            match entry {
+                ast::MessageEntry::Option(option) => self.options([option], path, scope),
+                ast::MessageEntry::Field(field) => self.field(field, &child, &name),
+                ast::MessageEntry::OneOf(oneof) => {
+                    for (index, entry) in oneof.entries.iter().enumerate() {
+                        match entry {
+                            ast::OneOfEntry::Field(field) => {
+                                self.field(field, &[&child[..], &[index]].concat(), &name)
+                            }
+                            ast::OneOfEntry::Option(option) => {
+                                self.options([option], &child, &name)
+                            }
+                            ast::OneOfEntry::Comment(_) => {}
+                        }
+                    }
+                }
+                ast::MessageEntry::Message(message) => self.message(message, &child, &name),
+                ast::MessageEntry::Enum(r#enum) => self.r#enum(r#enum, &child, &name),
+                ast::MessageEntry::Extend(extend) => self.extend(extend, &child, &name),
+                _ => {}
+            }
+        }
+    }
+
+    fn r#enum(&mut self, r#enum: &ast::Enum<'a>, path: &[usize], scope: &str) {
+        for (index, entry) in r#enum.entries.iter().enumerate() {
+            match entry {
+                ast::EnumEntry::Option(option) => self.options([option], path, scope),
+                ast::EnumEntry::Pair { options, .. } => {
+                    self.options(options, &[path, &[index]].concat(), scope)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn extend(&mut self, extend: &ast::Extend<'a>, path: &[usize], scope: &str) {
+        self.push(extend.r#type, ReferenceKind::Message, path, scope);
+
+        for (index, entry) in extend.entries.iter().enumerate() {
+            if let ast::ExtendEntry::Field(field) = entry {
+                self.field(field, &[path, &[index]].concat(), scope);
+            }
+        }
+    }
+
+    fn service(&mut self, service: &ast::Service<'a>, path: &[usize], scope: &str) {
+        let name = names::join(scope, service.ident);
+
+        for (index, entry) in service.entries.iter().enumerate() {
+            match entry {
+                ast::ServiceEntry::Option(option) => self.options([option], path, scope),
+                ast::ServiceEntry::Rpc(rpc) => {
+                    let path = [path, &[index]].concat();
+
+                    self.push(rpc.request, ReferenceKind::Message, &path, &name);
+                    self.push(rpc.reply, ReferenceKind::Message, &path, &name);
+
+                    for entry in &rpc.entries {
+                        if let ast::RpcEntry::Option(option) = entry {
+                            self.options([option], &path, &name);
+                        }
+                    }
+                }
+                ast::ServiceEntry::Comment(_) => {}
+            }
+        }
+    }
+}
+
+impl<'a> Workspace<'a> {
+    // References made by the file, in the order they are written.
+    pub fn references(&self, file: usize) -> Vec<Reference<'a>> {
+        let package = self.file(file).package();
+        let mut collector = Collector {
+            file,
+            references: vec![],
+        };
+
+        for (index, entry) in self.file(file).ast.iter().enumerate() {
+            let path = [index];
+
+            match entry {
+                ast::FileEntry::Option(option) => collector.options([option], &[], package),
+                ast::FileEntry::Message(message) => collector.message(message, &path, package),
+                ast::FileEntry::Enum(r#enum) => collector.r#enum(r#enum, &path, package),
+                ast::FileEntry::Extend(extend) => collector.extend(extend, &path, package),
+                ast::FileEntry::Service(service) => collector.service(service, &path, package),
+                _ => {}
+            }
+        }
+
+        collector.references
+    }
+
+    pub fn resolve_reference(&self, reference: &Reference) -> Result<&Symbol, ResolveError> {
+        self.resolve_name(reference, reference.name)
+    }
+
+    // Resolves the name as if it was written in place of the reference.
+    pub fn resolve_name(&self, reference: &Reference, name: &str) -> Result<&Symbol, ResolveError> {
+        let (file, scope) = (reference.file, reference.scope.as_str());
+
+        match reference.kind {
+            ReferenceKind::Type => self.resolve(file, scope, name, |kind| kind.is_type()),
+            ReferenceKind::Message => {
+                self.resolve(file, scope, name, |kind| kind == SymbolKind::Message)
+            }
+            ReferenceKind::Extension => {
+                self.resolve(file, scope, name, |kind| kind == SymbolKind::Extension)
+            }
+            // values are looked up among the ones of the enum the field is typed with
+            ReferenceKind::DefaultValue => {
+                let not_found = || ResolveError::NotFound(name.to_string());
+
+                let Some(crate::Node::Field(field)) = self.node_at(file, &reference.path) else {
+                    return Err(not_found());
+                };
+
+                let r#enum =
+                    self.resolve(file, scope, field.r#type, |kind| kind == SymbolKind::Enum)?;
+
+                self.symbol(&names::join(r#enum.scope(), name))
+                    .filter(|value| {
+                        value.kind == SymbolKind::EnumValue
+                            && value.file == r#enum.file
+                            && value.path.starts_with(&r#enum.path)
+                    })
+                    .ok_or_else(not_found)
+            }
+        }
+    }
+}
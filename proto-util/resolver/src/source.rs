@@ -0,0 +1,234 @@
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    // Name used in import statements, e.g. "google/protobuf/any.proto".
+    pub name: String,
+    // None for in-memory and bundled files.
+    pub path: Option<PathBuf>,
+    pub source: String,
+}
+
+#[derive(Debug)]
+pub enum LoadErrorKind {
+    Io(std::io::Error),
+    NotFound { imported_by: Option<String> },
+    Parse(String),
+}
+
+#[derive(Debug)]
+pub struct LoadError {
+    pub file: String,
+    pub kind: LoadErrorKind,
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            LoadErrorKind::Io(error) => write!(f, "{}: {}", self.file, error),
+            LoadErrorKind::NotFound {
+                imported_by: Some(importer),
+            } => write!(f, "{}: import \"{}\" was not found", importer, self.file),
+            LoadErrorKind::NotFound { imported_by: None } => {
+                write!(f, "{}: file was not found", self.file)
+            }
+            LoadErrorKind::Parse(message) => write!(f, "{}: {}", self.file, message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+// Set of .proto sources together with everything they import.
+#[derive(Debug, Default, Clone)]
+pub struct SourceTree {
+    include_paths: Vec<PathBuf>,
+    files: Vec<SourceFile>,
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl SourceTree {
+    pub fn new(include_paths: Vec<PathBuf>) -> Self {
+        Self {
+            include_paths,
+            files: vec![],
+        }
+    }
+
+    // Loads the files from disk along with their transitive imports.
+    pub fn load(include_paths: Vec<PathBuf>, files: &[PathBuf]) -> Result<Self, LoadError> {
+        let mut tree = Self::new(include_paths);
+
+        for file in files {
+            tree.add_path(file)?;
+        }
+
+        tree.load_imports()?;
+        Ok(tree)
+    }
+
+    pub fn include_paths(&self) -> &[PathBuf] {
+        &self.include_paths
+    }
+
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    pub fn file(&self, name: &str) -> Option<&SourceFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+
+    pub fn file_by_path(&self, path: &Path) -> Option<&SourceFile> {
+        let path = normalize(path);
+
+        self.files
+            .iter()
+            .find(|file| file.path.as_deref().map(normalize).as_ref() == Some(&path))
+    }
+
+    // Adds or replaces an in-memory file.
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> usize {
+        self.insert(SourceFile {
+            name: name.into(),
+            path: None,
+            source: source.into(),
+        })
+    }
+
+    fn insert(&mut self, file: SourceFile) -> usize {
+        match self.files.iter().position(|other| other.name == file.name) {
+            Some(index) => {
+                self.files[index] = file;
+                index
+            }
+            None => {
+                self.files.push(file);
+                self.files.len() - 1
+            }
+        }
+    }
+
+    // Import name of a file on disk, relative to the first include path containing it.
+    pub fn name_of(&self, path: &Path) -> Option<String> {
+        let path = normalize(path);
+
+        self.include_paths.iter().find_map(|include| {
+            let relative = path.strip_prefix(normalize(include)).ok()?;
+            let mut components = vec![];
+
+            for component in relative.components() {
+                match component {
+                    Component::Normal(component) => components.push(component.to_str()?),
+                    _ => return None,
+                }
+            }
+
+            Some(components.join("/"))
+        })
+    }
+
+    // Adds a file from disk. When it is outside of every include path, its directory becomes one.
+    pub fn add_path(&mut self, path: &Path) -> Result<String, LoadError> {
+        let name = match self.name_of(path) {
+            Some(name) => name,
+            None => {
+                let parent = path
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+
+                self.include_paths.push(parent.to_path_buf());
+
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default()
+                    .to_string()
+            }
+        };
+
+        let source = std::fs::read_to_string(path).map_err(|error| LoadError {
+            file: path.display().to_string(),
+            kind: LoadErrorKind::Io(error),
+        })?;
+
+        self.insert(SourceFile {
+            name: name.clone(),
+            path: Some(path.to_path_buf()),
+            source,
+        });
+
+        Ok(name)
+    }
+
+    // Loads every file imported by the tree, from the include paths or the bundled well-known types.
+    pub fn load_imports(&mut self) -> Result<(), LoadError> {
+        let mut index = 0;
+
+        while index < self.files.len() {
+            let file = &self.files[index];
+            let ast = parser::parse(&file.source).map_err(|error| LoadError {
+                file: file.name.clone(),
+                kind: LoadErrorKind::Parse(parser::error_message(&file.source, &error)),
+            })?;
+
+            let importer = file.name.clone();
+            let imports: Vec<String> = ast
+                .iter()
+                .filter_map(|entry| match entry {
+                    parser::ast::FileEntry::Import(import) => Some(import.path.to_string()),
+                    _ => None,
+                })
+                .collect();
+
+            for import in imports {
+                if self.file(&import).is_none() {
+                    let file = self.find(&import).ok_or_else(|| LoadError {
+                        file: import.clone(),
+                        kind: LoadErrorKind::NotFound {
+                            imported_by: Some(importer.clone()),
+                        },
+                    })??;
+
+                    self.files.push(file);
+                }
+            }
+
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Option<Result<SourceFile, LoadError>> {
+        for include in &self.include_paths {
+            let path = include.join(name);
+
+            if path.is_file() {
+                return Some(
+                    std::fs::read_to_string(&path)
+                        .map(|source| SourceFile {
+                            name: name.to_string(),
+                            path: Some(path.clone()),
+                            source,
+                        })
+                        .map_err(|error| LoadError {
+                            file: name.to_string(),
+                            kind: LoadErrorKind::Io(error),
+                        }),
+                );
+            }
+        }
+
+        crate::well_known::get(name).map(|source| {
+            Ok(SourceFile {
+                name: name.to_string(),
+                path: None,
+                source: source.to_string(),
+            })
+        })
+    }
+}
@@ -0,0 +1,647 @@
+use crate::SourceTree;
+use descriptor::{FieldType, names};
+use parser::ast;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, PartialEq)]
+pub struct File<'a> {
+    pub name: &'a str,
+    pub source: &'a str,
+    pub ast: ast::File<'a>,
+}
+
+impl<'a> File<'a> {
+    pub fn package(&self) -> &'a str {
+        self.ast
+            .iter()
+            .find_map(|entry| match entry {
+                ast::FileEntry::Package(package) => Some(*package),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn syntax(&self) -> &'a str {
+        self.ast
+            .iter()
+            .find_map(|entry| match entry {
+                ast::FileEntry::Syntax(syntax) => Some(*syntax),
+                _ => None,
+            })
+            .unwrap_or("proto2")
+    }
+
+    pub fn imports(&self) -> impl Iterator<Item = &ast::Import<'a>> {
+        self.ast.iter().filter_map(|entry| match entry {
+            ast::FileEntry::Import(import) => Some(import),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Package,
+    Message,
+    Enum,
+    EnumValue,
+    Service,
+    Rpc,
+    Field,
+    OneOf,
+    Extension,
+}
+
+impl SymbolKind {
+    pub fn is_type(&self) -> bool {
+        matches!(self, Self::Message | Self::Enum)
+    }
+
+    // Kinds that may contain other symbols.
+    pub fn is_aggregate(&self) -> bool {
+        matches!(
+            self,
+            Self::Package | Self::Message | Self::Enum | Self::Service
+        )
+    }
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Package => "package",
+            Self::Message => "message",
+            Self::Enum => "enum",
+            Self::EnumValue => "enum value",
+            Self::Service => "service",
+            Self::Rpc => "rpc",
+            Self::Field => "field",
+            Self::OneOf => "oneof",
+            Self::Extension => "extension",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    // Fully-qualified, without the leading dot.
+    pub name: String,
+    pub kind: SymbolKind,
+    pub file: usize,
+    // Indices of the entries leading to the declaration, starting from the file entries.
+    // Fields inside of oneofs and extends include the index of the oneof or extend.
+    pub path: Vec<usize>,
+}
+
+impl Symbol {
+    pub fn ident(&self) -> &str {
+        self.name.rsplit('.').next().unwrap_or(&self.name)
+    }
+
+    pub fn scope(&self) -> &str {
+        names::parent(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'r, 'a> {
+    Message(&'r ast::Message<'a>),
+    Enum(&'r ast::Enum<'a>),
+    EnumValue(&'r ast::EnumEntry<'a>),
+    Service(&'r ast::Service<'a>),
+    Rpc(&'r ast::Rpc<'a>),
+    Field(&'r ast::Field<'a>),
+    OneOf(&'r ast::OneOf<'a>),
+    Extend(&'r ast::Extend<'a>),
+}
+
+impl<'r, 'a> Node<'r, 'a> {
+    // Node at the entry-index path in the file.
+    pub fn at(file: &'r ast::File<'a>, path: &[usize]) -> Option<Self> {
+        let (first, rest) = path.split_first()?;
+
+        let mut node = match file.get(*first)? {
+            ast::FileEntry::Message(message) => Node::Message(message),
+            ast::FileEntry::Enum(r#enum) => Node::Enum(r#enum),
+            ast::FileEntry::Service(service) => Node::Service(service),
+            ast::FileEntry::Extend(extend) => Node::Extend(extend),
+            _ => return None,
+        };
+
+        for index in rest {
+            node = node.child(*index)?;
+        }
+
+        Some(node)
+    }
+
+    fn child(&self, index: usize) -> Option<Node<'r, 'a>> {
+        match self {
+            Self::Message(message) => match message.entries.get(index)? {
+                ast::MessageEntry::Message(message) => Some(Node::Message(message)),
+                ast::MessageEntry::Enum(r#enum) => Some(Node::Enum(r#enum)),
+                ast::MessageEntry::Field(field) => Some(Node::Field(field)),
+                ast::MessageEntry::OneOf(oneof) => Some(Node::OneOf(oneof)),
+                ast::MessageEntry::Extend(extend) => Some(Node::Extend(extend)),
+                _ => None,
+            },
+            Self::Enum(r#enum) => match r#enum.entries.get(index)? {
+                entry @ ast::EnumEntry::Pair { .. } => Some(Node::EnumValue(entry)),
+                _ => None,
+            },
+            Self::Service(service) => match service.entries.get(index)? {
+                ast::ServiceEntry::Rpc(rpc) => Some(Node::Rpc(rpc)),
+                _ => None,
+            },
+            Self::OneOf(oneof) => match oneof.entries.get(index)? {
+                ast::OneOfEntry::Field(field) => Some(Node::Field(field)),
+                _ => None,
+            },
+            Self::Extend(extend) => match extend.entries.get(index)? {
+                ast::ExtendEntry::Field(field) => Some(Node::Field(field)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    NotFound(String),
+    NotImported { name: String, file: String },
+    WrongKind { name: String, kind: SymbolKind },
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "\"{}\" is not defined", name),
+            Self::NotImported { name, file } => write!(
+                f,
+                "\"{}\" seems to be defined in \"{}\", which is not imported",
+                name, file
+            ),
+            Self::WrongKind { name, kind } => write!(f, "\"{}\" is a {}", name, kind),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeRef<'w> {
+    Scalar(FieldType),
+    Message(&'w Symbol),
+    Enum(&'w Symbol),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceError {
+    pub file: String,
+    pub message: String,
+    pub span: std::ops::Range<usize>,
+}
+
+impl std::fmt::Display for WorkspaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.file, self.message)
+    }
+}
+
+impl std::error::Error for WorkspaceError {}
+
+// Key and value types of a `map<K, V>` field type.
+pub fn map_types(r#type: &str) -> Option<(&str, &str)> {
+    let inner = r#type
+        .strip_prefix("map")?
+        .trim_start()
+        .strip_prefix('<')?
+        .strip_suffix('>')?;
+
+    let (key, value) = inner.split_once(',')?;
+    Some((key.trim(), value.trim()))
+}
+
+// Parsed files with a symbol table over all of them.
+#[derive(Debug)]
+pub struct Workspace<'a> {
+    files: Vec<File<'a>>,
+    by_name: HashMap<&'a str, usize>,
+    symbols: HashMap<String, Symbol>,
+    // Later declarations of an already defined name.
+    conflicts: Vec<Symbol>,
+    visible: Vec<HashSet<usize>>,
+}
+
+impl<'a> Workspace<'a> {
+    pub fn new(tree: &'a SourceTree) -> Result<Self, WorkspaceError> {
+        let files = tree
+            .files()
+            .iter()
+            .map(|file| {
+                parser::parse(&file.source)
+                    .map(|ast| File {
+                        name: &file.name,
+                        source: &file.source,
+                        ast,
+                    })
+                    .map_err(|error| WorkspaceError {
+                        file: file.name.clone(),
+                        message: parser::error_message(&file.source, &error),
+                        span: parser::error_span(&error),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::from_files(files))
+    }
+
+    pub fn from_files(files: Vec<File<'a>>) -> Self {
+        let mut workspace = Self {
+            by_name: files
+                .iter()
+                .enumerate()
+                .map(|(index, file)| (file.name, index))
+                .collect(),
+            files,
+            symbols: HashMap::new(),
+            conflicts: vec![],
+            visible: vec![],
+        };
+
+        for index in 0..workspace.files.len() {
+            workspace.register_file(index);
+        }
+
+        workspace.visible = (0..workspace.files.len())
+            .map(|index| workspace.compute_visible(index))
+            .collect();
+
+        workspace
+    }
+
+    pub fn files(&self) -> &[File<'a>] {
+        &self.files
+    }
+
+    pub fn file(&self, index: usize) -> &File<'a> {
+        &self.files[index]
+    }
+
+    pub fn file_index(&self, name: &str) -> Option<usize> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.values()
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name.strip_prefix('.').unwrap_or(name))
+    }
+
+    pub fn conflicts(&self) -> &[Symbol] {
+        &self.conflicts
+    }
+
+    // Files whose symbols can be referenced from the file: itself, its imports and everything
+    // they publicly import.
+    pub fn visible_files(&self, file: usize) -> &HashSet<usize> {
+        &self.visible[file]
+    }
+
+    // Files in an order where every file comes after its imports.
+    pub fn dependency_order(&self) -> Vec<usize> {
+        fn visit(
+            workspace: &Workspace,
+            index: usize,
+            done: &mut Vec<bool>,
+            order: &mut Vec<usize>,
+        ) {
+            if done[index] {
+                return;
+            }
+
+            done[index] = true;
+
+            for import in workspace.files[index].imports() {
+                if let Some(import) = workspace.file_index(import.path) {
+                    visit(workspace, import, done, order);
+                }
+            }
+
+            order.push(index);
+        }
+
+        let mut done = vec![false; self.files.len()];
+        let mut order = vec![];
+
+        for index in 0..self.files.len() {
+            visit(self, index, &mut done, &mut order);
+        }
+
+        order
+    }
+
+    fn compute_visible(&self, file: usize) -> HashSet<usize> {
+        let mut visible = HashSet::from([file]);
+        let mut pending: Vec<(usize, bool)> = vec![(file, true)];
+
+        while let Some((index, direct)) = pending.pop() {
+            for import in self.files[index].imports() {
+                if !direct && import.modifier != ast::ImportModifier::Public {
+                    continue;
+                }
+
+                if let Some(import) = self.file_index(import.path)
+                    && visible.insert(import)
+                {
+                    pending.push((import, false));
+                }
+            }
+        }
+
+        visible
+    }
+
+    fn define(&mut self, name: String, kind: SymbolKind, file: usize, path: Vec<usize>) {
+        let symbol = Symbol {
+            name,
+            kind,
+            file,
+            path,
+        };
+
+        match self.symbols.get(&symbol.name) {
+            // The same package may be declared by any number of files.
+            Some(existing)
+                if existing.kind == SymbolKind::Package && kind == SymbolKind::Package => {}
+            Some(_) => self.conflicts.push(symbol),
+            None => {
+                self.symbols.insert(symbol.name.clone(), symbol);
+            }
+        }
+    }
+
+    fn register_file(&mut self, file: usize) {
+        let package = self.files[file].package().to_string();
+        let mut end = 0;
+
+        for component in package.split('.').filter(|component| !component.is_empty()) {
+            end += component.len();
+            self.define(
+                package[..end].to_string(),
+                SymbolKind::Package,
+                file,
+                vec![],
+            );
+            end += 1;
+        }
+
+        let mut declarations = vec![];
+
+        for (index, entry) in self.files[file].ast.iter().enumerate() {
+            let node = match entry {
+                ast::FileEntry::Message(message) => Node::Message(message),
+                ast::FileEntry::Enum(r#enum) => Node::Enum(r#enum),
+                ast::FileEntry::Service(service) => Node::Service(service),
+                ast::FileEntry::Extend(extend) => Node::Extend(extend),
+                _ => continue,
+            };
+
+            collect(node, &package, vec![index], &mut declarations);
+        }
+
+        for (name, kind, path) in declarations {
+            self.define(name, kind, file, path);
+        }
+    }
+
+    pub fn node(&self, symbol: &Symbol) -> Option<Node<'_, 'a>> {
+        self.node_at(symbol.file, &symbol.path)
+    }
+
+    pub fn node_at(&self, file: usize, path: &[usize]) -> Option<Node<'_, 'a>> {
+        Node::at(&self.files.get(file)?.ast, path)
+    }
+
+    // Resolves a reference made from `scope` (a fully-qualified message, service or package name)
+    // in `file` the way protoc does. Only symbols of accepted kinds are considered when looking up
+    // a single-component name.
+    pub fn resolve(
+        &self,
+        file: usize,
+        scope: &str,
+        reference: &str,
+        accept: impl Fn(SymbolKind) -> bool,
+    ) -> Result<&Symbol, ResolveError> {
+        let not_found = || ResolveError::NotFound(reference.to_string());
+
+        let symbol = if let Some(absolute) = reference.strip_prefix('.') {
+            self.symbols.get(absolute).ok_or_else(not_found)?
+        } else {
+            let (first, rest) = match reference.split_once('.') {
+                Some((first, rest)) => (first, Some(rest)),
+                None => (reference, None),
+            };
+
+            let mut scope = scope;
+
+            loop {
+                let candidate = self.symbols.get(&names::join(scope, first));
+
+                match (candidate, rest) {
+                    (Some(symbol), None) if accept(symbol.kind) => break symbol,
+                    (Some(symbol), Some(rest)) if symbol.kind.is_aggregate() => {
+                        break self
+                            .symbols
+                            .get(&names::join(&symbol.name, rest))
+                            .ok_or_else(not_found)?;
+                    }
+                    _ => {}
+                }
+
+                if scope.is_empty() {
+                    return Err(not_found());
+                }
+
+                scope = names::parent(scope);
+            }
+        };
+
+        if !accept(symbol.kind) {
+            return Err(ResolveError::WrongKind {
+                name: symbol.name.clone(),
+                kind: symbol.kind,
+            });
+        }
+
+        if !self.is_visible(file, symbol) {
+            return Err(ResolveError::NotImported {
+                name: symbol.name.clone(),
+                file: self.files[symbol.file].name.to_string(),
+            });
+        }
+
+        Ok(symbol)
+    }
+
+    fn is_visible(&self, file: usize, symbol: &Symbol) -> bool {
+        let visible = &self.visible[file];
+
+        if symbol.kind != SymbolKind::Package {
+            return visible.contains(&symbol.file);
+        }
+
+        visible.iter().any(|&index| {
+            let package = self.files[index].package();
+            package == symbol.name
+                || package
+                    .strip_prefix(symbol.name.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
+    // Resolves the type of a field (not a map) declared in `scope`.
+    pub fn resolve_type(
+        &self,
+        file: usize,
+        scope: &str,
+        r#type: &str,
+    ) -> Result<TypeRef<'_>, ResolveError> {
+        if let Some(scalar) = FieldType::from_scalar_name(r#type) {
+            return Ok(TypeRef::Scalar(scalar));
+        }
+
+        let symbol = self.resolve(file, scope, r#type, |kind| kind.is_type())?;
+
+        Ok(match symbol.kind {
+            SymbolKind::Enum => TypeRef::Enum(symbol),
+            _ => TypeRef::Message(symbol),
+        })
+    }
+
+    // Fully-qualified name of the symbol enclosing the declaration at `path`, i.e. the scope
+    // references inside of it are resolved from.
+    pub fn scope_of(&self, file: usize, path: &[usize]) -> String {
+        let mut scope = self.files[file].package().to_string();
+
+        for depth in 1..=path.len() {
+            match self.node_at(file, &path[..depth]) {
+                Some(Node::Message(message)) => scope = names::join(&scope, message.ident),
+                Some(Node::Service(service)) => scope = names::join(&scope, service.ident),
+                _ => {}
+            }
+        }
+
+        scope
+    }
+}
+
+fn collect(
+    node: Node,
+    scope: &str,
+    path: Vec<usize>,
+    declarations: &mut Vec<(String, SymbolKind, Vec<usize>)>,
+) {
+    let child_path = |index: usize| {
+        let mut path = path.clone();
+        path.push(index);
+        path
+    };
+
+    match node {
+        Node::Message(message) => {
+            let name = names::join(scope, message.ident);
+            declarations.push((name.clone(), SymbolKind::Message, path.clone()));
+
+            for (index, entry) in message.entries.iter().enumerate() {
+                match entry {
+                    ast::MessageEntry::Field(field) => declarations.push((
+                        names::join(&name, field.ident),
+                        SymbolKind::Field,
+                        child_path(index),
+                    )),
+                    ast::MessageEntry::OneOf(oneof) => {
+                        collect(Node::OneOf(oneof), &name, child_path(index), declarations)
+                    }
+                    ast::MessageEntry::Message(message) => collect(
+                        Node::Message(message),
+                        &name,
+                        child_path(index),
+                        declarations,
+                    ),
+                    ast::MessageEntry::Enum(r#enum) => {
+                        collect(Node::Enum(r#enum), &name, child_path(index), declarations)
+                    }
+                    ast::MessageEntry::Extend(extend) => {
+                        collect(Node::Extend(extend), &name, child_path(index), declarations)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Node::OneOf(oneof) => {
+            declarations.push((
+                names::join(scope, oneof.ident),
+                SymbolKind::OneOf,
+                path.clone(),
+            ));
+
+            for (index, entry) in oneof.entries.iter().enumerate() {
+                if let ast::OneOfEntry::Field(field) = entry {
+                    declarations.push((
+                        names::join(scope, field.ident),
+                        SymbolKind::Field,
+                        child_path(index),
+                    ));
+                }
+            }
+        }
+        Node::Enum(r#enum) => {
+            declarations.push((
+                names::join(scope, r#enum.ident),
+                SymbolKind::Enum,
+                path.clone(),
+            ));
+
+            // Enum values are siblings of their enum, as in C++.
+            for (index, entry) in r#enum.entries.iter().enumerate() {
+                if let ast::EnumEntry::Pair { ident, .. } = entry {
+                    declarations.push((
+                        names::join(scope, ident),
+                        SymbolKind::EnumValue,
+                        child_path(index),
+                    ));
+                }
+            }
+        }
+        Node::Service(service) => {
+            let name = names::join(scope, service.ident);
+            declarations.push((name.clone(), SymbolKind::Service, path.clone()));
+
+            for (index, entry) in service.entries.iter().enumerate() {
+                if let ast::ServiceEntry::Rpc(rpc) = entry {
+                    declarations.push((
+                        names::join(&name, rpc.ident),
+                        SymbolKind::Rpc,
+                        child_path(index),
+                    ));
+                }
+            }
+        }
+        Node::Extend(extend) => {
+            for (index, entry) in extend.entries.iter().enumerate() {
+                if let ast::ExtendEntry::Field(field) = entry {
+                    declarations.push((
+                        names::join(scope, field.ident),
+                        SymbolKind::Extension,
+                        child_path(index),
+                    ));
+                }
+            }
+        }
+        Node::EnumValue(_) | Node::Rpc(_) | Node::Field(_) => {}
+    }
+}
@@ -1 +1,15 @@
-pub use parser;
\ No newline at end of file
+pub use codegen;
+pub use data;
+pub use descriptor;
+pub use diff;
+pub use docs;
+pub use editor;
+pub use formatter;
+pub use graph;
+pub use jsonschema;
+pub use lint;
+pub use lsp;
+pub use parser;
+pub use plugin;
+pub use resolver;
+pub use wire;
@@ -0,0 +1,265 @@
+// Field codecs of generated messages. Each field type of the schema has a kind telling how its
+// values are read and written, and the functions below handle fields with implicit or explicit
+// presence, repeated fields and maps of any kind.
+
+use crate::{DecodeError, Message, Reader, Tag, WireType, Writer};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+pub trait Kind {
+    type Value: Default + PartialEq;
+    const WIRE_TYPE: WireType;
+
+    fn read(reader: &mut Reader) -> Result<Self::Value, DecodeError>;
+
+    // Writes the value without its tag.
+    fn write(value: &Self::Value, writer: &mut Writer);
+
+    // Embedded messages merge into the value already there, everything else replaces it.
+    fn merge(value: &mut Self::Value, reader: &mut Reader) -> Result<(), DecodeError> {
+        *value = Self::read(reader)?;
+        Ok(())
+    }
+}
+
+macro_rules! varint {
+    ($kind:ident, $value:ty, $from:expr, $to:expr) => {
+        pub struct $kind;
+
+        impl Kind for $kind {
+            type Value = $value;
+            const WIRE_TYPE: WireType = WireType::Varint;
+
+            fn read(reader: &mut Reader) -> Result<$value, DecodeError> {
+                Ok($from(reader.read_varint()?))
+            }
+
+            fn write(value: &$value, writer: &mut Writer) {
+                writer.write_varint($to(*value));
+            }
+        }
+    };
+}
+
+// negative values take ten bytes, as they are sign-extended
+varint!(Int32, i32, |value| value as i32, |value| value as u64);
+varint!(Int64, i64, |value| value as i64, |value| value as u64);
+varint!(Uint32, u32, |value| value as u32, |value| value as u64);
+varint!(Uint64, u64, |value| value, |value| value);
+varint!(
+    Sint32,
+    i32,
+    |value| crate::decode_zigzag32(value as u32),
+    |value| crate::encode_zigzag32(value) as u64
+);
+varint!(Sint64, i64, crate::decode_zigzag64, crate::encode_zigzag64);
+varint!(Bool, bool, |value| value != 0, |value| value as u64);
+
+macro_rules! fixed {
+    ($kind:ident, $value:ty, $wire_type:ident, $read:ident, $write:ident, $bits:ty) => {
+        pub struct $kind;
+
+        impl Kind for $kind {
+            type Value = $value;
+            const WIRE_TYPE: WireType = WireType::$wire_type;
+
+            fn read(reader: &mut Reader) -> Result<$value, DecodeError> {
+                Ok(<$value>::from_le_bytes(reader.$read()?.to_le_bytes()))
+            }
+
+            fn write(value: &$value, writer: &mut Writer) {
+                writer.$write(<$bits>::from_le_bytes(value.to_le_bytes()));
+            }
+        }
+    };
+}
+
+fixed!(Fixed32, u32, Fixed32, read_fixed32, write_fixed32, u32);
+fixed!(Fixed64, u64, Fixed64, read_fixed64, write_fixed64, u64);
+fixed!(Sfixed32, i32, Fixed32, read_fixed32, write_fixed32, u32);
+fixed!(Sfixed64, i64, Fixed64, read_fixed64, write_fixed64, u64);
+fixed!(Float, f32, Fixed32, read_fixed32, write_fixed32, u32);
+fixed!(Double, f64, Fixed64, read_fixed64, write_fixed64, u64);
+
+pub struct String;
+
+impl Kind for String {
+    type Value = std::string::String;
+    const WIRE_TYPE: WireType = WireType::LengthDelimited;
+
+    fn read(reader: &mut Reader) -> Result<Self::Value, DecodeError> {
+        Ok(reader.read_string()?.to_string())
+    }
+
+    fn write(value: &Self::Value, writer: &mut Writer) {
+        writer.write_length_delimited(value.as_bytes());
+    }
+}
+
+pub struct Bytes;
+
+impl Kind for Bytes {
+    type Value = Vec<u8>;
+    const WIRE_TYPE: WireType = WireType::LengthDelimited;
+
+    fn read(reader: &mut Reader) -> Result<Self::Value, DecodeError> {
+        Ok(reader.read_length_delimited()?.to_vec())
+    }
+
+    fn write(value: &Self::Value, writer: &mut Writer) {
+        writer.write_length_delimited(value);
+    }
+}
+
+// Embedded messages of the type.
+pub struct Nested<M>(PhantomData<M>);
+
+impl<M: Message + PartialEq> Kind for Nested<M> {
+    type Value = M;
+    const WIRE_TYPE: WireType = WireType::LengthDelimited;
+
+    fn read(reader: &mut Reader) -> Result<M, DecodeError> {
+        let mut message = M::default();
+        Self::merge(&mut message, reader)?;
+        Ok(message)
+    }
+
+    fn write(value: &M, writer: &mut Writer) {
+        writer.write_length_delimited(&value.encode());
+    }
+
+    fn merge(value: &mut M, reader: &mut Reader) -> Result<(), DecodeError> {
+        let data = reader.read_length_delimited()?;
+        value.merge(&mut Reader::with_offset(
+            data,
+            reader.position() - data.len(),
+        ))
+    }
+}
+
+fn expect<K: Kind>(tag: Tag, reader: &Reader) -> Result<(), DecodeError> {
+    reader.expect_wire_type(tag, K::WIRE_TYPE)
+}
+
+pub fn merge<K: Kind>(
+    value: &mut K::Value,
+    tag: Tag,
+    reader: &mut Reader,
+) -> Result<(), DecodeError> {
+    expect::<K>(tag, reader)?;
+    K::merge(value, reader)
+}
+
+pub fn merge_optional<K: Kind>(
+    value: &mut Option<K::Value>,
+    tag: Tag,
+    reader: &mut Reader,
+) -> Result<(), DecodeError> {
+    expect::<K>(tag, reader)?;
+    K::merge(value.get_or_insert_with(Default::default), reader)
+}
+
+// Both packed and unpacked encodings are accepted for scalars.
+pub fn merge_repeated<K: Kind>(
+    values: &mut Vec<K::Value>,
+    tag: Tag,
+    reader: &mut Reader,
+) -> Result<(), DecodeError> {
+    if tag.wire_type == WireType::LengthDelimited && K::WIRE_TYPE != WireType::LengthDelimited {
+        let data = reader.read_length_delimited()?;
+        let mut packed = Reader::with_offset(data, reader.position() - data.len());
+
+        while !packed.is_empty() {
+            values.push(K::read(&mut packed)?);
+        }
+
+        return Ok(());
+    }
+
+    expect::<K>(tag, reader)?;
+    values.push(K::read(reader)?);
+    Ok(())
+}
+
+pub fn merge_map<K: Kind, V: Kind>(
+    map: &mut BTreeMap<K::Value, V::Value>,
+    tag: Tag,
+    reader: &mut Reader,
+) -> Result<(), DecodeError>
+where
+    K::Value: Ord,
+{
+    reader.expect_wire_type(tag, WireType::LengthDelimited)?;
+    let data = reader.read_length_delimited()?;
+    let mut entry = Reader::with_offset(data, reader.position() - data.len());
+
+    let mut key = K::Value::default();
+    let mut value = V::Value::default();
+
+    while !entry.is_empty() {
+        let tag = entry.read_tag()?;
+
+        match tag.number {
+            1 => merge::<K>(&mut key, tag, &mut entry)?,
+            2 => merge::<V>(&mut value, tag, &mut entry)?,
+            _ => {
+                entry.skip_field(tag)?;
+            }
+        }
+    }
+
+    map.insert(key, value);
+    Ok(())
+}
+
+// Writes the value with its tag, even if it is the default one.
+pub fn write<K: Kind>(value: &K::Value, number: u32, writer: &mut Writer) {
+    writer.write_tag(Tag::new(number, K::WIRE_TYPE));
+    K::write(value, writer);
+}
+
+// Fields without presence aren't written when they have the default value.
+pub fn encode<K: Kind>(value: &K::Value, number: u32, writer: &mut Writer) {
+    if *value != K::Value::default() {
+        write::<K>(value, number, writer);
+    }
+}
+
+pub fn encode_optional<K: Kind>(value: &Option<K::Value>, number: u32, writer: &mut Writer) {
+    if let Some(value) = value {
+        write::<K>(value, number, writer);
+    }
+}
+
+pub fn encode_repeated<K: Kind>(values: &[K::Value], number: u32, writer: &mut Writer) {
+    for value in values {
+        write::<K>(value, number, writer);
+    }
+}
+
+pub fn encode_packed<K: Kind>(values: &[K::Value], number: u32, writer: &mut Writer) {
+    if values.is_empty() {
+        return;
+    }
+
+    let mut packed = Writer::new();
+    for value in values {
+        K::write(value, &mut packed);
+    }
+
+    writer.write_tag(Tag::new(number, WireType::LengthDelimited));
+    writer.write_length_delimited(&packed.into_inner());
+}
+
+pub fn encode_map<K: Kind, V: Kind>(
+    map: &BTreeMap<K::Value, V::Value>,
+    number: u32,
+    writer: &mut Writer,
+) {
+    for (key, value) in map {
+        writer.write_nested(number, |writer| {
+            write::<K>(key, 1, writer);
+            write::<V>(value, 2, writer);
+        });
+    }
+}
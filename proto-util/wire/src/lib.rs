@@ -0,0 +1,173 @@
+pub mod codec;
+mod message;
+mod reader;
+mod writer;
+
+pub use message::Message;
+pub use reader::{DecodeError, DecodeErrorKind, Reader};
+pub use writer::Writer;
+
+pub const MAX_FIELD_NUMBER: u32 = (1 << 29) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WireType {
+    Varint,
+    Fixed64,
+    LengthDelimited,
+    StartGroup,
+    EndGroup,
+    Fixed32,
+}
+
+impl WireType {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Varint),
+            1 => Some(Self::Fixed64),
+            2 => Some(Self::LengthDelimited),
+            3 => Some(Self::StartGroup),
+            4 => Some(Self::EndGroup),
+            5 => Some(Self::Fixed32),
+            _ => None,
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::Varint => 0,
+            Self::Fixed64 => 1,
+            Self::LengthDelimited => 2,
+            Self::StartGroup => 3,
+            Self::EndGroup => 4,
+            Self::Fixed32 => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for WireType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Varint => "varint",
+            Self::Fixed64 => "fixed64",
+            Self::LengthDelimited => "length-delimited",
+            Self::StartGroup => "start-group",
+            Self::EndGroup => "end-group",
+            Self::Fixed32 => "fixed32",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tag {
+    pub number: u32,
+    pub wire_type: WireType,
+}
+
+impl Tag {
+    pub fn new(number: u32, wire_type: WireType) -> Self {
+        Self { number, wire_type }
+    }
+}
+
+pub fn encode_zigzag32(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+pub fn decode_zigzag32(value: u32) -> i32 {
+    ((value >> 1) as i32) ^ -((value & 1) as i32)
+}
+
+pub fn encode_zigzag64(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+pub fn decode_zigzag64(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+pub fn varint_len(mut value: u64) -> usize {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DecodeErrorKind, Reader, Tag, WireType, Writer};
+
+    #[test]
+    fn varint() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut writer = Writer::new();
+            writer.write_varint(value);
+
+            let data = writer.into_inner();
+            assert_eq!(data.len(), crate::varint_len(value));
+
+            let mut reader = Reader::new(&data);
+            assert_eq!(reader.read_varint().unwrap(), value);
+            assert!(reader.is_empty());
+        }
+
+        let mut reader = Reader::new(&[0xAC, 0x02]);
+        assert_eq!(reader.read_varint().unwrap(), 300);
+    }
+
+    #[test]
+    fn zigzag() {
+        for value in [0, -1, 1, i32::MIN, i32::MAX] {
+            assert_eq!(crate::decode_zigzag32(crate::encode_zigzag32(value)), value);
+        }
+
+        for value in [0, -1, 1, i64::MIN, i64::MAX] {
+            assert_eq!(crate::decode_zigzag64(crate::encode_zigzag64(value)), value);
+        }
+
+        assert_eq!(crate::encode_zigzag32(-1), 1);
+        assert_eq!(crate::encode_zigzag64(1), 2);
+    }
+
+    #[test]
+    fn skip_group() {
+        let mut writer = Writer::new();
+        writer.write_tag(Tag::new(1, WireType::StartGroup));
+        writer.write_tag(Tag::new(2, WireType::Varint));
+        writer.write_varint(150);
+        writer.write_tag(Tag::new(3, WireType::StartGroup));
+        writer.write_tag(Tag::new(3, WireType::EndGroup));
+        writer.write_tag(Tag::new(1, WireType::EndGroup));
+        writer.write_tag(Tag::new(4, WireType::Fixed32));
+        writer.write_fixed32(7);
+
+        let data = writer.into_inner();
+        let mut reader = Reader::new(&data);
+
+        let tag = reader.read_tag().unwrap();
+        assert_eq!(tag, Tag::new(1, WireType::StartGroup));
+        assert_eq!(reader.skip_field(tag).unwrap().len(), 6);
+
+        let tag = reader.read_tag().unwrap();
+        assert_eq!(tag, Tag::new(4, WireType::Fixed32));
+        assert_eq!(reader.read_fixed32().unwrap(), 7);
+    }
+
+    #[test]
+    fn errors() {
+        let mut reader = Reader::new(&[0x80, 0x80]);
+        let error = reader.read_varint().unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::UnexpectedEof);
+
+        let mut reader = Reader::new(&[0x0F]);
+        let error = reader.read_tag().unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::InvalidWireType(7));
+
+        let mut reader = Reader::new(&[0x0A, 0x05, 0x01]);
+        let tag = reader.read_tag().unwrap();
+        let error = reader.skip_field(tag).unwrap_err();
+        assert_eq!(error.kind, DecodeErrorKind::UnexpectedEof);
+        assert_eq!(error.position, 1);
+    }
+}
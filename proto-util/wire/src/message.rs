@@ -0,0 +1,42 @@
+// Messages decoded into and encoded from Rust types, those describing schemas as well as the
+// generated ones.
+
+use crate::{DecodeError, Reader, Tag, Writer};
+
+pub trait Message: Default {
+    fn merge_field(&mut self, tag: Tag, reader: &mut Reader) -> Result<(), DecodeError>;
+
+    fn encode_to(&self, writer: &mut Writer);
+
+    fn merge(&mut self, reader: &mut Reader) -> Result<(), DecodeError> {
+        while !reader.is_empty() {
+            let tag = reader.read_tag()?;
+            self.merge_field(tag, reader)?;
+        }
+
+        Ok(())
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut message = Self::default();
+        message.merge(&mut Reader::new(data))?;
+        Ok(message)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        self.encode_to(&mut writer);
+        writer.into_inner()
+    }
+}
+
+// Fields of recursive messages are boxed.
+impl<M: Message> Message for Box<M> {
+    fn merge_field(&mut self, tag: Tag, reader: &mut Reader) -> Result<(), DecodeError> {
+        self.as_mut().merge_field(tag, reader)
+    }
+
+    fn encode_to(&self, writer: &mut Writer) {
+        self.as_ref().encode_to(writer)
+    }
+}
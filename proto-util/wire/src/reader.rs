@@ -0,0 +1,243 @@
+use crate::{Tag, WireType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    UnexpectedEof,
+    VarintOverflow,
+    InvalidWireType(u8),
+    InvalidFieldNumber(u64),
+    UnexpectedEndGroup(u32),
+    UnterminatedGroup(u32),
+    UnexpectedWireType { expected: WireType, found: WireType },
+    InvalidUtf8,
+    Custom(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    pub position: usize,
+}
+
+impl DecodeError {
+    pub fn new(kind: DecodeErrorKind, position: usize) -> Self {
+        Self { kind, position }
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.kind {
+            DecodeErrorKind::UnexpectedEof => write!(f, "Unexpected end of input")?,
+            DecodeErrorKind::VarintOverflow => write!(f, "Varint is longer than 10 bytes")?,
+            DecodeErrorKind::InvalidWireType(wire_type) => {
+                write!(f, "Invalid wire type {}", wire_type)?
+            }
+            DecodeErrorKind::InvalidFieldNumber(number) => {
+                write!(f, "Invalid field number {}", number)?
+            }
+            DecodeErrorKind::UnexpectedEndGroup(number) => {
+                write!(f, "Unexpected end of group {}", number)?
+            }
+            DecodeErrorKind::UnterminatedGroup(number) => {
+                write!(f, "Group {} is not terminated", number)?
+            }
+            DecodeErrorKind::UnexpectedWireType { expected, found } => {
+                write!(f, "Expected {} wire type, found {}", expected, found)?
+            }
+            DecodeErrorKind::InvalidUtf8 => write!(f, "String is not valid UTF-8")?,
+            DecodeErrorKind::Custom(message) => f.write_str(message)?,
+        };
+
+        write!(f, " at byte {}", self.position)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            offset: 0,
+        }
+    }
+
+    // Reader over a slice of a bigger buffer; positions are reported relative to the bigger one.
+    pub fn with_offset(data: &'a [u8], offset: usize) -> Self {
+        Self {
+            data,
+            position: 0,
+            offset,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.offset + self.position
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.position..]
+    }
+
+    pub fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+        DecodeError::new(kind, self.position())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.data.len() - self.position < len {
+            return Err(self.error(DecodeErrorKind::UnexpectedEof));
+        }
+
+        let slice = &self.data[self.position..self.position + len];
+        self.position += len;
+
+        Ok(slice)
+    }
+
+    pub fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let start = self.position;
+        let mut value = 0u64;
+
+        for shift in (0..70).step_by(7) {
+            let Some(&byte) = self.data.get(self.position) else {
+                self.position = start;
+                return Err(self.error(DecodeErrorKind::UnexpectedEof));
+            };
+            self.position += 1;
+
+            value |= ((byte & 0x7F) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        self.position = start;
+        Err(self.error(DecodeErrorKind::VarintOverflow))
+    }
+
+    pub fn read_tag(&mut self) -> Result<Tag, DecodeError> {
+        let start = self.position;
+        let key = self.read_varint()?;
+
+        let Some(wire_type) = WireType::from_u8((key & 0x07) as u8) else {
+            self.position = start;
+            return Err(self.error(DecodeErrorKind::InvalidWireType((key & 0x07) as u8)));
+        };
+
+        let number = key >> 3;
+        if number == 0 || number > crate::MAX_FIELD_NUMBER as u64 {
+            self.position = start;
+            return Err(self.error(DecodeErrorKind::InvalidFieldNumber(number)));
+        }
+
+        Ok(Tag::new(number as u32, wire_type))
+    }
+
+    pub fn read_fixed32(&mut self) -> Result<u32, DecodeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn read_fixed64(&mut self) -> Result<u64, DecodeError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn read_length_delimited(&mut self) -> Result<&'a [u8], DecodeError> {
+        let start = self.position;
+        let len = self.read_varint()?;
+
+        if len > (self.data.len() - self.position) as u64 {
+            self.position = start;
+            return Err(self.error(DecodeErrorKind::UnexpectedEof));
+        }
+
+        self.take(len as usize)
+    }
+
+    pub fn read_string(&mut self) -> Result<&'a str, DecodeError> {
+        let start = self.position;
+        let bytes = self.read_length_delimited()?;
+
+        std::str::from_utf8(bytes)
+            .map_err(|_| DecodeError::new(DecodeErrorKind::InvalidUtf8, self.offset + start))
+    }
+
+    // Returns the group body, without the end group tag.
+    pub fn read_group(&mut self, number: u32) -> Result<&'a [u8], DecodeError> {
+        let start = self.position;
+
+        loop {
+            if self.is_empty() {
+                return Err(self.error(DecodeErrorKind::UnterminatedGroup(number)));
+            }
+
+            let end = self.position;
+            let tag = self.read_tag()?;
+
+            if tag.wire_type == WireType::EndGroup {
+                if tag.number != number {
+                    self.position = end;
+                    return Err(self.error(DecodeErrorKind::UnexpectedEndGroup(tag.number)));
+                }
+
+                return Ok(&self.data[start..end]);
+            }
+
+            self.skip_field(tag)?;
+        }
+    }
+
+    // Skips the value of the field and returns its raw encoding.
+    pub fn skip_field(&mut self, tag: Tag) -> Result<&'a [u8], DecodeError> {
+        let start = self.position;
+
+        match tag.wire_type {
+            WireType::Varint => {
+                self.read_varint()?;
+            }
+            WireType::Fixed64 => {
+                self.take(8)?;
+            }
+            WireType::LengthDelimited => {
+                self.read_length_delimited()?;
+            }
+            WireType::StartGroup => {
+                self.read_group(tag.number)?;
+            }
+            WireType::EndGroup => {
+                return Err(self.error(DecodeErrorKind::UnexpectedEndGroup(tag.number)));
+            }
+            WireType::Fixed32 => {
+                self.take(4)?;
+            }
+        }
+
+        Ok(&self.data[start..self.position])
+    }
+
+    pub fn expect_wire_type(&self, tag: Tag, expected: WireType) -> Result<(), DecodeError> {
+        if tag.wire_type != expected {
+            return Err(self.error(DecodeErrorKind::UnexpectedWireType {
+                expected,
+                found: tag.wire_type,
+            }));
+        }
+
+        Ok(())
+    }
+}
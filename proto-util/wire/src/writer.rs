@@ -0,0 +1,62 @@
+use crate::{Tag, WireType};
+
+#[derive(Debug, Default, Clone)]
+pub struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.data.push((value as u8 & 0x7F) | 0x80);
+            value >>= 7;
+        }
+        self.data.push(value as u8);
+    }
+
+    pub fn write_tag(&mut self, tag: Tag) {
+        self.write_varint(((tag.number as u64) << 3) | tag.wire_type.as_u8() as u64);
+    }
+
+    pub fn write_fixed32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_fixed64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_length_delimited(&mut self, value: &[u8]) {
+        self.write_varint(value.len() as u64);
+        self.data.extend_from_slice(value);
+    }
+
+    pub fn write_raw(&mut self, value: &[u8]) {
+        self.data.extend_from_slice(value);
+    }
+
+    // Writes a length-delimited field whose contents are produced by `f`.
+    pub fn write_nested(&mut self, number: u32, f: impl FnOnce(&mut Writer)) {
+        let mut nested = Writer::new();
+        f(&mut nested);
+
+        self.write_tag(Tag::new(number, WireType::LengthDelimited));
+        self.write_length_delimited(&nested.data);
+    }
+}
@@ -0,0 +1,44 @@
+// protoc plugin running the checks instead of generating code, e.g.
+// `protoc --protobuf-editor_out=. --protobuf-editor_opt=lint,format,breaking=old.binpb api.proto`.
+// Errors fail the build through the response, warnings go to stderr, which protoc passes on.
+
+use proto_util::descriptor::{CodeGeneratorRequest, Message};
+use proto_util::plugin::{self, Checks, Report};
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut input = vec![];
+    if let Err(error) = std::io::stdin().read_to_end(&mut input) {
+        eprintln!("error: {}", error);
+        return ExitCode::FAILURE;
+    }
+
+    let request = match CodeGeneratorRequest::decode(&input) {
+        Ok(request) => request,
+        Err(error) => {
+            eprintln!("error: invalid CodeGeneratorRequest: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = match Checks::parse(request.parameter.as_deref().unwrap_or_default()) {
+        Ok(checks) => plugin::check(&request, &checks),
+        Err(error) => Report {
+            errors: vec![error.to_string()],
+            warnings: vec![],
+        },
+    };
+
+    for warning in &report.warnings {
+        eprintln!("{}", warning);
+    }
+
+    match std::io::stdout().write_all(&report.response().encode()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use proto_util::editor;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    file: super::rewrite::SchemaFile,
+
+    /// Name of the enum, e.g. `pkg.Status`
+    r#enum: String,
+
+    /// Name of the value
+    name: String,
+
+    /// Number of the value, one more than the largest one if omitted
+    number: Option<i64>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    args.file
+        .rewrite(|source| editor::add_enum_value(source, &args.r#enum, &args.name, args.number))
+}
@@ -0,0 +1,57 @@
+use proto_util::editor::{self, NewField};
+use proto_util::parser::ast::FieldModifier;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Label {
+    Optional,
+    Required,
+    Repeated,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    file: super::rewrite::SchemaFile,
+
+    /// Name of the message, e.g. `pkg.User`
+    message: String,
+
+    /// Type of the field, e.g. `string` or `map<string, int32>`
+    #[arg(short, long = "type", value_name = "TYPE")]
+    r#type: String,
+
+    /// Name of the field
+    #[arg(short, long)]
+    name: String,
+
+    /// Field number, the next free one if omitted
+    #[arg(short = 'N', long)]
+    number: Option<i64>,
+
+    /// Label of the field
+    #[arg(short, long, value_enum)]
+    label: Option<Label>,
+
+    /// Oneof of the message to add the field to
+    #[arg(long, value_name = "NAME")]
+    oneof: Option<String>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    args.file.rewrite(|source| {
+        let field = NewField {
+            modifier: match args.label {
+                None => FieldModifier::None,
+                Some(Label::Optional) => FieldModifier::Optional,
+                Some(Label::Required) => FieldModifier::Required,
+                Some(Label::Repeated) => FieldModifier::Repeated,
+            },
+            r#type: &args.r#type,
+            name: &args.name,
+            number: args.number,
+            oneof: args.oneof.as_deref(),
+        };
+
+        editor::add_field(source, &args.message, field)
+    })
+}
@@ -0,0 +1,192 @@
+use proto_util::descriptor::{FileDescriptorSet, Message};
+use proto_util::diff::{self, Change, ChangeKind, Level};
+use proto_util::editor::{self, FileEdits};
+use proto_util::resolver::{self, SourceTree, Workspace};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum Compatibility {
+    /// Binary data and RPCs
+    Wire,
+    /// JSON data
+    Json,
+    /// Generated code
+    Source,
+}
+
+impl Compatibility {
+    fn level(&self) -> Level {
+        match self {
+            Self::Wire => Level::Wire,
+            Self::Json => Level::Json,
+            Self::Source => Level::Source,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// Old version: a .proto file, a directory of them or a FileDescriptorSet
+    old: PathBuf,
+
+    /// New version, given the same way
+    new: PathBuf,
+
+    /// Report only the changes breaking this compatibility, may be repeated
+    #[arg(short, long, value_enum, value_name = "LEVEL")]
+    level: Vec<Compatibility>,
+
+    /// Reserve the numbers and names freed by removed and renumbered fields and enum values in the
+    /// new .proto files
+    #[arg(long)]
+    fix: bool,
+}
+
+// Sources of the version, unless it is a FileDescriptorSet.
+fn sources(
+    include: &[PathBuf],
+    path: &Path,
+) -> Result<Option<SourceTree>, Box<dyn std::error::Error>> {
+    let mut include = include.to_vec();
+    let mut files = vec![];
+
+    if path.is_dir() {
+        include.insert(0, path.to_path_buf());
+        files = super::proto_files(path)?;
+    } else if path
+        .extension()
+        .is_some_and(|extension| extension == "proto")
+    {
+        files.push(path.to_path_buf());
+    } else {
+        return Ok(None);
+    }
+
+    Ok(Some(SourceTree::load(include, &files)?))
+}
+
+fn descriptors(
+    include: &[PathBuf],
+    path: &Path,
+) -> Result<FileDescriptorSet, Box<dyn std::error::Error>> {
+    match sources(include, path)? {
+        Some(tree) => Ok(resolver::compile(&Workspace::new(&tree)?)?),
+        None => Ok(FileDescriptorSet::decode(&std::fs::read(path)?)?),
+    }
+}
+
+// Edits reserving what the removal of fields and enum values frees for reuse by mistake: the
+// number unless wire compatibility is kept, and the name unless JSON compatibility is. The old
+// number of renumbered ones is reserved too, all of them merged into the `reserved` statements
+// of the message or enum.
+fn reservations(
+    tree: &SourceTree,
+    changes: &[Change],
+) -> Result<Vec<FileEdits>, Box<dyn std::error::Error>> {
+    // numbers and names to reserve by file and message or enum
+    let mut freed: Vec<(usize, &str, Vec<i64>, Vec<&str>)> = vec![];
+
+    for change in changes {
+        let (target, name, number) = match &change.kind {
+            ChangeKind::FieldRemoved {
+                message,
+                name,
+                number,
+            } => (message, Some(name), i64::from(*number)),
+            ChangeKind::FieldNumberChanged { message, old, .. } => (message, None, i64::from(*old)),
+            ChangeKind::EnumValueRemoved {
+                r#enum,
+                name,
+                number,
+            } => (r#enum, Some(name), i64::from(*number)),
+            ChangeKind::EnumValueNumberChanged { r#enum, old, .. } => {
+                (r#enum, None, i64::from(*old))
+            }
+            _ => continue,
+        };
+
+        let Some(file) = tree
+            .files()
+            .iter()
+            .position(|file| file.name == change.file)
+        else {
+            continue;
+        };
+
+        let index = match freed
+            .iter()
+            .position(|(other, other_target, ..)| *other == file && other_target == target)
+        {
+            Some(index) => index,
+            None => {
+                freed.push((file, target, vec![], vec![]));
+                freed.len() - 1
+            }
+        };
+        let (.., numbers, names) = &mut freed[index];
+
+        if change.breaks(Level::Wire) {
+            numbers.push(number);
+        }
+        if let Some(name) = name.filter(|_| change.breaks(Level::Json)) {
+            names.push(name);
+        }
+    }
+
+    let mut reservations: Vec<FileEdits> = vec![];
+
+    for (file, target, numbers, names) in freed {
+        // all the edits are made against the source as it was
+        let edits = editor::reserve_freed(&tree.files()[file].source, target, &numbers, &names)?;
+        if edits.is_empty() {
+            continue;
+        }
+
+        match reservations.iter_mut().find(|edits| edits.file == file) {
+            Some(existing) => existing.edits.extend(edits),
+            None => reservations.push(FileEdits { file, edits }),
+        }
+    }
+
+    Ok(reservations)
+}
+
+pub fn run(args: Args) -> super::Result {
+    let old = descriptors(&args.include, &args.old)?;
+
+    let levels: Vec<_> = args.level.iter().map(Compatibility::level).collect();
+    let compare = || -> Result<Vec<Change>, Box<dyn std::error::Error>> {
+        let new = descriptors(&args.include, &args.new)?;
+
+        Ok(diff::descriptor_changes(&old, &new)
+            .into_iter()
+            .filter(|change| levels.is_empty() || levels.iter().any(|level| change.breaks(*level)))
+            .collect())
+    };
+    let mut changes = compare()?;
+
+    if args.fix {
+        let tree = sources(&args.include, &args.new)?
+            .ok_or("--fix needs the new version as .proto files")?;
+        let reservations = reservations(&tree, &changes)?;
+
+        if !reservations.is_empty() {
+            super::rewrite::write_changes(&tree, reservations)?;
+            changes = compare()?;
+        }
+    }
+
+    for change in &changes {
+        println!("{}", change);
+    }
+
+    match changes.len() {
+        0 => Ok(()),
+        1 => Err("1 breaking change".into()),
+        count => Err(format!("{} breaking changes", count).into()),
+    }
+}
@@ -0,0 +1,50 @@
+use proto_util::codegen::{self, Options};
+use proto_util::resolver::Workspace;
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// .proto files or directories of them to generate code for
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Generate code for the files they import too
+    #[arg(long)]
+    include_imports: bool,
+
+    /// Path of the crate the generated code uses to encode and decode messages
+    #[arg(long, default_value = "::proto_util::wire")]
+    runtime: String,
+
+    /// Directory to write the generated files to
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let (tree, names) = super::load_sources(&args.include, &args.paths)?;
+
+    let workspace = Workspace::new(&tree)?;
+    let generated: Vec<_> = match args.include_imports {
+        true => (0..workspace.files().len()).collect(),
+        false => names
+            .iter()
+            .filter_map(|name| workspace.file_index(name))
+            .collect(),
+    };
+
+    let options = Options {
+        runtime: args.runtime,
+    };
+
+    std::fs::create_dir_all(&args.output)?;
+    for output in codegen::generate(&workspace, &generated, &options)? {
+        std::fs::write(args.output.join(&output.path), output.content)?;
+    }
+
+    Ok(())
+}
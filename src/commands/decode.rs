@@ -0,0 +1,44 @@
+use proto_util::data;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    /// Fields with their numbers, unknown fields and packing
+    Tree,
+    /// Protobuf text format, as printed by `protoc --decode`
+    Text,
+    /// Canonical proto3 JSON mapping
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    schema: super::schema::Schema,
+
+    /// Fully-qualified name of the message type, e.g. `pkg.User`
+    #[arg(short, long = "type", value_name = "NAME")]
+    r#type: String,
+
+    /// How to print the message
+    #[arg(short, long, value_enum, default_value_t = Format::Tree)]
+    format: Format,
+
+    /// Binary message, stdin is used if omitted
+    input: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let pool = args.schema.pool()?;
+    let data = super::read_input(&args.input)?;
+
+    let message = data::decode(&pool, &args.r#type, &data)?;
+
+    match args.format {
+        Format::Tree => print!("{}", data::print::tree(&pool, &message)),
+        Format::Text => print!("{}", data::text::print(&pool, &message)),
+        Format::Json => println!("{}", data::json::print(&pool, &message)?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use proto_util::descriptor::{self, Arena, FileDescriptorSet, Message};
+use proto_util::formatter;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// FileDescriptorSet, e.g. produced by `protoc --descriptor_set_out`
+    input: PathBuf,
+
+    /// Directory to write the .proto files to, stdout is used if omitted
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Decompile only the given files of the set
+    #[arg(long = "file", value_name = "NAME")]
+    files: Vec<String>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let data = std::fs::read(&args.input)?;
+    let set = FileDescriptorSet::decode(&data)?;
+
+    let arena = Arena::new();
+    let decompiled = descriptor::decompile(&set, &arena)?;
+
+    for file in decompiled {
+        if !args.files.is_empty() && !args.files.iter().any(|name| name == file.name) {
+            continue;
+        }
+
+        let source = formatter::format(&file.ast);
+
+        match &args.output {
+            Some(output) => {
+                let name = Path::new(file.name);
+                if !name
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+                {
+                    return Err(
+                        format!("refusing to write file outside of output: {}", file.name).into(),
+                    );
+                }
+
+                let path = output.join(name);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, source)?;
+            }
+            None => print!("// {}\n{}\n", file.name, source),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use proto_util::diff::{self, DiffOptions};
+use proto_util::parser::{self, ast};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Old version: a .proto file or a directory of them
+    old: PathBuf,
+
+    /// New version, given the same way
+    new: PathBuf,
+
+    /// Print the differences as JSON instead of a tree
+    #[arg(long)]
+    json: bool,
+
+    /// Also report changed leading, trailing and detached comments
+    #[arg(long)]
+    comments: bool,
+
+    /// Also report reordered elements
+    #[arg(long)]
+    order: bool,
+}
+
+// Sources by the name the files are compared under: their path relative to the directory.
+fn sources(path: &Path) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    if !path.is_dir() {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        return Ok(BTreeMap::from([(
+            name.to_string(),
+            std::fs::read_to_string(path)?,
+        )]));
+    }
+
+    let mut sources = BTreeMap::new();
+
+    for file in super::proto_files(path)? {
+        let name = file
+            .strip_prefix(path)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        sources.insert(name, std::fs::read_to_string(&file)?);
+    }
+
+    Ok(sources)
+}
+
+fn parse<'s>(name: &str, source: Option<&'s String>) -> Result<Option<ast::File<'s>>, String> {
+    source
+        .map(|source| {
+            parser::parse(source)
+                .map_err(|error| format!("{}: {}", name, parser::error_message(source, &error)))
+        })
+        .transpose()
+}
+
+pub fn run(args: Args) -> super::Result {
+    let (mut old, mut new) = (sources(&args.old)?, sources(&args.new)?);
+
+    // single files are compared whatever their names are
+    if !args.old.is_dir() && !args.new.is_dir() {
+        let name = new.keys().next().cloned().unwrap_or_default();
+        old = old
+            .into_values()
+            .map(|source| (name.clone(), source))
+            .collect();
+        new = new
+            .into_values()
+            .map(|source| (name.clone(), source))
+            .collect();
+    }
+
+    let options = DiffOptions {
+        comments: args.comments,
+        order: args.order,
+    };
+
+    let mut names: Vec<_> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut nodes = vec![];
+
+    for name in names {
+        let (old_source, new_source) = (old.get(name), new.get(name));
+        let (old_file, new_file) = (parse(name, old_source)?, parse(name, new_source)?);
+        nodes.extend(diff::semantic_diff(
+            name,
+            old_source.map(String::as_str).zip(old_file.as_ref()),
+            new_source.map(String::as_str).zip(new_file.as_ref()),
+            options,
+        ));
+    }
+
+    if args.json {
+        println!("{}", diff::print_json(&nodes));
+    } else {
+        for node in nodes {
+            print!("{}", node);
+        }
+    }
+
+    Ok(())
+}
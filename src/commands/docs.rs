@@ -0,0 +1,52 @@
+use proto_util::docs::{self, Format};
+use proto_util::resolver::Workspace;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Output {
+    /// GitHub-flavored Markdown
+    Markdown,
+    /// Static HTML
+    Html,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// .proto files or directories of them to document, without the files they import
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Format of the pages
+    #[arg(short, long, value_enum, default_value = "markdown")]
+    format: Output,
+
+    /// Directory to write the pages to
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let (tree, names) = super::load_sources(&args.include, &args.paths)?;
+
+    let workspace = Workspace::new(&tree)?;
+    let documented: Vec<_> = names
+        .iter()
+        .filter_map(|name| workspace.file_index(name))
+        .collect();
+
+    let format = match args.format {
+        Output::Markdown => Format::Markdown,
+        Output::Html => Format::Html,
+    };
+
+    std::fs::create_dir_all(&args.output)?;
+    for page in docs::generate(&workspace, &documented, format) {
+        std::fs::write(args.output.join(&page.path), page.content)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use crate::tui::{self, schema::SchemaEditor};
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// .proto file to edit
+    file: PathBuf,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let mut editor = SchemaEditor::open(args.include, &args.file)?;
+    tui::run(&mut editor)?;
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use crate::tui::{self, data::DataEditor};
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    schema: super::schema::Schema,
+
+    /// Fully-qualified name of the message type, e.g. `pkg.User`
+    #[arg(short, long = "type", value_name = "NAME")]
+    r#type: String,
+
+    /// Binary message to edit, created on save if it doesn't exist
+    file: PathBuf,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let mut editor = DataEditor::open(args.schema.pool()?, &args.r#type, &args.file)?;
+    tui::run(&mut editor)?;
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use proto_util::data;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    /// Protobuf text format, as read by `protoc --encode`
+    Text,
+    /// Canonical proto3 JSON mapping
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    schema: super::schema::Schema,
+
+    /// Fully-qualified name of the message type, e.g. `pkg.User`
+    #[arg(short, long = "type", value_name = "NAME")]
+    r#type: String,
+
+    /// Format of the input
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// File to write the binary message to, stdout is used if omitted
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Message in text format or JSON, stdin is used if omitted
+    input: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let pool = args.schema.pool()?;
+    let text = String::from_utf8(super::read_input(&args.input)?)?;
+
+    let message = match args.format {
+        Format::Text => data::text::parse(&pool, &args.r#type, &text)?,
+        Format::Json => data::json::parse(&pool, &args.r#type, &text)?,
+    };
+    let data = data::encode(&pool, &message)?;
+
+    match &args.output {
+        Some(path) => std::fs::write(path, data)?,
+        None => std::io::stdout().write_all(&data)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use proto_util::graph;
+use proto_util::resolver::Workspace;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    /// Graphviz DOT
+    Dot,
+    /// Nodes, edges and cycles in JSON
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// .proto files or directories of them
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Graph the imports of the files instead of the references between types
+    #[arg(long)]
+    imports: bool,
+
+    /// Only the given type, service or file and what it depends on, directly or not
+    #[arg(long, value_name = "NAME")]
+    focus: Option<String>,
+
+    /// Only services and the requests and replies of their methods
+    #[arg(long, conflicts_with = "imports")]
+    services: bool,
+
+    /// Format of the graph
+    #[arg(short, long, value_enum, default_value = "dot")]
+    format: Format,
+
+    /// File to write the graph to, stdout is used if omitted
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let (tree, names) = super::load_sources(&args.include, &args.paths)?;
+
+    let workspace = Workspace::new(&tree)?;
+    let graphed: Vec<_> = names
+        .iter()
+        .filter_map(|name| workspace.file_index(name))
+        .collect();
+
+    let mut graph = match args.imports {
+        true => graph::imports(&workspace),
+        false => graph::types(&workspace, &graphed),
+    };
+    if args.services {
+        graph = graph.services();
+    }
+    if let Some(name) = &args.focus {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        graph = graph
+            .closure(name)
+            .ok_or_else(|| format!("{} is not in the graph", name))?;
+    }
+
+    let text = match args.format {
+        Format::Dot => graph.to_dot(if args.imports { "imports" } else { "types" }),
+        Format::Json => graph::print_json(&graph) + "\n",
+    };
+    match &args.output {
+        Some(path) => std::fs::write(path, text)?,
+        None => print!("{}", text),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use proto_util::descriptor::Arena;
+use proto_util::formatter;
+use proto_util::jsonschema::{self, ImportOptions};
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// JSON Schema or OpenAPI document, in JSON
+    input: PathBuf,
+
+    /// Package of the messages and enums
+    #[arg(short, long, default_value = "")]
+    package: String,
+
+    /// Message of the root schema when it has no title, named after the file if omitted
+    #[arg(long, value_name = "NAME")]
+    root: Option<String>,
+
+    /// .proto file to write, stdout is used if omitted
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let source = std::fs::read_to_string(&args.input)?;
+    let root = args.root.unwrap_or_else(|| {
+        let stem = args.input.file_stem().unwrap_or_default().to_string_lossy();
+        // e.g. "user" of user.schema.json
+        stem.split('.').next().unwrap_or_default().to_string()
+    });
+    let options = ImportOptions {
+        package: args.package,
+        root,
+    };
+
+    let arena = Arena::new();
+    let file = jsonschema::import(&source, &options, &arena)?;
+    let proto = formatter::format(&file);
+
+    match &args.output {
+        Some(path) => std::fs::write(path, proto)?,
+        None => print!("{}", proto),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use proto_util::data;
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    schema: super::schema::Schema,
+
+    /// Message type to annotate the fields with, requires --proto or --descriptor-set
+    #[arg(short, long = "type", value_name = "NAME")]
+    r#type: Option<String>,
+
+    /// Binary message, stdin is used if omitted
+    input: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let data = super::read_input(&args.input)?;
+
+    let mut fields = data::raw::inspect(&data)?;
+
+    match &args.r#type {
+        Some(type_name) => {
+            let pool = args.schema.pool()?;
+
+            if pool.message(type_name).is_none() {
+                return Err(format!("Unknown message type {}", type_name).into());
+            }
+
+            data::raw::annotate(&mut fields, &pool, type_name);
+            print!("{}", data::print::raw(Some(&pool), &fields));
+        }
+        None => print!("{}", data::print::raw(None, &fields)),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use proto_util::descriptor::Pool;
+use proto_util::jsonschema;
+use proto_util::resolver::{self, Workspace};
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// .proto files or directories of them
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Fully-qualified message or enum to export, may be repeated; all of those of the files if
+    /// omitted
+    #[arg(short, long = "type", value_name = "NAME")]
+    types: Vec<String>,
+
+    /// File to write the schema to, stdout is used if omitted
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let (tree, names) = super::load_sources(&args.include, &args.paths)?;
+
+    let workspace = Workspace::new(&tree)?;
+    let pool = Pool::new(&resolver::compile(&workspace)?);
+
+    let types = match args.types.is_empty() {
+        true => {
+            let messages = pool
+                .messages()
+                .filter(|message| !message.map_entry && names.contains(&message.file))
+                .map(|message| message.name.clone());
+            let enums = pool
+                .enums()
+                .filter(|r#enum| names.contains(&r#enum.file))
+                .map(|r#enum| r#enum.name.clone());
+
+            let mut types: Vec<_> = messages.chain(enums).collect();
+            types.sort();
+            types
+        }
+        false => args.types,
+    };
+
+    let schema = jsonschema::print(&jsonschema::json_schema(&pool, &types)?);
+    match &args.output {
+        Some(path) => std::fs::write(path, schema + "\n")?,
+        None => println!("{}", schema),
+    }
+
+    Ok(())
+}
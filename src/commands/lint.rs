@@ -0,0 +1,242 @@
+use proto_util::editor;
+use proto_util::lint::{self, Config, Diagnostic, Preset, Severity};
+use proto_util::parser;
+use proto_util::resolver::{self, SourceTree, Workspace};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Rules {
+    /// Naming of the Google and Buf style guides
+    Default,
+    /// Also comments on every message, field, enum, value, service and method
+    Strict,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// .proto files or directories of them, with directories named after the packages
+    #[arg(required_unless_present = "list")]
+    paths: Vec<PathBuf>,
+
+    /// Rules to start from
+    #[arg(long, value_enum, default_value = "default", conflicts_with = "config")]
+    preset: Rules,
+
+    /// File of `preset = <name>` and `<RULE> = off|warning|error` lines
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Enable a rule, may be repeated
+    #[arg(long, value_name = "RULE")]
+    enable: Vec<String>,
+
+    /// Disable a rule, may be repeated
+    #[arg(long, value_name = "RULE")]
+    disable: Vec<String>,
+
+    /// List the rules and whether they are enabled
+    #[arg(long)]
+    list: bool,
+
+    /// Apply the fixes of the problems that have one, in place
+    #[arg(long)]
+    fix: bool,
+}
+
+// Fixes can make other problems fixable, e.g. a renamed field freeing its old name.
+const FIX_PASSES: usize = 10;
+
+// Files with the paths their packages are checked against: relative to the directory they were
+// found in, or as given.
+fn files(paths: &[PathBuf]) -> Result<Vec<(String, PathBuf)>, Box<dyn std::error::Error>> {
+    let mut files = vec![];
+
+    for path in paths {
+        if !path.is_dir() {
+            let name = path.strip_prefix(".").unwrap_or(path);
+            files.push((name.to_string_lossy().replace('\\', "/"), path.clone()));
+            continue;
+        }
+
+        for file in super::proto_files(path)? {
+            let name = file
+                .strip_prefix(path)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            files.push((name, file));
+        }
+    }
+
+    Ok(files)
+}
+
+fn lint_file(
+    name: &str,
+    path: &Path,
+    source: &str,
+    config: &Config,
+) -> Result<Vec<Diagnostic>, String> {
+    let file = parser::parse(source).map_err(|error| {
+        format!(
+            "{}: {}",
+            path.display(),
+            parser::error_message(source, &error)
+        )
+    })?;
+
+    Ok(lint::lint(name, source, &file, config))
+}
+
+// Names of the declarations of the file that other files refer to, e.g. an enum value used as the
+// default of a field; renaming them would break those files.
+fn referenced_elsewhere(workspace: &Workspace, file: usize) -> HashSet<Range<usize>> {
+    let source = workspace.file(file).source;
+
+    (0..workspace.files().len())
+        .filter(|&other| other != file)
+        .flat_map(|other| workspace.references(other))
+        .filter_map(|reference| {
+            let symbol = workspace.resolve_reference(&reference).ok()?;
+            let ident = workspace.node(symbol).and_then(editor::ident_of)?;
+            (symbol.file == file).then(|| editor::span_of(source, ident))?
+        })
+        .collect()
+}
+
+// Fixes what can be fixed in the files, leaving the sources fixed, and returns how many problems
+// were fixed in each. The files are resolved together, so that nothing other files refer to is
+// renamed, and must still compile together once fixed.
+fn fix(
+    files: &[(String, PathBuf)],
+    sources: &mut [String],
+    config: &Config,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    let paths: Vec<PathBuf> = files.iter().map(|(_, path)| path.clone()).collect();
+    let (loaded, _) = super::load_sources(&[], &paths)?;
+    let names = paths
+        .iter()
+        .map(|path| {
+            loaded
+                .file_by_path(path)
+                .map(|file| file.name.clone())
+                .ok_or_else(|| format!("{} was not loaded", path.display()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tree = |sources: &[String]| -> SourceTree {
+        let mut tree = loaded.clone();
+        for (name, source) in names.iter().zip(sources) {
+            tree.add(name.as_str(), source.as_str());
+        }
+        tree
+    };
+
+    let mut fixed = vec![0; files.len()];
+
+    for _ in 0..FIX_PASSES {
+        let tree = tree(sources);
+        let workspace = Workspace::new(&tree)?;
+        let mut count = 0;
+
+        for (index, (name, path)) in files.iter().enumerate() {
+            let file = workspace
+                .file_index(&names[index])
+                .ok_or_else(|| format!("{} was not loaded", path.display()))?;
+            let protected = referenced_elsewhere(&workspace, file);
+
+            let diagnostics: Vec<Diagnostic> = lint_file(name, path, &sources[index], config)?
+                .into_iter()
+                .filter(|diagnostic| !protected.contains(&diagnostic.range))
+                .collect();
+            let (result, fixes) = lint::fix(&sources[index], &diagnostics)
+                .map_err(|error| format!("{}: can't fix: {}", path.display(), error))?;
+
+            if fixes > 0 {
+                sources[index] = result;
+                fixed[index] += fixes;
+                count += fixes;
+            }
+        }
+
+        if count == 0 {
+            break;
+        }
+    }
+
+    resolver::compile(&Workspace::new(&tree(sources))?)
+        .map_err(|error| format!("the fixed files don't compile: {}", error))?;
+
+    Ok(fixed)
+}
+
+fn config(args: &Args) -> Result<Config, Box<dyn std::error::Error>> {
+    let mut config = match &args.config {
+        Some(path) => Config::parse(&std::fs::read_to_string(path)?)
+            .map_err(|error| format!("{}: {}", path.display(), error))?,
+        None => Config::preset(match args.preset {
+            Rules::Default => Preset::Default,
+            Rules::Strict => Preset::Strict,
+        }),
+    };
+
+    for rule in &args.enable {
+        config.enable(rule)?;
+    }
+    for rule in &args.disable {
+        config.disable(rule)?;
+    }
+
+    Ok(config)
+}
+
+pub fn run(args: Args) -> super::Result {
+    let config = config(&args)?;
+
+    if args.list {
+        for rule in lint::RULES {
+            let level = config
+                .severity(rule.id)
+                .map_or("off", |severity| severity.name());
+            println!("{:<28} {:<8} {}", rule.id, level, rule.description);
+        }
+        return Ok(());
+    }
+
+    let files = files(&args.paths)?;
+    let mut sources = files
+        .iter()
+        .map(|(_, path)| std::fs::read_to_string(path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if args.fix {
+        let fixed = fix(&files, &mut sources, &config)?;
+
+        for (((_, path), source), fixed) in files.iter().zip(&sources).zip(fixed) {
+            if fixed > 0 {
+                std::fs::write(path, source)?;
+                match fixed {
+                    1 => eprintln!("{}: fixed 1 problem", path.display()),
+                    fixed => eprintln!("{}: fixed {} problems", path.display(), fixed),
+                }
+            }
+        }
+    }
+
+    let mut errors = 0;
+
+    for ((name, path), source) in files.iter().zip(&sources) {
+        for diagnostic in lint_file(name, path, source, &config)? {
+            let (line, column) = parser::line_column(source, diagnostic.range.start);
+            println!("{}:{}:{}: {}", path.display(), line, column, diagnostic);
+            errors += usize::from(diagnostic.severity == Severity::Error);
+        }
+    }
+
+    match errors {
+        0 => Ok(()),
+        1 => Err("1 lint error".into()),
+        count => Err(format!("{} lint errors", count).into()),
+    }
+}
@@ -0,0 +1,30 @@
+use proto_util::lint::Config;
+use proto_util::lsp::{self, Settings};
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in before the workspace root, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// Lint configuration, as for the lint command; the default preset otherwise
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let lint = match &args.config {
+        Some(path) => Config::parse(&std::fs::read_to_string(path)?)
+            .map_err(|error| format!("{}: {}", path.display(), error))?,
+        None => Config::default(),
+    };
+
+    let settings = Settings {
+        include_paths: args.include,
+        lint,
+    };
+
+    lsp::serve(settings, std::io::stdin().lock(), std::io::stdout().lock())?;
+    Ok(())
+}
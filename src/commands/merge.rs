@@ -0,0 +1,37 @@
+use proto_util::editor;
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Version of the common ancestor (%O for a git merge driver)
+    base: PathBuf,
+
+    /// Our version, overwritten with the result unless --output is given (%A)
+    ours: PathBuf,
+
+    /// Their version (%B)
+    theirs: PathBuf,
+
+    /// File to write the result to instead
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let base = std::fs::read_to_string(&args.base)?;
+    let ours = std::fs::read_to_string(&args.ours)?;
+    let theirs = std::fs::read_to_string(&args.theirs)?;
+
+    let merge = editor::merge(&base, &ours, &theirs)?;
+    std::fs::write(args.output.as_ref().unwrap_or(&args.ours), &merge.source)?;
+
+    for conflict in &merge.conflicts {
+        eprintln!("conflict: {}", conflict);
+    }
+
+    match merge.conflicts.len() {
+        0 => Ok(()),
+        1 => Err("1 conflict".into()),
+        count => Err(format!("{} conflicts", count).into()),
+    }
+}
@@ -0,0 +1,101 @@
+pub mod add_enum_value;
+pub mod add_field;
+pub mod breaking;
+pub mod codegen;
+pub mod decode;
+pub mod decompile;
+pub mod diff;
+pub mod docs;
+pub mod edit;
+pub mod edit_data;
+pub mod encode;
+pub mod graph;
+pub mod import_schema;
+pub mod inspect;
+pub mod json_schema;
+pub mod lint;
+pub mod lsp;
+pub mod merge;
+pub mod move_definition;
+pub mod openapi;
+pub mod protoc;
+pub mod remove_enum_value;
+pub mod remove_field;
+pub mod rename;
+pub mod reserve;
+mod rewrite;
+pub mod schema;
+pub mod set_option;
+
+use proto_util::resolver::SourceTree;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+pub type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+// Contents of the input file, or of stdin if there is none.
+pub fn read_input(input: &Option<PathBuf>) -> std::io::Result<Vec<u8>> {
+    match input {
+        Some(path) => std::fs::read(path),
+        None => {
+            let mut data = vec![];
+            std::io::stdin().read_to_end(&mut data)?;
+            Ok(data)
+        }
+    }
+}
+
+// .proto files in the directory and its subdirectories, sorted by path.
+pub fn proto_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    let mut files = vec![];
+
+    for path in entries {
+        if path.is_dir() {
+            files.extend(proto_files(&path)?);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "proto")
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+// Source tree of the .proto files and of the files in the directories, which are searched for
+// imports first, with what they import; and the names of the files given, sorted without
+// duplicates.
+pub fn load_sources(
+    include: &[PathBuf],
+    paths: &[PathBuf],
+) -> std::result::Result<(SourceTree, Vec<String>), Box<dyn std::error::Error>> {
+    let mut include = include.to_vec();
+    let mut files = vec![];
+
+    for path in paths {
+        match path.is_dir() {
+            true => {
+                include.insert(0, path.clone());
+                files.extend(proto_files(path)?);
+            }
+            false => files.push(path.clone()),
+        }
+    }
+
+    let mut tree = SourceTree::new(include);
+    let mut names = files
+        .iter()
+        .map(|file| tree.add_path(file))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    tree.load_imports()?;
+
+    names.sort();
+    names.dedup();
+    Ok((tree, names))
+}
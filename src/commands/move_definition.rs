@@ -0,0 +1,72 @@
+use proto_util::editor;
+use proto_util::resolver::{SourceTree, Workspace};
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// Fully-qualified name of a top-level message, enum or service, e.g. `pkg.User`
+    name: String,
+
+    /// .proto file to move the definition to, created if it doesn't exist
+    to: PathBuf,
+
+    /// .proto files whose references are updated, along with the files they import
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Keep the definition visible to the importers of the old file with `import public`
+    #[arg(long)]
+    public: bool,
+
+    /// Package of the created file, the one of the old file if omitted
+    #[arg(long, value_name = "NAME")]
+    package: Option<String>,
+}
+
+// Header of a new file, with the syntax and the package of the file the definition is in.
+fn header(args: &Args) -> Result<String, Box<dyn std::error::Error>> {
+    let tree = SourceTree::load(args.include.clone(), &args.files)?;
+    let workspace = Workspace::new(&tree)?;
+    let symbol = workspace
+        .symbol(&args.name)
+        .ok_or_else(|| format!("{} is not defined", args.name))?;
+
+    let file = workspace.file(symbol.file);
+    let package = args.package.as_deref().unwrap_or(file.package());
+
+    Ok(match package.is_empty() {
+        true => format!("syntax = \"{}\";\n", file.syntax()),
+        false => format!("syntax = \"{}\";\n\npackage {};\n", file.syntax(), package),
+    })
+}
+
+fn move_to(args: &Args, to: &Path) -> super::Result {
+    let files = [args.files.as_slice(), &[to.to_path_buf()]].concat();
+    let tree = SourceTree::load(args.include.clone(), &files)?;
+    let workspace = Workspace::new(&tree)?;
+
+    let target = tree
+        .file_by_path(to)
+        .and_then(|file| workspace.file_index(&file.name))
+        .ok_or_else(|| format!("{} was not loaded", to.display()))?;
+    let changes = editor::move_definition(&workspace, &args.name, target, args.public)?;
+
+    super::rewrite::write_changes(&tree, changes)
+}
+
+pub fn run(args: Args) -> super::Result {
+    if args.to.exists() {
+        return move_to(&args, &args.to);
+    }
+
+    std::fs::write(&args.to, header(&args)?)?;
+
+    // the created file goes away if the definition can't be moved
+    move_to(&args, &args.to).inspect_err(|_| {
+        let _ = std::fs::remove_file(&args.to);
+    })
+}
@@ -0,0 +1,67 @@
+use proto_util::descriptor::{Pool, names};
+use proto_util::jsonschema::{self, Info};
+use proto_util::resolver::{self, Workspace};
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// .proto files or directories of them
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Fully-qualified service to export, may be repeated; all of those of the files if omitted
+    #[arg(short, long = "service", value_name = "NAME")]
+    services: Vec<String>,
+
+    /// Title of the API
+    #[arg(long, default_value = "API")]
+    title: String,
+
+    /// Version of the API
+    #[arg(long, default_value = "1.0.0")]
+    version: String,
+
+    /// File to write the document to, stdout is used if omitted
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let (tree, names) = super::load_sources(&args.include, &args.paths)?;
+
+    let workspace = Workspace::new(&tree)?;
+    let set = resolver::compile(&workspace)?;
+
+    // in the order they are declared
+    let services = match args.services.is_empty() {
+        true => set
+            .file
+            .iter()
+            .filter(|file| names.iter().any(|name| name == file.name()))
+            .flat_map(|file| {
+                file.service
+                    .iter()
+                    .map(|service| names::join(file.package(), service.name()))
+            })
+            .collect(),
+        false => args.services,
+    };
+
+    let info = Info {
+        title: args.title,
+        version: args.version,
+    };
+    let document = jsonschema::openapi(&Pool::new(&set), &services, &info)?;
+
+    let document = jsonschema::print(&document);
+    match &args.output {
+        Some(path) => std::fs::write(path, document + "\n")?,
+        None => println!("{}", document),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use proto_util::plugin::{self, Output};
+use proto_util::resolver::{SourceTree, Workspace};
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// protoc arguments: -IDIR, --plugin=[protoc-gen-NAME=]PATH, --NAME_out=[PARAM:]DIR,
+    /// --NAME_opt=PARAM and .proto files
+    #[arg(
+        required = true,
+        allow_hyphen_values = true,
+        trailing_var_arg = true,
+        value_name = "ARGS"
+    )]
+    arguments: Vec<String>,
+}
+
+struct Generator {
+    name: String,
+    output: PathBuf,
+    parameters: Vec<String>,
+}
+
+#[derive(Default)]
+struct Invocation {
+    include: Vec<PathBuf>,
+    plugins: Vec<(String, PathBuf)>,
+    generators: Vec<Generator>,
+    options: Vec<(String, String)>,
+    files: Vec<PathBuf>,
+}
+
+// Flags taking a value accept it in the same argument, after `=` or right after `-I`, or in the
+// next one.
+fn parse(arguments: &[String]) -> Result<Invocation, String> {
+    let mut invocation = Invocation::default();
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        if !argument.starts_with('-') {
+            invocation.files.push(PathBuf::from(argument));
+            continue;
+        }
+
+        let (flag, value) = match argument.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None if argument.starts_with("-I") && argument.len() > 2 => {
+                ("-I", Some(argument[2..].to_string()))
+            }
+            None => (argument.as_str(), None),
+        };
+        let mut value = || match value.clone() {
+            Some(value) => Ok(value),
+            None => arguments
+                .next()
+                .cloned()
+                .ok_or_else(|| format!("missing value for {}", flag)),
+        };
+
+        if flag == "-I" || flag == "--proto_path" {
+            invocation.include.push(PathBuf::from(value()?));
+        } else if flag == "--plugin" {
+            let value = value()?;
+            let (name, path) = match value.split_once('=') {
+                Some((name, path)) => (name.to_string(), PathBuf::from(path)),
+                None => {
+                    let path = PathBuf::from(&value);
+                    let name = path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    (name, path)
+                }
+            };
+            let name = name
+                .strip_prefix("protoc-gen-")
+                .unwrap_or(&name)
+                .to_string();
+            invocation.plugins.push((name, path));
+        } else if let Some(name) = flag
+            .strip_prefix("--")
+            .and_then(|flag| flag.strip_suffix("_out"))
+        {
+            let value = value()?;
+            let (parameter, output) = value.split_once(':').unwrap_or(("", &value));
+            invocation.generators.push(Generator {
+                name: name.to_string(),
+                output: PathBuf::from(output),
+                parameters: vec![parameter.to_string()],
+            });
+        } else if let Some(name) = flag
+            .strip_prefix("--")
+            .and_then(|flag| flag.strip_suffix("_opt"))
+        {
+            invocation.options.push((name.to_string(), value()?));
+        } else {
+            return Err(format!("unknown flag {}", flag));
+        }
+    }
+
+    // options apply to the generator of the same name wherever they are given
+    for generator in &mut invocation.generators {
+        generator.parameters.extend(
+            invocation
+                .options
+                .iter()
+                .filter(|(name, _)| *name == generator.name)
+                .map(|(_, parameter)| parameter.clone()),
+        );
+        generator
+            .parameters
+            .retain(|parameter| !parameter.is_empty());
+    }
+
+    if invocation.include.is_empty() {
+        invocation.include.push(PathBuf::from("."));
+    }
+
+    Ok(invocation)
+}
+
+pub fn run(args: Args) -> super::Result {
+    let invocation = parse(&args.arguments)?;
+    if invocation.files.is_empty() {
+        return Err("no .proto files given".into());
+    }
+    if invocation.generators.is_empty() {
+        return Err("no output flag given, e.g. --go_out=DIR".into());
+    }
+
+    let mut tree = SourceTree::new(invocation.include.clone());
+    let names = invocation
+        .files
+        .iter()
+        .map(|file| tree.add_path(file))
+        .collect::<Result<Vec<_>, _>>()?;
+    tree.load_imports()?;
+
+    let workspace = Workspace::new(&tree)?;
+    let mut files: Vec<_> = names
+        .iter()
+        .filter_map(|name| workspace.file_index(name))
+        .collect();
+    files.dedup();
+    let request = plugin::request(&workspace, &files)?;
+
+    // nothing is written unless every plugin succeeds; plugins writing to the same directory can
+    // fill the insertion points of each other
+    let mut outputs: Vec<(PathBuf, Output)> = vec![];
+
+    for generator in &invocation.generators {
+        let program = invocation
+            .plugins
+            .iter()
+            .rfind(|(name, _)| *name == generator.name)
+            .map(|(_, path)| path.clone())
+            .unwrap_or_else(|| PathBuf::from(format!("protoc-gen-{}", generator.name)));
+
+        let mut request = request.clone();
+        if !generator.parameters.is_empty() {
+            request.parameter = Some(generator.parameters.join(","));
+        }
+        let response = plugin::run(&program, &request)?;
+
+        let index = match outputs.iter().position(|(dir, _)| *dir == generator.output) {
+            Some(index) => index,
+            None => {
+                outputs.push((generator.output.clone(), Output::default()));
+                outputs.len() - 1
+            }
+        };
+        outputs[index].1.add(&response)?;
+    }
+
+    for (dir, output) in &outputs {
+        output.write(dir)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use proto_util::editor;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    file: super::rewrite::SchemaFile,
+
+    /// Name of the enum, e.g. `pkg.Status`
+    r#enum: String,
+
+    /// Name of the value to remove
+    name: String,
+
+    /// Don't reserve the number and the name of the value, letting them be reused
+    #[arg(long)]
+    no_reserve: bool,
+}
+
+pub fn run(args: Args) -> super::Result {
+    args.file.rewrite(|source| match args.no_reserve {
+        true => editor::remove_enum_value(source, &args.r#enum, &args.name),
+        false => editor::retire(source, &format!("{}.{}", args.r#enum, args.name)),
+    })
+}
@@ -0,0 +1,24 @@
+use proto_util::editor;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    file: super::rewrite::SchemaFile,
+
+    /// Name of the message, e.g. `pkg.User`
+    message: String,
+
+    /// Name of the field to remove
+    field: String,
+
+    /// Don't reserve the number and the name of the field, letting them be reused
+    #[arg(long)]
+    no_reserve: bool,
+}
+
+pub fn run(args: Args) -> super::Result {
+    args.file.rewrite(|source| match args.no_reserve {
+        true => editor::remove_field(source, &args.message, &args.field),
+        false => editor::retire(source, &format!("{}.{}", args.message, args.field)),
+    })
+}
@@ -0,0 +1,28 @@
+use proto_util::editor;
+use proto_util::resolver::{SourceTree, Workspace};
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// Fully-qualified name of the definition, e.g. `pkg.User` or `pkg.User.email`
+    name: String,
+
+    /// New identifier of the definition
+    new_name: String,
+
+    /// .proto files whose references are updated, along with the files they import
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+pub fn run(args: Args) -> super::Result {
+    let tree = SourceTree::load(args.include, &args.files)?;
+    let workspace = Workspace::new(&tree)?;
+    let changes = editor::rename_symbol(&workspace, &args.name, &args.new_name)?;
+
+    super::rewrite::write_changes(&tree, changes)
+}
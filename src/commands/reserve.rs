@@ -0,0 +1,48 @@
+use proto_util::editor;
+use proto_util::parser::ast;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    file: super::rewrite::SchemaFile,
+
+    /// Name of the message or enum, e.g. `pkg.User`
+    target: String,
+
+    /// Numbers, ranges such as `8-10` or `100-max`, and names to reserve
+    #[arg(required = true)]
+    items: Vec<String>,
+}
+
+// A number or a range of numbers, or `None` for a name.
+fn parse_range(item: &str) -> Result<Option<ast::Range>, String> {
+    if !item.starts_with(|ch: char| ch.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    let number = |text: &str| {
+        text.parse::<i64>()
+            .map_err(|_| format!("{} is not a valid range", item))
+    };
+
+    match item.split_once('-') {
+        None => Ok(Some(number(item)?.into())),
+        Some((start, "max")) => Ok(Some((number(start)?, ()).into())),
+        Some((start, end)) => Ok(Some((number(start)?..number(end)?).into())),
+    }
+}
+
+pub fn run(args: Args) -> super::Result {
+    let mut ranges = vec![];
+    let mut names = vec![];
+
+    for item in &args.items {
+        match parse_range(item)? {
+            Some(range) => ranges.push(range),
+            None => names.push(item.as_str()),
+        }
+    }
+
+    args.file
+        .rewrite(|source| editor::reserve(source, &args.target, &ranges, &names))
+}
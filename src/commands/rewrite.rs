@@ -0,0 +1,85 @@
+use proto_util::editor::{self, Edit, EditError, FileEdits};
+use proto_util::resolver::{self, SourceTree, Workspace};
+use std::path::{Path, PathBuf};
+
+// The .proto file changed by a scripted edit and where the result goes.
+#[derive(clap::Args)]
+pub struct SchemaFile {
+    /// .proto file to change
+    file: PathBuf,
+
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// File to write the result to instead of changing the file in place
+    #[arg(short, long, conflicts_with = "stdout")]
+    output: Option<PathBuf>,
+
+    /// Print the result instead of changing the file in place
+    #[arg(long)]
+    stdout: bool,
+}
+
+impl SchemaFile {
+    // Applies the edits made by the change, refusing the ones that leave the file unparsable or
+    // make it fail to compile with its imports.
+    pub fn rewrite(
+        &self,
+        change: impl FnOnce(&str) -> Result<Vec<Edit>, EditError>,
+    ) -> super::Result {
+        let source = std::fs::read_to_string(&self.file)?;
+        let result = editor::edit(&source, &change(&source)?)?;
+
+        let mut tree = SourceTree::new(self.include.clone());
+        let name = tree.add_path(&self.file)?;
+        tree.add(name, result.as_str());
+        tree.load_imports()?;
+        resolver::compile(&Workspace::new(&tree)?)?;
+
+        match (&self.output, self.stdout) {
+            (_, true) => print!("{}", result),
+            (Some(path), _) => std::fs::write(path, result)?,
+            (None, _) => std::fs::write(&self.file, result)?,
+        }
+
+        Ok(())
+    }
+}
+
+fn temporary(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+// Writes the files of the tree changed by a refactoring. They go to temporary files first, which
+// replace the files only once every one of them is written.
+pub fn write_changes(tree: &SourceTree, changes: Vec<FileEdits>) -> super::Result {
+    let mut results = vec![];
+
+    for change in changes {
+        let file = &tree.files()[change.file];
+        let path = file
+            .path
+            .as_ref()
+            .ok_or_else(|| format!("{} is bundled and can't be changed", file.name))?;
+
+        results.push((path, editor::apply(&file.source, &change.edits)));
+    }
+
+    for (index, (path, source)) in results.iter().enumerate() {
+        if let Err(error) = std::fs::write(temporary(path), source) {
+            for (path, _) in &results[..=index] {
+                let _ = std::fs::remove_file(temporary(path));
+            }
+            return Err(error.into());
+        }
+    }
+
+    for (path, _) in results {
+        std::fs::rename(temporary(path), path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use proto_util::descriptor::{FileDescriptorSet, Message, Pool};
+use proto_util::resolver::{self, SourceTree, Workspace};
+use std::path::PathBuf;
+
+// Where message types come from, shared by the commands working with message data.
+#[derive(clap::Args)]
+pub struct Schema {
+    /// Directory to search for imports in, may be repeated
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// .proto file defining the types, may be repeated
+    #[arg(long = "proto", value_name = "FILE")]
+    protos: Vec<PathBuf>,
+
+    /// FileDescriptorSet defining the types, instead of .proto files
+    #[arg(long, value_name = "FILE", conflicts_with = "protos")]
+    descriptor_set: Option<PathBuf>,
+}
+
+impl Schema {
+    pub fn descriptors(&self) -> Result<FileDescriptorSet, Box<dyn std::error::Error>> {
+        if let Some(path) = &self.descriptor_set {
+            return Ok(FileDescriptorSet::decode(&std::fs::read(path)?)?);
+        }
+
+        if self.protos.is_empty() {
+            return Err("either --proto or --descriptor-set is required".into());
+        }
+
+        let tree = SourceTree::load(self.include.clone(), &self.protos)?;
+        let workspace = Workspace::new(&tree)?;
+
+        Ok(resolver::compile(&workspace)?)
+    }
+
+    pub fn pool(&self) -> Result<Pool, Box<dyn std::error::Error>> {
+        Ok(Pool::new(&self.descriptors()?))
+    }
+}
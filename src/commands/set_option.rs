@@ -0,0 +1,23 @@
+use proto_util::editor;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    file: super::rewrite::SchemaFile,
+
+    /// Definition to set the option of, e.g. `pkg.User.email`; the file itself if omitted
+    #[arg(short, long, value_name = "NAME")]
+    target: Option<String>,
+
+    /// Name of the option, e.g. `deprecated` or `(my.option)`
+    key: String,
+
+    /// Value as written in a .proto file, e.g. `true` or `"text"`
+    value: String,
+}
+
+pub fn run(args: Args) -> super::Result {
+    args.file.rewrite(|source| {
+        editor::set_option(source, args.target.as_deref(), &args.key, &args.value)
+    })
+}
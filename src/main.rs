@@ -1,3 +1,109 @@
-fn main() {
-    todo!()
+mod commands;
+mod tui;
+
+use clap::{Parser, Subcommand};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(version, about = "CLI editor for protocol buffers")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add a value to an enum in a .proto file
+    AddEnumValue(commands::add_enum_value::Args),
+    /// Add a field to a message in a .proto file
+    AddField(commands::add_field::Args),
+    /// Report the changes between two versions of a schema that break compatibility
+    Breaking(commands::breaking::Args),
+    /// Generate Rust types with encoding, decoding and service traits from .proto files
+    Codegen(commands::codegen::Args),
+    /// Decode a binary message and print its fields
+    Decode(commands::decode::Args),
+    /// Decompile a binary FileDescriptorSet back to .proto sources
+    Decompile(commands::decompile::Args),
+    /// Show the structural differences between two versions of .proto files
+    Diff(commands::diff::Args),
+    /// Generate Markdown or HTML reference pages from .proto files
+    Docs(commands::docs::Args),
+    /// Edit a .proto file in an interactive terminal editor
+    Edit(commands::edit::Args),
+    /// Edit a binary message in an interactive terminal editor
+    EditData(commands::edit_data::Args),
+    /// Encode a message written in text format or JSON to binary
+    Encode(commands::encode::Args),
+    /// Export the references between types or the imports between files as a DOT or JSON graph
+    Graph(commands::graph::Args),
+    /// Bootstrap messages and enums from a JSON Schema or the components of an OpenAPI document
+    ImportSchema(commands::import_schema::Args),
+    /// Show the raw wire-format fields of a binary message, with or without a schema
+    Inspect(commands::inspect::Args),
+    /// Export JSON Schemas of messages and enums, as the proto3 JSON mapping writes them
+    JsonSchema(commands::json_schema::Args),
+    /// Check .proto files against style rules
+    Lint(commands::lint::Args),
+    /// Run a language server for .proto files over stdin and stdout
+    Lsp(commands::lsp::Args),
+    /// Merge two versions of a .proto file changed from a common base, e.g. as a git merge driver
+    Merge(commands::merge::Args),
+    /// Move a message, enum or service to another file and update the files using it
+    Move(commands::move_definition::Args),
+    /// Export an OpenAPI document of the methods bound to HTTP by google.api.http options
+    Openapi(commands::openapi::Args),
+    /// Run protoc plugins on .proto files, taking the same arguments as protoc
+    Protoc(commands::protoc::Args),
+    /// Remove a value from an enum in a .proto file
+    RemoveEnumValue(commands::remove_enum_value::Args),
+    /// Remove a field from a message in a .proto file
+    RemoveField(commands::remove_field::Args),
+    /// Rename a definition and update the references to it in every file
+    Rename(commands::rename::Args),
+    /// Reserve field or enum value numbers and names in a .proto file
+    Reserve(commands::reserve::Args),
+    /// Set an option of a .proto file or of a definition in it
+    SetOption(commands::set_option::Args),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::AddEnumValue(args) => commands::add_enum_value::run(args),
+        Command::AddField(args) => commands::add_field::run(args),
+        Command::Breaking(args) => commands::breaking::run(args),
+        Command::Codegen(args) => commands::codegen::run(args),
+        Command::Decode(args) => commands::decode::run(args),
+        Command::Decompile(args) => commands::decompile::run(args),
+        Command::Diff(args) => commands::diff::run(args),
+        Command::Docs(args) => commands::docs::run(args),
+        Command::Edit(args) => commands::edit::run(args),
+        Command::EditData(args) => commands::edit_data::run(args),
+        Command::Encode(args) => commands::encode::run(args),
+        Command::Graph(args) => commands::graph::run(args),
+        Command::ImportSchema(args) => commands::import_schema::run(args),
+        Command::Inspect(args) => commands::inspect::run(args),
+        Command::JsonSchema(args) => commands::json_schema::run(args),
+        Command::Lint(args) => commands::lint::run(args),
+        Command::Lsp(args) => commands::lsp::run(args),
+        Command::Merge(args) => commands::merge::run(args),
+        Command::Move(args) => commands::move_definition::run(args),
+        Command::Openapi(args) => commands::openapi::run(args),
+        Command::Protoc(args) => commands::protoc::run(args),
+        Command::RemoveEnumValue(args) => commands::remove_enum_value::run(args),
+        Command::RemoveField(args) => commands::remove_field::run(args),
+        Command::Rename(args) => commands::rename::run(args),
+        Command::Reserve(args) => commands::reserve::run(args),
+        Command::SetOption(args) => commands::set_option::run(args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,744 @@
+// Editor of a binary message. The decoded message keeps the order, packing and unknown fields of
+// the wire data, so saving re-encodes untouched fields the way they were.
+
+use super::{Form, FormEvent, Screen};
+use proto_util::data::edit::{self, Change, Step};
+use proto_util::data::{self, DynamicMessage, FieldValue, Value};
+use proto_util::descriptor::{FieldInfo, FieldType, Pool};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    // A field of the message at `path`, one element of it if `index` is set.
+    Field {
+        path: Vec<Step>,
+        number: u32,
+        index: Option<usize>,
+    },
+    OneOf {
+        path: Vec<Step>,
+        oneof: usize,
+    },
+    // Position in the fields of the message at `path`.
+    Unknown {
+        path: Vec<Step>,
+        position: usize,
+    },
+}
+
+struct Row {
+    target: Target,
+    // Path of the nested message or the repeated field, for collapsing.
+    key: Option<Vec<Step>>,
+    depth: usize,
+    label: String,
+    set: bool,
+}
+
+enum Mode {
+    Browse,
+    OneOf {
+        path: Vec<Step>,
+        oneof: usize,
+        members: Vec<FieldInfo>,
+        selected: usize,
+    },
+    // Value of a field being set, or added to a repeated field.
+    Value {
+        form: Form,
+        target: Target,
+        field: Box<FieldInfo>,
+        push: bool,
+    },
+    Quit,
+}
+
+pub struct DataEditor {
+    pool: Pool,
+    path: PathBuf,
+    message: DynamicMessage,
+    saved: DynamicMessage,
+    undo: Vec<DynamicMessage>,
+    collapsed: HashSet<Vec<Step>>,
+    list: ListState,
+    mode: Mode,
+    status: String,
+}
+
+impl DataEditor {
+    // A missing file is a new, empty message.
+    pub fn open(
+        pool: Pool,
+        type_name: &str,
+        path: &Path,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if pool.message(type_name).is_none() {
+            return Err(format!("Unknown message type {}", type_name).into());
+        }
+
+        let message = edit::load(&pool, type_name, path)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+
+        Ok(Self {
+            pool,
+            path: path.to_path_buf(),
+            saved: message.clone(),
+            message,
+            undo: vec![],
+            collapsed: HashSet::new(),
+            list: ListState::default().with_selected(Some(0)),
+            mode: Mode::Browse,
+            status: String::new(),
+        })
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = vec![];
+        self.message_rows(&self.message, &[], 0, &mut rows);
+        rows
+    }
+
+    fn message_rows(
+        &self,
+        message: &DynamicMessage,
+        path: &[Step],
+        depth: usize,
+        rows: &mut Vec<Row>,
+    ) {
+        let Some(info) = self.pool.message(&message.type_name) else {
+            self.unknown_rows(message, path, depth, rows);
+            return;
+        };
+
+        let mut oneofs = HashSet::new();
+
+        for field in &info.fields {
+            let Some(oneof) = field.oneof.filter(|oneof| !info.oneofs[*oneof].synthetic) else {
+                self.field_rows(message, field, path, depth, rows);
+                continue;
+            };
+
+            if !oneofs.insert(oneof) {
+                continue;
+            }
+
+            let case = message
+                .which_oneof(&self.pool, oneof)
+                .and_then(|number| info.field(number));
+
+            rows.push(Row {
+                target: Target::OneOf {
+                    path: path.to_vec(),
+                    oneof,
+                },
+                key: None,
+                depth,
+                label: format!(
+                    "oneof {} = {}",
+                    info.oneofs[oneof].name,
+                    case.map_or("(not set)", |case| case.name.as_str())
+                ),
+                set: case.is_some(),
+            });
+
+            if let Some(case) = case {
+                self.field_rows(message, case, path, depth + 1, rows);
+            }
+        }
+
+        let mut extensions = HashSet::new();
+
+        for field in &message.fields {
+            if info.field(field.number).is_none()
+                && extensions.insert(field.number)
+                && let Some(extension) = self
+                    .pool
+                    .extension_by_number(&message.type_name, field.number)
+            {
+                self.field_rows(message, extension, path, depth, rows);
+            }
+        }
+
+        self.unknown_rows(message, path, depth, rows);
+    }
+
+    fn unknown_rows(
+        &self,
+        message: &DynamicMessage,
+        path: &[Step],
+        depth: usize,
+        rows: &mut Vec<Row>,
+    ) {
+        for (position, field) in message.fields.iter().enumerate() {
+            if let FieldValue::Unknown(value) = &field.value {
+                rows.push(Row {
+                    target: Target::Unknown {
+                        path: path.to_vec(),
+                        position,
+                    },
+                    key: None,
+                    depth,
+                    label: format!(
+                        "({}): unknown {:?}, {} bytes",
+                        field.number,
+                        value.wire_type,
+                        value.data.len()
+                    ),
+                    set: true,
+                });
+            }
+        }
+    }
+
+    fn field_rows(
+        &self,
+        message: &DynamicMessage,
+        field: &FieldInfo,
+        path: &[Step],
+        depth: usize,
+        rows: &mut Vec<Row>,
+    ) {
+        let name = match field.is_extension() {
+            true => format!("[{}] ({})", field.full_name, field.number),
+            false => format!("{} ({})", field.name, field.number),
+        };
+        let values: Vec<&Value> = message.get(field.number).collect();
+        let target = |index| Target::Field {
+            path: path.to_vec(),
+            number: field.number,
+            index,
+        };
+
+        if field.is_repeated() {
+            let key = [path, &[(field.number, usize::MAX)]].concat();
+            let expanded = !self.collapsed.contains(&key);
+
+            rows.push(Row {
+                target: target(None),
+                key: Some(key),
+                depth,
+                label: format!("{}: [{}]", name, values.len()),
+                set: !values.is_empty(),
+            });
+
+            if expanded {
+                for (index, value) in values.into_iter().enumerate() {
+                    self.value_rows(
+                        field,
+                        value,
+                        format!("[{}]", index),
+                        target(Some(index)),
+                        path,
+                        depth + 1,
+                        rows,
+                    );
+                }
+            }
+
+            return;
+        }
+
+        match values.last() {
+            Some(value) => self.value_rows(field, value, name, target(None), path, depth, rows),
+            None => rows.push(Row {
+                target: target(None),
+                key: None,
+                depth,
+                label: format!("{}: (not set)", name),
+                set: false,
+            }),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn value_rows(
+        &self,
+        field: &FieldInfo,
+        value: &Value,
+        name: String,
+        target: Target,
+        path: &[Step],
+        depth: usize,
+        rows: &mut Vec<Row>,
+    ) {
+        let Value::Message(nested) = value else {
+            rows.push(Row {
+                target,
+                key: None,
+                depth,
+                label: format!(
+                    "{}: {}",
+                    name,
+                    data::print::scalar(&self.pool, field, value)
+                ),
+                set: true,
+            });
+            return;
+        };
+
+        // the last occurrence of a singular message is the one shown
+        let index = match &target {
+            Target::Field {
+                index: Some(index), ..
+            } => *index,
+            _ => self.message_at(path).map_or(0, |message| {
+                message.get(field.number).count().saturating_sub(1)
+            }),
+        };
+        let key = [path, &[(field.number, index)]].concat();
+        let expanded = !self.collapsed.contains(&key);
+
+        rows.push(Row {
+            target,
+            key: Some(key.clone()),
+            depth,
+            label: match expanded {
+                true => name,
+                false => format!("{} {{…}}", name),
+            },
+            set: true,
+        });
+
+        if expanded {
+            self.message_rows(nested, &key, depth + 1, rows);
+        }
+    }
+
+    fn message_at(&self, path: &[Step]) -> Option<&DynamicMessage> {
+        edit::message_at(&self.message, path)
+    }
+
+    fn field_info(&self, path: &[Step], number: u32) -> Option<FieldInfo> {
+        let message = self.message_at(path)?;
+        data::find_field(&self.pool, &message.type_name, number).cloned()
+    }
+
+    // Makes the change to the message at the path, keeping the previous state for undo.
+    fn change(&mut self, path: &[Step], change: Change) {
+        let previous = self.message.clone();
+
+        match edit::apply(&self.pool, &mut self.message, path, change) {
+            Ok(()) => {
+                self.undo.push(previous);
+                self.status.clear();
+            }
+            Err(error) => self.status = error.to_string(),
+        }
+
+        self.clamp_selection();
+    }
+
+    fn clamp_selection(&mut self) {
+        let count = self.rows().len();
+        let index = self.list.selected().unwrap_or_default();
+
+        self.list.select(Some(index.min(count.saturating_sub(1))));
+    }
+
+    fn value_form(&mut self, target: Target, field: FieldInfo, value: Option<&Value>, push: bool) {
+        let default = Value::default_of(&self.pool, &field);
+        let value = value.unwrap_or(&default);
+        let title = format!("{} ({})", field.name, field.number);
+
+        let form = match field.r#type {
+            FieldType::Enum => {
+                let mut names: Vec<String> = field
+                    .type_name
+                    .as_deref()
+                    .and_then(|name| self.pool.r#enum(name))
+                    .map(|r#enum| {
+                        r#enum
+                            .values
+                            .iter()
+                            .map(|value| value.name.clone())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let current = data::print::scalar(&self.pool, &field, value);
+
+                // numbers without a name are kept as a choice
+                if !names.contains(&current) {
+                    names.push(current.clone());
+                }
+
+                let names: Vec<&str> = names.iter().map(String::as_str).collect();
+                Form::new(title).choice("Value", &names, &current)
+            }
+            FieldType::Bool => Form::new(title).choice(
+                "Value",
+                &["false", "true"],
+                &data::print::scalar(&self.pool, &field, value),
+            ),
+            _ => Form::new(title).text("Value", data::print::scalar(&self.pool, &field, value)),
+        };
+
+        self.mode = Mode::Value {
+            form,
+            target,
+            field: Box::new(field),
+            push,
+        };
+    }
+
+    // Edits the value of the selected row: scalars in a form, oneofs by choosing the case.
+    fn edit(&mut self, row: &Row) {
+        match &row.target {
+            Target::Field {
+                path,
+                number,
+                index,
+            } => {
+                let Some(field) = self.field_info(path, *number) else {
+                    return;
+                };
+
+                if field.is_repeated() && index.is_none() {
+                    self.toggle(row);
+                    return;
+                }
+
+                let value = self.message_at(path).and_then(|message| match index {
+                    Some(index) => message.get(*number).nth(*index),
+                    None => message.get_single(*number),
+                });
+
+                match (value, field.r#type) {
+                    (Some(Value::Message(_)), _) => self.toggle(row),
+                    (None, FieldType::Message | FieldType::Group) => {
+                        let value = Value::default_of(&self.pool, &field);
+                        let change = Change::Set {
+                            number: field.number,
+                            index: None,
+                            value,
+                        };
+                        self.change(path, change);
+                    }
+                    (value, _) => {
+                        let value = value.cloned();
+                        self.value_form(row.target.clone(), field, value.as_ref(), false);
+                    }
+                }
+            }
+            Target::OneOf { path, oneof } => {
+                let members: Vec<FieldInfo> = self
+                    .message_at(path)
+                    .and_then(|message| self.pool.message(&message.type_name))
+                    .map(|info| info.oneof_fields(*oneof).cloned().collect())
+                    .unwrap_or_default();
+                let selected = self
+                    .message_at(path)
+                    .and_then(|message| message.which_oneof(&self.pool, *oneof))
+                    .and_then(|number| members.iter().position(|member| member.number == number))
+                    .map_or(0, |position| position + 1);
+
+                self.mode = Mode::OneOf {
+                    path: path.clone(),
+                    oneof: *oneof,
+                    members,
+                    selected,
+                };
+            }
+            Target::Unknown { .. } => {
+                self.status = "Unknown fields can only be deleted".to_string();
+            }
+        }
+    }
+
+    // Adds an element to the repeated field of the row, or the field the element belongs to.
+    fn add(&mut self, row: &Row) {
+        let Target::Field { path, number, .. } = &row.target else {
+            return;
+        };
+        let Some(field) = self
+            .field_info(path, *number)
+            .filter(FieldInfo::is_repeated)
+        else {
+            self.status = "Elements can only be added to repeated fields".to_string();
+            return;
+        };
+
+        if let Some(key) = &row.key {
+            self.collapsed.remove(key);
+        }
+
+        let target = Target::Field {
+            path: path.clone(),
+            number: *number,
+            index: None,
+        };
+
+        match field.r#type {
+            FieldType::Message | FieldType::Group => {
+                let value = Value::default_of(&self.pool, &field);
+                self.change(
+                    path,
+                    Change::Push {
+                        number: field.number,
+                        value,
+                    },
+                );
+            }
+            _ => self.value_form(target, field, None, true),
+        }
+    }
+
+    fn delete(&mut self, row: &Row) {
+        match row.target.clone() {
+            Target::Field {
+                path,
+                number,
+                index,
+            } => self.change(&path, Change::Delete { number, index }),
+            Target::OneOf { path, oneof } => {
+                self.change(&path, Change::OneOf { oneof, case: None })
+            }
+            Target::Unknown { path, position } => self.change(&path, Change::Remove(position)),
+        }
+    }
+
+    fn toggle(&mut self, row: &Row) {
+        if let Some(key) = &row.key
+            && !self.collapsed.remove(key)
+        {
+            self.collapsed.insert(key.clone());
+        }
+    }
+
+    fn save(&mut self) {
+        match edit::save(&self.pool, &self.message, &self.path) {
+            Ok(()) => {
+                self.saved = self.message.clone();
+                self.status = format!("Saved {}", self.path.display());
+            }
+            Err(error) => self.status = format!("{}: {}", self.path.display(), error),
+        }
+    }
+
+    fn browse(&mut self, key: KeyEvent) -> bool {
+        let mut rows = self.rows();
+        let selected = self.list.selected().unwrap_or_default();
+        let row = (selected < rows.len()).then(|| rows.swap_remove(selected));
+
+        match (key.code, row) {
+            (KeyCode::Up | KeyCode::Char('k'), _) => self.list.select_previous(),
+            (KeyCode::Down | KeyCode::Char('j'), _) => {
+                self.list.select(Some((selected + 1).min(rows.len())))
+            }
+            (KeyCode::Left | KeyCode::Char('h'), Some(row)) => {
+                if let Some(key) = row.key {
+                    self.collapsed.insert(key);
+                }
+            }
+            (KeyCode::Right | KeyCode::Char('l'), Some(row)) => {
+                if let Some(key) = &row.key {
+                    self.collapsed.remove(key);
+                }
+            }
+            (KeyCode::Enter | KeyCode::Char('e'), Some(row)) => self.edit(&row),
+            (KeyCode::Char(' '), Some(row)) => self.toggle(&row),
+            (KeyCode::Char('a'), Some(row)) => self.add(&row),
+            (KeyCode::Char('d') | KeyCode::Delete, Some(row)) => self.delete(&row),
+            (KeyCode::Char('u'), _) => match self.undo.pop() {
+                Some(message) => {
+                    self.message = message;
+                    self.status = "Undone".to_string();
+                    self.clamp_selection();
+                }
+                None => self.status = "Nothing to undo".to_string(),
+            },
+            (KeyCode::Char('s'), _) => self.save(),
+            (KeyCode::Char('q') | KeyCode::Esc, _) => match self.message == self.saved {
+                true => return false,
+                false => self.mode = Mode::Quit,
+            },
+            _ => {}
+        }
+
+        true
+    }
+}
+
+impl Screen for DataEditor {
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = self.rows();
+        let [tree_area, status_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+
+        let items = rows.iter().map(|row| {
+            let text = format!("{}{}", "  ".repeat(row.depth), row.label);
+
+            match row.set {
+                true => ListItem::new(text),
+                false => ListItem::new(text).style(Style::default().fg(Color::DarkGray)),
+            }
+        });
+        let dirty = if self.message == self.saved {
+            ""
+        } else {
+            " [+]"
+        };
+
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(format!(
+                    " {} ({}){} ",
+                    self.path.display(),
+                    self.message.type_name,
+                    dirty
+                )))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            tree_area,
+            &mut self.list,
+        );
+
+        let status = match self.status.is_empty() {
+            true => Line::styled(
+                "enter: edit  space: fold  a: add element  d: delete  u: undo  s: save  q: quit",
+                Style::default().fg(Color::DarkGray),
+            ),
+            false => Line::from(self.status.as_str()),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+
+        match &self.mode {
+            Mode::Browse => {}
+            Mode::OneOf {
+                members, selected, ..
+            } => {
+                let items = std::iter::once("(not set)")
+                    .chain(members.iter().map(|member| member.name.as_str()))
+                    .collect::<Vec<_>>();
+                let width = items
+                    .iter()
+                    .map(|item| item.len())
+                    .max()
+                    .unwrap_or_default() as u16;
+                let area = super::centered(frame.area(), width.max(20) + 4, items.len() as u16 + 2);
+
+                frame.render_widget(Clear, area);
+                frame.render_stateful_widget(
+                    List::new(items)
+                        .block(Block::bordered().title(" Case "))
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+                    area,
+                    &mut ListState::default().with_selected(Some(*selected)),
+                );
+            }
+            Mode::Value { form, .. } => form.draw(frame),
+            Mode::Quit => super::confirm(frame, "Quit without saving the changes?"),
+        }
+    }
+
+    fn key(&mut self, key: KeyEvent) -> bool {
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => return self.browse(key),
+            Mode::OneOf {
+                path,
+                oneof,
+                members,
+                selected,
+            } => match key.code {
+                KeyCode::Up | KeyCode::Down => {
+                    let count = members.len() + 1;
+                    let selected = match key.code {
+                        KeyCode::Up => (selected + count - 1) % count,
+                        _ => (selected + 1) % count,
+                    };
+
+                    self.mode = Mode::OneOf {
+                        path,
+                        oneof,
+                        members,
+                        selected,
+                    };
+                }
+                KeyCode::Enter => {
+                    let case = selected.checked_sub(1).map(|index| members[index].number);
+                    self.change(&path, Change::OneOf { oneof, case });
+                }
+                KeyCode::Esc => {}
+                _ => {
+                    self.mode = Mode::OneOf {
+                        path,
+                        oneof,
+                        members,
+                        selected,
+                    }
+                }
+            },
+            Mode::Value {
+                mut form,
+                target,
+                field,
+                push,
+            } => {
+                let event = form.key(key);
+                let parsed = data::text::parse_value(&self.pool, &field, form.value("Value"));
+
+                match event {
+                    FormEvent::Cancel => {}
+                    FormEvent::Submit => match parsed {
+                        Ok(value) => {
+                            let Target::Field {
+                                path,
+                                number,
+                                index,
+                            } = &target
+                            else {
+                                return true;
+                            };
+
+                            let change = match push {
+                                true => Change::Push {
+                                    number: *number,
+                                    value,
+                                },
+                                false => Change::Set {
+                                    number: *number,
+                                    index: *index,
+                                    value,
+                                },
+                            };
+                            self.change(path, change);
+                        }
+                        Err(error) => {
+                            form.error = Some(error.to_string());
+                            self.mode = Mode::Value {
+                                form,
+                                target,
+                                field,
+                                push,
+                            };
+                        }
+                    },
+                    event => {
+                        if event == FormEvent::Changed {
+                            form.error = parsed.err().map(|error| error.message);
+                        }
+
+                        self.mode = Mode::Value {
+                            form,
+                            target,
+                            field,
+                            push,
+                        };
+                    }
+                }
+            }
+            Mode::Quit => {
+                if key.code == KeyCode::Char('y') {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
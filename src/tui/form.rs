@@ -0,0 +1,179 @@
+// Popup with labelled inputs. The screen owning it checks the values after every change and sets
+// the error; a form with an error can't be submitted.
+
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Paragraph};
+
+enum Input {
+    Text {
+        label: String,
+        value: String,
+    },
+    Choice {
+        label: String,
+        options: Vec<String>,
+        selected: usize,
+    },
+}
+
+pub struct Form {
+    title: String,
+    inputs: Vec<Input>,
+    focus: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormEvent {
+    None,
+    Changed,
+    Submit,
+    Cancel,
+}
+
+impl Form {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            inputs: vec![],
+            focus: 0,
+            error: None,
+        }
+    }
+
+    pub fn text(mut self, label: impl Into<String>, value: impl Into<String>) -> Self {
+        self.inputs.push(Input::Text {
+            label: label.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn choice(mut self, label: impl Into<String>, options: &[&str], selected: &str) -> Self {
+        self.inputs.push(Input::Choice {
+            label: label.into(),
+            options: options.iter().map(|option| option.to_string()).collect(),
+            selected: options
+                .iter()
+                .position(|option| *option == selected)
+                .unwrap_or_default(),
+        });
+        self
+    }
+
+    // Value of the input with the label, empty if there is none.
+    pub fn value(&self, label: &str) -> &str {
+        self.inputs
+            .iter()
+            .find_map(|input| match input {
+                Input::Text {
+                    label: other,
+                    value,
+                } if other == label => Some(value.as_str()),
+                Input::Choice {
+                    label: other,
+                    options,
+                    selected,
+                } if other == label => Some(options[*selected].as_str()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn key(&mut self, key: KeyEvent) -> FormEvent {
+        let count = self.inputs.len();
+
+        match (key.code, &mut self.inputs[self.focus]) {
+            (KeyCode::Esc, _) => return FormEvent::Cancel,
+            (KeyCode::Enter, _) if self.error.is_none() => return FormEvent::Submit,
+            (KeyCode::Enter, _) => {}
+            (KeyCode::Tab | KeyCode::Down, _) => self.focus = (self.focus + 1) % count,
+            (KeyCode::BackTab | KeyCode::Up, _) => self.focus = (self.focus + count - 1) % count,
+            (KeyCode::Char(ch), Input::Text { value, .. }) => {
+                value.push(ch);
+                return FormEvent::Changed;
+            }
+            (KeyCode::Backspace, Input::Text { value, .. }) => {
+                value.pop();
+                return FormEvent::Changed;
+            }
+            (
+                KeyCode::Left | KeyCode::Right | KeyCode::Char(' '),
+                Input::Choice {
+                    options, selected, ..
+                },
+            ) => {
+                *selected = match key.code {
+                    KeyCode::Left => (*selected + options.len() - 1) % options.len(),
+                    _ => (*selected + 1) % options.len(),
+                };
+                return FormEvent::Changed;
+            }
+            _ => {}
+        }
+
+        FormEvent::None
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let label_width = self
+            .inputs
+            .iter()
+            .map(|input| match input {
+                Input::Text { label, .. } | Input::Choice { label, .. } => label.len(),
+            })
+            .max()
+            .unwrap_or_default();
+
+        let mut lines = self
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let style = match index == self.focus {
+                    true => Style::default().add_modifier(Modifier::REVERSED),
+                    false => Style::default(),
+                };
+
+                let (label, value) = match input {
+                    Input::Text { label, value } => (label, value.clone()),
+                    Input::Choice {
+                        label,
+                        options,
+                        selected,
+                    } => (label, format!("< {} >", options[*selected])),
+                };
+
+                Line::from(vec![
+                    Span::raw(format!("{:>width$}: ", label, width = label_width)),
+                    Span::styled(format!("{:<30}", value), style),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        lines.push(Line::default());
+        lines.push(match &self.error {
+            Some(error) => Line::styled(error.clone(), Style::default().fg(Color::Red)),
+            None => Line::styled(
+                "enter: save, esc: cancel, tab: next",
+                Style::default().fg(Color::DarkGray),
+            ),
+        });
+
+        let width = (label_width as u16 + 36).max(
+            self.error
+                .as_ref()
+                .map_or(0, |error| error.len() as u16 + 4),
+        );
+        let area = super::centered(frame.area(), width, lines.len() as u16 + 2);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(format!(" {} ", self.title))),
+            area,
+        );
+    }
+}
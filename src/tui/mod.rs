@@ -0,0 +1,72 @@
+// Terminal front end of the interactive editors.
+
+pub mod data;
+mod form;
+pub mod schema;
+
+pub use form::{Form, FormEvent};
+
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph, Wrap};
+
+pub trait Screen {
+    fn draw(&mut self, frame: &mut Frame);
+    // Returns false to quit.
+    fn key(&mut self, key: KeyEvent) -> bool;
+}
+
+pub fn run(screen: &mut impl Screen) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+
+    let result = loop {
+        if let Err(error) = terminal.draw(|frame| screen.draw(frame)) {
+            break Err(error);
+        }
+
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                if !screen.key(key) {
+                    break Ok(());
+                }
+            }
+            Ok(_) => {}
+            Err(error) => break Err(error),
+        }
+    };
+
+    ratatui::restore();
+    result
+}
+
+// Area of the given size in the middle of `area`, for popups.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+
+    area
+}
+
+// Yes/no question on top of everything else.
+pub fn confirm(frame: &mut Frame, question: &str) {
+    let width = (question.len() as u16 + 4).max(24);
+    let area = centered(frame.area(), width, 4);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(question),
+            Line::styled("y: yes, n: no", Style::default().fg(Color::DarkGray)),
+        ])
+        .wrap(Wrap { trim: false })
+        .block(Block::bordered().title(" Confirm ")),
+        area,
+    );
+}
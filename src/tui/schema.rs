@@ -0,0 +1,1126 @@
+// Editor of a single .proto file. Changes are made to the source text, so comments and formatting
+// around the changed entries stay as written; every change must parse, and the file is compiled
+// together with its imports after it to show what else is wrong.
+
+use super::{Form, FormEvent, Screen};
+use proto_util::editor::{self, Edit, Layout};
+use proto_util::formatter;
+use proto_util::parser::{self, ast};
+use proto_util::resolver::{self, Node, SourceTree, Workspace};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Layout as Split};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, List, ListState, Paragraph};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Message,
+    Enum,
+    Service,
+    OneOf,
+    Field,
+    Value,
+    Rpc,
+}
+
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Message => "message",
+            Self::Enum => "enum",
+            Self::Service => "service",
+            Self::OneOf => "oneof",
+            Self::Field => "field",
+            Self::Value => "enum value",
+            Self::Rpc => "rpc",
+        }
+    }
+}
+
+// Entry being added at `index` among the entries of `parent`, or the entry at that index being
+// edited.
+struct Target {
+    kind: Kind,
+    parent: Vec<usize>,
+    index: usize,
+    existing: bool,
+}
+
+impl Target {
+    fn path(&self) -> Vec<usize> {
+        [self.parent.as_slice(), &[self.index]].concat()
+    }
+}
+
+enum Mode {
+    Browse,
+    Menu {
+        kinds: Vec<Kind>,
+        selected: usize,
+        parent: Vec<usize>,
+        index: usize,
+    },
+    Form(Form, Target),
+    // deleting the selected entry, reserving its number and name if set
+    Delete {
+        reserve: bool,
+    },
+    Quit,
+}
+
+struct Row {
+    path: Vec<usize>,
+    depth: usize,
+    label: String,
+    block: bool,
+    range: Range<usize>,
+}
+
+pub struct SchemaEditor {
+    path: PathBuf,
+    // The edited file and everything it imports, for compiling.
+    tree: SourceTree,
+    name: String,
+    source: String,
+    saved: String,
+    undo: Vec<String>,
+    collapsed: HashSet<Vec<usize>>,
+    list: ListState,
+    mode: Mode,
+    status: String,
+    problems: Vec<String>,
+}
+
+impl SchemaEditor {
+    pub fn open(include: Vec<PathBuf>, path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut tree = SourceTree::new(include);
+        let name = tree.add_path(path)?;
+        tree.load_imports()?;
+
+        let source = tree
+            .file(&name)
+            .map(|file| file.source.clone())
+            .unwrap_or_default();
+
+        let mut editor = Self {
+            path: path.to_path_buf(),
+            tree,
+            name,
+            saved: source.clone(),
+            source,
+            undo: vec![],
+            collapsed: HashSet::new(),
+            list: ListState::default().with_selected(Some(0)),
+            mode: Mode::Browse,
+            status: String::new(),
+            problems: vec![],
+        };
+
+        editor.check();
+        Ok(editor)
+    }
+
+    fn check(&mut self) {
+        let mut tree = self.tree.clone();
+        tree.add(self.name.clone(), self.source.clone());
+
+        self.problems = match Workspace::new(&tree) {
+            Ok(workspace) => match resolver::compile(&workspace) {
+                Ok(_) => vec![],
+                Err(error) => vec![error.to_string()],
+            },
+            Err(error) => vec![error.to_string()],
+        };
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let file = parser::parse(&self.source).unwrap_or_default();
+        let layout = Layout::new(&self.source, &file);
+        let mut rows = vec![];
+
+        for (index, entry) in file.iter().enumerate() {
+            let item = match entry {
+                ast::FileEntry::Comment(_) => continue,
+                ast::FileEntry::Syntax(syntax) => Item::Leaf(format!("syntax = \"{}\"", syntax)),
+                ast::FileEntry::Package(package) => Item::Leaf(format!("package {}", package)),
+                ast::FileEntry::Import(import) => Item::Leaf(format!(
+                    "import {}\"{}\"",
+                    match import.modifier {
+                        ast::ImportModifier::None => "",
+                        ast::ImportModifier::Public => "public ",
+                        ast::ImportModifier::Weak => "weak ",
+                    },
+                    import.path
+                )),
+                ast::FileEntry::Option(option) => Item::Leaf(option_label(option)),
+                ast::FileEntry::Message(message) => Item::Node(Node::Message(message)),
+                ast::FileEntry::Enum(r#enum) => Item::Node(Node::Enum(r#enum)),
+                ast::FileEntry::Service(service) => Item::Node(Node::Service(service)),
+                ast::FileEntry::Extend(extend) => Item::Node(Node::Extend(extend)),
+            };
+
+            self.push_rows(&layout, item, vec![index], 0, &mut rows);
+        }
+
+        rows
+    }
+
+    fn push_rows(
+        &self,
+        layout: &Layout,
+        item: Item,
+        path: Vec<usize>,
+        depth: usize,
+        rows: &mut Vec<Row>,
+    ) {
+        let range = layout
+            .entry(&path)
+            .map_or(0..0, |entry| entry.range.clone());
+
+        let node = match item {
+            Item::Skip => return,
+            Item::Leaf(label) => {
+                rows.push(Row {
+                    path,
+                    depth,
+                    label,
+                    block: false,
+                    range,
+                });
+                return;
+            }
+            Item::Node(node) => node,
+        };
+
+        let children = children(node);
+        let block = !matches!(node, Node::Rpc(_) | Node::Field(_) | Node::EnumValue(_));
+        let expanded = block && !self.collapsed.contains(&path);
+
+        rows.push(Row {
+            path: path.clone(),
+            depth,
+            label: node_label(node),
+            block,
+            range,
+        });
+
+        if expanded {
+            for (index, item) in children.into_iter().enumerate() {
+                let path = [path.as_slice(), &[index]].concat();
+                self.push_rows(layout, item, path, depth + 1, rows);
+            }
+        }
+    }
+
+    fn selected(&self, rows: &[Row]) -> Option<Vec<usize>> {
+        rows.get(self.list.selected()?).map(|row| row.path.clone())
+    }
+
+    // Selects the first entry starting at or after the offset.
+    fn select_offset(&mut self, offset: usize) {
+        let rows = self.rows();
+        let index = rows
+            .iter()
+            .position(|row| row.range.start >= offset)
+            .unwrap_or(rows.len().saturating_sub(1));
+
+        self.list.select(Some(index));
+    }
+
+    fn clamp_selection(&mut self) {
+        let count = self.rows().len();
+        let index = self.list.selected().unwrap_or_default();
+
+        self.list.select(Some(index.min(count.saturating_sub(1))));
+    }
+
+    // Applies the edits and selects the entry at where `focus` ends up, `focus` being an edit
+    // out of `edits` or an offset in the current source.
+    fn apply(&mut self, edits: &[Edit], focus: Focus) {
+        match editor::edit(&self.source, edits) {
+            Ok(source) => {
+                let offset = match focus {
+                    Focus::Edit(index) => edited_offset(edits, index),
+                    Focus::Offset(offset) => offset,
+                };
+
+                self.undo.push(std::mem::replace(&mut self.source, source));
+                self.status.clear();
+                self.check();
+                self.select_offset(offset);
+            }
+            Err(error) => self.status = error.to_string(),
+        }
+    }
+
+    fn add(&mut self, inside: bool) {
+        let rows = self.rows();
+        let file = parser::parse(&self.source).unwrap_or_default();
+        let selected = self.selected(&rows).unwrap_or_default();
+
+        let (parent, index) = match inside && !kinds(&file, &selected).is_empty() {
+            true => {
+                let count = block_len(&file, &selected);
+                (selected, count)
+            }
+            false => match selected.split_last() {
+                Some((index, parent)) => (parent.to_vec(), index + 1),
+                None => (vec![], file.len()),
+            },
+        };
+
+        let kinds = kinds(&file, &parent);
+
+        match kinds.len() {
+            0 => self.status = "Nothing can be added here".to_string(),
+            1 => self.open_form(kinds[0], parent, index),
+            _ => {
+                self.mode = Mode::Menu {
+                    kinds,
+                    selected: 0,
+                    parent,
+                    index,
+                }
+            }
+        }
+    }
+
+    fn open_form(&mut self, kind: Kind, parent: Vec<usize>, index: usize) {
+        let file = parser::parse(&self.source).unwrap_or_default();
+        let parent_node = Node::at(&file, &parent);
+
+        let form = match kind {
+            Kind::Field => {
+                let number = match parent_node {
+                    Some(Node::Message(message)) => editor::next_field_number(message),
+                    Some(Node::OneOf(_)) => match Node::at(&file, &parent[..parent.len() - 1]) {
+                        Some(Node::Message(message)) => editor::next_field_number(message),
+                        _ => 1,
+                    },
+                    Some(Node::Extend(extend)) => extend
+                        .entries
+                        .iter()
+                        .filter_map(|entry| match entry {
+                            ast::ExtendEntry::Field(field) => Some(field.index + 1),
+                            _ => None,
+                        })
+                        .max()
+                        .unwrap_or(1),
+                    _ => 1,
+                };
+
+                field_form("New field", "", "string", "", number)
+            }
+            Kind::Value => {
+                let number = match parent_node {
+                    Some(Node::Enum(r#enum)) => r#enum
+                        .entries
+                        .iter()
+                        .filter_map(|entry| match entry {
+                            ast::EnumEntry::Pair { value, .. } => Some(value + 1),
+                            _ => None,
+                        })
+                        .max()
+                        .unwrap_or_default(),
+                    _ => 0,
+                };
+
+                Form::new("New enum value")
+                    .text("Name", "")
+                    .text("Number", number.to_string())
+            }
+            Kind::Rpc => rpc_form("New rpc", "", "", false, "", false),
+            kind => Form::new(format!("New {}", kind.name())).text("Name", ""),
+        };
+
+        self.collapsed.remove(&parent);
+        self.show_form(
+            form,
+            Target {
+                kind,
+                parent,
+                index,
+                existing: false,
+            },
+        );
+    }
+
+    fn edit(&mut self) {
+        let rows = self.rows();
+        let Some(path) = self.selected(&rows) else {
+            return;
+        };
+
+        let file = parser::parse(&self.source).unwrap_or_default();
+
+        let (kind, form) = match Node::at(&file, &path) {
+            Some(Node::Field(field)) => (
+                Kind::Field,
+                field_form(
+                    "Edit field",
+                    modifier_name(&field.modifier),
+                    field.r#type,
+                    field.ident,
+                    field.index,
+                ),
+            ),
+            Some(Node::EnumValue(ast::EnumEntry::Pair { ident, value, .. })) => (
+                Kind::Value,
+                Form::new("Edit enum value")
+                    .text("Name", *ident)
+                    .text("Number", value.to_string()),
+            ),
+            Some(Node::Rpc(rpc)) => (
+                Kind::Rpc,
+                rpc_form(
+                    "Edit rpc",
+                    rpc.ident,
+                    rpc.request,
+                    matches!(
+                        rpc.stream,
+                        ast::RpcStream::ServerBound | ast::RpcStream::Bidirectional
+                    ),
+                    rpc.reply,
+                    matches!(
+                        rpc.stream,
+                        ast::RpcStream::ClientBound | ast::RpcStream::Bidirectional
+                    ),
+                ),
+            ),
+            Some(node) => match block_kind(node) {
+                Some((kind, ident)) => (
+                    kind,
+                    Form::new(format!("Rename {}", kind.name())).text("Name", ident),
+                ),
+                None => {
+                    self.status = "This entry can't be edited".to_string();
+                    return;
+                }
+            },
+            None => {
+                self.status = "This entry can't be edited".to_string();
+                return;
+            }
+        };
+
+        let (index, parent) = path.split_last().unwrap();
+        self.show_form(
+            form,
+            Target {
+                kind,
+                parent: parent.to_vec(),
+                index: *index,
+                existing: true,
+            },
+        );
+    }
+
+    fn show_form(&mut self, mut form: Form, target: Target) {
+        form.error = self.build(&target, &form).err();
+        self.mode = Mode::Form(form, target);
+    }
+
+    // Edits making the change described by the form, or why it can't be made.
+    fn build(&self, target: &Target, form: &Form) -> Result<Vec<Edit>, String> {
+        let file = parser::parse(&self.source).map_err(|error| error.to_string())?;
+        let layout = Layout::new(&self.source, &file);
+        let path = target.path();
+        let existing = match target.existing {
+            true => Node::at(&file, &path),
+            false => None,
+        };
+        let parent = Node::at(&file, &target.parent);
+        let name = form.value("Name");
+        let proto3 = file
+            .iter()
+            .any(|entry| matches!(entry, ast::FileEntry::Syntax("proto3")));
+
+        let text = match target.kind {
+            Kind::Field => {
+                let modifier = match form.value("Label") {
+                    "optional" => ast::FieldModifier::Optional,
+                    "repeated" => ast::FieldModifier::Repeated,
+                    "required" => ast::FieldModifier::Required,
+                    _ => ast::FieldModifier::None,
+                };
+                let field = ast::Field {
+                    modifier,
+                    r#type: form.value("Type").trim(),
+                    ident: name,
+                    index: number(form.value("Number"))?,
+                    options: vec![],
+                };
+                let (replaced, options) = match existing {
+                    Some(Node::Field(field)) => (Some(field.ident), field.options.as_slice()),
+                    _ => (None, [].as_slice()),
+                };
+
+                editor::check_label(&field, proto3, matches!(parent, Some(Node::OneOf(_))))?;
+
+                match parent {
+                    Some(Node::Message(message)) => editor::check_field(message, &field, replaced)?,
+                    Some(Node::OneOf(_)) => {
+                        match Node::at(&file, &target.parent[..target.parent.len() - 1]) {
+                            Some(Node::Message(message)) => {
+                                editor::check_field(message, &field, replaced)?
+                            }
+                            _ => return Err("Oneof outside of a message".to_string()),
+                        }
+                    }
+                    _ => {
+                        editor::check_ident(field.ident)?;
+                        editor::check_type(field.r#type)?;
+                        editor::check_field_number(field.index)?;
+                    }
+                }
+
+                let line = formatter::field_line(&field);
+                format!(
+                    "{}{};",
+                    line.trim_end_matches(';'),
+                    formatter::options_list(options)
+                )
+            }
+            Kind::Value => {
+                let Some(Node::Enum(r#enum)) = parent else {
+                    return Err("Enum values can only be added to enums".to_string());
+                };
+                let value = number(form.value("Number"))?;
+                let (replaced, options) = match existing {
+                    Some(Node::EnumValue(ast::EnumEntry::Pair { ident, options, .. })) => {
+                        (Some(*ident), options.as_slice())
+                    }
+                    _ => (None, [].as_slice()),
+                };
+
+                editor::check_enum_value(r#enum, name, value, replaced)?;
+
+                let first = r#enum
+                    .entries
+                    .iter()
+                    .position(|entry| matches!(entry, ast::EnumEntry::Pair { .. }));
+                let is_first = match target.existing {
+                    true => first == Some(target.index),
+                    false => first.is_none_or(|first| target.index <= first),
+                };
+
+                if proto3 && is_first && value != 0 {
+                    return Err("The first enum value must be zero in proto3".to_string());
+                }
+
+                format!("{} = {}{};", name, value, formatter::options_list(options))
+            }
+            Kind::Rpc => {
+                let Some(Node::Service(service)) = parent else {
+                    return Err("Rpcs can only be added to services".to_string());
+                };
+                let names = service.entries.iter().filter_map(|entry| match entry {
+                    ast::ServiceEntry::Rpc(rpc) => Some(rpc.ident),
+                    _ => None,
+                });
+                let replaced = match existing {
+                    Some(Node::Rpc(rpc)) => Some(rpc.ident),
+                    _ => None,
+                };
+
+                editor::check_name(names, name, replaced)?;
+
+                for label in ["Request", "Reply"] {
+                    let r#type = form.value(label);
+
+                    if r#type.is_empty() || r#type.contains('<') {
+                        return Err(format!("{} must be a message type", label));
+                    }
+                    editor::check_type(r#type)?;
+                }
+
+                let rpc = ast::Rpc {
+                    ident: name,
+                    request: form.value("Request"),
+                    reply: form.value("Reply"),
+                    stream: ast::RpcStream::new(
+                        form.value("Stream request") == "yes",
+                        form.value("Stream reply") == "yes",
+                    ),
+                    entries: vec![],
+                };
+                let signature = formatter::rpc_signature(&rpc);
+
+                // an rpc with options keeps them, only its signature is replaced
+                if let Some(entry) = layout.entry(&path).filter(|_| target.existing)
+                    && let Some(body) = &entry.body
+                {
+                    let range = entry.range.start..body.start - 1;
+                    return Ok(vec![Edit::replace(range, format!("{} ", signature))]);
+                }
+
+                format!("{};", signature)
+            }
+            kind => {
+                let names: Vec<&str> = match parent {
+                    Some(Node::Message(message)) => editor::message_names(message).collect(),
+                    _ => file
+                        .iter()
+                        .filter_map(|entry| match entry {
+                            ast::FileEntry::Message(message) => Some(message.ident),
+                            ast::FileEntry::Enum(r#enum) => Some(r#enum.ident),
+                            ast::FileEntry::Service(service) => Some(service.ident),
+                            _ => None,
+                        })
+                        .collect(),
+                };
+
+                if let Some((_, ident)) = existing.and_then(block_kind) {
+                    editor::check_name(names, name, Some(ident))?;
+
+                    return editor::rename(&self.source, ident, name)
+                        .map(|edit| vec![edit])
+                        .ok_or_else(|| "Name not found in the source".to_string());
+                }
+
+                editor::check_name(names, name, None)?;
+                format!("{} {} {{\n}}", kind.name(), name)
+            }
+        };
+
+        let edit = match target.existing {
+            true => editor::replace(&self.source, &layout, &path, &text),
+            false => editor::insert(&self.source, &layout, &target.parent, target.index, &text),
+        };
+
+        let edits = vec![edit.map_err(|error| error.to_string())?];
+        editor::edit(&self.source, &edits).map_err(|error| error.to_string())?;
+
+        Ok(edits)
+    }
+
+    // Fields and enum values have their number and name reserved when deleted, except extensions.
+    fn reservable(&self, path: &[usize]) -> bool {
+        let file = parser::parse(&self.source).unwrap_or_default();
+        let mut parent = &path[..path.len() - 1];
+        if let Some(Node::OneOf(_)) = Node::at(&file, parent) {
+            parent = &parent[..parent.len() - 1];
+        }
+
+        matches!(
+            Node::at(&file, path),
+            Some(Node::Field(_) | Node::EnumValue(_))
+        ) && !matches!(Node::at(&file, parent), Some(Node::Extend(_)))
+    }
+
+    fn delete(&mut self, reserve: bool) {
+        let rows = self.rows();
+        let Some(path) = self.selected(&rows) else {
+            return;
+        };
+
+        let edits = match reserve {
+            true => editor::retire_at(&self.source, &path),
+            false => editor::remove_at(&self.source, &path),
+        };
+
+        match edits {
+            Ok(edits) => {
+                let offset = edits[0].range.start;
+                self.apply(&edits, Focus::Offset(offset));
+            }
+            Err(error) => self.status = error.to_string(),
+        }
+    }
+
+    // Swaps the entry with the previous or the next one, comments not counting as entries.
+    fn r#move(&mut self, up: bool) {
+        let rows = self.rows();
+        let Some(path) = self.selected(&rows) else {
+            return;
+        };
+
+        let (index, parent) = path.split_last().unwrap();
+        let neighbours: Vec<usize> = rows
+            .iter()
+            .filter(|row| row.path.len() == path.len() && row.path.starts_with(parent))
+            .map(|row| row.path[path.len() - 1])
+            .collect();
+
+        let position = neighbours.iter().position(|other| other == index).unwrap();
+        let to = match up {
+            true => position.checked_sub(1).map(|position| neighbours[position]),
+            false => neighbours.get(position + 1).copied(),
+        };
+
+        let Some(to) = to else {
+            return;
+        };
+
+        let file = parser::parse(&self.source).unwrap_or_default();
+        let layout = Layout::new(&self.source, &file);
+
+        match editor::move_to(&self.source, &layout, &path, to) {
+            Ok(edits) => self.apply(&edits, Focus::Edit(1)),
+            Err(error) => self.status = error.to_string(),
+        }
+    }
+
+    fn save(&mut self) {
+        match std::fs::write(&self.path, &self.source) {
+            Ok(()) => {
+                self.saved = self.source.clone();
+                self.status = format!("Saved {}", self.path.display());
+            }
+            Err(error) => self.status = format!("{}: {}", self.path.display(), error),
+        }
+    }
+
+    fn browse(&mut self, key: KeyEvent) -> bool {
+        let rows = self.rows();
+        let selected = self.list.selected().unwrap_or_default();
+        let row = rows.get(selected);
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self
+                .list
+                .select(Some((selected + 1).min(rows.len().saturating_sub(1)))),
+            KeyCode::Left | KeyCode::Char('h') => match row {
+                Some(row) if row.block && !self.collapsed.contains(&row.path) => {
+                    self.collapsed.insert(row.path.clone());
+                }
+                Some(row) => {
+                    let parent = &row.path[..row.path.len() - 1];
+
+                    if let Some(index) = rows.iter().position(|other| other.path == parent) {
+                        self.list.select(Some(index));
+                    }
+                }
+                None => {}
+            },
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(row) = row {
+                    self.collapsed.remove(&row.path);
+                }
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(row) = row.filter(|row| row.block)
+                    && !self.collapsed.remove(&row.path)
+                {
+                    self.collapsed.insert(row.path.clone());
+                }
+            }
+            KeyCode::Char('a') => self.add(true),
+            KeyCode::Char('A') => self.add(false),
+            KeyCode::Char('e') => self.edit(),
+            KeyCode::Char('d') | KeyCode::Delete if let Some(row) = row => {
+                self.mode = Mode::Delete {
+                    reserve: self.reservable(&row.path),
+                }
+            }
+            KeyCode::Char('D') if row.is_some() => self.mode = Mode::Delete { reserve: false },
+            KeyCode::Char('K') => self.r#move(true),
+            KeyCode::Char('J') => self.r#move(false),
+            KeyCode::Char('u') => match self.undo.pop() {
+                Some(source) => {
+                    self.source = source;
+                    self.status = "Undone".to_string();
+                    self.check();
+                    self.clamp_selection();
+                }
+                None => self.status = "Nothing to undo".to_string(),
+            },
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('q') | KeyCode::Esc => match self.source == self.saved {
+                true => return false,
+                false => self.mode = Mode::Quit,
+            },
+            _ => {}
+        }
+
+        true
+    }
+}
+
+enum Focus {
+    Edit(usize),
+    Offset(usize),
+}
+
+enum Item<'r, 'a> {
+    Skip,
+    Leaf(String),
+    Node(Node<'r, 'a>),
+}
+
+fn children<'r, 'a>(node: Node<'r, 'a>) -> Vec<Item<'r, 'a>> {
+    match node {
+        Node::Message(message) => message
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::MessageEntry::Comment(_) => Item::Skip,
+                ast::MessageEntry::Option(option) => Item::Leaf(option_label(option)),
+                ast::MessageEntry::Field(field) => Item::Node(Node::Field(field)),
+                ast::MessageEntry::OneOf(oneof) => Item::Node(Node::OneOf(oneof)),
+                ast::MessageEntry::Message(message) => Item::Node(Node::Message(message)),
+                ast::MessageEntry::Extend(extend) => Item::Node(Node::Extend(extend)),
+                ast::MessageEntry::Enum(r#enum) => Item::Node(Node::Enum(r#enum)),
+                ast::MessageEntry::ReservedIndices(ranges) => {
+                    Item::Leaf(format!("reserved {}", formatter::ranges_list(ranges)))
+                }
+                ast::MessageEntry::ReservedIdents(idents) => {
+                    Item::Leaf(format!("reserved {}", formatter::idents_list(idents)))
+                }
+                ast::MessageEntry::Extensions(ranges) => {
+                    Item::Leaf(format!("extensions {}", formatter::ranges_list(ranges)))
+                }
+            })
+            .collect(),
+        Node::Enum(r#enum) => r#enum
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::EnumEntry::Comment(_) => Item::Skip,
+                ast::EnumEntry::Option(option) => Item::Leaf(option_label(option)),
+                entry @ ast::EnumEntry::Pair { .. } => Item::Node(Node::EnumValue(entry)),
+                ast::EnumEntry::ReservedIndices(ranges) => {
+                    Item::Leaf(format!("reserved {}", formatter::ranges_list(ranges)))
+                }
+                ast::EnumEntry::ReservedIdents(idents) => {
+                    Item::Leaf(format!("reserved {}", formatter::idents_list(idents)))
+                }
+            })
+            .collect(),
+        Node::Service(service) => service
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::ServiceEntry::Comment(_) => Item::Skip,
+                ast::ServiceEntry::Option(option) => Item::Leaf(option_label(option)),
+                ast::ServiceEntry::Rpc(rpc) => Item::Node(Node::Rpc(rpc)),
+            })
+            .collect(),
+        Node::OneOf(oneof) => oneof
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::OneOfEntry::Comment(_) => Item::Skip,
+                ast::OneOfEntry::Option(option) => Item::Leaf(option_label(option)),
+                ast::OneOfEntry::Field(field) => Item::Node(Node::Field(field)),
+            })
+            .collect(),
+        Node::Extend(extend) => extend
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::ExtendEntry::Comment(_) => Item::Skip,
+                ast::ExtendEntry::Field(field) => Item::Node(Node::Field(field)),
+            })
+            .collect(),
+        Node::Rpc(_) | Node::Field(_) | Node::EnumValue(_) => vec![],
+    }
+}
+
+fn option_label(option: &ast::Option) -> String {
+    format!(
+        "option {} = {}",
+        option.key,
+        formatter::map_value(&option.value)
+    )
+}
+
+fn node_label(node: Node) -> String {
+    match node {
+        Node::Message(message) => format!("message {}", message.ident),
+        Node::Enum(r#enum) => format!("enum {}", r#enum.ident),
+        Node::EnumValue(ast::EnumEntry::Pair { ident, value, .. }) => {
+            format!("{} = {}", ident, value)
+        }
+        Node::EnumValue(_) => String::new(),
+        Node::Service(service) => format!("service {}", service.ident),
+        Node::Rpc(rpc) => formatter::rpc_signature(rpc),
+        Node::Field(field) => formatter::field_line(field)
+            .trim_end_matches(';')
+            .to_string(),
+        Node::OneOf(oneof) => format!("oneof {}", oneof.ident),
+        Node::Extend(extend) => format!("extend {}", extend.r#type),
+    }
+}
+
+// Kind and name of blocks that can be renamed.
+fn block_kind<'a>(node: Node<'_, 'a>) -> Option<(Kind, &'a str)> {
+    match node {
+        Node::Message(message) => Some((Kind::Message, message.ident)),
+        Node::Enum(r#enum) => Some((Kind::Enum, r#enum.ident)),
+        Node::Service(service) => Some((Kind::Service, service.ident)),
+        Node::OneOf(oneof) => Some((Kind::OneOf, oneof.ident)),
+        _ => None,
+    }
+}
+
+// What can be added to the file (empty path) or the block at the path.
+fn kinds(file: &ast::File, path: &[usize]) -> Vec<Kind> {
+    if path.is_empty() {
+        return vec![Kind::Message, Kind::Enum, Kind::Service];
+    }
+
+    match Node::at(file, path) {
+        Some(Node::Message(_)) => vec![Kind::Field, Kind::Message, Kind::Enum, Kind::OneOf],
+        Some(Node::OneOf(_) | Node::Extend(_)) => vec![Kind::Field],
+        Some(Node::Enum(_)) => vec![Kind::Value],
+        Some(Node::Service(_)) => vec![Kind::Rpc],
+        _ => vec![],
+    }
+}
+
+fn block_len(file: &ast::File, path: &[usize]) -> usize {
+    match Node::at(file, path) {
+        Some(Node::Message(message)) => message.entries.len(),
+        Some(Node::Enum(r#enum)) => r#enum.entries.len(),
+        Some(Node::Service(service)) => service.entries.len(),
+        Some(Node::OneOf(oneof)) => oneof.entries.len(),
+        Some(Node::Extend(extend)) => extend.entries.len(),
+        _ => 0,
+    }
+}
+
+fn modifier_name(modifier: &ast::FieldModifier) -> &'static str {
+    match modifier {
+        ast::FieldModifier::None => "",
+        ast::FieldModifier::Optional => "optional",
+        ast::FieldModifier::Required => "required",
+        ast::FieldModifier::Repeated => "repeated",
+    }
+}
+
+fn number(text: &str) -> Result<i64, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("{} is not a number", text))
+}
+
+fn field_form(title: &str, label: &str, r#type: &str, name: &str, number: i64) -> Form {
+    Form::new(title)
+        .choice("Label", &["", "optional", "repeated", "required"], label)
+        .text("Type", r#type)
+        .text("Name", name)
+        .text("Number", number.to_string())
+}
+
+fn rpc_form(
+    title: &str,
+    name: &str,
+    request: &str,
+    request_stream: bool,
+    reply: &str,
+    reply_stream: bool,
+) -> Form {
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+
+    Form::new(title)
+        .text("Name", name)
+        .text("Request", request)
+        .choice("Stream request", &["no", "yes"], yes_no(request_stream))
+        .text("Reply", reply)
+        .choice("Stream reply", &["no", "yes"], yes_no(reply_stream))
+}
+
+// Offset in the edited source where the first non-blank character inserted by the edit ends up.
+fn edited_offset(edits: &[Edit], index: usize) -> usize {
+    let edit = &edits[index];
+    let shift: isize = edits
+        .iter()
+        .filter(|other| other.range.end <= edit.range.start && *other != edit)
+        .map(|other| other.text.len() as isize - other.range.len() as isize)
+        .sum();
+    let blank = edit.text.len() - edit.text.trim_start().len();
+
+    (edit.range.start as isize + shift) as usize + blank
+}
+
+impl Screen for SchemaEditor {
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = self.rows();
+        let problems = self.problems.len().clamp(1, 5) as u16;
+
+        let [main, problems_area, status_area] = Split::vertical([
+            Constraint::Min(3),
+            Constraint::Length(problems + 2),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [tree_area, source_area] =
+            Split::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(main);
+
+        let items = rows.iter().map(|row| {
+            let marker = match (row.block, self.collapsed.contains(&row.path)) {
+                (false, _) => "  ",
+                (true, true) => "▸ ",
+                (true, false) => "▾ ",
+            };
+
+            format!("{}{}{}", "  ".repeat(row.depth), marker, row.label)
+        });
+        let dirty = if self.source == self.saved {
+            ""
+        } else {
+            " [+]"
+        };
+
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(format!(" {}{} ", self.name, dirty)))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            tree_area,
+            &mut self.list,
+        );
+
+        let text = match rows.get(self.list.selected().unwrap_or_default()) {
+            Some(row) => {
+                let start = editor::line_start(&self.source, row.range.start);
+                let end = editor::line_end(&self.source, row.range.end.max(start));
+                self.source[start..end].to_string()
+            }
+            None => String::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title(" Source ")),
+            source_area,
+        );
+
+        let lines = match self.problems.is_empty() {
+            true => vec![Line::styled(
+                "No problems",
+                Style::default().fg(Color::Green),
+            )],
+            false => self
+                .problems
+                .iter()
+                .map(|problem| Line::styled(problem.clone(), Style::default().fg(Color::Red)))
+                .collect(),
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Problems ")),
+            problems_area,
+        );
+
+        let status = match self.status.is_empty() {
+            true => Line::styled(
+                "a: add  A: add after  e: edit  d: delete  D: delete without reserving  K/J: move  u: undo  s: save  q: quit",
+                Style::default().fg(Color::DarkGray),
+            ),
+            false => Line::from(self.status.as_str()),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+
+        match &self.mode {
+            Mode::Browse => {}
+            Mode::Menu {
+                kinds, selected, ..
+            } => {
+                let area = super::centered(frame.area(), 24, kinds.len() as u16 + 2);
+                let items = kinds.iter().map(|kind| kind.name());
+
+                frame.render_widget(Clear, area);
+                frame.render_stateful_widget(
+                    List::new(items)
+                        .block(Block::bordered().title(" Add "))
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+                    area,
+                    &mut ListState::default().with_selected(Some(*selected)),
+                );
+            }
+            Mode::Form(form, _) => form.draw(frame),
+            Mode::Delete { reserve } => {
+                let label = rows
+                    .get(self.list.selected().unwrap_or_default())
+                    .map_or("", |row| row.label.as_str());
+                let question = match reserve {
+                    true => format!("Delete {} and reserve its number and name?", label),
+                    false => format!("Delete {}?", label),
+                };
+                super::confirm(frame, &question);
+            }
+            Mode::Quit => super::confirm(frame, "Quit without saving the changes?"),
+        }
+    }
+
+    fn key(&mut self, key: KeyEvent) -> bool {
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => return self.browse(key),
+            Mode::Menu {
+                kinds,
+                selected,
+                parent,
+                index,
+            } => match key.code {
+                KeyCode::Up => {
+                    self.mode = Mode::Menu {
+                        selected: (selected + kinds.len() - 1) % kinds.len(),
+                        kinds,
+                        parent,
+                        index,
+                    }
+                }
+                KeyCode::Down => {
+                    self.mode = Mode::Menu {
+                        selected: (selected + 1) % kinds.len(),
+                        kinds,
+                        parent,
+                        index,
+                    }
+                }
+                KeyCode::Enter => self.open_form(kinds[selected], parent, index),
+                KeyCode::Esc => {}
+                _ => {
+                    self.mode = Mode::Menu {
+                        kinds,
+                        selected,
+                        parent,
+                        index,
+                    }
+                }
+            },
+            Mode::Form(mut form, target) => match form.key(key) {
+                FormEvent::Cancel => {}
+                FormEvent::Submit => match self.build(&target, &form) {
+                    Ok(edits) => {
+                        let focus = match target.existing {
+                            // the entry being edited starts where it did
+                            true => Focus::Offset(
+                                self.rows()
+                                    .get(self.list.selected().unwrap_or_default())
+                                    .map_or(0, |row| row.range.start),
+                            ),
+                            false => Focus::Edit(0),
+                        };
+                        self.apply(&edits, focus);
+                    }
+                    Err(error) => {
+                        form.error = Some(error);
+                        self.mode = Mode::Form(form, target);
+                    }
+                },
+                FormEvent::Changed => {
+                    form.error = self.build(&target, &form).err();
+                    self.mode = Mode::Form(form, target);
+                }
+                FormEvent::None => self.mode = Mode::Form(form, target),
+            },
+            Mode::Delete { reserve } => {
+                if key.code == KeyCode::Char('y') {
+                    self.delete(reserve);
+                }
+            }
+            Mode::Quit => {
+                if key.code == KeyCode::Char('y') {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,145 @@
+// Generated from legacy/settings.proto. Do not edit.
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Settings {
+    pub retries: ::std::option::Option<i32>,
+    pub name: ::std::option::Option<::std::string::String>,
+    pub greeting: ::std::option::Option<::std::string::String>,
+    pub mode: ::std::option::Option<i32>,
+    pub codes: ::std::vec::Vec<i32>,
+    pub flags: ::std::vec::Vec<i32>,
+    pub salt: ::std::option::Option<::std::vec::Vec<u8>>,
+    pub ratio: ::std::option::Option<f32>,
+    pub fallback: ::std::option::Option<i32>,
+    pub parent: ::std::option::Option<::std::boxed::Box<Settings>>,
+}
+
+impl Settings {
+    /// Value of `retries`, or its default if it is unset.
+    pub fn retries(&self) -> i32 {
+        self.retries.unwrap_or(3)
+    }
+
+    /// Value of `name`, or its default if it is unset.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or_default()
+    }
+
+    /// Value of `greeting`, or its default if it is unset.
+    pub fn greeting(&self) -> &str {
+        self.greeting.as_deref().unwrap_or("hi \"there\"")
+    }
+
+    /// Value of `mode` as an enum, or its default if it is unset or unknown.
+    pub fn mode(&self) -> settings::Mode {
+        self.mode.and_then(|value| settings::Mode::try_from(value).ok()).unwrap_or(settings::Mode::Fast)
+    }
+
+    /// Sets `mode` to the value of the enum.
+    pub fn set_mode(&mut self, value: settings::Mode) {
+        self.mode = ::std::option::Option::Some(value as i32);
+    }
+
+    /// Value of `salt`, or its default if it is unset.
+    pub fn salt(&self) -> &[u8] {
+        self.salt.as_deref().unwrap_or(b"\x01x")
+    }
+
+    /// Value of `ratio`, or its default if it is unset.
+    pub fn ratio(&self) -> f32 {
+        self.ratio.unwrap_or(f32::INFINITY)
+    }
+
+    /// Value of `fallback` as an enum, or its default if it is unset or unknown.
+    pub fn fallback(&self) -> settings::Mode {
+        self.fallback.and_then(|value| settings::Mode::try_from(value).ok()).unwrap_or_default()
+    }
+
+    /// Sets `fallback` to the value of the enum.
+    pub fn set_fallback(&mut self, value: settings::Mode) {
+        self.fallback = ::std::option::Option::Some(value as i32);
+    }
+}
+
+impl ::wire::Message for Settings {
+    fn merge_field(
+        &mut self,
+        tag: ::wire::Tag,
+        reader: &mut ::wire::Reader,
+    ) -> ::std::result::Result<(), ::wire::DecodeError> {
+        match tag.number {
+            1 => ::wire::codec::merge_optional::<::wire::codec::Int32>(&mut self.retries, tag, reader),
+            2 => ::wire::codec::merge_optional::<::wire::codec::String>(&mut self.name, tag, reader),
+            3 => ::wire::codec::merge_optional::<::wire::codec::String>(&mut self.greeting, tag, reader),
+            4 => ::wire::codec::merge_optional::<::wire::codec::Int32>(&mut self.mode, tag, reader),
+            5 => ::wire::codec::merge_repeated::<::wire::codec::Int32>(&mut self.codes, tag, reader),
+            6 => ::wire::codec::merge_repeated::<::wire::codec::Int32>(&mut self.flags, tag, reader),
+            7 => ::wire::codec::merge_optional::<::wire::codec::Bytes>(&mut self.salt, tag, reader),
+            8 => ::wire::codec::merge_optional::<::wire::codec::Float>(&mut self.ratio, tag, reader),
+            9 => ::wire::codec::merge_optional::<::wire::codec::Int32>(&mut self.fallback, tag, reader),
+            10 => ::wire::codec::merge_optional::<::wire::codec::Nested<::std::boxed::Box<Settings>>>(&mut self.parent, tag, reader),
+            _ => reader.skip_field(tag).map(|_| ()),
+        }
+    }
+
+    fn encode_to(&self, writer: &mut ::wire::Writer) {
+        ::wire::codec::encode_optional::<::wire::codec::Int32>(&self.retries, 1, writer);
+        ::wire::codec::encode_optional::<::wire::codec::String>(&self.name, 2, writer);
+        ::wire::codec::encode_optional::<::wire::codec::String>(&self.greeting, 3, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Int32>(&self.mode, 4, writer);
+        ::wire::codec::encode_packed::<::wire::codec::Int32>(&self.codes, 5, writer);
+        ::wire::codec::encode_repeated::<::wire::codec::Int32>(&self.flags, 6, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Bytes>(&self.salt, 7, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Float>(&self.ratio, 8, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Int32>(&self.fallback, 9, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Nested<::std::boxed::Box<Settings>>>(&self.parent, 10, writer);
+    }
+}
+
+/// Nested declarations and oneofs of [`Settings`].
+pub mod settings {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+    #[repr(i32)]
+    pub enum Mode {
+        #[default]
+        Slow = 1,
+        Fast = 2,
+    }
+
+    impl Mode {
+        /// Name of the value in the schema.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Slow => "MODE_SLOW",
+                Self::Fast => "MODE_FAST",
+            }
+        }
+
+        /// Value by its name in the schema, aliases included.
+        pub fn from_str_name(name: &str) -> ::std::option::Option<Self> {
+            match name {
+                "MODE_SLOW" => ::std::option::Option::Some(Self::Slow),
+                "MODE_FAST" => ::std::option::Option::Some(Self::Fast),
+                _ => ::std::option::Option::None,
+            }
+        }
+    }
+
+    impl ::std::convert::TryFrom<i32> for Mode {
+        type Error = i32;
+
+        fn try_from(value: i32) -> ::std::result::Result<Self, i32> {
+            match value {
+                1 => ::std::result::Result::Ok(Self::Slow),
+                2 => ::std::result::Result::Ok(Self::Fast),
+                _ => ::std::result::Result::Err(value),
+            }
+        }
+    }
+
+    impl ::std::convert::From<Mode> for i32 {
+        fn from(value: Mode) -> i32 {
+            value as i32
+        }
+    }
+}
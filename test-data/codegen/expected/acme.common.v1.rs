@@ -0,0 +1,31 @@
+// Generated from acme/common/v1/money.proto. Do not edit.
+
+/// An amount of money in a currency.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Money {
+    /// ISO 4217 code.
+    pub currency: ::std::string::String,
+    pub units: i64,
+    pub nanos: i32,
+}
+
+impl ::wire::Message for Money {
+    fn merge_field(
+        &mut self,
+        tag: ::wire::Tag,
+        reader: &mut ::wire::Reader,
+    ) -> ::std::result::Result<(), ::wire::DecodeError> {
+        match tag.number {
+            1 => ::wire::codec::merge::<::wire::codec::String>(&mut self.currency, tag, reader),
+            2 => ::wire::codec::merge::<::wire::codec::Int64>(&mut self.units, tag, reader),
+            3 => ::wire::codec::merge::<::wire::codec::Sint32>(&mut self.nanos, tag, reader),
+            _ => reader.skip_field(tag).map(|_| ()),
+        }
+    }
+
+    fn encode_to(&self, writer: &mut ::wire::Writer) {
+        ::wire::codec::encode::<::wire::codec::String>(&self.currency, 1, writer);
+        ::wire::codec::encode::<::wire::codec::Int64>(&self.units, 2, writer);
+        ::wire::codec::encode::<::wire::codec::Sint32>(&self.nanos, 3, writer);
+    }
+}
@@ -0,0 +1,388 @@
+// Generated from acme/store/v1/store.proto. Do not edit.
+
+/// Something for sale.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Product {
+    pub id: ::std::string::String,
+    pub price: ::std::option::Option<super::super::common::v1::Money>,
+    pub tags: ::std::vec::Vec<::std::string::String>,
+    /// Units in stock by warehouse.
+    pub stock: ::std::collections::BTreeMap<::std::string::String, i32>,
+    pub status: i32,
+    /// Where the product comes from.
+    pub origin: ::std::option::Option<product::Origin>,
+    pub ratings: ::std::vec::Vec<i32>,
+    pub weight: ::std::option::Option<f64>,
+    pub thumbnail: ::std::vec::Vec<u8>,
+    pub variants: ::std::vec::Vec<product::Variant>,
+    pub sizes: ::std::collections::BTreeMap<i64, product::Variant>,
+    pub category: ::std::option::Option<Category>,
+    pub barcode: u64,
+}
+
+impl Product {
+    /// Value of `status` as an enum, or its default if it is unknown.
+    pub fn status(&self) -> product::Status {
+        product::Status::try_from(self.status).unwrap_or_default()
+    }
+
+    /// Sets `status` to the value of the enum.
+    pub fn set_status(&mut self, value: product::Status) {
+        self.status = value as i32;
+    }
+
+    /// Value of `weight`, or its default if it is unset.
+    pub fn weight(&self) -> f64 {
+        self.weight.unwrap_or_default()
+    }
+}
+
+impl ::wire::Message for Product {
+    fn merge_field(
+        &mut self,
+        tag: ::wire::Tag,
+        reader: &mut ::wire::Reader,
+    ) -> ::std::result::Result<(), ::wire::DecodeError> {
+        match tag.number {
+            1 => ::wire::codec::merge::<::wire::codec::String>(&mut self.id, tag, reader),
+            2 => ::wire::codec::merge_optional::<::wire::codec::Nested<super::super::common::v1::Money>>(&mut self.price, tag, reader),
+            3 => ::wire::codec::merge_repeated::<::wire::codec::String>(&mut self.tags, tag, reader),
+            4 => ::wire::codec::merge_map::<::wire::codec::String, ::wire::codec::Int32>(&mut self.stock, tag, reader),
+            5 => ::wire::codec::merge::<::wire::codec::Int32>(&mut self.status, tag, reader),
+            6 | 7 => product::Origin::merge(&mut self.origin, tag, reader),
+            8 => ::wire::codec::merge_repeated::<::wire::codec::Int32>(&mut self.ratings, tag, reader),
+            9 => ::wire::codec::merge_optional::<::wire::codec::Double>(&mut self.weight, tag, reader),
+            10 => ::wire::codec::merge::<::wire::codec::Bytes>(&mut self.thumbnail, tag, reader),
+            11 => ::wire::codec::merge_repeated::<::wire::codec::Nested<product::Variant>>(&mut self.variants, tag, reader),
+            12 => ::wire::codec::merge_map::<::wire::codec::Int64, ::wire::codec::Nested<product::Variant>>(&mut self.sizes, tag, reader),
+            13 => ::wire::codec::merge_optional::<::wire::codec::Nested<Category>>(&mut self.category, tag, reader),
+            14 => ::wire::codec::merge::<::wire::codec::Fixed64>(&mut self.barcode, tag, reader),
+            _ => reader.skip_field(tag).map(|_| ()),
+        }
+    }
+
+    fn encode_to(&self, writer: &mut ::wire::Writer) {
+        ::wire::codec::encode::<::wire::codec::String>(&self.id, 1, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Nested<super::super::common::v1::Money>>(&self.price, 2, writer);
+        ::wire::codec::encode_repeated::<::wire::codec::String>(&self.tags, 3, writer);
+        ::wire::codec::encode_map::<::wire::codec::String, ::wire::codec::Int32>(&self.stock, 4, writer);
+        ::wire::codec::encode::<::wire::codec::Int32>(&self.status, 5, writer);
+        if let ::std::option::Option::Some(origin) = &self.origin {
+            origin.encode_to(writer);
+        }
+        ::wire::codec::encode_packed::<::wire::codec::Int32>(&self.ratings, 8, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Double>(&self.weight, 9, writer);
+        ::wire::codec::encode::<::wire::codec::Bytes>(&self.thumbnail, 10, writer);
+        ::wire::codec::encode_repeated::<::wire::codec::Nested<product::Variant>>(&self.variants, 11, writer);
+        ::wire::codec::encode_map::<::wire::codec::Int64, ::wire::codec::Nested<product::Variant>>(&self.sizes, 12, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Nested<Category>>(&self.category, 13, writer);
+        ::wire::codec::encode::<::wire::codec::Fixed64>(&self.barcode, 14, writer);
+    }
+}
+
+/// Nested declarations and oneofs of [`Product`].
+pub mod product {
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub struct Variant {
+        pub name: ::std::string::String,
+        pub r#type: ::std::string::String,
+        pub available: bool,
+    }
+
+    impl ::wire::Message for Variant {
+        fn merge_field(
+            &mut self,
+            tag: ::wire::Tag,
+            reader: &mut ::wire::Reader,
+        ) -> ::std::result::Result<(), ::wire::DecodeError> {
+            match tag.number {
+                1 => ::wire::codec::merge::<::wire::codec::String>(&mut self.name, tag, reader),
+                2 => ::wire::codec::merge::<::wire::codec::String>(&mut self.r#type, tag, reader),
+                3 => ::wire::codec::merge::<::wire::codec::Bool>(&mut self.available, tag, reader),
+                _ => reader.skip_field(tag).map(|_| ()),
+            }
+        }
+
+        fn encode_to(&self, writer: &mut ::wire::Writer) {
+            ::wire::codec::encode::<::wire::codec::String>(&self.name, 1, writer);
+            ::wire::codec::encode::<::wire::codec::String>(&self.r#type, 2, writer);
+            ::wire::codec::encode::<::wire::codec::Bool>(&self.available, 3, writer);
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+    #[repr(i32)]
+    pub enum Status {
+        #[default]
+        Unspecified = 0,
+        Active = 1,
+        Retired = 2,
+    }
+
+    impl Status {
+        /// Name of the value in the schema.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unspecified => "STATUS_UNSPECIFIED",
+                Self::Active => "STATUS_ACTIVE",
+                Self::Retired => "STATUS_RETIRED",
+            }
+        }
+
+        /// Value by its name in the schema, aliases included.
+        pub fn from_str_name(name: &str) -> ::std::option::Option<Self> {
+            match name {
+                "STATUS_UNSPECIFIED" => ::std::option::Option::Some(Self::Unspecified),
+                "STATUS_ACTIVE" => ::std::option::Option::Some(Self::Active),
+                "STATUS_RETIRED" => ::std::option::Option::Some(Self::Retired),
+                _ => ::std::option::Option::None,
+            }
+        }
+    }
+
+    impl ::std::convert::TryFrom<i32> for Status {
+        type Error = i32;
+
+        fn try_from(value: i32) -> ::std::result::Result<Self, i32> {
+            match value {
+                0 => ::std::result::Result::Ok(Self::Unspecified),
+                1 => ::std::result::Result::Ok(Self::Active),
+                2 => ::std::result::Result::Ok(Self::Retired),
+                _ => ::std::result::Result::Err(value),
+            }
+        }
+    }
+
+    impl ::std::convert::From<Status> for i32 {
+        fn from(value: Status) -> i32 {
+            value as i32
+        }
+    }
+
+    /// Where the product comes from.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Origin {
+        Factory(::std::string::String),
+        /// Products sold together.
+        Bundle(::std::boxed::Box<super::Bundle>),
+    }
+
+    impl Origin {
+        pub fn merge(
+            field: &mut ::std::option::Option<Self>,
+            tag: ::wire::Tag,
+            reader: &mut ::wire::Reader,
+        ) -> ::std::result::Result<(), ::wire::DecodeError> {
+            match tag.number {
+                6 => {
+                    let mut value = match field.take() {
+                        ::std::option::Option::Some(Self::Factory(value)) => value,
+                        _ => ::std::default::Default::default(),
+                    };
+                    ::wire::codec::merge::<::wire::codec::String>(&mut value, tag, reader)?;
+                    *field = ::std::option::Option::Some(Self::Factory(value));
+                    ::std::result::Result::Ok(())
+                }
+                7 => {
+                    let mut value = match field.take() {
+                        ::std::option::Option::Some(Self::Bundle(value)) => value,
+                        _ => ::std::default::Default::default(),
+                    };
+                    ::wire::codec::merge::<::wire::codec::Nested<::std::boxed::Box<super::Bundle>>>(&mut value, tag, reader)?;
+                    *field = ::std::option::Option::Some(Self::Bundle(value));
+                    ::std::result::Result::Ok(())
+                }
+                _ => reader.skip_field(tag).map(|_| ()),
+            }
+        }
+
+        pub fn encode_to(&self, writer: &mut ::wire::Writer) {
+            match self {
+                Self::Factory(value) => ::wire::codec::write::<::wire::codec::String>(value, 6, writer),
+                Self::Bundle(value) => ::wire::codec::write::<::wire::codec::Nested<::std::boxed::Box<super::Bundle>>>(value, 7, writer),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bundle {
+    pub products: ::std::vec::Vec<Product>,
+    pub main: ::std::option::Option<::std::boxed::Box<Product>>,
+}
+
+impl ::wire::Message for Bundle {
+    fn merge_field(
+        &mut self,
+        tag: ::wire::Tag,
+        reader: &mut ::wire::Reader,
+    ) -> ::std::result::Result<(), ::wire::DecodeError> {
+        match tag.number {
+            1 => ::wire::codec::merge_repeated::<::wire::codec::Nested<Product>>(&mut self.products, tag, reader),
+            2 => ::wire::codec::merge_optional::<::wire::codec::Nested<::std::boxed::Box<Product>>>(&mut self.main, tag, reader),
+            _ => reader.skip_field(tag).map(|_| ()),
+        }
+    }
+
+    fn encode_to(&self, writer: &mut ::wire::Writer) {
+        ::wire::codec::encode_repeated::<::wire::codec::Nested<Product>>(&self.products, 1, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Nested<::std::boxed::Box<Product>>>(&self.main, 2, writer);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Category {
+    pub name: ::std::string::String,
+    pub parent: ::std::option::Option<::std::boxed::Box<Category>>,
+}
+
+impl ::wire::Message for Category {
+    fn merge_field(
+        &mut self,
+        tag: ::wire::Tag,
+        reader: &mut ::wire::Reader,
+    ) -> ::std::result::Result<(), ::wire::DecodeError> {
+        match tag.number {
+            1 => ::wire::codec::merge::<::wire::codec::String>(&mut self.name, tag, reader),
+            2 => ::wire::codec::merge_optional::<::wire::codec::Nested<::std::boxed::Box<Category>>>(&mut self.parent, tag, reader),
+            _ => reader.skip_field(tag).map(|_| ()),
+        }
+    }
+
+    fn encode_to(&self, writer: &mut ::wire::Writer) {
+        ::wire::codec::encode::<::wire::codec::String>(&self.name, 1, writer);
+        ::wire::codec::encode_optional::<::wire::codec::Nested<::std::boxed::Box<Category>>>(&self.parent, 2, writer);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[repr(i32)]
+pub enum Priority {
+    #[default]
+    Low = 0,
+    High = 1,
+}
+
+impl Priority {
+    /// Alias of [`Self::High`] named `PRIORITY_URGENT`.
+    pub const URGENT: Self = Self::High;
+
+    /// Name of the value in the schema.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Low => "PRIORITY_LOW",
+            Self::High => "PRIORITY_HIGH",
+        }
+    }
+
+    /// Value by its name in the schema, aliases included.
+    pub fn from_str_name(name: &str) -> ::std::option::Option<Self> {
+        match name {
+            "PRIORITY_LOW" => ::std::option::Option::Some(Self::Low),
+            "PRIORITY_HIGH" => ::std::option::Option::Some(Self::High),
+            "PRIORITY_URGENT" => ::std::option::Option::Some(Self::High),
+            _ => ::std::option::Option::None,
+        }
+    }
+}
+
+impl ::std::convert::TryFrom<i32> for Priority {
+    type Error = i32;
+
+    fn try_from(value: i32) -> ::std::result::Result<Self, i32> {
+        match value {
+            0 => ::std::result::Result::Ok(Self::Low),
+            1 => ::std::result::Result::Ok(Self::High),
+            _ => ::std::result::Result::Err(value),
+        }
+    }
+}
+
+impl ::std::convert::From<Priority> for i32 {
+    fn from(value: Priority) -> i32 {
+        value as i32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GetProductRequest {
+    pub id: ::std::string::String,
+    pub priority: i32,
+}
+
+impl GetProductRequest {
+    /// Value of `priority` as an enum, or its default if it is unknown.
+    pub fn priority(&self) -> Priority {
+        Priority::try_from(self.priority).unwrap_or_default()
+    }
+
+    /// Sets `priority` to the value of the enum.
+    pub fn set_priority(&mut self, value: Priority) {
+        self.priority = value as i32;
+    }
+}
+
+impl ::wire::Message for GetProductRequest {
+    fn merge_field(
+        &mut self,
+        tag: ::wire::Tag,
+        reader: &mut ::wire::Reader,
+    ) -> ::std::result::Result<(), ::wire::DecodeError> {
+        match tag.number {
+            1 => ::wire::codec::merge::<::wire::codec::String>(&mut self.id, tag, reader),
+            2 => ::wire::codec::merge::<::wire::codec::Int32>(&mut self.priority, tag, reader),
+            _ => reader.skip_field(tag).map(|_| ()),
+        }
+    }
+
+    fn encode_to(&self, writer: &mut ::wire::Writer) {
+        ::wire::codec::encode::<::wire::codec::String>(&self.id, 1, writer);
+        ::wire::codec::encode::<::wire::codec::Int32>(&self.priority, 2, writer);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImportReply {}
+
+impl ::wire::Message for ImportReply {
+    fn merge_field(
+        &mut self,
+        tag: ::wire::Tag,
+        reader: &mut ::wire::Reader,
+    ) -> ::std::result::Result<(), ::wire::DecodeError> {
+        reader.skip_field(tag).map(|_| ())
+    }
+
+    fn encode_to(&self, _writer: &mut ::wire::Writer) {}
+}
+
+/// Manages the catalog.
+pub trait Store {
+    type Error;
+
+    /// Fully-qualified name of the service.
+    const NAME: &'static str = "acme.store.v1.Store";
+
+    /// Looks a product up.
+    fn get_product(
+        &self,
+        request: GetProductRequest,
+    ) -> ::std::result::Result<Product, Self::Error>;
+
+    fn watch_products(
+        &self,
+        request: GetProductRequest,
+        replies: &mut dyn ::std::ops::FnMut(Product),
+    ) -> ::std::result::Result<(), Self::Error>;
+
+    fn import(
+        &self,
+        requests: &mut dyn ::std::iter::Iterator<Item = Product>,
+    ) -> ::std::result::Result<ImportReply, Self::Error>;
+
+    fn sync(
+        &self,
+        requests: &mut dyn ::std::iter::Iterator<Item = Product>,
+        replies: &mut dyn ::std::ops::FnMut(Product),
+    ) -> ::std::result::Result<(), Self::Error>;
+}
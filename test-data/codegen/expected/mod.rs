@@ -0,0 +1,15 @@
+include!("_.rs");
+
+pub mod acme {
+    pub mod common {
+        pub mod v1 {
+            include!("acme.common.v1.rs");
+        }
+    }
+
+    pub mod store {
+        pub mod v1 {
+            include!("acme.store.v1.rs");
+        }
+    }
+}